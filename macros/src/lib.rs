@@ -0,0 +1,409 @@
+/*!
+Procedural macros for the
+[`serde_mosaic`](https://docs.rs/serde_mosaic/latest/serde_mosaic/) crate. This
+crate is re-exported by `serde_mosaic` if its `macros` feature is enabled and
+shouldn't be used directly.
+ */
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/**
+Names of the modules within `serde_mosaic::with` which can be selected
+explicitly via `#[mosaic(<name>)]`.
+ */
+const MODULES: [&str; 14] = [
+    "link",
+    "opt_link",
+    "arc_link",
+    "opt_arc_link",
+    "rc_link",
+    "opt_rc_link",
+    "weak_link",
+    "vec_link",
+    "vec_arc_link",
+    "map_link",
+    "linked",
+    "link_or_default",
+    "opt_link_or_default",
+    "arc_link_or_default",
+];
+
+/**
+Replaces the `#[mosaic(...)]` attributes of the fields of a struct or enum by
+the matching `#[serde(with = "...")]` attribute, so the serialization and
+deserialization functions of a linked field can't be mismatched.
+
+The attribute `#[mosaic(link)]` selects the functions from the type of the
+field:
+
+| Field type                        | Functions                                             |
+|-----------------------------------|-------------------------------------------------------|
+| `T`                               | `serialize_link` / `deserialize_link`                 |
+| `Option<T>`                       | `serialize_opt_link` / `deserialize_opt_link`         |
+| `Arc<T>`                          | `serialize_arc_link` / `deserialize_arc_link`         |
+| `Option<Arc<T>>`                  | `serialize_opt_arc_link` / `deserialize_opt_arc_link` |
+| `Rc<T>`                           | `serialize_rc_link` / `deserialize_rc_link`           |
+| `Option<Rc<T>>`                   | `serialize_opt_rc_link` / `deserialize_opt_rc_link`   |
+| `Weak<T>`                         | `serialize_weak_link` / `deserialize_weak_link`       |
+| `Vec<T>`                          | `serialize_vec_link` / `deserialize_vec_link`         |
+| `Vec<Arc<T>>`                     | `serialize_vec_arc_link` / `deserialize_vec_arc_link` |
+| `HashMap<K, T>`, `BTreeMap<K, T>` | `serialize_map_link` / `deserialize_map_link`         |
+| `Linked<T>`                       | `serialize_linked` / `deserialize_linked`             |
+
+The type is only inspected syntactically, so type aliases aren't recognized.
+In this case, the functions can be named explicitly, e.g. via
+`#[mosaic(arc_link)]` or `#[mosaic(opt_arc_link)]` (see the modules within
+`serde_mosaic::with` for all options).
+
+This attribute needs to be placed above the `#[derive(Serialize, Deserialize)]`
+attribute, since the derive macros would otherwise see the unexpanded fields.
+The generated attributes refer to the crate as `::serde_mosaic`, so it can't be
+renamed in the `Cargo.toml` of the using crate.
+
+# Examples
+
+```ignore
+use std::ffi::OsStr;
+use std::sync::Arc;
+
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+    cotton_content: f64,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[mosaic]
+#[derive(Serialize, Deserialize)]
+struct FancyShirt {
+    owner: String,
+    #[mosaic(link)] // Expands to #[serde(with = "::serde_mosaic::with::opt_arc_link")]
+    cuff_material: Option<Arc<Material>>,
+    #[mosaic(link)] // Expands to #[serde(with = "::serde_mosaic::with::opt_link")]
+    collar_material: Option<Material>,
+    size: usize
+}
+```
+ */
+#[proc_macro_attribute]
+pub fn mosaic(args: TokenStream, item: TokenStream) -> TokenStream {
+    if let Some(token) = args.into_iter().next() {
+        return compile_error("`#[mosaic]` doesn't take any arguments", token.span());
+    }
+    let mut expanded = Vec::new();
+    for token in item {
+        match token {
+            // The fields of structs, tuple structs and enums
+            TokenTree::Group(group) if group.delimiter() != Delimiter::Bracket => {
+                match expand_fields(group.stream()) {
+                    Ok(stream) => expanded.push(regroup(&group, stream)),
+                    Err((message, span)) => return compile_error(&message, span),
+                }
+            }
+            token => expanded.push(token),
+        }
+    }
+    return expanded.into_iter().collect();
+}
+
+/**
+Error message and location of an invalid `#[mosaic(...)]` attribute.
+ */
+type Error = (String, Span);
+
+/**
+Expands the `#[mosaic(...)]` attributes within the comma-separated fields (or
+enum variants) of `stream`.
+ */
+fn expand_fields(stream: TokenStream) -> Result<TokenStream, Error> {
+    let mut expanded = Vec::new();
+    let mut field = Vec::new();
+    let mut depth = 0usize;
+    let mut previous: Option<TokenTree> = None;
+    for token in stream {
+        if let TokenTree::Punct(punct) = &token {
+            match punct.as_char() {
+                '<' => depth += 1,
+                // Skip the arrow of function types such as `fn() -> T`
+                '>' if !is_joint_punct(previous.as_ref(), '-') => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    expanded.extend(expand_field(std::mem::take(&mut field))?);
+                    expanded.push(token.clone());
+                    previous = Some(token);
+                    continue;
+                }
+                _ => (),
+            }
+        }
+        field.push(token.clone());
+        previous = Some(token);
+    }
+    expanded.extend(expand_field(field)?);
+    return Ok(expanded.into_iter().collect());
+}
+
+/**
+Expands the `#[mosaic(...)]` attribute of a single field. If the tokens don't
+contain such an attribute, they might belong to an enum variant, hence the
+attribute is searched within its fields.
+ */
+fn expand_field(tokens: Vec<TokenTree>) -> Result<Vec<TokenTree>, Error> {
+    // Find the end of the leading attributes
+    let mut attributes_end = 0;
+    let mut mosaic_attribute = None;
+    while let (Some(TokenTree::Punct(punct)), Some(TokenTree::Group(group))) =
+        (tokens.get(attributes_end), tokens.get(attributes_end + 1))
+    {
+        if punct.as_char() != '#' || group.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        if let Some(TokenTree::Ident(ident)) = group.stream().into_iter().next()
+            && ident.to_string() == "mosaic"
+        {
+            if mosaic_attribute.is_some() {
+                return Err((
+                    "A field can only have a single `#[mosaic(...)]` attribute".to_string(),
+                    ident.span(),
+                ));
+            }
+            mosaic_attribute = Some(attributes_end);
+        }
+        attributes_end += 2;
+    }
+
+    let Some(position) = mosaic_attribute else {
+        let mut expanded = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) if group.delimiter() != Delimiter::Bracket => {
+                    let stream = expand_fields(group.stream())?;
+                    expanded.push(regroup(&group, stream));
+                }
+                token => expanded.push(token),
+            }
+        }
+        return Ok(expanded);
+    };
+
+    let TokenTree::Group(attribute) = &tokens[position + 1] else {
+        unreachable!("attributes consist of a pound sign and a bracketed group")
+    };
+    let module = select_module(attribute, field_type(&tokens[attributes_end..]))?;
+    let attribute = with_attribute(module, attribute.span());
+
+    let mut expanded = tokens;
+    expanded[position + 1] = TokenTree::Group(attribute);
+    return Ok(expanded);
+}
+
+/**
+Returns the type of a field, given its tokens after the attributes. Named fields
+are prefixed by an optional visibility, the field name and a colon, while
+tuple fields only have an optional visibility.
+ */
+fn field_type(tokens: &[TokenTree]) -> &[TokenTree] {
+    let mut start = 0;
+    if let Some(TokenTree::Ident(ident)) = tokens.first()
+        && ident.to_string() == "pub"
+    {
+        start += 1;
+        if let Some(TokenTree::Group(group)) = tokens.get(start)
+            && group.delimiter() == Delimiter::Parenthesis
+        {
+            start += 1;
+        }
+    }
+    // The colon of a path separator (`::`) is joint with the following colon
+    if let (Some(TokenTree::Ident(_)), Some(TokenTree::Punct(punct))) =
+        (tokens.get(start), tokens.get(start + 1))
+        && punct.as_char() == ':'
+        && punct.spacing() == Spacing::Alone
+    {
+        start += 2;
+    }
+    return &tokens[start..];
+}
+
+/**
+Returns the name of the module within `serde_mosaic::with` selected by the
+attribute `#[mosaic(...)]` for a field of type `field_type`.
+ */
+fn select_module(attribute: &Group, field_type: &[TokenTree]) -> Result<&'static str, Error> {
+    let mut tokens = attribute.stream().into_iter().skip(1);
+    let argument = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::Parenthesis => {
+            let mut arguments = group.stream().into_iter();
+            match (arguments.next(), arguments.next()) {
+                (Some(TokenTree::Ident(ident)), None) => ident,
+                _ => {
+                    return Err((
+                        "Expected a single argument such as `#[mosaic(link)]`".to_string(),
+                        group.span(),
+                    ));
+                }
+            }
+        }
+        _ => {
+            return Err((
+                "Expected an argument such as `#[mosaic(link)]`".to_string(),
+                attribute.span(),
+            ));
+        }
+    };
+
+    let name = argument.to_string();
+    if name == "link" {
+        return Ok(infer_module(field_type));
+    }
+    match MODULES.into_iter().find(|module| *module == name) {
+        Some(module) => return Ok(module),
+        None => {
+            return Err((
+                format!(
+                    "Unknown link kind `{name}`, expected one of: {}",
+                    MODULES.join(", ")
+                ),
+                argument.span(),
+            ));
+        }
+    }
+}
+
+/**
+Infers the module within `serde_mosaic::with` from the wrapper types of
+`field_type`, see the table in the documentation of [`macro@mosaic`].
+ */
+fn infer_module(field_type: &[TokenTree]) -> &'static str {
+    let Some((wrapper, inner)) = split_wrapper(field_type) else {
+        return "link";
+    };
+    let inner_wrapper = split_wrapper(inner).map(|(name, _)| name);
+    match (wrapper.as_str(), inner_wrapper.as_deref()) {
+        ("Option", Some("Arc")) => return "opt_arc_link",
+        ("Option", Some("Rc")) => return "opt_rc_link",
+        ("Option", _) => return "opt_link",
+        ("Arc", _) => return "arc_link",
+        ("Rc", _) => return "rc_link",
+        ("Weak", _) => return "weak_link",
+        ("Vec", Some("Arc")) => return "vec_arc_link",
+        ("Vec", _) => return "vec_link",
+        ("HashMap" | "BTreeMap", _) => return "map_link",
+        ("Linked", _) => return "linked",
+        // A linked entry with generic parameters
+        _ => return "link",
+    }
+}
+
+/**
+Splits a generic type such as `std::sync::Arc<T>` into the name of the outer
+type (`Arc`) and the tokens of its generic arguments (`T`). Returns [`None`]
+if the type isn't generic.
+ */
+fn split_wrapper(field_type: &[TokenTree]) -> Option<(String, &[TokenTree])> {
+    let open = field_type
+        .iter()
+        .position(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == '<'))?;
+    let TokenTree::Ident(name) = field_type.get(open.checked_sub(1)?)? else {
+        return None;
+    };
+
+    let mut depth = 0usize;
+    for (index, token) in field_type.iter().enumerate().skip(open) {
+        if let TokenTree::Punct(punct) = token {
+            match punct.as_char() {
+                '<' => depth += 1,
+                '>' if !is_joint_punct(field_type.get(index - 1), '-') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some((name.to_string(), &field_type[open + 1..index]));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    return None;
+}
+
+/**
+Returns `true` if `token` is the joint punctuation character `ch`.
+ */
+fn is_joint_punct(token: Option<&TokenTree>, ch: char) -> bool {
+    return matches!(
+        token,
+        Some(TokenTree::Punct(punct)) if punct.as_char() == ch && punct.spacing() == Spacing::Joint
+    );
+}
+
+/**
+Creates the bracketed part of the attribute
+`#[serde(with = "::serde_mosaic::with::<module>")]`.
+ */
+fn with_attribute(module: &str, span: Span) -> Group {
+    let mut equals = Punct::new('=', Spacing::Alone);
+    equals.set_span(span);
+    let mut path = Literal::string(&format!("::serde_mosaic::with::{module}"));
+    path.set_span(span);
+    let arguments: TokenStream = [
+        TokenTree::Ident(Ident::new("with", span)),
+        TokenTree::Punct(equals),
+        TokenTree::Literal(path),
+    ]
+    .into_iter()
+    .collect();
+
+    let mut arguments = Group::new(Delimiter::Parenthesis, arguments);
+    arguments.set_span(span);
+    let attribute: TokenStream = [
+        TokenTree::Ident(Ident::new("serde", span)),
+        TokenTree::Group(arguments),
+    ]
+    .into_iter()
+    .collect();
+
+    let mut attribute = Group::new(Delimiter::Bracket, attribute);
+    attribute.set_span(span);
+    return attribute;
+}
+
+/**
+Creates a group with the delimiter and span of `group`, but the contents
+`stream`.
+ */
+fn regroup(group: &Group, stream: TokenStream) -> TokenTree {
+    let mut regrouped = Group::new(group.delimiter(), stream);
+    regrouped.set_span(group.span());
+    return TokenTree::Group(regrouped);
+}
+
+/**
+Creates an invocation of `compile_error!` with the given `message`, which is
+reported at `span`.
+ */
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    let mut message = Literal::string(message);
+    message.set_span(span);
+    let mut arguments = Group::new(Delimiter::Parenthesis, TokenTree::Literal(message).into());
+    arguments.set_span(span);
+    let mut semicolon = Punct::new(';', Spacing::Alone);
+    semicolon.set_span(span);
+    return [
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(bang),
+        TokenTree::Group(arguments),
+        TokenTree::Punct(semicolon),
+    ]
+    .into_iter()
+    .collect();
+}
@@ -0,0 +1,173 @@
+/*!
+This module contains the [`AsyncDatabaseManager`], an asynchronous front end
+for a [`DatabaseManager`] (requires the `tokio` feature).
+
+Deserializing an entry with links reads the linked files from within the
+deserialization machinery of [`serde`], which is synchronous. Therefore, the
+file I/O can't simply be replaced by `tokio::fs` (which itself runs blocking
+file operations on a separate thread pool). Instead, every operation of an
+[`AsyncDatabaseManager`] is run as a whole on the blocking thread pool of
+`tokio` (see [`tokio::task::spawn_blocking`]), so the executor threads are
+never blocked by file I/O.
+
+Since an operation doesn't yield until it is finished, the thread-local read
+and write contexts used by the functions of the [`attributes`](crate::attributes)
+module are never shared between different tasks: They are set up and torn down
+on the blocking thread which executes the operation.
+
+The operations themselves are implemented by a [`SharedDatabaseManager`], which
+can be used directly in synchronous code.
+ */
+
+use std::ffi::OsString;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{
+    DatabaseEntry, DatabaseKey, DatabaseKeyBuf, DatabaseManager, ReadOptions,
+    SharedDatabaseManager, WriteOptions,
+};
+
+/**
+An asynchronous wrapper around a [`DatabaseManager`], see the
+[module documentation](crate::async_manager). The wrapper can be cloned
+cheaply, all clones use the same underlying manager.
+
+Like for a [`SharedDatabaseManager`], reads are executed concurrently, while
+writes and removals are executed one after another.
+
+# Examples
+
+```no_run
+use std::ffi::OsStr;
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+    cotton_content: f64,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+async fn store(dbm: &AsyncDatabaseManager) -> std::io::Result<()> {
+    let material = Arc::new(Material { name: "cotton".into(), cotton_content: 1.0 });
+    dbm.write(material, WriteOptions::default()).await?;
+    let material: Material = dbm.read("cotton").await?;
+    assert_eq!(material.cotton_content, 1.0);
+    return dbm.remove(&material).await;
+}
+```
+ */
+#[derive(Clone)]
+pub struct AsyncDatabaseManager {
+    dbm: SharedDatabaseManager,
+}
+
+impl AsyncDatabaseManager {
+    /**
+    Creates a new [`AsyncDatabaseManager`] from `dbm`.
+     */
+    pub fn new(dbm: DatabaseManager) -> Self {
+        return Self {
+            dbm: SharedDatabaseManager::new(dbm),
+        };
+    }
+
+    /**
+    Gives shared access to the underlying [`DatabaseManager`], e.g. for
+    inspecting its configuration. The guard blocks the current thread while an
+    operation is writing, so it should only be held briefly.
+     */
+    pub fn database_manager(&self) -> RwLockReadGuard<'_, DatabaseManager> {
+        return self.dbm.database_manager();
+    }
+
+    /**
+    Gives exclusive access to the underlying [`DatabaseManager`], e.g. for
+    changing its configuration. The guard blocks the current thread until all
+    running operations are finished, so it should only be held briefly.
+     */
+    pub fn database_manager_mut(&self) -> RwLockWriteGuard<'_, DatabaseManager> {
+        return self.dbm.database_manager_mut();
+    }
+
+    /**
+    Asynchronous version of [`DatabaseManager::read`].
+     */
+    pub async fn read<T, O>(&self, name: O) -> std::io::Result<T>
+    where
+        T: DatabaseEntry + Send,
+        O: Into<OsString>,
+    {
+        return self.read_with(name, ReadOptions::default()).await;
+    }
+
+    /**
+    Asynchronous version of [`DatabaseManager::read_with`].
+     */
+    pub async fn read_with<T, O>(&self, name: O, read_options: ReadOptions) -> std::io::Result<T>
+    where
+        T: DatabaseEntry + Send,
+        O: Into<OsString>,
+    {
+        let shared = self.dbm.clone();
+        let name = name.into();
+        return run_blocking(move || shared.read_with(&name, &read_options)).await;
+    }
+
+    /**
+    Asynchronous version of [`DatabaseManager::write`]. Since the instance is
+    serialized on another thread, it needs to be passed as an [`Arc`].
+     */
+    pub async fn write<T>(
+        &self,
+        instance: Arc<T>,
+        write_options: WriteOptions,
+    ) -> std::io::Result<PathBuf>
+    where
+        T: DatabaseEntry + Send + Sync,
+    {
+        let shared = self.dbm.clone();
+        return run_blocking(move || shared.write(&*instance, &write_options)).await;
+    }
+
+    /**
+    Asynchronous version of [`DatabaseManager::remove`].
+     */
+    pub async fn remove<'a, K: Into<DatabaseKey<'a>>>(&self, key: K) -> std::io::Result<()> {
+        let shared = self.dbm.clone();
+        let key = DatabaseKeyBuf::from(key.into());
+        return run_blocking(move || shared.remove(&key)).await;
+    }
+}
+
+impl From<DatabaseManager> for AsyncDatabaseManager {
+    fn from(dbm: DatabaseManager) -> Self {
+        return Self::new(dbm);
+    }
+}
+
+/**
+Runs `f` on the blocking thread pool of `tokio`. If `f` panics, the panic is
+resumed on the calling task.
+ */
+async fn run_blocking<R, F>(f: F) -> std::io::Result<R>
+where
+    R: Send + 'static,
+    F: FnOnce() -> std::io::Result<R> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => return result,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        Err(err) => return Err(Error::new(ErrorKind::Interrupted, err.to_string())),
+    }
+}
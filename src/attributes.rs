@@ -60,7 +60,7 @@ from the linked file.
 
 See the docstrings of [`serialize_link`] and [`deserialize_link`] for more. The
 other functions within this module are basically variations of the former two
-for optional and reference-counted fields.
+for optional, reference-counted, weak and collection fields.
  */
 
 use std::any::{Any, TypeId};
@@ -68,14 +68,16 @@ use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::fmt;
 use std::marker::PhantomData;
-use std::sync::Arc;
+use std::rc::Rc;
+use std::sync::{Arc, Weak};
 
 use serde::de::{self, DeserializeOwned, MapAccess};
 use serde::ser;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CacheEntry, Cache, DatabaseEntry, DatabaseLink, LinkOrEntity, READ_CONTEXT, WRITE_CONTEXT, type_name
+    CacheEntry, Cache, ChecksumAlgorithm, DatabaseEntry, DatabaseLink, LenientDatabaseLink, LinkOrEntity, READ_CONTEXT,
+    RcCache, RcCacheEntry, WRITE_CONTEXT, WriteContext
 };
 
 /**
@@ -134,13 +136,28 @@ to this entry which can be interpreted when deserializing via
 
 The link itself is the string returned by
 [`DatabaseEntry::name(&instance)`](DatabaseEntry::name) and (optionally) a
-checksum (hash) of the database entry. See the "Serialized representation"
-section in README.md.
+checksum (hash) of the database entry. Depending on
+[`WriteOptions::link_style`](crate::WriteOptions::link_style), it is written
+either as a map or as a plain string containing only the name. See the
+"Serialized representation" section in README.md.
  */
 pub fn serialize_link<T: DatabaseEntry + Serialize, S: ser::Serializer>(
     instance: &T,
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
+    return serialize_link_with(instance, serializer, |_, _| ());
+}
+
+/**
+Implementation of [`serialize_link`] which calls `written` with the write
+context and the link after `instance` has been written to the database.
+ */
+fn serialize_link_with<T, S, F>(instance: &T, serializer: S, written: F) -> Result<S::Ok, S::Error>
+where
+    T: DatabaseEntry + Serialize,
+    S: ser::Serializer,
+    F: FnOnce(&WriteContext, &DatabaseLink),
+{
     return WRITE_CONTEXT.with(|thread_context| {
         match thread_context.get() {
             Some(context) => {
@@ -148,26 +165,46 @@ pub fn serialize_link<T: DatabaseEntry + Serialize, S: ser::Serializer>(
                 SAFETY: A WriteContext object is both created and destroyed within the function DatabaseManager::write_verbose.
                 This function takes a reference to a WriteOptions object. Therefore, the pointer is not dangling.
                 */
-                let write_mode = {
+                let (write_mode, link_style) = {
                     let write_options = unsafe { &*context.write_options };
-                    write_options.write_mode
+                    (write_options.write_mode, write_options.link_style)
                 };
+                let envelope = context.uses_link_envelope();
 
                 match write_mode {
+                    crate::WriteMode::Flat if envelope => {
+                        return SerializeEnvelope::Entity(instance).serialize(serializer);
+                    }
                     crate::WriteMode::Flat => return instance.serialize(serializer),
                     crate::WriteMode::Link => {
                         // Serialize the database entry itself
-                        let file_path = match context.write(instance) {
-                            Ok(file_path) => file_path,
+                        let link = match context.link(instance) {
+                            Ok(link) => link,
                             Err(msg) => return Err(ser::Error::custom(msg)),
                         };
+                        written(&context, &link);
 
                         // Write link to the serializer
-                        return DatabaseLink::new(
-                            instance,
-                            crate::checksum(file_path.as_path()),
-                        )
-                        .serialize(serializer);
+                        match link_style {
+                            crate::LinkStyle::Map if envelope && link.algorithm.is_default() => {
+                                return SerializeEnvelope::<T>::Link(&link.name, link.checksum)
+                                    .serialize(serializer);
+                            }
+                            crate::LinkStyle::Map if envelope => {
+                                return SerializeEnvelope::<T>::TaggedLink(
+                                    &link.name,
+                                    link.checksum,
+                                    link.algorithm,
+                                )
+                                .serialize(serializer);
+                            }
+                            crate::LinkStyle::Short if envelope => {
+                                return SerializeEnvelope::<T>::Link(&link.name, None)
+                                    .serialize(serializer);
+                            }
+                            crate::LinkStyle::Map => return link.serialize(serializer),
+                            crate::LinkStyle::Short => return serializer.serialize_str(&link.name),
+                        }
                     }
                 };
             }
@@ -195,31 +232,114 @@ pub fn serialize_opt_link<T: DatabaseEntry + Serialize, S: ser::Serializer>(
 }
 
 /**
-Like [`serialize_link`], but for an `Arc<T>`. This function just forwards to
-[`serialize_link`].
+Like [`serialize_link`], but for an `Arc<T>`. If the
+[`CachePolicy`](crate::CachePolicy) of the database manager is
+[`CachePolicy::WriteThrough`](crate::CachePolicy::WriteThrough), the written
+instance is additionally put into its [`Cache`].
  */
-pub fn serialize_arc_link<T: DatabaseEntry + Serialize, S: ser::Serializer>(
+pub fn serialize_arc_link<T: DatabaseEntry + Serialize + Send + Sync, S: ser::Serializer>(
     instance: &Arc<T>,
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
-    return serialize_link(&**instance, serializer);
+    return serialize_link_with(&**instance, serializer, |context, link| {
+        context.cache_written(instance, link)
+    });
 }
 
 /**
 Like [`serialize_opt_link`], but for an `Option<Arc<T>>`. This function just
-forwards to [`serialize_link`] if `instance` is [`Some`], otherwise [`None`]
-is serialized.
+forwards to [`serialize_arc_link`] if `instance` is [`Some`], otherwise
+[`None`] is serialized.
  */
-pub fn serialize_opt_arc_link<T: DatabaseEntry + Serialize, S: ser::Serializer>(
+pub fn serialize_opt_arc_link<T: DatabaseEntry + Serialize + Send + Sync, S: ser::Serializer>(
     instance: &Option<Arc<T>>,
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
     match instance {
-        Some(inst) => return serialize_link(&**inst, serializer),
+        Some(inst) => return serialize_arc_link(inst, serializer),
         None => return None::<Arc<T>>.serialize(serializer),
     }
 }
 
+/**
+Like [`serialize_link`], but for an `Rc<T>`. Contrary to
+[`serialize_arc_link`], written instances are never put into the [`Cache`] of
+the database manager, since it can only hold instances which can be shared
+between threads.
+ */
+pub fn serialize_rc_link<T: DatabaseEntry + Serialize, S: ser::Serializer>(
+    instance: &Rc<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    return serialize_link(&**instance, serializer);
+}
+
+/**
+Like [`serialize_opt_link`], but for an `Option<Rc<T>>`. This function just
+forwards to [`serialize_rc_link`] if `instance` is [`Some`], otherwise
+[`None`] is serialized.
+ */
+pub fn serialize_opt_rc_link<T: DatabaseEntry + Serialize, S: ser::Serializer>(
+    instance: &Option<Rc<T>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match instance {
+        Some(inst) => return serialize_rc_link(inst, serializer),
+        None => return None::<Rc<T>>.serialize(serializer),
+    }
+}
+
+/**
+Like [`serialize_arc_link`], but for a `Weak<T>`. This allows modelling cyclic
+structures (e.g. a document linking back to the folder which contains it)
+without reference cycles keeping the instances alive forever.
+
+Contrary to the other functions of this module, the linked entry itself is not
+written to the database, since this would recurse endlessly for cyclic
+structures. It needs to be written via an `Arc<T>` field annotated with
+[`serialize_arc_link`] (e.g. the field of the folder which contains the
+document) or separately, otherwise the link dangles. For the same reason, the
+link never contains a checksum and is also written if
+[`WriteOptions::write_mode`](crate::WriteOptions::write_mode) is
+[`WriteMode::Flat`](crate::WriteMode::Flat). If the entry `instance` points to
+has already been dropped, an error is returned.
+
+Without a database manager, only the name of the linked entry is serialized.
+ */
+pub fn serialize_weak_link<T: DatabaseEntry, S: ser::Serializer>(
+    instance: &Weak<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let Some(instance) = instance.upgrade() else {
+        return Err(ser::Error::custom(
+            "Can't serialize a link to an entry which has already been dropped",
+        ));
+    };
+    return WRITE_CONTEXT.with(|thread_context| {
+        let Some(context) = thread_context.get() else {
+            return serializer.serialize_str(&instance.name().to_string_lossy());
+        };
+        let name = context.link_name(&*instance);
+        if context.uses_link_envelope() {
+            return SerializeEnvelope::<T>::Link(&name, None).serialize(serializer);
+        }
+
+        // SAFETY: See serialize_link_with.
+        let link_style = unsafe { &*context.write_options }.link_style;
+        match link_style {
+            crate::LinkStyle::Map => {
+                return DatabaseLink {
+                    name,
+                    checksum: None,
+                    algorithm: ChecksumAlgorithm::default(),
+                }
+                .serialize(serializer);
+            }
+            crate::LinkStyle::Short => return serializer.serialize_str(&name),
+        }
+    });
+}
+
 /**
 Deserializes `instance` from a database if this function is called from
 [`DatabaseManager::read`](crate::DatabaseManager::read) and returns the
@@ -273,7 +393,9 @@ serialized representation of `material` is looked up in the database by
 following the link. Then, `material` is deserialized separately and put into
 the `Shirt` instance. If the `material` field of the serialized `Shirt`
 representation already contains a serialized `Material` representation,
-deserialization happens as usual and the database is not accessed.
+deserialization happens as usual and the database is not accessed. A plain
+string in place of the `material` field is interpreted as a link without a
+checksum (see [`LinkStyle::Short`](crate::LinkStyle::Short)).
 
 See the "Serialized representation" section in README.md for more information
 regarding the serialized representation of links.
@@ -292,7 +414,7 @@ where
         type Value = T;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("either a Material or a DatabaseLink struct.")
+            formatter.write_str("either a Material, a DatabaseLink struct or the name of a linked entry.")
         }
 
         fn visit_map<M>(self, visitor: M) -> Result<Self::Value, M::Error>
@@ -302,57 +424,74 @@ where
             let link_or_instance: LinkOrEntity<T> =
                 Deserialize::deserialize(de::value::MapAccessDeserializer::new(visitor))?;
 
-            let instance: T = match link_or_instance {
-                LinkOrEntity::Entity(val) => {
-                    val
+            match link_or_instance {
+                LinkOrEntity::Entity(val) => return Ok(val),
+                LinkOrEntity::DatabaseLink(link) => return resolve_link(&link),
+                LinkOrEntity::LenientDatabaseLink(lenient) => {
+                    log_ignored_link_fields(&lenient);
+                    return resolve_link(&lenient.link);
                 }
-                LinkOrEntity::DatabaseLink(link) => {
-                    // Read the deserialization context
-                    let res: Result<T, std::io::Error>  = READ_CONTEXT.with(|thread_context| {
-                        match thread_context.get() {
-                            Some(context) => {
-                                /*
-                                If the link has a checksum, assert that the file is "in sync" with the link. See the documentation of 
-                                DatabaseLink::test_for_checksum_mismatch for more information.
-
-                                SAFETY: A ReadContext object is both created and destroyed within the function DatabaseManager::read_verbose.
-                                This function takes a mutable reference to a DatabaseManager object. Therefore, the pointer is not dangling.
-                                The only two places where a mutable reference is built from the pointer is in this function and in
-                                ReadContext::read(). The lifetime of the references is chosen so that they do not alias.
-                                */
-                                let file_path = {
-                                    let dbm = unsafe {&mut *context.database_manager};
-                                    dbm.full_path_unchecked((type_name::<T>(), &link.name))
-                                };
-                                if let Some(mismatch) = link.test_for_checksum_mismatch(file_path) {
-                                    crate::RwInfo::log_checksum_mismatch(mismatch);
-                                }
-
-                                context.read(OsStr::new(&link.name))
-                            },
-                            None => {
-                                Err(std::io::Error::new(
-                                    std::io::ErrorKind::Other,
-                                    "No database manager has been set. Therefore, it is not possible to resolve links.".to_string(),
-                                ))
-                            }
-                        }
-                    });
+            }
+        }
 
-                    match res {
-                        Ok(val) => val,
-                        Err(msg) => return Err(de::Error::custom(msg)),
-                    }
-                }
-            };
-            return Ok(instance);
+        // A plain string is a link without checksum (LinkStyle::Short)
+        fn visit_str<E>(self, name: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            return resolve_link(&DatabaseLink {
+                name: name.to_string(),
+                checksum: None,
+                algorithm: ChecksumAlgorithm::default(),
+            });
+        }
+    }
+
+    if reads_envelope() {
+        match Envelope::<T>::deserialize(deserializer)? {
+            Envelope::Entity(val) => return Ok(val),
+            Envelope::Link(name, checksum) => {
+                return resolve_link(&DatabaseLink {
+                    name,
+                    checksum,
+                    algorithm: ChecksumAlgorithm::default(),
+                });
+            }
+            Envelope::TaggedLink(name, checksum, algorithm) => {
+                return resolve_link(&DatabaseLink {
+                    name,
+                    checksum,
+                    algorithm,
+                });
+            }
         }
     }
-    deserializer.deserialize_map(Visitor {
+    deserializer.deserialize_any(Visitor {
         phantom: PhantomData,
     })
 }
 
+/**
+Resolves `link` with the read context of the current thread. If the link has a
+checksum, `ReadContext::read_link` asserts that the file is "in sync" with the
+link, see the documentation of `DatabaseLink::test_for_checksum_mismatch` for
+more information.
+ */
+fn resolve_link<T: DatabaseEntry, E: de::Error>(link: &DatabaseLink) -> Result<T, E> {
+    let res: Result<T, std::io::Error> = READ_CONTEXT.with(|thread_context| {
+        match thread_context.get() {
+            Some(context) => return context.read_link(link),
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "No database manager has been set. Therefore, it is not possible to resolve links.",
+                ));
+            }
+        }
+    });
+    return res.map_err(de::Error::custom);
+}
+
 /**
 Like [`deserialize_link`], but for an `Option<T>`. If the "link" in the
 serialized representation of `T` is empty (string is empty), `Option<T>` is
@@ -405,6 +544,56 @@ where
     return Ok(deserialized_instance);
 }
 
+/**
+The tagged envelope of a linked field for formats which are not
+self-describing (see [`Format::is_self_describing`](crate::Format::is_self_describing)).
+It contains either the entry itself or the name and the checksum of the link.
+Links whose checksum has been calculated with another algorithm than the
+default [`ChecksumAlgorithm::Adler32`] are stored as `TaggedLink`, which is the
+last variant so the variant indices of older files stay valid.
+ */
+#[derive(Deserialize)]
+enum Envelope<T> {
+    Entity(T),
+    Link(String, Option<u32>),
+    TaggedLink(String, Option<u32>, ChecksumAlgorithm),
+}
+
+/**
+Serializable counterpart of [`Envelope`].
+ */
+#[derive(Serialize)]
+#[serde(rename = "Envelope")]
+enum SerializeEnvelope<'a, T> {
+    Entity(&'a T),
+    Link(&'a str, Option<u32>),
+    TaggedLink(&'a str, Option<u32>, ChecksumAlgorithm),
+}
+
+/**
+Returns `true` if the linked fields read by the current thread are wrapped in
+an [`Envelope`].
+ */
+fn reads_envelope() -> bool {
+    return READ_CONTEXT.with(|thread_context| {
+        thread_context
+            .get()
+            .is_some_and(|context| context.uses_link_envelope())
+    });
+}
+
+/**
+Reports the ignored entries of a link which was parsed leniently (see
+[`LinkParsing::Lenient`](crate::LinkParsing::Lenient)).
+ */
+fn log_ignored_link_fields(lenient: &LenientDatabaseLink) {
+    READ_CONTEXT.with(|thread_context| {
+        if let Some(context) = thread_context.get() {
+            context.log_ignored_link_fields(&lenient.link, lenient.ignored_fields.clone());
+        }
+    });
+}
+
 /**
 Similar to [`deserialize_link`], but for an `Arc<T>`. This function first checks
 [`DatabaseManager::cache`](crate::DatabaseManager::cache) to see if there is
@@ -427,68 +616,6 @@ pub fn deserialize_arc_link<'de, D, T: DatabaseEntry + Send + Sync + 'static + D
 where
     D: de::Deserializer<'de>,
 {
-    fn read_cache<T: Send + Sync + DatabaseEntry + 'static>(
-        cache: &mut Cache,
-        link: &DatabaseLink,
-    ) -> Option<Arc<T>> {
-        match cache.get_mut(&TypeId::of::<T>()) {
-            Some(name_map) => {
-                let mut remove_entry = false;
-
-                // Check if the instance already exists as Arc in the cache.
-                let instance = name_map
-                    .get(OsStr::new(&link.name))
-                    .map(|checksum_arc| {
-                        // If the checksum of checksum_arc is the same as the one of the link or no checksum exists in either the link or the
-                        // pointer map, return the Arc. If both checksums exists but are not equal, delete the entry in the cache
-                        // and deserialize the file directly.
-                        let use_arc_instance = match checksum_arc.checksum {
-                            Some(checksum_of_arc) => match link.checksum {
-                                Some(checksum_of_file) => checksum_of_arc == checksum_of_file,
-                                None => true,
-                            },
-                            None => true,
-                        };
-
-                        if use_arc_instance {
-                            let arc_any = checksum_arc.arc.clone() as Arc<dyn Any + Send +Sync>;
-                            arc_any.downcast::<T>().ok()
-                        } else {
-                            remove_entry = true;
-                            None
-                        }
-                    })
-                    .flatten();
-
-                // An instance existed inside the map, but it failed the checksum test => Delete the map entry
-                if remove_entry {
-                    let _ = name_map.remove(OsStr::new(&link.name));
-                }
-
-                return instance;
-            }
-            None => return None,
-        }
-    }
-
-    fn write_cache<T: Send + Sync + DatabaseEntry + 'static>(
-        cache: &mut Cache,
-        link: &DatabaseLink,
-        instance: Arc<dyn DatabaseEntry + Send + Sync + 'static>,
-    ) -> () {
-        // Try to create the category hash map first (will fail if it exists already)
-        if !cache.contains_key(&TypeId::of::<T>()) {
-            cache.insert(TypeId::of::<T>(), HashMap::new());
-        }
-        let name_map = cache.get_mut(&TypeId::of::<T>()).unwrap(); // Must not fail since we just inserted the hash map in case it didn't exist yet.
-        let checksum_arc = CacheEntry {
-            arc: instance,
-            checksum: link.checksum,
-        };
-        name_map.insert(link.name.clone().into(), checksum_arc);
-        return;
-    }
-
     struct VisitorArc<T> {
         phantom: PhantomData<T>,
     }
@@ -500,7 +627,7 @@ where
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
             formatter
-                .write_str("either a type implementing DatabaseEntry or a DatabaseLink struct.")
+                .write_str("either a type implementing DatabaseEntry, a DatabaseLink struct or the name of a linked entry.")
         }
 
         fn visit_map<M>(self, visitor: M) -> Result<Self::Value, M::Error>
@@ -510,83 +637,218 @@ where
             let link_or_instance: LinkOrEntity<T> =
                 Deserialize::deserialize(de::value::MapAccessDeserializer::new(visitor))?;
 
-            let instance: Self::Value = match link_or_instance {
-                LinkOrEntity::Entity(val) => {
-                    Arc::new(val)
+            match link_or_instance {
+                LinkOrEntity::Entity(val) => return Ok(Arc::new(val)),
+                LinkOrEntity::DatabaseLink(link) => return resolve_arc_link(&link),
+                LinkOrEntity::LenientDatabaseLink(lenient) => {
+                    log_ignored_link_fields(&lenient);
+                    return resolve_arc_link(&lenient.link);
                 }
-                LinkOrEntity::DatabaseLink(link) => {
-                    // Read the deserialization context
-                    let res: std::io::Result<Arc<T>> = READ_CONTEXT.with(|thread_context| {
-                        match thread_context.get() {
-                            Some(context) => {
-                                /*
-                                Check if the instance has already been deserialized by checking the cache
-                                If yes, reuse the pointer. If no, read the instance from the database and store the pointer in the context
-    
-                                SAFETY: A ReadContext object is both created and destroyed within the function DatabaseManager::read_verbose.
-                                This function takes a mutable reference to a DatabaseManager object. Therefore, the pointer is not dangling.
-                                The only two places where a mutable reference is built from the pointer is in this function and in
-                                ReadContext::read(). The lifetime of the references is chosen so that they do not alias.
-                                */
-                                if let Some(arc) = read_cache(&mut unsafe {&mut *context.database_manager}.cache_mut(), &link) {
-                                    Ok(arc)
-                                } else {
-                                    // Since we arrived here, the instance is not stored in the pointer map => Perform a regular deserialization
-                                    let instance: T = context.read(
-                                        OsStr::new(&link.name),
-                                    )?;
-                                    let arc = Arc::new(instance);
-    
-                                    /*
-                                    If the link has a checksum, assert that the file is "in sync" with the link. See the documentation of 
-                                    DatabaseLink::test_for_checksum_mismatch for more information.
-    
-                                    SAFETY: A ReadContext object is both created and destroyed within the function DatabaseManager::read_verbose.
-                                    This function takes a mutable reference to a DatabaseManager object. Therefore, the pointer is not dangling.
-                                    The only two places where a mutable reference is built from the pointer is in this function and in
-                                    ReadContext::read(). The lifetime of the references is chosen so that they do not alias.
-                                    */
-                                    let file_path = {
-                                        let dbm = unsafe {&mut *context.database_manager};
-                                        dbm.full_path_unchecked((type_name::<T>(), &link.name))
-                                    };
-                                    if let Some(mismatch) = link.test_for_checksum_mismatch(file_path) {
-                                        crate::RwInfo::log_checksum_mismatch(mismatch);
-                                    }
-    
-                                    // Store the entry in the hash map
-                                    write_cache::<T>(&mut unsafe {&mut *context.database_manager}.cache_mut(), &link, arc.clone());
-    
-                                    // Return the pointer
-                                    Ok(arc)
-                                }                                
-                            },
-                            None => {
-                                Err(std::io::Error::new(
-                                    std::io::ErrorKind::Other,
-                                    "No database manager has been set. Therefore, it is not possible to resolve links.".to_string(),
-                                ))
-                            }
-                        }
-                    });
+            }
+        }
 
-                    match res {
-                        Ok(val) => val,
-                        Err(msg) => return Err(de::Error::custom(msg)),
-                    }
-                }
-            };
-            return Ok(instance);
+        // A plain string is a link without checksum (LinkStyle::Short)
+        fn visit_str<E>(self, name: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            return resolve_arc_link(&DatabaseLink {
+                name: name.to_string(),
+                checksum: None,
+                algorithm: ChecksumAlgorithm::default(),
+            });
+        }
+    }
+
+    if reads_envelope() {
+        match Envelope::<T>::deserialize(deserializer)? {
+            Envelope::Entity(val) => return Ok(Arc::new(val)),
+            Envelope::Link(name, checksum) => {
+                return resolve_arc_link(&DatabaseLink {
+                    name,
+                    checksum,
+                    algorithm: ChecksumAlgorithm::default(),
+                });
+            }
+            Envelope::TaggedLink(name, checksum, algorithm) => {
+                return resolve_arc_link(&DatabaseLink {
+                    name,
+                    checksum,
+                    algorithm,
+                });
+            }
         }
     }
 
-    let deserialized_instance = deserializer.deserialize_map(VisitorArc {
+    let deserialized_instance = deserializer.deserialize_any(VisitorArc {
         phantom: PhantomData,
     })?;
 
     return Ok(deserialized_instance);
 }
 
+/**
+Returns the `Arc<T>` which is stored in `cache` under the name of `link`,
+unless the checksums of the cache entry and the link differ. If
+`file_checksum` is given (see
+[`CacheInvalidation::OnRead`](crate::CacheInvalidation::OnRead)), the checksum
+of the cache entry must match the current checksum of the file as well.
+ */
+fn read_arc_cache<T: Send + Sync + DatabaseEntry + 'static>(
+    cache: &mut Cache,
+    link: &DatabaseLink,
+    file_checksum: Option<Option<u32>>,
+) -> Option<Arc<T>> {
+    match cache.get_mut(&TypeId::of::<T>()) {
+        Some(name_map) => {
+            let mut remove_entry = false;
+
+            // Check if the instance already exists as Arc in the cache.
+            let instance = name_map
+                .get(OsStr::new(&link.name))
+                .map(|checksum_arc| {
+                    // If the checksum of checksum_arc is the same as the one of the link or no checksum exists in either the link or the
+                    // pointer map, return the Arc. If both checksums exists but are not equal, delete the entry in the cache
+                    // and deserialize the file directly.
+                    // Checksums calculated with different algorithms can't be compared
+                    let use_arc_instance = match checksum_arc.checksum {
+                        Some(checksum_of_arc) => match link.checksum {
+                            Some(checksum_of_file) => {
+                                checksum_arc.algorithm == link.algorithm
+                                    && checksum_of_arc == checksum_of_file
+                            }
+                            None => true,
+                        },
+                        None => true,
+                    };
+                    // The file has been changed since the instance was cached
+                    let use_arc_instance = use_arc_instance
+                        && match (checksum_arc.checksum, file_checksum) {
+                            (Some(checksum_of_arc), Some(file_checksum)) => {
+                                checksum_arc.algorithm == link.algorithm
+                                    && Some(checksum_of_arc) == file_checksum
+                            }
+                            _ => true,
+                        };
+
+                    if use_arc_instance {
+                        let arc_any = checksum_arc.arc.clone() as Arc<dyn Any + Send +Sync>;
+                        arc_any.downcast::<T>().ok()
+                    } else {
+                        remove_entry = true;
+                        None
+                    }
+                })
+                .flatten();
+
+            // An instance existed inside the map, but it failed the checksum test => Delete the map entry
+            if remove_entry {
+                let _ = name_map.remove(OsStr::new(&link.name));
+            }
+
+            return instance;
+        }
+        None => return None,
+    }
+}
+
+/**
+Stores `instance` in `cache` under the name of `link`.
+ */
+fn write_arc_cache<T: Send + Sync + DatabaseEntry + 'static>(
+    cache: &mut Cache,
+    link: &DatabaseLink,
+    instance: Arc<dyn DatabaseEntry + Send + Sync + 'static>,
+) -> () {
+    // Try to create the category hash map first (will fail if it exists already)
+    if !cache.contains_key(&TypeId::of::<T>()) {
+        cache.insert(TypeId::of::<T>(), HashMap::new());
+    }
+    let name_map = cache.get_mut(&TypeId::of::<T>()).unwrap(); // Must not fail since we just inserted the hash map in case it didn't exist yet.
+    let checksum_arc = CacheEntry {
+        arc: instance,
+        checksum: link.checksum,
+        algorithm: link.algorithm,
+    };
+    name_map.insert(link.name.clone().into(), checksum_arc);
+    return;
+}
+
+/**
+Returns the checksum and its algorithm stored in the [`CacheEntry`] of the type
+`T` under the name of `link`, i.e. the checksum of the file the cached instance
+has been read from.
+ */
+fn cached_checksum<T: 'static>(cache: &Cache, link: &DatabaseLink) -> Option<(u32, ChecksumAlgorithm)> {
+    let entry = cache.get(&TypeId::of::<T>())?.get(OsStr::new(&link.name))?;
+    return Some((entry.checksum?, entry.algorithm));
+}
+
+/**
+Resolves `link` to an `Arc<T>`, either by taking it from the [`Cache`] or by
+reading the linked file (see [`deserialize_arc_link`]).
+ */
+fn resolve_arc_link<T: DatabaseEntry + Send + Sync + 'static + DeserializeOwned, E: de::Error>(
+    link: &DatabaseLink,
+) -> Result<Arc<T>, E> {
+    // Read the deserialization context
+    let res: std::io::Result<Arc<T>> = READ_CONTEXT.with(|thread_context| {
+        match thread_context.get() {
+            Some(context) => {
+                /*
+                Check if the instance has already been deserialized by checking the cache
+                If yes, reuse the pointer. If no, read the instance from the database and store the pointer in the context.
+                The cache is only accessed via ReadContext::with_cache, since worker threads might resolve links
+                concurrently (see ReadContextHandle).
+                */
+                let file_checksum = context.cache_validation_checksum::<T>(link);
+                if context.bypasses_cache() {
+                    context.read_link_cyclic(link)
+                } else if let Some((arc, checksum)) = context.with_cache(|cache| {
+                    let arc = read_arc_cache(cache, link, file_checksum)?;
+                    Some((arc, cached_checksum::<T>(cache, link)))
+                }) {
+                    context.record_cached::<T>(link, checksum);
+                    context.record_cache_hit::<T>(link);
+                    Ok(arc)
+                } else {
+                    // Since we arrived here, the instance is not stored in the pointer map => Perform a regular deserialization
+                    // Since ReadContext::read_link is used, checksum mismatches are logged as well
+                    // Entries linked via deserialize_weak_link can link back to the instance
+                    let arc: Arc<T> = context.read_link_cyclic(link)?;
+
+                    // Store the entry in the hash map, unless another thread has cached it in the meantime (see
+                    // SharedCache). In that case, the instance of the other thread is used, so all threads share it.
+                    let cached = context.with_cache(|cache| {
+                        let cached = read_arc_cache(cache, link, file_checksum);
+                        if cached.is_none() {
+                            write_arc_cache::<T>(cache, link, arc.clone());
+                        }
+                        cached
+                    });
+                    match cached {
+                        Some(cached) => {
+                            context.record_cache_hit::<T>(link);
+                            Ok(cached)
+                        }
+                        None => {
+                            context.record_cache_miss::<T>(link);
+                            Ok(arc)
+                        }
+                    }
+                }
+            },
+            None => {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "No database manager has been set. Therefore, it is not possible to resolve links.".to_string(),
+                ))
+            }
+        }
+    });
+    return res.map_err(de::Error::custom);
+}
+
 /**
 Like [`deserialize_arc_link`], but for `Option<Arc<T>>`. This function just
 forwards to [`deserialize_arc_link`] if the link is not empty, otherwise
@@ -638,3 +900,1232 @@ where
 
     return Ok(deserialized_instance);
 }
+
+/**
+Like [`deserialize_arc_link`], but for an `Rc<T>`, which avoids the atomic
+reference counting of an `Arc<T>` in single-threaded code.
+
+Since an `Rc<T>` can't be shared between threads, it can't be stored in the
+[`Cache`] of the database manager. Instead, the deserialized instances are kept
+in a separate cache of the current thread, which only lives as long as the
+[`DatabaseManager::read`](crate::DatabaseManager::read) call. Hence, all
+`Rc<T>` fields of a composed struct which link to the same entry (with the same
+checksum) share one instance, but subsequent read calls deserialize the linked
+entries again. If [`ReadOptions::bypass_cache`](crate::ReadOptions::bypass_cache)
+is set, every link is deserialized separately.
+ */
+pub fn deserialize_rc_link<'de, D, T: DatabaseEntry + DeserializeOwned>(
+    deserializer: D,
+) -> Result<Rc<T>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    fn read_cache<T: DatabaseEntry>(cache: &mut RcCache, link: &DatabaseLink) -> Option<Rc<T>> {
+        let name_map = cache.get_mut(&TypeId::of::<T>())?;
+        let entry = name_map.get(OsStr::new(&link.name))?;
+
+        // Same checksum test as in deserialize_arc_link: An instance whose
+        // checksum differs from the one of the link is replaced.
+        let use_rc_instance = match (entry.checksum, link.checksum) {
+            (Some(checksum_of_rc), Some(checksum_of_file)) => {
+                entry.algorithm == link.algorithm && checksum_of_rc == checksum_of_file
+            }
+            _ => true,
+        };
+        if use_rc_instance {
+            return entry.rc.clone().downcast::<T>().ok();
+        }
+        let _ = name_map.remove(OsStr::new(&link.name));
+        return None;
+    }
+
+    struct VisitorRc<T> {
+        phantom: PhantomData<T>,
+    }
+
+    impl<'de, T: DatabaseEntry + DeserializeOwned> de::Visitor<'de> for VisitorRc<T> {
+        type Value = Rc<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter
+                .write_str("either a type implementing DatabaseEntry, a DatabaseLink struct or the name of a linked entry.")
+        }
+
+        fn visit_map<M>(self, visitor: M) -> Result<Self::Value, M::Error>
+        where
+            M: MapAccess<'de>,
+        {
+            let link_or_instance: LinkOrEntity<T> =
+                Deserialize::deserialize(de::value::MapAccessDeserializer::new(visitor))?;
+
+            match link_or_instance {
+                LinkOrEntity::Entity(val) => return Ok(Rc::new(val)),
+                LinkOrEntity::DatabaseLink(link) => return resolve(&link),
+                LinkOrEntity::LenientDatabaseLink(lenient) => {
+                    log_ignored_link_fields(&lenient);
+                    return resolve(&lenient.link);
+                }
+            }
+        }
+
+        // A plain string is a link without checksum (LinkStyle::Short)
+        fn visit_str<E>(self, name: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            return resolve(&DatabaseLink {
+                name: name.to_string(),
+                checksum: None,
+                algorithm: ChecksumAlgorithm::default(),
+            });
+        }
+    }
+
+    fn resolve<T: DatabaseEntry + DeserializeOwned, E: de::Error>(
+        link: &DatabaseLink,
+    ) -> Result<Rc<T>, E> {
+        let res: std::io::Result<Rc<T>> = READ_CONTEXT.with(|thread_context| {
+            match thread_context.get() {
+                Some(context) => {
+                    if context.bypasses_cache() {
+                        return context.read_link(link).map(Rc::new);
+                    }
+                    if let Some(rc) = context.with_rc_cache(|cache| read_cache(cache, link)) {
+                        context.record_rc_cached::<T>(link);
+                        return Ok(rc);
+                    }
+
+                    // The cache must not be borrowed while reading, since the
+                    // linked entry might contain Rc links itself.
+                    let rc = Rc::new(context.read_link::<T>(link)?);
+                    context.with_rc_cache(|cache| {
+                        cache.entry(TypeId::of::<T>()).or_default().insert(
+                            link.name.clone().into(),
+                            RcCacheEntry {
+                                rc: rc.clone(),
+                                checksum: link.checksum,
+                                algorithm: link.algorithm,
+                            },
+                        );
+                    });
+                    return Ok(rc);
+                }
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        "No database manager has been set. Therefore, it is not possible to resolve links.".to_string(),
+                    ));
+                }
+            }
+        });
+        return res.map_err(de::Error::custom);
+    }
+
+    if reads_envelope() {
+        match Envelope::<T>::deserialize(deserializer)? {
+            Envelope::Entity(val) => return Ok(Rc::new(val)),
+            Envelope::Link(name, checksum) => {
+                return resolve(&DatabaseLink {
+                    name,
+                    checksum,
+                    algorithm: ChecksumAlgorithm::default(),
+                });
+            }
+            Envelope::TaggedLink(name, checksum, algorithm) => {
+                return resolve(&DatabaseLink {
+                    name,
+                    checksum,
+                    algorithm,
+                });
+            }
+        }
+    }
+
+    let deserialized_instance = deserializer.deserialize_any(VisitorRc {
+        phantom: PhantomData,
+    })?;
+
+    return Ok(deserialized_instance);
+}
+
+/**
+Like [`deserialize_rc_link`], but for `Option<Rc<T>>`. This function just
+forwards to [`deserialize_rc_link`] if the link is not empty, otherwise
+[`None`] is returned.
+ */
+pub fn deserialize_opt_rc_link<'de, D, T: DatabaseEntry + DeserializeOwned>(
+    deserializer: D,
+) -> Result<Option<Rc<T>>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct Visitor<T> {
+        phantom: PhantomData<T>,
+    }
+
+    impl<'de, T: DatabaseEntry + DeserializeOwned> de::Visitor<'de> for Visitor<T> {
+        type Value = Option<Rc<T>>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("either a Material, a DatabaseLink or None.")
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            let instance = deserialize_rc_link(deserializer)?;
+            return Ok(Some(instance));
+        }
+
+        // We need to use F here as a generic for the error, because E is already taken
+        fn visit_none<F>(self) -> Result<Self::Value, F>
+        where
+            F: de::Error,
+        {
+            return Ok(None);
+        }
+    }
+
+    let deserialized_instance = deserializer.deserialize_option(Visitor {
+        phantom: PhantomData,
+    })?;
+
+    return Ok(deserialized_instance);
+}
+
+/**
+Like [`deserialize_arc_link`], but for a `Weak<T>` written by
+[`serialize_weak_link`]. The link is resolved as follows:
+
+1. If the linked entry is currently being deserialized by
+[`deserialize_arc_link`] within the same read call (i.e. the entry links back
+to an entry which links to it), a `Weak<T>` pointing to this entry is
+returned. It can be upgraded as soon as the linked entry has been deserialized
+completely.
+2. Otherwise, the link is resolved like in [`deserialize_arc_link`] and a
+`Weak<T>` pointing to the result is returned. Since the instance is then only
+kept alive by the [`Cache`], the returned pointer can't be upgraded anymore
+after the instance has been removed from the cache. If
+[`ReadOptions::bypass_cache`](crate::ReadOptions::bypass_cache) is set, the
+returned pointer can't be upgraded at all.
+
+Hence, cyclic structures should be read via an `Arc<T>` link to the entry the
+`Weak<T>` points to, as shown in the example below.
+
+# Examples
+
+```no_run
+use std::ffi::OsStr;
+use std::sync::{Arc, Weak};
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Folder {
+    name: String,
+    #[serde(serialize_with = "serialize_vec_arc_link")]
+    #[serde(deserialize_with = "deserialize_vec_arc_link")]
+    documents: Vec<Arc<Document>>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Folder {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Document {
+    name: String,
+    #[serde(serialize_with = "serialize_weak_link")]
+    #[serde(deserialize_with = "deserialize_weak_link")]
+    folder: Weak<Folder>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Document {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Archive {
+    name: String,
+    #[serde(serialize_with = "serialize_arc_link")]
+    #[serde(deserialize_with = "deserialize_arc_link")]
+    folder: Arc<Folder>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Archive {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+let archive: Archive = dbm.read("2024").expect("entry exists");
+let document = &archive.folder.documents[0];
+assert!(Arc::ptr_eq(&document.folder.upgrade().unwrap(), &archive.folder));
+```
+ */
+pub fn deserialize_weak_link<'de, D, T: DatabaseEntry + Send + Sync + 'static + DeserializeOwned>(
+    deserializer: D,
+) -> Result<Weak<T>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct VisitorWeak<T> {
+        phantom: PhantomData<T>,
+    }
+
+    impl<'de, T: DatabaseEntry + Send + Sync + 'static + DeserializeOwned> de::Visitor<'de>
+        for VisitorWeak<T>
+    {
+        type Value = Weak<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("either a DatabaseLink struct or the name of a linked entry.")
+        }
+
+        fn visit_map<M>(self, visitor: M) -> Result<Self::Value, M::Error>
+        where
+            M: MapAccess<'de>,
+        {
+            let link_or_instance: LinkOrEntity<T> =
+                Deserialize::deserialize(de::value::MapAccessDeserializer::new(visitor))?;
+
+            match link_or_instance {
+                LinkOrEntity::Entity(_) => {
+                    return Err(de::Error::custom(
+                        "A weak link can't point to an inlined entry",
+                    ));
+                }
+                LinkOrEntity::DatabaseLink(link) => return resolve_weak_link(&link),
+                LinkOrEntity::LenientDatabaseLink(lenient) => {
+                    log_ignored_link_fields(&lenient);
+                    return resolve_weak_link(&lenient.link);
+                }
+            }
+        }
+
+        // A plain string is a link without checksum (LinkStyle::Short)
+        fn visit_str<E>(self, name: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            return resolve_weak_link(&DatabaseLink {
+                name: name.to_string(),
+                checksum: None,
+                algorithm: ChecksumAlgorithm::default(),
+            });
+        }
+    }
+
+    fn resolve_weak_link<T, E>(link: &DatabaseLink) -> Result<Weak<T>, E>
+    where
+        T: DatabaseEntry + Send + Sync + 'static + DeserializeOwned,
+        E: de::Error,
+    {
+        let pending = READ_CONTEXT.with(|thread_context| {
+            return thread_context
+                .get()
+                .and_then(|context| context.pending_weak::<T>(&link.name));
+        });
+        match pending {
+            Some(weak) => return Ok(weak),
+            None => return resolve_arc_link(link).map(|arc| Arc::downgrade(&arc)),
+        }
+    }
+
+    if reads_envelope() {
+        match Envelope::<T>::deserialize(deserializer)? {
+            Envelope::Entity(_) => {
+                return Err(de::Error::custom(
+                    "A weak link can't point to an inlined entry",
+                ));
+            }
+            Envelope::Link(name, checksum) => {
+                return resolve_weak_link(&DatabaseLink {
+                    name,
+                    checksum,
+                    algorithm: ChecksumAlgorithm::default(),
+                });
+            }
+            Envelope::TaggedLink(name, checksum, algorithm) => {
+                return resolve_weak_link(&DatabaseLink {
+                    name,
+                    checksum,
+                    algorithm,
+                });
+            }
+        }
+    }
+
+    return deserializer.deserialize_any(VisitorWeak {
+        phantom: PhantomData,
+    });
+}
+
+/**
+Like [`serialize_link`], but for a `Vec<T>`. Each element is written to its own
+database entry and the parent struct contains a list of links. The elements
+should have distinct names, since elements with the same name are written to
+the same file.
+ */
+pub fn serialize_vec_link<T: DatabaseEntry + Serialize, S: ser::Serializer>(
+    instances: &[T],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    return serializer.collect_seq(instances.iter().map(SerializeLink));
+}
+
+/**
+Like [`serialize_vec_link`], but for a `Vec<Arc<T>>`. Each element is
+serialized via [`serialize_arc_link`].
+ */
+pub fn serialize_vec_arc_link<T: DatabaseEntry + Serialize + Send + Sync, S: ser::Serializer>(
+    instances: &[Arc<T>],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    return serializer.collect_seq(instances.iter().map(SerializeArcLink));
+}
+
+/**
+Like [`deserialize_link`], but for a `Vec<T>`. Each element of the list can
+either be a link (which is resolved via [`deserialize_link`]) or the element
+itself, so lists written with [`WriteMode::Flat`](crate::WriteMode::Flat) can
+be read as well.
+ */
+pub fn deserialize_vec_link<'de, D, T: DatabaseEntry + DeserializeOwned>(
+    deserializer: D,
+) -> Result<Vec<T>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let elements: Vec<DeserializeLink<T>> = Deserialize::deserialize(deserializer)?;
+    return Ok(elements.into_iter().map(|element| element.0).collect());
+}
+
+/**
+Like [`deserialize_vec_link`], but for a `Vec<Arc<T>>`. Each element is
+deserialized via [`deserialize_arc_link`], so elements linking to the same
+entry share one instance.
+ */
+pub fn deserialize_vec_arc_link<
+    'de,
+    D,
+    T: DatabaseEntry + Send + Sync + 'static + DeserializeOwned,
+>(
+    deserializer: D,
+) -> Result<Vec<Arc<T>>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let elements: Vec<DeserializeArcLink<T>> = Deserialize::deserialize(deserializer)?;
+    return Ok(elements.into_iter().map(|element| element.0).collect());
+}
+
+/**
+Like [`serialize_link`], but for the values of a map such as a `HashMap<K, T>`
+or a `BTreeMap<K, T>`. Each value is written to its own database entry and the
+parent struct contains a map from the keys to the links.
+
+The entries of a `HashMap` are serialized in an arbitrary order, so the file of
+the parent struct (and hence its checksum) can change every time it is written.
+A `BTreeMap` should be preferred if this is undesired.
+ */
+pub fn serialize_map_link<'a, M, K, T, S>(map: &'a M, serializer: S) -> Result<S::Ok, S::Error>
+where
+    &'a M: IntoIterator<Item = (&'a K, &'a T)>,
+    K: Serialize + 'a,
+    T: DatabaseEntry + Serialize + 'a,
+    S: ser::Serializer,
+{
+    return serializer.collect_map(
+        map.into_iter()
+            .map(|(key, instance)| (key, SerializeLink(instance))),
+    );
+}
+
+/**
+Like [`deserialize_link`], but for the values of a map such as a
+`HashMap<K, T>` or a `BTreeMap<K, T>`. Each value can either be a link (which
+is resolved via [`deserialize_link`]) or the value itself.
+ */
+pub fn deserialize_map_link<'de, D, M, K, T>(deserializer: D) -> Result<M, D::Error>
+where
+    D: de::Deserializer<'de>,
+    M: IntoIterator<Item = (K, T)> + FromIterator<(K, T)>,
+    K: Deserialize<'de>,
+    T: DatabaseEntry + DeserializeOwned,
+{
+    struct Visitor<M, K, T> {
+        phantom: PhantomData<(M, K, T)>,
+    }
+
+    impl<'de, M, K, T> de::Visitor<'de> for Visitor<M, K, T>
+    where
+        M: FromIterator<(K, T)>,
+        K: Deserialize<'de>,
+        T: DatabaseEntry + DeserializeOwned,
+    {
+        type Value = M;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map whose values are links or database entries.")
+        }
+
+        fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut entries = Vec::new();
+            while let Some((key, value)) = access.next_entry::<K, DeserializeLink<T>>()? {
+                entries.push((key, value.0));
+            }
+            return Ok(entries.into_iter().collect());
+        }
+    }
+
+    let deserialized_map = deserializer.deserialize_map(Visitor {
+        phantom: PhantomData,
+    })?;
+
+    return Ok(deserialized_map);
+}
+
+/**
+Serializes the wrapped instance via [`serialize_link`]. This is used to
+serialize the elements of collections.
+ */
+struct SerializeLink<'a, T>(&'a T);
+
+impl<T: DatabaseEntry + Serialize> Serialize for SerializeLink<'_, T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serialize_link(self.0, serializer);
+    }
+}
+
+/**
+Like [`SerializeLink`], but via [`serialize_arc_link`].
+ */
+struct SerializeArcLink<'a, T>(&'a Arc<T>);
+
+impl<T: DatabaseEntry + Serialize + Send + Sync> Serialize for SerializeArcLink<'_, T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serialize_arc_link(self.0, serializer);
+    }
+}
+
+/**
+Deserializes the wrapped instance via [`deserialize_link`]. This is used to
+deserialize the elements of collections.
+ */
+struct DeserializeLink<T>(T);
+
+impl<'de, T: DatabaseEntry + DeserializeOwned> Deserialize<'de> for DeserializeLink<T> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        return deserialize_link(deserializer).map(DeserializeLink);
+    }
+}
+
+/**
+Like [`DeserializeLink`], but via [`deserialize_arc_link`].
+ */
+struct DeserializeArcLink<T>(Arc<T>);
+
+impl<'de, T: DatabaseEntry + Send + Sync + 'static + DeserializeOwned> Deserialize<'de>
+    for DeserializeArcLink<T>
+{
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        return deserialize_arc_link(deserializer).map(DeserializeArcLink);
+    }
+}
+
+/**
+A linked field which is only resolved up to a certain depth, see
+[`ReadOptions::max_link_depth`](crate::ReadOptions::max_link_depth). Fields of
+this type need to be annotated with [`serialize_linked`] and
+[`deserialize_linked`] (or with the [`with::linked`] module):
+
+```
+use std::ffi::OsStr;
+
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+    cotton_content: f64,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Shirt {
+    owner: String,
+    #[serde(with = "serde_mosaic::with::linked")]
+    material: Linked<Material>,
+}
+```
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Linked<T> {
+    /**
+    The linked entry has been read (or was stored inline).
+     */
+    Resolved(T),
+    /**
+    The link has not been resolved, because it exceeded
+    [`ReadOptions::max_link_depth`](crate::ReadOptions::max_link_depth).
+    Writing the parent entry writes the link as it is, without writing the
+    linked entry.
+     */
+    Unresolved {
+        /**
+        The name of the linked entry.
+         */
+        name: String,
+        /**
+        The checksum stored within the link, if any.
+         */
+        checksum: Option<u32>,
+        /**
+        The [`ChecksumAlgorithm`] the checksum has been calculated with.
+         */
+        algorithm: ChecksumAlgorithm,
+    },
+}
+
+impl<T> Linked<T> {
+    /**
+    Returns the linked entry if it has been resolved.
+     */
+    pub fn resolved(&self) -> Option<&T> {
+        match self {
+            Linked::Resolved(instance) => return Some(instance),
+            Linked::Unresolved { .. } => return None,
+        }
+    }
+
+    /**
+    Returns `true` if the linked entry has been resolved.
+     */
+    pub fn is_resolved(&self) -> bool {
+        return self.resolved().is_some();
+    }
+}
+
+/**
+Like [`serialize_link`], but for a [`Linked<T>`]. A resolved entry is
+serialized via [`serialize_link`], an unresolved link is written as it is.
+ */
+pub fn serialize_linked<T: DatabaseEntry + Serialize, S: ser::Serializer>(
+    instance: &Linked<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match instance {
+        Linked::Resolved(instance) => return serialize_link(instance, serializer),
+        Linked::Unresolved {
+            name,
+            checksum,
+            algorithm,
+        } => {
+            let envelope = WRITE_CONTEXT.with(|thread_context| {
+                thread_context
+                    .get()
+                    .is_some_and(|context| context.uses_link_envelope())
+            });
+            if envelope && algorithm.is_default() {
+                return SerializeEnvelope::<T>::Link(name, *checksum).serialize(serializer);
+            }
+            if envelope {
+                return SerializeEnvelope::<T>::TaggedLink(name, *checksum, *algorithm)
+                    .serialize(serializer);
+            }
+            return DatabaseLink {
+                name: name.clone(),
+                checksum: *checksum,
+                algorithm: *algorithm,
+            }
+            .serialize(serializer);
+        }
+    }
+}
+
+/**
+Like [`deserialize_link`], but for a [`Linked<T>`]. If the link exceeds
+[`ReadOptions::max_link_depth`](crate::ReadOptions::max_link_depth), it is not
+resolved and [`Linked::Unresolved`] is returned instead.
+ */
+pub fn deserialize_linked<'de, D, T: DatabaseEntry + DeserializeOwned>(
+    deserializer: D,
+) -> Result<Linked<T>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    fn resolve<T: DatabaseEntry, E: de::Error>(link: DatabaseLink) -> Result<Linked<T>, E> {
+        let res: Result<Linked<T>, std::io::Error> = READ_CONTEXT.with(|thread_context| {
+            match thread_context.get() {
+                Some(context) if context.exceeds_link_depth() => {
+                    return Ok(Linked::Unresolved {
+                        name: link.name,
+                        checksum: link.checksum,
+                        algorithm: link.algorithm,
+                    });
+                }
+                Some(context) => return context.read_link(&link).map(Linked::Resolved),
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        "No database manager has been set. Therefore, it is not possible to resolve links.",
+                    ));
+                }
+            }
+        });
+        return res.map_err(de::Error::custom);
+    }
+
+    struct VisitorLinked<T> {
+        phantom: PhantomData<T>,
+    }
+
+    impl<'de, T: DatabaseEntry + DeserializeOwned> de::Visitor<'de> for VisitorLinked<T> {
+        type Value = Linked<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter
+                .write_str("either a type implementing DatabaseEntry, a DatabaseLink struct or the name of a linked entry.")
+        }
+
+        fn visit_map<M>(self, visitor: M) -> Result<Self::Value, M::Error>
+        where
+            M: MapAccess<'de>,
+        {
+            let link_or_instance: LinkOrEntity<T> =
+                Deserialize::deserialize(de::value::MapAccessDeserializer::new(visitor))?;
+
+            match link_or_instance {
+                LinkOrEntity::Entity(val) => return Ok(Linked::Resolved(val)),
+                LinkOrEntity::DatabaseLink(link) => return resolve(link),
+                LinkOrEntity::LenientDatabaseLink(lenient) => {
+                    log_ignored_link_fields(&lenient);
+                    return resolve(lenient.link);
+                }
+            }
+        }
+
+        // A plain string is a link without checksum (LinkStyle::Short)
+        fn visit_str<E>(self, name: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            return resolve(DatabaseLink {
+                name: name.to_string(),
+                checksum: None,
+                algorithm: ChecksumAlgorithm::default(),
+            });
+        }
+    }
+
+    if reads_envelope() {
+        match Envelope::<T>::deserialize(deserializer)? {
+            Envelope::Entity(val) => return Ok(Linked::Resolved(val)),
+            Envelope::Link(name, checksum) => {
+                return resolve(DatabaseLink {
+                    name,
+                    checksum,
+                    algorithm: ChecksumAlgorithm::default(),
+                });
+            }
+            Envelope::TaggedLink(name, checksum, algorithm) => {
+                return resolve(DatabaseLink {
+                    name,
+                    checksum,
+                    algorithm,
+                });
+            }
+        }
+    }
+    return deserializer.deserialize_any(VisitorLinked {
+        phantom: PhantomData,
+    });
+}
+
+/**
+Deserializes either an inline instance of `T` (returned as
+[`Linked::Resolved`]) or a link (returned as [`Linked::Unresolved`]) without
+resolving the latter.
+ */
+fn deserialize_unresolved<'de, D, T: DatabaseEntry + DeserializeOwned>(
+    deserializer: D,
+) -> Result<Linked<T>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    fn unresolved<T>(link: DatabaseLink) -> Linked<T> {
+        return Linked::Unresolved {
+            name: link.name,
+            checksum: link.checksum,
+            algorithm: link.algorithm,
+        };
+    }
+
+    struct VisitorUnresolved<T> {
+        phantom: PhantomData<T>,
+    }
+
+    impl<'de, T: DatabaseEntry + DeserializeOwned> de::Visitor<'de> for VisitorUnresolved<T> {
+        type Value = Linked<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter
+                .write_str("either a type implementing DatabaseEntry, a DatabaseLink struct or the name of a linked entry.")
+        }
+
+        fn visit_map<M>(self, visitor: M) -> Result<Self::Value, M::Error>
+        where
+            M: MapAccess<'de>,
+        {
+            let link_or_instance: LinkOrEntity<T> =
+                Deserialize::deserialize(de::value::MapAccessDeserializer::new(visitor))?;
+
+            match link_or_instance {
+                LinkOrEntity::Entity(val) => return Ok(Linked::Resolved(val)),
+                LinkOrEntity::DatabaseLink(link) => return Ok(unresolved(link)),
+                LinkOrEntity::LenientDatabaseLink(lenient) => {
+                    log_ignored_link_fields(&lenient);
+                    return Ok(unresolved(lenient.link));
+                }
+            }
+        }
+
+        // A plain string is a link without checksum (LinkStyle::Short)
+        fn visit_str<E>(self, name: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            return Ok(Linked::Unresolved {
+                name: name.to_string(),
+                checksum: None,
+                algorithm: ChecksumAlgorithm::default(),
+            });
+        }
+    }
+
+    if reads_envelope() {
+        match Envelope::<T>::deserialize(deserializer)? {
+            Envelope::Entity(val) => return Ok(Linked::Resolved(val)),
+            Envelope::Link(name, checksum) => {
+                return Ok(Linked::Unresolved {
+                    name,
+                    checksum,
+                    algorithm: ChecksumAlgorithm::default(),
+                });
+            }
+            Envelope::TaggedLink(name, checksum, algorithm) => {
+                return Ok(Linked::Unresolved {
+                    name,
+                    checksum,
+                    algorithm,
+                });
+            }
+        }
+    }
+    return deserializer.deserialize_any(VisitorUnresolved {
+        phantom: PhantomData,
+    });
+}
+
+/**
+Returns the link to the entry `name` if the linked file doesn't exist and the
+link should therefore be substituted by a fallback instance. Outside of
+[`DatabaseManager::read`](crate::DatabaseManager::read), links are never
+substituted (and fail to resolve as usual).
+ */
+fn missing_link<T: DatabaseEntry>(
+    name: String,
+    checksum: Option<u32>,
+    algorithm: ChecksumAlgorithm,
+) -> Result<DatabaseLink, DatabaseLink> {
+    let link = DatabaseLink {
+        name,
+        checksum,
+        algorithm,
+    };
+    let missing = READ_CONTEXT.with(|thread_context| {
+        thread_context
+            .get()
+            .is_some_and(|context| context.substitute_missing_link::<T>(&link))
+    });
+    if missing {
+        return Err(link);
+    }
+    return Ok(link);
+}
+
+/**
+Like [`deserialize_link`], but if the linked file doesn't exist, `fallback` is
+called to construct the instance instead of failing the read. This allows
+reading entries whose (optional) linked entries have been deleted. The
+substitution is reported in
+[`ReadInfo::substituted_links`](crate::ReadInfo::substituted_links). All other
+errors (e.g. a linked file which can't be deserialized) still fail the read.
+
+Since [`deserialize_with`](https://serde.rs/field-attrs.html#deserialize_with)
+expects a function with a single argument, a custom fallback needs to be
+wrapped in a function:
+
+```
+use std::ffi::OsStr;
+
+use serde::{Serialize, Deserialize, Deserializer};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+    cotton_content: f64,
+}
+
+impl Material {
+    fn cotton() -> Self {
+        return Material {
+            name: "cotton".to_string(),
+            cotton_content: 1.0,
+        };
+    }
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+fn link_or_cotton<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Material, D::Error> {
+    return deserialize_link_or_else(deserializer, Material::cotton);
+}
+
+#[derive(Serialize, Deserialize)]
+struct Shirt {
+    owner: String,
+    #[serde(serialize_with = "serialize_link")]
+    #[serde(deserialize_with = "link_or_cotton")]
+    material: Material,
+}
+```
+ */
+pub fn deserialize_link_or_else<'de, D, T, F>(deserializer: D, fallback: F) -> Result<T, D::Error>
+where
+    D: de::Deserializer<'de>,
+    T: DatabaseEntry + DeserializeOwned,
+    F: FnOnce() -> T,
+{
+    match deserialize_unresolved(deserializer)? {
+        Linked::Resolved(val) => return Ok(val),
+        Linked::Unresolved {
+            name,
+            checksum,
+            algorithm,
+        } => match missing_link::<T>(name, checksum, algorithm) {
+            Ok(link) => return resolve_link(&link),
+            Err(_) => return Ok(fallback()),
+        },
+    }
+}
+
+/**
+Like [`deserialize_link_or_else`], with [`Default::default`] as the fallback.
+ */
+pub fn deserialize_link_or_default<'de, D, T: DatabaseEntry + DeserializeOwned + Default>(
+    deserializer: D,
+) -> Result<T, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    return deserialize_link_or_else(deserializer, T::default);
+}
+
+/**
+Like [`deserialize_opt_link`], but if the linked file doesn't exist, [`None`]
+is returned instead of failing the read (see [`deserialize_link_or_else`]).
+ */
+pub fn deserialize_opt_link_or_default<'de, D, T: DatabaseEntry + DeserializeOwned>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct Visitor<T> {
+        phantom: PhantomData<T>,
+    }
+
+    impl<'de, T: DatabaseEntry + DeserializeOwned> de::Visitor<'de> for Visitor<T> {
+        type Value = Option<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("either a type implementing DatabaseEntry, a DatabaseLink or None.")
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            match deserialize_unresolved(deserializer)? {
+                Linked::Resolved(val) => return Ok(Some(val)),
+                Linked::Unresolved {
+                    name,
+                    checksum,
+                    algorithm,
+                } => match missing_link::<T>(name, checksum, algorithm) {
+                    Ok(link) => return resolve_link(&link).map(Some),
+                    Err(_) => return Ok(None),
+                },
+            }
+        }
+
+        // We need to use F here as a generic for the error, because E is already taken
+        fn visit_none<F>(self) -> Result<Self::Value, F>
+        where
+            F: de::Error,
+        {
+            return Ok(None);
+        }
+    }
+
+    return deserializer.deserialize_option(Visitor {
+        phantom: PhantomData,
+    });
+}
+
+/**
+Like [`deserialize_arc_link`], but if the linked file doesn't exist,
+`fallback` is called to construct the instance instead of failing the read
+(see [`deserialize_link_or_else`]). The fallback instance is not put into the
+[`Cache`].
+ */
+pub fn deserialize_arc_link_or_else<'de, D, T, F>(
+    deserializer: D,
+    fallback: F,
+) -> Result<Arc<T>, D::Error>
+where
+    D: de::Deserializer<'de>,
+    T: DatabaseEntry + Send + Sync + 'static + DeserializeOwned,
+    F: FnOnce() -> T,
+{
+    match deserialize_unresolved(deserializer)? {
+        Linked::Resolved(val) => return Ok(Arc::new(val)),
+        Linked::Unresolved {
+            name,
+            checksum,
+            algorithm,
+        } => match missing_link::<T>(name, checksum, algorithm) {
+            Ok(link) => return resolve_arc_link(&link),
+            Err(_) => return Ok(Arc::new(fallback())),
+        },
+    }
+}
+
+/**
+Like [`deserialize_arc_link_or_else`], with [`Default::default`] as the
+fallback.
+ */
+pub fn deserialize_arc_link_or_default<
+    'de,
+    D,
+    T: DatabaseEntry + Send + Sync + 'static + DeserializeOwned + Default,
+>(
+    deserializer: D,
+) -> Result<Arc<T>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    return deserialize_arc_link_or_else(deserializer, T::default);
+}
+
+/**
+Modules pairing the serialization and deserialization functions of this module
+for the [`with`](https://serde.rs/field-attrs.html#with) attribute of [`serde`].
+Using a single attribute makes it impossible to combine e.g. [`serialize_link`]
+with [`deserialize_arc_link`] by accident:
+
+```
+use std::ffi::OsStr;
+use std::sync::Arc;
+
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+    cotton_content: f64,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Shirt {
+    owner: String,
+    #[serde(with = "serde_mosaic::with::opt_arc_link")]
+    material: Option<Arc<Material>>,
+}
+```
+
+With the `macros` feature, the matching module can also be selected
+automatically from the field type via the `#[mosaic(link)]` attribute, see
+`serde_mosaic::mosaic`.
+ */
+pub mod with {
+    /**
+    Uses [`serialize_link`](crate::serialize_link) and
+    [`deserialize_link`](crate::deserialize_link) for a field of type `T`.
+     */
+    pub mod link {
+        pub use crate::{deserialize_link as deserialize, serialize_link as serialize};
+    }
+
+    /**
+    Uses [`serialize_opt_link`](crate::serialize_opt_link) and
+    [`deserialize_opt_link`](crate::deserialize_opt_link) for a field of type
+    `Option<T>`.
+     */
+    pub mod opt_link {
+        pub use crate::{deserialize_opt_link as deserialize, serialize_opt_link as serialize};
+    }
+
+    /**
+    Uses [`serialize_arc_link`](crate::serialize_arc_link) and
+    [`deserialize_arc_link`](crate::deserialize_arc_link) for a field of type
+    `Arc<T>`.
+     */
+    pub mod arc_link {
+        pub use crate::{deserialize_arc_link as deserialize, serialize_arc_link as serialize};
+    }
+
+    /**
+    Uses [`serialize_opt_arc_link`](crate::serialize_opt_arc_link) and
+    [`deserialize_opt_arc_link`](crate::deserialize_opt_arc_link) for a field
+    of type `Option<Arc<T>>`.
+     */
+    pub mod opt_arc_link {
+        pub use crate::{
+            deserialize_opt_arc_link as deserialize, serialize_opt_arc_link as serialize,
+        };
+    }
+
+    /**
+    Uses [`serialize_rc_link`](crate::serialize_rc_link) and
+    [`deserialize_rc_link`](crate::deserialize_rc_link) for a field of type
+    `Rc<T>`.
+     */
+    pub mod rc_link {
+        pub use crate::{deserialize_rc_link as deserialize, serialize_rc_link as serialize};
+    }
+
+    /**
+    Uses [`serialize_opt_rc_link`](crate::serialize_opt_rc_link) and
+    [`deserialize_opt_rc_link`](crate::deserialize_opt_rc_link) for a field of
+    type `Option<Rc<T>>`.
+     */
+    pub mod opt_rc_link {
+        pub use crate::{
+            deserialize_opt_rc_link as deserialize, serialize_opt_rc_link as serialize,
+        };
+    }
+
+    /**
+    Uses [`serialize_weak_link`](crate::serialize_weak_link) and
+    [`deserialize_weak_link`](crate::deserialize_weak_link) for a field of type
+    `Weak<T>`.
+     */
+    pub mod weak_link {
+        pub use crate::{deserialize_weak_link as deserialize, serialize_weak_link as serialize};
+    }
+
+    /**
+    Uses [`serialize_vec_link`](crate::serialize_vec_link) and
+    [`deserialize_vec_link`](crate::deserialize_vec_link) for a field of type
+    `Vec<T>`.
+     */
+    pub mod vec_link {
+        pub use crate::{deserialize_vec_link as deserialize, serialize_vec_link as serialize};
+    }
+
+    /**
+    Uses [`serialize_vec_arc_link`](crate::serialize_vec_arc_link) and
+    [`deserialize_vec_arc_link`](crate::deserialize_vec_arc_link) for a field
+    of type `Vec<Arc<T>>`.
+     */
+    pub mod vec_arc_link {
+        pub use crate::{
+            deserialize_vec_arc_link as deserialize, serialize_vec_arc_link as serialize,
+        };
+    }
+
+    /**
+    Uses [`serialize_map_link`](crate::serialize_map_link) and
+    [`deserialize_map_link`](crate::deserialize_map_link) for a map field such
+    as a `HashMap<K, T>` or a `BTreeMap<K, T>`.
+     */
+    pub mod map_link {
+        pub use crate::{deserialize_map_link as deserialize, serialize_map_link as serialize};
+    }
+
+    /**
+    Uses [`serialize_linked`](crate::serialize_linked) and
+    [`deserialize_linked`](crate::deserialize_linked) for a field of type
+    [`Linked<T>`](crate::Linked).
+     */
+    pub mod linked {
+        pub use crate::{deserialize_linked as deserialize, serialize_linked as serialize};
+    }
+
+    /**
+    Uses [`serialize_link`](crate::serialize_link) and
+    [`deserialize_link_or_default`](crate::deserialize_link_or_default) for a
+    field of type `T`.
+     */
+    pub mod link_or_default {
+        pub use crate::{deserialize_link_or_default as deserialize, serialize_link as serialize};
+    }
+
+    /**
+    Uses [`serialize_opt_link`](crate::serialize_opt_link) and
+    [`deserialize_opt_link_or_default`](crate::deserialize_opt_link_or_default)
+    for a field of type `Option<T>`.
+     */
+    pub mod opt_link_or_default {
+        pub use crate::{
+            deserialize_opt_link_or_default as deserialize, serialize_opt_link as serialize,
+        };
+    }
+
+    /**
+    Uses [`serialize_arc_link`](crate::serialize_arc_link) and
+    [`deserialize_arc_link_or_default`](crate::deserialize_arc_link_or_default)
+    for a field of type `Arc<T>`.
+     */
+    pub mod arc_link_or_default {
+        pub use crate::{
+            deserialize_arc_link_or_default as deserialize, serialize_arc_link as serialize,
+        };
+    }
+}
@@ -0,0 +1,151 @@
+/*!
+This module contains functionality to read many entries of the same type at
+once, see [`DatabaseManager::read_batch`] and
+[`DatabaseManager::read_batch_parallel`].
+
+When reading in parallel, every worker thread uses its own clone of the
+manager. All clones share a single [`SharedCache`], so linked entries which are
+shared via [`Arc`](std::sync::Arc) are still deduplicated across the entries
+read on different threads.
+ */
+
+use std::ffi::OsStr;
+use std::mem;
+use std::num::NonZeroUsize;
+
+use crate::{DatabaseEntry, DatabaseManager, SharedCache};
+
+impl DatabaseManager {
+    /**
+    Reads the entries of the type `T` with the given `names` one after another
+    and returns the results in the same order. In contrast to
+    [`DatabaseManager::read_all`], a failing entry doesn't affect the other
+    entries. Links are resolved as in [`DatabaseManager::read`], so linked
+    entries which are shared via [`Arc`](std::sync::Arc) are only read once.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let results = dbm.read_batch::<Material, _>(&["cotton", "linen", "silk"]);
+    for material in results.into_iter().flatten() {
+        println!("{}: {}", material.name, material.cotton_content);
+    }
+    ```
+     */
+    pub fn read_batch<T: DatabaseEntry, N: AsRef<OsStr>>(
+        &mut self,
+        names: &[N],
+    ) -> Vec<std::io::Result<T>> {
+        return names.iter().map(|name| self.read(name)).collect();
+    }
+
+    /**
+    Like [`DatabaseManager::read_batch`], but the entries are read by up to
+    `threads` worker threads at once. Each worker reads a contiguous part of
+    `names` with its own clone of `self`. The results are returned in the
+    order of `names` nonetheless.
+
+    The workers share the [`SharedCache`] of `self` (see
+    [`DatabaseManager::set_shared_cache`]). If `self` has no shared cache, its
+    own [`Cache`](crate::Cache) is shared by the workers for the duration of
+    this call, so the instances cached by the workers end up in the cache of
+    `self` afterwards. Either way, an `Arc`-wrapped linked entry is only
+    instantiated once, even if it is linked by entries read on different
+    threads. The cache hits and misses of the workers are added to the
+    [`CacheStats`](crate::CacheStats) of `self`.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use std::num::NonZeroUsize;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let names: Vec<_> = dbm.list::<Material>().expect("folder is accessible").collect();
+    let threads = NonZeroUsize::new(4).unwrap();
+    let results = dbm.read_batch_parallel::<Material, _>(&names, threads);
+    assert_eq!(results.len(), names.len());
+    ```
+     */
+    pub fn read_batch_parallel<T: DatabaseEntry + Send, N: AsRef<OsStr> + Sync>(
+        &mut self,
+        names: &[N],
+        threads: NonZeroUsize,
+    ) -> Vec<std::io::Result<T>> {
+        if threads.get() == 1 || names.len() < 2 {
+            return self.read_batch(names);
+        }
+
+        // Without a shared cache, the own cache is lent to the workers
+        let lent_cache = self.shared_cache.is_none();
+        let shared_cache = match self.shared_cache.clone() {
+            Some(shared_cache) => shared_cache,
+            None => SharedCache::from_parts(
+                mem::take(&mut self.cache),
+                mem::take(&mut self.cache_usage),
+            ),
+        };
+        let mut worker_dbm = self.clone();
+        worker_dbm.set_shared_cache(Some(shared_cache.clone()));
+
+        let chunk_size = names.len().div_ceil(threads.get());
+        let chunks: Vec<Vec<std::io::Result<T>>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = names
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let mut dbm = worker_dbm.clone();
+                    return scope.spawn(move || dbm.read_batch(chunk));
+                })
+                .collect();
+            return workers
+                .into_iter()
+                .map(|worker| match worker.join() {
+                    Ok(chunk) => return chunk,
+                    Err(panic) => std::panic::resume_unwind(panic),
+                })
+                .collect();
+        });
+
+        let mut results = Vec::with_capacity(names.len());
+        for chunk in chunks {
+            results.extend(chunk);
+        }
+        if lent_cache {
+            (self.cache, self.cache_usage) = shared_cache.take_parts();
+        }
+        return results;
+    }
+}
@@ -0,0 +1,338 @@
+/*!
+This module contains functionality to limit the size of the [`Cache`] of a
+[`DatabaseManager`], see [`DatabaseManager::set_cache_capacity`], and to
+monitor its effectiveness, see [`DatabaseManager::cache_stats`].
+
+By default, every [`Arc`](std::sync::Arc)-wrapped entry which is read via a
+link stays in the cache until it is removed manually (or its file changes).
+For long-running applications which read many distinct entries, the cache
+therefore grows without bound. With a [`CacheCapacity`], the least recently
+used instances are evicted once the capacity is exceeded. Evicting an instance
+only removes it from the cache; instances which are still in use elsewhere are
+not affected.
+ */
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+
+use crate::{Cache, DatabaseManager};
+
+/**
+The capacity of the [`Cache`] of a [`DatabaseManager`], see
+[`DatabaseManager::set_cache_capacity`]. Manually created
+[`CacheEntry`](crate::CacheEntry)s (without checksum) are never evicted.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheCapacity {
+    #[default]
+    /**
+    The cache is not limited. This is the default.
+     */
+    Unbounded,
+    /**
+    The cache holds at most the given number of instances.
+     */
+    Entries(usize),
+    /**
+    The cache holds instances whose files have at most the given total size
+    in bytes. The size of the file an instance has been read from (or written
+    to) serves as an approximation of the memory used by the instance.
+     */
+    Bytes(u64),
+}
+
+/**
+Statistics about the usage of the [`Cache`] of a [`DatabaseManager`], see
+[`DatabaseManager::cache_stats`].
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /**
+    Number of links which have been resolved with a cached instance.
+     */
+    pub hits: u64,
+    /**
+    Number of links which have been resolved by reading the linked file,
+    because no (valid) instance was cached. Reads which bypass the cache (see
+    [`ReadOptions::bypass_cache`](crate::ReadOptions::bypass_cache)) are not
+    counted.
+     */
+    pub misses: u64,
+    /**
+    Number of instances which have been evicted because the
+    [`CacheCapacity`] was exceeded.
+     */
+    pub evictions: u64,
+    /**
+    Number of instances currently stored in the cache.
+     */
+    pub entries: usize,
+    /**
+    Total size in bytes of the files of the instances currently stored in the
+    cache (see [`CacheCapacity::Bytes`]). Manually created entries don't
+    count.
+     */
+    pub bytes: u64,
+}
+
+/**
+Bookkeeping of the [`Cache`] of a [`DatabaseManager`]: The time of the last
+use and the file size of every cached instance as well as the counters of the
+[`CacheStats`].
+ */
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CacheUsage {
+    clock: u64,
+    entries: HashMap<TypeId, HashMap<OsString, (u64, u64)>>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl DatabaseManager {
+    /**
+    Sets the [`CacheCapacity`] of `self`. If the cache currently exceeds the
+    capacity, the least recently used instances are evicted immediately.
+    Afterwards, the capacity is enforced whenever an instance is put into the
+    cache.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.set_cache_capacity(CacheCapacity::Entries(10_000));
+
+    // ... read entries ...
+
+    let stats = dbm.cache_stats();
+    println!("{} hits, {} misses", stats.hits, stats.misses);
+    ```
+     */
+    pub fn set_cache_capacity(&mut self, cache_capacity: CacheCapacity) {
+        self.cache_capacity = cache_capacity;
+        self.enforce_cache_capacity();
+    }
+
+    /**
+    Returns the [`CacheCapacity`] of `self`, see
+    [`DatabaseManager::set_cache_capacity`].
+     */
+    pub fn cache_capacity(&self) -> CacheCapacity {
+        return self.cache_capacity;
+    }
+
+    /**
+    Returns the [`CacheStats`] of `self`. The counters start at zero when the
+    manager is created and can be reset via
+    [`DatabaseManager::reset_cache_stats`]. Clones of a manager start with
+    the counters of the original. If `self` uses a
+    [`SharedCache`](crate::SharedCache), the statistics of the shared cache
+    are returned, which include the usage by all managers sharing it.
+     */
+    pub fn cache_stats(&self) -> CacheStats {
+        match &self.shared_cache {
+            Some(shared_cache) => {
+                return shared_cache.with_usage(|cache, usage| usage.stats(cache));
+            }
+            None => return self.cache_usage.stats(&self.cache),
+        }
+    }
+
+    /**
+    Resets the counters of the [`CacheStats`] of `self` to zero.
+     */
+    pub fn reset_cache_stats(&mut self) {
+        self.with_cache_usage(|_, usage| {
+            usage.hits = 0;
+            usage.misses = 0;
+            usage.evictions = 0;
+        });
+    }
+
+    /**
+    Records that the cached instance `name` of the type `type_id` has been
+    used to resolve a link.
+     */
+    pub(crate) fn record_cache_hit(&mut self, type_id: TypeId, name: &OsStr) {
+        self.with_cache_usage(|_, usage| usage.record_hit(type_id, name));
+    }
+
+    /**
+    Records that the instance `name` of the type `type_id`, whose file has
+    the size `bytes`, has been put into the cache and evicts the least
+    recently used instances if the [`CacheCapacity`] is exceeded afterwards.
+    If `miss` is `true`, the instance has been read because it was not
+    cached.
+     */
+    pub(crate) fn record_cache_insert(
+        &mut self,
+        type_id: TypeId,
+        name: &OsStr,
+        bytes: u64,
+        miss: bool,
+    ) {
+        let capacity = self.cache_capacity;
+        self.with_cache_usage(|cache, usage| {
+            usage.record_insert(type_id, name, bytes, miss);
+            usage.enforce_capacity(cache, capacity);
+        });
+    }
+
+    /**
+    Returns the size of the (possibly staged) file of the entry `name` within
+    the type folder `type_name` or zero if the file doesn't exist.
+     */
+    pub(crate) fn cached_file_size(&self, type_name: &str, name: &OsStr) -> u64 {
+        let path = self.full_path_unchecked((type_name, name));
+        match self.staged(&path) {
+            Some(staged) => return staged.len() as u64,
+            None => return fs::metadata(&path).map_or(0, |metadata| metadata.len()),
+        }
+    }
+
+    /**
+    Evicts the least recently used instances from the cache until it doesn't
+    exceed the [`CacheCapacity`] anymore. Instances which have been put into
+    the cache manually are treated as least recently used, but manually
+    created entries (without checksum) are never evicted.
+     */
+    pub(crate) fn enforce_cache_capacity(&mut self) {
+        let capacity = self.cache_capacity;
+        self.with_cache_usage(|cache, usage| usage.enforce_capacity(cache, capacity));
+    }
+}
+
+impl CacheUsage {
+    /**
+    Returns the [`CacheStats`] of `cache`, whose bookkeeping is `self`.
+     */
+    pub(crate) fn stats(&self, cache: &Cache) -> CacheStats {
+        let mut stats = CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            entries: 0,
+            bytes: 0,
+        };
+        for (type_id, subcache) in cache.iter() {
+            stats.entries += subcache.len();
+            for name in subcache.keys() {
+                stats.bytes += self.get(type_id, name).map_or(0, |(_, bytes)| bytes);
+            }
+        }
+        return stats;
+    }
+
+    /**
+    See [`DatabaseManager::record_cache_hit`].
+     */
+    pub(crate) fn record_hit(&mut self, type_id: TypeId, name: &OsStr) {
+        self.hits += 1;
+        self.clock += 1;
+        let clock = self.clock;
+        if let Some((last_used, _)) = self
+            .entries
+            .get_mut(&type_id)
+            .and_then(|subcache| subcache.get_mut(name))
+        {
+            *last_used = clock;
+        }
+    }
+
+    /**
+    Like [`DatabaseManager::record_cache_insert`], but without enforcing the
+    [`CacheCapacity`] (see [`CacheUsage::enforce_capacity`]).
+     */
+    pub(crate) fn record_insert(&mut self, type_id: TypeId, name: &OsStr, bytes: u64, miss: bool) {
+        if miss {
+            self.misses += 1;
+        }
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries
+            .entry(type_id)
+            .or_default()
+            .insert(name.to_os_string(), (clock, bytes));
+    }
+
+    /**
+    Evicts the least recently used instances from `cache`, whose bookkeeping
+    is `self`, until it doesn't exceed `capacity` anymore (see
+    [`DatabaseManager::enforce_cache_capacity`]).
+     */
+    pub(crate) fn enforce_capacity(&mut self, cache: &mut Cache, capacity: CacheCapacity) {
+        // Forget instances which have been removed from the cache
+        for (type_id, subcache) in self.entries.iter_mut() {
+            subcache.retain(|name, _| {
+                return cache
+                    .get(type_id)
+                    .is_some_and(|cached| cached.contains_key(name));
+            });
+        }
+        self.entries.retain(|_, subcache| !subcache.is_empty());
+
+        let (max_entries, max_bytes) = match capacity {
+            CacheCapacity::Unbounded => return,
+            CacheCapacity::Entries(max_entries) => (max_entries, u64::MAX),
+            CacheCapacity::Bytes(max_bytes) => (usize::MAX, max_bytes),
+        };
+        let stats = self.stats(cache);
+        let (mut entries, mut bytes) = (stats.entries, stats.bytes);
+        if entries <= max_entries && bytes <= max_bytes {
+            return;
+        }
+
+        let mut candidates: Vec<(u64, u64, TypeId, OsString)> = Vec::new();
+        for (type_id, subcache) in cache.iter() {
+            for (name, entry) in subcache.iter() {
+                if entry.checksum.is_none() {
+                    continue;
+                }
+                let (last_used, size) = self.get(type_id, name).unwrap_or((0, 0));
+                candidates.push((last_used, size, *type_id, name.clone()));
+            }
+        }
+        candidates.sort_by_key(|(last_used, _, _, _)| *last_used);
+
+        for (_, size, type_id, name) in candidates {
+            if entries <= max_entries && bytes <= max_bytes {
+                break;
+            }
+            remove_cached(cache, type_id, &name);
+            if let Some(subcache) = self.entries.get_mut(&type_id) {
+                subcache.remove(&name);
+            }
+            entries -= 1;
+            bytes -= size;
+            self.evictions += 1;
+        }
+    }
+
+    /**
+    Returns the time of the last use and the file size of the cached instance
+    `name` of the type `type_id`.
+     */
+    fn get(&self, type_id: &TypeId, name: &OsStr) -> Option<(u64, u64)> {
+        return self
+            .entries
+            .get(type_id)
+            .and_then(|subcache| subcache.get(name))
+            .copied();
+    }
+}
+
+/**
+Removes the instance `name` of the type `type_id` from `cache`.
+ */
+fn remove_cached(cache: &mut Cache, type_id: TypeId, name: &OsStr) {
+    if let Some(subcache) = cache.get_mut(&type_id) {
+        subcache.remove(name);
+        if subcache.is_empty() {
+            cache.remove(&type_id);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+/*!
+This module contains the [`ChecksumAlgorithm`]s which can be used to calculate
+the checksums of database files, see
+[`DatabaseManager::set_checksum_algorithm`](crate::DatabaseManager::set_checksum_algorithm).
+
+A checksum is always a [`u32`], since it is stored within links and returned
+by methods such as [`DatabaseManager::checksum`](crate::DatabaseManager::checksum).
+Algorithms producing longer digests are truncated to their first four bytes,
+which preserves their uniform distribution.
+ */
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+/**
+The algorithm used to calculate the checksum of a database file.
+
+Links store the algorithm their checksum has been calculated with (the
+`algorithm` entry of a link map, which is omitted for the default
+[`ChecksumAlgorithm::Adler32`]). Hence, a link is always validated with the
+algorithm it has been written with, so databases containing links written by
+managers with different algorithms are validated correctly. When a link is
+rewritten (e.g. because its checksum is healed, see
+[`ChecksumMismatchPolicy::Heal`](crate::ChecksumMismatchPolicy::Heal)), the
+algorithm of the writing manager is used.
+
+# Examples
+
+```
+use serde_mosaic::ChecksumAlgorithm;
+
+let bytes = b"name: cotton";
+assert_ne!(
+    ChecksumAlgorithm::Adler32.checksum_bytes(bytes),
+    ChecksumAlgorithm::Sha256.checksum_bytes(bytes)
+);
+assert_eq!(ChecksumAlgorithm::from_tag("crc32"), Some(ChecksumAlgorithm::Crc32));
+```
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChecksumAlgorithm {
+    /**
+    The Adler-32 checksum. It is very fast, but has a weak collision
+    resistance, especially for short files. This is the default for backwards
+    compatibility, since all links written by earlier versions of this crate
+    use it.
+     */
+    #[default]
+    #[serde(rename = "adler32")]
+    Adler32,
+    /**
+    The CRC-32 (IEEE) checksum.
+     */
+    #[serde(rename = "crc32")]
+    Crc32,
+    /**
+    The 32-bit variant of the xxHash algorithm (XXH32) with seed 0.
+     */
+    #[serde(rename = "xxhash")]
+    XxHash,
+    /**
+    The first four bytes (big endian) of the SHA-256 digest. This is the
+    slowest algorithm, but its checksums are evenly distributed regardless of
+    the file contents.
+     */
+    #[serde(rename = "sha256")]
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    /**
+    All available algorithms.
+     */
+    pub(crate) const ALL: [ChecksumAlgorithm; 4] = [
+        ChecksumAlgorithm::Adler32,
+        ChecksumAlgorithm::Crc32,
+        ChecksumAlgorithm::XxHash,
+        ChecksumAlgorithm::Sha256,
+    ];
+
+    /**
+    Returns the tag of the algorithm which is stored in links (`adler32`,
+    `crc32`, `xxhash` or `sha256`).
+     */
+    pub fn tag(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Adler32 => return "adler32",
+            ChecksumAlgorithm::Crc32 => return "crc32",
+            ChecksumAlgorithm::XxHash => return "xxhash",
+            ChecksumAlgorithm::Sha256 => return "sha256",
+        }
+    }
+
+    /**
+    Returns the algorithm with the given `tag` (see [`ChecksumAlgorithm::tag`])
+    or [`None`] if the tag is unknown.
+     */
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "adler32" => return Some(ChecksumAlgorithm::Adler32),
+            "crc32" => return Some(ChecksumAlgorithm::Crc32),
+            "xxhash" => return Some(ChecksumAlgorithm::XxHash),
+            "sha256" => return Some(ChecksumAlgorithm::Sha256),
+            _ => return None,
+        }
+    }
+
+    /**
+    Returns `true` for the default [`ChecksumAlgorithm::Adler32`], whose tag is
+    omitted in links.
+     */
+    pub(crate) fn is_default(&self) -> bool {
+        return *self == ChecksumAlgorithm::default();
+    }
+
+    /**
+    Calculates the checksum of `bytes`.
+     */
+    pub fn checksum_bytes(&self, bytes: &[u8]) -> u32 {
+        let mut hasher = Hasher::new(*self);
+        hasher.update(bytes);
+        return hasher.finalize();
+    }
+
+    /**
+    Calculates the checksum of the file contents at the given `path`. If there
+    is no file at `path` or it can't be read, [`None`] is returned.
+     */
+    pub fn checksum_file(&self, path: &Path) -> Option<u32> {
+        let mut reader = BufReader::new(File::open(path).ok()?);
+        let mut hasher = Hasher::new(*self);
+        let mut buffer = [0u8; 8192];
+        loop {
+            match reader.read(&mut buffer).ok()? {
+                0 => return Some(hasher.finalize()),
+                read => hasher.update(&buffer[..read]),
+            }
+        }
+    }
+}
+
+/**
+Incremental state of a [`ChecksumAlgorithm`].
+ */
+enum Hasher {
+    Adler32(adler32::RollingAdler32),
+    Crc32(crc32fast::Hasher),
+    XxHash(xxhash_rust::xxh32::Xxh32),
+    Sha256(sha2::Sha256),
+}
+
+impl Hasher {
+    fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Adler32 => return Hasher::Adler32(adler32::RollingAdler32::new()),
+            ChecksumAlgorithm::Crc32 => return Hasher::Crc32(crc32fast::Hasher::new()),
+            ChecksumAlgorithm::XxHash => return Hasher::XxHash(xxhash_rust::xxh32::Xxh32::new(0)),
+            ChecksumAlgorithm::Sha256 => return Hasher::Sha256(sha2::Sha256::new()),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Adler32(hasher) => hasher.update_buffer(bytes),
+            Hasher::Crc32(hasher) => hasher.update(bytes),
+            Hasher::XxHash(hasher) => hasher.update(bytes),
+            Hasher::Sha256(hasher) => hasher.update(bytes),
+        }
+    }
+
+    fn finalize(self) -> u32 {
+        match self {
+            Hasher::Adler32(hasher) => return hasher.hash(),
+            Hasher::Crc32(hasher) => return hasher.finalize(),
+            Hasher::XxHash(hasher) => return hasher.digest(),
+            Hasher::Sha256(hasher) => {
+                // Truncate the digest to its first four bytes
+                let digest = hasher.finalize();
+                return u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+            }
+        }
+    }
+}
@@ -0,0 +1,393 @@
+/*!
+This module contains the [`Compression`] setting, which allows storing large
+database entries gzip-compressed (requires the `compression` feature). Small
+entries are stored uncompressed, since compressing them costs more time than
+it saves space.
+
+Compression is enabled per write via [`WriteOptions::compression`](crate::WriteOptions::compression).
+Whether a file is compressed is detected from its header (the gzip magic
+bytes), so reading compressed files is transparent and doesn't require any
+configuration. Compressed files keep the file extension of the
+[`Format`](crate::Format).
+
+If both compression and [`Encryption`](crate::Encryption) are used, the entry
+is compressed first and then encrypted.
+
+Alternatively, an entire database can be stored compressed by wrapping its
+[`Format`] into a [`Compressed`] format, which compresses every entry with a
+[`Codec`] ([`Gzip`] or, with the `zstd` feature, [`Zstd`]) and marks the files
+with an additional file extension (e.g. `json.zst`).
+ */
+
+use std::error::Error as StdError;
+use std::ffi::{OsStr, OsString};
+use std::io::{Error, ErrorKind, Read, Write};
+
+use flate2::Compression as Level;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::de::DeserializeOwned;
+
+use crate::{DatabaseEntry, Format, FormatOptions, Value};
+
+/**
+Specifies which database entries are compressed by
+[`DatabaseManager::write`](crate::DatabaseManager::write), see
+[`WriteOptions::compression`](crate::WriteOptions::compression).
+
+# Examples
+
+```
+use serde_mosaic::*;
+
+let mut write_options = WriteOptions::default();
+
+// Compress all entries which are larger than 64 kB when serialized
+write_options.compression = Some(Compression::new(64 * 1024));
+```
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    /**
+    Entries whose serialized size in bytes exceeds this threshold are
+    compressed, all other entries are written uncompressed.
+     */
+    pub threshold: u64,
+    /**
+    The compression level from 0 (no compression) to 9 (best compression).
+
+    Defaults to 6.
+     */
+    pub level: u32,
+}
+
+impl Compression {
+    /**
+    Creates a setting which compresses all entries larger than `threshold`
+    bytes with the default compression level.
+     */
+    pub fn new(threshold: u64) -> Self {
+        return Self {
+            threshold,
+            level: Level::default().level(),
+        };
+    }
+
+    /**
+    Returns `true` if an entry with the serialized size `size` is compressed.
+     */
+    pub fn applies_to(&self, size: u64) -> bool {
+        return size > self.threshold;
+    }
+
+    /**
+    Compresses the serialized entry `bytes`.
+     */
+    pub(crate) fn compress(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Level::new(self.level.min(9)));
+        encoder.write_all(bytes)?;
+        return encoder.finish();
+    }
+}
+
+/**
+Decompresses the gzip-compressed `bytes`.
+ */
+pub(crate) fn decompress(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    GzDecoder::new(bytes)
+        .read_to_end(&mut decompressed)
+        .map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("decompression failed: {}", err),
+            )
+        })?;
+    return Ok(decompressed);
+}
+
+/**
+A compression algorithm used by the [`Compressed`] format.
+ */
+pub trait Codec: Clone + Send + Sync + 'static {
+    /**
+    Returns the file extension which is appended to the file extension of the
+    wrapped [`Format`] (e.g. "gz").
+     */
+    fn file_ext(&self) -> &'static str;
+
+    /**
+    Compresses `bytes`.
+     */
+    fn compress(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>>;
+
+    /**
+    Decompresses `bytes`, which have been compressed by [`Codec::compress`].
+     */
+    fn decompress(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>>;
+
+    /**
+    Returns `true` if `bytes` start with the header of the compressed data.
+    Data without this header is passed on uncompressed by the [`Compressed`]
+    format. This is necessary since the
+    [`DatabaseManager`](crate::DatabaseManager) already decompresses
+    gzip-compressed files before they are deserialized (see the module
+    docstring).
+     */
+    fn is_compressed(&self, bytes: &[u8]) -> bool;
+}
+
+/**
+A [`Codec`] using gzip compression. The file extension is "gz".
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gzip {
+    /**
+    The compression level from 0 (no compression) to 9 (best compression).
+
+    Defaults to 6.
+     */
+    pub level: u32,
+}
+
+impl Default for Gzip {
+    fn default() -> Self {
+        return Self {
+            level: Level::default().level(),
+        };
+    }
+}
+
+impl Codec for Gzip {
+    fn file_ext(&self) -> &'static str {
+        return "gz";
+    }
+
+    fn compress(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        return Compression {
+            threshold: 0,
+            level: self.level,
+        }
+        .compress(bytes);
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        return decompress(bytes);
+    }
+
+    fn is_compressed(&self, bytes: &[u8]) -> bool {
+        return bytes.starts_with(&[0x1f, 0x8b]);
+    }
+}
+
+/**
+A [`Codec`] using Zstandard compression (requires the `zstd` feature). The file
+extension is "zst". Zstandard compresses and decompresses considerably faster
+than gzip at a similar ratio, which makes it the better choice for large
+entries.
+ */
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Zstd {
+    /**
+    The compression level from 1 (fastest) to 22 (best compression). Negative
+    levels trade even more compression ratio for speed.
+
+    Defaults to 3.
+     */
+    pub level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl Default for Zstd {
+    fn default() -> Self {
+        return Self {
+            level: zstd::DEFAULT_COMPRESSION_LEVEL,
+        };
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Codec for Zstd {
+    fn file_ext(&self) -> &'static str {
+        return "zst";
+    }
+
+    fn compress(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        return zstd::encode_all(bytes, self.level);
+    }
+
+    fn decompress(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        return zstd::decode_all(bytes).map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("decompression failed: {}", err),
+            )
+        });
+    }
+
+    fn is_compressed(&self, bytes: &[u8]) -> bool {
+        return bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]);
+    }
+}
+
+/**
+A [`Format`] which wraps another format and compresses all entries with the
+[`Codec`] `C`. The file extension is the one of the wrapped format followed by
+the one of the codec, e.g. "json.zst" for
+`Compressed::new(SerdeJson, Zstd::default())`.
+
+In contrast to [`WriteOptions::compression`](crate::WriteOptions::compression),
+which compresses individual large entries, every entry is compressed. All
+methods of the wrapped format (including the conversion to and from untyped
+[`Value`]s) are available, they just operate on the decompressed data.
+
+# Examples
+
+```
+use std::ffi::OsStr;
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct PointCloud {
+    name: String,
+    points: Vec<[f64; 3]>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for PointCloud {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+let format = Compressed::new(SerdeYaml, Gzip::default());
+assert_eq!(format.file_ext(), "yaml.gz");
+
+let dir = std::env::temp_dir().join("serde_mosaic_compressed_example");
+let mut dbm = DatabaseManager::new(&dir, format).expect("directory can be created");
+let scan = PointCloud {
+    name: "scan".into(),
+    points: vec![[0.0, 0.0, 0.0]; 1000],
+};
+let mut write_options = WriteOptions::default();
+write_options.name_collisions = NameCollisions::Overwrite;
+dbm.write(&scan, &write_options).expect("writing succeeds");
+assert!(dir.join("PointCloud/scan.yaml.gz").exists());
+
+let read: PointCloud = dbm.read("scan").expect("entry exists");
+assert_eq!(read, scan);
+```
+ */
+#[derive(Debug, Clone)]
+pub struct Compressed<F: Format, C: Codec> {
+    format: F,
+    codec: C,
+    file_ext: OsString,
+}
+
+impl<F: Format, C: Codec> Compressed<F, C> {
+    /**
+    Wraps `format` into a format which compresses all entries with `codec`.
+     */
+    pub fn new(format: F, codec: C) -> Self {
+        let mut file_ext = format.file_ext().to_os_string();
+        if !file_ext.is_empty() {
+            file_ext.push(".");
+        }
+        file_ext.push(codec.file_ext());
+        return Self {
+            format,
+            codec,
+            file_ext,
+        };
+    }
+
+    /**
+    Returns the wrapped format.
+     */
+    pub fn format(&self) -> &F {
+        return &self.format;
+    }
+
+    /**
+    Returns the codec used for compression.
+     */
+    pub fn codec(&self) -> &C {
+        return &self.codec;
+    }
+
+    fn decompress<'a>(&self, bytes: &'a [u8]) -> std::io::Result<std::borrow::Cow<'a, [u8]>> {
+        if self.codec.is_compressed(bytes) {
+            return Ok(self.codec.decompress(bytes)?.into());
+        }
+        return Ok(bytes.into());
+    }
+}
+
+impl<F: Format + Clone, C: Codec> Format for Compressed<F, C> {
+    fn file_ext(&self) -> &OsStr {
+        return &self.file_ext;
+    }
+
+    fn serialize_dyn(
+        &self,
+        value: &dyn DatabaseEntry,
+    ) -> Result<Vec<u8>, Box<dyn StdError + Send + Sync>> {
+        let bytes = self.format.serialize_dyn(value)?;
+        return Ok(self.codec.compress(&bytes)?);
+    }
+
+    fn deserialize_dyn(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Box<dyn DatabaseEntry>, Box<dyn StdError + Send + Sync>> {
+        return self.format.deserialize_dyn(&self.decompress(bytes)?);
+    }
+
+    fn deserialize<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, Box<dyn StdError + Send + Sync>> {
+        return self.format.deserialize(&self.decompress(bytes)?);
+    }
+
+    fn serialize_value(&self, value: &Value) -> Result<Vec<u8>, Box<dyn StdError + Send + Sync>> {
+        let bytes = self.format.serialize_value(value)?;
+        return Ok(self.codec.compress(&bytes)?);
+    }
+
+    fn deserialize_value(&self, bytes: &[u8]) -> Result<Value, Box<dyn StdError + Send + Sync>> {
+        return self.format.deserialize_value(&self.decompress(bytes)?);
+    }
+
+    fn serialize_value_formatted(
+        &self,
+        value: &Value,
+        format_options: &FormatOptions,
+    ) -> Result<Vec<u8>, Box<dyn StdError + Send + Sync>> {
+        let bytes = self
+            .format
+            .serialize_value_formatted(value, format_options)?;
+        return Ok(self.codec.compress(&bytes)?);
+    }
+
+    fn merge_existing(
+        &self,
+        existing: &[u8],
+        bytes: &[u8],
+    ) -> Result<Option<Vec<u8>>, Box<dyn StdError + Send + Sync>> {
+        let merged = self
+            .format
+            .merge_existing(&self.decompress(existing)?, &self.decompress(bytes)?)?;
+        match merged {
+            Some(merged) => return Ok(Some(self.codec.compress(&merged)?)),
+            None => return Ok(None),
+        }
+    }
+
+    fn is_self_describing(&self) -> bool {
+        return self.format.is_self_describing();
+    }
+}
@@ -10,9 +10,13 @@ database entries across multiple composed types (see also
 [`deserialize_arc_link`](crate::attributes::deserialize_arc_link)).
 - The [`DatabaseKey`] is used to interact with the database (e.g. check the
 existence of entries, delete them etc.)
-- The [`WriteOptions`] type and its components [`WriteMode`] and
-[`NameCollisions`] allows customizing the behaviour when serializing
+- The [`WriteOptions`] type and its components [`WriteMode`],
+[`NameCollisions`] and [`SizeLimits`] allows customizing the behaviour when serializing
 into the database with [`DatabaseManager::write`].
+- The [`ReadOptions`] type and its components [`ChecksumMismatchPolicy`],
+[`LinkParsing`] and [`EnvInterpolation`] allows
+customizing the behaviour when deserializing from the database with
+[`DatabaseManager::read_with`].
 - [`WriteInfo`] and [`ReadInfo`] are returned by the verbose write / read
 alternatives [`DatabaseManager::write_verbose`] and
 [`DatabaseManager::read_verbose`]. They contain additional informations about
@@ -21,13 +25,15 @@ the writing / reading process.
 
 use std::any::{Any, TypeId};
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError, Weak};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, hash_map::Entry},
     ffi::{OsStr, OsString},
-    fs::{self, File, remove_file},
-    io::{BufReader, Error, ErrorKind, Write},
-    mem,
+    fs::{self, remove_file},
+    io::{Error, ErrorKind, Write},
+    mem::{self, MaybeUninit},
     path::{Path, PathBuf},
 };
 
@@ -35,8 +41,12 @@ use deserialize_untagged_verbose_error::DeserializeUntaggedVerboseError;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 
-use crate::Format;
+use crate::{
+    ChangeKind, ChecksumAlgorithm, DatabaseReport, Format, LinkError, SignatureProblem,
+    SignatureStatus, Value,
+};
 
 /**
 Returns the "name" of a type as a string slice. This function uses
@@ -45,10 +55,11 @@ slice which is the actual file name. For example,
 `std::any::type_name::<String>` might return `std::string::String`, whereas this
 function always just returns `String`.
 
-This function is used throughout this crate to derive the folder names within
-a database created by a [`DatabaseManager`]. For example, if a type `Material`
-is stored within a database in `/path/to/db`, the folder name for the file is
-determined by calling `type_name::<Material>()`, resulting in the file path
+This function is the default of [`DatabaseEntry::folder_name`], which is used
+throughout this crate to derive the folder names within a database created by a
+[`DatabaseManager`]. For example, if a type `Material` is stored within a
+database in `/path/to/db`, the folder name for the file is determined by calling
+`type_name::<Material>()`, resulting in the file path
 `/path/to/db/Material/file_name`.
  */
 pub fn type_name<T>() -> &'static str {
@@ -59,6 +70,29 @@ pub fn type_name<T>() -> &'static str {
         .expect("full type name has at least one entry")
 }
 
+/**
+Returns the [`type_name`] of `T` followed by a dot and a hash of its full module
+path, e.g. `Material.3f5a09c1`. In contrast to [`type_name`], the result differs
+for two types with the same name from different modules, so it can be used as
+[`DatabaseEntry::folder_name`] to store both types within the same database.
+
+The hash is the CRC-32 checksum (see [`ChecksumAlgorithm::Crc32`]) of the path
+returned by [`std::any::type_name`]. Hence, moving the type into another module
+changes its folder name, and the entries written before need to be moved to the
+new folder.
+ */
+pub fn qualified_type_name<T>() -> &'static str {
+    static NAMES: Mutex<BTreeMap<&'static str, &'static str>> = Mutex::new(BTreeMap::new());
+
+    let full_name = std::any::type_name::<T>();
+    let mut names = NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+    return names.entry(full_name).or_insert_with(|| {
+        let hash = ChecksumAlgorithm::Crc32.checksum_bytes(full_name.as_bytes());
+        // Every type is only leaked once, since the names are memoized
+        return Box::leak(format!("{}.{hash:08x}", type_name::<T>()).into_boxed_str());
+    });
+}
+
 /**
 Trait which allows storing an object within a database.
 
@@ -93,6 +127,68 @@ pub trait DatabaseEntry: Any {
     where the actual field contents are stored.
      */
     fn name(&self) -> &OsStr;
+
+    /**
+    Returns the name of the folder which contains the entries of this type.
+    Defaults to [`type_name`], so two types with the same name from different
+    modules would share a folder. [`DatabaseManager::write`] refuses to write
+    entries of a type into a folder which already contains entries written for
+    another type, so one of the types needs to overwrite this method, either
+    with a custom name or with its [`qualified_type_name`]:
+
+    ```
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    mod geometry {
+        use super::*;
+
+        #[derive(Serialize, Deserialize)]
+        pub struct Material {
+            pub name: String,
+            pub density: f64,
+        }
+
+        #[typetag::serde(name = "geometry::Material")]
+        impl DatabaseEntry for Material {
+            fn name(&self) -> &OsStr {
+                self.name.as_ref()
+            }
+
+            fn folder_name() -> &'static str {
+                return "geometry.Material";
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+
+        fn folder_name() -> &'static str {
+            return qualified_type_name::<Self>();
+        }
+    }
+
+    assert_eq!(geometry::Material::folder_name(), "geometry.Material");
+    assert!(Material::folder_name().starts_with("Material."));
+    ```
+     */
+    fn folder_name() -> &'static str
+    where
+        Self: Sized,
+    {
+        return type_name::<Self>();
+    }
 }
 
 /**
@@ -151,7 +247,8 @@ The [`Cache`] type itself is created as part of a [`DatabaseManager`] and is
 populated everytime an [`Arc`]-wrapped [`DatabaseEntry`] annotated with
 [`deserialize_arc_link`](crate::attributes::deserialize_arc_link) or
 [`deserialize_opt_arc_link`](crate::attributes::deserialize_opt_arc_link)
-gets deserialized. The cache is accessible via [`DatabaseManager::cache`] and
+gets deserialized (and, depending on the [`CachePolicy`], when such an entry
+is written). The cache is accessible via [`DatabaseManager::cache`] and
 can also be manually adjusted with [`DatabaseManager::cache_mut`] (see
 [`CacheEntry::insert`] for an example).
 
@@ -241,6 +338,12 @@ pub struct CacheEntry {
     creating a [`CacheEntry`], this field is set to [`None`].
      */
     pub checksum: Option<u32>,
+    /**
+    The [`ChecksumAlgorithm`] [`CacheEntry::checksum`] has been calculated
+    with. A cached instance is only reused for links whose checksum has been
+    calculated with the same algorithm.
+     */
+    pub algorithm: ChecksumAlgorithm,
 }
 
 impl CacheEntry {
@@ -329,6 +432,7 @@ impl From<Arc<dyn DatabaseEntry + Send + Sync + 'static>> for CacheEntry {
         return Self {
             arc: value,
             checksum: None,
+            algorithm: ChecksumAlgorithm::default(),
         };
     }
 }
@@ -339,6 +443,71 @@ impl From<CacheEntry> for Arc<dyn Any + Send + Sync + 'static> {
     }
 }
 
+/**
+Specifies when the [`Cache`] of a [`DatabaseManager`] is populated, see
+[`DatabaseManager::set_cache_policy`]. Independent of the policy, individual
+reads can bypass the cache via [`ReadOptions::bypass_cache`].
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CachePolicy {
+    #[default]
+    /**
+    The cache is only populated when an [`Arc`]-wrapped entry is read via
+    [`deserialize_arc_link`](crate::attributes::deserialize_arc_link) or
+    [`deserialize_opt_arc_link`](crate::attributes::deserialize_opt_arc_link).
+     */
+    ReadThrough,
+    /**
+    Additionally to [`CachePolicy::ReadThrough`], every [`Arc`]-wrapped entry
+    written via [`serialize_arc_link`](crate::attributes::serialize_arc_link)
+    or [`serialize_opt_arc_link`](crate::attributes::serialize_opt_arc_link)
+    is put into the cache together with the checksum of its written file. A
+    subsequent read of an entry linking to it therefore reuses the written
+    instance instead of reading the file again. Entries which are written as
+    plain (non-[`Arc`]) links or which are passed directly to
+    [`DatabaseManager::write`] are only available by reference during the
+    write and are therefore not cached.
+
+    The cache is only updated if the whole write call succeeds.
+     */
+    WriteThrough,
+}
+
+/**
+Specifies how the [`Cache`] of a [`DatabaseManager`] detects instances whose
+file has been changed since they were cached (e.g. by another process or by
+hand), see [`DatabaseManager::set_cache_invalidation`]. Manually created
+[`CacheEntry`]s (without checksum) are never invalidated.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheInvalidation {
+    #[default]
+    /**
+    Cached instances are used as long as their checksum matches the checksum
+    stored in the link which is resolved. If the file of a linked entry has
+    been changed without updating the links to it, the cached instance is
+    still used. This is the default.
+     */
+    Never,
+    /**
+    Before a cached instance is used, the checksum of its file is calculated
+    and compared to the checksum of the cached instance. If they differ, the
+    instance is evicted and the file is read again. This detects all changes,
+    but requires reading the file for every use of the cache.
+     */
+    OnRead,
+    /**
+    The database directory is watched for changes (see
+    [`DatabaseManager::watch`]). Instances whose file has been changed are
+    evicted at the beginning of the next read call. This is cheaper than
+    [`CacheInvalidation::OnRead`], but changes are only detected once the
+    operating system has delivered the notification. Requires the `watch`
+    feature.
+     */
+    #[cfg(feature = "watch")]
+    Watch,
+}
+
 /**
 This struct is used to access database entries via a [`DatabaseManager`]. It
 contains the folder (typename) where a file containing the contents of an entry
@@ -388,11 +557,15 @@ dbm.write(&pure_cotton, &write_options).expect("serialization and writing succee
 assert!(dbm.exists(&pure_cotton));
 assert!(dbm.exists(("Material", "pure_cotton")));
 ```
+
+See [`DatabaseKeyBuf`] for an owned version of this struct.
  */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DatabaseKey<'a> {
     /**
     The name of the folder where all database entries for a type `T` are stored.
-    It is equivalent to the string returned by [`type_name`]. For example, for
+    It is equivalent to the string returned by [`DatabaseEntry::folder_name`],
+    which defaults to [`type_name`]. For example, for
     the type `Material` from the struct docstring, the folder name is simply
     "Material".
      */
@@ -408,7 +581,7 @@ pub struct DatabaseKey<'a> {
 impl<'a, T: DatabaseEntry> From<&'a T> for DatabaseKey<'a> {
     fn from(value: &'a T) -> Self {
         return Self {
-            type_name: OsStr::new(type_name::<T>()),
+            type_name: OsStr::new(T::folder_name()),
             name: value.name(),
         };
     }
@@ -445,6 +618,78 @@ impl<'a> From<[&'a str; 2]> for DatabaseKey<'a> {
     }
 }
 
+/**
+An owned version of [`DatabaseKey`].
+
+This struct is used whenever the [`DatabaseManager`] returns keys of database
+entries, e.g. as part of a report. It can be converted into a [`DatabaseKey`]
+via [`DatabaseKeyBuf::as_key`] (or its [`From`] implementation) and is
+displayed as `type_name/name`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DatabaseKeyBuf {
+    /**
+    The name of the folder where the database entry is stored. See
+    [`DatabaseKey::type_name`].
+     */
+    pub type_name: OsString,
+    /**
+    The name of the database entry. See [`DatabaseKey::name`].
+     */
+    pub name: OsString,
+}
+
+impl DatabaseKeyBuf {
+    /**
+    Creates a new [`DatabaseKeyBuf`] from the given `type_name` and `name`.
+     */
+    pub fn new<A: Into<OsString>, B: Into<OsString>>(type_name: A, name: B) -> Self {
+        return Self {
+            type_name: type_name.into(),
+            name: name.into(),
+        };
+    }
+
+    /**
+    Borrows `self` as a [`DatabaseKey`].
+     */
+    pub fn as_key(&self) -> DatabaseKey<'_> {
+        return DatabaseKey {
+            type_name: &self.type_name,
+            name: &self.name,
+        };
+    }
+}
+
+impl<'a> From<&'a DatabaseKeyBuf> for DatabaseKey<'a> {
+    fn from(value: &'a DatabaseKeyBuf) -> Self {
+        return value.as_key();
+    }
+}
+
+impl<'a> From<DatabaseKey<'a>> for DatabaseKeyBuf {
+    fn from(value: DatabaseKey<'a>) -> Self {
+        return Self::new(value.type_name, value.name);
+    }
+}
+
+impl std::fmt::Display for DatabaseKeyBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(
+            f,
+            "{}/{}",
+            self.type_name.to_string_lossy(),
+            self.name.to_string_lossy()
+        );
+    }
+}
+
+/**
+Unknown fields of read entries, see
+[`DatabaseManager::set_preserve_unknown_fields`].
+ */
+type UnknownFields = HashMap<DatabaseKeyBuf, Vec<(Value, Value)>>;
+
 /**
 A manager for a file-system database.
 
@@ -533,15 +778,81 @@ All methods which manipulate files (e.g. [`read`](DatabaseManager::read),
 [`write`](DatabaseManager::write) or [`remove`](DatabaseManager::remove) take a
 mutable reference of `self`. This is done in order to prevent race conditions
 when operating multi-threaded. If it is necessary to use a [`DatabaseManager`]
-in multiple threads at once, consider using a [`Mutex`](std::sync::Mutex) lock
-or creating one manager instance per thread (although this prevents sharing the
-[`Cache`] over the different threads).
+in multiple threads at once, consider using a
+[`SharedDatabaseManager`](crate::SharedDatabaseManager), which reads
+concurrently and shares the [`Cache`] between the threads, or creating one
+manager instance per thread (although this prevents sharing the [`Cache`] over
+the different threads).
  */
 #[derive(Clone)]
 pub struct DatabaseManager {
-    dir: PathBuf,
-    format: Box<dyn Format>,
-    cache: Cache,
+    pub(crate) dir: PathBuf,
+    pub(crate) format: Box<dyn Format>,
+    pub(crate) type_formats: HashMap<OsString, Box<dyn Format>>,
+    pub(crate) cache: Cache,
+    cache_policy: CachePolicy,
+    cache_invalidation: CacheInvalidation,
+    #[cfg(feature = "watch")]
+    pub(crate) cache_watcher: Option<Arc<Mutex<crate::DatabaseWatcher>>>,
+    pub(crate) shared_cache: Option<crate::SharedCache>,
+    pub(crate) cache_capacity: crate::CacheCapacity,
+    pub(crate) cache_usage: crate::CacheUsage,
+    pub(crate) checksum_algorithm: ChecksumAlgorithm,
+    pub(crate) write_profiles: HashMap<String, WriteOptions>,
+    pub(crate) field_aliases: HashMap<OsString, HashMap<String, String>>,
+    pub(crate) migrations: Option<crate::MigrationRegistry>,
+    preserve_unknown_fields: bool,
+    unknown_fields: Arc<Mutex<UnknownFields>>,
+    pub(crate) format_options: Option<crate::FormatOptions>,
+    name_counters: HashMap<PathBuf, u64>,
+    pub(crate) sharding: crate::Sharding,
+    pub(crate) held_locks: Arc<Mutex<HashSet<PathBuf>>>,
+    folder_types: Arc<Mutex<HashMap<OsString, (TypeId, &'static str)>>>,
+    pub(crate) subscribers: crate::events::Subscribers,
+    pub(crate) staging: Option<Arc<Mutex<crate::staging::Staging>>>,
+    temp_dir: Option<Arc<TempDir>>,
+    #[cfg(feature = "encryption")]
+    pub(crate) encryption: Option<crate::Encryption>,
+    #[cfg(feature = "signatures")]
+    pub(crate) signatures: Option<crate::Signatures>,
+    #[cfg(feature = "testing")]
+    pub(crate) fault_injector: Option<crate::testing::FaultInjector>,
+}
+
+/**
+Replaces the contents of the file at `path` with `bytes`. The bytes are written
+into a temporary file first, which is then moved to `path`. In contrast to
+[`fs::write`], the file is therefore replaced by a new one, so other hard links
+to the old file (see [`DatabaseManager::fork`]) keep their contents.
+ */
+pub(crate) fn replace_file(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut temp_file_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_file_name.push(".tmp");
+    let temp_file_path = path.with_file_name(temp_file_name);
+    let result = fs::write(&temp_file_path, bytes).and_then(|_| fs::rename(&temp_file_path, path));
+    if result.is_err() {
+        let _ = remove_file(&temp_file_path);
+    }
+    return result;
+}
+
+/**
+Counter which makes the directories of temporary databases created within the
+same process unique.
+ */
+static TEMP_DIR_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/**
+The directory of a database created with [`DatabaseManager::temp`]. It is
+shared between all clones of the manager and removed when the last clone is
+dropped.
+ */
+struct TempDir(PathBuf);
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
 }
 
 impl DatabaseManager {
@@ -604,6 +915,87 @@ impl DatabaseManager {
         return Self::open_with_boxed_format(path, Box::new(format));
     }
 
+    /**
+    Creates a new database within a new temporary directory (a subdirectory of
+    [`std::env::temp_dir`]). The directory and all its contents are removed
+    when the returned manager and all of its clones have been dropped. This is
+    useful for scratch databases, e.g. for intermediate results of a pipeline.
+
+    # Examples
+
+    ```
+    use serde_mosaic::*;
+
+    let dbm = DatabaseManager::temp(SerdeYaml).expect("temporary directory can be created");
+    let dir = dbm.dir().to_path_buf();
+    assert!(dir.exists());
+
+    drop(dbm);
+    assert!(!dir.exists());
+    ```
+     */
+    pub fn temp<F>(format: F) -> std::io::Result<Self>
+    where
+        F: Format + 'static,
+    {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "serde_mosaic_{}_{}_{}",
+            std::process::id(),
+            nanos,
+            TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).map_err(|err: Error| {
+            Error::new(
+                err.kind(),
+                format!("Could not create directory {}", dir.display()),
+            )
+        })?;
+        let temp_dir = Arc::new(TempDir(dir.clone()));
+        let mut dbm = Self::open(dir, format)?;
+        dbm.temp_dir = Some(temp_dir);
+        return Ok(dbm);
+    }
+
+    /**
+    Returns `true` if the directory of `self` has been created by
+    [`DatabaseManager::temp`] and is therefore removed when `self` and all of
+    its clones have been dropped.
+     */
+    pub fn is_temp(&self) -> bool {
+        return self.temp_dir.is_some();
+    }
+
+    /**
+    Like [`DatabaseManager::open`], but additionally scans the database for
+    files and folders which don't match the expected layout (see
+    [`DatabaseManager::validate_layout`]). The returned [`DatabaseReport`] lists
+    all findings; it is up to the caller to decide how to deal with them.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let (dbm, report) = DatabaseManager::open_validated("/path/to/db", SerdeYaml).expect("directory exists");
+    if !report.is_clean() {
+        eprint!("{}", report);
+    }
+    ```
+     */
+    pub fn open_validated<P, F>(path: P, format: F) -> std::io::Result<(Self, DatabaseReport)>
+    where
+        P: AsRef<Path>,
+        F: Format + 'static,
+    {
+        let dbm = Self::open(path, format)?;
+        let report = dbm.validate_layout()?;
+        return Ok((dbm, report));
+    }
+
     /**
     Like [`DatabaseManager::open`], but takes a boxed [`Format`] instead of
     being generic. See [`DatabaseManager::with_boxed_format`] for details.
@@ -619,7 +1011,35 @@ impl DatabaseManager {
             return Ok(Self {
                 dir,
                 format,
+                type_formats: HashMap::new(),
                 cache: Default::default(),
+                cache_policy: CachePolicy::ReadThrough,
+                cache_invalidation: CacheInvalidation::Never,
+                #[cfg(feature = "watch")]
+                cache_watcher: None,
+                shared_cache: None,
+                cache_capacity: crate::CacheCapacity::Unbounded,
+                cache_usage: Default::default(),
+                checksum_algorithm: ChecksumAlgorithm::default(),
+                write_profiles: HashMap::new(),
+                field_aliases: HashMap::new(),
+                migrations: None,
+                preserve_unknown_fields: false,
+                unknown_fields: Default::default(),
+                format_options: None,
+                name_counters: HashMap::new(),
+                sharding: Default::default(),
+                held_locks: Default::default(),
+                folder_types: Default::default(),
+                subscribers: Default::default(),
+                staging: None,
+                temp_dir: None,
+                #[cfg(feature = "encryption")]
+                encryption: None,
+                #[cfg(feature = "signatures")]
+                signatures: None,
+                #[cfg(feature = "testing")]
+                fault_injector: None,
             });
         } else {
             return Err(Error::new(
@@ -672,352 +1092,181 @@ impl DatabaseManager {
     /**
     Returns the file extension used by `self` to write and read files.
 
-    This function is a shorthand for `dbm.data_format().file_ext()`.
+    This function is a shorthand for `dbm.data_format().file_ext()`. Types with
+    a format override (see [`DatabaseManager::set_format_for`]) may use a
+    different file extension, see [`DatabaseManager::file_ext_for_type`].
      */
     pub fn file_ext(&self) -> &OsStr {
         return self.format.file_ext();
     }
 
     /**
-    Returns the checksum of a database file specified by the given `key`. If
-    the file doesn't exist, this function returns `None`.
-     */
-    pub fn checksum<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> Option<u32> {
-        return checksum(&self.full_path_unchecked(key));
-    }
-
-    /**
-    Removes all empty subfolders within the database path `self.dir()`.
+    Stores the entries of type `T` with `format` instead of the
+    [`data_format`](DatabaseManager::data_format) of `self`. This allows mixing
+    formats within a database, e.g. storing bulky types in a compact binary
+    format while small, hand-edited types remain human-readable. The type name
+    is derived via [`type_name`].
+
+    The format of a file is determined by its type folder: All paths within the
+    folder of `T` use the file extension of `format`, and the entries within it
+    (including linked entries of type `T`) are serialized and deserialized with
+    `format`. Links between entries of different formats are resolved as
+    usual. Existing files of `T` with the file extension of another format are
+    ignored, they are not converted.
+
+    If a format override already existed for `T`, it is replaced and
+    returned.
 
-    Be aware that the [`DatabaseManager`] doesn't know which folders belong to
-    the database and which folders do not. For example, the following snippet
-    would remove an empty folder `/path/to/db/foo`, even though it wasn't
-    created by the database manager:
+    # Examples
 
     ```no_run
-    use std::path::PathBuf;
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
     use serde_mosaic::*;
 
-    let unrelated_dir = PathBuf::from("/path/to/db/foo");
-
-    assert!(unrelated_dir.exists());
-    assert!(unrelated_dir.read_dir().expect("read permissions available").next().is_none());
-
-    let mut dbm = DatabaseManager::new("/path/to/db", SerdeYaml).expect("directory exists or can be created");
-
-    assert!(unrelated_dir.exists());
-    assert!(unrelated_dir.read_dir().expect("read permissions available").next().is_none());
+    #[derive(Serialize, Deserialize)]
+    struct PointCloud {
+        name: String,
+        points: Vec<[f64; 3]>,
+    }
 
-    dbm.remove_empty_subfolders();
+    #[typetag::serde]
+    impl DatabaseEntry for PointCloud {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
 
-    assert!(!unrelated_dir.exists());
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.set_format_for::<PointCloud, _>(Bincode);
+    assert_eq!(dbm.file_ext_for_type("PointCloud"), "bincode");
+    assert_eq!(dbm.file_ext_for_type("Material"), "yaml");
     ```
      */
-    pub fn remove_empty_subfolders(&mut self) -> std::io::Result<()> {
-        fn remove_priv(path: &Path) -> std::io::Result<()> {
-            let reader = path.read_dir()?;
-            for folder in reader {
-                let dir_entry = folder?;
-
-                // Check if the folder is empty
-                let path = dir_entry.path();
-
-                // Check if the folder is empty:
-                // https://stackoverflow.com/questions/56744383/how-would-i-check-if-a-directory-is-empty-in-rust
-                if path.read_dir()?.next().is_none() {
-                    std::fs::remove_dir_all(path)?;
-                }
-            }
-            return Ok(());
-        }
-
-        // =====================================================================
-
-        remove_priv(self.dir())?;
-        return Ok(());
+    pub fn set_format_for<T: DatabaseEntry, F: Format>(
+        &mut self,
+        format: F,
+    ) -> Option<Box<dyn Format>> {
+        return self.set_format_for_type(T::folder_name(), Box::new(format));
     }
 
     /**
-    Tries to remove the specified database file from the database.
-
-    This function essentially derives the file path from the given `key` with
-    [`DatabaseManager::full_path`] and then tries to delete the file. If the
-    file doesn't exist or can't be removed, this function returns an error.
-
-    Be aware that the [`DatabaseManager`] does not know which files "belong" to
-    the database - if a file fitting the naming scheme has been created in an
-    unrelated way, it will still be removed.
+    Like [`DatabaseManager::set_format_for`], but for the type folder
+    `type_name` and with a boxed [`Format`].
      */
-    pub fn remove<'a, T: Into<DatabaseKey<'a>>>(&mut self, key: T) -> std::io::Result<()> {
-        let file_path = self.full_path_unchecked(key);
-        if file_path.exists() {
-            return std::fs::remove_file(&file_path).map_err(|err| {
-                Error::new(
-                    err.kind(),
-                    format!("Could not remove file {}: {}", file_path.display(), err),
-                )
-            });
-        } else {
-            return Ok(());
-        }
+    pub fn set_format_for_type<O: Into<OsString>>(
+        &mut self,
+        type_name: O,
+        format: Box<dyn Format>,
+    ) -> Option<Box<dyn Format>> {
+        return self.type_formats.insert(type_name.into(), format);
     }
 
     /**
-    Searches through all direct subfolders (non-recursively) of `self.dir()` and
-    removes all files with the given file name whose file extension matches that
-    of `self.file_ext`. Similar to [`DatabaseManager::remove`], this function
-    does not discriminate between files which were created by `self` and files
-    which were created by something else.
+    Removes the format override of the type folder `type_name` (see
+    [`DatabaseManager::set_format_for`]) and returns it, if it existed. The
+    entries of the type are stored with the
+    [`data_format`](DatabaseManager::data_format) of `self` afterwards.
      */
-    pub fn remove_all<O: AsRef<OsStr>>(&mut self, name: O) -> std::io::Result<()> {
-        fn remove_all_inner(dbm: &mut DatabaseManager, name: &OsStr) -> std::io::Result<()> {
-            let mut file_with_ext = name.to_os_string();
-            if !dbm.file_ext().is_empty() {
-                file_with_ext.push(".");
-                file_with_ext.push(dbm.file_ext());
-            }
-
-            let paths = fs::read_dir(dbm.dir())?;
-
-            // Iterate through all folders of the database
-            for path in paths {
-                if let Ok(dir) = path {
-                    let file_path = dir.path().join(&file_with_ext);
-                    if file_path.exists() {
-                        std::fs::remove_file(&file_path)?;
-                    }
-                }
-            }
+    pub fn remove_format_for_type<O: AsRef<OsStr>>(
+        &mut self,
+        type_name: O,
+    ) -> Option<Box<dyn Format>> {
+        return self.type_formats.remove(type_name.as_ref());
+    }
 
-            return Ok(());
+    /**
+    Returns the [`Format`] used for the entries within the type folder
+    `type_name`. This is either the format override of the type (see
+    [`DatabaseManager::set_format_for`]) or the
+    [`data_format`](DatabaseManager::data_format) of `self`.
+     */
+    pub fn format_for_type<O: AsRef<OsStr>>(&self, type_name: O) -> &dyn Format {
+        match self.type_formats.get(type_name.as_ref()) {
+            Some(format) => return &**format,
+            None => return &*self.format,
         }
-        return remove_all_inner(self, name.as_ref());
     }
 
     /**
-    Checks if the database has an entry for the given `key`.
-
-    Under the hood, this function calls `self.full_path(key).is_some()`.
+    Returns the file extension of the entries within the type folder
+    `type_name`, see [`DatabaseManager::format_for_type`].
      */
-    pub fn exists<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> bool {
-        return self.full_path(key).is_some();
+    pub fn file_ext_for_type<O: AsRef<OsStr>>(&self, type_name: O) -> &OsStr {
+        return self.format_for_type(type_name).file_ext();
     }
 
     /**
-    Returns the full path of the database entry specified by `key`, if the entry
-    exist. If not, returns `None`.
+    Returns the [`Format`] of the database file at `path`, which is determined
+    by its type folder (see [`DatabaseManager::format_for_type`]).
      */
-    pub fn full_path<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> Option<PathBuf> {
-        let path = self.full_path_unchecked(key);
-        if path.exists() {
-            return Some(path);
-        } else {
-            return None;
+    pub(crate) fn format_of_file(&self, path: &Path) -> &dyn Format {
+        match self.type_folder_of(path) {
+            Some(type_name) => return self.format_for_type(type_name),
+            None => return &*self.format,
         }
     }
 
-    pub(crate) fn full_path_unchecked<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> PathBuf {
-        let key: DatabaseKey = key.into();
-        let mut file_with_ext = OsStr::new(&key.name).to_os_string();
-        if !self.file_ext().is_empty() {
-            file_with_ext.push(".");
-            file_with_ext.push(self.file_ext());
-        }
-        return self
-            .dir()
-            .join(OsStr::new(&key.type_name))
-            .join(file_with_ext);
-    }
-
     /**
-    Returns a reference to the [`Cache`] used within `self`.
-     */
-    pub fn cache(&self) -> &Cache {
-        return &self.cache;
-    }
-
-    /**
-    Returns a mutable reference to the [`Cache`] used within `self`. This can
-    be used to manually add entries to the [`Cache`]. See the docstrings of
-    [`Cache`] and [`CacheEntry`].
+    Returns the checksum of a database file specified by the given `key`. If
+    the file doesn't exist, this function returns `None`. If the file is
+    staged (see [`DatabaseManager::enable_staging`]), the checksum of the
+    staged contents is returned.
      */
-    pub fn cache_mut(&mut self) -> &mut Cache {
-        return &mut self.cache;
+    pub fn checksum<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> Option<u32> {
+        return self.file_checksum(&self.full_path_unchecked(key));
     }
 
-    // ====================================================================
-    // Serialization
-
     /**
-    Serializes the given `instance` into the database according to the given
-    [`WriteOptions`]. If successfull, the path to the written file is returned.
+    Sets the [`ChecksumAlgorithm`] used by `self` to calculate the checksums of
+    files, e.g. for [`DatabaseManager::checksum`] and for the links written by
+    `self`. Defaults to [`ChecksumAlgorithm::Adler32`].
 
-    This is the central function to store new entries within the database. As
-    outlined in the docstring of [`DatabaseManager`], calling this function
-    can actually result in multiple files being written, if `instance` is
-    composed of other [`DatabaseEntry`] implementor instances which are
-    annotated with one of the "link"
-    [attributes for serialization](crate::attributes) (depending on the
-    [`WriteMode`] of [`WriteOptions`]). Using serialization functions from other
-    packages (as e.g. `serde_yaml::to_string`) bypasses the entire linking
-    machinery of this crate and just creates the expected serialized
-    representations.
-    */
-    pub fn write<T: DatabaseEntry>(
-        &mut self,
-        instance: &T,
-        write_options: &WriteOptions,
-    ) -> std::io::Result<PathBuf> {
-        return self
-            .write_verbose_log(instance, write_options, false)
-            .map(|arg| arg.0);
-    }
+    Links store the algorithm their checksum has been calculated with, so
+    links written before changing the algorithm (or by managers using another
+    algorithm) are still validated correctly. They are updated to the new
+    algorithm when their checksum is refreshed, e.g. via
+    [`ChecksumMismatchPolicy::Heal`].
 
-    /**
-    Like [`DatabaseManager::write`], but returns additional [`WriteInfo`] in
-    case writing to the database was successfull.
+    # Examples
 
-    The [`WriteInfo`] contains the following information:
-    - Which files were created new.
-    - Which existing files have been overwritten.
+    ```no_run
+    use serde_mosaic::*;
 
-    These results heavily depend on the settings within [`WriteOptions`], see
-    its docstring for more.
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.set_checksum_algorithm(ChecksumAlgorithm::Sha256);
+    assert_eq!(dbm.checksum_algorithm(), ChecksumAlgorithm::Sha256);
+    ```
      */
-    pub fn write_verbose<T: DatabaseEntry>(
-        &mut self,
-        instance: &T,
-        write_options: &WriteOptions,
-    ) -> std::io::Result<(PathBuf, WriteInfo)> {
-        return self.write_verbose_log(instance, write_options, true);
-    }
-
-    fn write_verbose_log<T: DatabaseEntry>(
-        &mut self,
-        instance: &T,
-        write_options: &WriteOptions,
-        log: bool,
-    ) -> std::io::Result<(PathBuf, WriteInfo)> {
-        let result = WRITE_CONTEXT.with(|thread_context| {
-            // Context only exist for the duration of this function call.
-            let context = WriteContext::new(self, write_options, log);
-
-            // Set the thread context
-            thread_context.set(Some(context.clone()));
-
-            let result = context.write(instance);
-
-            // Remove the thread context
-            thread_context.set(None);
-
-            result
-        });
-
-        // Get writing metadata
-        let write_info = RwInfo::take_write_info();
-
-        match result {
-            Ok(path_buf) => return Ok((path_buf, write_info)),
-            Err(err) => return Err(err),
-        }
-    }
-
-    // ====================================================================
-    // Deserialization
-
-    /**
-    Deserializes an instance of `T` stored within the file with the given `name`
-    from the database and returns it.
-
-    This function first derives the full file path name by concatenating
-    `self.dir()`, the name of `T` (see [`type_name`]) and by combining `name`
-    and `self.file_ext` to the file name. If this file exists, its content is
-    then deserialized using [`Format::deserialize_dyn`] of `self.data_format()`.
-    Any encountered links are resolved by reading the corresponding files and
-    storing the resulting object within the created `T` instance.
-
-    Like [`DatabaseManager::write`], using this function is mandatory in order
-    to read files with links in them. Using serialization functions from other
-    packages (as e.g. `serde_yaml::from_str`) bypasses the entire linking
-    machinery of this crate and will result in failure if any links are stored
-    within the files.
-    */
-    pub fn read<T: DatabaseEntry, O: AsRef<OsStr>>(&mut self, name: O) -> std::io::Result<T> {
-        return self.read_verbose(name).map(|arg| arg.0);
+    pub fn set_checksum_algorithm(&mut self, algorithm: ChecksumAlgorithm) {
+        self.checksum_algorithm = algorithm;
     }
 
     /**
-    Like [`DatabaseManager::read`], but returns additional [`ReadInfo`] in case
-    reading from the database was successfull.
-
-    The [`ReadInfo`] contains all [`ChecksumMismatch`]es which happened when a
-    link contained a checksum which didn't match the linked file. If such a
-    mismatch occurs, the file is still read and its contents are deserialized
-    and replace the link regardless. Therefore, this information is useful to
-    check if a linked file was changed since the creation of the link (e.g. in
-    order to determine whether the returned instance of `T` should be used or
-    not).
+    Returns the [`ChecksumAlgorithm`] of `self`, see
+    [`DatabaseManager::set_checksum_algorithm`].
      */
-    pub fn read_verbose<T: DatabaseEntry, O: AsRef<OsStr>>(
-        &mut self,
-        name: O,
-    ) -> std::io::Result<(T, ReadInfo)> {
-        return self.read_verbose_log(name, true);
-    }
-
-    fn read_verbose_log<T: DatabaseEntry, O: AsRef<OsStr>>(
-        &mut self,
-        name: O,
-        log: bool,
-    ) -> std::io::Result<(T, ReadInfo)> {
-        let result = READ_CONTEXT.with(|thread_context| {
-            // Context only exist for the duration of this function call.
-            let context = ReadContext::new(self, log);
-
-            // Set the thread context
-            thread_context.set(Some(context.clone()));
-
-            let result = context.read(name.as_ref());
-
-            // Remove the thread context
-            thread_context.set(None);
-
-            result
-        });
-
-        // Get reading metadata
-        let read_info = RwInfo::take_read_info();
-
-        match result {
-            Ok(instance) => return Ok((instance, read_info)),
-            Err(err) => return Err(err),
-        }
+    pub fn checksum_algorithm(&self) -> ChecksumAlgorithm {
+        return self.checksum_algorithm;
     }
 
     /**
-    Deserializes the given string using [`Format::deserialize`] from
-    `self.data_format()` and resolves any encountered links using the underlying
-    database.
-
-    This function behaves similarily to [`DatabaseManager::read`], except that
-    the starting point is not a file from the database, but `str` instead.
-    Because the [`Format`] is stored as a trait object inside `self`, it needs
-    to be downcasted into its concrete type `F` inside this function. Specifying
-    the wrong type will result in an error.
+    Returns the checksum the file of `instance` would have if it was written
+    via [`DatabaseManager::write`] with the default [`WriteOptions`], without
+    writing any files. Linked entries are serialized as links whose checksums
+    are calculated in the same way.
 
-    # Examples
+    This allows e.g. comparing an instance against the database before
+    deciding whether to write it:
 
     ```no_run
     use std::ffi::OsStr;
-    use std::sync::Arc;
-
     use serde::{Serialize, Deserialize};
     use serde_mosaic::*;
 
-    #[derive(Serialize, Deserialize, Clone)]
+    #[derive(Serialize, Deserialize)]
     struct Material {
         name: String,
-        cotton_content: f64,
     }
 
     #[typetag::serde]
@@ -1027,460 +1276,4230 @@ impl DatabaseManager {
         }
     }
 
-    #[derive(Deserialize)]
-    struct Shirt {
-        owner: String,
-        #[serde(deserialize_with = "deserialize_arc_link")]
-        #[serde(serialize_with = "serialize_arc_link")]
-        material: Arc<Material>,
-        size: usize
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let steel = Material { name: "steel".into() };
+    if dbm.checksum(&steel) != Some(dbm.checksum_of(&steel).expect("serializable")) {
+        dbm.write(&steel, &WriteOptions::default()).expect("writing succeeds");
     }
+    ```
 
-    let mut dbm = DatabaseManager::new("/path/to/db", SerdeYaml).expect("directory exists");
+    The checksum of an entry whose type is encrypted (see
+    [`Encryption`](crate::Encryption)) can't be predicted, since every write
+    uses a new nonce. In this case, the checksum of the unencrypted file is
+    returned.
+     */
+    pub fn checksum_of<T: DatabaseEntry>(&mut self, instance: &T) -> std::io::Result<u32> {
+        return self
+            .serialize_dry_run(instance)
+            .map(|data| self.checksum_algorithm.checksum_bytes(&data));
+    }
 
-    let shirt_str = indoc::indoc! {"
-    ---
-    owner: Sven
-    material:
-      name: pure_cotton
-    size: 46
-    "};
+    /**
+    Returns `true` if the file of `instance` exists and its contents equal the
+    serialized representation of `instance` (as it would be written by
+    [`DatabaseManager::write`] with the default [`WriteOptions`]). This answers
+    the question whether an instance has been modified since it was read from
+    the database. No files are written.
+
+    Linked entries are compared via the checksums within the links, i.e. the
+    instance is not in sync if a linked entry differs from its file or if the
+    link in the file is outdated. Since the checksums of encrypted files can't
+    be predicted (see [`DatabaseManager::checksum_of`]), links to entries of
+    encrypted types are never in sync.
+     */
+    pub fn is_in_sync<T: DatabaseEntry>(&mut self, instance: &T) -> std::io::Result<bool> {
+        let path = self.full_path_unchecked(instance);
+        let bytes = match self.read_file(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        let bytes = self.decode_file(&path, bytes)?;
+        return Ok(self.serialize_dry_run(instance)? == bytes);
+    }
 
-    let shirt = dbm.from_str::<Shirt, SerdeYaml>(&shirt_str).unwrap();
-    assert_eq!(shirt.material.name, "pure_cotton");
-    ```
+    /**
+    Serializes `instance` like [`DatabaseManager::write`] with the default
+    [`WriteOptions`], but without writing any files.
      */
-    pub fn from_str<T: DeserializeOwned + 'static, F: Format>(
-        &mut self,
-        str: impl AsRef<str>,
-    ) -> std::io::Result<T> {
-        READ_CONTEXT.with(|thread_context| {
+    fn serialize_dry_run<T: DatabaseEntry>(&self, instance: &T) -> std::io::Result<Vec<u8>> {
+        let write_options = WriteOptions::default();
+        return WRITE_CONTEXT.with(|thread_context| {
             // Context only exist for the duration of this function call.
-            let context = ReadContext::new(self, false);
+            let written_names = RefCell::new(HashMap::new());
+            let written_through = RefCell::new(Vec::new());
+            let context =
+                WriteContext::new_dry_run(self, &write_options, &written_names, &written_through);
+
+            // A dry run may happen while reading, so the previous context is
+            // restored afterwards.
+            let previous_context = thread_context.replace(Some(context));
+            let result = self.serialize_entry(instance);
+            thread_context.set(previous_context);
+            let result = result
+                .and_then(|bytes| self.apply_format_options(OsStr::new(T::folder_name()), bytes));
+
+            return result;
+        });
+    }
 
-            // Set the thread context
-            thread_context.set(Some(context.clone()));
+    /**
+    Removes all empty subfolders within the database path `self.dir()`. If the
+    database is sharded (see [`DatabaseManager::set_sharding`]), empty shards
+    are removed as well.
 
-            let dbm = unsafe { &mut *context.database_manager };
+    Be aware that the [`DatabaseManager`] doesn't know which folders belong to
+    the database and which folders do not. For example, the following snippet
+    would remove an empty folder `/path/to/db/foo`, even though it wasn't
+    created by the database manager:
 
-            // Try to downcast the format into F
-            let format: &F =
-                (dbm.format.as_ref() as &dyn Any)
-                    .downcast_ref()
-                    .ok_or(std::io::Error::new(
-                        std::io::ErrorKind::InvalidData,
-                        "given type F does not match the format of self",
-                    ))?;
+    ```no_run
+    use std::path::PathBuf;
+    use serde_mosaic::*;
 
-            let result = format
-                .deserialize::<T>(str.as_ref().as_bytes())
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let unrelated_dir = PathBuf::from("/path/to/db/foo");
 
-            // Remove the thread context
-            thread_context.set(None);
+    assert!(unrelated_dir.exists());
+    assert!(unrelated_dir.read_dir().expect("read permissions available").next().is_none());
 
-            Ok(result)
-        })
-    }
-}
+    let mut dbm = DatabaseManager::new("/path/to/db", SerdeYaml).expect("directory exists or can be created");
 
-impl From<DatabaseManager> for Box<dyn Format> {
-    fn from(value: DatabaseManager) -> Self {
-        return value.format;
-    }
-}
+    assert!(unrelated_dir.exists());
+    assert!(unrelated_dir.read_dir().expect("read permissions available").next().is_none());
 
-impl From<DatabaseManager> for Cache {
-    fn from(value: DatabaseManager) -> Self {
-        return value.cache;
+    dbm.remove_empty_subfolders();
+
+    assert!(!unrelated_dir.exists());
+    ```
+     */
+    pub fn remove_empty_subfolders(&mut self) -> std::io::Result<()> {
+        self.remove_empty_subfolders_priv()?;
+        return Ok(());
     }
-}
 
-// ========================================================================================================
+    /**
+    Implementation of [`DatabaseManager::remove_empty_subfolders`] which
+    returns the paths of the removed folders.
+     */
+    pub(crate) fn remove_empty_subfolders_priv(&mut self) -> std::io::Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        let reader = self.dir().read_dir()?;
+        for folder in reader {
+            let dir_entry = folder?;
+
+            // Check if the folder is empty
+            let path = dir_entry.path();
+            if !dir_entry.file_type()?.is_dir() {
+                continue;
+            }
 
-#[derive(Clone, Copy)]
-pub(crate) struct WriteContext {
-    log: bool,
-    pub(crate) database_manager: *mut DatabaseManager,
-    pub(crate) write_options: *const WriteOptions,
-}
+            // Check if the folder is empty:
+            // https://stackoverflow.com/questions/56744383/how-would-i-check-if-a-directory-is-empty-in-rust
+            if path.read_dir()?.next().is_none() {
+                std::fs::remove_dir_all(&path)?;
+                removed.push(path);
+                continue;
+            }
 
-thread_local!(pub(crate) static WRITE_CONTEXT: Cell<Option<WriteContext>> = Cell::new(None));
+            // Remove empty shards (see DatabaseManager::set_sharding) as well
+            // as the type folder if all its shards were empty
+            if self.sharding.is_sharded() {
+                removed.extend(crate::sharding::remove_empty_shards(&path)?);
+                if path.read_dir()?.next().is_none() {
+                    std::fs::remove_dir_all(&path)?;
+                    removed.push(path);
+                }
+            }
+        }
+        return Ok(removed);
+    }
 
-impl WriteContext {
-    pub(crate) fn new(
-        database_manager: &mut DatabaseManager,
-        write_options: &WriteOptions,
-        log: bool,
-    ) -> Self {
+    /**
+    Tries to remove the specified database file from the database.
+
+    This function essentially derives the file path from the given `key` with
+    [`DatabaseManager::full_path`] and then tries to delete the file. If the
+    file doesn't exist or can't be removed, this function returns an error.
+
+    Be aware that the [`DatabaseManager`] does not know which files "belong" to
+    the database - if a file fitting the naming scheme has been created in an
+    unrelated way, it will still be removed. The detached signature of the
+    file (see [`signature`](crate::signature)) is removed as well. Entries
+    which are locked by another manager (see [`DatabaseManager::lock`]) can't
+    be removed.
+     */
+    pub fn remove<'a, T: Into<DatabaseKey<'a>>>(&mut self, key: T) -> std::io::Result<()> {
+        let file_path = self.full_path_unchecked(key);
+        self.unstage(&file_path);
+        if file_path.exists() {
+            self.check_database_lock()?;
+            self.check_lock(&file_path)?;
+            std::fs::remove_file(&file_path).map_err(|err| {
+                Error::new(
+                    err.kind(),
+                    format!("Could not remove file {}: {}", file_path.display(), err),
+                )
+            })?;
+            for companion_path in [
+                Self::signature_path(&file_path),
+                Self::expiry_path(&file_path),
+            ] {
+                if companion_path.exists() {
+                    std::fs::remove_file(&companion_path)?;
+                }
+            }
+            self.notify(ChangeKind::Removed, &file_path);
+            return Ok(());
+        } else {
+            return Ok(());
+        }
+    }
+
+    /**
+    Remembers that the folder of `T` (see [`DatabaseEntry::folder_name`])
+    contains entries of `T`. If entries of another type have already been
+    written into the folder by `self` or one of its clones, an error is
+    returned.
+     */
+    fn claim_folder<T: DatabaseEntry>(&self) -> std::io::Result<()> {
+        let mut folder_types = self
+            .folder_types
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match folder_types.entry(T::folder_name().into()) {
+            Entry::Occupied(entry) if entry.get().0 != TypeId::of::<T>() => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Folder collision: the types {} and {} would both be written to the folder {} (see DatabaseEntry::folder_name)",
+                        entry.get().1,
+                        std::any::type_name::<T>(),
+                        T::folder_name()
+                    ),
+                ));
+            }
+            Entry::Occupied(_) => (),
+            Entry::Vacant(entry) => {
+                entry.insert((TypeId::of::<T>(), std::any::type_name::<T>()));
+            }
+        }
+        return Ok(());
+    }
+
+    /**
+    Serializes `instance` with the [`Format`] of its type (see
+    [`DatabaseManager::format_for_type`]). The type is remembered during the
+    serialization, so linked fields know the format of the file they are
+    written into.
+     */
+    fn serialize_entry<T: DatabaseEntry>(&self, instance: &T) -> std::io::Result<Vec<u8>> {
+        let type_name = T::folder_name();
+        TYPE_STACK.with(|stack| stack.borrow_mut().push(type_name));
+        let result = self
+            .format_for_type(type_name)
+            .serialize_dyn(instance)
+            .map_err(Error::other);
+        TYPE_STACK.with(|stack| stack.borrow_mut().pop());
+        return result;
+    }
+
+    /**
+    Searches through all direct subfolders (non-recursively) of `self.dir()` and
+    removes all files with the given file name whose file extension matches that
+    of the folder's type (see [`DatabaseManager::file_ext_for_type`]). Similar to [`DatabaseManager::remove`], this function
+    does not discriminate between files which were created by `self` and files
+    which were created by something else.
+     */
+    pub fn remove_all<O: AsRef<OsStr>>(&mut self, name: O) -> std::io::Result<()> {
+        fn remove_all_inner(dbm: &mut DatabaseManager, name: &OsStr) -> std::io::Result<()> {
+            let paths = fs::read_dir(dbm.dir())?;
+
+            // Iterate through all folders of the database
+            for path in paths {
+                if let Ok(dir) = path {
+                    let file_path = dbm.full_path_unchecked((dir.file_name().as_os_str(), name));
+                    if file_path.exists() {
+                        std::fs::remove_file(&file_path)?;
+                        dbm.notify(ChangeKind::Removed, &file_path);
+                    }
+                }
+            }
+
+            return Ok(());
+        }
+        return remove_all_inner(self, name.as_ref());
+    }
+
+    /**
+    Checks if the database has an entry for the given `key`. Staged entries
+    (see [`DatabaseManager::enable_staging`]) exist as well, expired entries
+    (see [`DatabaseManager::write_with_expiry`]) don't.
+     */
+    pub fn exists<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> bool {
+        let file_path = self.full_path_unchecked(key);
+        return self.file_exists(&file_path) && !self.is_expired_file(&file_path);
+    }
+
+    /**
+    Like [`DatabaseManager::exists`], but checks many `keys` at once. The
+    returned vector contains the result for each key in the order of `keys`.
+
+    Instead of checking each file individually, every involved type folder is
+    listed once, which is much faster for large numbers of keys (especially
+    on network file systems). The expiry of an entry (see
+    [`DatabaseManager::write_with_expiry`]) is only read if its expiry file is
+    part of the listing.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let exists = dbm.exists_many([("Material", "cotton"), ("Material", "steel")]);
+    assert_eq!(exists.len(), 2);
+    ```
+     */
+    pub fn exists_many<'a, I, T>(&self, keys: I) -> Vec<bool>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<DatabaseKey<'a>>,
+    {
+        // Listings are cached per folder, which is the shard of the entry if
+        // the database is sharded (see DatabaseManager::set_sharding)
+        let mut listings: HashMap<PathBuf, Option<HashSet<OsString>>> = HashMap::new();
+        return keys
+            .into_iter()
+            .map(|key| {
+                let key: DatabaseKey = key.into();
+                let full_path = self.full_path_unchecked(key);
+                let folder = full_path.parent().unwrap_or(self.dir()).to_path_buf();
+                let listing = listings.entry(folder).or_insert_with_key(|folder| {
+                    let entries = fs::read_dir(folder).ok()?;
+                    return entries
+                        .map(|entry| entry.map(|entry| entry.file_name()))
+                        .collect::<std::io::Result<HashSet<OsString>>>()
+                        .ok();
+                });
+                if self.is_staged(&full_path) {
+                    return true;
+                }
+                let (exists, has_expiry) = match listing {
+                    Some(file_names) => {
+                        let contains = |path: &Path| {
+                            return path
+                                .file_name()
+                                .is_some_and(|file_name| file_names.contains(file_name));
+                        };
+                        (
+                            contains(&full_path),
+                            contains(&Self::expiry_path(&full_path)),
+                        )
+                    }
+                    // The folder could not be listed, fall back to checking the files
+                    None => (full_path.exists(), true),
+                };
+                return exists && !(has_expiry && self.is_expired_file(&full_path));
+            })
+            .collect();
+    }
+
+    /**
+    Returns all `keys` which have no entry in the database, see
+    [`DatabaseManager::exists_many`].
+     */
+    pub fn missing_of<'a, I, T>(&self, keys: I) -> Vec<DatabaseKeyBuf>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<DatabaseKey<'a>>,
+    {
+        let keys: Vec<DatabaseKey> = keys.into_iter().map(Into::into).collect();
+        let exists = self.exists_many(keys.iter().copied());
+        return keys
+            .into_iter()
+            .zip(exists)
+            .filter(|(_, exists)| !exists)
+            .map(|(key, _)| DatabaseKeyBuf::from(key))
+            .collect();
+    }
+
+    /**
+    Returns the full path of the database entry specified by `key`, if the entry
+    exist. If not, returns `None`.
+     */
+    pub fn full_path<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> Option<PathBuf> {
+        let path = self.full_path_unchecked(key);
+        if path.exists() {
+            return Some(path);
+        } else {
+            return None;
+        }
+    }
+
+    pub(crate) fn full_path_unchecked<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> PathBuf {
+        let key: DatabaseKey = key.into();
+        let file_ext = self.file_ext_for_type(key.type_name);
+        let mut file_with_ext = OsStr::new(&key.name).to_os_string();
+        if !file_ext.is_empty() {
+            file_with_ext.push(".");
+            file_with_ext.push(file_ext);
+        }
+        let mut path = self.dir().join(OsStr::new(&key.type_name));
+        if let Some(shard) = self.sharding.shard(key.name) {
+            path.push(shard);
+        }
+        path.push(file_with_ext);
+        return path;
+    }
+
+    /**
+    Returns an error if reading the file at `path` is scripted to fail by a
+    [`FaultInjector`](crate::testing::FaultInjector).
+     */
+    pub(crate) fn inject_read_fault(&self, path: &Path) -> std::io::Result<()> {
+        #[cfg(feature = "testing")]
+        if let Some(fault_injector) = &self.fault_injector {
+            let type_name = self.type_folder_of(path).unwrap_or_default();
+            return fault_injector.before_read(path, type_name);
+        }
+        let _ = path;
+        return Ok(());
+    }
+
+    /**
+    Returns an error if writing the file at `path` is scripted to fail by a
+    [`FaultInjector`](crate::testing::FaultInjector).
+     */
+    pub(crate) fn inject_write_fault(&self, path: &Path) -> std::io::Result<()> {
+        #[cfg(feature = "testing")]
+        if let Some(fault_injector) = &self.fault_injector {
+            let type_name = self.type_folder_of(path).unwrap_or_default();
+            return fault_injector.before_write(path, type_name);
+        }
+        let _ = path;
+        return Ok(());
+    }
+
+    /**
+    Converts the raw contents `bytes` of the database file at `path` into the
+    serialized entry, i.e. decrypts them if the type folder of `path` is
+    encrypted (see [`Encryption`](crate::Encryption)) and decompresses them if
+    they are compressed (see [`Compression`](crate::Compression)).
+     */
+    pub(crate) fn decode_file(&self, path: &Path, bytes: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        return self
+            .decode_file_compressed(path, bytes)
+            .map(|(bytes, _)| bytes);
+    }
+
+    /**
+    Like [`DatabaseManager::decode_file`], but additionally returns whether
+    the file was compressed.
+     */
+    pub(crate) fn decode_file_compressed(
+        &self,
+        path: &Path,
+        bytes: Vec<u8>,
+    ) -> std::io::Result<(Vec<u8>, bool)> {
+        #[cfg(feature = "encryption")]
+        let bytes = if let Some(encryption) = &self.encryption
+            && let Some(type_name) = self.type_folder_of(path)
+            && encryption.is_encrypted(type_name)
+        {
+            encryption.decrypt_bytes(type_name, &bytes)?
+        } else {
+            bytes
+        };
+        let _ = path;
+        if !bytes.starts_with(GZIP_MAGIC) {
+            return Ok((bytes, false));
+        }
+        #[cfg(feature = "compression")]
+        return Ok((crate::compression::decompress(&bytes)?, true));
+        #[cfg(not(feature = "compression"))]
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "file is compressed, but the compression feature is disabled",
+        ));
+    }
+
+    /**
+    Returns `true` if the raw contents of the database file at `path` differ
+    from the serialized entry, i.e. if [`DatabaseManager::encode_file`] and
+    [`DatabaseManager::decode_file`] are not a no-op for this file.
+     */
+    pub(crate) fn encodes_file(&self, path: &Path) -> bool {
+        #[cfg(feature = "encryption")]
+        if let Some(encryption) = &self.encryption
+            && let Some(type_name) = self.type_folder_of(path)
+        {
+            return encryption.is_encrypted(type_name);
+        }
+        let _ = path;
+        return false;
+    }
+
+    /**
+    Counterpart to [`DatabaseManager::decode_file`]: Converts the serialized
+    entry `bytes` into the raw contents of the database file at `path`.
+     */
+    pub(crate) fn encode_file(&self, path: &Path, bytes: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        #[cfg(feature = "encryption")]
+        if let Some(encryption) = &self.encryption
+            && let Some(type_name) = self.type_folder_of(path)
+            && encryption.is_encrypted(type_name)
+        {
+            return encryption.encrypt_bytes(type_name, &bytes);
+        }
+        let _ = path;
+        return Ok(bytes);
+    }
+
+    /**
+    Returns a reference to the [`Cache`] used within `self`. If a
+    [`SharedCache`](crate::SharedCache) is set (see
+    [`DatabaseManager::set_shared_cache`]), this cache is not used.
+     */
+    pub fn cache(&self) -> &Cache {
+        return &self.cache;
+    }
+
+    /**
+    Returns a mutable reference to the [`Cache`] used within `self`. This can
+    be used to manually add entries to the [`Cache`]. See the docstrings of
+    [`Cache`] and [`CacheEntry`]. If a [`SharedCache`](crate::SharedCache) is
+    set (see [`DatabaseManager::set_shared_cache`]), this cache is not used.
+     */
+    pub fn cache_mut(&mut self) -> &mut Cache {
+        return &mut self.cache;
+    }
+
+    /**
+    Returns the cached instance of the type `T` whose [`DatabaseEntry::name`]
+    is `name` or [`None`] if no such instance is stored in the [`Cache`] of
+    `self`. Looking up an instance via this method neither counts as a cache
+    hit (see [`DatabaseManager::cache_stats`]) nor checks whether the instance
+    is still up to date.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use std::sync::Arc;
+
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let pure_cotton = Arc::new(Material {
+        name: "pure_cotton".into(),
+        cotton_content: 100.0,
+    });
+    assert!(dbm.cache_insert(pure_cotton.clone()).is_none());
+
+    let cached = dbm.cache_get::<Material>("pure_cotton").expect("instance is cached");
+    assert!(Arc::ptr_eq(&cached, &pure_cotton));
+
+    assert!(dbm.cache_remove::<Material>("pure_cotton").is_some());
+    assert!(dbm.cache_get::<Material>("pure_cotton").is_none());
+    ```
+     */
+    pub fn cache_get<T: DatabaseEntry + Send + Sync>(
+        &self,
+        name: impl AsRef<OsStr>,
+    ) -> Option<Arc<T>> {
+        let type_id = TypeId::of::<T>();
+        let entry = match &self.shared_cache {
+            Some(shared_cache) => shared_cache
+                .read()
+                .get(&type_id)?
+                .get(name.as_ref())?
+                .clone(),
+            None => self.cache.get(&type_id)?.get(name.as_ref())?.clone(),
+        };
+        let any_arc = entry.arc as Arc<dyn Any + Send + Sync + 'static>;
+        return any_arc.downcast().ok();
+    }
+
+    /**
+    Puts `instance` into the [`Cache`] of `self` under its
+    [`DatabaseEntry::name`], so it is reused when a link to it is
+    deserialized. If an instance of the same type and name was cached before,
+    it is replaced and returned. See [`CacheEntry::insert`] for details.
+     */
+    pub fn cache_insert<T: DatabaseEntry + Send + Sync>(
+        &mut self,
+        instance: Arc<T>,
+    ) -> Option<Arc<T>> {
+        return self.with_cache_mut(|cache| CacheEntry::insert(cache, instance));
+    }
+
+    /**
+    Removes the cached instance of the type `T` whose [`DatabaseEntry::name`]
+    is `name` from the [`Cache`] of `self` and returns it. Returns [`None`] if
+    no such instance was cached.
+     */
+    pub fn cache_remove<T: DatabaseEntry + Send + Sync>(
+        &mut self,
+        name: impl AsRef<OsStr>,
+    ) -> Option<Arc<T>> {
+        let type_id = TypeId::of::<T>();
+        let entry = self.with_cache_mut(|cache| {
+            let subcache = cache.get_mut(&type_id)?;
+            let entry = subcache.remove(name.as_ref())?;
+            if subcache.is_empty() {
+                cache.remove(&type_id);
+            }
+            return Some(entry);
+        })?;
+        let any_arc = entry.arc as Arc<dyn Any + Send + Sync + 'static>;
+        return any_arc.downcast().ok();
+    }
+
+    /**
+    Removes all cached instances of the type `T` from the [`Cache`] of `self`
+    and returns their number. The instances of other types are kept.
+     */
+    pub fn cache_clear<T: DatabaseEntry + Send + Sync>(&mut self) -> usize {
+        return self.with_cache_mut(|cache| {
+            return cache
+                .remove(&TypeId::of::<T>())
+                .map_or(0, |subcache| subcache.len());
+        });
+    }
+
+    /**
+    Sets the [`CachePolicy`] which specifies whether written entries are put
+    into the [`Cache`] of `self`. The cache is not modified by this call.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use std::sync::Arc;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Shirt {
+        owner: String,
+        #[serde(serialize_with = "serialize_arc_link")]
+        #[serde(deserialize_with = "deserialize_arc_link")]
+        material: Arc<Material>,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Shirt {
+        fn name(&self) -> &OsStr {
+            self.owner.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.set_cache_policy(CachePolicy::WriteThrough);
+
+    let cotton = Arc::new(Material { name: "cotton".into() });
+    let shirt = Shirt { owner: "joe".into(), material: cotton.clone() };
+    dbm.write(&shirt, &WriteOptions::default()).expect("writing succeeds");
+
+    // The material is taken from the cache instead of being read again
+    let shirt: Shirt = dbm.read("joe").expect("entry exists");
+    assert!(Arc::ptr_eq(&shirt.material, &cotton));
+    ```
+     */
+    pub fn set_cache_policy(&mut self, cache_policy: CachePolicy) {
+        self.cache_policy = cache_policy;
+    }
+
+    /**
+    Returns the [`CachePolicy`] of `self`, see
+    [`DatabaseManager::set_cache_policy`].
+     */
+    pub fn cache_policy(&self) -> CachePolicy {
+        return self.cache_policy;
+    }
+
+    /**
+    Sets the [`CacheInvalidation`] which specifies how cached instances whose
+    file has been changed are detected. For [`CacheInvalidation::Watch`], the
+    watcher is started by this call, which fails if the directory of `self`
+    can't be watched. The watcher is shared by the clones of `self` and
+    stopped when another invalidation is set (or when the last clone is
+    dropped). The cache is not modified by this call.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.set_cache_invalidation(CacheInvalidation::OnRead)
+        .expect("invalidation can be set");
+    ```
+     */
+    pub fn set_cache_invalidation(
+        &mut self,
+        cache_invalidation: CacheInvalidation,
+    ) -> std::io::Result<()> {
+        #[cfg(feature = "watch")]
+        {
+            self.cache_watcher = match cache_invalidation {
+                CacheInvalidation::Watch => Some(Arc::new(Mutex::new(self.watch()?))),
+                _ => None,
+            };
+        }
+        self.cache_invalidation = cache_invalidation;
+        return Ok(());
+    }
+
+    /**
+    Returns the [`CacheInvalidation`] of `self`, see
+    [`DatabaseManager::set_cache_invalidation`].
+     */
+    pub fn cache_invalidation(&self) -> CacheInvalidation {
+        return self.cache_invalidation;
+    }
+
+    /**
+    Removes all [`Cache`] entries whose file has been changed according to the
+    notifications received by the watcher of [`CacheInvalidation::Watch`].
+    Manually created entries (without checksum) are kept.
+     */
+    #[cfg(feature = "watch")]
+    fn evict_watched_cache_entries(&mut self) {
+        let Some(watcher) = self.cache_watcher.as_ref() else {
+            return;
+        };
+        let changed: HashSet<DatabaseKeyBuf> = watcher
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .receiver()
+            .try_iter()
+            .map(|event| event.key)
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        self.with_cache_mut(|cache| {
+            for subcache in cache.values_mut() {
+                subcache.retain(|name, entry| {
+                    return entry.checksum.is_none()
+                        || !changed.contains(&DatabaseKeyBuf::new(entry.arc.typetag_name(), name));
+                });
+            }
+            cache.retain(|_, subcache| !subcache.is_empty());
+        });
+    }
+
+    // ====================================================================
+    // Serialization
+
+    /**
+    Serializes the given `instance` into the database according to the given
+    [`WriteOptions`]. If successfull, the path to the written file is returned.
+
+    This is the central function to store new entries within the database. As
+    outlined in the docstring of [`DatabaseManager`], calling this function
+    can actually result in multiple files being written, if `instance` is
+    composed of other [`DatabaseEntry`] implementor instances which are
+    annotated with one of the "link"
+    [attributes for serialization](crate::attributes) (depending on the
+    [`WriteMode`] of [`WriteOptions`]). Using serialization functions from other
+    packages (as e.g. `serde_yaml::to_string`) bypasses the entire linking
+    machinery of this crate and just creates the expected serialized
+    representations.
+
+    The manager remembers which type has been written into which folder (see
+    [`DatabaseEntry::folder_name`]). If two different types with the same folder
+    name are written, an error of kind [`ErrorKind::InvalidInput`] is returned
+    for the second one instead of silently mixing their entries.
+    */
+    pub fn write<T: DatabaseEntry>(
+        &mut self,
+        instance: &T,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<PathBuf> {
+        return self
+            .write_verbose_log(instance, write_options, false, false)
+            .map(|arg| arg.0);
+    }
+
+    /**
+    Like [`DatabaseManager::write`], but returns additional [`WriteInfo`] in
+    case writing to the database was successfull.
+
+    The [`WriteInfo`] contains the following information:
+    - Which files were created new.
+    - Which existing files have been overwritten.
+
+    These results heavily depend on the settings within [`WriteOptions`], see
+    its docstring for more.
+     */
+    pub fn write_verbose<T: DatabaseEntry>(
+        &mut self,
+        instance: &T,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<(PathBuf, WriteInfo)> {
+        return self.write_verbose_log(instance, write_options, true, false);
+    }
+
+    /**
+    Like [`DatabaseManager::write`], but fails if any file recorded in
+    `revision` has been modified since it was read (optimistic concurrency
+    control). The `revision` is obtained from [`ReadInfo::revision`] and
+    covers the read entry as well as all linked entries, so concurrent
+    modifications of linked entries are detected as well.
+
+    If a file has changed, nothing is written and an error of kind
+    [`ErrorKind::Other`] listing the changed files is returned. The check is
+    performed right before writing, so writes by other processes between the
+    check and the write are not detected.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        price: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let (mut steel, read_info) = dbm.read_verbose::<Material, _>("steel").expect("entry exists");
+    steel.price *= 1.1;
+
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    if dbm.write_if_revision(&steel, &read_info.revision, &write_options).is_err() {
+        // Someone else modified the entry in the meantime => reload and retry
+    }
+    ```
+     */
+    pub fn write_if_revision<T: DatabaseEntry>(
+        &mut self,
+        instance: &T,
+        revision: &Revision,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<PathBuf> {
+        let changed_files = revision.changed_files();
+        if !changed_files.is_empty() {
+            let changed_files: Vec<String> = changed_files
+                .iter()
+                .map(|file_path| file_path.display().to_string())
+                .collect();
+            return Err(Error::other(format!(
+                "Revision conflict: the files {} have been modified since they were read",
+                changed_files.join(", ")
+            )));
+        }
+        return self.write(instance, write_options);
+    }
+
+    /**
+    Like [`DatabaseManager::write_verbose`], but files whose serialized
+    contents equal the existing files are not written again. This applies to
+    `instance` as well as to all linked entries. The paths of the skipped
+    files are listed in [`WriteInfo::unchanged_files`].
+
+    Skipping unchanged files keeps their modification time, which e.g. allows
+    incremental backups of the database to only copy the actually modified
+    files.
+     */
+    pub fn write_if_changed<T: DatabaseEntry>(
+        &mut self,
+        instance: &T,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<(PathBuf, WriteInfo)> {
+        return self.write_verbose_log(instance, write_options, true, true);
+    }
+
+    /**
+    Serializes `instance` like [`DatabaseManager::write`], but returns the
+    serialized representation of `instance` instead of writing it into its
+    file. The linked entries of `instance` are still written to the database
+    according to `write_options`, so the returned links point to existing
+    entries. This is useful if e.g. the parent entry is sent over a network
+    socket, while its components should land in the database.
+
+    The returned bytes are neither compressed nor encrypted, even if the
+    written files would be. Use [`DatabaseManager::to_string_linked`] for
+    text-based formats.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Shirt {
+        name: String,
+        #[serde(serialize_with = "serialize_link")]
+        #[serde(deserialize_with = "deserialize_link")]
+        material: Material,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Shirt {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let shirt = Shirt {
+        name: "t_shirt".into(),
+        material: Material { name: "pure_cotton".into(), cotton_content: 1.0 },
+    };
+    let text = dbm.to_string_linked(&shirt, &WriteOptions::default()).expect("serializable");
+    assert!(text.contains("pure_cotton"));
+    assert!(dbm.exists(("Material", "pure_cotton")));
+    assert!(!dbm.exists(("Shirt", "t_shirt")));
+    ```
+     */
+    pub fn to_bytes_linked<T: DatabaseEntry>(
+        &mut self,
+        instance: &T,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<Vec<u8>> {
+        return self
+            .with_write_context(write_options, false, false, |context| {
+                return context.serialize(instance);
+            })
+            .map(|(bytes, _)| bytes);
+    }
+
+    /**
+    Like [`DatabaseManager::to_bytes_linked`], but returns the serialized
+    representation as a [`String`]. If it is not valid UTF-8 (e.g. for binary
+    formats), an error of kind [`ErrorKind::InvalidData`] is returned.
+     */
+    pub fn to_string_linked<T: DatabaseEntry>(
+        &mut self,
+        instance: &T,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<String> {
+        let bytes = self.to_bytes_linked(instance, write_options)?;
+        return String::from_utf8(bytes).map_err(|err| Error::new(ErrorKind::InvalidData, err));
+    }
+
+    /**
+    Serializes `instance` like [`DatabaseManager::write`] with
+    [`WriteMode::Flat`] and returns the serialized representation. Since all
+    linked entries are inlined, no files are written at all.
+     */
+    pub fn to_bytes_flat<T: DatabaseEntry>(&self, instance: &T) -> std::io::Result<Vec<u8>> {
+        let mut write_options = WriteOptions::default();
+        write_options.write_mode = WriteMode::Flat;
+        return WRITE_CONTEXT.with(|thread_context| {
+            // Context only exist for the duration of this function call.
+            let written_names = RefCell::new(HashMap::new());
+            let written_through = RefCell::new(Vec::new());
+            let context =
+                WriteContext::new_dry_run(self, &write_options, &written_names, &written_through);
+
+            // The previous context is restored afterwards, see
+            // DatabaseManager::serialize_dry_run
+            let previous_context = thread_context.replace(Some(context));
+            let result = context.serialize(instance);
+            thread_context.set(previous_context);
+            return result;
+        });
+    }
+
+    /**
+    Like [`DatabaseManager::to_bytes_flat`], but returns the serialized
+    representation as a [`String`]. If it is not valid UTF-8 (e.g. for binary
+    formats), an error of kind [`ErrorKind::InvalidData`] is returned.
+     */
+    pub fn to_string_flat<T: DatabaseEntry>(&self, instance: &T) -> std::io::Result<String> {
+        let bytes = self.to_bytes_flat(instance)?;
+        return String::from_utf8(bytes).map_err(|err| Error::new(ErrorKind::InvalidData, err));
+    }
+
+    /**
+    Like [`DatabaseManager::write`], but uses the [`WriteOptions`] registered
+    under the name `profile` (see [`DatabaseManager::set_write_profile`]).
+    Returns an error of kind [`ErrorKind::NotFound`] if no such profile
+    exists.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+
+    let mut import = WriteOptions::default();
+    import.name_collisions = NameCollisions::KeepExisting;
+    dbm.set_write_profile("import", import);
+
+    let mut regenerate = WriteOptions::default();
+    regenerate.name_collisions = NameCollisions::Overwrite;
+    dbm.set_write_profile("regenerate", regenerate);
+
+    // Somewhere else in the code base:
+    // dbm.write_with_profile(&entry, "import")?;
+    ```
+     */
+    pub fn write_with_profile<T: DatabaseEntry>(
+        &mut self,
+        instance: &T,
+        profile: &str,
+    ) -> std::io::Result<PathBuf> {
+        let write_options = self.write_profile(profile).cloned().ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("No write profile named {}", profile),
+            )
+        })?;
+        return self.write(instance, &write_options);
+    }
+
+    /**
+    Registers `write_options` under the name `profile`, so they can be used
+    via [`DatabaseManager::write_with_profile`]. If a profile with this name
+    already existed, it is replaced and returned.
+     */
+    pub fn set_write_profile<S: Into<String>>(
+        &mut self,
+        profile: S,
+        write_options: WriteOptions,
+    ) -> Option<WriteOptions> {
+        return self.write_profiles.insert(profile.into(), write_options);
+    }
+
+    /**
+    Returns the [`WriteOptions`] registered under the name `profile`, if any.
+     */
+    pub fn write_profile(&self, profile: &str) -> Option<&WriteOptions> {
+        return self.write_profiles.get(profile);
+    }
+
+    /**
+    Removes the profile `profile` and returns its [`WriteOptions`], if it
+    existed.
+     */
+    pub fn remove_write_profile(&mut self, profile: &str) -> Option<WriteOptions> {
+        return self.write_profiles.remove(profile);
+    }
+
+    /**
+    Enables or disables the preservation of unknown fields (disabled by
+    default).
+
+    Files are often decorated by hand with additional fields which are not
+    part of the corresponding struct (e.g. annotations for other tools). If
+    the struct ignores unknown fields, these fields are silently dropped when
+    the entry is read, modified and written again. If preservation is
+    enabled, the unknown fields of every read entry (including linked
+    entries) are remembered by `self` and appended to the entry again the
+    next time it is written by `self`. An unknown field is a field of the
+    file which is not contained in the serialized representation of the read
+    instance. Only the top-level fields of an entry are considered.
+
+    This requires a [`Format`] which supports untyped [`Value`]s (see
+    [`Format::deserialize_value`]). Disabling preservation forgets all
+    remembered fields.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        price: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.set_preserve_unknown_fields(true);
+
+    // The file contains an additional field "reviewed_by: Hank"
+    let mut steel: Material = dbm.read("steel").expect("entry exists");
+    steel.price *= 1.1;
+
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&steel, &write_options).expect("writing succeeds");
+    // The file still contains "reviewed_by: Hank"
+    ```
+     */
+    pub fn set_preserve_unknown_fields(&mut self, preserve_unknown_fields: bool) {
+        self.preserve_unknown_fields = preserve_unknown_fields;
+        if !preserve_unknown_fields {
+            self.unknown_fields
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+    }
+
+    /**
+    Returns whether unknown fields are preserved, see
+    [`DatabaseManager::set_preserve_unknown_fields`].
+     */
+    pub fn preserves_unknown_fields(&self) -> bool {
+        return self.preserve_unknown_fields;
+    }
+
+    /**
+    Returns the unknown fields (key-value pairs) which have been remembered
+    for the entry `key` when it was last read, see
+    [`DatabaseManager::set_preserve_unknown_fields`].
+     */
+    pub fn unknown_fields<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> Vec<(Value, Value)> {
+        let key: DatabaseKeyBuf = key.into().into();
+        return self
+            .unknown_fields
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+            .cloned()
+            .unwrap_or_default();
+    }
+
+    /**
+    Remembers the fields of the serialized entry `bytes` which are not part of
+    the serialized representation of `instance`. Formats without support for
+    untyped values are ignored.
+     */
+    fn capture_unknown_fields<T: DatabaseEntry>(&self, instance: &T, name: &OsStr, bytes: &[u8]) {
+        let key = DatabaseKeyBuf::new(T::folder_name(), name);
+        let format = self.format_for_type(T::folder_name());
+        let read_fields = format
+            .deserialize_value(bytes)
+            .ok()
+            .and_then(Value::into_entry_contents);
+        let Some(Value::Map(read_fields)) = read_fields else {
+            return;
+        };
+        let Some(Value::Map(known_fields)) = self
+            .serialize_dry_run(instance)
+            .ok()
+            .and_then(|bytes| format.deserialize_value(&bytes).ok())
+            .and_then(Value::into_entry_contents)
+        else {
+            return;
+        };
+
+        let unknown: Vec<(Value, Value)> = read_fields
+            .into_iter()
+            .filter(|(field, _)| known_fields.iter().all(|(known, _)| known != field))
+            .collect();
+        let mut unknown_fields = self
+            .unknown_fields
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if unknown.is_empty() {
+            unknown_fields.remove(&key);
+        } else {
+            unknown_fields.insert(key, unknown);
+        }
+    }
+
+    /**
+    Appends the remembered unknown fields of the entry `key` to the serialized
+    entry `bytes` (see [`DatabaseManager::set_preserve_unknown_fields`]). Fields
+    which are already contained in `bytes` are not appended.
+     */
+    fn append_unknown_fields(&self, key: DatabaseKey, bytes: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        if !self.preserve_unknown_fields {
+            return Ok(bytes);
+        }
+        let unknown = self.unknown_fields(key);
+        if unknown.is_empty() {
+            return Ok(bytes);
+        }
+        let format = self.format_for_type(key.type_name);
+        let mut value = format
+            .deserialize_value(&bytes)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        if let Value::Map(entries) = &mut value
+            && let [(_, Value::Map(fields))] = entries.as_mut_slice()
+        {
+            for (field, field_value) in unknown {
+                if fields.iter().all(|(known, _)| *known != field) {
+                    fields.push((field, field_value));
+                }
+            }
+        }
+        return format
+            .serialize_value(&value)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err));
+    }
+
+    /**
+    Returns the path of the next unused file `name_<counter>` (+ file
+    extension) of the type folder `type_name`, see
+    [`NameCollisions::AdjustName`].
+
+    The highest used counter is found by listing the type folder once and is
+    then remembered per name, so subsequent calls usually need only a single
+    file system check. Gaps in the counters (e.g. from removed files) are not
+    filled.
+     */
+    fn adjusted_file_path(&mut self, type_name: &OsStr, name: &OsStr) -> std::io::Result<PathBuf> {
+        let file_path = |dbm: &Self, counter: u64| {
+            let mut adjusted_name = name.to_os_string();
+            adjusted_name.push(format!("_{}", counter));
+            return dbm.full_path_unchecked((type_name, adjusted_name.as_os_str()));
+        };
+
+        let key = self.dir().join(type_name).join(name);
+        let mut counter = match self.name_counters.get(&key) {
+            Some(counter) => *counter,
+            None => {
+                // Find the highest counter which is already in use
+                let prefix = format!("{}_", name.to_string_lossy());
+                let mut next = 0;
+                for entry_name in self.entry_names(type_name)? {
+                    if let Some(used) = entry_name
+                        .to_str()
+                        .and_then(|entry_name| entry_name.strip_prefix(&prefix))
+                        .and_then(|counter| counter.parse::<u64>().ok())
+                    {
+                        next = next.max(used + 1);
+                    }
+                }
+                next
+            }
+        };
+
+        // The folder may have been modified by someone else in the meantime
+        while self.file_exists(&file_path(self, counter)) {
+            counter += 1;
+        }
+        self.name_counters.insert(key, counter + 1);
+        return Ok(file_path(self, counter));
+    }
+
+    /**
+    Removes the least recently written entries of all types written by a
+    write call until their [`EntryQuotas`] are met. `written` contains the
+    files written by the call, which are never removed. Neither are entries
+    which are still linked by other entries (see
+    [`DatabaseManager::find_orphans`]). If the links into a type folder can't
+    be determined, an error is returned and no further entries are removed.
+     */
+    fn evict_over_quota(
+        &mut self,
+        entry_quotas: &EntryQuotas,
+        written: &HashMap<PathBuf, OsString>,
+    ) -> std::io::Result<Vec<PathBuf>> {
+        let type_names: BTreeSet<OsString> = written
+            .keys()
+            .filter_map(|path| self.type_folder_of(path))
+            .map(OsStr::to_os_string)
+            .collect();
+        let mut evicted = Vec::new();
+        for type_name in type_names {
+            let Some(quota) = entry_quotas.quota_for(&type_name) else {
+                continue;
+            };
+            let count = self.entry_count(&type_name)?;
+            if count <= quota {
+                continue;
+            }
+
+            // Removing linked entries would leave dangling links behind
+            let linked = self.linked_names_of_type(&type_name)?;
+
+            // Sort by modification time, ties are broken by the file path
+            let mut candidates = Vec::new();
+            for name in self.entry_names(&type_name)? {
+                let path = self.full_path_unchecked((type_name.as_os_str(), name.as_os_str()));
+                if written.contains_key(&path)
+                    || self.check_lock(&path).is_err()
+                    || linked.contains(&name)
+                {
+                    continue;
+                }
+                let modified = fs::metadata(&path)?.modified()?;
+                candidates.push((modified, path, name));
+            }
+            candidates.sort();
+            for (_, path, name) in candidates.into_iter().take((count - quota) as usize) {
+                self.remove((type_name.as_os_str(), name.as_os_str()))?;
+                evicted.push(path);
+            }
+        }
+        return Ok(evicted);
+    }
+
+    fn write_verbose_log<T: DatabaseEntry>(
+        &mut self,
+        instance: &T,
+        write_options: &WriteOptions,
+        log: bool,
+        skip_unchanged: bool,
+    ) -> std::io::Result<(PathBuf, WriteInfo)> {
+        return self.with_write_context(write_options, log, skip_unchanged, |context| {
+            return context.write(instance, false);
+        });
+    }
+
+    /**
+    Calls `f` with a [`WriteContext`] which is set as the context of the
+    current thread, so all entries written by `f` (including linked entries)
+    share the same `write_options`. Afterwards, the written entries are put
+    into the [`Cache`], quotas are enforced and subscribers are notified (see
+    [`DatabaseManager::subscribe`]).
+     */
+    fn with_write_context<R, F>(
+        &mut self,
+        write_options: &WriteOptions,
+        log: bool,
+        skip_unchanged: bool,
+        f: F,
+    ) -> std::io::Result<(R, WriteInfo)>
+    where
+        F: FnOnce(&WriteContext) -> std::io::Result<R>,
+    {
+        self.check_database_lock()?;
+        let written_names = RefCell::new(HashMap::new());
+        let written_through = RefCell::new(Vec::new());
+        let content_index = RefCell::new(HashMap::new());
+        let result = WRITE_CONTEXT.with(|thread_context| {
+            // Context only exist for the duration of this function call.
+            let log_changes = log || self.has_subscribers();
+            let mut context = WriteContext::new(
+                self,
+                write_options,
+                &written_names,
+                &written_through,
+                &content_index,
+                log_changes,
+            );
+            context.skip_unchanged = skip_unchanged;
+
+            // Set the thread context
+            thread_context.set(Some(context.clone()));
+
+            let result = f(&context);
+
+            // Remove the thread context
+            thread_context.set(None);
+
+            result
+        });
+
+        // Get writing metadata
+        let mut write_info = RwInfo::take_write_info();
+
+        if result.is_ok() {
+            for (type_id, name, entry, bytes) in written_through.into_inner() {
+                self.with_cache_mut(|cache| {
+                    cache
+                        .entry(type_id)
+                        .or_default()
+                        .insert(name.clone(), entry);
+                });
+                self.record_cache_insert(type_id, &name, bytes, false);
+            }
+            if write_options.entry_quotas.policy == QuotaPolicy::EvictLeastRecentlyWritten {
+                write_info.evicted_files = self
+                    .evict_over_quota(&write_options.entry_quotas, &written_names.into_inner())
+                    .map_err(|err| {
+                        Error::new(
+                            err.kind(),
+                            format!(
+                                "Writing succeeded, but enforcing the entry quotas failed: {}",
+                                err
+                            ),
+                        )
+                    })?;
+            }
+        }
+
+        match result {
+            // Staged files are reported when they are flushed
+            Ok(value) if self.is_staging() => return Ok((value, write_info)),
+            Ok(value) => {
+                for file_path in write_info.created_files.iter() {
+                    self.notify(ChangeKind::Created, file_path);
+                }
+                for file_path in write_info.overwritten_files.iter() {
+                    self.notify(ChangeKind::Overwritten, file_path);
+                }
+                return Ok((value, write_info));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    // ====================================================================
+    // Deserialization
+
+    /**
+    Deserializes an instance of `T` stored within the file with the given `name`
+    from the database and returns it.
+
+    This function first derives the full file path name by concatenating
+    `self.dir()`, the name of `T` (see [`type_name`]) and by combining `name`
+    and `self.file_ext` to the file name. If this file exists, its content is
+    then deserialized using [`Format::deserialize_dyn`] of `self.data_format()`.
+    Any encountered links are resolved by reading the corresponding files and
+    storing the resulting object within the created `T` instance.
+
+    Like [`DatabaseManager::write`], using this function is mandatory in order
+    to read files with links in them. Using serialization functions from other
+    packages (as e.g. `serde_yaml::from_str`) bypasses the entire linking
+    machinery of this crate and will result in failure if any links are stored
+    within the files.
+    */
+    pub fn read<T: DatabaseEntry, O: AsRef<OsStr>>(&mut self, name: O) -> std::io::Result<T> {
+        return self.read_with(name, &ReadOptions::default());
+    }
+
+    /**
+    Like [`DatabaseManager::read`], but the reading behaviour can be customized
+    via the given [`ReadOptions`].
+     */
+    pub fn read_with<T: DatabaseEntry, O: AsRef<OsStr>>(
+        &mut self,
+        name: O,
+        read_options: &ReadOptions,
+    ) -> std::io::Result<T> {
+        return self
+            .read_verbose_log(name, read_options, false)
+            .map(|arg| arg.0);
+    }
+
+    /**
+    Like [`DatabaseManager::read`], but returns the entry as an `Arc<T>` which
+    is taken from the [`Cache`] of `self` if possible. Otherwise, the entry is
+    read and put into the cache afterwards, so subsequent calls of this method
+    and links to the entry (see
+    [`deserialize_arc_link`](crate::attributes::deserialize_arc_link)) reuse
+    the same instance. A cached instance is only reused if the checksum of
+    the file didn't change since it was cached (manually cached instances
+    are always reused).
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use std::sync::Arc;
+
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let first = dbm.read_arc::<Material, _>("pure_cotton").expect("entry exists");
+    let second = dbm.read_arc::<Material, _>("pure_cotton").expect("entry exists");
+    assert!(Arc::ptr_eq(&first, &second));
+    ```
+     */
+    pub fn read_arc<T: DatabaseEntry + Send + Sync, O: AsRef<OsStr>>(
+        &mut self,
+        name: O,
+    ) -> std::io::Result<Arc<T>> {
+        let name = name.as_ref();
+        let type_id = TypeId::of::<T>();
+        let algorithm = self.checksum_algorithm;
+        let file_path = self.full_path_unchecked((T::folder_name(), name));
+        let checksum = self.file_checksum_with(&file_path, algorithm);
+
+        let cached = self.with_cache_mut(|cache| {
+            let entry = cache.get(&type_id)?.get(name)?;
+            let up_to_date = match entry.checksum {
+                Some(checksum_of_arc) => {
+                    entry.algorithm == algorithm && Some(checksum_of_arc) == checksum
+                }
+                None => true,
+            };
+            if !up_to_date {
+                return None;
+            }
+            let any_arc = entry.arc.clone() as Arc<dyn Any + Send + Sync + 'static>;
+            return any_arc.downcast::<T>().ok();
+        });
+        if let Some(instance) = cached {
+            self.record_cache_hit(type_id, name);
+            return Ok(instance);
+        }
+
+        let instance: Arc<T> = Arc::new(self.read(name)?);
+        let entry = CacheEntry {
+            arc: instance.clone(),
+            checksum,
+            algorithm,
+        };
+        self.with_cache_mut(|cache| {
+            cache
+                .entry(type_id)
+                .or_default()
+                .insert(name.to_os_string(), entry);
+        });
+        let bytes = self.cached_file_size(T::folder_name(), name);
+        self.record_cache_insert(type_id, name, bytes, true);
+        return Ok(instance);
+    }
+
+    /**
+    Like [`DatabaseManager::read`], but substitutes the parameter placeholders
+    (`{{name}}`) within the read files with the given `parameters`. This
+    allows storing multiple near-identical entries as a single parameterized
+    file. See [`ReadOptions::parameters`] for details.
+
+    # Examples
+
+    ```no_run
+    use std::collections::HashMap;
+    use std::ffi::OsStr;
+
+    use serde::{Deserialize, Serialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    // The file Material/blend.yaml contains the following:
+    // ---
+    // Material:
+    //   name: "blend_{{ratio}}"
+    //   cotton_content: "{{ratio}}"
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let parameters = HashMap::from([("ratio".to_string(), Value::F64(80.0))]);
+    let material: Material = dbm.read_with_params("blend", &parameters).expect("file exists");
+    assert_eq!(material.name, "blend_80");
+    assert_eq!(material.cotton_content, 80.0);
+    ```
+     */
+    pub fn read_with_params<T: DatabaseEntry, O: AsRef<OsStr>>(
+        &mut self,
+        name: O,
+        parameters: &HashMap<String, Value>,
+    ) -> std::io::Result<T> {
+        let read_options = ReadOptions {
+            parameters: parameters.clone(),
+            ..Default::default()
+        };
+        return self.read_with(name, &read_options);
+    }
+
+    /**
+    Like [`DatabaseManager::read`], but returns additional [`ReadInfo`] in case
+    reading from the database was successfull.
+
+    The [`ReadInfo`] contains all [`ChecksumMismatch`]es which happened when a
+    link contained a checksum which didn't match the linked file. If such a
+    mismatch occurs, the file is still read and its contents are deserialized
+    and replace the link regardless. Therefore, this information is useful to
+    check if a linked file was changed since the creation of the link (e.g. in
+    order to determine whether the returned instance of `T` should be used or
+    not).
+     */
+    pub fn read_verbose<T: DatabaseEntry, O: AsRef<OsStr>>(
+        &mut self,
+        name: O,
+    ) -> std::io::Result<(T, ReadInfo)> {
+        return self.read_verbose_with(name, &ReadOptions::default());
+    }
+
+    /**
+    Like [`DatabaseManager::read_verbose`], but the reading behaviour can be
+    customized via the given [`ReadOptions`].
+     */
+    pub fn read_verbose_with<T: DatabaseEntry, O: AsRef<OsStr>>(
+        &mut self,
+        name: O,
+        read_options: &ReadOptions,
+    ) -> std::io::Result<(T, ReadInfo)> {
+        return self.read_verbose_log(name, read_options, true);
+    }
+
+    fn read_verbose_log<T: DatabaseEntry, O: AsRef<OsStr>>(
+        &mut self,
+        name: O,
+        read_options: &ReadOptions,
+        log: bool,
+    ) -> std::io::Result<(T, ReadInfo)> {
+        return self.with_read_context(log, read_options, |context| context.read(name.as_ref()));
+    }
+
+    /**
+    Sets up a [`ReadContext`] for the current thread, runs `f` with it and
+    collects the [`ReadInfo`] afterwards (including the infos logged by worker
+    threads which entered the context via a [`ReadContextHandle`]).
+     */
+    pub(crate) fn with_read_context<R, F: FnOnce(ReadContext) -> std::io::Result<R>>(
+        &mut self,
+        log: bool,
+        read_options: &ReadOptions,
+        f: F,
+    ) -> std::io::Result<(R, ReadInfo)> {
+        self.check_database_lock()?;
+        #[cfg(feature = "watch")]
+        self.evict_watched_cache_entries();
+        let shared = Arc::new(SharedReadState {
+            id: READ_ID_COUNTER.fetch_add(1, Ordering::Relaxed),
+            cache: Mutex::new(ReadCache {
+                cache: mem::take(&mut self.cache),
+                usage: mem::take(&mut self.cache_usage),
+            }),
+            ..Default::default()
+        });
+
+        /*
+        Removes the thread context and waits until all worker threads have left
+        the read context, even if `f` panics. From then on, handles held by
+        worker threads don't access the database manager anymore.
+         */
+        struct Guard<'a>(&'a SharedReadState);
+
+        impl Drop for Guard<'_> {
+            fn drop(&mut self) {
+                READ_CONTEXT.with(|thread_context| thread_context.set(None));
+                self.0.finish();
+            }
+        }
+
+        let result = {
+            // Context only exist for the duration of this block.
+            let context = ReadContext::new(self, read_options, Arc::as_ptr(&shared), log);
+            let _guard = Guard(&shared);
+            READ_CONTEXT.with(|thread_context| thread_context.set(Some(context)));
+            f(context)
+        };
+
+        // Give the cache back to the database manager
+        let read_cache =
+            mem::take(&mut *shared.cache.lock().unwrap_or_else(PoisonError::into_inner));
+        self.cache = read_cache.cache;
+        self.cache_usage = read_cache.usage;
+
+        // Release the Rc<T> instances of this read call. Worker threads clear
+        // their instances when they resolve the links of the next read call.
+        let rc_cache = RC_CACHE.with(|rc_cache| mem::take(&mut rc_cache.borrow_mut().1));
+        drop(rc_cache);
+
+        // Get reading metadata
+        let mut read_info = RwInfo::take_read_info();
+        read_info.checksum_mismatch.extend(mem::take(
+            &mut *shared
+                .worker_mismatches
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        ));
+        read_info.ignored_link_fields.extend(mem::take(
+            &mut *shared
+                .worker_ignored_link_fields
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        ));
+        read_info.substituted_links.extend(mem::take(
+            &mut *shared
+                .worker_substituted_links
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        ));
+        read_info.signature_problems = mem::take(
+            &mut *shared
+                .signature_problems
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        read_info.revision = mem::take(
+            &mut *shared
+                .revision
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+
+        let instance = result?;
+        if read_options.checksum_mismatch == ChecksumMismatchPolicy::Heal {
+            let resolved_links = mem::take(
+                &mut *shared
+                    .resolved_links
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+            );
+            read_info.healed_files = self.heal_links(resolved_links).map_err(|err| {
+                Error::new(
+                    err.kind(),
+                    format!("Reading succeeded, but healing the links failed: {}", err),
+                )
+            })?;
+            for healed_file in read_info.healed_files.iter() {
+                read_info.revision.record(
+                    healed_file.clone(),
+                    self.checksum_algorithm.checksum_file(healed_file),
+                );
+            }
+        }
+        return Ok((instance, read_info));
+    }
+
+    /**
+    Substitutes the parameter placeholders within the serialized entry `bytes`
+    of the type `type_name` (see [`ReadOptions::parameters`]). The bytes are
+    only reserialized if a placeholder was found.
+     */
+    fn substitute_parameters(
+        &self,
+        type_name: &OsStr,
+        bytes: Vec<u8>,
+        parameters: &HashMap<String, Value>,
+    ) -> std::io::Result<Vec<u8>> {
+        let format = self.format_for_type(type_name);
+        let mut value = format
+            .deserialize_value(&bytes)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        if !value
+            .substitute_parameters(parameters)
+            .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?
+        {
+            return Ok(bytes);
+        }
+        return format
+            .serialize_value(&value)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err));
+    }
+
+    /**
+    Updates the checksums of all links in `resolved_links` which don't match
+    the checksum of the linked file anymore and returns the paths of all
+    rewritten files. Since rewriting a file changes its checksum, links to the
+    rewritten file are updated as well, up to the root of the read call.
+     */
+    fn heal_links(&self, mut resolved_links: Vec<ResolvedLink>) -> std::io::Result<Vec<PathBuf>> {
+        let mut healed_files: Vec<PathBuf> = Vec::new();
+
+        // The links form a directed acyclic graph, hence all links are up to
+        // date after at most resolved_links.len() passes.
+        for _ in 0..=resolved_links.len() {
+            let mut outdated: HashMap<PathBuf, Vec<(String, u32, u32)>> = HashMap::new();
+            for resolved_link in resolved_links.iter_mut() {
+                let Some(checksum_in_link) = resolved_link.link.checksum else {
+                    continue;
+                };
+                let algorithm = resolved_link.link.algorithm;
+                let Some(checksum_of_file) = algorithm.checksum_file(&resolved_link.child) else {
+                    continue;
+                };
+                if checksum_in_link != checksum_of_file {
+                    // The healed link uses the algorithm of self
+                    let Some(new_checksum) =
+                        self.checksum_algorithm.checksum_file(&resolved_link.child)
+                    else {
+                        continue;
+                    };
+                    outdated
+                        .entry(resolved_link.parent.clone())
+                        .or_default()
+                        .push((
+                            resolved_link.link.name.clone(),
+                            checksum_in_link,
+                            new_checksum,
+                        ));
+                    resolved_link.link.checksum = Some(new_checksum);
+                    resolved_link.link.algorithm = self.checksum_algorithm;
+                }
+            }
+
+            if outdated.is_empty() {
+                break;
+            }
+
+            for (parent, updates) in outdated.into_iter() {
+                self.rewrite_links(&parent, |link| {
+                    for (name, old_checksum, new_checksum) in updates.iter() {
+                        if &link.name == name && link.checksum == Some(*old_checksum) {
+                            link.checksum = Some(*new_checksum);
+                            link.algorithm = self.checksum_algorithm;
+                            return true;
+                        }
+                    }
+                    return false;
+                })?;
+                if !healed_files.contains(&parent) {
+                    healed_files.push(parent);
+                }
+            }
+        }
+        return Ok(healed_files);
+    }
+
+    /**
+    Deserializes the file at `path` into an untyped [`Value`](crate::Value), calls `f` for
+    each link within it (see [`Value::for_each_link_mut`](crate::Value::for_each_link_mut)) and writes the file
+    again if any link was modified. Returns whether the file was rewritten.
+     */
+    pub(crate) fn rewrite_links<F: FnMut(&mut DatabaseLink) -> bool>(
+        &self,
+        path: &Path,
+        mut f: F,
+    ) -> std::io::Result<bool> {
+        let bytes = fs::read(path).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Could not read file {}: {}", path.display(), err),
+            )
+        })?;
+        let (bytes, compressed) = self.decode_file_compressed(path, bytes)?;
+        let mut value = self
+            .format_of_file(path)
+            .deserialize_value(&bytes)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        if !value.for_each_link_mut(&mut f) {
+            return Ok(false);
+        }
+        let bytes = self.serialize_value(path, &value)?;
+
+        // Keep compressed files compressed
+        #[cfg(feature = "compression")]
+        let bytes = if compressed {
+            crate::Compression::new(0).compress(&bytes)?
+        } else {
+            bytes
+        };
+        #[cfg(not(feature = "compression"))]
+        let _ = compressed;
+        let bytes = self.encode_file(path, bytes)?;
+        replace_file(path, &bytes).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Could not write file {}: {}", path.display(), err),
+            )
+        })?;
+        self.sign_file(path)?;
+        self.notify(ChangeKind::Overwritten, path);
+        return Ok(true);
+    }
+
+    /**
+    Deserializes the given string using [`Format::deserialize`] from
+    `self.data_format()` and resolves any encountered links using the underlying
+    database.
+
+    This function behaves similarily to [`DatabaseManager::read`], except that
+    the starting point is not a file from the database, but `str` instead.
+    Because the [`Format`] is stored as a trait object inside `self`, it needs
+    to be downcasted into its concrete type `F` inside this function. Specifying
+    the wrong type will result in an error.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use std::sync::Arc;
+
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize, Clone)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Shirt {
+        owner: String,
+        #[serde(deserialize_with = "deserialize_arc_link")]
+        #[serde(serialize_with = "serialize_arc_link")]
+        material: Arc<Material>,
+        size: usize
+    }
+
+    let mut dbm = DatabaseManager::new("/path/to/db", SerdeYaml).expect("directory exists");
+
+    let shirt_str = indoc::indoc! {"
+    ---
+    owner: Sven
+    material:
+      name: pure_cotton
+    size: 46
+    "};
+
+    let shirt = dbm.from_str::<Shirt, SerdeYaml>(&shirt_str).unwrap();
+    assert_eq!(shirt.material.name, "pure_cotton");
+    ```
+     */
+    pub fn from_str<T: DeserializeOwned + 'static, F: Format>(
+        &mut self,
+        str: impl AsRef<str>,
+    ) -> std::io::Result<T> {
+        return self
+            .with_read_context(false, &ReadOptions::default(), |context| {
+                let dbm = unsafe { &*context.database_manager };
+
+                // Try to downcast the format into F
+                let format: &F =
+                    (dbm.format.as_ref() as &dyn Any)
+                        .downcast_ref()
+                        .ok_or(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "given type F does not match the format of self",
+                        ))?;
+
+                format
+                    .deserialize::<T>(str.as_ref().as_bytes())
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            })
+            .map(|arg| arg.0);
+    }
+}
+
+impl From<DatabaseManager> for Box<dyn Format> {
+    fn from(value: DatabaseManager) -> Self {
+        return value.format;
+    }
+}
+
+impl From<DatabaseManager> for Cache {
+    fn from(value: DatabaseManager) -> Self {
+        return value.cache;
+    }
+}
+
+// ========================================================================================================
+
+#[derive(Clone, Copy)]
+pub(crate) struct WriteContext {
+    log: bool,
+    pub(crate) database_manager: *mut DatabaseManager,
+    pub(crate) write_options: *const WriteOptions,
+    written_names: *const RefCell<HashMap<PathBuf, OsString>>,
+    written_through: *const RefCell<Vec<(TypeId, OsString, CacheEntry, u64)>>,
+    content_index: *const RefCell<ContentIndex>,
+    dry_run: bool,
+    skip_unchanged: bool,
+}
+
+/**
+The entry files of the type folders which have been searched for duplicates
+during a write call (see [`WriteOptions::deduplicate`]), grouped by type folder
+and by the checksum of their serialized contents.
+ */
+type ContentIndex = HashMap<OsString, HashMap<u32, Vec<PathBuf>>>;
+
+/**
+Magic bytes at the start of every gzip-compressed file, see
+[`Compression`](crate::Compression).
+ */
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+thread_local!(pub(crate) static WRITE_CONTEXT: Cell<Option<WriteContext>> = Cell::new(None));
+
+impl WriteContext {
+    pub(crate) fn new(
+        database_manager: &mut DatabaseManager,
+        write_options: &WriteOptions,
+        written_names: &RefCell<HashMap<PathBuf, OsString>>,
+        written_through: &RefCell<Vec<(TypeId, OsString, CacheEntry, u64)>>,
+        content_index: &RefCell<ContentIndex>,
+        log: bool,
+    ) -> Self {
+        return Self {
+            database_manager: std::ptr::from_mut(database_manager),
+            write_options: std::ptr::from_ref(write_options),
+            written_names: std::ptr::from_ref(written_names),
+            written_through: std::ptr::from_ref(written_through),
+            content_index: std::ptr::from_ref(content_index),
+            log,
+            dry_run: false,
+            skip_unchanged: false,
+        };
+    }
+
+    /**
+    Creates a context which only serializes entries without writing any files,
+    see [`DatabaseManager::checksum_of`]. Since no files are written, a shared
+    reference to the database manager suffices.
+     */
+    fn new_dry_run(
+        database_manager: &DatabaseManager,
+        write_options: &WriteOptions,
+        written_names: &RefCell<HashMap<PathBuf, OsString>>,
+        written_through: &RefCell<Vec<(TypeId, OsString, CacheEntry, u64)>>,
+    ) -> Self {
+        return Self {
+            // SAFETY: The database manager is never modified during a dry run.
+            database_manager: std::ptr::from_ref(database_manager).cast_mut(),
+            write_options: std::ptr::from_ref(write_options),
+            written_names: std::ptr::from_ref(written_names),
+            written_through: std::ptr::from_ref(written_through),
+            // Dry runs never write files, so the index is never accessed
+            content_index: std::ptr::null(),
+            log: false,
+            dry_run: true,
+            skip_unchanged: false,
+        };
+    }
+
+    /**
+    Writes `instance` (unless this is a dry run) and returns the link to it.
+     */
+    pub(crate) fn link<T: DatabaseEntry>(&self, instance: &T) -> std::io::Result<DatabaseLink> {
+        if self.dry_run {
+            // SAFETY: See WriteContext::write.
+            let write_options = unsafe { &*self.write_options };
+            let dbm = unsafe { &*self.database_manager };
+            let data = dbm.serialize_entry(instance)?;
+            let data = dbm.tag_version(OsStr::new(T::folder_name()), data)?;
+            let data = dbm.apply_format_options(OsStr::new(T::folder_name()), data)?;
+            return Ok(DatabaseLink {
+                name: write_options.name(instance).to_string_lossy().to_string(),
+                checksum: Some(dbm.checksum_algorithm.checksum_bytes(&data)),
+                algorithm: dbm.checksum_algorithm,
+            });
+        }
+        let file_path = self.write(instance, true)?;
+        // SAFETY: See WriteContext::write.
+        let dbm = unsafe { &*self.database_manager };
+        let mut link = DatabaseLink::new(&file_path, dbm.file_ext_for_type(T::folder_name()));
+        link.checksum = dbm.file_checksum(&file_path);
+        link.algorithm = dbm.checksum_algorithm;
+        return Ok(link);
+    }
+
+    /**
+    Returns the name of the file `instance` would be written to (without
+    writing it), see [`WriteOptions::alias`] and [`WriteOptions::renamer`].
+     */
+    pub(crate) fn link_name<T: DatabaseEntry>(&self, instance: &T) -> String {
+        // SAFETY: See WriteContext::write.
+        let write_options = unsafe { &*self.write_options };
+        return write_options.name(instance).to_string_lossy().to_string();
+    }
+
+    /**
+    Returns `true` if linked fields are wrapped in a tagged envelope, see
+    [`Format::is_self_describing`].
+     */
+    pub(crate) fn uses_link_envelope(&self) -> bool {
+        // SAFETY: See WriteContext::write.
+        let dbm = unsafe { &*self.database_manager };
+        let format = match TYPE_STACK.with(|stack| stack.borrow().last().copied()) {
+            Some(type_name) => dbm.format_for_type(type_name),
+            None => dbm.data_format(),
+        };
+        return !format.is_self_describing();
+    }
+
+    /**
+    Remembers the written `instance` for the [`Cache`] if the
+    [`CachePolicy`] is [`CachePolicy::WriteThrough`]. `link` is the link to
+    the written file.
+     */
+    pub(crate) fn cache_written<T: DatabaseEntry + Send + Sync>(
+        &self,
+        instance: &Arc<T>,
+        link: &DatabaseLink,
+    ) {
+        // SAFETY: See WriteContext::write.
+        let dbm = unsafe { &*self.database_manager };
+        if self.dry_run || dbm.cache_policy != CachePolicy::WriteThrough {
+            return;
+        }
+        let bytes = dbm.cached_file_size(T::folder_name(), OsStr::new(&link.name));
+        let written_through = unsafe { &*self.written_through };
+        written_through.borrow_mut().push((
+            TypeId::of::<T>(),
+            link.name.clone().into(),
+            CacheEntry {
+                arc: instance.clone(),
+                checksum: link.checksum,
+                algorithm: link.algorithm,
+            },
+            bytes,
+        ));
+    }
+
+    /**
+    Returns the path of an existing entry file within the type folder
+    `type_name` whose serialized contents equal `data` (with the given
+    `checksum`), see [`WriteOptions::deduplicate`]. The type folder is indexed
+    on its first search within a write call.
+     */
+    fn find_duplicate(
+        &self,
+        type_name: &OsStr,
+        checksum: u32,
+        data: &[u8],
+    ) -> std::io::Result<Option<PathBuf>> {
+        // SAFETY: See WriteContext::write. The index lives as long as the
+        // WriteContext (see DatabaseManager::write_verbose_log).
+        let dbm = unsafe { &*self.database_manager };
+        let content_index = unsafe { &*self.content_index };
+        let contents = |path: &Path| {
+            return dbm
+                .read_file(path)
+                .and_then(|bytes| dbm.decode_file(path, bytes))
+                .ok();
+        };
+
+        let mut content_index = content_index.borrow_mut();
+        if !content_index.contains_key(type_name) {
+            let mut checksums: HashMap<u32, Vec<PathBuf>> = HashMap::new();
+            for name in dbm.entry_names(type_name)? {
+                let path = dbm.full_path_unchecked((type_name, name.as_os_str()));
+                if let Some(bytes) = contents(&path) {
+                    checksums
+                        .entry(dbm.checksum_algorithm.checksum_bytes(&bytes))
+                        .or_default()
+                        .push(path);
+                }
+            }
+            content_index.insert(type_name.to_os_string(), checksums);
+        }
+
+        // Checksums may collide, hence the contents are compared as well
+        let candidates = content_index
+            .get(type_name)
+            .and_then(|checksums| checksums.get(&checksum));
+        for path in candidates.into_iter().flatten() {
+            if contents(path).is_some_and(|bytes| bytes == data) {
+                return Ok(Some(path.clone()));
+            }
+        }
+        return Ok(None);
+    }
+
+    /**
+    Adds the entry file `path` of the type folder `type_name` with the given
+    `checksum` to the index of [`WriteContext::find_duplicate`], if the type
+    folder has already been indexed.
+     */
+    fn index_content(&self, type_name: &OsStr, checksum: u32, path: &Path) {
+        // SAFETY: See WriteContext::find_duplicate.
+        let content_index = unsafe { &*self.content_index };
+        if let Some(checksums) = content_index.borrow_mut().get_mut(type_name) {
+            let paths = checksums.entry(checksum).or_default();
+            if !paths.iter().any(|indexed| indexed == path) {
+                paths.push(path.to_path_buf());
+            }
+        }
+    }
+
+    /**
+    Serializes `instance` into the contents of its file (before they are
+    compressed or encrypted). Linked entries are written or inlined according
+    to [`WriteOptions::write_mode`].
+     */
+    pub(crate) fn serialize<T: DatabaseEntry>(&self, instance: &T) -> std::io::Result<Vec<u8>> {
+        // Serialize self into a string. During the call of this function, no &mut
+        // DatabaseManager must exist, since to_string could end up calling
+        // Self::write, which would lead to aliasing mutable pointers.
+        // SAFETY: See WriteContext::write.
+        let dbm = unsafe { &*self.database_manager };
+        let data = dbm.serialize_entry(instance)?;
+        let data = dbm
+            .append_unknown_fields(DatabaseKey::from((T::folder_name(), instance.name())), data)?;
+        let data = dbm.tag_version(OsStr::new(T::folder_name()), data)?;
+        return dbm.apply_format_options(OsStr::new(T::folder_name()), data);
+    }
+
+    /**
+    Writes `instance` into its file and returns the path of the file. `linked`
+    is `true` if `instance` is the linked entry of another written entry.
+     */
+    pub(crate) fn write<T: DatabaseEntry>(
+        &self,
+        instance: &T,
+        linked: bool,
+    ) -> std::io::Result<PathBuf> {
+        // Enable / disable logging
+        RwInfo::set_log(self.log);
+
+        /*
+        SAFETY: A WriteContext object is both created and destroyed within the function DatabaseManager::write_verbose.
+        This function takes a mutable reference to a DatabaseManager. Therefore, the pointer is not dangling
+        during the lifetime of the WriteContext. To avoid aliasing, we need to make sure that the mutable
+        reference only exists AFTER serializing instance with self.data_format.to_string(instance), since this function
+        could end up calling WriteContext::write again.
+
+        The same is true for WriteOptions, but here we don't need to worry about aliasing.
+         */
+        let dbm = unsafe { &mut *self.database_manager }; // Casting from a *mut
+        let write_options = unsafe { &*self.write_options }; // Casting from a *
+        dbm.claim_folder::<T>()?;
+        let data = self.serialize(instance)?;
+
+        // Adjust the file name, if necessary
+        let name = write_options.name(instance);
+        let full_file_path = dbm.full_path_unchecked((T::folder_name(), name.as_os_str()));
+        let file_exists = dbm.file_exists(&full_file_path) && !dbm.is_expired_file(&full_file_path);
+
+        // Two different entries must not end up in the same file because of
+        // an alias or the renamer. SAFETY: The map lives as long as the
+        // WriteContext (see DatabaseManager::write_verbose_log).
+        let written_names = unsafe { &*self.written_names };
+        match written_names.borrow_mut().entry(full_file_path.clone()) {
+            Entry::Occupied(entry) if entry.get() != instance.name() => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Alias collision: the entries {} and {} of type {} would both be written to {}",
+                        entry.get().to_string_lossy(),
+                        instance.name().to_string_lossy(),
+                        type_name::<T>(),
+                        full_file_path.display()
+                    ),
+                ));
+            }
+            Entry::Occupied(_) => (),
+            Entry::Vacant(entry) => {
+                entry.insert(instance.name().to_os_string());
+            }
+        }
+
+        // Skip files whose contents would not change
+        if self.skip_unchanged
+            && file_exists
+            && dbm
+                .read_file(&full_file_path)
+                .and_then(|bytes| dbm.decode_file(&full_file_path, bytes))
+                .is_ok_and(|bytes| bytes == data)
+        {
+            RwInfo::log_unchanged_file_path(full_file_path.clone());
+            return Ok(full_file_path);
+        }
+
+        // Link to an existing entry with the same contents instead of writing
+        // a duplicate
+        let checksum = (linked && write_options.deduplicate)
+            .then(|| dbm.checksum_algorithm.checksum_bytes(&data));
+        if let Some(checksum) = checksum
+            && let Some(duplicate) =
+                self.find_duplicate(OsStr::new(T::folder_name()), checksum, &data)?
+            && duplicate != full_file_path
+        {
+            RwInfo::log_deduplicated_file_path(duplicate.clone());
+            return Ok(duplicate);
+        }
+
+        let file_path = match write_options.name_collisions {
+            NameCollisions::Overwrite => {
+                if file_exists {
+                    RwInfo::log_overwritten_file_path(full_file_path.clone());
+                } else {
+                    RwInfo::log_created_file_path(full_file_path.clone());
+                }
+                full_file_path
+            }
+            NameCollisions::KeepExisting => {
+                // If the file already exists, do nothing
+                if file_exists {
+                    RwInfo::log_kept_file_path(full_file_path.clone());
+                    return Ok(full_file_path);
+                } else {
+                    RwInfo::log_created_file_path(full_file_path.clone());
+                    full_file_path
+                }
+            }
+            NameCollisions::AdjustName => {
+                // Check if a file `name` already exists within the type
+                // folder. If that is the case, find a new file name which
+                // isn't used yet.
+                if file_exists {
+                    let trial_file_path =
+                        dbm.adjusted_file_path(OsStr::new(T::folder_name()), &name)?;
+                    RwInfo::log_created_file_path(trial_file_path.clone());
+                    trial_file_path
+                } else {
+                    RwInfo::log_created_file_path(full_file_path.clone());
+                    full_file_path
+                }
+            }
+        };
+
+        // Let the format keep parts of the overwritten file (e.g. comments)
+        let data =
+            if file_exists && matches!(write_options.name_collisions, NameCollisions::Overwrite) {
+                dbm.read_file(&file_path)
+                    .and_then(|bytes| dbm.decode_file(&file_path, bytes))
+                    .ok()
+                    .and_then(|existing| {
+                        dbm.format_for_type(T::folder_name())
+                            .merge_existing(&existing, &data)
+                            .ok()
+                    })
+                    .flatten()
+                    .unwrap_or(data)
+            } else {
+                data
+            };
+
+        // Check the size limits before creating the file
+        let size = data.len() as u64;
+        if let Some(limit) = write_options.size_limits.limit_for(T::folder_name())
+            && size > limit
+        {
+            match write_options.size_limits.policy {
+                SizeLimitPolicy::Reject => {
+                    return Err(Error::new(
+                        ErrorKind::FileTooLarge,
+                        format!(
+                            "Serialized size of {} ({} bytes) exceeds the limit of {} bytes",
+                            file_path.display(),
+                            size,
+                            limit
+                        ),
+                    ));
+                }
+                SizeLimitPolicy::Warn => {
+                    RwInfo::log_size_limit_violation(SizeLimitViolation {
+                        file_path: file_path.clone(),
+                        size,
+                        limit,
+                    });
+                }
+            }
+        }
+
+        // Check the entry quota before creating a new file
+        let creates_file =
+            !file_exists || !matches!(write_options.name_collisions, NameCollisions::Overwrite);
+        if creates_file
+            && write_options.entry_quotas.policy == QuotaPolicy::Reject
+            && let Some(quota) = write_options.entry_quotas.quota_for(T::folder_name())
+            && dbm.entry_count(OsStr::new(T::folder_name()))? >= quota
+        {
+            return Err(Error::new(
+                ErrorKind::QuotaExceeded,
+                format!(
+                    "Creating {} exceeds the quota of {} entries of type {}",
+                    file_path.display(),
+                    quota,
+                    T::folder_name()
+                ),
+            ));
+        }
+
+        #[cfg(feature = "compression")]
+        let data = match &write_options.compression {
+            Some(compression) if compression.applies_to(size) => compression.compress(&data)?,
+            _ => data,
+        };
+        let data = dbm.encode_file(&file_path, data)?;
+
+        // Entries locked by other managers must not be modified
+        dbm.check_lock(&file_path)?;
+        dbm.inject_write_fault(&file_path)?;
+
+        // Staged files are written by DatabaseManager::flush
+        if dbm.is_staging() {
+            dbm.stage(&file_path, data);
+            if let Some(checksum) = checksum {
+                self.index_content(OsStr::new(T::folder_name()), checksum, &file_path);
+            }
+            return Ok(file_path);
+        }
+
+        // If the folder (or shard) for the file is missing, create it
+        if let Some(folder) = file_path.parent()
+            && !folder.exists()
+        {
+            std::fs::create_dir_all(folder)?;
+        }
+
+        // Store the serialized data in a temporary file first and then move it
+        // to its final location, so an existing file is either replaced
+        // completely or not at all.
+        let mut temp_file_name = file_path.file_name().unwrap_or_default().to_os_string();
+        temp_file_name.push(".tmp");
+        let temp_file_path = file_path.with_file_name(temp_file_name);
+        let mut file = File::create(&temp_file_path).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Could not create file {}", file_path.display()),
+            )
+        })?;
+        let result = file.write_all(&data);
+        drop(file);
+        match result.and_then(|_| fs::rename(&temp_file_path, &file_path)) {
+            Ok(_) => {
+                dbm.sign_file(&file_path)?;
+                dbm.clear_expired(&file_path)?;
+                if let Some(checksum) = checksum {
+                    self.index_content(OsStr::new(T::folder_name()), checksum, &file_path);
+                }
+                return Ok(file_path);
+            }
+            Err(err) => {
+                // Cleanup: Remove the temporary file
+                let _ = remove_file(&temp_file_path);
+                return Err(err);
+            }
+        };
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct ReadContext {
+    log: bool,
+    pub(crate) database_manager: *mut DatabaseManager,
+    pub(crate) read_options: *const ReadOptions,
+    shared: *const SharedReadState,
+}
+
+thread_local!(pub(crate) static READ_CONTEXT: Cell<Option<ReadContext>> = Cell::new(None));
+
+// The files which are currently being deserialized by this thread. The last
+// element is the parent of any link encountered during deserialization.
+thread_local!(static FILE_STACK: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) });
+
+// The number of files which are currently being deserialized by this thread,
+// including the files of the thread a ReadContextHandle was obtained on. Links
+// encountered during deserialization are one level deeper.
+thread_local!(static LINK_DEPTH: Cell<usize> = const { Cell::new(0) });
+
+// The last link which failed on this thread. Errors are converted into strings
+// when they are passed through the deserializer of the parent entry, so the
+// parent takes the structured error from here (see ReadContext::read).
+thread_local!(static LINK_ERROR: RefCell<Option<LinkError>> = const { RefCell::new(None) });
+
+// The types whose entries are currently being serialized by this thread. The
+// last element is the type of the file any link is written into.
+thread_local!(static TYPE_STACK: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) });
+
+/**
+The `Rc<T>` instances deserialized by
+[`deserialize_rc_link`](crate::attributes::deserialize_rc_link) on this thread,
+together with the id of the read call they belong to. Since `Rc<T>` can't be
+shared between threads, these instances are not stored in the [`Cache`] of the
+database manager and are only reused within a single read call.
+ */
+thread_local!(static RC_CACHE: RefCell<(u64, RcCache)> = RefCell::new((0, HashMap::new())));
+
+/**
+Like [`Cache`], but for `Rc<T>` instances, see [`ReadContext::with_rc_cache`].
+ */
+pub(crate) type RcCache = HashMap<TypeId, HashMap<OsString, RcCacheEntry>>;
+
+/**
+Like [`CacheEntry`], but for an `Rc<T>` instance.
+ */
+pub(crate) struct RcCacheEntry {
+    pub(crate) rc: Rc<dyn Any>,
+    pub(crate) checksum: Option<u32>,
+    pub(crate) algorithm: ChecksumAlgorithm,
+}
+
+// Source of the ids of the read calls (see SharedReadState::id). The id 0 is
+// never used, so an empty RC_CACHE doesn't belong to any read call.
+static READ_ID_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+/**
+A link which has been resolved during a read call. Only recorded if the
+[`ChecksumMismatchPolicy::Heal`] is used.
+ */
+pub(crate) struct ResolvedLink {
+    parent: PathBuf,
+    child: PathBuf,
+    link: DatabaseLink,
+}
+
+/**
+State of a single [`DatabaseManager::read`] call which is shared between the
+reading thread and any worker threads which entered the read context via a
+[`ReadContextHandle`].
+ */
+#[derive(Default)]
+pub(crate) struct SharedReadState {
+    id: u64,
+    active: AtomicBool,
+    entered: Mutex<usize>,
+    left: Condvar,
+    cache: Mutex<ReadCache>,
+    worker_mismatches: Mutex<Vec<ChecksumMismatch>>,
+    worker_ignored_link_fields: Mutex<Vec<IgnoredLinkFields>>,
+    worker_substituted_links: Mutex<Vec<SubstitutedLink>>,
+    resolved_links: Mutex<Vec<ResolvedLink>>,
+    signature_problems: Mutex<Vec<SignatureProblem>>,
+    revision: Mutex<Revision>,
+    pending: Mutex<PendingEntries>,
+}
+
+impl SharedReadState {
+    /**
+    Marks the read call as finished and blocks until all threads which entered
+    it via a [`ReadContextHandle`] have left it again. Afterwards, no thread
+    accesses the database manager of the read call anymore.
+     */
+    fn finish(&self) {
+        let mut entered = self.entered.lock().unwrap_or_else(PoisonError::into_inner);
+        self.active.store(false, Ordering::Release);
+        while *entered > 0 {
+            entered = self
+                .left
+                .wait(entered)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+/**
+The [`Cache`] of a database manager together with its bookkeeping. Both are
+moved into the [`SharedReadState`] for the duration of a read call (see
+[`ReadContext::with_cache`]), so threads which resolve links only ever need
+shared access to the database manager itself.
+ */
+#[derive(Default)]
+pub(crate) struct ReadCache {
+    cache: Cache,
+    usage: crate::CacheUsage,
+}
+
+/**
+The `Weak<T>` pointers to the entries which are currently being deserialized
+by [`ReadContext::read_link_cyclic`], keyed by type and name.
+ */
+type PendingEntries = HashMap<(TypeId, OsString), Box<dyn Any + Send + Sync>>;
+
+impl ReadContext {
+    pub(crate) fn new(
+        database_manager: &mut DatabaseManager,
+        read_options: &ReadOptions,
+        shared: *const SharedReadState,
+        log: bool,
+    ) -> Self {
+        // SAFETY: The shared state is created right before the context and
+        // outlives it (see DatabaseManager::with_read_context).
+        unsafe { &*shared }.active.store(true, Ordering::Release);
         return Self {
-            database_manager: std::ptr::from_mut(database_manager),
-            write_options: std::ptr::from_ref(write_options),
             log,
+            database_manager: std::ptr::from_mut(database_manager),
+            read_options: std::ptr::from_ref(read_options),
+            shared,
+        };
+    }
+
+    /**
+    Resolves `link` by reading the linked file. If the link contains a
+    checksum which doesn't match the file, the mismatch is logged (see
+    [`DatabaseLink::test_for_checksum_mismatch`]).
+     */
+    pub(crate) fn read_link<T: DatabaseEntry>(&self, link: &DatabaseLink) -> std::io::Result<T> {
+        /*
+        SAFETY: See ReadContext::read. Only shared references are created from
+        the pointers.
+         */
+        let dbm = unsafe { &*self.database_manager };
+
+        let file_path = dbm.full_path_unchecked((T::folder_name(), &link.name));
+        let failing_path = file_path.clone();
+        if let Some(mismatch) = link.test_for_checksum_mismatch(
+            file_path.clone(),
+            dbm.file_checksum_with(&file_path, link.algorithm),
+        ) {
+            RwInfo::log_checksum_mismatch(mismatch);
+        }
+
+        self.record_resolved_link(file_path, link);
+        return self
+            .read(OsStr::new(&link.name))
+            .map_err(|err| self.link_error(failing_path, err));
+    }
+
+    /**
+    Converts `err`, which occurred while reading the linked file at
+    `file_path`, into a [`LinkError`] containing the chain of files which are
+    currently being read. Errors which already are a [`LinkError`] (because a
+    link within the linked file failed) are kept as they are.
+     */
+    fn link_error(&self, file_path: PathBuf, err: Error) -> Error {
+        let link_error = match LinkError::from_io_error(&err) {
+            Some(link_error) => link_error.clone(),
+            None => {
+                // SAFETY: See ReadContext::read_link.
+                let dbm = unsafe { &*self.database_manager };
+                let mut chain = FILE_STACK.with(|stack| stack.borrow().clone());
+                chain.push(file_path);
+                LinkError::new(dbm.dir(), chain, &err)
+            }
+        };
+        LINK_ERROR.with(|last_error| last_error.replace(Some(link_error.clone())));
+        return link_error.into();
+    }
+
+    /**
+    Like [`ReadContext::read_link`], but wraps the read instance in an
+    `Arc<T>` which is constructed via [`Arc::new_cyclic`]. While the instance
+    is deserialized, [`ReadContext::pending_weak`] returns a `Weak<T>` pointing
+    to it, so entries linked by it can link back to it (see
+    [`deserialize_weak_link`](crate::attributes::deserialize_weak_link)).
+     */
+    pub(crate) fn read_link_cyclic<T: DatabaseEntry + Send + Sync + 'static>(
+        &self,
+        link: &DatabaseLink,
+    ) -> std::io::Result<Arc<T>> {
+        // SAFETY: See ReadContext::read_link.
+        let shared = unsafe { &*self.shared };
+        let key = (TypeId::of::<T>(), OsString::from(&link.name));
+
+        /*
+        The closure of Arc::new_cyclic can't fail, hence the allocation is
+        created for a MaybeUninit<T> which stays uninitialized if reading
+        fails. Weak pointers handed out during the read can't be upgraded
+        before Arc::new_cyclic returns and can never be upgraded if reading
+        failed, so the uninitialized value is never accessed.
+         */
+        let mut result = Ok(());
+        let uninit = Arc::<MaybeUninit<T>>::new_cyclic(|weak| {
+            // SAFETY: MaybeUninit<T> has the same size and alignment as T.
+            let weak = unsafe { Weak::from_raw(weak.clone().into_raw().cast::<T>()) };
+            let previous = shared
+                .pending
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(key.clone(), Box::new(weak));
+            let read = self.read_link::<T>(link);
+
+            // Restore the pointer of an enclosing read of the same entry, if any
+            let mut pending = shared
+                .pending
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            match previous {
+                Some(previous) => pending.insert(key, previous),
+                None => pending.remove(&key),
+            };
+            drop(pending);
+            match read {
+                Ok(instance) => return MaybeUninit::new(instance),
+                Err(err) => {
+                    result = Err(err);
+                    return MaybeUninit::uninit();
+                }
+            }
+        });
+        result?;
+        // SAFETY: Reading succeeded, hence the value has been initialized.
+        return Ok(unsafe { uninit.assume_init() });
+    }
+
+    /**
+    Returns a `Weak<T>` pointing to the entry `name` if it is currently being
+    deserialized by [`ReadContext::read_link_cyclic`].
+     */
+    pub(crate) fn pending_weak<T: DatabaseEntry + Send + Sync + 'static>(
+        &self,
+        name: &str,
+    ) -> Option<Weak<T>> {
+        // SAFETY: See ReadContext::read_link.
+        let shared = unsafe { &*self.shared };
+        return shared
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(TypeId::of::<T>(), OsString::from(name)))
+            .and_then(|weak| weak.downcast_ref::<Weak<T>>())
+            .cloned();
+    }
+
+    /**
+    Returns the [`LinkParsing`] of the read call.
+     */
+    pub(crate) fn link_parsing(&self) -> LinkParsing {
+        // SAFETY: See ReadContext::read_link.
+        let read_options = unsafe { &*self.read_options };
+        return read_options.link_parsing;
+    }
+
+    /**
+    Returns `true` if linked fields are wrapped in a tagged envelope, see
+    [`Format::is_self_describing`].
+     */
+    pub(crate) fn uses_link_envelope(&self) -> bool {
+        // SAFETY: See ReadContext::read_link.
+        let dbm = unsafe { &*self.database_manager };
+        let format = match FILE_STACK.with(|stack| stack.borrow().last().cloned()) {
+            Some(path) => dbm.format_of_file(&path),
+            None => dbm.data_format(),
+        };
+        return !format.is_self_describing();
+    }
+
+    /**
+    Returns `true` if the [`Cache`] is bypassed, see
+    [`ReadOptions::bypass_cache`]. This is also the case if parameters are
+    given or environment variables are interpolated, since the instances read
+    from such files depend on them (see [`ReadOptions::parameters`] and
+    [`ReadOptions::env_interpolation`]).
+     */
+    pub(crate) fn bypasses_cache(&self) -> bool {
+        // SAFETY: See ReadContext::read_link.
+        let read_options = unsafe { &*self.read_options };
+        return read_options.bypass_cache
+            || !read_options.parameters.is_empty()
+            || read_options.env_interpolation != EnvInterpolation::Disabled;
+    }
+
+    /**
+    Returns `true` if links encountered at the current depth of the read
+    exceed [`ReadOptions::max_link_depth`] and should therefore not be
+    resolved.
+     */
+    pub(crate) fn exceeds_link_depth(&self) -> bool {
+        // SAFETY: See ReadContext::read_link.
+        let read_options = unsafe { &*self.read_options };
+        return read_options
+            .max_link_depth
+            .is_some_and(|max_link_depth| LINK_DEPTH.with(Cell::get) > max_link_depth);
+    }
+
+    /**
+    Logs the entries of `link` which have been ignored (see
+    [`LinkParsing::Lenient`]).
+     */
+    pub(crate) fn log_ignored_link_fields(&self, link: &DatabaseLink, fields: Vec<String>) {
+        let file_path = FILE_STACK.with(|stack| stack.borrow().last().cloned());
+        RwInfo::log_ignored_link_fields(IgnoredLinkFields {
+            file_path,
+            name: link.name.clone(),
+            fields,
+        });
+    }
+
+    /**
+    Returns `true` if the file linked by `link` doesn't exist (or has expired),
+    so the link is substituted by a fallback instance (see
+    [`deserialize_link_or_default`](crate::deserialize_link_or_default)). The
+    substitution is logged as a [`SubstitutedLink`].
+     */
+    pub(crate) fn substitute_missing_link<T: DatabaseEntry>(&self, link: &DatabaseLink) -> bool {
+        // SAFETY: See ReadContext::read_link.
+        let dbm = unsafe { &*self.database_manager };
+        let linked_file = dbm.full_path_unchecked((T::folder_name(), &link.name));
+        if dbm.file_exists(&linked_file) && !dbm.is_expired_file(&linked_file) {
+            return false;
+        }
+        let file_path = FILE_STACK.with(|stack| stack.borrow().last().cloned());
+        RwInfo::log_substituted_link(SubstitutedLink {
+            file_path,
+            name: link.name.clone(),
+            linked_file,
+        });
+        return true;
+    }
+
+    /**
+    Records the checksum of a file involved in the read, see [`Revision`].
+     */
+    fn record_revision(&self, file_path: PathBuf, checksum: Option<u32>) {
+        // SAFETY: See ReadContext::read_link.
+        let shared = unsafe { &*self.shared };
+        let dbm = unsafe { &*self.database_manager };
+        let mut revision = shared
+            .revision
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        revision.algorithm = dbm.checksum_algorithm;
+        revision.record(file_path, checksum);
+    }
+
+    /**
+    Records the file `link` points to for an entry which was taken from the
+    [`Cache`] instead of being read. `checksum` is the checksum and the
+    algorithm stored in the cache entry, i.e. the checksum of the file the
+    cached instance has been read from. Without it, the state of the file is
+    recorded as unknown (see [`Revision`]). The link is remembered for healing
+    as well (see [`ReadContext::record_resolved_link`]), since the linked file
+    might be rewritten while healing other links of the same read call.
+     */
+    pub(crate) fn record_cached<T: DatabaseEntry>(
+        &self,
+        link: &DatabaseLink,
+        checksum: Option<(u32, ChecksumAlgorithm)>,
+    ) {
+        // SAFETY: See ReadContext::read_link.
+        let dbm = unsafe { &*self.database_manager };
+        let file_path = dbm.full_path_unchecked((T::folder_name(), &link.name));
+        match checksum {
+            Some((checksum, algorithm)) if algorithm == dbm.checksum_algorithm => {
+                self.record_revision(file_path.clone(), Some(checksum));
+            }
+            _ => {
+                let shared = unsafe { &*self.shared };
+                let mut revision = shared
+                    .revision
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                revision.algorithm = dbm.checksum_algorithm;
+                revision.record_unknown(file_path.clone());
+            }
+        }
+        self.record_resolved_link(file_path, link);
+    }
+
+    /**
+    Like [`ReadContext::record_cached`], but for entries taken from the
+    [`RcCache`]. These have been read during the current read call, so the
+    checksum of their file has already been recorded and only the link is
+    remembered for healing.
+     */
+    pub(crate) fn record_rc_cached<T: DatabaseEntry>(&self, link: &DatabaseLink) {
+        // SAFETY: See ReadContext::read_link.
+        let dbm = unsafe { &*self.database_manager };
+        let file_path = dbm.full_path_unchecked((T::folder_name(), &link.name));
+        self.record_resolved_link(file_path, link);
+    }
+
+    /**
+    Remembers `link`, which points to the file at `file_path`, for healing it
+    after the read call succeeded (see [`ChecksumMismatchPolicy::Heal`]). The
+    file containing the link is taken from the file stack of the current
+    thread. Links resolved without a file on the stack (e.g. inside a
+    [`ReadContextHandle`] obtained outside of any file) are therefore not
+    remembered.
+     */
+    fn record_resolved_link(&self, file_path: PathBuf, link: &DatabaseLink) {
+        // SAFETY: See ReadContext::read_link.
+        let read_options = unsafe { &*self.read_options };
+        if read_options.checksum_mismatch != ChecksumMismatchPolicy::Heal {
+            return;
+        }
+        let Some(parent) = FILE_STACK.with(|stack| stack.borrow().last().cloned()) else {
+            return;
+        };
+        let shared = unsafe { &*self.shared };
+        shared
+            .resolved_links
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(ResolvedLink {
+                parent,
+                child: file_path,
+                link: link.clone(),
+            });
+    }
+
+    /**
+    Returns the current checksum of the file of the linked entry `link` of the
+    type `T` (calculated with the algorithm of the link) if cached instances
+    need to be validated against their file before they are used (see
+    [`CacheInvalidation::OnRead`]). The inner option is [`None`] if the file
+    doesn't exist.
+     */
+    pub(crate) fn cache_validation_checksum<T: DatabaseEntry>(
+        &self,
+        link: &DatabaseLink,
+    ) -> Option<Option<u32>> {
+        // SAFETY: See ReadContext::read_link.
+        let dbm = unsafe { &*self.database_manager };
+        if dbm.cache_invalidation != CacheInvalidation::OnRead {
+            return None;
+        }
+        let file_path = dbm.full_path_unchecked((T::folder_name(), &link.name));
+        return Some(dbm.file_checksum_with(&file_path, link.algorithm));
+    }
+
+    /**
+    Gives exclusive access to the [`Cache`] of the database manager. Since
+    links may be resolved by multiple threads at once (see
+    [`ReadContextHandle`]), the cache is moved out of the database manager into
+    a mutex for the duration of the read call (see [`ReadCache`]) and must
+    only be accessed via this function during reading.
+     */
+    pub(crate) fn with_cache<R, F: FnOnce(&mut Cache) -> R>(&self, f: F) -> R {
+        /*
+        SAFETY: The shared state and the database manager outlive the context
+        (see DatabaseManager::with_read_context). Only shared references are
+        created from the pointers; the cache is protected by its mutex.
+         */
+        let shared = unsafe { &*self.shared };
+        let dbm = unsafe { &*self.database_manager };
+        match dbm.shared_cache.as_ref() {
+            Some(shared_cache) => return f(&mut *shared_cache.write()),
+            None => {
+                let mut read_cache = shared.cache.lock().unwrap_or_else(PoisonError::into_inner);
+                return f(&mut read_cache.cache);
+            }
+        }
+    }
+
+    /**
+    Like [`ReadContext::with_cache`], but gives access to the bookkeeping of
+    the cache as well (see [`SharedCache::with_usage`](crate::SharedCache::with_usage)).
+     */
+    fn with_cache_usage<R, F: FnOnce(&mut Cache, &mut crate::CacheUsage) -> R>(&self, f: F) -> R {
+        // SAFETY: See ReadContext::with_cache.
+        let shared = unsafe { &*self.shared };
+        let dbm = unsafe { &*self.database_manager };
+        match dbm.shared_cache.as_ref() {
+            Some(shared_cache) => return shared_cache.with_usage(f),
+            None => {
+                let mut read_cache = shared.cache.lock().unwrap_or_else(PoisonError::into_inner);
+                let read_cache = &mut *read_cache;
+                return f(&mut read_cache.cache, &mut read_cache.usage);
+            }
+        }
+    }
+
+    /**
+    Records that `link` to an entry of the type `T` has been resolved with a
+    cached instance (see [`DatabaseManager::cache_stats`]).
+     */
+    pub(crate) fn record_cache_hit<T: DatabaseEntry>(&self, link: &DatabaseLink) {
+        self.with_cache_usage(|_, usage| {
+            usage.record_hit(TypeId::of::<T>(), OsStr::new(&link.name));
+        });
+    }
+
+    /**
+    Records that the instance of the type `T` linked by `link` has been read
+    and put into the cache, since it was not cached before. If the
+    [`CacheCapacity`](crate::CacheCapacity) is exceeded afterwards, the least
+    recently used instances are evicted.
+     */
+    pub(crate) fn record_cache_miss<T: DatabaseEntry>(&self, link: &DatabaseLink) {
+        // SAFETY: See ReadContext::with_cache.
+        let dbm = unsafe { &*self.database_manager };
+        let bytes = dbm.cached_file_size(T::folder_name(), OsStr::new(&link.name));
+        self.with_cache_usage(|cache, usage| {
+            usage.record_insert(TypeId::of::<T>(), OsStr::new(&link.name), bytes, true);
+            usage.enforce_capacity(cache, dbm.cache_capacity);
+        });
+    }
+
+    /**
+    Gives access to the [`RcCache`] of the current thread. The cache only
+    contains instances which were deserialized during the read call of `self`;
+    instances of previous read calls are discarded.
+     */
+    pub(crate) fn with_rc_cache<R, F: FnOnce(&mut RcCache) -> R>(&self, f: F) -> R {
+        // SAFETY: See ReadContext::with_cache.
+        let id = unsafe { &*self.shared }.id;
+        return RC_CACHE.with(|rc_cache| {
+            let mut rc_cache = rc_cache.borrow_mut();
+            if rc_cache.0 != id {
+                *rc_cache = (id, HashMap::new());
+            }
+            f(&mut rc_cache.1)
+        });
+    }
+
+    /**
+    Like [`ReadContext::read`], but deserializes the entry `name` within the
+    type folder `type_name` into a trait object, so the concrete type doesn't
+    need to be known. Returns the entry together with the decoded contents of
+    its file.
+     */
+    pub(crate) fn read_dyn(
+        &self,
+        type_name: &str,
+        name: &OsStr,
+    ) -> std::io::Result<(Box<dyn DatabaseEntry>, Vec<u8>)> {
+        // Enable / disable logging
+        RwInfo::set_log(self.log);
+
+        /*
+        SAFETY: A ReadContext object is both created and destroyed within the function DatabaseManager::read_verbose.
+        This function takes a mutable reference to a DatabaseManager. Therefore, the pointer is not dangling
+        during the lifetime of the ReadContext. Only a shared reference is created here, since the deserialization
+        could end up calling ReadContext::read again (possibly from another thread, see ReadContextHandle).
+         */
+        let dbm = unsafe { &*self.database_manager };
+        let file_path = dbm.full_path_unchecked((type_name, name));
+
+        if !dbm.file_exists(&file_path) {
+            return Err(Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Could not find file {}", file_path.display()),
+            ));
+        }
+        if dbm.is_expired_file(&file_path) {
+            return Err(Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("File {} has expired", file_path.display()),
+            ));
+        }
+        // A file which links back to itself (transitively) would be read forever
+        if FILE_STACK.with(|stack| stack.borrow().contains(&file_path)) {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Cyclic link to file {}", file_path.display()),
+            ));
+        }
+
+        // Reading from the cache failed => read directly from the file
+        // SAFETY: The read options outlive the context, see above.
+        let read_options = unsafe { &*self.read_options };
+        dbm.inject_read_fault(&file_path)?;
+
+        // Staged files are preferred and signed only when they are written
+        let (data, staged) = match dbm.staged(&file_path) {
+            Some(data) => (data, true),
+            None => (fs::read(file_path.as_path())?, false),
+        };
+        self.record_revision(
+            file_path.clone(),
+            Some(dbm.checksum_algorithm.checksum_bytes(&data)),
+        );
+        if !staged
+            && let Some(status) = dbm.check_signature(&file_path, &data)
+            && status != SignatureStatus::Valid
+        {
+            if dbm.requires_signatures() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Signature of file {} is {}",
+                        file_path.display(),
+                        match status {
+                            SignatureStatus::Missing => "missing",
+                            _ => "invalid",
+                        }
+                    ),
+                ));
+            }
+            // SAFETY: See ReadContext::read_link.
+            let shared = unsafe { &*self.shared };
+            shared
+                .signature_problems
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(SignatureProblem {
+                    file_path: file_path.clone(),
+                    status,
+                });
+        }
+        let data = dbm
+            .decode_file(&file_path, data)
+            .and_then(|data| read_options.env_interpolation.interpolate(data))
+            .map_err(|err| {
+                Error::new(
+                    err.kind(),
+                    format!("Could not read file {}: {}", file_path.display(), err),
+                )
+            })?;
+        let data = if read_options.parameters.is_empty() {
+            data
+        } else {
+            dbm.substitute_parameters(OsStr::new(type_name), data, &read_options.parameters)
+                .map_err(|err| {
+                    Error::new(
+                        err.kind(),
+                        format!("Could not read file {}: {}", file_path.display(), err),
+                    )
+                })?
+        };
+        let data = dbm
+            .migrate_entry(OsStr::new(type_name), data)
+            .map_err(|err| {
+                Error::new(
+                    err.kind(),
+                    format!("Could not read file {}: {}", file_path.display(), err),
+                )
+            })?;
+        let data = match dbm.field_aliases.get(OsStr::new(type_name)) {
+            Some(aliases) => dbm
+                .rename_fields(OsStr::new(type_name), data, aliases)
+                .map_err(|err| {
+                    Error::new(
+                        err.kind(),
+                        format!("Could not read file {}: {}", file_path.display(), err),
+                    )
+                })?,
+            None => data,
+        };
+
+        FILE_STACK.with(|stack| stack.borrow_mut().push(file_path));
+        LINK_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        let result = dbm.format_for_type(type_name).deserialize_dyn(&data);
+        LINK_DEPTH.with(|depth| depth.set(depth.get() - 1));
+        FILE_STACK.with(|stack| stack.borrow_mut().pop());
+
+        match result {
+            Ok(val) => return Ok((val, data)),
+            Err(err) => {
+                // A failing link within the file is reported with the chain of files
+                let message = err.to_string();
+                if let Some(link_error) = LINK_ERROR.with(|last_error| last_error.take())
+                    && message.contains(&link_error.to_string())
+                {
+                    return Err(link_error.into());
+                }
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    message,
+                ));
+            }
+        }
+    }
+
+    pub(crate) fn read<T: DatabaseEntry>(&self, name: &OsStr) -> std::io::Result<T> {
+        let (val, data) = self.read_dyn(T::folder_name(), name)?;
+        let val = val as Box<dyn Any>;
+        match val.downcast::<T>() {
+            Ok(val) => {
+                // SAFETY: See ReadContext::read_dyn.
+                let dbm = unsafe { &*self.database_manager };
+                if dbm.preserve_unknown_fields {
+                    dbm.capture_unknown_fields(&*val, name, &data);
+                }
+                return Ok(*val);
+            }
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("type is not {}", type_name::<T>()),
+                ));
+            }
+        }
+    }
+}
+
+/**
+A handle to the read context of an ongoing [`DatabaseManager::read`] (or
+[`DatabaseManager::from_str`]) call which can be sent to other threads.
+
+The database manager injects itself into the deserialization process via a
+thread-local variable. Therefore, links can only be resolved on the thread
+which called [`DatabaseManager::read`] - deserializing a linked field on any
+other thread fails with a "No database manager has been set" error. If a
+[`Format`] or a custom deserialization function spawns worker threads (e.g. via
+`rayon`), the read context can be made available to these threads by obtaining
+a handle with [`ReadContextHandle::current`] on the reading thread and calling
+[`ReadContextHandle::enter`] within the worker thread. Access to the [`Cache`]
+is synchronized, so [`Arc`]-wrapped entries are still shared between all
+threads. [`ChecksumMismatch`]es encountered by worker threads are part of the
+[`ReadInfo`] returned by [`DatabaseManager::read_verbose`].
+
+The handle is only valid during the read call it was obtained from. The read
+call doesn't return while any thread is inside [`ReadContextHandle::enter`],
+so worker threads should be joined before the deserialization function which
+obtained the handle returns (e.g. by using [`std::thread::scope`]). Entering a
+handle whose read call already finished is a no-op, i.e. the given closure is
+executed without a read context.
+
+# Examples
+
+```no_run
+use std::ffi::OsStr;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+    cotton_content: f64,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Shirt {
+    owner: String,
+    #[serde(deserialize_with = "deserialize_link")]
+    #[serde(serialize_with = "serialize_link")]
+    material: Material,
+}
+
+// Deserializes the shirts stored as raw YAML strings in parallel
+fn deserialize_parallel<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Shirt>, D::Error> {
+    let raw: Vec<String> = Vec::deserialize(deserializer)?;
+    let handle = ReadContextHandle::current();
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = raw
+            .iter()
+            .map(|raw| {
+                let handle = handle.clone();
+                scope.spawn(move || {
+                    let parse = || serde_yaml::from_str::<Shirt>(raw);
+                    match handle {
+                        Some(handle) => handle.enter(parse),
+                        None => parse(),
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap().map_err(serde::de::Error::custom))
+            .collect()
+    })
+}
+```
+ */
+#[derive(Clone)]
+pub struct ReadContextHandle {
+    context: ReadContext,
+    shared: Arc<SharedReadState>,
+    parent_file: Option<PathBuf>,
+    link_depth: usize,
+}
+
+/*
+SAFETY: The raw pointers inside the context are only dereferenced while the
+handle is entered. Entering checks SharedReadState::active and registers the
+thread in SharedReadState::entered, which keeps the read call (and therefore
+the database manager) alive until the thread has left the handle. During a
+read call, only shared references to the database manager are created; the
+mutable cache lives in SharedReadState::cache instead. Sharing the manager
+between threads is fine since Format requires Send + Sync.
+ */
+unsafe impl Send for ReadContextHandle {}
+unsafe impl Sync for ReadContextHandle {}
+
+impl ReadContextHandle {
+    /**
+    Returns a handle to the read context of the current thread, if the current
+    thread is inside a [`DatabaseManager::read`] call (or has entered another
+    handle via [`ReadContextHandle::enter`]). Otherwise, [`None`] is returned.
+     */
+    pub fn current() -> Option<Self> {
+        let context = READ_CONTEXT.with(|thread_context| thread_context.get())?;
+
+        // SAFETY: The pointer was created from an Arc inside
+        // DatabaseManager::with_read_context, which is alive since the context
+        // is set for this thread.
+        let shared = unsafe {
+            Arc::increment_strong_count(context.shared);
+            Arc::from_raw(context.shared)
         };
+        let parent_file = FILE_STACK.with(|stack| stack.borrow().last().cloned());
+        return Some(Self {
+            context,
+            shared,
+            parent_file,
+            link_depth: LINK_DEPTH.with(Cell::get),
+        });
     }
 
-    pub(crate) fn write<T: DatabaseEntry>(&self, instance: &T) -> std::io::Result<PathBuf> {
-        // Enable / disable logging
-        RwInfo::set_log(self.log);
+    /**
+    Returns whether the read call this handle was obtained from is still in
+    progress.
+     */
+    pub fn is_active(&self) -> bool {
+        return self.shared.active.load(Ordering::Acquire);
+    }
 
-        /*
-        SAFETY: A WriteContext object is both created and destroyed within the function DatabaseManager::write_verbose.
-        This function takes a mutable reference to a DatabaseManager. Therefore, the pointer is not dangling
-        during the lifetime of the WriteContext. To avoid aliasing, we need to make sure that the mutable
-        reference only exists AFTER serializing instance with self.data_format.to_string(instance), since this function
-        could end up calling WriteContext::write again.
+    /**
+    Executes `f` with the read context of this handle set for the current
+    thread, so links encountered during deserialization inside `f` are
+    resolved using the database manager of the read call. The previous
+    context of the current thread (if any) is restored afterwards.
+     */
+    pub fn enter<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        struct Guard {
+            previous_context: Option<ReadContext>,
+            previous_info: ReadInfo,
+            previous_log: bool,
+            previous_depth: usize,
+            pushed_file: bool,
+            shared: *const SharedReadState,
+        }
 
-        The same is true for WriteOptions, but here we don't need to worry about aliasing.
-         */
-        let dbm = unsafe { &mut *self.database_manager }; // Casting from a *mut
-        let write_options = unsafe { &*self.write_options }; // Casting from a *
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                READ_CONTEXT.with(|thread_context| thread_context.set(self.previous_context));
+                LINK_DEPTH.with(|depth| depth.set(self.previous_depth));
+                if self.pushed_file {
+                    FILE_STACK.with(|stack| stack.borrow_mut().pop());
+                }
 
-        // Serialize self into a string. During the call of this function, no &mut
-        // DatabaseManager must exist, since to_string could end up calling
-        // Self::write, which would lead to aliasing mutable pointers.
-        let data = dbm
-            .format
-            .serialize_dyn(instance)
-            .map_err(|err| std::io::Error::new(ErrorKind::Other, err))?;
+                // Move everything logged inside the handle to the shared state
+                // and restore the logs of the previous context.
+                let info = RwInfo::take_read_info();
+                // SAFETY: The handle owning the Arc outlives the guard.
+                let shared = unsafe { &*self.shared };
+                shared
+                    .worker_mismatches
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .extend(info.checksum_mismatch);
+                shared
+                    .worker_ignored_link_fields
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .extend(info.ignored_link_fields);
+                shared
+                    .worker_substituted_links
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .extend(info.substituted_links);
+                RwInfo::restore_read_info(mem::take(&mut self.previous_info), self.previous_log);
+
+                // Allow the read call to finish
+                let mut entered = shared
+                    .entered
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                *entered -= 1;
+                if *entered == 0 {
+                    shared.left.notify_all();
+                }
+            }
+        }
+
+        // =====================================================================
 
-        let mut name = write_options.name(instance);
-        if !dbm.file_ext().is_empty() {
-            name.push(".");
-            name.push(dbm.file_ext());
+        /*
+        The read call can't finish while the handle is entered (see
+        SharedReadState::finish), so the database manager stays valid until
+        the guard is dropped.
+         */
+        {
+            let mut entered = self
+                .shared
+                .entered
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if !self.is_active() {
+                drop(entered);
+                return f();
+            }
+            *entered += 1;
         }
 
-        // If the folder for the file is missing, create it
-        let folder_dir = dbm.dir().join(type_name::<T>());
-        if !folder_dir.exists() {
-            std::fs::create_dir_all(&folder_dir)?;
+        let previous_log = RwInfo::log();
+        let previous_info = RwInfo::take_read_info();
+        let previous_context =
+            READ_CONTEXT.with(|thread_context| thread_context.replace(Some(self.context)));
+        RwInfo::set_log(self.context.log);
+        let previous_depth = LINK_DEPTH.with(|depth| depth.replace(self.link_depth));
+        if let Some(parent_file) = self.parent_file.as_ref() {
+            FILE_STACK.with(|stack| stack.borrow_mut().push(parent_file.clone()));
         }
 
-        // Adjust the file name, if necessary
-        let full_file_path = folder_dir.join(name);
-        let file_exists = full_file_path.exists();
+        let _guard = Guard {
+            previous_context,
+            previous_info,
+            previous_log,
+            previous_depth,
+            pushed_file: self.parent_file.is_some(),
+            shared: Arc::as_ptr(&self.shared),
+        };
+        return f();
+    }
+}
 
-        let file_path = match write_options.name_collisions {
-            NameCollisions::Overwrite => {
-                if file_exists {
-                    RwInfo::log_overwritten_file_path(full_file_path.clone());
-                } else {
-                    RwInfo::log_created_file_path(full_file_path.clone());
-                }
-                full_file_path
+thread_local!(static RW_INFO: RefCell<RwInfo> = RefCell::new(RwInfo::default()));
+
+#[derive(Default)]
+pub(crate) struct RwInfo {
+    log: bool,
+    overwritten_files: Vec<PathBuf>,
+    kept_files: Vec<PathBuf>,
+    unchanged_files: Vec<PathBuf>,
+    deduplicated_files: Vec<PathBuf>,
+    created_files: Vec<PathBuf>,
+    checksum_mismatch: Vec<ChecksumMismatch>,
+    ignored_link_fields: Vec<IgnoredLinkFields>,
+    substituted_links: Vec<SubstitutedLink>,
+    size_limit_violations: Vec<SizeLimitViolation>,
+}
+
+impl RwInfo {
+    fn set_log(log: bool) {
+        RW_INFO.with(|f| {
+            let rw_info = &mut *f.borrow_mut();
+            rw_info.log = log;
+        });
+    }
+
+    fn log() -> bool {
+        return RW_INFO.with(|f| f.borrow().log);
+    }
+
+    fn restore_read_info(read_info: ReadInfo, log: bool) {
+        RW_INFO.with(|f| {
+            let rw_info = &mut *f.borrow_mut();
+            rw_info.log = log;
+            rw_info.checksum_mismatch = read_info.checksum_mismatch;
+            rw_info.ignored_link_fields = read_info.ignored_link_fields;
+            rw_info.substituted_links = read_info.substituted_links;
+        });
+    }
+
+    fn take_write_info() -> WriteInfo {
+        return RW_INFO.with(|f| {
+            let rw_info = &mut *f.borrow_mut();
+            return WriteInfo {
+                overwritten_files: mem::replace(&mut rw_info.overwritten_files, Vec::new()),
+                created_files: mem::replace(&mut rw_info.created_files, Vec::new()),
+                kept_files: mem::replace(&mut rw_info.kept_files, Vec::new()),
+                unchanged_files: mem::replace(&mut rw_info.unchanged_files, Vec::new()),
+                deduplicated_files: mem::replace(&mut rw_info.deduplicated_files, Vec::new()),
+                size_limit_violations: mem::replace(&mut rw_info.size_limit_violations, Vec::new()),
+                evicted_files: Vec::new(),
+            };
+        });
+    }
+
+    fn take_read_info() -> ReadInfo {
+        return RW_INFO.with(|f| {
+            let rw_info = &mut *f.borrow_mut();
+            return ReadInfo {
+                checksum_mismatch: mem::replace(&mut rw_info.checksum_mismatch, Vec::new()),
+                healed_files: Vec::new(),
+                signature_problems: Vec::new(),
+                ignored_link_fields: mem::take(&mut rw_info.ignored_link_fields),
+                substituted_links: mem::take(&mut rw_info.substituted_links),
+                revision: Revision::default(),
+            };
+        });
+    }
+
+    fn log_overwritten_file_path(path: PathBuf) {
+        RW_INFO.with(|f| {
+            let mut borrowed = f.borrow_mut();
+            if borrowed.log {
+                borrowed.overwritten_files.push(path);
             }
-            NameCollisions::KeepExisting => {
-                // If the file already exists, do nothing
-                if file_exists {
-                    RwInfo::log_kept_file_path(full_file_path.clone());
-                    return Ok(full_file_path);
-                } else {
-                    RwInfo::log_created_file_path(full_file_path.clone());
-                    full_file_path
-                }
+        });
+    }
+
+    fn log_created_file_path(path: PathBuf) {
+        RW_INFO.with(|f| {
+            let mut borrowed = f.borrow_mut();
+            if borrowed.log {
+                borrowed.created_files.push(path);
             }
-            NameCollisions::AdjustName => {
-                // Check if a file `name` already exists within folder_dir. If
-                // that is the case, find a new file name which isn't used yet.
-                if file_exists {
-                    let mut counter = 0;
-                    let mut trial_file_path: PathBuf;
-                    loop {
-                        let mut name = write_options.name(instance);
-                        name.push(&format!("_{}", counter));
-                        if !dbm.file_ext().is_empty() {
-                            name.push(".");
-                            name.push(dbm.file_ext());
-                        }
-                        trial_file_path = folder_dir.join(name);
-                        if !trial_file_path.exists() {
-                            break;
-                        }
-                        counter += 1;
-                    }
-                    RwInfo::log_created_file_path(trial_file_path.clone());
-                    trial_file_path
-                } else {
-                    RwInfo::log_created_file_path(full_file_path.clone());
-                    full_file_path
-                }
+        });
+    }
+
+    fn log_kept_file_path(path: PathBuf) {
+        RW_INFO.with(|f| {
+            let mut borrowed = f.borrow_mut();
+            if borrowed.log {
+                borrowed.kept_files.push(path);
             }
-        };
+        });
+    }
 
-        // Create the corresponding file
-        let mut file = File::create(&file_path).map_err(|err| {
-            Error::new(
-                err.kind(),
-                format!("Could not create file {}", file_path.display()),
-            )
-        })?;
+    fn log_unchanged_file_path(path: PathBuf) {
+        RW_INFO.with(|f| {
+            let mut borrowed = f.borrow_mut();
+            if borrowed.log {
+                borrowed.unchanged_files.push(path);
+            }
+        });
+    }
+
+    fn log_deduplicated_file_path(path: PathBuf) {
+        RW_INFO.with(|f| {
+            let mut borrowed = f.borrow_mut();
+            if borrowed.log {
+                borrowed.deduplicated_files.push(path);
+            }
+        });
+    }
+
+    fn log_size_limit_violation(val: SizeLimitViolation) {
+        RW_INFO.with(|f| {
+            let mut borrowed = f.borrow_mut();
+            if borrowed.log {
+                borrowed.size_limit_violations.push(val);
+            }
+        });
+    }
+
+    pub(crate) fn log_checksum_mismatch(val: ChecksumMismatch) {
+        RW_INFO.with(|f| {
+            let mut borrowed = f.borrow_mut();
+            if borrowed.log {
+                borrowed.checksum_mismatch.push(val);
+            }
+        });
+    }
+
+    fn log_ignored_link_fields(val: IgnoredLinkFields) {
+        RW_INFO.with(|f| {
+            let mut borrowed = f.borrow_mut();
+            if borrowed.log {
+                borrowed.ignored_link_fields.push(val);
+            }
+        });
+    }
+
+    fn log_substituted_link(val: SubstitutedLink) {
+        RW_INFO.with(|f| {
+            let mut borrowed = f.borrow_mut();
+            if borrowed.log {
+                borrowed.substituted_links.push(val);
+            }
+        });
+    }
+}
+
+// Linked entries
+// ======================================================
+
+#[derive(DeserializeUntaggedVerboseError, Debug)]
+pub(crate) enum LinkOrEntity<T> {
+    DatabaseLink(DatabaseLink),
+    Entity(T),
+    LenientDatabaseLink(LenientDatabaseLink),
+}
+
+/**
+Version of the link representation written by this crate. A link map without
+a `version` entry is interpreted as version 1, which is the representation
+consisting of `name` and the optional `checksum`. Links of version 1 are
+written without the `version` entry so that older readers can still read them.
+
+Version 2 adds the optional `algorithm` entry, which contains the
+[`ChecksumAlgorithm::tag`] of the algorithm the checksum has been calculated
+with. Links whose checksum has been calculated with the default
+[`ChecksumAlgorithm::Adler32`] are still written as version 1.
+
+Future versions may add further entries to a link map (e.g. timestamps). When
+reading a link whose `version` is newer than [`LINK_VERSION`], these unknown
+entries are ignored and only `name` and `checksum` are used. Links of a known
+version must not contain unknown entries, since such a map is likely an inlined
+entity rather than a link.
+ */
+pub const LINK_VERSION: u32 = 2;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct DatabaseLink {
+    pub name: String,
+    pub checksum: Option<u32>,
+    pub algorithm: ChecksumAlgorithm,
+}
+
+impl Serialize for DatabaseLink {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        // Links using the default algorithm are written as version 1 (see LINK_VERSION)
+        if self.algorithm.is_default() {
+            let mut link = serializer.serialize_struct("DatabaseLink", 2)?;
+            link.serialize_field("name", &self.name)?;
+            link.serialize_field("checksum", &self.checksum)?;
+            return link.end();
+        }
+        let mut link = serializer.serialize_struct("DatabaseLink", 4)?;
+        link.serialize_field("name", &self.name)?;
+        link.serialize_field("checksum", &self.checksum)?;
+        link.serialize_field("algorithm", &self.algorithm)?;
+        link.serialize_field("version", &LINK_VERSION)?;
+        return link.end();
+    }
+}
+
+impl<'de> Deserialize<'de> for DatabaseLink {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (link, _) = deserializer.deserialize_map(LinkVisitor { lenient: false })?;
+        return Ok(link);
+    }
+}
+
+/**
+A link map whose unknown entries are ignored. It can only be deserialized if
+[`ReadOptions::link_parsing`] is set to [`LinkParsing::Lenient`], otherwise
+deserialization fails.
+ */
+#[derive(Debug)]
+pub(crate) struct LenientDatabaseLink {
+    pub link: DatabaseLink,
+    pub ignored_fields: Vec<String>,
+}
+
+impl<'de> Deserialize<'de> for LenientDatabaseLink {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let lenient = READ_CONTEXT.with(|thread_context| {
+            thread_context
+                .get()
+                .is_some_and(|context| context.link_parsing() == LinkParsing::Lenient)
+        });
+        if !lenient {
+            return Err(serde::de::Error::custom(
+                "lenient link parsing is disabled (see ReadOptions::link_parsing)",
+            ));
+        }
+        let (link, ignored_fields) = deserializer.deserialize_map(LinkVisitor { lenient: true })?;
+        return Ok(LenientDatabaseLink {
+            link,
+            ignored_fields,
+        });
+    }
+}
+
+/**
+The checksum of a link. Besides an integer, it may be given as a string
+containing a decimal or hexadecimal (`0x` prefix) number, since hand editors
+and some emitters quote numbers.
+ */
+struct LinkChecksum(Option<u32>);
+
+impl<'de> Deserialize<'de> for LinkChecksum {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = LinkChecksum;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a checksum as integer, decimal string or hexadecimal string")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                let checksum = u32::try_from(v)
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(v), &self))?;
+                return Ok(LinkChecksum(Some(checksum)));
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                let checksum = u32::try_from(v)
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(v), &self))?;
+                return Ok(LinkChecksum(Some(checksum)));
+            }
 
-        // Store the serialized data in the file
-        match file.write_all(&data) {
-            Ok(_) => {
-                return Ok(file_path);
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                let checksum = parse_checksum(v)
+                    .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(v), &self))?;
+                return Ok(LinkChecksum(Some(checksum)));
             }
-            Err(err) => {
-                // Cleanup: Remove the file
-                remove_file(&file_path)?;
-                return Err(err);
+
+            fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+                return Ok(LinkChecksum(None));
             }
-        };
-    }
-}
 
-#[derive(Clone, Copy)]
-pub(crate) struct ReadContext {
-    log: bool,
-    pub(crate) database_manager: *mut DatabaseManager,
-}
+            fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+                return Ok(LinkChecksum(None));
+            }
 
-thread_local!(pub(crate) static READ_CONTEXT: Cell<Option<ReadContext>> = Cell::new(None));
+            fn visit_some<D: serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                return deserializer.deserialize_any(Visitor);
+            }
+        }
 
-impl ReadContext {
-    pub(crate) fn new(database_manager: &mut DatabaseManager, log: bool) -> Self {
-        return Self {
-            log,
-            database_manager: std::ptr::from_mut(database_manager),
-        };
+        deserializer.deserialize_any(Visitor)
     }
+}
 
-    pub(crate) fn read<T: DatabaseEntry>(&self, name: &OsStr) -> std::io::Result<T> {
-        // Enable / disable logging
-        RwInfo::set_log(self.log);
+/**
+Parses a checksum given as a decimal or hexadecimal (`0x` prefix) string.
+ */
+pub(crate) fn parse_checksum(string: &str) -> Option<u32> {
+    let string = string.trim();
+    match string
+        .strip_prefix("0x")
+        .or_else(|| string.strip_prefix("0X"))
+    {
+        Some(hex) => return u32::from_str_radix(hex, 16).ok(),
+        None => return string.parse().ok(),
+    }
+}
 
-        /*
-        SAFETY: A WriteContext object is both created and destroyed within the function DatabaseManager::read_verbose.
-        This function takes a mutable reference to a DatabaseManager. Therefore, the pointer is not dangling
-        during the lifetime of the WriteContext. To avoid aliasing, we need to make sure that the mutable
-        reference does not exist anymore when calling self.data_format.from_reader(instance), since this function
-        could end up calling WriteContext::read again.
-         */
-        let dbm = unsafe { &mut *self.database_manager };
-        let file_path = dbm.full_path_unchecked((type_name::<T>(), name));
+/**
+Deserializes a link map and returns the link together with the keys of all
+unknown entries. Unknown entries are only accepted if `lenient` is true or if
+the link is of a version newer than [`LINK_VERSION`].
+ */
+struct LinkVisitor {
+    lenient: bool,
+}
 
-        if !file_path.exists() {
-            return Err(Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("Could not find file {}", file_path.display()),
-            ));
-        }
+impl<'de> serde::de::Visitor<'de> for LinkVisitor {
+    type Value = (DatabaseLink, Vec<String>);
 
-        // Reading from the cache failed => read directly from the file
-        let data = fs::read(file_path.as_path())?;
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a link map containing a name and optionally a checksum")
+    }
 
-        match dbm.format.deserialize_dyn(&data) {
-            Ok(val) => {
-                let val = val as Box<dyn Any>;
-                match val.downcast::<T>() {
-                    Ok(val) => Ok(*val),
-                    Err(_) => {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::InvalidData,
-                            format!("type is not {}", type_name::<T>()),
-                        ));
+    fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
+    where
+        M: serde::de::MapAccess<'de>,
+    {
+        const FIELDS: &[&str] = &["name", "checksum", "algorithm", "version"];
+
+        let mut name: Option<String> = None;
+        let mut checksum: Option<u32> = None;
+        let mut algorithm: Option<ChecksumAlgorithm> = None;
+        let mut version: Option<u32> = None;
+        let mut unknown_fields: Vec<String> = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "name" => {
+                    if name.is_some() {
+                        return Err(serde::de::Error::duplicate_field("name"));
                     }
+                    name = Some(map.next_value()?);
+                }
+                "checksum" => {
+                    if checksum.is_some() {
+                        return Err(serde::de::Error::duplicate_field("checksum"));
+                    }
+                    checksum = map.next_value::<LinkChecksum>()?.0;
+                }
+                "algorithm" => {
+                    if algorithm.is_some() {
+                        return Err(serde::de::Error::duplicate_field("algorithm"));
+                    }
+                    algorithm = Some(map.next_value()?);
+                }
+                "version" => {
+                    if version.is_some() {
+                        return Err(serde::de::Error::duplicate_field("version"));
+                    }
+                    version = Some(map.next_value()?);
+                }
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                    unknown_fields.push(key);
                 }
             }
-            Err(err) => {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    err.to_string(),
-                ));
-            }
         }
+
+        // Unknown fields are only accepted from links written by a newer version
+        if let Some(field) = unknown_fields.first()
+            && !self.lenient
+            && version.unwrap_or(1) <= LINK_VERSION
+        {
+            return Err(serde::de::Error::unknown_field(field, FIELDS));
+        }
+
+        let name = name.ok_or_else(|| serde::de::Error::missing_field("name"))?;
+        let link = DatabaseLink {
+            name,
+            checksum,
+            algorithm: algorithm.unwrap_or_default(),
+        };
+        return Ok((link, unknown_fields));
     }
 }
 
-thread_local!(static RW_INFO: RefCell<RwInfo> = RefCell::new(RwInfo::default()));
+impl DatabaseLink {
+    /**
+    Creates a link to the database file at `file_path` which was written with
+    the file extension `file_ext`. The link name is derived from the file name
+    rather than from [`DatabaseEntry::name`], since the file name may have been
+    changed (see [`WriteOptions::alias`] and [`WriteOptions::renamer`]).
+     */
+    pub(crate) fn new(file_path: &Path, file_ext: &OsStr) -> Self {
+        let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+        let extension = format!(".{}", file_ext.to_string_lossy());
+        let name = if file_ext.is_empty() {
+            &file_name
+        } else {
+            file_name.strip_suffix(&extension).unwrap_or(&file_name)
+        };
+        DatabaseLink {
+            name: name.to_string(),
+            checksum: checksum(file_path),
+            algorithm: ChecksumAlgorithm::Adler32,
+        }
+    }
 
-#[derive(Default)]
-pub(crate) struct RwInfo {
-    log: bool,
-    overwritten_files: Vec<PathBuf>,
-    kept_files: Vec<PathBuf>,
-    created_files: Vec<PathBuf>,
-    checksum_mismatch: Vec<ChecksumMismatch>,
-}
+    /**
+    A problem with links is the "silent" manipulation of files. Consider the following example:
+    Struct A contains another struct of type B. Through the use of the annotation deserialize_link (or deserialize_arc_link),
+    struct A is stored as two distinct files (one for B and one for A containing a link to B). Now the file containing B is
+    changed (e.g. by changing some field value of B). Reading the file of A therefore does not result in the same struct
+    which was serialized.
 
-impl RwInfo {
-    fn set_log(log: bool) {
-        RW_INFO.with(|f| {
-            let rw_info = &mut *f.borrow_mut();
-            rw_info.log = log;
+    To mitigate this problem, a link may store the checksum of the file containing B as an optional field.
+    This optional field is always populated when serializing A with the DatabaseManager. When the checksum of the link
+    does not equal the checksum of file B during deserialization, the checksum mismatch is documented in the ReadInfo
+    struct which is returned by DatabaseManager::read_verbose. However, the deserialization itself does not fail even
+    though the file of B has been changed (because the indirect change to A through the file of B might have been intentional).
+     */
+    pub(crate) fn test_for_checksum_mismatch(
+        &self,
+        file_path: PathBuf,
+        checksum_loaded_file: Option<u32>,
+    ) -> Option<ChecksumMismatch> {
+        let checksum_cached_in_link = self.checksum?;
+        let checksum_loaded_file = checksum_loaded_file?;
+        if checksum_cached_in_link == checksum_loaded_file {
+            return None;
+        }
+        return Some(ChecksumMismatch {
+            checksum_cached_in_link,
+            checksum_loaded_file,
+            file_path,
         });
     }
+}
 
-    fn take_write_info() -> WriteInfo {
-        return RW_INFO.with(|f| {
-            let rw_info = &mut *f.borrow_mut();
-            return WriteInfo {
-                overwritten_files: mem::replace(&mut rw_info.overwritten_files, Vec::new()),
-                created_files: mem::replace(&mut rw_info.created_files, Vec::new()),
-                kept_files: mem::replace(&mut rw_info.kept_files, Vec::new()),
-            };
-        });
+/*
+    Serialize the given instance into the database managed by self, using the specified link mode. Return the path to the resulting file.
+    The file is saved with the file name returned by the `DatabaseEntry::name` method. If a file of the same name already exists, it is
+    overwritten unless `overwrite` is set to false. In the latter case, `_x` is appended to the string returned by `DatabaseEntry::name`,
+    where x is the first free number (no name collision).
+*/
+
+/**
+Options to modify the behaviour of [`DatabaseManager::write`]. See the
+individual fields for details.
+ */
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    /**
+    Specifies the behaviour when [`DatabaseManager::write`] attempts to write
+    a file which already exists. See [`NameCollisions`] for more.
+
+    Defaults to [`NameCollisions::KeepExisting`].
+     */
+    pub name_collisions: NameCollisions,
+    /**
+    Specifies the [`WriteMode`] when a link attribute is encountered. See
+    [`WriteMode`] for more.
+
+    Defaults to [`WriteMode::Link`].
+     */
+    pub write_mode: WriteMode,
+    /**
+    Specifies how links are represented in the written files if
+    [`WriteOptions::write_mode`] is [`WriteMode::Link`]. See [`LinkStyle`] for
+    more.
+
+    Defaults to [`LinkStyle::Map`].
+     */
+    pub link_style: LinkStyle,
+    /**
+    This map allows modifying the names of the written files. The keys are the
+    [`DatabaseKeyBuf`]s of the original entries, so entries with the same name
+    but of different types can be renamed independently. For example, if a
+    file `Material/pure_cotton` (+ file extension) should be written, but the
+    map contains a key-value pair `Material/pure_cotton: 100percent_cotton`,
+    then a file `Material/100percent_cotton` (+ file extension) will be written
+    instead. Any links
+    to this file which are created in other files also then link to the
+    `100percent_cotton` file.
+
+    If two different entries of the same type would be written to the same
+    file because of an alias (e.g. because two names are mapped to the same
+    alias or because an alias equals the name of another written entry), the
+    write fails with an error naming both entries. Files which were written
+    before the collision was detected are not removed.
+
+    Defaults to an empty [`HashMap`].
+     */
+    pub alias: HashMap<DatabaseKeyBuf, OsString>,
+    /**
+    Rewrites the names of the written files via a callback, see [`Renamer`].
+    The callback is only called for entries without an entry in
+    [`WriteOptions::alias`]. The same collision rules as for
+    [`WriteOptions::alias`] apply.
+
+    Defaults to [`None`].
+     */
+    pub renamer: Option<Renamer>,
+    /**
+    Limits for the serialized size of the written database entries. See
+    [`SizeLimits`] for more.
+
+    Defaults to no limits at all.
+     */
+    pub size_limits: SizeLimits,
+    /**
+    Limits for the number of entries per type. See [`EntryQuotas`] for more.
+
+    Defaults to no quotas at all.
+     */
+    pub entry_quotas: EntryQuotas,
+    /**
+    Compresses database entries whose serialized size exceeds a threshold
+    (requires the `compression` feature). See [`Compression`](crate::Compression) for more.
+
+    Defaults to [`None`] (no compression).
+     */
+    #[cfg(feature = "compression")]
+    pub compression: Option<crate::Compression>,
+    /**
+    If `true`, a linked entry whose serialized contents are identical to those
+    of an existing entry of the same type (with a different name) is not
+    written. Instead, the link points to the existing entry. This avoids
+    duplicated files when many entries embed identical components with
+    generated names. The reused files are listed in
+    [`WriteInfo::deduplicated_files`]. The entry passed to
+    [`DatabaseManager::write`] itself is always written.
+
+    Since the contents of all entries of a linked type are read once per write
+    call to find duplicates, this slows down writing into large type folders.
+
+    Defaults to `false`.
+     */
+    pub deduplicate: bool,
+}
+
+impl WriteOptions {
+    fn name<T: DatabaseEntry>(&self, instance: &T) -> OsString {
+        if let Some(alias) = self
+            .alias
+            .get(&DatabaseKeyBuf::new(T::folder_name(), instance.name()))
+        {
+            return alias.clone();
+        }
+        if let Some(renamer) = &self.renamer
+            && let Some(name) = renamer.rename(&DatabaseKey::from(instance))
+        {
+            return name;
+        }
+        return instance.name().to_os_string();
     }
+}
 
-    fn take_read_info() -> ReadInfo {
-        return RW_INFO.with(|f| {
-            let rw_info = &mut *f.borrow_mut();
-            return ReadInfo {
-                checksum_mismatch: mem::replace(&mut rw_info.checksum_mismatch, Vec::new()),
-            };
-        });
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            name_collisions: Default::default(),
+            write_mode: Default::default(),
+            link_style: Default::default(),
+            alias: Default::default(),
+            renamer: None,
+            size_limits: Default::default(),
+            entry_quotas: Default::default(),
+            #[cfg(feature = "compression")]
+            compression: None,
+            deduplicate: false,
+        }
     }
+}
 
-    fn log_overwritten_file_path(path: PathBuf) {
-        RW_INFO.with(|f| {
-            let mut borrowed = f.borrow_mut();
-            if borrowed.log {
-                borrowed.overwritten_files.push(path);
-            }
-        });
+/**
+A callback which rewrites the names of written files, used within
+[`WriteOptions::renamer`]. The callback receives the [`DatabaseKey`] of the
+written entry and returns the new name (without file extension) or [`None`] if
+the name should be kept. Any links to a renamed entry which are created in
+other files link to the new name.
+
+# Examples
+
+```
+use std::ffi::OsString;
+use serde_mosaic::*;
+
+let mut write_options = WriteOptions::default();
+
+// Prefix all `Material` entries with `imported_`
+write_options.renamer = Some(Renamer::new(|key| {
+    if key.type_name != "Material" {
+        return None;
     }
+    let mut name = OsString::from("imported_");
+    name.push(key.name);
+    return Some(name);
+}));
+```
+ */
+#[derive(Clone)]
+pub struct Renamer(Arc<RenameFn>);
 
-    fn log_created_file_path(path: PathBuf) {
-        RW_INFO.with(|f| {
-            let mut borrowed = f.borrow_mut();
-            if borrowed.log {
-                borrowed.created_files.push(path);
-            }
-        });
+/**
+Callback wrapped by a [`Renamer`].
+ */
+type RenameFn = dyn Fn(&DatabaseKey) -> Option<OsString> + Send + Sync;
+
+impl Renamer {
+    /**
+    Wraps the callback `f` into a [`Renamer`].
+     */
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&DatabaseKey) -> Option<OsString> + Send + Sync + 'static,
+    {
+        return Self(Arc::new(f));
     }
 
-    fn log_kept_file_path(path: PathBuf) {
-        RW_INFO.with(|f| {
-            let mut borrowed = f.borrow_mut();
-            if borrowed.log {
-                borrowed.kept_files.push(path);
-            }
-        });
+    /**
+    Returns the new name for the entry `key` or [`None`] if the name is kept.
+     */
+    pub fn rename(&self, key: &DatabaseKey) -> Option<OsString> {
+        return (self.0)(key);
     }
+}
 
-    pub(crate) fn log_checksum_mismatch(val: ChecksumMismatch) {
-        RW_INFO.with(|f| {
-            let mut borrowed = f.borrow_mut();
-            if borrowed.log {
-                borrowed.checksum_mismatch.push(val);
-            }
-        });
+impl Debug for Renamer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.write_str("Renamer");
     }
 }
 
-// Linked entries
-// ======================================================
+/**
+Limits for the serialized size of a single database entry, used within
+[`WriteOptions::size_limits`]. If the serialized representation of an entry is
+larger than the applicable limit, the behaviour is specified by
+[`SizeLimits::policy`].
+
+The limits apply to every file written during a [`DatabaseManager::write`]
+call, i.e. also to the files created for linked entries. Files which are not
+written at all (see [`NameCollisions::KeepExisting`]) are not checked.
+
+# Examples
+
+```
+use serde_mosaic::*;
+
+let mut write_options = WriteOptions::default();
+
+// No entry may be larger than 1 MB ...
+write_options.size_limits.max_entry_size = Some(1_000_000);
+
+// ... except for `Mesh` entries, which may be up to 100 MB large.
+write_options.size_limits.max_entry_size_per_type.insert("Mesh".into(), 100_000_000);
+```
+ */
+#[derive(Debug, Clone)]
+pub struct SizeLimits {
+    /**
+    The maximum size in bytes of a single serialized database entry. This
+    limit applies to all types which are not contained in
+    [`SizeLimits::max_entry_size_per_type`].
 
-#[derive(DeserializeUntaggedVerboseError, Debug)]
-pub(crate) enum LinkOrEntity<T> {
-    DatabaseLink(DatabaseLink),
-    Entity(T),
+    Defaults to [`None`] (no limit).
+     */
+    pub max_entry_size: Option<u64>,
+    /**
+    The maximum size in bytes of a single serialized database entry per type.
+    The keys are the type names as returned by [`type_name`] (i.e. the names of
+    the type folders). An entry within this map overrides
+    [`SizeLimits::max_entry_size`] for the corresponding type.
+
+    Defaults to an empty [`HashMap`].
+     */
+    pub max_entry_size_per_type: HashMap<OsString, u64>,
+    /**
+    Specifies what happens if a limit is exceeded. See [`SizeLimitPolicy`].
+
+    Defaults to [`SizeLimitPolicy::Reject`].
+     */
+    pub policy: SizeLimitPolicy,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
-pub(crate) struct DatabaseLink {
-    pub name: String,
-    #[serde(default)]
-    pub checksum: Option<u32>,
+impl SizeLimits {
+    /**
+    Returns the size limit for entries of the type `type_name`, if any.
+     */
+    pub fn limit_for<O: AsRef<OsStr>>(&self, type_name: O) -> Option<u64> {
+        return self
+            .max_entry_size_per_type
+            .get(type_name.as_ref())
+            .copied()
+            .or(self.max_entry_size);
+    }
 }
 
-impl DatabaseLink {
-    pub(crate) fn new<T: DatabaseEntry>(instance: &T, checksum: Option<u32>) -> Self {
-        DatabaseLink {
-            name: instance.name().to_string_lossy().to_string(),
-            checksum,
+impl Default for SizeLimits {
+    fn default() -> Self {
+        Self {
+            max_entry_size: None,
+            max_entry_size_per_type: HashMap::new(),
+            policy: Default::default(),
         }
     }
+}
 
+/**
+Specifies the behaviour of [`DatabaseManager::write`] when a serialized
+database entry exceeds its limit in [`SizeLimits`].
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SizeLimitPolicy {
+    #[default]
     /**
-    A problem with links is the "silent" manipulation of files. Consider the following example:
-    Struct A contains another struct of type B. Through the use of the annotation deserialize_link (or deserialize_arc_link),
-    struct A is stored as two distinct files (one for B and one for A containing a link to B). Now the file containing B is
-    changed (e.g. by changing some field value of B). Reading the file of A therefore does not result in the same struct
-    which was serialized.
-
-    To mitigate this problem, a link may store the checksum of the file containing B as an optional field.
-    This optional field is always populated when serializing A with the DatabaseManager. When the checksum of the link
-    does not equal the checksum of file B during deserialization, the checksum mismatch is documented in the ReadInfo
-    struct which is returned by DatabaseManager::read_verbose. However, the deserialization itself does not fail even
-    though the file of B has been changed (because the indirect change to A through the file of B might have been intentional).
+    The write is aborted with an error of kind [`ErrorKind::FileTooLarge`].
+    Files written before the violation was detected (e.g. linked entries) are
+    not removed.
      */
-    pub(crate) fn test_for_checksum_mismatch(
-        &self,
-        file_path: PathBuf,
-    ) -> Option<ChecksumMismatch> {
-        let checksum_cached_in_link = self.checksum?;
-        let checksum_loaded_file = checksum(file_path.as_path())?;
-        return Some(ChecksumMismatch {
-            checksum_cached_in_link,
-            checksum_loaded_file,
-            file_path,
-        });
-    }
+    Reject,
+    /**
+    The file is written anyway and the violation is reported in
+    [`WriteInfo::size_limit_violations`].
+     */
+    Warn,
 }
 
-/*
-    Serialize the given instance into the database managed by self, using the specified link mode. Return the path to the resulting file.
-    The file is saved with the file name returned by the `DatabaseEntry::name` method. If a file of the same name already exists, it is
-    overwritten unless `overwrite` is set to false. In the latter case, `_x` is appended to the string returned by `DatabaseEntry::name`,
-    where x is the first free number (no name collision).
-*/
-
 /**
-Options to modify the behaviour of [`DatabaseManager::write`]. See the
-individual fields for details.
+Limits for the number of entries per type, used within
+[`WriteOptions::entry_quotas`]. If writing a new file would exceed the
+applicable quota, the behaviour is specified by [`EntryQuotas::policy`].
+
+Only the creation of new files is checked, overwriting an existing entry never
+violates a quota. The quotas apply to every file written during a
+[`DatabaseManager::write`] call, i.e. also to the files created for linked
+entries. Expired entries (see [`DatabaseManager::write_with_expiry`]) are not
+counted.
+
+# Examples
+
+```
+use serde_mosaic::*;
+
+// Keep only the 100 most recently written `Result` entries
+let mut write_options = WriteOptions::default();
+write_options.entry_quotas.max_entries_per_type.insert("Result".into(), 100);
+write_options.entry_quotas.policy = QuotaPolicy::EvictLeastRecentlyWritten;
+```
  */
 #[derive(Debug, Clone)]
-pub struct WriteOptions {
+pub struct EntryQuotas {
     /**
-    Specifies the behaviour when [`DatabaseManager::write`] attempts to write
-    a file which already exists. See [`NameCollisions`] for more.
+    The maximum number of entries of a single type. This quota applies to all
+    types which are not contained in [`EntryQuotas::max_entries_per_type`].
 
-    Defaults to [`NameCollisions::KeepExisting`].
+    Defaults to [`None`] (no quota).
      */
-    pub name_collisions: NameCollisions,
+    pub max_entries: Option<u64>,
     /**
-    Specifies the [`WriteMode`] when a link attribute is encountered. See
-    [`WriteMode`] for more.
+    The maximum number of entries per type. The keys are the type names as
+    returned by [`type_name`] (i.e. the names of the type folders). An entry
+    within this map overrides [`EntryQuotas::max_entries`] for the
+    corresponding type.
 
-    Defaults to [`WriteMode::Link`].
+    Defaults to an empty [`HashMap`].
      */
-    pub write_mode: WriteMode,
+    pub max_entries_per_type: HashMap<OsString, u64>,
     /**
-    This map allows modifying the names of the written files. For example,
-    if a file `pure_cotton` (+ file extension) should be written, but the map
-    contains a key-value pair `pure_cotton: 100percent_cotton`, then a file
-    `100percent_cotton` (+ file extension) will be written instead. Any links
-    to this file which are created in other files also then link to the
-    `100percent_cotton` file.
+    Specifies what happens if a quota is exceeded. See [`QuotaPolicy`].
 
-    Defaults to an empty [`HashMap`].
+    Defaults to [`QuotaPolicy::Reject`].
      */
-    pub alias: HashMap<OsString, OsString>,
+    pub policy: QuotaPolicy,
 }
 
-impl WriteOptions {
-    fn name<T: DatabaseEntry>(&self, instance: &T) -> OsString {
+impl EntryQuotas {
+    /**
+    Returns the quota for entries of the type `type_name`, if any.
+     */
+    pub fn quota_for<O: AsRef<OsStr>>(&self, type_name: O) -> Option<u64> {
         return self
-            .alias
-            .get(instance.name())
-            .map(|string| string.as_os_str())
-            .unwrap_or(instance.name())
-            .to_os_string();
+            .max_entries_per_type
+            .get(type_name.as_ref())
+            .copied()
+            .or(self.max_entries);
     }
 }
 
-impl Default for WriteOptions {
+impl Default for EntryQuotas {
     fn default() -> Self {
         Self {
-            name_collisions: Default::default(),
-            write_mode: Default::default(),
-            alias: Default::default(),
+            max_entries: None,
+            max_entries_per_type: HashMap::new(),
+            policy: Default::default(),
         }
     }
 }
 
+/**
+Specifies the behaviour of [`DatabaseManager::write`] when creating a new file
+would exceed the quota of its type in [`EntryQuotas`].
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuotaPolicy {
+    #[default]
+    /**
+    The write is aborted with an error of kind [`ErrorKind::QuotaExceeded`].
+    Files written before the violation was detected (e.g. linked entries) are
+    not removed.
+     */
+    Reject,
+    /**
+    The file is written and afterwards, the least recently written entries of
+    the type (according to the modification times of their files) are removed
+    until the quota is met again. Entries written by the same call, entries
+    locked by another manager (see [`DatabaseManager::lock`]) and entries
+    which are still linked by other entries (see
+    [`DatabaseManager::find_orphans`]) are never removed, so the quota may
+    stay exceeded. The removed files are reported in
+    [`WriteInfo::evicted_files`]. If the links to the entries of the type
+    can't be determined (e.g. because another entry can't be parsed), the
+    write returns an error after the file has been written.
+     */
+    EvictLeastRecentlyWritten,
+}
+
 /**
 During the write process, [`DatabaseManager::write`] may attempt to overwrite
 files which already exist. This enum specifies the behaviour in such a case.
@@ -1499,12 +5518,9 @@ pub enum NameCollisions {
     /**
     Keep the existing file and create a new file with a modified name. If a link
     is being created, it links to the new file. The modification scheme is as
-    follows:
-    1) Append "_0" to the file name and check if that name is taken as well.
-    2) If that is the case, add 1 to the number at the end and check if that
-    name is also taken.
-    3) Repeat 2) until an available name has been found, save the file then
-    under the available name.
+    follows: The suffix `_<counter>` is appended to the file name, where
+    `<counter>` is one higher than the highest counter already used for this
+    name (starting at 0). Gaps within the used counters are not filled.
     For example, if set to false and attempting to write `pure_cotton` from
     the [`DatabaseManager`] docstring four times, the following files would be
     created:
@@ -1538,11 +5554,269 @@ pub enum WriteMode {
     Link,
 }
 
+/**
+Specifies the serialized representation of a link written with
+[`WriteMode::Link`]. Both representations are accepted when reading, so the
+styles can be mixed freely within a database.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkStyle {
+    #[default]
+    /**
+    The link is written as a map containing the `name` of the linked entry and
+    the `checksum` of its file:
+
+    ```yaml
+    material:
+      name: pure_cotton
+      checksum: 94637245
+    ```
+
+    This is the default style.
+     */
+    Map,
+    /**
+    The link is written as a plain string containing only the name of the
+    linked entry:
+
+    ```yaml
+    material: pure_cotton
+    ```
+
+    Since such a link carries no checksum, checksum mismatches can't be
+    detected when it is read. Functions which inspect the links of the stored
+    files without knowing the types of the entries (e.g.
+    [`DatabaseManager::verify_entry`](crate::DatabaseManager::verify_entry)) can't
+    distinguish a short link from an ordinary string field and therefore
+    ignore short links.
+     */
+    Short,
+}
+
+/**
+Options to modify the behaviour of [`DatabaseManager::read_with`] and
+[`DatabaseManager::read_verbose_with`]. See the individual fields for details.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /**
+    Specifies the behaviour when the checksum stored within a link does not
+    match the checksum of the linked file. See [`ChecksumMismatchPolicy`] for
+    more.
+
+    Defaults to [`ChecksumMismatchPolicy::Report`].
+     */
+    pub checksum_mismatch: ChecksumMismatchPolicy,
+    /**
+    Specifies whether `${ENV_VAR}` placeholders in the read files are replaced
+    by the values of the corresponding environment variables before the files
+    are deserialized. See [`EnvInterpolation`] for more.
+
+    Defaults to [`EnvInterpolation::Disabled`].
+     */
+    pub env_interpolation: EnvInterpolation,
+    /**
+    Values for the parameter placeholders (`{{name}}`) within the read files.
+    If this map is not empty, every read file (including linked files) is
+    parsed into an untyped [`Value`] and all placeholders within its string
+    values are replaced before the file is deserialized. A string which
+    consists of a single placeholder is replaced by the parameter value
+    itself (so e.g. a number can be inserted into a numeric field); otherwise
+    the parameter is embedded into the string. Reading fails if a placeholder
+    refers to a parameter which is not given.
+
+    Substituting parameters requires the [`Format`] of the database manager to
+    support untyped values (see [`Format::deserialize_value`]). If this map is
+    not empty, the [`Cache`] is bypassed like with
+    [`ReadOptions::bypass_cache`], since linked entries read with different
+    parameters differ from each other. See also
+    [`DatabaseManager::read_with_params`].
+
+    Defaults to an empty map (no substitution).
+     */
+    pub parameters: HashMap<String, Value>,
+    /**
+    Specifies how link maps containing unknown entries are treated. See
+    [`LinkParsing`] for more.
+
+    Defaults to [`LinkParsing::Strict`].
+     */
+    pub link_parsing: LinkParsing,
+    /**
+    If `true`, [`Arc`]-wrapped linked entries are always read from their files
+    instead of being taken from the [`Cache`], and the read entries are not
+    put into the cache. This is useful for one-off reads which should see the
+    current files (e.g. after they have been modified by another process)
+    without affecting the cache.
+
+    Defaults to `false`.
+     */
+    pub bypass_cache: bool,
+    /**
+    The maximum depth up to which links are resolved. Links within the read
+    entry itself have the depth 1, links within these linked entries have
+    the depth 2 and so on. Links beyond this depth are left unresolved if
+    they are deserialized into a [`Linked`](crate::Linked) field (see
+    [`deserialize_linked`](crate::deserialize_linked)); all other links are
+    resolved regardless of their depth. A depth of 0 leaves all links of
+    [`Linked`](crate::Linked) fields unresolved.
+
+    This is useful for reading only the top-level fields of entries with deep
+    link graphs.
+
+    Defaults to [`None`] (no limit).
+     */
+    pub max_link_depth: Option<usize>,
+}
+
+/**
+Specifies whether [`DatabaseManager::read_with`] substitutes placeholders of
+the form `${ENV_VAR}` with the value of the environment variable `ENV_VAR`.
+This allows storing deployment-specific values (e.g. paths) as placeholders in
+a shared database.
+
+The substitution is performed on the raw bytes of every read file (including
+linked files) before it is deserialized, hence placeholders are replaced
+regardless of where they occur (keys, values, comments). The substituted
+value is inserted as-is, so it must not break the syntax of the
+[`Format`] (e.g. a value containing a quote inside a quoted string). A
+placeholder can be escaped by doubling the dollar sign: `$${ENV_VAR}` is read
+as the literal `${ENV_VAR}`.
+
+The files within the database are never modified. Since the read instances
+depend on the environment, the [`Cache`] is bypassed if placeholders are
+substituted (see [`ReadOptions::bypass_cache`]).
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EnvInterpolation {
+    #[default]
+    /**
+    Placeholders are not substituted.
+     */
+    Disabled,
+    /**
+    Placeholders are substituted. Placeholders of environment variables which
+    are not set are left as they are.
+     */
+    Lenient,
+    /**
+    Placeholders are substituted. If a placeholder refers to an environment
+    variable which is not set, reading fails with an error naming the
+    variable.
+     */
+    Strict,
+}
+
+impl EnvInterpolation {
+    /**
+    Substitutes the placeholders in `bytes` according to `self`.
+     */
+    fn interpolate(&self, bytes: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        if *self == EnvInterpolation::Disabled || !bytes.windows(2).any(|w| w == b"${") {
+            return Ok(bytes);
+        }
+
+        let mut output = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+        while index < bytes.len() {
+            let rest = &bytes[index..];
+            if rest.starts_with(b"$${") {
+                output.extend_from_slice(b"${");
+                index += 3;
+                continue;
+            }
+            if rest.starts_with(b"${")
+                && let Some(end) = rest.iter().position(|byte| *byte == b'}')
+                && let Ok(var_name) = std::str::from_utf8(&rest[2..end])
+                && !var_name.is_empty()
+            {
+                match std::env::var_os(var_name) {
+                    Some(value) => {
+                        output.extend_from_slice(value.as_encoded_bytes());
+                        index += end + 1;
+                        continue;
+                    }
+                    None => {
+                        if *self == EnvInterpolation::Strict {
+                            return Err(Error::new(
+                                ErrorKind::NotFound,
+                                format!("Environment variable {} is not set", var_name),
+                            ));
+                        }
+                    }
+                }
+            }
+            output.push(bytes[index]);
+            index += 1;
+        }
+        return Ok(output);
+    }
+}
+
+/**
+Specifies how [`DatabaseManager::read_with`] treats link maps which contain
+entries besides `name`, `checksum` and `version` (e.g. a typo in a hand-edited
+file). Links whose version is newer than [`LINK_VERSION`] are always read
+leniently.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkParsing {
+    #[default]
+    /**
+    A map with unknown entries is not a link. It is therefore deserialized as
+    the linked type itself, which usually fails with an error about missing
+    fields of that type.
+     */
+    Strict,
+    /**
+    If a map with unknown entries can't be deserialized as the linked type, it
+    is interpreted as a link and the unknown entries are ignored. They are
+    reported in [`ReadInfo::ignored_link_fields`].
+     */
+    Lenient,
+}
+
+/**
+During the read process, [`DatabaseManager::read_with`] may encounter links
+whose checksum does not match the linked file (see [`ChecksumMismatch`]). This
+enum specifies the behaviour in such a case.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChecksumMismatchPolicy {
+    #[default]
+    /**
+    The linked file is read regardless and the mismatch is reported in the
+    [`ReadInfo`] returned by [`DatabaseManager::read_verbose_with`]. The files
+    are not modified.
+     */
+    Report,
+    /**
+    Like [`ChecksumMismatchPolicy::Report`], but after the read call succeeded,
+    the outdated links are rewritten with the checksum of the linked file, so
+    the mismatch is not reported by subsequent reads anymore. Since rewriting a
+    file changes its checksum, links to rewritten files which were resolved
+    during the same read call are updated as well. All rewritten files are
+    listed in [`ReadInfo::healed_files`].
+
+    Links to entries taken from the [`Cache`] are healed as well. The file
+    containing a link is tracked per thread, so links resolved inside a
+    [`ReadContextHandle`] are only healed if the handle was obtained while a
+    file was being read (see [`ReadContextHandle::current`]).
+
+    Rewriting a file requires the [`Format`] of the database manager to support
+    untyped values (see [`Format::deserialize_value`]). The rewritten file is
+    serialized from scratch, hence manual formatting of the file is lost.
+
+    Use this policy only if the changes to the linked files were intentional.
+     */
+    Heal,
+}
+
 /**
 This struct is returned by [`DatabaseManager::read_verbose`] and contains
 information about the reading procedure within its fields.
  */
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ReadInfo {
     /**
     A vector of all [`ChecksumMismatch`]es which happened when reading a linked
@@ -1551,6 +5825,93 @@ pub struct ReadInfo {
     for inspection. See the docstring of [`ChecksumMismatch`] for more.
      */
     pub checksum_mismatch: Vec<ChecksumMismatch>,
+    /**
+    If [`ReadOptions::checksum_mismatch`] is set to
+    [`ChecksumMismatchPolicy::Heal`], this vector contains the paths of all
+    files whose links have been rewritten to resolve checksum mismatches.
+     */
+    pub healed_files: Vec<PathBuf>,
+    /**
+    All read files whose signature could not be verified (see
+    [`Signatures`](crate::signature)). Empty if no signatures are configured.
+     */
+    pub signature_problems: Vec<SignatureProblem>,
+    /**
+    All links whose unknown entries have been ignored because
+    [`ReadOptions::link_parsing`] is set to [`LinkParsing::Lenient`].
+     */
+    pub ignored_link_fields: Vec<IgnoredLinkFields>,
+    /**
+    All links whose linked file didn't exist and which have therefore been
+    substituted by a fallback instance (see
+    [`deserialize_link_or_default`](crate::deserialize_link_or_default)).
+     */
+    pub substituted_links: Vec<SubstitutedLink>,
+    /**
+    The [`Revision`] of all files involved in the read, which can be used
+    with [`DatabaseManager::write_if_revision`].
+     */
+    pub revision: Revision,
+}
+
+/**
+An opaque token describing the state of all files involved in a
+[`DatabaseManager::read_verbose`] call (see [`ReadInfo::revision`]), i.e. the
+file of the read entry and the files of all linked entries. It is used for
+optimistic concurrency control via [`DatabaseManager::write_if_revision`].
+
+Linked entries which were taken from the [`Cache`] instead of being read are
+recorded with the checksum stored in their [`CacheEntry`], i.e. the checksum
+of the file the cached instance has been read from. If the cache entry has no
+checksum (e.g. because it has been inserted manually) or its checksum has been
+calculated with another [`ChecksumAlgorithm`] than the one of the database
+manager, the state of the file is unknown and it is always considered changed.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Revision {
+    checksums: BTreeMap<PathBuf, Option<u32>>,
+    unknown: BTreeSet<PathBuf>,
+    algorithm: ChecksumAlgorithm,
+}
+
+impl Revision {
+    fn record(&mut self, file_path: PathBuf, checksum: Option<u32>) {
+        self.unknown.remove(&file_path);
+        self.checksums.insert(file_path, checksum);
+    }
+
+    fn record_unknown(&mut self, file_path: PathBuf) {
+        self.checksums.remove(&file_path);
+        self.unknown.insert(file_path);
+    }
+
+    /**
+    Returns the paths of all files recorded in `self`.
+     */
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        return self
+            .checksums
+            .keys()
+            .chain(self.unknown.iter())
+            .map(PathBuf::as_path);
+    }
+
+    /**
+    Returns the paths of all files recorded in `self` which have been
+    modified, created or removed since `self` was created. Files whose state
+    at the time of the read is unknown are always included.
+     */
+    pub fn changed_files(&self) -> Vec<PathBuf> {
+        let mut changed_files: Vec<PathBuf> = self
+            .checksums
+            .iter()
+            .filter(|(file_path, recorded)| self.algorithm.checksum_file(file_path) != **recorded)
+            .map(|(file_path, _)| file_path.clone())
+            .collect();
+        changed_files.extend(self.unknown.iter().cloned());
+        changed_files.sort();
+        return changed_files;
+    }
 }
 
 /**
@@ -1578,13 +5939,58 @@ pub struct WriteInfo {
     overwritten files are listed within this field.
      */
     pub overwritten_files: Vec<PathBuf>,
+    /**
+    When writing via [`DatabaseManager::write_if_changed`], files whose
+    contents would not change are not written again. The paths of these files
+    are listed within this field.
+     */
+    pub unchanged_files: Vec<PathBuf>,
+    /**
+    If [`WriteOptions::deduplicate`] is `true`, linked entries whose contents
+    are identical to those of an existing entry are not written. The paths of
+    the existing files the links point to instead are listed within this field.
+     */
+    pub deduplicated_files: Vec<PathBuf>,
+    /**
+    If [`SizeLimits::policy`] is set to [`SizeLimitPolicy::Warn`], all files
+    which exceeded their size limit are listed within this field.
+     */
+    pub size_limit_violations: Vec<SizeLimitViolation>,
+    /**
+    If [`EntryQuotas::policy`] is set to
+    [`QuotaPolicy::EvictLeastRecentlyWritten`], all files which have been
+    removed to meet the quotas are listed within this field.
+     */
+    pub evicted_files: Vec<PathBuf>,
+}
+
+/**
+Information about a database entry whose serialized size exceeded its limit
+(see [`SizeLimits`]).
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeLimitViolation {
+    /**
+    Path to the written file.
+     */
+    pub file_path: PathBuf,
+    /**
+    The serialized size of the entry in bytes.
+     */
+    pub size: u64,
+    /**
+    The limit which was exceeded in bytes.
+     */
+    pub limit: u64,
 }
 
 /**
 Information about a checksum mismatch.
 
-A checksum is an [`u32`] integer derived from the contents of a file using
-[`adler32::adler32`] (see also the [`checksum`] function). When deserializing
+A checksum is an [`u32`] integer derived from the contents of a file using a
+[`ChecksumAlgorithm`] (see also the [`checksum`] function). Links store the
+algorithm their checksum has been calculated with, so both checksums of a
+mismatch have been calculated with the same algorithm. When deserializing
 a link which contains a checksum and the contents of the linked file do not
 match that checksum, a checksum mismatch occurs. The file is still deserialized
 and the resulting type is used to replace the link. However, sometimes it might
@@ -1612,15 +6018,59 @@ pub struct ChecksumMismatch {
 }
 
 /**
-Calculates the checksum of the file contents at the given `path` using
-[`adler32::adler32`].
+A link which contained unknown entries that have been ignored during reading
+(see [`LinkParsing::Lenient`]). It is returned as part of [`ReadInfo`] when
+using [`DatabaseManager::read_verbose_with`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoredLinkFields {
+    /**
+    Path to the file containing the link. [`None`] if the link was not read
+    from a file of the database (e.g. via [`DatabaseManager::from_str`]).
+     */
+    pub file_path: Option<PathBuf>,
+    /**
+    The name of the linked entry.
+     */
+    pub name: String,
+    /**
+    The keys of the ignored entries.
+     */
+    pub fields: Vec<String>,
+}
+
+/**
+A link whose linked file didn't exist and which has therefore been substituted
+by a fallback instance instead of failing the read (see
+[`deserialize_link_or_default`](crate::deserialize_link_or_default)). It is
+returned as part of [`ReadInfo`] when using [`DatabaseManager::read_verbose`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubstitutedLink {
+    /**
+    Path to the file containing the link. [`None`] if the link was not read
+    from a file of the database (e.g. via [`DatabaseManager::from_str`]).
+     */
+    pub file_path: Option<PathBuf>,
+    /**
+    The name of the linked entry.
+     */
+    pub name: String,
+    /**
+    Path to the linked file which could not be found.
+     */
+    pub linked_file: PathBuf,
+}
+
+/**
+Calculates the checksum of the file contents at the given `path` using the
+default [`ChecksumAlgorithm::Adler32`]. Use [`ChecksumAlgorithm::checksum_file`]
+for other algorithms.
 
 This function can be used to determine the checksum of a file outside of this
 crate (e.g. when a link is written manually). If there is no file at the given
 `path`, [`None`] is returned.
  */
 pub fn checksum(path: &Path) -> Option<u32> {
-    let f = File::open(path).ok()?;
-    let reader = BufReader::new(f);
-    return adler32::adler32(reader).ok();
+    return ChecksumAlgorithm::Adler32.checksum_file(path);
 }
@@ -0,0 +1,358 @@
+/*!
+This module contains functionality to compare two databases, e.g. two
+snapshots of the same database. See [`DatabaseManager::diff`] and
+[`DatabaseManager::diff_with`], which return a [`DatabaseDiff`].
+
+Entries are compared by the checksums of their (decrypted and decompressed)
+file contents. Optionally, the entries which differ are parsed into untyped
+[`Value`]s and compared field by field (see [`DiffOptions::fields`]), hence no
+concrete types are needed.
+ */
+
+use std::collections::HashSet;
+use std::fs;
+
+use crate::{
+    ChecksumAlgorithm, DatabaseKeyBuf, DatabaseManager, DatabaseReport, FileStatus, Problem, Value,
+};
+
+/**
+Options to modify the behaviour of [`DatabaseManager::diff_with`]. See the
+individual fields for details.
+ */
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /**
+    If `true`, entries which exist in both databases with different checksums
+    are parsed and their differing fields are listed in
+    [`EntryDiff::fields`].
+
+    Defaults to `false`.
+     */
+    pub fields: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self { fields: false }
+    }
+}
+
+/**
+This struct is returned by [`DatabaseManager::diff`] and contains the
+differences between two databases within its fields. All keys are sorted by
+type name first and name second.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseDiff {
+    /**
+    Entries which only exist in the database of `self`.
+     */
+    pub only_in_self: Vec<DatabaseKeyBuf>,
+    /**
+    Entries which only exist in the database of `other`.
+     */
+    pub only_in_other: Vec<DatabaseKeyBuf>,
+    /**
+    Entries which exist in both databases with different contents.
+     */
+    pub changed: Vec<EntryDiff>,
+    /**
+    Files which could not be read or parsed. These entries are neither listed
+    in [`DatabaseDiff::changed`] nor in the fields of the other entries.
+     */
+    pub report: DatabaseReport,
+}
+
+impl DatabaseDiff {
+    /**
+    Returns `true` if both databases contain the same entries with identical
+    contents.
+     */
+    pub fn is_empty(&self) -> bool {
+        return self.only_in_self.is_empty()
+            && self.only_in_other.is_empty()
+            && self.changed.is_empty();
+    }
+}
+
+/**
+A database entry which exists in both databases of a
+[`DatabaseManager::diff`] call with different contents.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryDiff {
+    /**
+    The key of the entry.
+     */
+    pub key: DatabaseKeyBuf,
+    /**
+    The checksum of the entry within the database of `self`.
+     */
+    pub checksum_self: u32,
+    /**
+    The checksum of the entry within the database of `other`.
+     */
+    pub checksum_other: u32,
+    /**
+    The fields whose values differ, in the order in which they appear in the
+    entry of `self` (followed by fields which only exist in the entry of
+    `other`). This vector is empty unless [`DiffOptions::fields`] is `true`.
+    If the entries have identical parsed contents (e.g. because only the
+    formatting differs), it is empty as well.
+     */
+    pub fields: Vec<FieldDiff>,
+}
+
+/**
+A single field whose value differs between two versions of an entry, see
+[`EntryDiff::fields`].
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    /**
+    The path of the field, consisting of field names separated by dots (e.g.
+    `material.name`). Elements of sequences are accessed by their index, e.g.
+    `legs.0.id`. Links are not resolved, so a changed linked entry shows up as
+    a changed `checksum` field of the link.
+     */
+    pub path: String,
+    /**
+    The value of the field within the entry of `self` or [`None`] if the
+    field doesn't exist there.
+     */
+    pub value_self: Option<Value>,
+    /**
+    The value of the field within the entry of `other` or [`None`] if the
+    field doesn't exist there.
+     */
+    pub value_other: Option<Value>,
+}
+
+impl DatabaseManager {
+    /**
+    Compares the database of `self` with the database of `other` and returns
+    the entries which only exist in either of them and the entries whose
+    contents differ. `self` and `other` may use different
+    [`Format`](crate::Format)s, but then every common entry is reported as
+    changed, since their file contents differ. Both databases are never
+    modified.
+
+    The checksums are calculated with the
+    [`ChecksumAlgorithm`](crate::ChecksumAlgorithm) of `self`. Entries which
+    can't be read are skipped and reported in [`DatabaseDiff::report`].
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let before = DatabaseManager::open("/path/to/snapshot_1", SerdeYaml).expect("directory exists");
+    let after = DatabaseManager::open("/path/to/snapshot_2", SerdeYaml).expect("directory exists");
+    let diff = before.diff(&after).expect("databases are accessible");
+    for key in diff.only_in_other.iter() {
+        println!("added {}", key);
+    }
+    for entry in diff.changed.iter() {
+        println!("changed {}", entry.key);
+    }
+    ```
+     */
+    pub fn diff(&self, other: &DatabaseManager) -> std::io::Result<DatabaseDiff> {
+        return self.diff_with(other, &DiffOptions::default());
+    }
+
+    /**
+    Like [`DatabaseManager::diff`], but the comparison can be customized via
+    the given [`DiffOptions`].
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let before = DatabaseManager::open("/path/to/snapshot_1", SerdeYaml).expect("directory exists");
+    let after = DatabaseManager::open("/path/to/snapshot_2", SerdeYaml).expect("directory exists");
+    let mut diff_options = DiffOptions::default();
+    diff_options.fields = true;
+    let diff = before.diff_with(&after, &diff_options).expect("databases are accessible");
+    for entry in diff.changed.iter() {
+        for field in entry.fields.iter() {
+            println!(
+                "{}.{}: {:?} -> {:?}",
+                entry.key, field.path, field.value_self, field.value_other
+            );
+        }
+    }
+    ```
+     */
+    pub fn diff_with(
+        &self,
+        other: &DatabaseManager,
+        diff_options: &DiffOptions,
+    ) -> std::io::Result<DatabaseDiff> {
+        let mut diff = DatabaseDiff::default();
+        let our_keys = self.entry_keys()?;
+        let their_keys = other.entry_keys()?;
+        let our_key_set: HashSet<&DatabaseKeyBuf> = our_keys.iter().collect();
+        let their_key_set: HashSet<&DatabaseKeyBuf> = their_keys.iter().collect();
+
+        diff.only_in_other = their_keys
+            .iter()
+            .filter(|key| !our_key_set.contains(key))
+            .cloned()
+            .collect();
+
+        for key in our_keys.iter() {
+            if !their_key_set.contains(key) {
+                diff.only_in_self.push(key.clone());
+                continue;
+            }
+            let (Some(checksum_self), Some(checksum_other)) = (
+                self.content_checksum(key, self.checksum_algorithm, &mut diff.report),
+                other.content_checksum(key, self.checksum_algorithm, &mut diff.report),
+            ) else {
+                continue;
+            };
+            if checksum_self == checksum_other {
+                continue;
+            }
+
+            let mut fields = Vec::new();
+            if diff_options.fields {
+                let mut values = Vec::with_capacity(2);
+                for dbm in [self, other] {
+                    match dbm.read_value(key) {
+                        Ok(value) => values.push(value.into_entry_contents()),
+                        Err(status) => {
+                            let path = dbm.full_path_unchecked(key);
+                            match status {
+                                FileStatus::Unreadable(message) => {
+                                    diff.report.push(Problem::Unreadable { path, message })
+                                }
+                                FileStatus::Unparseable(message) => {
+                                    diff.report.push(Problem::Unparseable { path, message })
+                                }
+                                FileStatus::Missing | FileStatus::Valid => (),
+                            }
+                        }
+                    }
+                }
+                let [Some(value_self), Some(value_other)] = values.as_slice() else {
+                    continue;
+                };
+                diff_values(String::new(), value_self, value_other, &mut fields);
+            }
+            diff.changed.push(EntryDiff {
+                key: key.clone(),
+                checksum_self,
+                checksum_other,
+                fields,
+            });
+        }
+        return Ok(diff);
+    }
+
+    /**
+    Returns the checksum of the (decrypted and decompressed) contents of the
+    entry `key`, calculated with `algorithm`. If the file can't be read, the
+    problem is added to `report`.
+     */
+    fn content_checksum(
+        &self,
+        key: &DatabaseKeyBuf,
+        algorithm: ChecksumAlgorithm,
+        report: &mut DatabaseReport,
+    ) -> Option<u32> {
+        let path = self.full_path_unchecked(key);
+        match fs::read(&path).and_then(|bytes| self.decode_file(&path, bytes)) {
+            Ok(bytes) => return Some(algorithm.checksum_bytes(&bytes)),
+            Err(err) => {
+                report.push(Problem::Unreadable {
+                    path,
+                    message: err.to_string(),
+                });
+                return None;
+            }
+        }
+    }
+}
+
+/**
+Compares `value_self` and `value_other` recursively and appends a
+[`FieldDiff`] for every differing field below `path` to `fields`. Maps are
+compared key by key and sequences element by element, all other values are
+compared as a whole.
+ */
+fn diff_values(path: String, value_self: &Value, value_other: &Value, fields: &mut Vec<FieldDiff>) {
+    if value_self == value_other {
+        return;
+    }
+    let field_path = |key: String| {
+        if path.is_empty() {
+            return key;
+        }
+        return format!("{}.{}", path, key);
+    };
+    match (value_self, value_other) {
+        (Value::Map(entries_self), Value::Map(entries_other)) => {
+            for (key, child_self) in entries_self.iter() {
+                let child_other = entries_other
+                    .iter()
+                    .find(|(other_key, _)| other_key == key)
+                    .map(|(_, value)| value);
+                let child_path = field_path(key_string(key));
+                match child_other {
+                    Some(child_other) => diff_values(child_path, child_self, child_other, fields),
+                    None => fields.push(FieldDiff {
+                        path: child_path,
+                        value_self: Some(child_self.clone()),
+                        value_other: None,
+                    }),
+                }
+            }
+            for (key, child_other) in entries_other.iter() {
+                if !entries_self.iter().any(|(self_key, _)| self_key == key) {
+                    fields.push(FieldDiff {
+                        path: field_path(key_string(key)),
+                        value_self: None,
+                        value_other: Some(child_other.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Seq(elements_self), Value::Seq(elements_other)) => {
+            for index in 0..elements_self.len().max(elements_other.len()) {
+                let child_path = field_path(index.to_string());
+                match (elements_self.get(index), elements_other.get(index)) {
+                    (Some(child_self), Some(child_other)) => {
+                        diff_values(child_path, child_self, child_other, fields)
+                    }
+                    (child_self, child_other) => fields.push(FieldDiff {
+                        path: child_path,
+                        value_self: child_self.cloned(),
+                        value_other: child_other.cloned(),
+                    }),
+                }
+            }
+        }
+        _ => fields.push(FieldDiff {
+            path,
+            value_self: Some(value_self.clone()),
+            value_other: Some(value_other.clone()),
+        }),
+    }
+}
+
+/**
+Returns the representation of the map key `key` within a field path.
+ */
+fn key_string(key: &Value) -> String {
+    match key {
+        Value::String(string) => return string.clone(),
+        Value::I64(int) => return int.to_string(),
+        Value::U64(int) => return int.to_string(),
+        Value::Bool(boolean) => return boolean.to_string(),
+        other => return format!("{:?}", other),
+    }
+}
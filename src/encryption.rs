@@ -0,0 +1,219 @@
+/*!
+This module contains the [`Encryption`] policy, which allows storing the
+entries of selected types encrypted at rest (requires the `encryption`
+feature). Entries of all other types are stored in plain text, so only
+sensitive type folders pay the overhead of encryption.
+
+The entries are encrypted with ChaCha20-Poly1305 using a 256 bit key per type,
+which is provided by a user-defined callback. The type name is used as
+associated data, hence an encrypted file can't be moved into another type
+folder unnoticed. An encrypted file consists of a short header, a random nonce
+and the ciphertext.
+
+Encryption is applied by [`DatabaseManager::write`] and decryption by
+[`DatabaseManager::read`] as well as by all operations which work on untyped
+[`Value`](crate::Value)s (e.g. [`DatabaseManager::verify_entry`]). Checksums
+(see [`checksum`](crate::checksum)) are calculated from the encrypted files.
+Since every write uses a new nonce, the checksum of an encrypted file changes
+whenever it is written, even if its contents stay the same.
+ */
+
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+
+use chacha20poly1305::aead::{Aead, OsRng, Payload};
+use chacha20poly1305::{AeadCore, ChaCha20Poly1305, KeyInit, Nonce};
+
+use crate::{DatabaseEntry, DatabaseManager};
+
+/**
+A 256 bit key used to encrypt the entries of a type. See [`Encryption`].
+ */
+pub type EncryptionKey = [u8; 32];
+
+/**
+Callback which returns the key of a type, see [`Encryption::new`].
+ */
+type KeyLookup = dyn Fn(&OsStr) -> Option<EncryptionKey> + Send + Sync;
+
+/**
+Header which marks an encrypted file.
+ */
+const HEADER: &[u8] = b"serde_mosaic:chacha20poly1305\n";
+
+/**
+Length of the nonce which follows the header.
+ */
+const NONCE_LEN: usize = 12;
+
+/**
+Specifies which types of a [`DatabaseManager`](crate::DatabaseManager) are
+encrypted at rest and how the keys are obtained. See the module docstring and
+[`DatabaseManager::set_encryption`].
+
+# Examples
+
+```no_run
+use serde_mosaic::*;
+
+let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+let encryption = Encryption::new(|type_name| {
+    // Look up the key, e.g. from a key store or an environment variable
+    match type_name.to_str() {
+        Some("Credentials") => Some([42; 32]),
+        _ => None,
+    }
+})
+.encrypt_type("Credentials");
+dbm.set_encryption(Some(encryption));
+```
+ */
+#[derive(Clone)]
+pub struct Encryption {
+    types: HashSet<OsString>,
+    key_lookup: Arc<KeyLookup>,
+}
+
+impl Encryption {
+    /**
+    Creates a new policy which doesn't encrypt any type yet. `key_lookup` is
+    called with the type name whenever an entry of an encrypted type is read
+    or written and must return the key for this type. If it returns [`None`],
+    the read or write fails with an error of kind
+    [`ErrorKind::PermissionDenied`].
+     */
+    pub fn new<F>(key_lookup: F) -> Self
+    where
+        F: Fn(&OsStr) -> Option<EncryptionKey> + Send + Sync + 'static,
+    {
+        return Self {
+            types: HashSet::new(),
+            key_lookup: Arc::new(key_lookup),
+        };
+    }
+
+    /**
+    Marks the type `T` as encrypted. The type name is derived via
+    [`DatabaseEntry::folder_name`].
+     */
+    pub fn encrypt<T: DatabaseEntry>(self) -> Self {
+        return self.encrypt_type(T::folder_name());
+    }
+
+    /**
+    Marks the type with the given folder name as encrypted.
+     */
+    pub fn encrypt_type<O: Into<OsString>>(mut self, type_name: O) -> Self {
+        self.types.insert(type_name.into());
+        return self;
+    }
+
+    /**
+    Returns `true` if the entries of the type with the given folder name are
+    encrypted.
+     */
+    pub fn is_encrypted<O: AsRef<OsStr>>(&self, type_name: O) -> bool {
+        return self.types.contains(type_name.as_ref());
+    }
+
+    fn cipher(&self, type_name: &OsStr) -> std::io::Result<ChaCha20Poly1305> {
+        let key = (self.key_lookup)(type_name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::PermissionDenied,
+                format!("No encryption key for type {}", type_name.to_string_lossy()),
+            )
+        })?;
+        return Ok(ChaCha20Poly1305::new(&key.into()));
+    }
+
+    /**
+    Encrypts the serialized entry `bytes` of the type `type_name`.
+     */
+    pub(crate) fn encrypt_bytes(
+        &self,
+        type_name: &OsStr,
+        bytes: &[u8],
+    ) -> std::io::Result<Vec<u8>> {
+        let cipher = self.cipher(type_name)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: bytes,
+                    aad: type_name.as_encoded_bytes(),
+                },
+            )
+            .map_err(|_| Error::other("Encryption failed"))?;
+
+        let mut encrypted = Vec::with_capacity(HEADER.len() + NONCE_LEN + ciphertext.len());
+        encrypted.extend_from_slice(HEADER);
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend_from_slice(&ciphertext);
+        return Ok(encrypted);
+    }
+
+    /**
+    Decrypts the encrypted entry `bytes` of the type `type_name`.
+     */
+    pub(crate) fn decrypt_bytes(
+        &self,
+        type_name: &OsStr,
+        bytes: &[u8],
+    ) -> std::io::Result<Vec<u8>> {
+        let Some(rest) = bytes
+            .strip_prefix(HEADER)
+            .filter(|rest| rest.len() >= NONCE_LEN)
+        else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "file is not encrypted, but its type requires encryption",
+            ));
+        };
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        return self
+            .cipher(type_name)?
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: type_name.as_encoded_bytes(),
+                },
+            )
+            .map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    "decryption failed (wrong key or corrupted file)",
+                )
+            });
+    }
+}
+
+impl std::fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut types: Vec<&OsString> = self.types.iter().collect();
+        types.sort();
+        return f.debug_struct("Encryption").field("types", &types).finish();
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Sets the [`Encryption`] policy of `self`. Entries of encrypted types are
+    encrypted when written and decrypted when read from now on; existing files
+    are not converted. Passing [`None`] disables encryption, which makes the
+    files of previously encrypted types unreadable by `self`.
+     */
+    pub fn set_encryption(&mut self, encryption: Option<Encryption>) {
+        self.encryption = encryption;
+    }
+
+    /**
+    Returns the [`Encryption`] policy of `self`, if any.
+     */
+    pub fn encryption(&self) -> Option<&Encryption> {
+        return self.encryption.as_ref();
+    }
+}
@@ -0,0 +1,162 @@
+/*!
+This module contains the change-event feed of a [`DatabaseManager`], see
+[`DatabaseManager::subscribe`].
+
+Every time a manager creates, overwrites or removes an entry file, a
+[`ChangeEvent`] is sent to all subscribers. This allows e.g. search indexes or
+user interfaces to react to changes without comparing the [`WriteInfo`](crate::WriteInfo)s
+of all write operations. Only the operations of the manager itself (and of its
+clones, which share the subscribers) are observed - changes made by other
+managers or processes are not.
+ */
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{DatabaseKeyBuf, DatabaseManager};
+
+/**
+The kind of change described by a [`ChangeEvent`].
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    /**
+    The file of the entry has been created.
+     */
+    Created,
+    /**
+    The existing file of the entry has been overwritten.
+     */
+    Overwritten,
+    /**
+    The file of the entry has been removed.
+     */
+    Removed,
+}
+
+/**
+A change of a database entry, see [`DatabaseManager::subscribe`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeEvent {
+    /**
+    What happened to the entry.
+     */
+    pub kind: ChangeKind,
+    /**
+    The key of the changed entry.
+     */
+    pub key: DatabaseKeyBuf,
+    /**
+    The path to the file of the changed entry.
+     */
+    pub file_path: PathBuf,
+    /**
+    The checksum of the file after the change. This is [`None`] for removed
+    entries.
+     */
+    pub checksum: Option<u32>,
+}
+
+/**
+The senders of all subscribers of a [`DatabaseManager`]. Clones of a manager
+share their subscribers.
+ */
+pub(crate) type Subscribers = Arc<Mutex<Vec<Sender<ChangeEvent>>>>;
+
+impl DatabaseManager {
+    /**
+    Returns a receiver for [`ChangeEvent`]s, which are sent whenever `self` (or
+    one of its clones) creates, overwrites or removes an entry file. This
+    includes linked entries written as part of [`DatabaseManager::write`] as
+    well as files modified by bulk operations such as
+    [`DatabaseManager::merge_from`]. Writes which fail don't generate events.
+
+    The events are buffered until they are received, so the receiver should
+    be drained regularly (e.g. in a separate thread). Dropping the receiver
+    ends the subscription.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let events = dbm.subscribe();
+    std::thread::spawn(move || {
+        for event in events {
+            println!("{:?} {}", event.kind, event.key);
+        }
+    });
+    ```
+     */
+    pub fn subscribe(&self) -> Receiver<ChangeEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sender);
+        return receiver;
+    }
+
+    /**
+    Returns `true` if events need to be generated. Subscribers whose receiver
+    has been dropped are only detected when the next event is sent.
+     */
+    pub(crate) fn has_subscribers(&self) -> bool {
+        return !self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty();
+    }
+
+    /**
+    Sends a [`ChangeEvent`] for the entry file at `file_path` to all
+    subscribers. Files which are not database entries are ignored.
+     */
+    pub(crate) fn notify(&self, kind: ChangeKind, file_path: &Path) {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if subscribers.is_empty() {
+            return;
+        }
+        let Some(type_name) = self.type_folder_of(file_path) else {
+            return;
+        };
+        let Some(name) = file_path
+            .file_name()
+            .and_then(|file_name| self.entry_name(type_name, file_name))
+        else {
+            return;
+        };
+        let event = ChangeEvent {
+            kind,
+            key: DatabaseKeyBuf::new(type_name, name),
+            file_path: file_path.to_path_buf(),
+            checksum: match kind {
+                ChangeKind::Removed => None,
+                ChangeKind::Created | ChangeKind::Overwritten => self.file_checksum(file_path),
+            },
+        };
+
+        // Subscribers whose receiver has been dropped are removed
+        subscribers.retain(|sender| sender.send(event.clone()).is_ok());
+    }
+
+    /**
+    Like [`DatabaseManager::notify`] for a written file, which existed before
+    if `existed` is `true`.
+     */
+    pub(crate) fn notify_written(&self, file_path: &Path, existed: bool) {
+        let kind = if existed {
+            ChangeKind::Overwritten
+        } else {
+            ChangeKind::Created
+        };
+        self.notify(kind, file_path);
+    }
+}
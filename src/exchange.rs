@@ -0,0 +1,1376 @@
+/*!
+This module contains functionality to import files into and export entries out
+of a database:
+- [`DatabaseManager::adopt`] imports an existing file (e.g. created by another
+tool) as a database entry.
+- [`DatabaseManager::export_flat`] exports all root entries with their links
+resolved and inlined, [`DatabaseManager::export_entry_flat`] does the same for
+a single entry. [`DatabaseManager::import_exploded`] reverses the latter and
+writes a flat document into the database with its links split off again.
+- [`DatabaseManager::export_jsonl`] and [`DatabaseManager::import_jsonl`]
+stream all entries of a type as [JSON Lines](https://jsonlines.org/) (requires
+the `serde_json` feature).
+- [`DatabaseManager::export_csv`] writes selected scalar fields of all entries
+of a type into a CSV table.
+- [`DatabaseManager::transcode_all`] converts the whole database into another
+[`Format`](crate::Format) without deserializing the entries into their concrete
+types.
+- [`DatabaseManager::export_parquet`] converts all entries of a type into a
+columnar [Parquet](https://parquet.apache.org/) file (requires the `parquet`
+feature).
+- [`DatabaseManager::fork`] creates a copy of the whole database which shares
+the unchanged files with the original database via hard links.
+ */
+
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "serde_json")]
+use std::io::BufRead;
+
+#[cfg(feature = "serde_json")]
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::database_manager::replace_file;
+use crate::{
+    DatabaseEntry, DatabaseKeyBuf, DatabaseManager, DatabaseReport, FileStatus, Format, LinkTarget,
+    Problem, ReadOptions, Value, WriteOptions,
+};
+
+/**
+Specifies how links are treated when exporting database entries, e.g. via
+[`DatabaseManager::export_jsonl`].
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkHandling {
+    #[default]
+    /**
+    Links are resolved and replaced by the linked entries, so the exported data
+    is self-contained.
+     */
+    Resolve,
+    /**
+    Links are exported as they are stored in the database (name and checksum).
+    Importing such data requires the linked entries to be present in the
+    target database.
+     */
+    Preserve,
+}
+
+/**
+Options to modify the behaviour of [`DatabaseManager::adopt`]. See the
+individual fields for details.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct AdoptOptions {
+    /**
+    If `true`, the file is moved into the database. Otherwise, it is copied and
+    the original file is left untouched.
+
+    Defaults to `false`.
+     */
+    pub move_file: bool,
+    /**
+    If `true`, an existing database entry with the same key is overwritten.
+    Otherwise, adopting fails with an error of kind
+    [`ErrorKind::AlreadyExists`].
+
+    Defaults to `false`.
+     */
+    pub overwrite: bool,
+    /**
+    If `true`, the checksums in all links within the adopted file are set to
+    the checksums of the (already existing) linked entries. This is useful if
+    the file has been created by a tool which doesn't know about checksums.
+    Links which cannot be resolved unambiguously are left untouched.
+
+    Defaults to `false`.
+     */
+    pub refresh_checksums: bool,
+}
+
+impl DatabaseManager {
+    /**
+    Adopts the existing file at `path` as the database entry with the given
+    `type_name` and `name`. The file is copied (or moved, see
+    [`AdoptOptions::move_file`]) to the location derived from the key via
+    [`DatabaseManager::full_path`], i.e. it ends up in the correct type folder
+    with the file extension of `self`, regardless of its original name.
+
+    Before anything is copied, the file is validated: It must be parseable by
+    the [`Format`](crate::Format) of `self` (see
+    [`Format::deserialize_value`](crate::Format::deserialize_value)) and
+    contain a single entry tagged with `type_name`, as written by
+    [`DatabaseManager::write`]. On success, the path of the adopted file and
+    its checksum are returned.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let mut adopt_options = AdoptOptions::default();
+    adopt_options.refresh_checksums = true;
+    let (path, checksum) = dbm
+        .adopt("/tmp/export/cotton.yml", "Material", "pure_cotton", &adopt_options)
+        .expect("file is a valid Material entry");
+    ```
+     */
+    pub fn adopt<P: AsRef<Path>, A: AsRef<OsStr>, B: AsRef<OsStr>>(
+        &mut self,
+        path: P,
+        type_name: A,
+        name: B,
+        adopt_options: &AdoptOptions,
+    ) -> std::io::Result<(PathBuf, u32)> {
+        let source = path.as_ref();
+        let type_name = type_name.as_ref();
+        let target = self.full_path_unchecked((type_name, name.as_ref()));
+
+        // Validate the file
+        let bytes = fs::read(source).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Could not read file {}: {}", source.display(), err),
+            )
+        })?;
+        let format = self.format_for_type(type_name);
+        let mut value = format.deserialize_value(&bytes).map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Could not parse file {}: {}", source.display(), err),
+            )
+        })?;
+        let tag = match &value {
+            Value::Map(entries) if entries.len() == 1 => entries[0].0.as_str(),
+            _ => None,
+        };
+        if tag.map(OsStr::new) != Some(type_name) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "File {} does not contain a database entry of type {}",
+                    source.display(),
+                    type_name.to_string_lossy()
+                ),
+            ));
+        }
+
+        if target.exists() && !adopt_options.overwrite {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Database entry {} already exists", target.display()),
+            ));
+        }
+        if let Some(folder) = target.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        let existed = target.exists();
+        if existed {
+            // Replace the file instead of modifying it, since it might be
+            // shared with another database via a hard link (see DatabaseManager::fork)
+            fs::remove_file(&target)?;
+        }
+
+        // Update the checksums within the links, if requested
+        let mut modified = false;
+        if adopt_options.refresh_checksums {
+            let type_folders = self.type_folders()?;
+            modified = value.for_each_link_mut(&mut |link| {
+                let LinkTarget::Resolved(linked) = self.resolve_link(link, &type_folders) else {
+                    return false;
+                };
+                let path = self.full_path_unchecked(&linked);
+                if link.algorithm.checksum_file(&path) == link.checksum {
+                    return false;
+                }
+                link.checksum = self.checksum_algorithm.checksum_file(&path);
+                link.algorithm = self.checksum_algorithm;
+                return true;
+            });
+        }
+
+        if modified || self.encodes_file(&target) {
+            let bytes = if modified {
+                format
+                    .serialize_value(&value)
+                    .map_err(|err| Error::new(ErrorKind::InvalidData, err))?
+            } else {
+                bytes
+            };
+            fs::write(&target, self.encode_file(&target, bytes)?)?;
+            if adopt_options.move_file {
+                fs::remove_file(source)?;
+            }
+        } else if adopt_options.move_file {
+            // Renaming fails across file systems, fall back to copying
+            if fs::rename(source, &target).is_err() {
+                fs::copy(source, &target)?;
+                fs::remove_file(source)?;
+            }
+        } else {
+            fs::copy(source, &target)?;
+        }
+
+        self.sign_file(&target)?;
+        self.notify_written(&target, existed);
+
+        let checksum = self
+            .checksum_algorithm
+            .checksum_file(&target)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("Could not read adopted file {}", target.display()),
+                )
+            })?;
+        return Ok((target, checksum));
+    }
+
+    /**
+    Exports all root entries of the database fully materialized into the
+    directory `dir`: All links are resolved and replaced by the contents of the
+    linked entries (recursively), as if the entries had been written with
+    [`WriteMode::Flat`](crate::WriteMode::Flat). The result can be used by
+    consumers which are not able to resolve links.
+
+    A root entry is an entry which is not linked by any other entry of the
+    database. Every root entry is written to its own file; the layout of `dir`
+    is the same as the one of the database (`dir/type_name/name` plus the file
+    extension of `self`). Therefore, `dir` can be opened as a database itself.
+
+    Links are resolved as described in [`DatabaseManager::verify_entry`]. Root
+    entries which can't be materialized (e.g. because of a dangling link or an
+    unparseable linked file) are skipped and the reason is reported in the
+    [`FlatExport::report`]. Checksum mismatches are ignored.
+
+    Links written with [`LinkStyle::Short`](crate::LinkStyle::Short) can't be
+    told apart from ordinary string fields without knowing the types of the
+    entries. They are therefore not inlined, but stay plain strings in the
+    exported files, and the linked entries are exported as root entries. Use
+    [`DatabaseManager::export_entry_flat`], which resolves links via the type
+    of the entry, for databases containing short links.
+
+    Entries of types which are encrypted in `self` (requires the `encryption`
+    feature) are encrypted in `dir` as well and, if `self` has a signing key
+    (see [`signature`](crate::signature)), the exported files are signed with
+    it. Every file is replaced atomically.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let export = dbm.export_flat("/path/to/export").expect("export directory is writable");
+    if !export.report.is_clean() {
+        eprintln!("{}", export.report);
+    }
+    ```
+     */
+    pub fn export_flat<P: AsRef<Path>>(&self, dir: P) -> std::io::Result<FlatExport> {
+        let dir = dir.as_ref();
+        let inliner = Inliner::new(self)?;
+        let target = self.export_target(dir, self.format.clone())?;
+        let mut export = FlatExport::default();
+
+        // Find out which entries are linked by others
+        let mut linked: HashSet<DatabaseKeyBuf> = HashSet::new();
+        for value in inliner.values.values() {
+            for link in value.links() {
+                match self.resolve_link(&link, &inliner.type_folders) {
+                    LinkTarget::Resolved(target) => {
+                        linked.insert(target);
+                    }
+                    LinkTarget::Ambiguous(candidates) => linked.extend(candidates),
+                    LinkTarget::Dangling => (),
+                }
+            }
+        }
+
+        for key in inliner.keys.iter().filter(|key| !linked.contains(*key)) {
+            let result = inliner.inline_entry(key);
+            match result {
+                Ok(value) => {
+                    let bytes = self
+                        .format_for_type(&key.type_name)
+                        .serialize_value(&value)
+                        .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+                    let path = self.full_path_unchecked(key);
+                    let path = dir.join(path.strip_prefix(self.dir()).unwrap_or(&path));
+                    target.write_exported_file(&path, bytes)?;
+                    export.written_files.push(path);
+                }
+                Err(problem) => {
+                    if !export.report.problems.contains(&problem) {
+                        export.report.push(problem);
+                    }
+                }
+            }
+        }
+        return Ok(export);
+    }
+
+    /**
+    Reads the entry `name` of the type `T`, resolves all of its links
+    (recursively) and writes it as a single self-contained document without
+    any links into `writer`, as if it had been written with
+    [`WriteMode::Flat`](crate::WriteMode::Flat). The document is serialized
+    with the [`Format`] of the type (see [`DatabaseManager::format_for_type`])
+    and is neither compressed nor encrypted. This allows handing entries to
+    tools which are not able to resolve links.
+
+    Like an entry file, the document is tagged with the type name (e.g.
+    `Material: {...}` in YAML), so it can be imported again via
+    [`DatabaseManager::import_exploded`]. Tools which deserialize the document
+    directly therefore need to unwrap this tag, e.g. by deserializing it into
+    a map from the type name to the entry.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.export_entry_flat::<Material, _, _>("pure_cotton", std::io::stdout())
+        .expect("entry exists");
+    ```
+     */
+    pub fn export_entry_flat<T: DatabaseEntry, O: AsRef<OsStr>, W: Write>(
+        &mut self,
+        name: O,
+        mut writer: W,
+    ) -> std::io::Result<()> {
+        let instance: T = self.read(name)?;
+        writer.write_all(&self.to_bytes_flat(&instance)?)?;
+        return writer.flush();
+    }
+
+    /**
+    Parses the flat document read from `reader` as an entry of the type `T`
+    and writes it into the database like [`DatabaseManager::write`], i.e. all
+    fields annotated as links are split off into their own files again. This
+    is the counterpart to [`DatabaseManager::export_entry_flat`]. Returns the
+    path of the file of the root entry.
+
+    The document is deserialized with the [`Format`] of the type (see
+    [`DatabaseManager::format_for_type`]) and has the same layout as an entry
+    file, i.e. it is tagged with the type name. Links which are already present in
+    the document are resolved against the database. An error of kind
+    [`ErrorKind::InvalidData`] is returned if the document can't be parsed.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let document = "Material:\n  name: pure_cotton\n  cotton_content: 1.0\n";
+    let path = dbm
+        .import_exploded::<Material, _>(document.as_bytes(), &WriteOptions::default())
+        .expect("document is valid");
+    assert!(path.ends_with("Material/pure_cotton.yaml"));
+    ```
+     */
+    pub fn import_exploded<T: DatabaseEntry, R: Read>(
+        &mut self,
+        mut reader: R,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<PathBuf> {
+        let mut document = Vec::new();
+        reader.read_to_end(&mut document)?;
+        let (instance, _) = self.with_read_context(false, &ReadOptions::default(), |context| {
+            // SAFETY: The context only exists during this call, see DatabaseManager::from_str.
+            let dbm = unsafe { &*context.database_manager };
+            let entry = dbm
+                .format_for_type(T::folder_name())
+                .deserialize_dyn(&document)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+            return (entry as Box<dyn Any>)
+                .downcast::<T>()
+                .map(|entry| *entry)
+                .map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Document is not an entry of the type {}", T::folder_name()),
+                    )
+                });
+        })?;
+        return self.write(&instance, write_options);
+    }
+
+    /**
+    Converts the whole database into a new database in `dir` which uses
+    `format` instead of the [`Format`] of `self`. The entries are not
+    deserialized into their concrete types, hence this works without knowing
+    (or compiling in) the types stored in the database.
+
+    Every entry is parsed into an untyped [`Value`] by
+    [`Format::deserialize_value`] of `self` and written with
+    [`Format::serialize_value`] of `format`, so both formats need to support
+    untyped values. The conversion is not streaming: Every entry is held in
+    memory completely as a [`Value`] before it is written. A streaming
+    conversion (e.g. via `serde_transcode`) would need the concrete
+    deserializer and serializer of the formats, which the [`Format`] trait
+    objects don't provide. For the same reason, there is no parameter for the
+    source format: The entries are read with the formats of `self`, including
+    the ones set per type via [`DatabaseManager::set_format_for_type`].
+
+    The layout of `dir` is the same as the one of the database
+    (`dir/type_name/name` plus the file extension of `format`). Files which
+    are not database entries are not converted.
+
+    Links are kept as they are, except for their checksums: Because the
+    converted files differ from the original ones, a checksum which matches
+    the linked file in `self` is updated to match the converted file. Outdated
+    checksums stay outdated (see
+    [`Problem::ChecksumMismatch`](crate::Problem::ChecksumMismatch)).
+
+    After each converted entry, `progress` is called with a
+    [`TranscodeProgress`]. Entries which can't be parsed are skipped and
+    reported in [`Transcode::report`]. `dir` must not be the directory of
+    `self`.
+
+    Like with [`DatabaseManager::export_flat`], encrypted types stay encrypted
+    in `dir` and the converted files are signed if `self` has a signing key.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    # #[cfg(feature = "serde_json")]
+    # {
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let transcode = dbm
+        .transcode_all("/path/to/json_db", SerdeJson, |progress| {
+            println!("{}/{}: {}", progress.converted, progress.total, progress.key);
+        })
+        .expect("target directory is writable");
+    assert!(transcode.report.is_clean());
+    # }
+    ```
+     */
+    pub fn transcode_all<P, F, C>(
+        &self,
+        dir: P,
+        format: F,
+        mut progress: C,
+    ) -> std::io::Result<Transcode>
+    where
+        P: AsRef<Path>,
+        F: Format + 'static,
+        C: FnMut(TranscodeProgress),
+    {
+        if let (Ok(source), Ok(target)) = (self.dir().canonicalize(), dir.as_ref().canonicalize())
+            && source == target
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "A database can't be transcoded into its own directory",
+            ));
+        }
+        let target = self.export_target(dir.as_ref(), Box::new(format))?;
+        let type_folders = self.type_folders()?;
+        let keys = self.entry_keys()?;
+        let mut transcode = Transcode::default();
+
+        // Links whose checksum matches the linked file of self
+        let mut valid_links: HashSet<(DatabaseKeyBuf, String)> = HashSet::new();
+        let mut converted_keys: Vec<DatabaseKeyBuf> = Vec::new();
+
+        for (index, key) in keys.iter().enumerate() {
+            let value = match self.read_value(key) {
+                Ok(value) => value,
+                Err(status) => {
+                    let path = self.full_path_unchecked(key);
+                    match status {
+                        FileStatus::Unreadable(message) => {
+                            transcode.report.push(Problem::Unreadable { path, message })
+                        }
+                        FileStatus::Unparseable(message) => transcode
+                            .report
+                            .push(Problem::Unparseable { path, message }),
+                        FileStatus::Missing | FileStatus::Valid => (),
+                    }
+                    continue;
+                }
+            };
+            for link in value.links() {
+                if let Some(checksum_in_link) = link.checksum
+                    && let LinkTarget::Resolved(linked) = self.resolve_link(&link, &type_folders)
+                    && link
+                        .algorithm
+                        .checksum_file(&self.full_path_unchecked(&linked))
+                        == Some(checksum_in_link)
+                {
+                    valid_links.insert((key.clone(), link.name));
+                }
+            }
+
+            let bytes = target
+                .format
+                .serialize_value(&value)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+            let path = target.full_path_unchecked(key);
+            target.write_exported_file(&path, bytes)?;
+            transcode.written_files.push(path);
+            converted_keys.push(key.clone());
+
+            progress(TranscodeProgress {
+                key,
+                converted: index + 1,
+                total: keys.len(),
+            });
+        }
+
+        target.refresh_link_checksums_where(&converted_keys, |key, link| {
+            return valid_links.contains(&(key.clone(), link.name.clone()));
+        })?;
+        return Ok(transcode);
+    }
+
+    /**
+    Creates the manager which writes the files of an export of `self` into
+    `dir` (see [`DatabaseManager::export_flat`] and
+    [`DatabaseManager::transcode_all`]). It encrypts the same types as `self`
+    and signs the written files with the signing key of `self`, if any.
+     */
+    fn export_target(
+        &self,
+        dir: &Path,
+        format: Box<dyn Format>,
+    ) -> std::io::Result<DatabaseManager> {
+        fs::create_dir_all(dir).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Could not create directory {}", dir.display()),
+            )
+        })?;
+        #[cfg_attr(
+            not(any(feature = "encryption", feature = "signatures")),
+            allow(unused_mut)
+        )]
+        let mut target = DatabaseManager::open_with_boxed_format(dir, format)?;
+        #[cfg(feature = "encryption")]
+        {
+            target.encryption = self.encryption.clone();
+        }
+        #[cfg(feature = "signatures")]
+        {
+            target.signatures = self.signatures.clone();
+        }
+        return Ok(target);
+    }
+
+    /**
+    Writes the serialized entry `bytes` of an export into the file at `path`:
+    The bytes are encoded (see [`DatabaseManager::encode_file`]), the file is
+    replaced atomically and signed afterwards.
+     */
+    fn write_exported_file(&self, path: &Path, bytes: Vec<u8>) -> std::io::Result<()> {
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        let bytes = self.encode_file(path, bytes)?;
+        replace_file(path, &bytes).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Could not write file {}: {}", path.display(), err),
+            )
+        })?;
+        return self.sign_file(path);
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Creates a new database in the directory `dir` which contains the current
+    state of `self` and returns its manager. `dir` must either not exist or be
+    empty.
+
+    Instead of copying the database files, hard links to the files of `self`
+    are created, so even large databases are forked quickly and without
+    duplicating the data. If a hard link can't be created (e.g. because `dir`
+    is on another file system), the file is copied instead. Since this crate
+    always replaces a file when writing it (instead of modifying its
+    contents), changes in one of the databases don't affect the other one.
+    This does not hold for external tools which modify files in place.
+
+    Lock files (see [`lock`](crate::lock)) and temporary files are not
+    forked. The returned manager uses the same [`Format`] as `self` and
+    starts with a clone of its [`Cache`](crate::Cache), but doesn't share any
+    locks with `self`.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let mut scenario = dbm.fork("/path/to/scenario").expect("directory is empty");
+    scenario.remove(("Material", "pure_cotton")).expect("entry exists");
+    assert!(dbm.exists(("Material", "pure_cotton")));
+    ```
+     */
+    pub fn fork<P: AsRef<Path>>(&self, dir: P) -> std::io::Result<DatabaseManager> {
+        let dir = dir.as_ref();
+        if dir.exists() && fs::read_dir(dir)?.next().is_some() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Directory {} is not empty", dir.display()),
+            ));
+        }
+        fs::create_dir_all(dir)?;
+        link_dir(self.dir(), dir)?;
+
+        let mut fork = DatabaseManager::open_with_boxed_format(dir, self.format.clone())?;
+        fork.cache = self.cache.clone();
+        fork.type_formats = self.type_formats.clone();
+        fork.write_profiles = self.write_profiles.clone();
+        fork.field_aliases = self.field_aliases.clone();
+        fork.migrations = self.migrations.clone();
+        fork.format_options = self.format_options.clone();
+        fork.sharding = self.sharding;
+        fork.set_preserve_unknown_fields(self.preserves_unknown_fields());
+        #[cfg(feature = "encryption")]
+        {
+            fork.encryption = self.encryption.clone();
+        }
+        #[cfg(feature = "signatures")]
+        {
+            fork.signatures = self.signatures.clone();
+        }
+        return Ok(fork);
+    }
+}
+
+/**
+Recursively recreates the contents of `source` within the existing directory
+`target` using hard links (or copies, if hard links are not possible). Lock
+files and temporary files are skipped.
+ */
+fn link_dir(source: &Path, target: &Path) -> std::io::Result<()> {
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let target_path = target.join(&file_name);
+        if entry.file_type()?.is_dir() {
+            fs::create_dir(&target_path)?;
+            link_dir(&entry.path(), &target_path)?;
+            continue;
+        }
+        let bytes = file_name.as_encoded_bytes();
+        if bytes.starts_with(b".serde_mosaic.")
+            || bytes.ends_with(b".lock")
+            || bytes.ends_with(b".tmp")
+        {
+            continue;
+        }
+        if fs::hard_link(entry.path(), &target_path).is_err() {
+            fs::copy(entry.path(), &target_path)?;
+        }
+    }
+    return Ok(());
+}
+
+impl DatabaseManager {
+    /**
+    Writes the given `fields` of all database entries within the type folder
+    `type_name` as a CSV table (comma-separated, RFC 4180 quoting) to `writer`.
+    The first row contains the field paths, followed by one row per entry
+    (sorted by name). Returns the number of written entries.
+
+    A field path consists of field names separated by dots, e.g.
+    `material.name`. Elements of sequences are accessed by their index, e.g.
+    `legs.0.id`. If a path traverses a link, the link is resolved and the path
+    continues within the linked entry (see
+    [`DatabaseManager::verify_entry`] for how links are resolved). The entries
+    are accessed as untyped [`Value`]s, hence no concrete type is needed. If
+    a link can't be resolved, the path continues within the link itself (i.e.
+    only `name` and `checksum` are available).
+
+    Fields which are missing or do not contain a scalar value (a string, a
+    number, a boolean or null) result in empty cells.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let mut csv = Vec::new();
+    dbm.export_csv("Shirt", &["owner", "size", "material.cotton_content"], &mut csv)
+        .expect("all shirts can be parsed");
+    ```
+     */
+    pub fn export_csv<O: AsRef<OsStr>, W: Write>(
+        &self,
+        type_name: O,
+        fields: &[&str],
+        mut writer: W,
+    ) -> std::io::Result<usize> {
+        fn write_row<W: Write>(writer: &mut W, cells: &[String]) -> std::io::Result<()> {
+            for (index, cell) in cells.iter().enumerate() {
+                if index > 0 {
+                    writer.write_all(b",")?;
+                }
+                if cell.contains([',', '"', '\n', '\r']) {
+                    write!(writer, "\"{}\"", cell.replace('"', "\"\""))?;
+                } else {
+                    writer.write_all(cell.as_bytes())?;
+                }
+            }
+            return writer.write_all(b"\r\n");
+        }
+
+        // =====================================================================
+
+        let type_name = type_name.as_ref();
+        let type_folders = self.type_folders()?;
+        let names = self.entry_names(type_name)?;
+
+        let header: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+        write_row(&mut writer, &header)?;
+
+        for name in names.iter() {
+            let key = DatabaseKeyBuf::new(type_name, name);
+            let contents = self
+                .read_value(&key)
+                .map_err(|status| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Could not export {}: {:?}",
+                            self.full_path_unchecked(&key).display(),
+                            status
+                        ),
+                    )
+                })?
+                .into_entry_contents()
+                .unwrap_or(Value::Null);
+
+            let row: Vec<String> = fields
+                .iter()
+                .map(|field| {
+                    let path: Vec<&str> = field.split('.').collect();
+                    match self.field_value(contents.clone(), &path, &type_folders) {
+                        Some(Value::Bool(val)) => val.to_string(),
+                        Some(Value::I64(val)) => val.to_string(),
+                        Some(Value::U64(val)) => val.to_string(),
+                        Some(Value::F64(val)) => val.to_string(),
+                        Some(Value::String(val)) => val,
+                        _ => String::new(),
+                    }
+                })
+                .collect();
+            write_row(&mut writer, &row)?;
+        }
+        writer.flush()?;
+        return Ok(names.len());
+    }
+
+    /**
+    Returns the value at `path` within `value`, resolving links along the way
+    (see [`DatabaseManager::export_csv`]).
+     */
+    fn field_value(
+        &self,
+        mut value: Value,
+        path: &[&str],
+        type_folders: &[OsString],
+    ) -> Option<Value> {
+        for segment in path.iter() {
+            // Continue within the linked entry. If the link can't be resolved,
+            // continue within the link itself.
+            if let Some(link) = value.as_link()
+                && let LinkTarget::Resolved(target) = self.resolve_link(&link, type_folders)
+                && let Some(contents) = self
+                    .read_value(&target)
+                    .ok()
+                    .and_then(Value::into_entry_contents)
+            {
+                value = contents;
+            }
+            value = match value {
+                Value::Seq(mut elements) => {
+                    let index: usize = segment.parse().ok()?;
+                    if index >= elements.len() {
+                        return None;
+                    }
+                    elements.swap_remove(index)
+                }
+                Value::Map(_) => value.get(segment)?.clone(),
+                _ => return None,
+            };
+        }
+        return Some(value);
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl DatabaseManager {
+    /**
+    Writes all database entries of type `T` to `writer` as
+    [JSON Lines](https://jsonlines.org/), i.e. one JSON object per line, sorted
+    by name. Returns the number of exported entries.
+
+    With [`LinkHandling::Resolve`], every entry is read via
+    [`DatabaseManager::read`] and the resulting `T` is serialized, i.e. all
+    links are replaced by the linked entries. With [`LinkHandling::Preserve`],
+    the files are converted without constructing `T`, hence links are kept as
+    they are (see [`Format::deserialize_value`](crate::Format::deserialize_value)).
+
+    This function is only available if the `serde_json` feature is enabled.
+    The database itself can use any [`Format`](crate::Format).
+
+    # Examples
+
+    ```no_run
+    use std::fs::File;
+    use std::io::BufWriter;
+    use std::ffi::OsStr;
+
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let writer = BufWriter::new(File::create("materials.jsonl").expect("file can be created"));
+    dbm.export_jsonl::<Material, _>(writer, LinkHandling::Resolve).expect("export succeeds");
+    ```
+     */
+    pub fn export_jsonl<T: DatabaseEntry + Serialize, W: Write>(
+        &mut self,
+        mut writer: W,
+        link_handling: LinkHandling,
+    ) -> std::io::Result<usize> {
+        let type_name = OsStr::new(T::folder_name());
+        let names = self.entry_names(type_name)?;
+        for name in names.iter() {
+            match link_handling {
+                LinkHandling::Resolve => {
+                    let instance: T = self.read(name)?;
+                    serde_json::to_writer(&mut writer, &instance)?;
+                }
+                LinkHandling::Preserve => {
+                    let key = DatabaseKeyBuf::new(type_name, name);
+                    let value = self.read_value(&key).map_err(|status| {
+                        Error::new(
+                            ErrorKind::InvalidData,
+                            format!(
+                                "Could not export {}: {:?}",
+                                self.full_path_unchecked(&key).display(),
+                                status
+                            ),
+                        )
+                    })?;
+                    let contents = value.into_entry_contents().unwrap_or(Value::Null);
+                    serde_json::to_writer(&mut writer, &contents)?;
+                }
+            }
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        return Ok(names.len());
+    }
+
+    /**
+    Reads [JSON Lines](https://jsonlines.org/) from `reader` (e.g. created by
+    [`DatabaseManager::export_jsonl`]), deserializes every non-empty line into
+    a `T` and writes it into the database using the given `write_options`.
+    Returns the paths of the written files in the order of the lines.
+
+    Links within the lines (see [`LinkHandling::Preserve`]) are resolved using
+    the database, hence the linked entries must exist. If a line can't be
+    deserialized or written, the import is aborted and the error message
+    contains the line number. Entries imported before the error are kept.
+
+    This function is only available if the `serde_json` feature is enabled.
+     */
+    pub fn import_jsonl<T: DatabaseEntry + DeserializeOwned, R: BufRead>(
+        &mut self,
+        reader: R,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let add_line_number =
+                |err: Error| Error::new(err.kind(), format!("line {}: {}", index + 1, err));
+            let instance: T = self
+                .with_read_context(false, &ReadOptions::default(), |_| {
+                    serde_json::from_str(&line)
+                        .map_err(|err| Error::new(ErrorKind::InvalidData, err))
+                })
+                .map_err(add_line_number)?
+                .0;
+            paths.push(
+                self.write(&instance, write_options)
+                    .map_err(add_line_number)?,
+            );
+        }
+        return Ok(paths);
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl DatabaseManager {
+    /**
+    Converts all database entries of type `T` into a
+    [Parquet](https://parquet.apache.org/) file at `path`, which can then be
+    analyzed with e.g. pandas or Polars. Returns the number of exported
+    entries.
+
+    Every entry becomes a row. The entries are accessed as untyped [`Value`]s
+    with all links resolved and inlined (see [`DatabaseManager::export_flat`]).
+    Only the entries of `T` and the entries linked by them are parsed, the
+    rest of the database is not read. Nested maps and sequences are flattened into columns whose names are the
+    field paths, e.g. `material.name` or `legs.0.id` (see
+    [`DatabaseManager::export_csv`]). Missing fields result in null values.
+
+    The data type of a column is inferred from its values: booleans, integers,
+    floating point numbers (also used for a mix of integers and floats) or
+    strings (used for all other combinations).
+
+    If an entry can't be parsed or one of its links can't be resolved, an
+    error of kind [`ErrorKind::InvalidData`] is returned and no file is
+    written.
+
+    This function is only available if the `parquet` feature is enabled.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.export_parquet::<Material, _>("materials.parquet").expect("export succeeds");
+    ```
+     */
+    pub fn export_parquet<T: crate::DatabaseEntry, P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> std::io::Result<usize> {
+        use std::sync::Arc;
+
+        use arrow_array::{ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch};
+        use arrow_array::{RecordBatchOptions, StringArray};
+        use arrow_schema::{DataType, Field, Schema};
+        use parquet::arrow::ArrowWriter;
+
+        fn flatten(value: Value, prefix: String, row: &mut HashMap<String, Value>) {
+            let join = |key: &str| {
+                if prefix.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", prefix, key)
+                }
+            };
+            match value {
+                Value::Map(entries) => {
+                    for (key, value) in entries {
+                        let key = match key.as_str() {
+                            Some(key) => join(key),
+                            None => join(&format!("{:?}", key)),
+                        };
+                        flatten(value, key, row);
+                    }
+                }
+                Value::Seq(elements) => {
+                    for (index, value) in elements.into_iter().enumerate() {
+                        flatten(value, join(&index.to_string()), row);
+                    }
+                }
+                scalar => {
+                    let key = if prefix.is_empty() {
+                        "value".to_string()
+                    } else {
+                        prefix
+                    };
+                    row.insert(key, scalar);
+                }
+            }
+        }
+
+        // =====================================================================
+
+        let type_name = OsStr::new(T::folder_name());
+        let keys: Vec<DatabaseKeyBuf> = self
+            .entry_names(type_name)?
+            .iter()
+            .map(|name| DatabaseKeyBuf::new(type_name, name))
+            .collect();
+        let inliner = Inliner::for_entries(self, &keys)?;
+
+        // Flatten all entries and collect the column names in order of
+        // appearance
+        let mut columns: Vec<String> = Vec::new();
+        let mut rows: Vec<HashMap<String, Value>> = Vec::new();
+        for key in keys {
+            let value = inliner
+                .inline_entry(&key)
+                .map_err(|problem| Error::new(ErrorKind::InvalidData, problem.to_string()))?;
+            let mut row = HashMap::new();
+            flatten(
+                value.into_entry_contents().unwrap_or(Value::Null),
+                String::new(),
+                &mut row,
+            );
+            let mut new_columns: Vec<&String> =
+                row.keys().filter(|key| !columns.contains(*key)).collect();
+            new_columns.sort();
+            columns.extend(new_columns.into_iter().cloned());
+            rows.push(row);
+        }
+
+        // Infer the data type of each column and create the arrays
+        let mut fields = Vec::new();
+        let mut arrays: Vec<ArrayRef> = Vec::new();
+        for column in columns.iter() {
+            let cells: Vec<Option<&Value>> = rows
+                .iter()
+                .map(|row| row.get(column).filter(|value| **value != Value::Null))
+                .collect();
+            let values = || cells.iter().flatten();
+            let data_type = if values().all(|value| matches!(value, Value::Bool(_))) {
+                DataType::Boolean
+            } else if values().all(|value| matches!(value, Value::I64(_))) {
+                DataType::Int64
+            } else if values()
+                .all(|value| matches!(value, Value::I64(_) | Value::U64(_) | Value::F64(_)))
+            {
+                DataType::Float64
+            } else {
+                DataType::Utf8
+            };
+
+            let array: ArrayRef =
+                match data_type {
+                    DataType::Boolean => Arc::new(BooleanArray::from_iter(cells.iter().map(
+                        |cell| match cell {
+                            Some(Value::Bool(val)) => Some(*val),
+                            _ => None,
+                        },
+                    ))),
+                    DataType::Int64 => {
+                        Arc::new(Int64Array::from_iter(cells.iter().map(|cell| match cell {
+                            Some(Value::I64(val)) => Some(*val),
+                            _ => None,
+                        })))
+                    }
+                    DataType::Float64 => Arc::new(Float64Array::from_iter(cells.iter().map(
+                        |cell| match cell {
+                            Some(Value::I64(val)) => Some(*val as f64),
+                            Some(Value::U64(val)) => Some(*val as f64),
+                            Some(Value::F64(val)) => Some(*val),
+                            _ => None,
+                        },
+                    ))),
+                    _ => Arc::new(StringArray::from_iter(cells.iter().map(
+                        |cell| match cell {
+                            Some(Value::String(val)) => Some(val.clone()),
+                            Some(Value::Bool(val)) => Some(val.to_string()),
+                            Some(Value::I64(val)) => Some(val.to_string()),
+                            Some(Value::U64(val)) => Some(val.to_string()),
+                            Some(Value::F64(val)) => Some(val.to_string()),
+                            Some(Value::Bytes(val)) => {
+                                Some(String::from_utf8_lossy(val).into_owned())
+                            }
+                            _ => None,
+                        },
+                    ))),
+                };
+            fields.push(Field::new(column, data_type, true));
+            arrays.push(array);
+        }
+
+        let schema = Arc::new(Schema::new(fields));
+        let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+        let batch = RecordBatch::try_new_with_options(schema.clone(), arrays, &options)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+
+        let file = fs::File::create(path.as_ref()).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Could not create file {}: {}", path.as_ref().display(), err),
+            )
+        })?;
+        let mut writer = ArrowWriter::try_new(file, schema, None).map_err(Error::other)?;
+        writer.write(&batch).map_err(Error::other)?;
+        writer.close().map_err(Error::other)?;
+        return Ok(rows.len());
+    }
+}
+
+/**
+This struct is returned by [`DatabaseManager::export_flat`] and contains
+information about the export within its fields.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct FlatExport {
+    /**
+    Paths of all files which have been written.
+     */
+    pub written_files: Vec<PathBuf>,
+    /**
+    Problems which prevented the export of root entries. Every skipped root
+    entry results in (at least) one problem.
+     */
+    pub report: DatabaseReport,
+}
+
+/**
+The result of [`DatabaseManager::transcode_all`].
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Transcode {
+    /**
+    Paths of all files which have been written.
+     */
+    pub written_files: Vec<PathBuf>,
+    /**
+    Problems which prevented the conversion of entries. Every skipped entry
+    results in one problem.
+     */
+    pub report: DatabaseReport,
+}
+
+/**
+Passed to the `progress` callback of [`DatabaseManager::transcode_all`] after
+each converted entry.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct TranscodeProgress<'a> {
+    /**
+    The key of the entry which has just been converted.
+     */
+    pub key: &'a DatabaseKeyBuf,
+    /**
+    The number of entries which have been processed so far (including skipped
+    entries).
+     */
+    pub converted: usize,
+    /**
+    The total number of entries in the database.
+     */
+    pub total: usize,
+}
+
+/**
+Helper for [`DatabaseManager::export_flat`] which replaces links by the
+contents of the linked entries.
+ */
+pub(crate) struct Inliner<'a> {
+    dbm: &'a DatabaseManager,
+    type_folders: Vec<OsString>,
+    keys: Vec<DatabaseKeyBuf>,
+    values: HashMap<DatabaseKeyBuf, Value>,
+    invalid: HashMap<DatabaseKeyBuf, Problem>,
+}
+
+impl<'a> Inliner<'a> {
+    /**
+    Parses all entries of the database.
+     */
+    pub(crate) fn new(dbm: &'a DatabaseManager) -> std::io::Result<Self> {
+        let mut inliner = Inliner {
+            dbm,
+            type_folders: dbm.type_folders()?,
+            keys: dbm.entry_keys()?,
+            values: HashMap::new(),
+            invalid: HashMap::new(),
+        };
+        for key in inliner.keys.clone() {
+            inliner.parse(key);
+        }
+        return Ok(inliner);
+    }
+
+    /**
+    Like [`Inliner::new`], but only parses the entries `keys` and the entries
+    they link (transitively).
+     */
+    #[cfg(any(feature = "server", feature = "parquet"))]
+    pub(crate) fn for_entries(
+        dbm: &'a DatabaseManager,
+        keys: &[DatabaseKeyBuf],
+    ) -> std::io::Result<Self> {
+        let mut inliner = Inliner {
+            dbm,
+            type_folders: dbm.type_folders()?,
+            keys: Vec::new(),
+            values: HashMap::new(),
+            invalid: HashMap::new(),
+        };
+        let mut pending = keys.to_vec();
+        while let Some(key) = pending.pop() {
+            if inliner.keys.contains(&key) {
+                continue;
+            }
+            inliner.keys.push(key.clone());
+            let links = inliner.parse(key).map(Value::links).unwrap_or_default();
+            for link in links {
+                if let LinkTarget::Resolved(target) = dbm.resolve_link(&link, &inliner.type_folders)
+                {
+                    pending.push(target);
+                }
+            }
+        }
+        return Ok(inliner);
+    }
+
+    /**
+    Parses the entry `key` and stores either its value or the reason why it
+    couldn't be parsed.
+     */
+    fn parse(&mut self, key: DatabaseKeyBuf) -> Option<&Value> {
+        match self.dbm.read_value(&key) {
+            Ok(value) => return Some(self.values.entry(key).or_insert(value)),
+            Err(status) => {
+                let path = self.dbm.full_path_unchecked(&key);
+                let problem = match status {
+                    FileStatus::Unreadable(message) => Problem::Unreadable { path, message },
+                    FileStatus::Unparseable(message) => Problem::Unparseable { path, message },
+                    FileStatus::Missing | FileStatus::Valid => return None,
+                };
+                self.invalid.insert(key, problem);
+                return None;
+            }
+        }
+    }
+
+    /**
+    Returns the entry `key` with all links inlined.
+     */
+    pub(crate) fn inline_entry(&self, key: &DatabaseKeyBuf) -> Result<Value, Problem> {
+        match self.values.get(key) {
+            Some(value) => return self.inline(value.clone(), &mut vec![key.clone()]),
+            None => return Err(self.problem_of(key.clone())),
+        }
+    }
+
+    /**
+    Recursively inlines all links of `value`, which is the parsed file of the
+    last key in `stack`. The stack is used to detect cyclic links.
+     */
+    fn inline(&self, mut value: Value, stack: &mut Vec<DatabaseKeyBuf>) -> Result<Value, Problem> {
+        let path = self
+            .dbm
+            .full_path_unchecked(stack.last().expect("stack contains the inlined key"));
+        value.try_for_each_link_value(&mut |slot, link| {
+            let target = match self.dbm.resolve_link(&link, &self.type_folders) {
+                LinkTarget::Resolved(target) => target,
+                LinkTarget::Dangling => {
+                    return Err(Problem::DanglingLink {
+                        path: path.clone(),
+                        link: link.name,
+                    });
+                }
+                LinkTarget::Ambiguous(candidates) => {
+                    return Err(Problem::AmbiguousLink {
+                        path: path.clone(),
+                        link: link.name,
+                        candidates,
+                    });
+                }
+            };
+            if stack.contains(&target) {
+                return Err(Problem::CyclicLink {
+                    path: path.clone(),
+                    link: link.name,
+                });
+            }
+            let Some(linked_value) = self.values.get(&target) else {
+                return Err(self.problem_of(target));
+            };
+
+            stack.push(target);
+            let inlined = self.inline(linked_value.clone(), stack)?;
+            stack.pop();
+
+            *slot = inlined.into_entry_contents().unwrap_or(Value::Null);
+            return Ok(true);
+        })?;
+        return Ok(value);
+    }
+
+    /**
+    Returns the problem of an entry which couldn't be parsed.
+     */
+    fn problem_of(&self, key: DatabaseKeyBuf) -> Problem {
+        match self.invalid.get(&key) {
+            Some(problem) => return problem.clone(),
+            None => {
+                let path = self.dbm.full_path_unchecked(&key);
+                return Problem::Missing { key, path };
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+/*!
+This module contains the expiry of database entries, see
+[`DatabaseManager::write_with_expiry`].
+
+An entry can be given an expiry timestamp, which is stored as a companion file
+next to the entry file, with `.expires` appended to the file name (e.g.
+`Token/abc.yaml.expires`). The companion file contains the expiry timestamp as
+seconds since the Unix epoch. Once this timestamp has passed, the entry is
+treated as absent: Reading it fails with [`ErrorKind::NotFound`],
+[`DatabaseManager::exists`] returns `false` and it is omitted from listings
+(e.g. of a [`DatabaseServer`](crate::DatabaseServer)). Writing an entry whose
+predecessor has expired creates a new entry without an expiry.
+
+Expired files stay on disk until they are removed by
+[`DatabaseManager::prune_expired`], which can e.g. be called periodically or
+when the application starts.
+ */
+
+use std::ffi::OsString;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::database_manager::replace_file;
+use crate::{DatabaseEntry, DatabaseKey, DatabaseKeyBuf, DatabaseManager, WriteOptions};
+
+impl DatabaseManager {
+    /**
+    Like [`DatabaseManager::write`], but the written entry expires at
+    `expires_at`. Linked entries written by this call don't expire. If the
+    file of the entry already existed and was kept (see
+    [`NameCollisions::KeepExisting`](crate::NameCollisions::KeepExisting)),
+    its expiry is not changed.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use std::time::{Duration, SystemTime};
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Token {
+        id: String,
+        user: String,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Token {
+        fn name(&self) -> &OsStr {
+            self.id.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let token = Token { id: "abc".into(), user: "joe".into() };
+    let expires_at = SystemTime::now() + Duration::from_secs(3600);
+    dbm.write_with_expiry(&token, expires_at, &WriteOptions::default())
+        .expect("writing succeeds");
+
+    // An hour later, the token is gone
+    assert!(dbm.read::<Token, _>("abc").is_err());
+    dbm.prune_expired().expect("database is writable");
+    ```
+     */
+    pub fn write_with_expiry<T: DatabaseEntry>(
+        &mut self,
+        instance: &T,
+        expires_at: SystemTime,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<PathBuf> {
+        let (file_path, write_info) = self.write_verbose(instance, write_options)?;
+        if !write_info.kept_files.contains(&file_path) {
+            write_expiry(&file_path, expires_at)?;
+        }
+        return Ok(file_path);
+    }
+
+    /**
+    Sets the expiry timestamp of the entry `key`. If `expires_at` is [`None`],
+    the entry doesn't expire anymore. Returns an error of kind
+    [`ErrorKind::NotFound`] if the entry doesn't exist (or has already
+    expired).
+     */
+    pub fn set_expiry<'a, K: Into<DatabaseKey<'a>>>(
+        &mut self,
+        key: K,
+        expires_at: Option<SystemTime>,
+    ) -> std::io::Result<()> {
+        let key: DatabaseKey = key.into();
+        if !self.exists(key) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("No entry {}", DatabaseKeyBuf::from(key)),
+            ));
+        }
+        let file_path = self.full_path_unchecked(key);
+        self.check_database_lock()?;
+        self.check_lock(&file_path)?;
+        match expires_at {
+            Some(expires_at) => return write_expiry(&file_path, expires_at),
+            None => return remove_expiry(&file_path),
+        }
+    }
+
+    /**
+    Returns the expiry timestamp of the entry `key` or [`None`] if the entry
+    doesn't expire. The timestamp is returned even if it has already passed.
+     */
+    pub fn expiry<'a, K: Into<DatabaseKey<'a>>>(&self, key: K) -> Option<SystemTime> {
+        return read_expiry(&self.full_path_unchecked(key));
+    }
+
+    /**
+    Removes all expired entries (and their companion files) from the database
+    and returns their keys in alphabetical order. Entries which are locked by
+    another manager (see [`DatabaseManager::lock`]) are skipped.
+     */
+    pub fn prune_expired(&mut self) -> std::io::Result<Vec<DatabaseKeyBuf>> {
+        self.check_database_lock()?;
+        let mut pruned = Vec::new();
+        for type_name in self.type_folders()? {
+            let mut expired = Vec::new();
+            for path in self.type_folder_files(&type_name)? {
+                let Some(file_path) = strip_expiry_suffix(&path) else {
+                    continue;
+                };
+                let Some(name) = file_path
+                    .file_name()
+                    .and_then(|file_name| self.entry_name(&type_name, file_name))
+                else {
+                    continue;
+                };
+                if self.is_expired_file(&file_path) && self.check_lock(&file_path).is_ok() {
+                    expired.push(name);
+                }
+            }
+            expired.sort();
+            for name in expired {
+                let key = DatabaseKeyBuf::new(type_name.clone(), name);
+                self.remove(&key)?;
+                // Companion files of entries whose file is already gone
+                remove_expiry(&self.full_path_unchecked(&key))?;
+                pruned.push(key);
+            }
+        }
+        return Ok(pruned);
+    }
+
+    /**
+    Returns `true` if the entry file at `path` has expired. Staged files (see
+    [`DatabaseManager::enable_staging`]) never expire, since they replace the
+    file on disk.
+     */
+    pub(crate) fn is_expired_file(&self, path: &Path) -> bool {
+        return !self.is_staged(path)
+            && read_expiry(path).is_some_and(|expires_at| expires_at <= SystemTime::now());
+    }
+
+    /**
+    Removes the expiry of the entry file at `path` if it has expired. This is
+    called after a new file has been written in place of an expired one.
+     */
+    pub(crate) fn clear_expired(&self, path: &Path) -> std::io::Result<()> {
+        if read_expiry(path).is_some_and(|expires_at| expires_at <= SystemTime::now()) {
+            return remove_expiry(path);
+        }
+        return Ok(());
+    }
+
+    /**
+    Returns the path of the expiry companion file of the entry file at `path`.
+     */
+    pub(crate) fn expiry_path(path: &Path) -> PathBuf {
+        let mut expiry_path: OsString = path.as_os_str().to_os_string();
+        expiry_path.push(".expires");
+        return PathBuf::from(expiry_path);
+    }
+}
+
+fn read_expiry(path: &Path) -> Option<SystemTime> {
+    let seconds = fs::read_to_string(DatabaseManager::expiry_path(path)).ok()?;
+    let seconds: u64 = seconds.trim().parse().ok()?;
+    return UNIX_EPOCH.checked_add(Duration::from_secs(seconds));
+}
+
+fn write_expiry(path: &Path, expires_at: SystemTime) -> std::io::Result<()> {
+    let seconds = expires_at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    return replace_file(
+        &DatabaseManager::expiry_path(path),
+        seconds.to_string().as_bytes(),
+    );
+}
+
+fn remove_expiry(path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(DatabaseManager::expiry_path(path)) {
+        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+        _ => return Ok(()),
+    }
+}
+
+/**
+Returns the path of the entry file belonging to the expiry companion file at
+`path` or [`None`] if `path` is not an expiry companion file.
+ */
+fn strip_expiry_suffix(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+    return Some(path.with_file_name(file_name.strip_suffix(".expires")?));
+}
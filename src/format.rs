@@ -5,10 +5,17 @@ This module contains the [`Format`] trait, which is used by a
 
 Additionally, it also contains the following predefined implementors of
 [`Format`]:
+- [`Bincode`]
+- [`Postcard`]
 - [`SerdeJson`]
+- [`SerdeRon`]
+- [`SerdeToml`]
 - [`SerdeYaml`]
+- [`SerdeYamlPreserving`]
 */
 
+#[cfg(feature = "serde_yaml")]
+use std::collections::HashMap;
 use std::error::Error;
 use std::ffi::OsStr;
 
@@ -16,7 +23,7 @@ use dyn_clone::DynClone;
 
 use serde::de::DeserializeOwned;
 
-use crate::DatabaseEntry;
+use crate::{DatabaseEntry, FormatOptions, Value};
 
 /**
 A trait defining the serialization / deserialization strategy used by a
@@ -37,9 +44,14 @@ implementations for the predefined types are also very simple (6 LoC per type)
 and can be used as examples.
 
 Because a [`DatabaseManager`](crate::DatabaseManager) must be cloneable, any
-implementor of this trait must implement [`Clone`] as well.
+implementor of this trait must implement [`Clone`] as well. Furthermore, links
+may be resolved from multiple threads at once (see
+[`ReadContextHandle`](crate::ReadContextHandle)), hence implementors must also
+be [`Send`] and [`Sync`]. These bounds have been added in version 0.3.0, which
+is a breaking change for formats holding non-thread-safe state (e.g. an `Rc`
+or a `RefCell`). Such state can be wrapped in an `Arc` or a `Mutex` instead.
  */
-pub trait Format: DynClone + std::any::Any {
+pub trait Format: DynClone + std::any::Any + Send + Sync {
     /**
     Returns the file extension used within the database. This extension is added
     to any files created by the [`DatabaseManager`](crate::DatabaseManager) and
@@ -181,6 +193,92 @@ pub trait Format: DynClone + std::any::Any {
     ) -> Result<T, Box<dyn Error + Send + Sync>>
     where
         Self: Sized;
+
+    /**
+    Serializes an untyped [`Value`] into a serialized bytes representation.
+
+    This function is used by the [`DatabaseManager`](crate::DatabaseManager)
+    for operations which need to modify a database file without knowing the
+    concrete type stored within it (e.g. updating the checksum of a link, see
+    [`ChecksumMismatchPolicy::Heal`](crate::ChecksumMismatchPolicy::Heal)).
+    Implementing it is optional - the default implementation returns an error,
+    which disables those operations for the format.
+     */
+    fn serialize_value(&self, value: &Value) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let _ = value;
+        return Err(unsupported_value_conversion(self.file_ext()));
+    }
+
+    /**
+    Deserializes an untyped [`Value`] from a serialized bytes representation.
+    This is the counterpart to [`Format::serialize_value`], see its docstring.
+    The default implementation returns an error.
+     */
+    fn deserialize_value(&self, bytes: &[u8]) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let _ = bytes;
+        return Err(unsupported_value_conversion(self.file_ext()));
+    }
+
+    /**
+    Like [`Format::serialize_value`], but lays out the serialized
+    representation according to `format_options` (see
+    [`DatabaseManager::set_format_options`](crate::DatabaseManager::set_format_options)).
+    Implementing it is optional - the default implementation ignores the
+    options and calls [`Format::serialize_value`].
+     */
+    fn serialize_value_formatted(
+        &self,
+        value: &Value,
+        format_options: &FormatOptions,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let _ = format_options;
+        return self.serialize_value(value);
+    }
+
+    /**
+    Merges the serialized representation `bytes` of an entry into the
+    `existing` contents of its file before the file is overwritten. If
+    [`Some`] is returned, the contained bytes are written instead of `bytes`.
+
+    This allows formats to keep parts of a file which are not represented in
+    the serialized entry, such as comments (see [`SerdeYamlPreserving`]). The
+    default implementation returns [`None`]. If an error is returned, the file
+    is overwritten with `bytes`.
+     */
+    fn merge_existing(
+        &self,
+        existing: &[u8],
+        bytes: &[u8],
+    ) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+        let _ = (existing, bytes);
+        return Ok(None);
+    }
+
+    /**
+    Returns `false` if the format is not self-describing, i.e. if serialized
+    data can only be deserialized when its type is known in advance (e.g.
+    [`Bincode`] and [`Postcard`]). A linked field contains either the entry
+    itself or a link, which such a format can't tell apart. Therefore, the
+    functions of the [`attributes`](crate::attributes) module wrap the contents
+    of linked fields in a tagged envelope (an enum with the variants `Entity`
+    and `Link`) if this method returns `false`. The default implementation
+    returns `true`.
+
+    Links of a non-self-describing format can only be resolved by a
+    [`DatabaseManager`](crate::DatabaseManager), since the envelope is only
+    written and expected while the manager serializes / deserializes an entry.
+     */
+    fn is_self_describing(&self) -> bool {
+        return true;
+    }
+}
+
+fn unsupported_value_conversion(file_ext: &OsStr) -> Box<dyn Error + Send + Sync> {
+    return format!(
+        "the format with file extension \"{}\" does not support untyped values",
+        file_ext.to_string_lossy()
+    )
+    .into();
 }
 
 dyn_clone::clone_trait_object!(Format);
@@ -229,6 +327,383 @@ impl Format for SerdeYaml {
         let value = serde_yaml::from_str(str)?;
         return Ok(value);
     }
+
+    fn serialize_value(&self, value: &Value) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let value = serde_yaml::to_string(value)?;
+        return Ok(value.into_bytes());
+    }
+
+    fn deserialize_value(&self, bytes: &[u8]) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let str = std::str::from_utf8(bytes)?;
+        let value = serde_yaml::from_str(str)?;
+        return Ok(value);
+    }
+    fn serialize_value_formatted(
+        &self,
+        value: &Value,
+        format_options: &FormatOptions,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        return Ok(crate::formatting::to_yaml(value, format_options)?.into_bytes());
+    }
+}
+
+/**
+A variant of [`SerdeYaml`] which preserves comments and the order of keys when
+an existing file is overwritten (see [`Format::merge_existing`]). Reading is
+identical to [`SerdeYaml`], so both formats can be used for the same database.
+
+When an entry is written to an existing file, the keys of all maps within the
+new entry are sorted according to their order in the existing file (new keys
+are appended). Afterwards, the comments of the existing file are transferred:
+Full-line comments are placed in front of the same key as before, trailing
+comments are appended to the line of the same key and comments at the end of
+the file stay at the end. Comments in front of removed keys are moved to the
+next remaining key. Keys are identified by their path within the
+document, elements of sequences by their index.
+
+Since the existing file is patched line by line, this works best with block
+style YAML as written by [`SerdeYaml`]. Comments within multi-line scalars and
+flow style collections are not preserved.
+
+# Examples
+
+```
+use std::ffi::OsStr;
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Solder {
+    name: String,
+    density: f64,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Solder {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+let dir = std::env::temp_dir().join("serde_mosaic_yaml_preserving_example");
+let mut dbm = DatabaseManager::new(&dir, SerdeYamlPreserving).expect("directory can be created");
+std::fs::create_dir_all(dir.join("Solder")).expect("folder can be created");
+std::fs::write(
+    dir.join("Solder/tin.yaml"),
+    "Solder:\n  # In kg/m³\n  density: 7300.0 # Measured\n  name: tin\n",
+).expect("file can be written");
+
+let mut tin: Solder = dbm.read("tin").expect("entry exists");
+tin.density = 7280.0;
+let mut write_options = WriteOptions::default();
+write_options.name_collisions = NameCollisions::Overwrite;
+dbm.write(&tin, &write_options).expect("writing succeeds");
+
+let contents = std::fs::read_to_string(dir.join("Solder/tin.yaml")).expect("file exists");
+assert!(contents.contains("Solder:\n  # In kg/m³\n  density: 7280.0 # Measured\n  name: tin\n"));
+```
+ */
+#[cfg(feature = "serde_yaml")]
+#[derive(Clone, Copy, Debug)]
+pub struct SerdeYamlPreserving;
+
+#[cfg(feature = "serde_yaml")]
+impl Format for SerdeYamlPreserving {
+    fn file_ext(&self) -> &OsStr {
+        return SerdeYaml.file_ext();
+    }
+
+    fn serialize_dyn(
+        &self,
+        value: &dyn DatabaseEntry,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        return SerdeYaml.serialize_dyn(value);
+    }
+
+    fn deserialize_dyn(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Box<dyn DatabaseEntry>, Box<dyn Error + Send + Sync>> {
+        return SerdeYaml.deserialize_dyn(bytes);
+    }
+
+    fn deserialize<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        return SerdeYaml.deserialize(bytes);
+    }
+
+    fn serialize_value(&self, value: &Value) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        return SerdeYaml.serialize_value(value);
+    }
+
+    fn deserialize_value(&self, bytes: &[u8]) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        return SerdeYaml.deserialize_value(bytes);
+    }
+
+    fn serialize_value_formatted(
+        &self,
+        value: &Value,
+        format_options: &FormatOptions,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        return SerdeYaml.serialize_value_formatted(value, format_options);
+    }
+
+    fn merge_existing(
+        &self,
+        existing: &[u8],
+        bytes: &[u8],
+    ) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+        // Restore the key order of the existing file
+        let existing_value = self.deserialize_value(existing)?;
+        let mut value = self.deserialize_value(bytes)?;
+        let sorted = if sort_like(&mut value, &existing_value) {
+            Some(self.serialize_value(&value)?)
+        } else {
+            None
+        };
+
+        // Transfer the comments
+        let existing = std::str::from_utf8(existing)?;
+        let new = std::str::from_utf8(sorted.as_deref().unwrap_or(bytes))?;
+        return Ok(Some(transfer_yaml_comments(existing, new).into_bytes()));
+    }
+}
+
+/**
+Sorts the keys of all maps within `value` according to their order within the
+corresponding maps of `template` (keys which are not contained in `template`
+are moved to the end). Returns whether any key was moved.
+ */
+#[cfg(feature = "serde_yaml")]
+fn sort_like(value: &mut Value, template: &Value) -> bool {
+    match (value, template) {
+        (Value::Map(entries), Value::Map(template_entries)) => {
+            let position = |key: &Value| {
+                template_entries
+                    .iter()
+                    .position(|(template_key, _)| template_key == key)
+                    .unwrap_or(usize::MAX)
+            };
+            let mut moved = !entries.is_sorted_by_key(|(key, _)| position(key));
+            entries.sort_by_key(|(key, _)| position(key));
+            for (key, element) in entries.iter_mut() {
+                if let Some((_, template_element)) = template_entries
+                    .iter()
+                    .find(|(template_key, _)| template_key == key)
+                {
+                    moved |= sort_like(element, template_element);
+                }
+            }
+            return moved;
+        }
+        (Value::Seq(elements), Value::Seq(template_elements)) => {
+            let mut moved = false;
+            for (element, template_element) in elements.iter_mut().zip(template_elements.iter()) {
+                moved |= sort_like(element, template_element);
+            }
+            return moved;
+        }
+        _ => return false,
+    }
+}
+
+/**
+Copies the comments of the YAML document `existing` into the YAML document
+`new`, see [`SerdeYamlPreserving`].
+ */
+#[cfg(feature = "serde_yaml")]
+fn transfer_yaml_comments(existing: &str, new: &str) -> String {
+    let new_paths = yaml_line_paths(new);
+
+    // Assign the full-line comments to the following key. Comments of keys
+    // which don't exist anymore are carried over to the next existing key.
+    let mut leading: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut trailing: HashMap<&str, &str> = HashMap::new();
+    let mut pending: Vec<&str> = Vec::new();
+    let existing_paths = yaml_line_paths(existing);
+    for (line, path) in existing.lines().zip(existing_paths.iter()) {
+        if line.trim_start().starts_with('#') {
+            pending.push(line);
+            continue;
+        }
+        let Some(path) = path else {
+            continue;
+        };
+        if !new_paths.contains(&Some(path.clone())) {
+            continue;
+        }
+        if let Some(comment) = trailing_comment(line) {
+            trailing.insert(path, comment);
+        }
+        if !pending.is_empty() {
+            leading.entry(path).or_default().append(&mut pending);
+        }
+    }
+
+    let mut output = String::with_capacity(existing.len().max(new.len()));
+    for (line, path) in new.lines().zip(new_paths.iter()) {
+        if let Some(path) = path {
+            if let Some(comments) = leading.remove(path.as_str()) {
+                for comment in comments {
+                    output.push_str(comment);
+                    output.push('\n');
+                }
+            }
+            output.push_str(line);
+            if let Some(comment) = trailing.get(path.as_str())
+                && trailing_comment(line).is_none()
+            {
+                output.push(' ');
+                output.push_str(comment);
+            }
+        } else {
+            output.push_str(line);
+        }
+        output.push('\n');
+    }
+
+    // Comments at the end of the document
+    for comment in pending {
+        output.push_str(comment);
+        output.push('\n');
+    }
+    return output;
+}
+
+/**
+Returns the path of the key (or sequence element) defined in each line of the
+YAML document `document`, e.g. `Shirt/material/name` or `Shirt/sizes/0`.
+Lines which don't define a key (e.g. comments, blank lines or continuations
+of multi-line scalars) are [`None`].
+ */
+#[cfg(feature = "serde_yaml")]
+fn yaml_line_paths(document: &str) -> Vec<Option<String>> {
+    struct Node {
+        indent: usize,
+        path: String,
+        opens_block: bool,
+        elements: usize,
+    }
+
+    let mut root = Node {
+        indent: 0,
+        path: String::new(),
+        opens_block: true,
+        elements: 0,
+    };
+    let mut stack: Vec<Node> = Vec::new();
+    let mut paths = Vec::new();
+    for line in document.lines() {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            paths.push(None);
+            continue;
+        }
+        if content.starts_with("---") || content.starts_with("...") {
+            paths.push(Some(content[..3].to_string()));
+            continue;
+        }
+        let indent = line.len() - content.len();
+        let is_element = content == "-" || content.starts_with("- ");
+
+        // Sequences may have the same indentation as their key
+        while let Some(node) = stack.last()
+            && (node.indent > indent
+                || (node.indent == indent && !(is_element && node.opens_block)))
+        {
+            stack.pop();
+        }
+        let parent = match stack.last_mut() {
+            Some(node) => node,
+            None => &mut root,
+        };
+
+        if is_element {
+            let path = format!("{}/{}", parent.path, parent.elements);
+            parent.elements += 1;
+            let rest = content[1..].trim_start();
+            let element_indent = indent + (content.len() - rest.len());
+            stack.push(Node {
+                indent,
+                path: path.clone(),
+                opens_block: false,
+                elements: 0,
+            });
+            match yaml_key(rest) {
+                Some((key, opens_block)) => {
+                    let key_path = format!("{}/{}", path, key);
+                    stack.push(Node {
+                        indent: element_indent,
+                        path: key_path.clone(),
+                        opens_block,
+                        elements: 0,
+                    });
+                    paths.push(Some(key_path));
+                }
+                None => paths.push(Some(path)),
+            }
+        } else {
+            match yaml_key(content) {
+                Some((key, opens_block)) => {
+                    let path = format!("{}/{}", parent.path, key);
+                    stack.push(Node {
+                        indent,
+                        path: path.clone(),
+                        opens_block,
+                        elements: 0,
+                    });
+                    paths.push(Some(path));
+                }
+                None => paths.push(None),
+            }
+        }
+    }
+    return paths;
+}
+
+/**
+If the line content `content` starts with a (plain) map key, returns the key
+and whether the value is a block on the following lines.
+ */
+#[cfg(feature = "serde_yaml")]
+fn yaml_key(content: &str) -> Option<(&str, bool)> {
+    let content = match trailing_comment(content) {
+        Some(comment) => content[..content.len() - comment.len()].trim_end(),
+        None => content,
+    };
+    if let Some(key) = content.strip_suffix(':') {
+        return Some((key.trim_end(), true));
+    }
+    let (key, value) = content.split_once(": ")?;
+    if key.starts_with(['"', '\'', '{', '[']) {
+        return None;
+    }
+    return Some((key.trim_end(), value.trim().is_empty()));
+}
+
+/**
+Returns the trailing comment (starting with `#`) of `line`, if any. Hash signs
+within quoted strings or not preceded by whitespace are not comments.
+ */
+#[cfg(feature = "serde_yaml")]
+fn trailing_comment(line: &str) -> Option<&str> {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (index, char) in line.char_indices() {
+        match quote {
+            Some(open) if char == open => quote = None,
+            Some(_) => (),
+            None if char == '"' || char == '\'' => quote = Some(char),
+            None if char == '#' && previous.is_whitespace() && !line[..index].trim().is_empty() => {
+                return Some(&line[index..]);
+            }
+            None => (),
+        }
+        previous = char;
+    }
+    return None;
 }
 
 /**
@@ -275,4 +750,228 @@ impl Format for SerdeJson {
         let value = serde_json::from_str(str)?;
         return Ok(value);
     }
+
+    fn serialize_value(&self, value: &Value) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let value = serde_json::to_string(value)?;
+        return Ok(value.into_bytes());
+    }
+
+    fn deserialize_value(&self, bytes: &[u8]) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let str = std::str::from_utf8(bytes)?;
+        let value = serde_json::from_str(str)?;
+        return Ok(value);
+    }
+    fn serialize_value_formatted(
+        &self,
+        value: &Value,
+        format_options: &FormatOptions,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        return Ok(crate::formatting::to_json(value, format_options)?.into_bytes());
+    }
+}
+
+/**
+A [`Format`] which uses [`ron`] for its implementation of
+[`Format::serialize`] and [`Format::deserialize`]. The file extension is "ron".
+
+The entries are written in the pretty-printed form of the Rusty Object Notation,
+which keeps the names of enum variants and is pleasant to edit by hand. Links
+are written as structs (`(name: "pure_cotton", checksum: Some(1234114))`) or,
+with [`LinkStyle::Short`](crate::LinkStyle::Short), as strings.
+
+Untyped [`Value`]s are not supported, since RON distinguishes between `Some(x)`
+and `x`, which is lost in a [`Value`]. This disables the operations relying on
+them (e.g. [`ChecksumMismatchPolicy::Heal`](crate::ChecksumMismatchPolicy::Heal)).
+ */
+#[cfg(feature = "ron")]
+#[derive(Clone, Copy, Debug)]
+pub struct SerdeRon;
+
+#[cfg(feature = "ron")]
+impl Format for SerdeRon {
+    fn file_ext(&self) -> &OsStr {
+        return OsStr::new("ron");
+    }
+
+    fn serialize_dyn(
+        &self,
+        value: &dyn DatabaseEntry,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let value = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+        return Ok(value.into_bytes());
+    }
+
+    fn deserialize_dyn(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Box<dyn DatabaseEntry>, Box<dyn Error + Send + Sync>> {
+        let str = std::str::from_utf8(bytes)?;
+        let value = ron::from_str(str)?;
+        return Ok(value);
+    }
+
+    fn deserialize<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        let str = std::str::from_utf8(bytes)?;
+        let value = ron::from_str(str)?;
+        return Ok(value);
+    }
+}
+
+/**
+A [`Format`] which uses [`toml`] for its implementation of
+[`Format::serialize`] and [`Format::deserialize`]. The file extension is "toml".
+
+This format is well suited for entries which are edited by hand, e.g.
+configuration files. Each entry is stored as a table named after its type
+(e.g. `[Shirt]`). Links are stored as subtables containing the keys `name` and
+`checksum` (e.g. `[Shirt.material]`) or, with
+[`LinkStyle::Short`](crate::LinkStyle::Short), as strings.
+
+TOML has no representation for "no value", so fields which are [`None`] are
+omitted when serializing. Since `serde` only treats a missing field as [`None`]
+if it doesn't have a custom deserialization function, fields annotated with
+e.g. [`deserialize_opt_link`](crate::deserialize_opt_link) also need the
+`#[serde(default)]` attribute to be read from this format.
+ */
+#[cfg(feature = "toml")]
+#[derive(Clone, Copy, Debug)]
+pub struct SerdeToml;
+
+#[cfg(feature = "toml")]
+impl Format for SerdeToml {
+    fn file_ext(&self) -> &OsStr {
+        return OsStr::new("toml");
+    }
+
+    fn serialize_dyn(
+        &self,
+        value: &dyn DatabaseEntry,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let value = toml::to_string(value)?;
+        return Ok(value.into_bytes());
+    }
+
+    fn deserialize_dyn(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Box<dyn DatabaseEntry>, Box<dyn Error + Send + Sync>> {
+        let str = std::str::from_utf8(bytes)?;
+        let value = toml::from_str(str)?;
+        return Ok(value);
+    }
+
+    fn deserialize<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        let str = std::str::from_utf8(bytes)?;
+        let value = toml::from_str(str)?;
+        return Ok(value);
+    }
+
+    fn serialize_value(&self, value: &Value) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let value = toml::to_string(value)?;
+        return Ok(value.into_bytes());
+    }
+
+    fn deserialize_value(&self, bytes: &[u8]) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let str = std::str::from_utf8(bytes)?;
+        let value = toml::from_str(str)?;
+        return Ok(value);
+    }
+}
+
+/**
+A [`Format`] which uses [`bincode`] for its implementation of
+[`Format::serialize`] and [`Format::deserialize`]. The file extension is
+"bincode".
+
+The files are compact and fast to read, but not human-readable. Since
+[`bincode`] is not self-describing, links are written in a tagged envelope (see
+[`Format::is_self_describing`]) and untyped [`Value`]s are not supported, which
+disables the operations relying on them (e.g.
+[`ChecksumMismatchPolicy::Heal`](crate::ChecksumMismatchPolicy::Heal)).
+ */
+#[cfg(feature = "bincode")]
+#[derive(Clone, Copy, Debug)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl Format for Bincode {
+    fn file_ext(&self) -> &OsStr {
+        return OsStr::new("bincode");
+    }
+
+    fn serialize_dyn(
+        &self,
+        value: &dyn DatabaseEntry,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        return Ok(bincode::serialize(value)?);
+    }
+
+    fn deserialize_dyn(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Box<dyn DatabaseEntry>, Box<dyn Error + Send + Sync>> {
+        return Ok(bincode::deserialize(bytes)?);
+    }
+
+    fn deserialize<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        return Ok(bincode::deserialize(bytes)?);
+    }
+
+    fn is_self_describing(&self) -> bool {
+        return false;
+    }
+}
+
+/**
+A [`Format`] which uses [`postcard`] for its implementation of
+[`Format::serialize`] and [`Format::deserialize`]. The file extension is
+"postcard".
+
+[`postcard`] produces even smaller files than [`Bincode`] (e.g. integers are
+varint-encoded), which makes it a good fit for embedded devices. Like
+[`Bincode`], it is not self-describing, so the same limitations apply.
+ */
+#[cfg(feature = "postcard")]
+#[derive(Clone, Copy, Debug)]
+pub struct Postcard;
+
+#[cfg(feature = "postcard")]
+impl Format for Postcard {
+    fn file_ext(&self) -> &OsStr {
+        return OsStr::new("postcard");
+    }
+
+    fn serialize_dyn(
+        &self,
+        value: &dyn DatabaseEntry,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        return Ok(postcard::to_allocvec(value)?);
+    }
+
+    fn deserialize_dyn(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Box<dyn DatabaseEntry>, Box<dyn Error + Send + Sync>> {
+        return Ok(postcard::from_bytes(bytes)?);
+    }
+
+    fn deserialize<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        return Ok(postcard::from_bytes(bytes)?);
+    }
+
+    fn is_self_describing(&self) -> bool {
+        return false;
+    }
 }
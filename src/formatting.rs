@@ -0,0 +1,599 @@
+/*!
+This module contains the [`FormatOptions`], which control the layout of the
+files written by a [`DatabaseManager`] (e.g. the indentation or the notation
+of floating point numbers). This allows matching the conventions of
+hand-edited files, so that programmatic edits result in minimal diffs.
+
+The options are configured on the manager via
+[`DatabaseManager::set_format_options`] and applied by the
+[`Format`](crate::Format) via
+[`Format::serialize_value_formatted`](crate::Format::serialize_value_formatted).
+The predefined YAML and JSON formats support all options; other formats ignore
+them by default.
+ */
+
+use std::ffi::OsStr;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use crate::{DatabaseManager, Value};
+
+/**
+Layout options for the files written by a [`DatabaseManager`], see the module
+docstring. Not all options are meaningful for every format, see the individual
+fields for details.
+
+# Examples
+
+```no_run
+use serde_mosaic::*;
+
+let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+let mut format_options = FormatOptions::default();
+format_options.indent = 4;
+format_options.float_precision = Some(3);
+format_options.quote_style = QuoteStyle::Double;
+format_options.line_width = Some(80);
+dbm.set_format_options(Some(format_options));
+```
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    /**
+    Number of spaces per indentation level. For JSON, an indentation of zero
+    writes the whole entry into a single line.
+
+    Defaults to 2.
+     */
+    pub indent: usize,
+    /**
+    Maximum number of digits after the decimal point of floating point numbers
+    (in scientific notation: of the mantissa). Trailing zeros are removed. If
+    [`None`], the shortest representation which reads back as the same number
+    is used.
+
+    Defaults to [`None`].
+     */
+    pub float_precision: Option<usize>,
+    /**
+    The notation of floating point numbers, see [`FloatNotation`].
+
+    Defaults to [`FloatNotation::Auto`].
+     */
+    pub float_notation: FloatNotation,
+    /**
+    The quoting of strings (YAML only, JSON strings are always double-quoted),
+    see [`QuoteStyle`]. Map keys are only quoted if necessary.
+
+    Defaults to [`QuoteStyle::Minimal`].
+     */
+    pub quote_style: QuoteStyle,
+    /**
+    If given, sequences of scalars are written into a single line (e.g.
+    `[1, 2, 3]`) if that line (including indentation and key) does not exceed
+    this number of characters. Otherwise, every element is written into its
+    own line.
+
+    Defaults to [`None`] (every element in its own line).
+     */
+    pub line_width: Option<usize>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            float_precision: None,
+            float_notation: FloatNotation::Auto,
+            quote_style: QuoteStyle::Minimal,
+            line_width: None,
+        }
+    }
+}
+
+/**
+The notation of floating point numbers, see [`FormatOptions::float_notation`].
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatNotation {
+    #[default]
+    /**
+    Very large and very small numbers are written in scientific notation, all
+    other numbers in decimal notation.
+     */
+    Auto,
+    /**
+    Always use decimal notation, e.g. `0.000015`.
+     */
+    Decimal,
+    /**
+    Always use scientific notation, e.g. `1.5e-5`.
+     */
+    Scientific,
+}
+
+/**
+The quoting of strings, see [`FormatOptions::quote_style`].
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    #[default]
+    /**
+    Strings are only quoted (with double quotes) if they would otherwise be
+    read as something else, e.g. `"true"` or `"1.5"`.
+     */
+    Minimal,
+    /**
+    All strings are enclosed in double quotes.
+     */
+    Double,
+    /**
+    All strings are enclosed in single quotes. Strings containing control
+    characters (e.g. line breaks) use double quotes, since they can't be
+    escaped within single quotes.
+     */
+    Single,
+}
+
+impl DatabaseManager {
+    /**
+    Sets the [`FormatOptions`] used for all files written by `self` from now
+    on. Passing [`None`] restores the default layout of the
+    [`Format`](crate::Format).
+    Existing files are not modified.
+     */
+    pub fn set_format_options(&mut self, format_options: Option<FormatOptions>) {
+        self.format_options = format_options;
+    }
+
+    /**
+    Returns the [`FormatOptions`] of `self`, if any.
+     */
+    pub fn format_options(&self) -> Option<&FormatOptions> {
+        return self.format_options.as_ref();
+    }
+
+    /**
+    Rewrites the serialized entry `bytes` of the type `type_name` according to
+    the [`FormatOptions`] of `self`. If no options are set, `bytes` are
+    returned unchanged.
+     */
+    pub(crate) fn apply_format_options(
+        &self,
+        type_name: &OsStr,
+        bytes: Vec<u8>,
+    ) -> std::io::Result<Vec<u8>> {
+        let Some(format_options) = &self.format_options else {
+            return Ok(bytes);
+        };
+        let format = self.format_for_type(type_name);
+        let value = format
+            .deserialize_value(&bytes)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        return format
+            .serialize_value_formatted(&value, format_options)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err));
+    }
+
+    /**
+    Serializes the untyped `value` which is written to the database file at
+    `path` according to the [`FormatOptions`] of `self` (if any).
+     */
+    pub(crate) fn serialize_value(&self, path: &Path, value: &Value) -> std::io::Result<Vec<u8>> {
+        let format = self.format_of_file(path);
+        let bytes = match &self.format_options {
+            Some(format_options) => format.serialize_value_formatted(value, format_options),
+            None => format.serialize_value(value),
+        };
+        return bytes.map_err(|err| Error::new(ErrorKind::InvalidData, err));
+    }
+}
+
+/**
+Formats the floating point number `number` according to `format_options`.
+Returns [`None`] for NaN and infinite numbers.
+ */
+#[cfg(any(feature = "serde_yaml", feature = "serde_json"))]
+fn format_float(number: f64, format_options: &FormatOptions) -> Option<String> {
+    if !number.is_finite() {
+        return None;
+    }
+    let scientific = match format_options.float_notation {
+        FloatNotation::Auto => number != 0.0 && !(1e-5..1e16).contains(&number.abs()),
+        FloatNotation::Decimal => false,
+        FloatNotation::Scientific => true,
+    };
+    let formatted = match (scientific, format_options.float_precision) {
+        (false, None) => format!("{}", number),
+        (false, Some(precision)) => format!("{:.*}", precision, number),
+        (true, None) => format!("{:e}", number),
+        (true, Some(precision)) => format!("{:.*e}", precision, number),
+    };
+
+    // Remove trailing zeros of the fraction, but keep at least one digit
+    // after the decimal point so the number is read as a float again.
+    let (mantissa, exponent) = match formatted.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa.to_string(), format!("e{}", exponent)),
+        None => (formatted, String::new()),
+    };
+    let mantissa = if mantissa.contains('.') {
+        let trimmed = mantissa.trim_end_matches('0');
+        if trimmed.ends_with('.') {
+            if exponent.is_empty() {
+                format!("{}0", trimmed)
+            } else {
+                trimmed.trim_end_matches('.').to_string()
+            }
+        } else {
+            trimmed.to_string()
+        }
+    } else if exponent.is_empty() {
+        format!("{}.0", mantissa)
+    } else {
+        mantissa
+    };
+    return Some(format!("{}{}", mantissa, exponent));
+}
+
+/**
+Returns `true` if `value` is written on a single line, i.e. if it is a scalar
+or an empty collection.
+ */
+#[cfg(any(feature = "serde_yaml", feature = "serde_json"))]
+fn is_inline(value: &Value) -> bool {
+    match value {
+        Value::Seq(elements) => return elements.is_empty(),
+        Value::Map(entries) => return entries.is_empty(),
+        _ => return true,
+    }
+}
+
+/**
+Writes the elements of the sequence `elements` in flow style (e.g. `[1, 2]`)
+if all of them are scalars and the resulting line (which already contains
+`prefix_len` characters) fits into the line width.
+ */
+#[cfg(any(feature = "serde_yaml", feature = "serde_json"))]
+fn flow_sequence<F>(
+    elements: &[Value],
+    prefix_len: usize,
+    format_options: &FormatOptions,
+    scalar: F,
+) -> Option<String>
+where
+    F: Fn(&Value) -> Option<String>,
+{
+    let line_width = format_options.line_width?;
+    let mut items = Vec::with_capacity(elements.len());
+    for element in elements.iter() {
+        if matches!(element, Value::Seq(_) | Value::Map(_)) {
+            return None;
+        }
+        items.push(scalar(element)?);
+    }
+    let flow = format!("[{}]", items.join(", "));
+    if prefix_len + flow.len() > line_width {
+        return None;
+    }
+    return Some(flow);
+}
+
+/**
+Writes `value` as a YAML document, see
+[`Format::serialize_value_formatted`](crate::Format::serialize_value_formatted).
+ */
+#[cfg(feature = "serde_yaml")]
+pub(crate) fn to_yaml(value: &Value, format_options: &FormatOptions) -> Result<String, String> {
+    let mut output = String::from("---");
+    if is_inline(value) {
+        output.push(' ');
+        output.push_str(&yaml_scalar(value, format_options)?);
+        output.push('\n');
+    } else {
+        output.push('\n');
+        write_yaml_block(value, 0, format_options, &mut output)?;
+    }
+    return Ok(output);
+}
+
+/**
+Writes the non-empty collection `value` in block style with the given
+`indent` to `output`.
+ */
+#[cfg(feature = "serde_yaml")]
+fn write_yaml_block(
+    value: &Value,
+    indent: usize,
+    format_options: &FormatOptions,
+    output: &mut String,
+) -> Result<(), String> {
+    match value {
+        Value::Map(entries) => {
+            for (key, element) in entries.iter() {
+                if !is_inline(key) {
+                    return Err("maps with collections as keys are not supported".into());
+                }
+                // Keys are only quoted if necessary
+                let key = match key {
+                    Value::String(key) => yaml_string(key, QuoteStyle::Minimal),
+                    key => yaml_scalar(key, format_options)?,
+                };
+                let line = format!("{}{}:", " ".repeat(indent), key);
+                write_yaml_entry(
+                    line,
+                    element,
+                    indent + format_options.indent,
+                    format_options,
+                    output,
+                )?;
+            }
+        }
+        Value::Seq(elements) => {
+            for element in elements.iter() {
+                if is_inline(element) {
+                    output.push_str(&" ".repeat(indent));
+                    output.push_str("- ");
+                    output.push_str(&yaml_scalar(element, format_options)?);
+                    output.push('\n');
+                } else {
+                    // Nested collections start on the line of the dash
+                    let mut nested = String::new();
+                    write_yaml_block(element, indent + 2, format_options, &mut nested)?;
+                    output.push_str(&" ".repeat(indent));
+                    output.push_str("- ");
+                    output.push_str(&nested[indent + 2..]);
+                }
+            }
+        }
+        _ => {
+            output.push_str(&" ".repeat(indent));
+            output.push_str(&yaml_scalar(value, format_options)?);
+            output.push('\n');
+        }
+    }
+    return Ok(());
+}
+
+/**
+Writes the map entry whose key is already contained in `line` to `output`.
+ */
+#[cfg(feature = "serde_yaml")]
+fn write_yaml_entry(
+    mut line: String,
+    element: &Value,
+    indent: usize,
+    format_options: &FormatOptions,
+    output: &mut String,
+) -> Result<(), String> {
+    if is_inline(element) {
+        line.push(' ');
+        line.push_str(&yaml_scalar(element, format_options)?);
+    } else if let Value::Seq(elements) = element
+        && let Some(flow) = flow_sequence(elements, line.len() + 1, format_options, |element| {
+            yaml_scalar(element, format_options).ok()
+        })
+    {
+        line.push(' ');
+        line.push_str(&flow);
+    } else {
+        output.push_str(&line);
+        output.push('\n');
+        return write_yaml_block(element, indent, format_options, output);
+    }
+    output.push_str(&line);
+    output.push('\n');
+    return Ok(());
+}
+
+/**
+Writes the scalar (or empty collection) `value` in YAML syntax.
+ */
+#[cfg(feature = "serde_yaml")]
+fn yaml_scalar(value: &Value, format_options: &FormatOptions) -> Result<String, String> {
+    match value {
+        Value::Null => return Ok("~".into()),
+        Value::Bool(val) => return Ok(val.to_string()),
+        Value::I64(val) => return Ok(val.to_string()),
+        Value::U64(val) => return Ok(val.to_string()),
+        Value::F64(val) => {
+            return Ok(format_float(*val, format_options).unwrap_or_else(|| {
+                if val.is_nan() {
+                    ".nan".into()
+                } else if *val > 0.0 {
+                    ".inf".into()
+                } else {
+                    "-.inf".into()
+                }
+            }));
+        }
+        Value::String(string) => return Ok(yaml_string(string, format_options.quote_style)),
+        Value::Bytes(bytes) => {
+            let bytes: Vec<String> = bytes.iter().map(u8::to_string).collect();
+            return Ok(format!("[{}]", bytes.join(", ")));
+        }
+        Value::Seq(elements) if elements.is_empty() => return Ok("[]".into()),
+        Value::Map(entries) if entries.is_empty() => return Ok("{}".into()),
+        _ => return Err("collections can't be written as scalars".into()),
+    }
+}
+
+/**
+Quotes `string` according to `quote_style`, if necessary.
+ */
+#[cfg(feature = "serde_yaml")]
+fn yaml_string(string: &str, quote_style: QuoteStyle) -> String {
+    let has_control = string.chars().any(char::is_control);
+    match quote_style {
+        QuoteStyle::Single if !has_control => return format!("'{}'", string.replace('\'', "''")),
+        QuoteStyle::Minimal if !has_control && is_plain_yaml(string) => return string.into(),
+        _ => (),
+    }
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+    for char in string.chars() {
+        match char {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            char if char.is_control() => quoted.push_str(&format!("\\u{:04x}", char as u32)),
+            char => quoted.push(char),
+        }
+    }
+    quoted.push('"');
+    return quoted;
+}
+
+/**
+Returns `true` if `string` can be written as a plain (unquoted) YAML scalar
+without being read as something else.
+ */
+#[cfg(feature = "serde_yaml")]
+fn is_plain_yaml(string: &str) -> bool {
+    const RESERVED: [&str; 14] = [
+        "~", "null", "true", "false", "yes", "no", "on", "off", "y", "n", ".nan", ".inf", "-.inf",
+        "+.inf",
+    ];
+    let Some(first) = string.chars().next() else {
+        return false;
+    };
+    return !(string.trim() != string
+        || "-?:,[]{}#&*!|>'\"%@`.+".contains(first)
+        || first.is_ascii_digit()
+        || string.contains(": ")
+        || string.contains(" #")
+        || string.ends_with(':')
+        || RESERVED.contains(&string.to_lowercase().as_str()));
+}
+
+/**
+Writes `value` as a JSON document, see
+[`Format::serialize_value_formatted`](crate::Format::serialize_value_formatted).
+NaN and infinite numbers are written as `null`.
+ */
+#[cfg(feature = "serde_json")]
+pub(crate) fn to_json(value: &Value, format_options: &FormatOptions) -> Result<String, String> {
+    let mut output = String::new();
+    write_json(value, 0, format_options, &mut output)?;
+    return Ok(output);
+}
+
+/**
+Writes `value` starting at the current position of `output`. Lines within
+collections are indented by `indent` plus one indentation level.
+ */
+#[cfg(feature = "serde_json")]
+fn write_json(
+    value: &Value,
+    indent: usize,
+    format_options: &FormatOptions,
+    output: &mut String,
+) -> Result<(), String> {
+    let step = format_options.indent;
+    let (open, close, len) = match value {
+        Value::Seq(elements) if !elements.is_empty() => ('[', ']', elements.len()),
+        Value::Map(entries) if !entries.is_empty() => ('{', '}', entries.len()),
+        _ => {
+            output.push_str(&json_scalar(value, format_options)?);
+            return Ok(());
+        }
+    };
+    if let Value::Seq(elements) = value
+        && step > 0
+    {
+        let line_start = output.rfind('\n').map_or(0, |index| index + 1);
+        if let Some(flow) = flow_sequence(
+            elements,
+            output.len() - line_start,
+            format_options,
+            |element| json_scalar(element, format_options).ok(),
+        ) {
+            output.push_str(&flow);
+            return Ok(());
+        }
+    }
+
+    output.push(open);
+    for index in 0..len {
+        if index > 0 {
+            output.push(',');
+        }
+        if step > 0 {
+            output.push('\n');
+            output.push_str(&" ".repeat(indent + step));
+        }
+        match value {
+            Value::Seq(elements) => {
+                write_json(&elements[index], indent + step, format_options, output)?
+            }
+            Value::Map(entries) => {
+                let (key, element) = &entries[index];
+                let key = match key {
+                    Value::String(key) => json_string(key),
+                    Value::Bool(_) | Value::I64(_) | Value::U64(_) | Value::F64(_) => {
+                        json_string(&json_scalar(key, format_options)?)
+                    }
+                    _ => return Err("JSON object keys must be strings or numbers".into()),
+                };
+                output.push_str(&key);
+                output.push(':');
+                if step > 0 {
+                    output.push(' ');
+                }
+                write_json(element, indent + step, format_options, output)?;
+            }
+            _ => (),
+        }
+    }
+    if step > 0 {
+        output.push('\n');
+        output.push_str(&" ".repeat(indent));
+    }
+    output.push(close);
+    return Ok(());
+}
+
+/**
+Writes the scalar (or empty collection) `value` in JSON syntax.
+ */
+#[cfg(feature = "serde_json")]
+fn json_scalar(value: &Value, format_options: &FormatOptions) -> Result<String, String> {
+    match value {
+        Value::Null => return Ok("null".into()),
+        Value::Bool(val) => return Ok(val.to_string()),
+        Value::I64(val) => return Ok(val.to_string()),
+        Value::U64(val) => return Ok(val.to_string()),
+        Value::F64(val) => return Ok(format_float(*val, format_options).unwrap_or("null".into())),
+        Value::String(string) => return Ok(json_string(string)),
+        Value::Bytes(bytes) => {
+            let bytes: Vec<String> = bytes.iter().map(u8::to_string).collect();
+            return Ok(format!("[{}]", bytes.join(",")));
+        }
+        Value::Seq(elements) if elements.is_empty() => return Ok("[]".into()),
+        Value::Map(entries) if entries.is_empty() => return Ok("{}".into()),
+        _ => return Err("collections can't be written as scalars".into()),
+    }
+}
+
+/**
+Writes `string` as a quoted JSON string.
+ */
+#[cfg(feature = "serde_json")]
+fn json_string(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+    for char in string.chars() {
+        match char {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            char if char.is_control() => quoted.push_str(&format!("\\u{:04x}", char as u32)),
+            char => quoted.push(char),
+        }
+    }
+    quoted.push('"');
+    return quoted;
+}
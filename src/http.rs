@@ -0,0 +1,265 @@
+/*!
+Minimal HTTP/1.1 message handling shared by the `remote` client and the
+`server`. Only the subset of HTTP needed by the protocol described in the
+`remote` module is supported: messages are sent with a
+`Content-Length` and received either with a `Content-Length`, chunked or
+delimited by the end of the connection.
+ */
+
+use std::ffi::OsStr;
+use std::io::{BufRead, Error, ErrorKind, Read, Write};
+
+/**
+The header which transmits the checksum of a database file.
+ */
+pub(crate) const CHECKSUM_HEADER: &str = "X-Mosaic-Checksum";
+
+/**
+The maximum length of the start line or of a single header line in bytes.
+ */
+const MAX_LINE_LENGTH: u64 = 16 * 1024;
+
+/**
+An HTTP request or response.
+ */
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Message {
+    /**
+    The request line (e.g. `GET /Material/steel HTTP/1.1`) or the status line
+    (e.g. `HTTP/1.1 200 OK`).
+     */
+    pub(crate) start_line: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Message {
+    /**
+    Creates a message with the given start line and neither headers nor body.
+     */
+    pub(crate) fn new<S: Into<String>>(start_line: S) -> Self {
+        return Self {
+            start_line: start_line.into(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        };
+    }
+
+    /**
+    Returns the value of the first header called `name` (case-insensitive).
+     */
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        return self
+            .headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim());
+    }
+
+    /**
+    Adds a header to the message.
+     */
+    pub(crate) fn with_header<A: Into<String>, B: Into<String>>(
+        mut self,
+        name: A,
+        value: B,
+    ) -> Self {
+        self.headers.push((name.into(), value.into()));
+        return self;
+    }
+
+    /**
+    Returns the status code of a response.
+     */
+    #[cfg(feature = "remote")]
+    pub(crate) fn status(&self) -> Option<u16> {
+        return self.start_line.split_whitespace().nth(1)?.parse().ok();
+    }
+
+    /**
+    Reads a message from `reader`. If the message has neither a
+    `Content-Length` nor a `Transfer-Encoding` header, its body is read until
+    the end of the stream if `body_until_eof` is `true` (responses) and is
+    empty otherwise (requests).
+     */
+    pub(crate) fn read<R: BufRead>(reader: &mut R, body_until_eof: bool) -> std::io::Result<Self> {
+        let mut message = Self::read_head(reader)?;
+        message.read_body(reader, body_until_eof, usize::MAX)?;
+        return Ok(message);
+    }
+
+    /**
+    Reads the start line and the headers of a message from `reader`, but not
+    its body (e.g. for responses to `HEAD` requests).
+     */
+    pub(crate) fn read_head<R: BufRead>(reader: &mut R) -> std::io::Result<Self> {
+        let start_line = read_line(reader)?;
+        if start_line.is_empty() {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "Connection closed before a message was received",
+            ));
+        }
+        let mut message = Message::new(start_line);
+        loop {
+            let line = read_line(reader)?;
+            if line.is_empty() {
+                break;
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Malformed header line \"{line}\""),
+                ));
+            };
+            message
+                .headers
+                .push((name.trim().to_string(), value.trim().to_string()));
+        }
+        return Ok(message);
+    }
+
+    /**
+    Reads the body of a message whose head has been read via
+    [`Message::read_head`], see [`Message::read`]. If the body is longer than
+    `max_len` bytes, an error of the kind [`ErrorKind::FileTooLarge`] is
+    returned. A body with a `Content-Length` is rejected before any memory is
+    allocated for it.
+     */
+    pub(crate) fn read_body<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        body_until_eof: bool,
+        max_len: usize,
+    ) -> std::io::Result<()> {
+        if self
+            .header("Transfer-Encoding")
+            .is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked"))
+        {
+            self.body = read_chunked(reader, max_len)?;
+        } else if let Some(length) = self.header("Content-Length") {
+            let length: usize = length.parse().map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid Content-Length \"{length}\""),
+                )
+            })?;
+            if length > max_len {
+                return Err(body_too_large(max_len));
+            }
+            self.body = vec![0; length];
+            reader.read_exact(&mut self.body)?;
+        } else if body_until_eof {
+            reader
+                .take((max_len as u64).saturating_add(1))
+                .read_to_end(&mut self.body)?;
+            if self.body.len() > max_len {
+                return Err(body_too_large(max_len));
+            }
+        }
+        return Ok(());
+    }
+
+    /**
+    Writes the message to `writer`. The `Content-Length` header is added
+    automatically.
+     */
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let mut head = format!("{}\r\n", self.start_line);
+        for (name, value) in self.headers.iter() {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        head.push_str(&format!("Content-Length: {}\r\n\r\n", self.body.len()));
+        writer.write_all(head.as_bytes())?;
+        writer.write_all(&self.body)?;
+        return writer.flush();
+    }
+}
+
+/**
+Reads a single line without its line break.
+ */
+fn read_line<R: BufRead>(reader: &mut R) -> std::io::Result<String> {
+    let mut line = String::new();
+    reader.take(MAX_LINE_LENGTH).read_line(&mut line)?;
+    if !line.is_empty() && !line.ends_with('\n') && line.len() as u64 == MAX_LINE_LENGTH {
+        return Err(Error::new(ErrorKind::InvalidData, "HTTP line too long"));
+    }
+    let trimmed_len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(trimmed_len);
+    return Ok(line);
+}
+
+/**
+Returns the error for a message body which is longer than `max_len` bytes.
+ */
+fn body_too_large(max_len: usize) -> Error {
+    return Error::new(
+        ErrorKind::FileTooLarge,
+        format!("The message body exceeds the limit of {max_len} bytes"),
+    );
+}
+
+/**
+Reads a body sent with `Transfer-Encoding: chunked`. The chunks are rejected
+as soon as their total length exceeds `max_len` bytes.
+ */
+fn read_chunked<R: BufRead>(reader: &mut R, max_len: usize) -> std::io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid chunk size \"{size}\""),
+            )
+        })?;
+        if size == 0 {
+            // Skip the trailer
+            while !read_line(reader)?.is_empty() {}
+            return Ok(body);
+        }
+        let start = body.len();
+        if size > max_len - start {
+            return Err(body_too_large(max_len));
+        }
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        read_line(reader)?;
+    }
+}
+
+/**
+Percent-encodes `segment` so it can be used as a single path segment of an URL.
+ */
+pub(crate) fn encode_segment(segment: &OsStr) -> String {
+    let mut encoded = String::new();
+    for byte in segment.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    return encoded;
+}
+
+/**
+Reverts [`encode_segment`]. Returns [`None`] if `segment` is not a valid
+percent-encoded UTF-8 string.
+ */
+pub(crate) fn decode_segment(segment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut iter = segment.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let high = (iter.next()? as char).to_digit(16)?;
+            let low = (iter.next()? as char).to_digit(16)?;
+            bytes.push((high * 16 + low) as u8);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    return String::from_utf8(bytes).ok();
+}
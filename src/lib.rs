@@ -1,12 +1,96 @@
 #![doc = include_str!("../README.md")]
 #![deny(missing_docs)]
 
+#[cfg(feature = "tokio")]
+pub mod async_manager;
 pub mod attributes;
+pub mod batch;
+pub mod cache_capacity;
+pub mod checksum_algorithm;
+#[cfg(feature = "compression")]
+pub mod compression;
 pub mod database_manager;
+pub mod diff;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod events;
+pub mod exchange;
+pub mod expiry;
 pub mod format;
+pub mod formatting;
+#[cfg(any(feature = "remote", feature = "server"))]
+mod http;
+pub mod link_error;
+pub mod link_graph;
+pub mod listing;
+pub mod lock;
+pub mod maintenance;
+pub mod merge;
+pub mod migration;
+#[cfg(feature = "figment")]
+pub mod provider;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod report;
+pub mod scope;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod sharding;
+pub mod shared_cache;
+pub mod shared_manager;
+pub mod signature;
+pub mod staging;
+pub mod statistics;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod value;
+pub mod verification;
+#[cfg(feature = "watch")]
+pub mod watch;
 
+#[cfg(feature = "tokio")]
+pub use async_manager::*;
 pub use attributes::*;
+pub use batch::*;
+pub use cache_capacity::*;
+pub use checksum_algorithm::*;
+#[cfg(feature = "compression")]
+pub use compression::*;
 pub use database_manager::*;
+pub use diff::*;
+#[cfg(feature = "encryption")]
+pub use encryption::*;
+pub use events::*;
+pub use exchange::*;
+pub use expiry::*;
 pub use format::*;
+pub use formatting::*;
+pub use link_error::*;
+pub use link_graph::*;
+pub use listing::*;
+pub use lock::*;
+pub use maintenance::*;
+pub use merge::*;
+pub use migration::*;
+#[cfg(feature = "figment")]
+pub use provider::*;
+#[cfg(feature = "remote")]
+pub use remote::*;
+pub use report::*;
+pub use scope::*;
+#[cfg(feature = "server")]
+pub use server::*;
+pub use sharding::*;
+pub use shared_cache::*;
+pub use shared_manager::*;
+pub use signature::*;
+pub use staging::*;
+pub use statistics::*;
+pub use value::*;
+pub use verification::*;
+#[cfg(feature = "watch")]
+pub use watch::*;
 
 pub use serde;
+#[cfg(feature = "macros")]
+pub use serde_mosaic_macros::mosaic;
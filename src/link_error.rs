@@ -0,0 +1,146 @@
+/*!
+This module contains the [`LinkError`], which describes a link which could not
+be resolved while reading an entry.
+
+Reading an entry whose (possibly deeply nested) linked entry can't be read
+fails with an [`std::io::Error`] which wraps a [`LinkError`]. Besides the
+message of the original error, it contains the chain of files which have been
+read to arrive at the failing link, so the error message points to the
+responsible file:
+
+```text
+Could not find file /path/to/db/Material/pure_cotton.yaml
+(while reading Shirt/mike.yaml → Material/pure_cotton.yaml)
+```
+
+The [`LinkError`] can be obtained from the returned error via
+[`LinkError::from_io_error`].
+ */
+
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/**
+An error which occurred while resolving a link, see the
+[module documentation](crate::link_error). It is returned wrapped in an
+[`std::io::Error`] of the same [`ErrorKind`].
+
+# Examples
+
+```no_run
+use std::ffi::OsStr;
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Shirt {
+    owner: String,
+    #[serde(with = "serde_mosaic::with::link")]
+    material: Material,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Shirt {
+    fn name(&self) -> &OsStr {
+        self.owner.as_ref()
+    }
+}
+
+let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+if let Err(err) = dbm.read::<Shirt, _>("mike") {
+    if let Some(link_error) = LinkError::from_io_error(&err) {
+        println!("could not read {:?}", link_error.chain().last());
+    }
+}
+```
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkError {
+    dir: PathBuf,
+    chain: Vec<PathBuf>,
+    kind: ErrorKind,
+    message: String,
+}
+
+impl LinkError {
+    /**
+    Creates a new [`LinkError`] for a link within the database at `dir`.
+    `chain` contains the files which were being read, starting with the read
+    entry and ending with the file of the linked entry which failed.
+     */
+    pub(crate) fn new(dir: &Path, chain: Vec<PathBuf>, err: &std::io::Error) -> Self {
+        return Self {
+            dir: dir.to_path_buf(),
+            chain,
+            kind: err.kind(),
+            message: err.to_string(),
+        };
+    }
+
+    /**
+    Returns the [`LinkError`] wrapped in `err`, if any.
+     */
+    pub fn from_io_error(err: &std::io::Error) -> Option<&LinkError> {
+        return err.get_ref()?.downcast_ref::<LinkError>();
+    }
+
+    /**
+    Returns the paths of the files which were being read when the link failed.
+    The first path is the file of the read entry, each following path is the
+    file of an entry linked by its predecessor and the last path is the file
+    of the linked entry which could not be read.
+     */
+    pub fn chain(&self) -> &[PathBuf] {
+        return &self.chain;
+    }
+
+    /**
+    Returns the [`ErrorKind`] of the original error.
+     */
+    pub fn kind(&self) -> ErrorKind {
+        return self.kind;
+    }
+
+    /**
+    Returns the message of the original error.
+     */
+    pub fn message(&self) -> &str {
+        return &self.message;
+    }
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (while reading ", self.message)?;
+        for (index, path) in self.chain.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" → ")?;
+            }
+            // Paths within the database are shown relative to its directory
+            let path = path.strip_prefix(&self.dir).unwrap_or(path);
+            write!(f, "{}", path.display())?;
+        }
+        return f.write_str(")");
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+impl From<LinkError> for std::io::Error {
+    fn from(err: LinkError) -> Self {
+        return std::io::Error::new(err.kind, err);
+    }
+}
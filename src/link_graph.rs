@@ -0,0 +1,837 @@
+/*!
+This module contains functionality to analyze the links between the entries of
+a database. [`DatabaseManager::referrers`] reports all entries linking to a
+given entry, [`DatabaseManager::find_orphans`] reports all entries which are
+not linked by any other entry and [`DatabaseManager::gc`] removes such entries
+from selected type folders. [`DatabaseManager::remove_checked`] and
+[`DatabaseManager::remove_recursive`] only remove entries which are not linked
+by other entries, while [`DatabaseManager::rename`] renames an entry and updates
+all links to it. [`DatabaseManager::refresh_checksums`] updates the checksums
+of all links to an entry which has been edited by hand.
+
+Links are resolved as described in the [`verification`](crate::verification)
+module. If a link is ambiguous, it is treated as a link to all candidates, so
+that no entry which might still be in use is considered an orphan. Links of
+an entry to itself are ignored. Since the links of a file which can't be read
+or parsed are unknown, the analysis fails with an error of kind
+[`ErrorKind::InvalidData`] if the database contains such a file.
+
+# Short links
+
+Links written with [`LinkStyle::Short`](crate::LinkStyle::Short) are plain
+strings which can't be told apart from ordinary string fields without knowing
+the type of the entry. Therefore, every string value of an entry which equals
+the name of another entry is treated as a possible short link: Entries which
+might be linked this way are never considered orphans (and hence neither
+removed by [`DatabaseManager::gc`] nor by
+[`DatabaseManager::remove_recursive`]), are listed by
+[`DatabaseManager::referrers`] and can't be removed via
+[`DatabaseManager::remove_checked`]. [`DatabaseManager::rename`] refuses to
+rename them, since short links can't be rewritten. As a consequence, an entry
+whose name merely coincides with a string field of another entry is treated
+as linked as well.
+ */
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+use crate::{
+    ChangeKind, ChecksumAlgorithm, DatabaseKey, DatabaseKeyBuf, DatabaseLink, DatabaseManager,
+    FileStatus, LinkTarget,
+};
+
+/**
+Options to modify the behaviour of [`DatabaseManager::gc`]. See the individual
+fields for details.
+ */
+#[derive(Debug, Clone)]
+pub struct GcOptions {
+    /**
+    The names of the type folders (e.g. `Material`) whose orphaned entries are
+    removed. Entries of other types are never removed, which protects the
+    top-level entries of the database (e.g. `Shirt`), since these are usually
+    not linked by any other entry. If this vector is empty, no entries are
+    removed at all.
+
+    Defaults to an empty vector.
+     */
+    pub types: Vec<OsString>,
+    /**
+    If `true`, the orphans are only determined, but not removed. The
+    [`GcSummary`] then lists the entries which would have been removed.
+
+    Defaults to `false`.
+     */
+    pub dry_run: bool,
+}
+
+impl Default for GcOptions {
+    fn default() -> Self {
+        Self {
+            types: Vec::new(),
+            dry_run: false,
+        }
+    }
+}
+
+/**
+This struct is returned by [`DatabaseManager::gc`] and contains information
+about the collected garbage within its fields.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct GcSummary {
+    /**
+    Keys of all orphaned entries, sorted by type name first and name second.
+    In a dry run, these entries have not been removed.
+     */
+    pub orphans: Vec<DatabaseKeyBuf>,
+    /**
+    Paths of all files which have been removed. This vector is empty for a
+    dry run.
+     */
+    pub removed_files: Vec<PathBuf>,
+}
+
+/**
+The links between all entries of a database, see
+[`DatabaseManager::link_graph`]. Every entry of the database is contained in
+both maps, even if it doesn't link to or isn't linked by any other entry.
+ */
+pub(crate) struct LinkGraph {
+    /**
+    The entries linked by each entry.
+     */
+    pub(crate) links: BTreeMap<DatabaseKeyBuf, BTreeSet<DatabaseKeyBuf>>,
+    /**
+    The entries linking to each entry.
+     */
+    pub(crate) referrers: BTreeMap<DatabaseKeyBuf, BTreeSet<DatabaseKeyBuf>>,
+    /**
+    The entries which might link to each entry via a short link (see the
+    [module documentation](crate::link_graph)). Only entries with at least
+    one such referrer are contained.
+     */
+    pub(crate) short_link_referrers: BTreeMap<DatabaseKeyBuf, BTreeSet<DatabaseKeyBuf>>,
+}
+
+impl LinkGraph {
+    /**
+    Returns `true` if the entry `key` is neither linked by any other entry nor
+    might be linked via a short link.
+     */
+    pub(crate) fn is_orphan(&self, key: &DatabaseKeyBuf) -> bool {
+        return self
+            .referrers
+            .get(key)
+            .is_none_or(|referrers| referrers.is_empty())
+            && !self.short_link_referrers.contains_key(key);
+    }
+
+    /**
+    Removes the entries `keys` from the graph. Entries which are orphans (see
+    [`LinkGraph::is_orphan`]) afterwards are removed as well if `is_collected`
+    returns `true` for them. Returns the keys of all removed entries.
+     */
+    pub(crate) fn remove_cascading<F: Fn(&DatabaseKeyBuf) -> bool>(
+        &mut self,
+        keys: Vec<DatabaseKeyBuf>,
+        is_collected: F,
+    ) -> BTreeSet<DatabaseKeyBuf> {
+        let mut removed: BTreeSet<DatabaseKeyBuf> = BTreeSet::new();
+        let mut pending = keys;
+        while let Some(key) = pending.pop() {
+            if !removed.insert(key.clone()) {
+                continue;
+            }
+            for target in self.links.remove(&key).unwrap_or_default() {
+                if let Some(referrers) = self.referrers.get_mut(&target) {
+                    referrers.remove(&key);
+                }
+                if self.is_orphan(&target) && is_collected(&target) {
+                    pending.push(target);
+                }
+            }
+        }
+        return removed;
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Returns the keys of all entries which are not linked by any other entry of
+    the database, sorted by type name first and name second. See the
+    [module documentation](crate::link_graph) for details on how links are
+    resolved.
+
+    Note that this includes the top-level entries of the database (e.g. the
+    shirts of a database of shirts and their materials), since these are
+    usually not linked by any other entry. Use [`DatabaseManager::gc`] to
+    remove orphans of specific types only. Entries which might be linked via
+    a [`LinkStyle::Short`](crate::LinkStyle::Short) link are not considered
+    orphans (see the [module documentation](crate::link_graph#short-links)).
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    for key in dbm.find_orphans().expect("database is accessible") {
+        println!("{key} is not linked by any other entry");
+    }
+    ```
+     */
+    pub fn find_orphans(&self) -> std::io::Result<Vec<DatabaseKeyBuf>> {
+        let graph = self.link_graph()?;
+        return Ok(graph
+            .referrers
+            .keys()
+            .filter(|key| graph.is_orphan(key))
+            .cloned()
+            .collect());
+    }
+
+    /**
+    Removes all orphaned entries (see [`DatabaseManager::find_orphans`]) within
+    the type folders [`GcOptions::types`]. Removing an orphan can turn the
+    entries it links to into orphans as well, hence these are removed too if
+    they belong to one of the type folders. Entries which only link to each
+    other in a cycle are never considered orphans. Neither are entries which
+    might be linked via a [`LinkStyle::Short`](crate::LinkStyle::Short) link
+    (see the [module documentation](crate::link_graph#short-links)).
+
+    If [`GcOptions::dry_run`] is `true`, no entries are removed and the
+    returned [`GcSummary`] lists the entries which would have been removed.
+    Otherwise, the entries are removed via [`DatabaseManager::remove`] and
+    the [`Cache`](crate::Cache) entries of removed files are evicted.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let gc_options = GcOptions {
+        types: vec!["Material".into()],
+        dry_run: true,
+    };
+    let summary = dbm.gc(&gc_options).expect("database is accessible");
+    for key in summary.orphans.iter() {
+        println!("would remove {key}");
+    }
+    ```
+     */
+    pub fn gc(&mut self, gc_options: &GcOptions) -> std::io::Result<GcSummary> {
+        let mut summary = GcSummary::default();
+        if gc_options.types.is_empty() {
+            return Ok(summary);
+        }
+        let is_collected =
+            |key: &DatabaseKeyBuf| gc_options.types.iter().any(|name| *name == key.type_name);
+
+        let mut graph = self.link_graph()?;
+        let orphans: Vec<DatabaseKeyBuf> = graph
+            .referrers
+            .iter()
+            .keys()
+            .filter(|key| graph.is_orphan(key) && is_collected(key))
+            .cloned()
+            .collect();
+        summary.orphans = graph
+            .remove_cascading(orphans, is_collected)
+            .into_iter()
+            .collect();
+
+        if !gc_options.dry_run {
+            for key in summary.orphans.iter() {
+                let path = self.full_path_unchecked(key);
+                self.remove(key)?;
+                summary.removed_files.push(path);
+            }
+            self.evict_stale_cache_entries();
+        }
+        return Ok(summary);
+    }
+
+    /**
+    Returns the entries which contain a link to the entry `key`, grouped by
+    their type name. Both the type names and the names of the entries are
+    sorted alphabetically. If the entry isn't linked by any other entry, the
+    returned map is empty. See the [module documentation](crate::link_graph)
+    for details on how links are resolved. Entries which contain a string
+    equal to the name of `key` are included as well, since the string might
+    be a [`LinkStyle::Short`](crate::LinkStyle::Short) link.
+
+    Since every entry of the database needs to be read, this is an expensive
+    operation for large databases.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let referrers = dbm
+        .referrers(("Material", "cotton"))
+        .expect("database is accessible");
+    for (type_name, names) in referrers.iter() {
+        println!("{}: {:?}", type_name.to_string_lossy(), names);
+    }
+    ```
+     */
+    pub fn referrers<'a, K: Into<DatabaseKey<'a>>>(
+        &self,
+        key: K,
+    ) -> std::io::Result<BTreeMap<OsString, Vec<OsString>>> {
+        let key = DatabaseKeyBuf::from(key.into());
+        let (linking, short_linking) = self.split_referrers(&key)?;
+        let mut referrers: BTreeMap<OsString, Vec<OsString>> = BTreeMap::new();
+        for referrer in linking.into_iter().chain(short_linking) {
+            referrers
+                .entry(referrer.type_name)
+                .or_default()
+                .push(referrer.name);
+        }
+        for names in referrers.values_mut() {
+            names.sort();
+        }
+        return Ok(referrers);
+    }
+
+    /**
+    Like [`DatabaseManager::remove`], but fails if the entry `key` is still
+    linked by other entries of the database (see
+    [`DatabaseManager::referrers`]). In this case, nothing is removed and an
+    error of kind [`ErrorKind::Other`] listing the referring entries is
+    returned. This includes entries which might link to `key` via a
+    [`LinkStyle::Short`](crate::LinkStyle::Short) link.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    if let Err(err) = dbm.remove_checked(("Material", "cotton")) {
+        println!("cotton can't be removed: {err}");
+    }
+    ```
+     */
+    pub fn remove_checked<'a, K: Into<DatabaseKey<'a>>>(&mut self, key: K) -> std::io::Result<()> {
+        let key: DatabaseKey = key.into();
+        self.check_unreferenced(key)?;
+        return self.remove(key);
+    }
+
+    /**
+    Like [`DatabaseManager::remove_checked`], but additionally removes all
+    entries linked by `key` which are not linked by any other entry
+    afterwards. This is repeated for the entries linked by the removed
+    entries, so an entry is removed together with all components which are
+    not shared with other entries. Entries which might be linked via a
+    [`LinkStyle::Short`](crate::LinkStyle::Short) link are never removed this
+    way (see the [module documentation](crate::link_graph#short-links)).
+    Returns the keys of all removed entries, sorted by type name first and
+    name second.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let removed = dbm.remove_recursive(("Shirt", "mike")).expect("shirt is not linked");
+    for key in removed.iter() {
+        println!("removed {key}");
+    }
+    ```
+     */
+    pub fn remove_recursive<'a, K: Into<DatabaseKey<'a>>>(
+        &mut self,
+        key: K,
+    ) -> std::io::Result<Vec<DatabaseKeyBuf>> {
+        let key = DatabaseKeyBuf::from(key.into());
+        self.check_unreferenced(key.as_key())?;
+        let mut graph = self.link_graph()?;
+        let removed = graph.remove_cascading(vec![key], |_| true);
+        for key in removed.iter() {
+            self.remove(key)?;
+        }
+        return Ok(removed.into_iter().collect());
+    }
+
+    /**
+    Renames the entry `key` to `new_name` (within the same type folder) and
+    rewrites all links to the entry in other files of the database, so these
+    files link to the renamed entry afterwards. Since rewriting a file changes
+    its checksum, links to the rewritten files are updated as well if their
+    checksum was up to date before the rename. Returns the new path of the
+    entry.
+
+    Only the file (and its companion files, e.g. the detached signature) is
+    renamed, its contents are not modified. If the name of the entry is
+    derived from one of its fields (see
+    [`DatabaseEntry::name`](crate::DatabaseEntry::name)), this field still
+    contains the old name. Links which could point to entries of different
+    types (see [`LinkTarget::Ambiguous`]) are not rewritten.
+
+    The following errors can be returned:
+    - [`ErrorKind::NotFound`] if the entry doesn't exist on disk.
+    - [`ErrorKind::AlreadyExists`] if an entry called `new_name` already exists.
+    - [`ErrorKind::InvalidInput`] if `new_name` is not a single, normal path
+    segment or not valid UTF-8 (links store names as strings).
+    - [`ErrorKind::InvalidData`] if a file of the database can't be parsed,
+    since its links are unknown. Nothing is modified in this case.
+    - [`ErrorKind::ResourceBusy`] if the entry or a file linking to it is
+    locked by another manager (see [`DatabaseManager::lock`]).
+    - [`ErrorKind::Unsupported`] if the entry might be linked via a
+    [`LinkStyle::Short`](crate::LinkStyle::Short) link (see the
+    [module documentation](crate::link_graph#short-links)), since such links
+    can't be rewritten. Nothing is modified in this case.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let path = dbm
+        .rename(("Material", "cotton"), "organic_cotton")
+        .expect("entry can be renamed");
+    assert!(path.ends_with("Material/organic_cotton.yaml"));
+    ```
+     */
+    pub fn rename<'a, K: Into<DatabaseKey<'a>>, O: AsRef<OsStr>>(
+        &mut self,
+        key: K,
+        new_name: O,
+    ) -> std::io::Result<PathBuf> {
+        let key = DatabaseKeyBuf::from(key.into());
+        let new_name = new_name.as_ref();
+        let mut components = Path::new(new_name).components();
+        let is_segment = matches!(
+            components.next(),
+            Some(Component::Normal(segment)) if segment == new_name
+        ) && components.next().is_none();
+        let Some(new_link_name) = new_name.to_str().filter(|_| is_segment) else {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid entry name {}", new_name.to_string_lossy()),
+            ));
+        };
+
+        let path = self.full_path_unchecked(&key);
+        let new_key = DatabaseKeyBuf::new(key.type_name.clone(), new_name);
+        let new_path = self.full_path_unchecked(&new_key);
+        if !path.exists() {
+            return Err(Error::new(ErrorKind::NotFound, format!("No entry {key}")));
+        }
+        if self.exists(&new_key) {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Entry {new_key} already exists"),
+            ));
+        }
+
+        let (referrers, short_link_referrers) = self.split_referrers(&key)?;
+        if !short_link_referrers.is_empty() {
+            let short_link_referrers: Vec<String> = short_link_referrers
+                .iter()
+                .map(DatabaseKeyBuf::to_string)
+                .collect();
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "Entry {} might be linked by short links in {}, which can't be rewritten",
+                    key,
+                    short_link_referrers.join(", ")
+                ),
+            ));
+        }
+        self.check_database_lock()?;
+        self.check_lock(&path)?;
+        for referrer in referrers.iter() {
+            self.check_lock(&self.full_path_unchecked(referrer))?;
+        }
+
+        // Remember which checksums were up to date before files are rewritten.
+        // Links may have been written with any algorithm, hence all are used.
+        let keys = self.entry_keys()?;
+        let mut checksums: HashMap<String, HashSet<(ChecksumAlgorithm, u32)>> = HashMap::new();
+        for entry in keys.iter() {
+            if let Ok(bytes) = fs::read(self.full_path_unchecked(entry)) {
+                let valid = checksums
+                    .entry(entry.name.to_string_lossy().into_owned())
+                    .or_default();
+                for algorithm in ChecksumAlgorithm::ALL {
+                    valid.insert((algorithm, algorithm.checksum_bytes(&bytes)));
+                }
+            }
+        }
+
+        let type_folders = self.type_folders()?;
+        let old_link_name = key.name.to_string_lossy();
+        for referrer in referrers.iter() {
+            self.rewrite_links(&self.full_path_unchecked(referrer), |link| {
+                if link.name != old_link_name
+                    || self.resolve_link(link, &type_folders) != LinkTarget::Resolved(key.clone())
+                {
+                    return false;
+                }
+                link.name = new_link_name.to_string();
+                if link.checksum.is_some() {
+                    link.checksum = link.algorithm.checksum_file(&path);
+                }
+                return true;
+            })?;
+        }
+
+        fs::rename(&path, &new_path).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!(
+                    "Could not rename file {} to {}: {}",
+                    path.display(),
+                    new_path.display(),
+                    err
+                ),
+            )
+        })?;
+        for (companion_path, new_companion_path) in [
+            (Self::signature_path(&path), Self::signature_path(&new_path)),
+            (Self::expiry_path(&path), Self::expiry_path(&new_path)),
+        ] {
+            if companion_path.exists() {
+                fs::rename(&companion_path, &new_companion_path)?;
+            }
+        }
+        self.notify(ChangeKind::Removed, &path);
+        self.notify(ChangeKind::Created, &new_path);
+
+        let keys = self.entry_keys()?;
+        self.refresh_link_checksums_where(&keys, |_, link| {
+            return link.checksum.is_some_and(|checksum_in_link| {
+                checksums.get(&link.name).is_some_and(|checksums| {
+                    checksums.contains(&(link.algorithm, checksum_in_link))
+                })
+            });
+        })?;
+        self.evict_stale_cache_entries();
+        return Ok(new_path);
+    }
+
+    /**
+    Updates the checksums stored in all links to the entry `key`, e.g. after
+    its file has been edited by hand. Without this, every read of an entry
+    linking to `key` reports a [`ChecksumMismatch`](crate::ChecksumMismatch).
+    Since rewriting a referring file changes its checksum, the links to the
+    referring entries are updated as well, up to the top-level entries. Links
+    to other entries are not modified, even if their checksums are outdated
+    as well (use [`DatabaseManager::refresh_all_checksums`] for that).
+
+    The updated links use the [`ChecksumAlgorithm`] of `self`. Links without a
+    checksum are left as they are. Returns the paths of all rewritten files.
+    The [`Cache`](crate::Cache) entries of rewritten files are evicted.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    // The cotton content of the material has been corrected by hand
+    let rewritten = dbm
+        .refresh_checksums(("Material", "cotton"))
+        .expect("database is accessible");
+    for path in rewritten.iter() {
+        println!("updated links in {}", path.display());
+    }
+    ```
+     */
+    pub fn refresh_checksums<'a, K: Into<DatabaseKey<'a>>>(
+        &mut self,
+        key: K,
+    ) -> std::io::Result<Vec<PathBuf>> {
+        let key = DatabaseKeyBuf::from(key.into());
+        if !self.full_path_unchecked(&key).exists() {
+            return Err(Error::new(ErrorKind::NotFound, format!("No entry {key}")));
+        }
+
+        // All entries which (transitively) link to the refreshed entry
+        let graph = self.link_graph()?;
+        let mut targets: BTreeSet<DatabaseKeyBuf> = BTreeSet::new();
+        targets.insert(key.clone());
+        let mut referrers: Vec<DatabaseKeyBuf> = Vec::new();
+        let mut pending = vec![key];
+        while let Some(target) = pending.pop() {
+            for referrer in graph.referrers.get(&target).into_iter().flatten() {
+                if targets.insert(referrer.clone()) {
+                    referrers.push(referrer.clone());
+                    pending.push(referrer.clone());
+                }
+            }
+        }
+
+        self.check_database_lock()?;
+        for referrer in referrers.iter() {
+            self.check_lock(&self.full_path_unchecked(referrer))?;
+        }
+        let type_folders = self.type_folders()?;
+        let rewritten = self.refresh_link_checksums_where(&referrers, |_, link| {
+            match self.resolve_link(link, &type_folders) {
+                LinkTarget::Resolved(target) => return targets.contains(&target),
+                _ => return false,
+            }
+        })?;
+        self.evict_stale_cache_entries();
+        return Ok(rewritten);
+    }
+
+    /**
+    Like [`DatabaseManager::refresh_checksums`], but updates the outdated
+    checksums of all links within the database.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let rewritten = dbm.refresh_all_checksums().expect("database is accessible");
+    println!("{} files updated", rewritten.len());
+    ```
+     */
+    pub fn refresh_all_checksums(&mut self) -> std::io::Result<Vec<PathBuf>> {
+        let keys = self.entry_keys()?;
+        self.check_database_lock()?;
+        for key in keys.iter() {
+            self.check_lock(&self.full_path_unchecked(key))?;
+        }
+        let rewritten = self.refresh_link_checksums(&keys)?;
+        self.evict_stale_cache_entries();
+        return Ok(rewritten);
+    }
+
+    /**
+    Returns an error if any entry of the database links to `key`.
+     */
+    fn check_unreferenced(&self, key: DatabaseKey) -> std::io::Result<()> {
+        let referrers = self.referrer_keys(key)?;
+        if referrers.is_empty() {
+            return Ok(());
+        }
+        let referrers: Vec<String> = referrers.iter().map(DatabaseKeyBuf::to_string).collect();
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "Entry {} is still linked by {}",
+                DatabaseKeyBuf::from(key),
+                referrers.join(", ")
+            ),
+        ));
+    }
+
+    /**
+    Returns the entries which link to `key` and the entries which only might
+    link to `key` via a short link (see the
+    [module documentation](crate::link_graph#short-links)).
+     */
+    fn split_referrers(
+        &self,
+        key: &DatabaseKeyBuf,
+    ) -> std::io::Result<(BTreeSet<DatabaseKeyBuf>, BTreeSet<DatabaseKeyBuf>)> {
+        let type_folders = self.type_folders()?;
+        let mut linking = BTreeSet::new();
+        let mut short_linking = BTreeSet::new();
+        for referrer in self.entry_keys()? {
+            let (targets, short_link_targets) =
+                self.linked_keys(&referrer, &type_folders, |link| {
+                    return key.name.as_os_str() == OsStr::new(&link.name);
+                })?;
+            if targets.contains(key) {
+                linking.insert(referrer);
+            } else if short_link_targets.contains(key) {
+                short_linking.insert(referrer);
+            }
+        }
+        return Ok((linking, short_linking));
+    }
+
+    /**
+    Like [`DatabaseManager::referrers`], but returns the keys of the referring
+    entries as a flat list.
+     */
+    fn referrer_keys(&self, key: DatabaseKey) -> std::io::Result<Vec<DatabaseKeyBuf>> {
+        return Ok(self
+            .referrers(key)?
+            .into_iter()
+            .flat_map(|(type_name, names)| {
+                return names
+                    .into_iter()
+                    .map(move |name| DatabaseKeyBuf::new(type_name.clone(), name));
+            })
+            .collect());
+    }
+
+    /**
+    Returns the names of all entries of the type `type_name` which are linked
+    by any other entry of the database, including entries which might be
+    linked via a short link (see the
+    [module documentation](crate::link_graph#short-links)). Unlike
+    [`DatabaseManager::link_graph`], only links whose name matches an entry of
+    `type_name` are resolved.
+     */
+    pub(crate) fn linked_names_of_type(
+        &self,
+        type_name: &OsStr,
+    ) -> std::io::Result<BTreeSet<OsString>> {
+        let type_folders = self.type_folders()?;
+        let names: HashSet<OsString> = self.entry_names(type_name)?.into_iter().collect();
+        let mut linked = BTreeSet::new();
+        for referrer in self.entry_keys()? {
+            let (targets, short_link_targets) =
+                self.linked_keys(&referrer, &type_folders, |link| {
+                    return names.contains(OsStr::new(&link.name));
+                })?;
+            linked.extend(
+                targets
+                    .into_iter()
+                    .chain(short_link_targets)
+                    .filter(|target| target.type_name.as_os_str() == type_name)
+                    .map(|target| target.name),
+            );
+        }
+        return Ok(linked);
+    }
+
+    /**
+    Reads all entries of the database and determines the links between them.
+    Staged entries (see [`DatabaseManager::enable_staging`]) which haven't
+    been written to disk yet are not considered.
+     */
+    pub(crate) fn link_graph(&self) -> std::io::Result<LinkGraph> {
+        let type_folders = self.type_folders()?;
+        let keys = self.entry_keys()?;
+        let mut graph = LinkGraph {
+            links: BTreeMap::new(),
+            referrers: keys
+                .iter()
+                .map(|key| (key.clone(), BTreeSet::new()))
+                .collect(),
+            short_link_referrers: BTreeMap::new(),
+        };
+        for key in keys {
+            let (targets, short_link_targets) = self.linked_keys(&key, &type_folders, |_| true)?;
+            for target in targets.iter() {
+                if let Some(referrers) = graph.referrers.get_mut(target) {
+                    referrers.insert(key.clone());
+                }
+            }
+            for target in short_link_targets {
+                graph
+                    .short_link_referrers
+                    .entry(target)
+                    .or_default()
+                    .insert(key.clone());
+            }
+            graph.links.insert(key, targets);
+        }
+        return Ok(graph);
+    }
+
+    /**
+    Returns the keys of all entries linked by the entry `key` and the keys of
+    all other entries which might be linked via a short link (see the
+    [module documentation](crate::link_graph#short-links)), except for `key`
+    itself. Only the links for which `filter` returns `true` are resolved.
+     */
+    fn linked_keys<F: Fn(&DatabaseLink) -> bool>(
+        &self,
+        key: &DatabaseKeyBuf,
+        type_folders: &[OsString],
+        filter: F,
+    ) -> std::io::Result<(BTreeSet<DatabaseKeyBuf>, BTreeSet<DatabaseKeyBuf>)> {
+        let value = self.read_value(key).map_err(|status| {
+            let message = match status {
+                FileStatus::Unreadable(message) | FileStatus::Unparseable(message) => message,
+                FileStatus::Missing | FileStatus::Valid => String::new(),
+            };
+            return Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Could not determine the links of {}: {}",
+                    self.full_path_unchecked(key).display(),
+                    message
+                ),
+            );
+        })?;
+
+        let resolve = |links: Vec<DatabaseLink>| {
+            let mut targets = BTreeSet::new();
+            for link in links.into_iter().filter(|link| filter(link)) {
+                match self.resolve_link(&link, type_folders) {
+                    LinkTarget::Resolved(target) => {
+                        targets.insert(target);
+                    }
+                    LinkTarget::Ambiguous(candidates) => targets.extend(candidates),
+                    LinkTarget::Dangling => (),
+                }
+            }
+            targets.remove(key);
+            return targets;
+        };
+        let targets = resolve(value.links());
+        let short_link_targets: BTreeSet<DatabaseKeyBuf> = resolve(
+            value
+                .possible_short_links()
+                .into_iter()
+                .filter(|link| is_entry_name(&link.name))
+                .collect(),
+        )
+        .difference(&targets)
+        .cloned()
+        .collect();
+        return Ok((targets, short_link_targets));
+    }
+
+    /**
+    Adds all entries to `keys` which are (transitively) linked by the entries
+    in `keys`, including possible short links (see the
+    [module documentation](crate::link_graph#short-links)). Fails if the
+    links of one of these entries are unknown.
+     */
+    pub(crate) fn extend_by_linked_keys(
+        &self,
+        keys: &mut HashSet<DatabaseKeyBuf>,
+    ) -> std::io::Result<()> {
+        let type_folders = self.type_folders()?;
+        let mut pending: Vec<DatabaseKeyBuf> = keys.iter().cloned().collect();
+        while let Some(key) = pending.pop() {
+            if !self.full_path_unchecked(&key).exists() {
+                continue;
+            }
+            let (targets, short_link_targets) = self.linked_keys(&key, &type_folders, |_| true)?;
+            for target in targets.into_iter().chain(short_link_targets) {
+                if keys.insert(target.clone()) {
+                    pending.push(target);
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+/**
+Returns `true` if `string` can be the name of an entry, i.e. if it is a single,
+normal path segment. Other strings are never considered short links.
+ */
+fn is_entry_name(string: &str) -> bool {
+    let mut components = Path::new(string).components();
+    return matches!(
+        components.next(),
+        Some(Component::Normal(segment)) if segment == OsStr::new(string)
+    ) && components.next().is_none();
+}
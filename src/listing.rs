@@ -0,0 +1,258 @@
+/*!
+This module contains functionality to list the contents of a database. See
+[`DatabaseManager::list`], [`DatabaseManager::list_all`] and
+[`DatabaseManager::read_all`].
+ */
+
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::{DatabaseEntry, DatabaseKey, DatabaseKeyBuf, DatabaseManager};
+
+/**
+Metadata of a single database entry, see [`DatabaseManager::entry_metadata`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryMetadata {
+    /**
+    The key of the database entry.
+     */
+    pub key: DatabaseKeyBuf,
+    /**
+    The path of the file which contains the database entry.
+     */
+    pub path: PathBuf,
+    /**
+    The checksum of the file (see [`DatabaseManager::checksum`]).
+     */
+    pub checksum: Option<u32>,
+    /**
+    The time of the last modification of the file. This is [`None`] for staged
+    entries which haven't been written to disk yet (see
+    [`DatabaseManager::enable_staging`]) and on platforms which don't provide
+    modification times.
+     */
+    pub modified: Option<SystemTime>,
+    /**
+    The size of the file in bytes. For staged entries, this is the size of the
+    staged contents.
+     */
+    pub bytes: u64,
+}
+
+/**
+This struct is returned by [`DatabaseManager::read_all`] and contains the
+successfully read entries as well as the entries which could not be read.
+ */
+#[derive(Debug)]
+pub struct ReadAllSummary<T> {
+    /**
+    Names and deserialized instances of all entries which have been read
+    successfully, in alphabetical order of the names.
+     */
+    pub entries: Vec<(OsString, T)>,
+    /**
+    Keys of all entries which could not be read, together with the
+    corresponding error.
+     */
+    pub failures: Vec<(DatabaseKeyBuf, Error)>,
+}
+
+impl<T> ReadAllSummary<T> {
+    /**
+    Returns `true` if all entries have been read successfully.
+     */
+    pub fn is_success(&self) -> bool {
+        return self.failures.is_empty();
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Returns the names of all entries of the type `T` in alphabetical order.
+    Staged entries (see [`DatabaseManager::enable_staging`]) are included,
+    expired entries (see [`DatabaseManager::write_with_expiry`]) are not. If
+    the database doesn't contain any entries of `T`, the iterator is empty.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    for name in dbm.list::<Material>().expect("database is accessible") {
+        println!("{}", name.to_string_lossy());
+    }
+    ```
+     */
+    pub fn list<T: DatabaseEntry>(&self) -> std::io::Result<impl Iterator<Item = OsString>> {
+        return Ok(self.listed_names(OsStr::new(T::folder_name()))?.into_iter());
+    }
+
+    /**
+    Like [`DatabaseManager::list`], but returns the [`EntryMetadata`] of the
+    entries. The metadata is determined lazily while iterating, so an error
+    is returned for entries which have been removed in the meantime.
+     */
+    pub fn list_with_metadata<T: DatabaseEntry>(
+        &self,
+    ) -> std::io::Result<impl Iterator<Item = std::io::Result<EntryMetadata>> + '_> {
+        return Ok(self
+            .list::<T>()?
+            .map(|name| self.entry_metadata((T::folder_name(), name.as_os_str()))));
+    }
+
+    /**
+    Returns the keys of all entries within all type folders of the database,
+    sorted by type name first and name second. Like [`DatabaseManager::list`],
+    staged entries are included and expired entries are not.
+     */
+    pub fn list_all(&self) -> std::io::Result<impl Iterator<Item = DatabaseKeyBuf>> {
+        let mut type_names = self.type_folders()?;
+        // Types which have only been staged don't have a folder yet
+        for path in self.staged_files() {
+            if let Some(type_name) = self.type_folder_of(&path)
+                && !type_names.iter().any(|name| name == type_name)
+            {
+                type_names.push(type_name.to_os_string());
+            }
+        }
+        type_names.sort();
+
+        let mut keys = Vec::new();
+        for type_name in type_names {
+            for name in self.listed_names(&type_name)? {
+                keys.push(DatabaseKeyBuf::new(type_name.clone(), name));
+            }
+        }
+        return Ok(keys.into_iter());
+    }
+
+    /**
+    Reads all entries of the type `T` (see [`DatabaseManager::list`]). Links
+    are resolved as in [`DatabaseManager::read`], so linked entries which are
+    shared via [`Arc`](std::sync::Arc) are only read once and the instances
+    are shared between all read entries. If an entry can't be read (e.g.
+    because its file is corrupted or a linked entry is missing), the error is
+    recorded in [`ReadAllSummary::failures`] and the remaining entries are
+    read nonetheless. An error is only returned if the type folder itself
+    can't be accessed.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let summary = dbm.read_all::<Material>().expect("database is accessible");
+    for (name, material) in summary.entries.iter() {
+        println!("{}: {}", name.to_string_lossy(), material.cotton_content);
+    }
+    for (key, err) in summary.failures.iter() {
+        println!("could not read {}: {}", key, err);
+    }
+    ```
+     */
+    pub fn read_all<T: DatabaseEntry>(&mut self) -> std::io::Result<ReadAllSummary<T>> {
+        let type_name = T::folder_name();
+        let mut summary = ReadAllSummary {
+            entries: Vec::new(),
+            failures: Vec::new(),
+        };
+        for name in self.list::<T>()? {
+            match self.read::<T, _>(&name) {
+                Ok(instance) => summary.entries.push((name, instance)),
+                Err(err) => summary
+                    .failures
+                    .push((DatabaseKeyBuf::new(type_name, name), err)),
+            }
+        }
+        return Ok(summary);
+    }
+
+    /**
+    Returns the [`EntryMetadata`] of the entry `key`. If the entry doesn't
+    exist (or has expired), an error of kind [`ErrorKind::NotFound`] is
+    returned.
+     */
+    pub fn entry_metadata<'a, K: Into<DatabaseKey<'a>>>(
+        &self,
+        key: K,
+    ) -> std::io::Result<EntryMetadata> {
+        let key: DatabaseKey = key.into();
+        if !self.exists(key) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("No entry {}", DatabaseKeyBuf::from(key)),
+            ));
+        }
+        let path = self.full_path_unchecked(key);
+        let (modified, bytes) = match self.staged(&path) {
+            Some(staged) => (None, staged.len() as u64),
+            None => {
+                let metadata = fs::metadata(&path)?;
+                (metadata.modified().ok(), metadata.len())
+            }
+        };
+        return Ok(EntryMetadata {
+            key: key.into(),
+            checksum: self.file_checksum(&path),
+            path,
+            modified,
+            bytes,
+        });
+    }
+
+    /**
+    Returns the sorted names of the entries within the type folder
+    `type_name`, including staged entries which don't exist on disk yet.
+     */
+    fn listed_names(&self, type_name: &OsStr) -> std::io::Result<Vec<OsString>> {
+        let mut names = self.entry_names(type_name)?;
+        for path in self.staged_files() {
+            if self.type_folder_of(&path) == Some(type_name)
+                && let Some(name) = path
+                    .file_name()
+                    .and_then(|file_name| self.entry_name(type_name, file_name))
+                && !names.contains(&name)
+            {
+                names.push(name);
+            }
+        }
+        names.sort();
+        return Ok(names);
+    }
+}
@@ -0,0 +1,504 @@
+/*!
+This module contains functionality to lock single database entries for
+editing, see [`DatabaseManager::lock`].
+
+A lock is a file next to the file of the locked entry, with `.lock` appended to
+the file name (e.g. `Material/cotton.yaml.lock`). As long as the lock exists,
+all other [`DatabaseManager`]s (also within other processes or on other
+machines sharing the database directory) refuse to write or remove the entry
+with an error of kind [`ErrorKind::ResourceBusy`]. The lock is released when
+the returned [`EntryGuard`] is dropped.
+
+If a process crashes while holding a lock, the lock file is left behind. Such
+a lock is considered stale once its age exceeds [`LockOptions::stale_after`]
+and is then ignored respectively replaced by new locks. Long editing sessions
+can keep their lock alive via [`EntryGuard::refresh`].
+
+Additionally, the whole database can be locked for bulk operations (e.g.
+migrations) via [`DatabaseManager::lock_exclusive`] and
+[`DatabaseManager::lock_shared`]. While an exclusive lock exists, all other
+managers refuse to read, write or remove entries. The locks of the whole
+database are stored as hidden files within the database directory.
+ */
+
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{DatabaseKey, DatabaseKeyBuf, DatabaseManager};
+
+/**
+Counter which makes the tokens of locks created within the same process
+unique.
+ */
+static LOCK_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/**
+Options for [`DatabaseManager::lock_with`].
+ */
+#[derive(Debug, Clone)]
+pub struct LockOptions {
+    /**
+    A lock whose file hasn't been modified for this duration is considered
+    stale (e.g. because the process holding it crashed) and is replaced. The
+    duration is stored within the lock file, so all managers use the same
+    value for a given lock.
+
+    Defaults to one hour.
+     */
+    pub stale_after: Duration,
+}
+
+impl Default for LockOptions {
+    fn default() -> Self {
+        Self {
+            stale_after: Duration::from_secs(3600),
+        }
+    }
+}
+
+/**
+A lock file held by a guard. The lock file is removed when this struct is
+dropped.
+ */
+#[derive(Debug)]
+struct LockFile {
+    lock_path: PathBuf,
+    token: String,
+    stale_after: Duration,
+    held_locks: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl LockFile {
+    /**
+    Creates the lock file at `lock_path` for `dbm`. Stale lock files are
+    replaced. Returns [`None`] if an active lock file exists already.
+     */
+    fn create(
+        dbm: &DatabaseManager,
+        lock_path: PathBuf,
+        lock_options: &LockOptions,
+    ) -> std::io::Result<Option<Self>> {
+        if let Some(folder) = lock_path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        let token = new_token();
+
+        // If a stale lock is replaced, a second attempt is necessary
+        for _ in 0..2 {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock_path)
+            {
+                Ok(mut file) => {
+                    file.write_all(lock_contents(&token, lock_options.stale_after).as_bytes())?;
+                    dbm.held_locks
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .insert(lock_path.clone());
+                    return Ok(Some(Self {
+                        lock_path,
+                        token,
+                        stale_after: lock_options.stale_after,
+                        held_locks: dbm.held_locks.clone(),
+                    }));
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    if is_active(&lock_path) {
+                        return Ok(None);
+                    }
+                    match fs::remove_file(&lock_path) {
+                        Ok(()) => (),
+                        Err(err) if err.kind() == ErrorKind::NotFound => (),
+                        Err(err) => return Err(err),
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        return Ok(None);
+    }
+
+    fn refresh(&self) -> std::io::Result<()> {
+        if !self.is_owned() {
+            return Err(Error::new(
+                ErrorKind::ResourceBusy,
+                format!(
+                    "Lock {} has been taken over by someone else",
+                    self.lock_path.display()
+                ),
+            ));
+        }
+        return fs::write(
+            &self.lock_path,
+            lock_contents(&self.token, self.stale_after),
+        );
+    }
+
+    fn is_owned(&self) -> bool {
+        return fs::read_to_string(&self.lock_path)
+            .is_ok_and(|contents| contents.lines().next() == Some(self.token.as_str()));
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        // Don't remove locks which were taken over by someone else
+        if self.is_owned() {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+        self.held_locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.lock_path);
+    }
+}
+
+/**
+A lock on a single database entry, created by [`DatabaseManager::lock`]. The
+lock is released when the guard is dropped.
+ */
+#[derive(Debug)]
+pub struct EntryGuard {
+    key: DatabaseKeyBuf,
+    lock: LockFile,
+}
+
+impl EntryGuard {
+    /**
+    Returns the key of the locked database entry.
+     */
+    pub fn key(&self) -> &DatabaseKeyBuf {
+        return &self.key;
+    }
+
+    /**
+    Returns the path of the lock file.
+     */
+    pub fn lock_path(&self) -> &Path {
+        return &self.lock.lock_path;
+    }
+
+    /**
+    Rewrites the lock file, which resets its age (see
+    [`LockOptions::stale_after`]). Returns an error if the lock has been
+    taken over by someone else in the meantime because it was stale.
+     */
+    pub fn refresh(&self) -> std::io::Result<()> {
+        return self.lock.refresh();
+    }
+}
+
+/**
+Specifies whether a [`DatabaseGuard`] locks the database exclusively or
+shared.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseLockMode {
+    /**
+    Created by [`DatabaseManager::lock_exclusive`].
+     */
+    Exclusive,
+    /**
+    Created by [`DatabaseManager::lock_shared`].
+     */
+    Shared,
+}
+
+/**
+A lock on the whole database, created by [`DatabaseManager::lock_exclusive`]
+or [`DatabaseManager::lock_shared`]. The lock is released when the guard is
+dropped.
+ */
+#[derive(Debug)]
+pub struct DatabaseGuard {
+    mode: DatabaseLockMode,
+    lock: LockFile,
+}
+
+impl DatabaseGuard {
+    /**
+    Returns whether the database is locked exclusively or shared.
+     */
+    pub fn mode(&self) -> DatabaseLockMode {
+        return self.mode;
+    }
+
+    /**
+    Returns the path of the lock file.
+     */
+    pub fn lock_path(&self) -> &Path {
+        return &self.lock.lock_path;
+    }
+
+    /**
+    Rewrites the lock file, which resets its age (see
+    [`LockOptions::stale_after`]). Bulk operations which take longer than
+    the stale duration must call this function regularly.
+     */
+    pub fn refresh(&self) -> std::io::Result<()> {
+        return self.lock.refresh();
+    }
+}
+
+/**
+File name of the exclusive lock of the whole database.
+ */
+const EXCLUSIVE_LOCK: &str = ".serde_mosaic.exclusive.lock";
+
+/**
+File name prefix of the shared locks of the whole database. Every shared lock
+has its own file, which is suffixed by the lock token and `.lock`.
+ */
+const SHARED_LOCK_PREFIX: &str = ".serde_mosaic.shared.";
+
+fn new_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    return format!(
+        "{}-{}-{}",
+        std::process::id(),
+        nanos,
+        LOCK_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+}
+
+fn lock_contents(token: &str, stale_after: Duration) -> String {
+    return format!("{}\nstale_after {}\n", token, stale_after.as_secs());
+}
+
+/**
+Returns `true` if the lock file at `lock_path` exists and is not stale.
+ */
+fn is_active(lock_path: &Path) -> bool {
+    let Ok(metadata) = fs::metadata(lock_path) else {
+        return false;
+    };
+    let stale_after = fs::read_to_string(lock_path)
+        .ok()
+        .and_then(|contents| {
+            contents
+                .lines()
+                .find_map(|line| line.strip_prefix("stale_after "))
+                .and_then(|secs| secs.trim().parse().ok())
+        })
+        .map(Duration::from_secs)
+        .unwrap_or_else(|| LockOptions::default().stale_after);
+    let age = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .unwrap_or_default();
+    return age <= stale_after;
+}
+
+impl DatabaseManager {
+    /**
+    Locks the database entry `key` for editing with the default
+    [`LockOptions`]. See [`DatabaseManager::lock_with`].
+     */
+    pub fn lock<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> std::io::Result<EntryGuard> {
+        return self.lock_with(key, &LockOptions::default());
+    }
+
+    /**
+    Locks the database entry `key` for editing, see the
+    [module docstring](crate::lock). The entry doesn't need to exist yet.
+
+    Returns an error of kind [`ErrorKind::ResourceBusy`] if the entry is
+    already locked (also if the lock is held by `self`). While the returned
+    [`EntryGuard`] is alive, `self` (and its clones) can still write and
+    remove the entry, while all other managers can't.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let guard = dbm.lock(("Material", "cotton")).expect("entry is not locked");
+    // ... edit and write the entry ...
+    drop(guard);
+    ```
+     */
+    pub fn lock_with<'a, T: Into<DatabaseKey<'a>>>(
+        &self,
+        key: T,
+        lock_options: &LockOptions,
+    ) -> std::io::Result<EntryGuard> {
+        let key: DatabaseKey = key.into();
+        let lock_path = Self::lock_path(&self.full_path_unchecked(key));
+        match LockFile::create(self, lock_path, lock_options)? {
+            Some(lock) => {
+                return Ok(EntryGuard {
+                    key: key.into(),
+                    lock,
+                });
+            }
+            None => {
+                return Err(Error::new(
+                    ErrorKind::ResourceBusy,
+                    format!("Database entry {} is locked", DatabaseKeyBuf::from(key)),
+                ));
+            }
+        }
+    }
+
+    /**
+    Locks the whole database exclusively, see the
+    [module docstring](crate::lock). While the returned [`DatabaseGuard`] is
+    alive, all other managers can neither read, write nor remove entries and
+    can't lock the database themselves.
+
+    Returns an error of kind [`ErrorKind::ResourceBusy`] if the database is
+    already locked (exclusively or shared, also by `self`).
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let guard = dbm.lock_exclusive().expect("no one else uses the database");
+    // ... migrate the database ...
+    drop(guard);
+    ```
+     */
+    pub fn lock_exclusive(&self) -> std::io::Result<DatabaseGuard> {
+        let Some(lock) = LockFile::create(
+            self,
+            self.dir().join(EXCLUSIVE_LOCK),
+            &LockOptions::default(),
+        )?
+        else {
+            return Err(Error::new(
+                ErrorKind::ResourceBusy,
+                "Database is locked exclusively",
+            ));
+        };
+
+        // Shared locks prevent the exclusive lock (which is released again)
+        let shared_locks = self.active_shared_locks()?;
+        if shared_locks > 0 {
+            return Err(Error::new(
+                ErrorKind::ResourceBusy,
+                format!("Database is locked by {} shared locks", shared_locks),
+            ));
+        }
+        return Ok(DatabaseGuard {
+            mode: DatabaseLockMode::Exclusive,
+            lock,
+        });
+    }
+
+    /**
+    Locks the whole database shared, see the [module docstring](crate::lock).
+    Any number of shared locks can exist at once, but while one of them is
+    alive, the database can't be locked exclusively. Shared locks don't
+    restrict the regular reading and writing of entries.
+
+    Returns an error of kind [`ErrorKind::ResourceBusy`] if another manager
+    has locked the database exclusively.
+     */
+    pub fn lock_shared(&self) -> std::io::Result<DatabaseGuard> {
+        self.check_database_lock()?;
+        let lock_path = self
+            .dir()
+            .join(format!("{}{}.lock", SHARED_LOCK_PREFIX, new_token()));
+        let Some(lock) = LockFile::create(self, lock_path, &LockOptions::default())? else {
+            return Err(Error::new(ErrorKind::ResourceBusy, "Database is locked"));
+        };
+
+        // An exclusive lock might have been created in the meantime
+        self.check_database_lock()?;
+        return Ok(DatabaseGuard {
+            mode: DatabaseLockMode::Shared,
+            lock,
+        });
+    }
+
+    /**
+    Returns an error of kind [`ErrorKind::ResourceBusy`] if another manager
+    has locked the database exclusively.
+     */
+    pub(crate) fn check_database_lock(&self) -> std::io::Result<()> {
+        let lock_path = self.dir().join(EXCLUSIVE_LOCK);
+        if !self
+            .held_locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(&lock_path)
+            && is_active(&lock_path)
+        {
+            return Err(Error::new(
+                ErrorKind::ResourceBusy,
+                "Database is locked exclusively by another manager",
+            ));
+        }
+        return Ok(());
+    }
+
+    /**
+    Returns the number of active shared locks of the whole database.
+     */
+    fn active_shared_locks(&self) -> std::io::Result<usize> {
+        let mut count = 0;
+        for entry in fs::read_dir(self.dir())? {
+            let entry = entry?;
+            if entry
+                .file_name()
+                .to_str()
+                .is_some_and(|file_name| file_name.starts_with(SHARED_LOCK_PREFIX))
+                && is_active(&entry.path())
+            {
+                count += 1;
+            }
+        }
+        return Ok(count);
+    }
+
+    /**
+    Returns `true` if the database entry `key` is locked by another manager,
+    i.e. if `self` can't write or remove it.
+     */
+    pub fn is_locked<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> bool {
+        return self.check_lock(&self.full_path_unchecked(key)).is_err();
+    }
+
+    /**
+    Returns an error of kind [`ErrorKind::ResourceBusy`] if the database file
+    at `file_path` is locked by another manager.
+     */
+    pub(crate) fn check_lock(&self, file_path: &Path) -> std::io::Result<()> {
+        let lock_path = Self::lock_path(file_path);
+        if !self
+            .held_locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(&lock_path)
+            && is_active(&lock_path)
+        {
+            return Err(Error::new(
+                ErrorKind::ResourceBusy,
+                format!("File {} is locked", file_path.display()),
+            ));
+        }
+        return Ok(());
+    }
+
+    /**
+    Returns the path of the lock file of the database file at `path`.
+     */
+    fn lock_path(path: &Path) -> PathBuf {
+        let mut lock_path = path.as_os_str().to_os_string();
+        lock_path.push(".lock");
+        return PathBuf::from(lock_path);
+    }
+}
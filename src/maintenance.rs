@@ -0,0 +1,469 @@
+/*!
+This module contains maintenance functionality for long-lived databases. The
+central method is [`DatabaseManager::compact`], which bundles several
+housekeeping tasks into a single call:
+
+1. Refreshing outdated checksums in links (optional, see
+[`CompactOptions::refresh_checksums`]).
+2. Removing all entries which can't be reached from a given set of roots (see
+[`CompactOptions::roots`]).
+3. Removing empty folders (see [`DatabaseManager::remove_empty_subfolders`]).
+4. Evicting [`Cache`](crate::Cache) entries whose file has been removed or
+modified.
+
+There is no manifest or index refresh: The database doesn't store a manifest
+or an index of its entries, the files within the type folders are the only
+source of truth. The only state derived from them is the
+[`Cache`](crate::Cache), which is updated in the last step.
+
+The results are returned as a [`CompactSummary`].
+
+After a crash, [`DatabaseManager::open_or_repair`] can be used to open a
+database and fix trivial layout issues such as leftover temporary files (see
+[`RepairOptions`] and [`RepairSummary`]).
+ */
+
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    ChangeKind, DatabaseKeyBuf, DatabaseLink, DatabaseManager, DatabaseReport, FileStatus, Format,
+    LinkTarget,
+};
+
+/**
+Options to modify the behaviour of [`DatabaseManager::compact`]. See the
+individual fields for details.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CompactOptions {
+    /**
+    The root entries of the database. All entries which are neither a root nor
+    (transitively) linked by a root are considered orphans and removed. If this
+    vector is empty or one of the roots doesn't exist (e.g. because of a typo),
+    no entries are removed at all. Entries which might be linked via a
+    [`LinkStyle::Short`](crate::LinkStyle::Short) link are kept as well (see
+    the [`link_graph`](crate::link_graph#short-links) module).
+
+    Defaults to an empty vector.
+     */
+    pub roots: Vec<DatabaseKeyBuf>,
+    /**
+    If `true`, the checksums stored in links are updated to match the current
+    checksum of the linked files (see
+    [`Problem::ChecksumMismatch`](crate::Problem::ChecksumMismatch)) before
+    searching for orphans.
+
+    Defaults to `false`.
+     */
+    pub refresh_checksums: bool,
+}
+
+/**
+This struct is returned by [`DatabaseManager::compact`] and contains
+information about the performed maintenance within its fields.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CompactSummary {
+    /**
+    Paths of all files whose links have been rewritten because of outdated
+    checksums.
+     */
+    pub refreshed_files: Vec<PathBuf>,
+    /**
+    Paths of all orphaned entries which have been removed.
+     */
+    pub removed_orphans: Vec<PathBuf>,
+    /**
+    Paths of all empty folders which have been removed.
+     */
+    pub removed_folders: Vec<PathBuf>,
+    /**
+    Number of entries which have been evicted from the
+    [`Cache`](crate::Cache).
+     */
+    pub evicted_cache_entries: usize,
+    /**
+    Problems found during compaction which could not be fixed automatically,
+    e.g. unparseable files or dangling links reachable from the roots. If the
+    link closure of the roots contains unreadable or unparseable files, no
+    orphans are removed, since their links are unknown. The same applies if
+    one of the roots is missing.
+     */
+    pub report: DatabaseReport,
+}
+
+/**
+Options to modify the behaviour of [`DatabaseManager::open_or_repair`]. See the
+individual fields for details.
+ */
+#[derive(Debug, Clone)]
+pub struct RepairOptions {
+    /**
+    File extensions (without the leading dot) which have been used for the
+    database files in the past, e.g. `yml` for a database which now uses
+    [`SerdeYaml`](crate::SerdeYaml). Files within the type folders which have
+    one of these extensions are renamed to the file extension of the database,
+    unless an entry of the same name already exists. Their contents are not
+    converted, so only extensions of files which can be read by the current
+    [`Format`] should be listed here.
+
+    Defaults to an empty vector.
+     */
+    pub legacy_extensions: Vec<OsString>,
+    /**
+    If `true`, temporary files left over by interrupted writes (see
+    [`DatabaseManager::write`]) are removed. Temporary files of entries which
+    are locked by another manager are kept, since they might belong to a write
+    in progress.
+
+    Defaults to `true`.
+     */
+    pub remove_temp_files: bool,
+    /**
+    If `true`, empty folders are removed (see
+    [`DatabaseManager::remove_empty_subfolders`]).
+
+    Defaults to `true`.
+     */
+    pub remove_empty_folders: bool,
+}
+
+impl Default for RepairOptions {
+    fn default() -> Self {
+        Self {
+            legacy_extensions: Vec::new(),
+            remove_temp_files: true,
+            remove_empty_folders: true,
+        }
+    }
+}
+
+/**
+This struct is returned by [`DatabaseManager::open_or_repair`] and contains
+information about the performed repairs within its fields.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct RepairSummary {
+    /**
+    The original and the new path of all files which have been renamed because
+    of a legacy file extension (see [`RepairOptions::legacy_extensions`]).
+     */
+    pub renamed_files: Vec<(PathBuf, PathBuf)>,
+    /**
+    Paths of all temporary files which have been removed.
+     */
+    pub removed_temp_files: Vec<PathBuf>,
+    /**
+    Paths of all empty folders which have been removed.
+     */
+    pub removed_folders: Vec<PathBuf>,
+    /**
+    Layout problems which remain after the repair (see
+    [`DatabaseManager::validate_layout`]).
+     */
+    pub report: DatabaseReport,
+}
+
+impl DatabaseManager {
+    /**
+    Like [`DatabaseManager::open`], but additionally fixes trivial issues of
+    the database layout which typically remain after a crash or a change of
+    the [`Format`]. See [`RepairOptions`] for the performed repairs. The
+    returned [`RepairSummary`] lists all changes as well as the layout problems
+    which could not be fixed.
+
+    Returns an error if the database is locked exclusively by another manager
+    (see [`DatabaseManager::lock_exclusive`]). Other managers must not write
+    into the database while it is being repaired.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let repair_options = RepairOptions {
+        legacy_extensions: vec!["yml".into()],
+        ..Default::default()
+    };
+    let (dbm, summary) = DatabaseManager::open_or_repair("/path/to/db", SerdeYaml, &repair_options)
+        .expect("directory exists");
+    for (old_path, new_path) in summary.renamed_files.iter() {
+        println!("renamed {} to {}", old_path.display(), new_path.display());
+    }
+    ```
+     */
+    pub fn open_or_repair<P, F>(
+        path: P,
+        format: F,
+        repair_options: &RepairOptions,
+    ) -> std::io::Result<(Self, RepairSummary)>
+    where
+        P: AsRef<Path>,
+        F: Format + 'static,
+    {
+        let mut dbm = Self::open(path, format)?;
+        dbm.check_database_lock()?;
+        let mut summary = RepairSummary::default();
+
+        for type_name in dbm.type_folders()? {
+            let paths = dbm.type_folder_files(&type_name)?;
+            for path in paths {
+                if repair_options.remove_temp_files && path.extension() == Some(OsStr::new("tmp")) {
+                    let entry_path = path.with_extension("");
+                    let is_entry = entry_path
+                        .file_name()
+                        .and_then(|file_name| dbm.entry_name(&type_name, file_name))
+                        .is_some();
+                    if is_entry && dbm.check_lock(&entry_path).is_ok() {
+                        fs::remove_file(&path)?;
+                        summary.removed_temp_files.push(path);
+                    }
+                    continue;
+                }
+
+                let has_legacy_extension = path.extension().is_some_and(|extension| {
+                    extension != dbm.file_ext()
+                        && repair_options
+                            .legacy_extensions
+                            .iter()
+                            .any(|legacy| legacy == extension)
+                });
+                if has_legacy_extension {
+                    let new_path = path.with_extension(dbm.file_ext());
+                    if new_path.exists() {
+                        continue;
+                    }
+                    fs::rename(&path, &new_path)?;
+
+                    // Companion files move along, the signature covers the
+                    // contents, which are unchanged
+                    for suffix in [".sig", ".expires"] {
+                        let mut companion_path = path.clone().into_os_string();
+                        companion_path.push(suffix);
+                        let companion_path = PathBuf::from(companion_path);
+                        if companion_path.exists() {
+                            let mut new_companion_path = new_path.clone().into_os_string();
+                            new_companion_path.push(suffix);
+                            fs::rename(&companion_path, new_companion_path)?;
+                        }
+                    }
+                    dbm.notify(ChangeKind::Created, &new_path);
+                    summary.renamed_files.push((path, new_path));
+                }
+            }
+        }
+
+        if repair_options.remove_empty_folders {
+            summary.removed_folders = dbm.remove_empty_subfolders_priv()?;
+        }
+        summary.report = dbm.validate_layout()?;
+        return Ok((dbm, summary));
+    }
+
+    /**
+    Performs several maintenance tasks on the database in one call. See the
+    module docstring and [`CompactOptions`] for details.
+
+    Only files with the file extension of `self` inside the type folders are
+    considered database entries. Since links do not store the type of the
+    linked entry, links are resolved as described in
+    [`DatabaseManager::verify_entry`]. If a link is ambiguous, all candidates
+    are kept.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let compact_options = CompactOptions {
+        roots: vec![DatabaseKeyBuf::new("Shirt", "mike")],
+        refresh_checksums: true,
+    };
+    let summary = dbm.compact(&compact_options).expect("database is accessible");
+    for path in summary.removed_orphans.iter() {
+        println!("removed {}", path.display());
+    }
+    ```
+     */
+    pub fn compact(&mut self, compact_options: &CompactOptions) -> std::io::Result<CompactSummary> {
+        let mut summary = CompactSummary::default();
+
+        if compact_options.refresh_checksums {
+            let keys = self.entry_keys()?;
+            summary.refreshed_files = self.refresh_link_checksums(&keys)?;
+        }
+
+        if !compact_options.roots.is_empty() {
+            let mut reachable: HashSet<DatabaseKeyBuf> = HashSet::new();
+            let mut links_known = true;
+            for root in compact_options.roots.iter() {
+                let verification = self.verify_entry(root);
+
+                // A missing root would turn every entry into an orphan
+                if verification
+                    .files
+                    .first()
+                    .is_some_and(|file| file.status == FileStatus::Missing)
+                {
+                    links_known = false;
+                }
+                for file in verification.files.iter() {
+                    if matches!(
+                        file.status,
+                        FileStatus::Unreadable(_) | FileStatus::Unparseable(_)
+                    ) {
+                        links_known = false;
+                    }
+                    reachable.insert(file.key.clone());
+                    for link in file.links.iter() {
+                        if let LinkTarget::Ambiguous(candidates) = &link.target {
+                            reachable.extend(candidates.iter().cloned());
+                        }
+                    }
+                }
+                summary.report.append(verification.report());
+            }
+
+            // Entries which might be linked via short links are kept as well,
+            // since verify_entry doesn't see such links
+            if links_known && self.extend_by_linked_keys(&mut reachable).is_err() {
+                links_known = false;
+            }
+
+            if links_known {
+                for key in self.entry_keys()? {
+                    if !reachable.contains(&key) {
+                        let path = self.full_path_unchecked(&key);
+                        self.remove(&key)?;
+                        summary.removed_orphans.push(path);
+                    }
+                }
+            }
+        }
+
+        summary.removed_folders = self.remove_empty_subfolders_priv()?;
+        summary.evicted_cache_entries = self.evict_stale_cache_entries();
+        return Ok(summary);
+    }
+
+    /**
+    Rewrites the links in the files of `keys` whose checksum doesn't match the
+    checksum of the linked file anymore. Since rewriting a file changes its
+    checksum, this is repeated until no more links need to be updated (at most
+    once per file in order to terminate for cyclic links). Returns the paths
+    of all rewritten files.
+     */
+    pub(crate) fn refresh_link_checksums(
+        &self,
+        keys: &[DatabaseKeyBuf],
+    ) -> std::io::Result<Vec<PathBuf>> {
+        return self.refresh_link_checksums_where(keys, |_, _| true);
+    }
+
+    /**
+    Like [`DatabaseManager::refresh_link_checksums`], but only updates the
+    links for which `filter` (called with the key of the file containing the
+    link and the link itself) returns `true`.
+     */
+    pub(crate) fn refresh_link_checksums_where<F: Fn(&DatabaseKeyBuf, &DatabaseLink) -> bool>(
+        &self,
+        keys: &[DatabaseKeyBuf],
+        filter: F,
+    ) -> std::io::Result<Vec<PathBuf>> {
+        let type_folders = self.type_folders()?;
+        let mut rewritten: Vec<PathBuf> = Vec::new();
+        for _ in 0..=keys.len() {
+            let mut modified = false;
+            for key in keys.iter() {
+                let path = self.full_path_unchecked(key);
+                if self.read_value(key).is_err() {
+                    continue;
+                }
+                let file_modified = self.rewrite_links(&path, |link| {
+                    if link.checksum.is_none() || !filter(key, link) {
+                        return false;
+                    }
+                    let LinkTarget::Resolved(target) = self.resolve_link(link, &type_folders)
+                    else {
+                        return false;
+                    };
+                    // Outdated links are updated to the algorithm of self
+                    let path = self.full_path_unchecked(&target);
+                    if link.algorithm.checksum_file(&path) == link.checksum {
+                        return false;
+                    }
+                    let current = self.checksum_algorithm.checksum_file(&path);
+                    if current.is_none() {
+                        return false;
+                    }
+                    link.checksum = current;
+                    link.algorithm = self.checksum_algorithm;
+                    return true;
+                })?;
+                if file_modified {
+                    modified = true;
+                    if !rewritten.contains(&path) {
+                        rewritten.push(path);
+                    }
+                }
+            }
+            if !modified {
+                break;
+            }
+        }
+        return Ok(rewritten);
+    }
+
+    /**
+    Removes all [`Cache`](crate::Cache) entries whose file doesn't exist
+    anymore or whose checksum differs from the one stored in the entry.
+    Manually created entries (without checksum) are kept. Returns the number
+    of evicted entries.
+     */
+    pub(crate) fn evict_stale_cache_entries(&mut self) -> usize {
+        let mut evicted = 0;
+        let dir = self.dir.clone();
+        let format = &self.format;
+        let type_formats = &self.type_formats;
+        let sharding = self.sharding;
+        let shared_cache = self.shared_cache.clone();
+        let mut shared_guard = shared_cache
+            .as_ref()
+            .map(|shared_cache| shared_cache.write());
+        let cache = match shared_guard.as_mut() {
+            Some(guard) => &mut **guard,
+            None => &mut self.cache,
+        };
+        for subcache in cache.values_mut() {
+            let len = subcache.len();
+            subcache.retain(|name, entry| {
+                let Some(checksum_in_cache) = entry.checksum else {
+                    return true;
+                };
+                let type_name = entry.arc.typetag_name();
+                let file_ext = type_formats
+                    .get(OsStr::new(type_name))
+                    .unwrap_or(format)
+                    .file_ext();
+                let mut file_name = name.clone();
+                if !file_ext.is_empty() {
+                    file_name.push(".");
+                    file_name.push(file_ext);
+                }
+                let mut path = dir.join(type_name);
+                if let Some(shard) = sharding.shard(name) {
+                    path.push(shard);
+                }
+                path.push(file_name);
+                return entry.algorithm.checksum_file(&path) == Some(checksum_in_cache);
+            });
+            evicted += len - subcache.len();
+        }
+        cache.retain(|_, subcache| !subcache.is_empty());
+        return evicted;
+    }
+}
@@ -0,0 +1,800 @@
+/*!
+This module contains functionality to combine databases. The central method
+is [`DatabaseManager::merge_from`], which imports all entries of another
+database. Entries which exist in both databases with different contents are
+conflicts, which are resolved according to a [`MergeStrategy`]. The results
+are returned as a [`MergeSummary`].
+
+[`DatabaseManager::sync_to`] mirrors a database into another one, i.e. the
+target database is made to reflect the source database (see [`SyncOptions`]
+and [`SyncSummary`]).
+
+[`DatabaseManager::copy_entry_to`] and [`DatabaseManager::copy_all_to`] copy
+selected or all entries (together with all entries they link to) into another
+database, overwriting existing entries.
+
+The entries are transferred as untyped [`Value`]s, hence no concrete types are
+needed and the databases may use different [`Format`](crate::Format)s.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::database_manager::replace_file;
+use crate::{
+    DatabaseKey, DatabaseKeyBuf, DatabaseManager, DatabaseReport, FileStatus, LinkTarget, Problem,
+    Value,
+};
+
+/**
+Options to modify the behaviour of [`DatabaseManager::merge_from`]. See the
+individual fields for details.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /**
+    Specifies how conflicts are resolved, i.e. entries which exist in both
+    databases with different contents. See [`MergeStrategy`] for more.
+
+    Defaults to [`MergeStrategy::KeepOurs`].
+     */
+    pub strategy: MergeStrategy,
+}
+
+/**
+Specifies how [`DatabaseManager::merge_from`] resolves a [`MergeConflict`].
+ */
+#[derive(Debug, Clone, Default)]
+pub enum MergeStrategy {
+    #[default]
+    /**
+    The entry of the database which is merged into ("ours") is kept.
+     */
+    KeepOurs,
+    /**
+    The entry of the database which is merged from ("theirs") overwrites ours.
+     */
+    KeepTheirs,
+    /**
+    The entry whose file has been modified more recently is kept. If the
+    modification times are equal or not available, ours is kept.
+     */
+    KeepNewer,
+    /**
+    Ours is kept and theirs is imported under a new name: The suffix
+    `_<counter>` is appended to the name, where `<counter>` is the first number
+    (starting at 0) which results in an unused name. Links within the imported
+    entries are updated to the new name.
+     */
+    Rename,
+    /**
+    The [`MergeResolver`] callback decides for every conflict.
+     */
+    Resolve(MergeResolver),
+}
+
+/**
+The decision for a single [`MergeConflict`] returned by a [`MergeResolver`].
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeDecision {
+    /**
+    See [`MergeStrategy::KeepOurs`].
+     */
+    KeepOurs,
+    /**
+    See [`MergeStrategy::KeepTheirs`].
+     */
+    KeepTheirs,
+    /**
+    See [`MergeStrategy::Rename`].
+     */
+    Rename,
+}
+
+/**
+An entry which exists in both databases of a [`DatabaseManager::merge_from`]
+call with different contents.
+ */
+#[derive(Debug, Clone)]
+pub struct MergeConflict {
+    /**
+    The key of the entry.
+     */
+    pub key: DatabaseKeyBuf,
+    /**
+    The path of the entry in the database which is merged into.
+     */
+    pub ours: PathBuf,
+    /**
+    The path of the entry in the database which is merged from.
+     */
+    pub theirs: PathBuf,
+}
+
+impl MergeConflict {
+    /**
+    Returns `true` if the file of theirs has been modified more recently than
+    the file of ours.
+     */
+    pub fn theirs_is_newer(&self) -> bool {
+        fn modified(path: &Path) -> Option<std::time::SystemTime> {
+            return fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+        }
+        match (modified(&self.ours), modified(&self.theirs)) {
+            (Some(ours), Some(theirs)) => return theirs > ours,
+            _ => return false,
+        }
+    }
+}
+
+/**
+Wraps a callback which resolves conflicts for [`MergeStrategy::Resolve`].
+
+# Examples
+
+```
+use serde_mosaic::*;
+
+// Materials are curated centrally, everything else is taken from the engineers
+let strategy = MergeStrategy::Resolve(MergeResolver::new(|conflict| {
+    if conflict.key.type_name == "Material" {
+        return MergeDecision::KeepOurs;
+    }
+    return MergeDecision::KeepTheirs;
+}));
+```
+ */
+#[derive(Clone)]
+pub struct MergeResolver(Arc<ResolveFn>);
+
+/**
+Callback wrapped by a [`MergeResolver`].
+ */
+type ResolveFn = dyn Fn(&MergeConflict) -> MergeDecision + Send + Sync;
+
+impl MergeResolver {
+    /**
+    Wraps the callback `f` into a [`MergeResolver`].
+     */
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&MergeConflict) -> MergeDecision + Send + Sync + 'static,
+    {
+        return Self(Arc::new(f));
+    }
+
+    /**
+    Returns the decision for `conflict`.
+     */
+    pub fn resolve(&self, conflict: &MergeConflict) -> MergeDecision {
+        return (self.0)(conflict);
+    }
+}
+
+impl Debug for MergeResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.write_str("MergeResolver");
+    }
+}
+
+/**
+This struct is returned by [`DatabaseManager::merge_from`] and contains
+information about the merge within its fields.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MergeSummary {
+    /**
+    Entries which only existed in the database merged from and have been
+    added.
+     */
+    pub added: Vec<DatabaseKeyBuf>,
+    /**
+    Conflicting entries which have been overwritten by theirs.
+     */
+    pub overwritten: Vec<DatabaseKeyBuf>,
+    /**
+    Conflicting entries for which ours has been kept.
+     */
+    pub kept: Vec<DatabaseKeyBuf>,
+    /**
+    Conflicting entries which have been imported under a new name. The first
+    key is the original key, the second one the new key.
+     */
+    pub renamed: Vec<(DatabaseKeyBuf, DatabaseKeyBuf)>,
+    /**
+    Entries which exist in both databases with identical contents.
+     */
+    pub unchanged: Vec<DatabaseKeyBuf>,
+    /**
+    Entries of the database merged from which could not be read and have
+    therefore been skipped.
+     */
+    pub report: DatabaseReport,
+}
+
+impl DatabaseManager {
+    /**
+    Imports all entries of the database of `other` into `self`. Entries which
+    only exist in `other` are added. Entries which exist in both databases
+    with different contents are resolved according to
+    [`MergeOptions::strategy`]. Entries are compared by their parsed contents,
+    so formatting differences or different [`Format`](crate::Format)s don't
+    cause conflicts.
+
+    Links within the imported entries are resolved within `other` as
+    described in [`DatabaseManager::verify_entry`]. If the linked entry has
+    been imported under a new name (see [`MergeStrategy::Rename`]), the link
+    is updated. If the checksum of a link matched the linked file within
+    `other` and the linked entry has been imported (or has identical contents
+    in both databases), the checksum is updated to match the file within
+    `self`.
+
+    Returns an error if `other` manages the same directory as `self` or if the
+    database of `self` is locked exclusively by another manager. Entries of
+    `other` which can't be read are skipped and reported in
+    [`MergeSummary::report`].
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut shared = DatabaseManager::open("/path/to/shared", SerdeYaml).expect("directory exists");
+    let personal = DatabaseManager::open("/path/to/personal", SerdeYaml).expect("directory exists");
+    let merge_options = MergeOptions {
+        strategy: MergeStrategy::KeepNewer,
+    };
+    let summary = shared.merge_from(&personal, &merge_options).expect("databases are accessible");
+    println!("added {} entries", summary.added.len());
+    ```
+     */
+    pub fn merge_from(
+        &mut self,
+        other: &DatabaseManager,
+        merge_options: &MergeOptions,
+    ) -> std::io::Result<MergeSummary> {
+        if let (Ok(ours), Ok(theirs)) = (self.dir().canonicalize(), other.dir().canonicalize())
+            && ours == theirs
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "A database can't be merged with itself",
+            ));
+        }
+        self.check_database_lock()?;
+
+        let mut summary = MergeSummary::default();
+        let their_keys = other.entry_keys()?;
+        let their_key_set: HashSet<&DatabaseKeyBuf> = their_keys.iter().collect();
+
+        // Decide what to do with every entry of other. The values are only
+        // written afterwards, since links may point to renamed entries.
+        let mut imports: Vec<(DatabaseKeyBuf, DatabaseKeyBuf, Value)> = Vec::new();
+        let mut new_names: HashMap<DatabaseKeyBuf, DatabaseKeyBuf> = HashMap::new();
+        for key in their_keys.iter() {
+            let value = match other.read_value(key) {
+                Ok(value) => value,
+                Err(status) => {
+                    let path = other.full_path_unchecked(key);
+                    match status {
+                        FileStatus::Unreadable(message) => {
+                            summary.report.push(Problem::Unreadable { path, message })
+                        }
+                        FileStatus::Unparseable(message) => {
+                            summary.report.push(Problem::Unparseable { path, message })
+                        }
+                        FileStatus::Missing | FileStatus::Valid => (),
+                    }
+                    continue;
+                }
+            };
+
+            let our_path = self.full_path_unchecked(key);
+            if !our_path.exists() {
+                summary.added.push(key.clone());
+                imports.push((key.clone(), key.clone(), value));
+                continue;
+            }
+            if self.read_value(key).is_ok_and(|ours| ours == value) {
+                summary.unchanged.push(key.clone());
+                continue;
+            }
+
+            let conflict = MergeConflict {
+                key: key.clone(),
+                ours: our_path,
+                theirs: other.full_path_unchecked(key),
+            };
+            let decision = match &merge_options.strategy {
+                MergeStrategy::KeepOurs => MergeDecision::KeepOurs,
+                MergeStrategy::KeepTheirs => MergeDecision::KeepTheirs,
+                MergeStrategy::KeepNewer => {
+                    if conflict.theirs_is_newer() {
+                        MergeDecision::KeepTheirs
+                    } else {
+                        MergeDecision::KeepOurs
+                    }
+                }
+                MergeStrategy::Rename => MergeDecision::Rename,
+                MergeStrategy::Resolve(resolver) => resolver.resolve(&conflict),
+            };
+            match decision {
+                MergeDecision::KeepOurs => summary.kept.push(key.clone()),
+                MergeDecision::KeepTheirs => {
+                    summary.overwritten.push(key.clone());
+                    imports.push((key.clone(), key.clone(), value));
+                }
+                MergeDecision::Rename => {
+                    let mut counter: u64 = 0;
+                    let new_key = loop {
+                        let mut name = key.name.clone();
+                        name.push(format!("_{}", counter));
+                        let candidate = DatabaseKeyBuf::new(key.type_name.clone(), name);
+                        if !self.exists(&candidate)
+                            && !their_key_set.contains(&candidate)
+                            && !new_names.values().any(|new_key| new_key == &candidate)
+                        {
+                            break candidate;
+                        }
+                        counter += 1;
+                    };
+                    new_names.insert(key.clone(), new_key.clone());
+                    summary.renamed.push((key.clone(), new_key.clone()));
+                    imports.push((key.clone(), new_key, value));
+                }
+            }
+        }
+
+        // Links whose checksum needs to be updated after writing. Only links
+        // to entries whose contents are the same in both databases are updated.
+        let synced: HashSet<&DatabaseKeyBuf> = imports
+            .iter()
+            .map(|(key, _, _)| key)
+            .chain(summary.unchanged.iter())
+            .collect();
+        let their_type_folders = other.type_folders()?;
+        let mut refreshed_links: HashSet<(DatabaseKeyBuf, String)> = HashSet::new();
+        let mut written_keys: Vec<DatabaseKeyBuf> = Vec::new();
+        for (_, target, mut value) in imports.iter().cloned() {
+            value.for_each_link_mut(&mut |link| {
+                let LinkTarget::Resolved(linked) = other.resolve_link(link, &their_type_folders)
+                else {
+                    return false;
+                };
+                let is_valid = link.checksum.is_some()
+                    && link
+                        .algorithm
+                        .checksum_file(&other.full_path_unchecked(&linked))
+                        == link.checksum;
+                let mut modified = false;
+                if let Some(new_key) = new_names.get(&linked) {
+                    link.name = new_key.name.to_string_lossy().into_owned();
+                    modified = true;
+                }
+                if is_valid && synced.contains(&linked) {
+                    refreshed_links.insert((target.clone(), link.name.clone()));
+                }
+                return modified;
+            });
+
+            let path = self.full_path_unchecked(&target);
+            if let Some(folder) = path.parent() {
+                fs::create_dir_all(folder)?;
+            }
+            self.check_lock(&path)?;
+            let existed = path.exists();
+            let bytes = self.serialize_value(&path, &value)?;
+            let bytes = self.encode_file(&path, bytes)?;
+            replace_file(&path, &bytes).map_err(|err| {
+                Error::new(
+                    err.kind(),
+                    format!("Could not write file {}: {}", path.display(), err),
+                )
+            })?;
+            self.sign_file(&path)?;
+            self.notify_written(&path, existed);
+            written_keys.push(target);
+        }
+
+        self.refresh_link_checksums_where(&written_keys, |key, link| {
+            return refreshed_links.contains(&(key.clone(), link.name.clone()));
+        })?;
+        self.evict_stale_cache_entries();
+        return Ok(summary);
+    }
+}
+
+/**
+Options to modify the behaviour of [`DatabaseManager::sync_to`]. See the
+individual fields for details.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct SyncOptions {
+    /**
+    If `true`, entries of the target database which don't exist in the source
+    database are removed.
+
+    Defaults to `false`.
+     */
+    pub delete_extraneous: bool,
+    /**
+    If `true`, the target database is not modified. The returned
+    [`SyncSummary`] lists the changes which would have been made.
+
+    Defaults to `false`.
+     */
+    pub dry_run: bool,
+}
+
+/**
+This struct is returned by [`DatabaseManager::sync_to`] and contains the delta
+between the source and the target database within its fields.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct SyncSummary {
+    /**
+    Entries which didn't exist in the target database and have been created.
+     */
+    pub created: Vec<DatabaseKeyBuf>,
+    /**
+    Entries whose contents differed and which have been overwritten in the
+    target database.
+     */
+    pub updated: Vec<DatabaseKeyBuf>,
+    /**
+    Entries which only existed in the target database and have been removed
+    (see [`SyncOptions::delete_extraneous`]).
+     */
+    pub deleted: Vec<DatabaseKeyBuf>,
+    /**
+    Entries which are identical in both databases.
+     */
+    pub unchanged: Vec<DatabaseKeyBuf>,
+    /**
+    Entries of the source database which could not be read and have
+    therefore been skipped.
+     */
+    pub report: DatabaseReport,
+}
+
+impl DatabaseManager {
+    /**
+    Makes the database of `target` reflect the database of `self`: Entries
+    which don't exist in `target` are created and entries whose contents
+    differ are overwritten. If [`SyncOptions::delete_extraneous`] is `true`,
+    entries which only exist in `target` are removed. `self` is never
+    modified.
+
+    The entries are compared one by one. If both databases use the same file
+    extension, the (decrypted and decompressed) file contents are compared and
+    copied byte by byte, so the checksums stored in links stay valid.
+    Otherwise, the entries are compared by their parsed contents and
+    converted into the [`Format`](crate::Format) of `target` (like in
+    [`DatabaseManager::transcode_all`]); links whose checksum was valid in
+    `self` are updated afterwards.
+
+    Returns an error if `target` manages the same directory as `self` or if
+    the database of `target` is locked exclusively by another manager. Entries
+    of `self` which can't be read are skipped and reported in
+    [`SyncSummary::report`]; the corresponding entries of `target` are never
+    removed.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let curated = DatabaseManager::open("/path/to/curated", SerdeYaml).expect("directory exists");
+    let mut rig = DatabaseManager::open("/mnt/rig_1/db", SerdeYaml).expect("directory exists");
+    let sync_options = SyncOptions {
+        delete_extraneous: true,
+        ..Default::default()
+    };
+    let summary = curated.sync_to(&mut rig, &sync_options).expect("databases are accessible");
+    println!("updated {} entries", summary.updated.len());
+    ```
+     */
+    pub fn sync_to(
+        &self,
+        target: &mut DatabaseManager,
+        sync_options: &SyncOptions,
+    ) -> std::io::Result<SyncSummary> {
+        if let (Ok(source), Ok(target)) = (self.dir().canonicalize(), target.dir().canonicalize())
+            && source == target
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "A database can't be synchronized with itself",
+            ));
+        }
+        target.check_database_lock()?;
+
+        let mut summary = SyncSummary::default();
+        let source_keys = self.entry_keys()?;
+        let type_folders = self.type_folders()?;
+        let mut valid_links: HashSet<(DatabaseKeyBuf, String)> = HashSet::new();
+        let mut written_keys: Vec<DatabaseKeyBuf> = Vec::new();
+
+        for key in source_keys.iter() {
+            let source_path = self.full_path_unchecked(key);
+            let target_path = target.full_path_unchecked(key);
+            let same_ext =
+                self.file_ext_for_type(&key.type_name) == target.file_ext_for_type(&key.type_name);
+
+            // Serialized entry which is written into target, if it differs
+            let bytes = if same_ext {
+                let bytes = match fs::read(&source_path)
+                    .and_then(|bytes| self.decode_file(&source_path, bytes))
+                {
+                    Ok(bytes) => bytes,
+                    Err(err) => {
+                        summary.report.push(Problem::Unreadable {
+                            path: source_path,
+                            message: err.to_string(),
+                        });
+                        continue;
+                    }
+                };
+                let existing = fs::read(&target_path)
+                    .and_then(|existing| target.decode_file(&target_path, existing));
+                if existing.is_ok_and(|existing| existing == bytes) {
+                    summary.unchanged.push(key.clone());
+                    continue;
+                }
+                bytes
+            } else {
+                let value = match self.read_value(key) {
+                    Ok(value) => value,
+                    Err(status) => {
+                        match status {
+                            FileStatus::Unreadable(message) => {
+                                summary.report.push(Problem::Unreadable {
+                                    path: source_path,
+                                    message,
+                                })
+                            }
+                            FileStatus::Unparseable(message) => {
+                                summary.report.push(Problem::Unparseable {
+                                    path: source_path,
+                                    message,
+                                })
+                            }
+                            FileStatus::Missing | FileStatus::Valid => (),
+                        }
+                        continue;
+                    }
+                };
+                if target
+                    .read_value(key)
+                    .is_ok_and(|existing| existing == value)
+                {
+                    summary.unchanged.push(key.clone());
+                    continue;
+                }
+                for link in value.links() {
+                    if let Some(checksum_in_link) = link.checksum
+                        && let LinkTarget::Resolved(linked) =
+                            self.resolve_link(&link, &type_folders)
+                        && link
+                            .algorithm
+                            .checksum_file(&self.full_path_unchecked(&linked))
+                            == Some(checksum_in_link)
+                    {
+                        valid_links.insert((key.clone(), link.name));
+                    }
+                }
+                target.serialize_value(&target_path, &value)?
+            };
+
+            if target_path.exists() {
+                summary.updated.push(key.clone());
+            } else {
+                summary.created.push(key.clone());
+            }
+            if sync_options.dry_run {
+                continue;
+            }
+            if let Some(folder) = target_path.parent() {
+                fs::create_dir_all(folder)?;
+            }
+            target.check_lock(&target_path)?;
+            let existed = target_path.exists();
+            let bytes = target.encode_file(&target_path, bytes)?;
+            replace_file(&target_path, &bytes).map_err(|err| {
+                Error::new(
+                    err.kind(),
+                    format!("Could not write file {}: {}", target_path.display(), err),
+                )
+            })?;
+            target.sign_file(&target_path)?;
+            target.notify_written(&target_path, existed);
+            written_keys.push(key.clone());
+        }
+
+        if sync_options.delete_extraneous {
+            let source_key_set: HashSet<&DatabaseKeyBuf> = source_keys.iter().collect();
+            for key in target.entry_keys()? {
+                if source_key_set.contains(&key) {
+                    continue;
+                }
+                if !sync_options.dry_run {
+                    target.remove(&key)?;
+                }
+                summary.deleted.push(key);
+            }
+        }
+
+        if !sync_options.dry_run {
+            // Only entries converted into another format have such links
+            if !valid_links.is_empty() {
+                target.refresh_link_checksums_where(&written_keys, |key, link| {
+                    return valid_links.contains(&(key.clone(), link.name.clone()));
+                })?;
+            }
+            target.evict_stale_cache_entries();
+        }
+        return Ok(summary);
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Copies the entry `key` and all entries it links to (transitively) from
+    `self` into the database of `target`. Entries which already exist in
+    `target` are overwritten, `self` is never modified. Returns the keys of
+    all copied entries, sorted by type name first and name second.
+
+    Links are resolved as described in [`DatabaseManager::verify_entry`]. If a
+    link is ambiguous, all candidates are copied, dangling links are copied
+    as they are. If both databases use the same file extension for a type,
+    the (decrypted and decompressed) file contents are copied byte by byte.
+    Otherwise, the entry is converted into the [`Format`](crate::Format) of
+    `target` (like in [`DatabaseManager::transcode_all`]) and links whose
+    checksum was valid in `self` are updated afterwards.
+
+    Returns an error of kind [`ErrorKind::NotFound`] if `key` doesn't exist
+    and of kind [`ErrorKind::InvalidData`] if one of the copied entries can't
+    be read or parsed. An error is also returned if `target` manages the same
+    directory as `self` or if the database of `target` is locked exclusively
+    by another manager.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let staging = DatabaseManager::open("/path/to/staging", SerdeYaml).expect("directory exists");
+    let mut production =
+        DatabaseManager::open("/path/to/production", SerdeYaml).expect("directory exists");
+    let copied = staging
+        .copy_entry_to(&mut production, ("Shirt", "polo"))
+        .expect("entry exists");
+    println!("promoted {} entries", copied.len());
+    ```
+     */
+    pub fn copy_entry_to<'a, K: Into<DatabaseKey<'a>>>(
+        &self,
+        target: &mut DatabaseManager,
+        key: K,
+    ) -> std::io::Result<Vec<DatabaseKeyBuf>> {
+        let key = DatabaseKeyBuf::from(key.into());
+        if !self.exists(&key) {
+            return Err(Error::new(ErrorKind::NotFound, format!("No entry {}", key)));
+        }
+        return self.copy_closure_to(target, vec![key]);
+    }
+
+    /**
+    Like [`DatabaseManager::copy_entry_to`], but copies all entries of `self`
+    into the database of `target`. Unlike [`DatabaseManager::sync_to`],
+    entries are always written and no entries of `target` are removed.
+     */
+    pub fn copy_all_to(
+        &self,
+        target: &mut DatabaseManager,
+    ) -> std::io::Result<Vec<DatabaseKeyBuf>> {
+        return self.copy_closure_to(target, self.entry_keys()?);
+    }
+
+    /**
+    Copies the entries `keys` and all entries they link to into `target`, see
+    [`DatabaseManager::copy_entry_to`].
+     */
+    fn copy_closure_to(
+        &self,
+        target: &mut DatabaseManager,
+        mut pending: Vec<DatabaseKeyBuf>,
+    ) -> std::io::Result<Vec<DatabaseKeyBuf>> {
+        if let (Ok(source), Ok(target)) = (self.dir().canonicalize(), target.dir().canonicalize())
+            && source == target
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "A database can't be copied into itself",
+            ));
+        }
+        target.check_database_lock()?;
+
+        let type_folders = self.type_folders()?;
+        let mut visited: HashSet<DatabaseKeyBuf> = HashSet::new();
+        let mut valid_links: HashSet<(DatabaseKeyBuf, String)> = HashSet::new();
+        let mut copied: Vec<DatabaseKeyBuf> = Vec::new();
+
+        while let Some(key) = pending.pop() {
+            if !visited.insert(key.clone()) {
+                continue;
+            }
+            let source_path = self.full_path_unchecked(&key);
+            let target_path = target.full_path_unchecked(&key);
+            let same_ext =
+                self.file_ext_for_type(&key.type_name) == target.file_ext_for_type(&key.type_name);
+
+            // The entry is parsed in any case to find its links
+            let value = self.read_value(&key).map_err(|status| {
+                let message = match status {
+                    FileStatus::Unreadable(message) | FileStatus::Unparseable(message) => message,
+                    FileStatus::Missing | FileStatus::Valid => "file is missing".to_string(),
+                };
+                return Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Could not read file {}: {}", source_path.display(), message),
+                );
+            })?;
+            for link in value.links() {
+                match self.resolve_link(&link, &type_folders) {
+                    LinkTarget::Resolved(linked) => {
+                        if !same_ext
+                            && link.checksum.is_some()
+                            && link
+                                .algorithm
+                                .checksum_file(&self.full_path_unchecked(&linked))
+                                == link.checksum
+                        {
+                            valid_links.insert((key.clone(), link.name));
+                        }
+                        pending.push(linked);
+                    }
+                    LinkTarget::Ambiguous(candidates) => pending.extend(candidates),
+                    LinkTarget::Dangling => (),
+                }
+            }
+
+            let bytes = if same_ext {
+                fs::read(&source_path).and_then(|bytes| self.decode_file(&source_path, bytes))?
+            } else {
+                target.serialize_value(&target_path, &value)?
+            };
+            if let Some(folder) = target_path.parent() {
+                fs::create_dir_all(folder)?;
+            }
+            target.check_lock(&target_path)?;
+            let existed = target_path.exists();
+            let bytes = target.encode_file(&target_path, bytes)?;
+            replace_file(&target_path, &bytes).map_err(|err| {
+                Error::new(
+                    err.kind(),
+                    format!("Could not write file {}: {}", target_path.display(), err),
+                )
+            })?;
+            target.sign_file(&target_path)?;
+            target.notify_written(&target_path, existed);
+            copied.push(key);
+        }
+
+        if !valid_links.is_empty() {
+            target.refresh_link_checksums_where(&copied, |key, link| {
+                return valid_links.contains(&(key.clone(), link.name.clone()));
+            })?;
+        }
+        target.evict_stale_cache_entries();
+        copied.sort();
+        return Ok(copied);
+    }
+}
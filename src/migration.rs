@@ -0,0 +1,549 @@
+/*!
+This module contains functionality for migrating the entries of a database,
+e.g. after the definition of a struct has been changed. The central method is
+[`DatabaseManager::map_all`], which reads all entries of a type, transforms
+them and writes them back, reporting the results as a [`MapSummary`].
+
+Renamed fields don't require a migration of the files: Registering the old
+field names via [`DatabaseManager::set_field_alias`] allows reading historical
+files directly. More complex changes of a struct layout can be handled at read
+time as well by tagging the entries with a schema version and registering
+transformations between the versions in a [`MigrationRegistry`].
+ */
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::{DatabaseEntry, DatabaseKeyBuf, DatabaseManager, Value, WriteOptions};
+
+/**
+Name of the field which stores the schema version of an entry, see
+[`MigrationRegistry`].
+ */
+pub const VERSION_FIELD: &str = "__version";
+
+/**
+Callback which transforms the contents of an entry from one version to the
+next, see [`MigrationRegistry::register`].
+ */
+type MigrationFn = dyn Fn(Value) -> Value + Send + Sync;
+
+/**
+Schema version and migrations of a single type.
+ */
+#[derive(Clone, Default)]
+struct TypeMigrations {
+    version: u32,
+    migrations: HashMap<u32, Arc<MigrationFn>>,
+}
+
+/**
+Specifies the schema versions of the types of a
+[`DatabaseManager`] and how entries written with an older version are
+converted to the current one. See [`DatabaseManager::set_migrations`].
+
+Entries of a type with a version (see [`MigrationRegistry::version`]) are
+tagged with a [`VERSION_FIELD`] when they are written. When such an entry is
+read, the migrations registered via [`MigrationRegistry::register`] are
+applied one after another to its contents, starting with the version found in
+the file, until the current version is reached. Files without a version field
+(e.g. written before versioning was enabled) have version 0. The version field
+is removed before the entry is deserialized, so it doesn't need to be part of
+the struct.
+
+The migrations are applied to the untyped [`Value`] of the entry contents,
+i.e. the map of its fields (without the type tag). Therefore, the
+[`Format`](crate::Format) of the manager needs to support
+[`Format::deserialize_value`](crate::Format::deserialize_value) and
+[`Format::serialize_value`](crate::Format::serialize_value). Migrations are
+applied before the field aliases (see [`DatabaseManager::set_field_alias`]),
+so they operate on the layout which is actually stored in the file. The files
+themselves are not modified; use [`DatabaseManager::map_all`] to write the
+migrated entries back.
+
+Reading an entry fails with an error of kind [`ErrorKind::InvalidData`] if its
+version is newer than the current version of its type or if a migration
+between two versions is missing.
+
+# Examples
+
+```no_run
+use std::ffi::OsStr;
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+    // Version 0 stored the price in cents as `price`
+    price_in_euros: f64,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+let migrations = MigrationRegistry::new()
+    .version::<Material>(1)
+    .register::<Material, _>(0, |mut contents| {
+        if let Some(Value::I64(cents)) = contents.remove("price") {
+            contents.insert("price_in_euros", Value::F64(cents as f64 / 100.0));
+        }
+        contents
+    });
+
+let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+dbm.set_migrations(Some(migrations));
+let steel: Material = dbm.read("steel").expect("old file can be read");
+```
+ */
+#[derive(Clone, Default)]
+pub struct MigrationRegistry {
+    types: HashMap<OsString, TypeMigrations>,
+}
+
+impl MigrationRegistry {
+    /**
+    Creates a new registry without any versioned types.
+     */
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /**
+    Sets the current schema version of the type `T`. The type name is derived
+    via [`DatabaseEntry::folder_name`].
+     */
+    pub fn version<T: DatabaseEntry>(self, version: u32) -> Self {
+        return self.version_type(T::folder_name(), version);
+    }
+
+    /**
+    Sets the current schema version of the type with the given folder name.
+     */
+    pub fn version_type<O: Into<OsString>>(mut self, type_name: O, version: u32) -> Self {
+        self.types.entry(type_name.into()).or_default().version = version;
+        return self;
+    }
+
+    /**
+    Registers the migration `f` of the type `T`, which converts the contents of
+    an entry from version `from` to version `from + 1`. An existing migration
+    of `T` from the same version is replaced. Registering a migration doesn't
+    change the current version of `T` (see [`MigrationRegistry::version`]).
+     */
+    pub fn register<T, F>(self, from: u32, f: F) -> Self
+    where
+        T: DatabaseEntry,
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+    {
+        return self.register_type(T::folder_name(), from, f);
+    }
+
+    /**
+    Like [`MigrationRegistry::register`], but for the type with the given
+    folder name.
+     */
+    pub fn register_type<O, F>(mut self, type_name: O, from: u32, f: F) -> Self
+    where
+        O: Into<OsString>,
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+    {
+        self.types
+            .entry(type_name.into())
+            .or_default()
+            .migrations
+            .insert(from, Arc::new(f));
+        return self;
+    }
+
+    /**
+    Returns the current schema version of the type with the given folder name
+    or [`None`] if the type isn't versioned. A type becomes versioned by
+    calling [`MigrationRegistry::version`] or [`MigrationRegistry::register`]
+    for it; the version defaults to 0.
+     */
+    pub fn current_version<O: AsRef<OsStr>>(&self, type_name: O) -> Option<u32> {
+        return self
+            .types
+            .get(type_name.as_ref())
+            .map(|migrations| migrations.version);
+    }
+
+    /**
+    Converts the `contents` of an entry of the type `type_name` from version
+    `from` to the current version of the type.
+     */
+    fn migrate(&self, type_name: &OsStr, from: u32, mut contents: Value) -> std::io::Result<Value> {
+        let Some(migrations) = self.types.get(type_name) else {
+            return Ok(contents);
+        };
+        if from > migrations.version {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "entry has version {}, but the current version of type {} is {}",
+                    from,
+                    type_name.to_string_lossy(),
+                    migrations.version
+                ),
+            ));
+        }
+        for version in from..migrations.version {
+            let migration = migrations.migrations.get(&version).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "no migration of type {} from version {} to {}",
+                        type_name.to_string_lossy(),
+                        version,
+                        version + 1
+                    ),
+                )
+            })?;
+            contents = migration(contents);
+        }
+        return Ok(contents);
+    }
+}
+
+impl std::fmt::Debug for MigrationRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut versions: Vec<(&OsString, u32)> = self
+            .types
+            .iter()
+            .map(|(type_name, migrations)| (type_name, migrations.version))
+            .collect();
+        versions.sort();
+        return f
+            .debug_struct("MigrationRegistry")
+            .field("versions", &versions)
+            .finish();
+    }
+}
+
+/**
+This struct is returned by [`DatabaseManager::map_all`] and contains
+information about the migration within its fields.
+ */
+#[derive(Debug, Default)]
+pub struct MapSummary {
+    /**
+    Paths of all files which have been written (including linked entries).
+     */
+    pub written_files: Vec<PathBuf>,
+    /**
+    Keys of all entries which could not be read or written, together with the
+    corresponding error. The files of these entries are left untouched.
+     */
+    pub failures: Vec<(DatabaseKeyBuf, Error)>,
+}
+
+impl MapSummary {
+    /**
+    Returns `true` if all entries have been migrated successfully.
+     */
+    pub fn is_success(&self) -> bool {
+        return self.failures.is_empty();
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Reads every entry of type `T`, transforms it with `f` and writes the result
+    back using the given `write_options`. This is useful for data migrations,
+    e.g. after a field has been added to `T`.
+
+    Since the entries already exist, [`WriteOptions::name_collisions`] should
+    usually be set to [`NameCollisions::Overwrite`](crate::NameCollisions::Overwrite),
+    otherwise the existing files are kept. Every file is replaced atomically,
+    i.e. it either contains the old or the new entry, even if writing fails.
+    If `f` changes the name of an entry, the entry is written under the new
+    name and the old file is kept.
+
+    Entries which can't be read or written are skipped and listed in
+    [`MapSummary::failures`]; the remaining entries are migrated nevertheless.
+    An error is only returned if the type folder of `T` can't be accessed.
+    Migrated entries of type `T` are removed from the [`Cache`](crate::Cache).
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        price: f64,
+        #[serde(default)]
+        currency: String,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+
+    let summary = dbm
+        .map_all(|mut material: Material| {
+            if material.currency.is_empty() {
+                material.currency = "EUR".into();
+            }
+            material
+        }, &write_options)
+        .expect("type folder is accessible");
+    for (key, err) in summary.failures.iter() {
+        println!("could not migrate {}: {}", key, err);
+    }
+    ```
+     */
+    pub fn map_all<T: DatabaseEntry, F: FnMut(T) -> T>(
+        &mut self,
+        mut f: F,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<MapSummary> {
+        let type_name = OsStr::new(T::folder_name());
+        let mut summary = MapSummary::default();
+
+        for name in self.entry_names(type_name)? {
+            let key = DatabaseKeyBuf::new(type_name, &name);
+            let instance = match self.read::<T, _>(&name) {
+                Ok(instance) => f(instance),
+                Err(err) => {
+                    summary.failures.push((key, err));
+                    continue;
+                }
+            };
+            match self.write_verbose(&instance, write_options) {
+                Ok((_, write_info)) => {
+                    summary.written_files.extend(write_info.created_files);
+                    summary.written_files.extend(write_info.overwritten_files);
+                }
+                Err(err) => summary.failures.push((key, err)),
+            }
+            if let Some(cached) = self.cache.get_mut(&TypeId::of::<T>()) {
+                cached.remove(instance.name());
+                cached.remove(&name);
+            }
+        }
+        return Ok(summary);
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Registers the field name `old` as an alias of the field `new` for all
+    entries within the type folder `type_name`. When such an entry is read,
+    a field named `old` is renamed to `new` before the entry is deserialized,
+    so files written before a field of a struct was renamed can still be
+    read. This applies to all reads, including linked entries (which are
+    subject to the aliases of their own type). If the file contains both
+    fields, `old` is not renamed. Aliases only affect the top-level fields of
+    an entry; renamed fields of nested (non-linked) structs still need
+    `#[serde(alias)]`.
+
+    If an alias for `old` already existed for this type, it is replaced and
+    its previous new name is returned.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        // Formerly called `price`
+        price_per_kg: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.set_field_alias("Material", "price", "price_per_kg");
+    let steel: Material = dbm.read("steel").expect("old file can be read");
+    ```
+     */
+    pub fn set_field_alias<T, A, B>(&mut self, type_name: T, old: A, new: B) -> Option<String>
+    where
+        T: Into<OsString>,
+        A: Into<String>,
+        B: Into<String>,
+    {
+        return self
+            .field_aliases
+            .entry(type_name.into())
+            .or_default()
+            .insert(old.into(), new.into());
+    }
+
+    /**
+    Returns the field aliases (old name to new name) registered for the type
+    folder `type_name` via [`DatabaseManager::set_field_alias`], if any.
+     */
+    pub fn field_aliases<T: AsRef<OsStr>>(&self, type_name: T) -> Option<&HashMap<String, String>> {
+        return self.field_aliases.get(type_name.as_ref());
+    }
+
+    /**
+    Removes the alias `old` of the type folder `type_name` and returns the new
+    name it was mapped to, if it existed.
+     */
+    pub fn remove_field_alias<T: AsRef<OsStr>>(
+        &mut self,
+        type_name: T,
+        old: &str,
+    ) -> Option<String> {
+        let aliases = self.field_aliases.get_mut(type_name.as_ref())?;
+        let new = aliases.remove(old);
+        if aliases.is_empty() {
+            self.field_aliases.remove(type_name.as_ref());
+        }
+        return new;
+    }
+
+    /**
+    Renames the fields within the serialized entry `bytes` of the type
+    `type_name` according to `aliases`. The bytes are only reserialized if a
+    field was renamed.
+     */
+    pub(crate) fn rename_fields(
+        &self,
+        type_name: &OsStr,
+        bytes: Vec<u8>,
+        aliases: &HashMap<String, String>,
+    ) -> std::io::Result<Vec<u8>> {
+        let format = self.format_for_type(type_name);
+        let mut value = format
+            .deserialize_value(&bytes)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        if !value.rename_entry_fields(aliases) {
+            return Ok(bytes);
+        }
+        return format
+            .serialize_value(&value)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err));
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Sets the [`MigrationRegistry`] of `self`. From now on, written entries of
+    versioned types are tagged with their current version and older entries
+    are migrated when they are read. Passing [`None`] disables versioning; the
+    version fields of existing files are then treated like any other field.
+     */
+    pub fn set_migrations(&mut self, migrations: Option<MigrationRegistry>) {
+        self.migrations = migrations;
+    }
+
+    /**
+    Returns the [`MigrationRegistry`] of `self`, if any.
+     */
+    pub fn migrations(&self) -> Option<&MigrationRegistry> {
+        return self.migrations.as_ref();
+    }
+
+    /**
+    Migrates the serialized entry `bytes` of the type `type_name` to the
+    current version of the type and removes its [`VERSION_FIELD`]. The bytes
+    are only reserialized if the entry contained a version field or was
+    migrated.
+     */
+    pub(crate) fn migrate_entry(
+        &self,
+        type_name: &OsStr,
+        bytes: Vec<u8>,
+    ) -> std::io::Result<Vec<u8>> {
+        let Some(registry) = self.migrations.as_ref() else {
+            return Ok(bytes);
+        };
+        let Some(version) = registry.current_version(type_name) else {
+            return Ok(bytes);
+        };
+        let format = self.format_for_type(type_name);
+        let mut value = format
+            .deserialize_value(&bytes)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        let Some(contents) = value.entry_contents_mut() else {
+            return Ok(bytes);
+        };
+        let found = match contents.remove(VERSION_FIELD) {
+            Some(found) => found
+                .as_u64()
+                .and_then(|found| u32::try_from(found).ok())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("field {} is not a valid version", VERSION_FIELD),
+                    )
+                })?,
+            None if version == 0 => return Ok(bytes),
+            None => 0,
+        };
+        let old = std::mem::replace(contents, Value::Null);
+        *contents = registry.migrate(type_name, found, old)?;
+        return format
+            .serialize_value(&value)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err));
+    }
+
+    /**
+    Inserts the [`VERSION_FIELD`] into the serialized entry `bytes` of the
+    type `type_name`, if the type is versioned. Entries whose contents are not
+    a map (e.g. unit structs) are not tagged.
+     */
+    pub(crate) fn tag_version(
+        &self,
+        type_name: &OsStr,
+        bytes: Vec<u8>,
+    ) -> std::io::Result<Vec<u8>> {
+        let Some(version) = self
+            .migrations
+            .as_ref()
+            .and_then(|registry| registry.current_version(type_name))
+        else {
+            return Ok(bytes);
+        };
+        let format = self.format_for_type(type_name);
+        let mut value = format
+            .deserialize_value(&bytes)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        let Some(Value::Map(fields)) = value.entry_contents_mut() else {
+            return Ok(bytes);
+        };
+        fields.retain(|(field, _)| field.as_str() != Some(VERSION_FIELD));
+        fields.insert(
+            0,
+            (
+                Value::String(VERSION_FIELD.into()),
+                Value::I64(version.into()),
+            ),
+        );
+        return format
+            .serialize_value(&value)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err));
+    }
+}
@@ -0,0 +1,130 @@
+/*!
+This module contains [`EntryProvider`], an adapter which exposes a database
+entry as a configuration source for the [`figment`] crate (requires the
+`figment` feature). This allows composing application settings from linked
+database entries without reading them into their concrete types first. See
+[`DatabaseManager::config_provider`].
+ */
+
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+use figment::value::{Dict, Map};
+use figment::{Metadata, Profile, Provider, Source};
+
+use crate::exchange::Inliner;
+use crate::{DatabaseKey, DatabaseKeyBuf, DatabaseManager, Problem, Value};
+
+/**
+A [`figment::Provider`] which provides the contents of a database entry with
+all links resolved. Created by [`DatabaseManager::config_provider`].
+
+The fields of the entry become the top-level keys of the configuration, the
+fields of linked entries are nested below the name of the linking field. By
+default, the configuration is provided for [`Profile::Default`]; this can be
+changed with [`EntryProvider::profile`].
+ */
+#[derive(Debug, Clone)]
+pub struct EntryProvider {
+    key: DatabaseKeyBuf,
+    path: PathBuf,
+    contents: Value,
+    profile: Profile,
+}
+
+impl EntryProvider {
+    /**
+    Sets the profile for which the configuration is provided.
+     */
+    pub fn profile<P: Into<Profile>>(mut self, profile: P) -> Self {
+        self.profile = profile.into();
+        return self;
+    }
+
+    /**
+    Returns the key of the provided database entry.
+     */
+    pub fn key(&self) -> &DatabaseKeyBuf {
+        return &self.key;
+    }
+}
+
+impl Provider for EntryProvider {
+    fn metadata(&self) -> Metadata {
+        return Metadata::named(format!("serde_mosaic entry {}", self.key))
+            .source(Source::File(self.path.clone()));
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, figment::Error> {
+        let value = figment::value::Value::serialize(&self.contents)?;
+        let dict = value.into_dict().ok_or_else(|| {
+            figment::Error::from(format!(
+                "database entry {} does not contain a map of fields",
+                self.key
+            ))
+        })?;
+        return Ok(self.profile.collect(dict));
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Creates an [`EntryProvider`] for the database entry specified by `key`,
+    which can be merged into a [`figment::Figment`] like any other provider.
+
+    The entry is read as an untyped [`Value`] and all links are resolved and
+    replaced by the contents of the linked entries (recursively), as described
+    in [`DatabaseManager::export_flat`]. The contents are read when calling
+    this method, later modifications of the database are not reflected by the
+    returned provider.
+
+    Returns an error of kind [`ErrorKind::NotFound`] if the entry doesn't
+    exist and of kind [`ErrorKind::InvalidData`] if the entry or one of its
+    linked entries can't be parsed or a link can't be resolved.
+
+    # Examples
+
+    ```no_run
+    use figment::Figment;
+    use figment::providers::Serialized;
+    use serde::{Deserialize, Serialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize, Default)]
+    struct Settings {
+        port: u16,
+        theme: String,
+    }
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let provider = dbm
+        .config_provider(("Settings", "production"))
+        .expect("entry is valid");
+    let settings: Settings = Figment::from(Serialized::defaults(Settings::default()))
+        .merge(provider)
+        .extract()
+        .expect("configuration is complete");
+    ```
+     */
+    pub fn config_provider<'a, K: Into<DatabaseKey<'a>>>(
+        &self,
+        key: K,
+    ) -> std::io::Result<EntryProvider> {
+        let key: DatabaseKeyBuf = key.into().into();
+        let path = self.full_path_unchecked(&key);
+        let inliner = Inliner::new(self)?;
+        let value = inliner.inline_entry(&key).map_err(|problem| {
+            let kind = match problem {
+                Problem::Missing { .. } => ErrorKind::NotFound,
+                _ => ErrorKind::InvalidData,
+            };
+            return Error::new(kind, problem.to_string());
+        })?;
+        return Ok(EntryProvider {
+            key,
+            path,
+            contents: value.into_entry_contents().unwrap_or(Value::Null),
+            profile: Profile::Default,
+        });
+    }
+}
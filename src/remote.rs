@@ -0,0 +1,479 @@
+/*!
+This module contains the [`RemoteDatabase`] client, which reads and writes the
+entries of a database served over HTTP (requires the `remote` feature). This
+allows thin clients to work with a central database without having access to
+its file system (e.g. via a network mount).
+
+The entries are addressed as `<url>/<type_name>/<name>` resources, where both
+path segments are percent-encoded:
+- `GET` returns the serialized entry as it is stored in its file (decrypted and
+decompressed, if necessary). With the query `?resolve`, the entry is returned with all links resolved and
+inlined instead (see [`DatabaseManager::export_flat`](crate::DatabaseManager::export_flat)).
+- `HEAD` is like `GET`, but without the response body.
+- `PUT` replaces the file of the entry with the request body.
+- `DELETE` removes the entry.
+
+Additionally, `GET <url>/` lists the type names and `GET <url>/<type_name>/`
+lists the entry names of a type (one percent-encoded name per line).
+
+The checksum of an entry file (calculated with the
+[`ChecksumAlgorithm`] of the serving manager) is transmitted in the
+`X-Mosaic-Checksum` header of all responses which refer to an existing file.
+`PUT` and `DELETE` requests can be made conditional with the `If-Match` header
+(containing the expected checksum of the current file) or the
+`If-None-Match: *` header (the entry must not exist yet), see
+[`Precondition`]. If the precondition doesn't hold, the server responds with
+`412 Precondition Failed`.
+
+A database can be served with this protocol via the `DatabaseServer` (requires
+the `server` feature). Only plain HTTP is supported. If the connection needs to be encrypted, the
+client should talk to the server through a tunnel or a local TLS proxy.
+ */
+
+use std::any::Any;
+use std::ffi::OsStr;
+use std::io::{BufReader, Error, ErrorKind};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::http::{CHECKSUM_HEADER, Message, decode_segment, encode_segment};
+use crate::{ChecksumAlgorithm, DatabaseEntry, DatabaseKey, Format, type_name};
+
+/**
+A condition which must hold for a [`RemoteDatabase::put_raw`],
+[`RemoteDatabase::write`] or [`RemoteDatabase::remove`] request to be
+executed by the server.
+
+This allows optimistic concurrency control between multiple clients: read an
+entry via [`RemoteDatabase::get_raw`], modify it and only write it back if
+its checksum is still the same.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Precondition {
+    #[default]
+    /**
+    The request is executed unconditionally.
+     */
+    None,
+    /**
+    The request is only executed if the entry doesn't exist yet.
+     */
+    Absent,
+    /**
+    The request is only executed if the file of the entry exists and has the
+    given checksum.
+     */
+    Checksum(u32),
+}
+
+/**
+The file of an entry as returned by [`RemoteDatabase::get_raw`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteFile {
+    /**
+    The file contents.
+     */
+    pub bytes: Vec<u8>,
+    /**
+    The checksum of the file as reported by the server.
+     */
+    pub checksum: Option<u32>,
+}
+
+/**
+A client for a database which is served over HTTP, see the
+[module documentation](crate::remote) for the protocol.
+
+The client is stateless: every method sends a single request over a new
+connection. Therefore, it can be cloned and shared between threads freely.
+
+# Examples
+
+```no_run
+use std::ffi::OsStr;
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+    price: f64,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+let mut remote = RemoteDatabase::new("http://db.example.com:8080/materials", SerdeYaml)
+    .expect("valid URL");
+remote.set_header("Authorization", "Bearer 1234");
+
+let mut steel: Material = remote.read("steel").expect("entry exists");
+steel.price *= 1.1;
+remote.write(&steel, Precondition::None).expect("server is writable");
+```
+ */
+#[derive(Clone)]
+pub struct RemoteDatabase {
+    host: String,
+    base_path: String,
+    format: Box<dyn Format>,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+}
+
+impl RemoteDatabase {
+    /**
+    Creates a client for the database served at `url`, which has the form
+    `http://host[:port][/base/path]`. The port defaults to 80. The format must
+    be the one of the served database.
+
+    No connection is established by this function. An error is only returned
+    if `url` is malformed.
+     */
+    pub fn new<F: Format + 'static>(url: &str, format: F) -> std::io::Result<Self> {
+        return Self::with_boxed_format(url, Box::new(format));
+    }
+
+    /**
+    Like [`RemoteDatabase::new`], but takes an already boxed format.
+     */
+    pub fn with_boxed_format(url: &str, format: Box<dyn Format>) -> std::io::Result<Self> {
+        let Some(rest) = url.strip_prefix("http://") else {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("URL \"{url}\" must start with \"http://\""),
+            ));
+        };
+        let (authority, base_path) = match rest.find('/') {
+            Some(pos) => rest.split_at(pos),
+            None => (rest, ""),
+        };
+        if authority.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("URL \"{url}\" has no host"),
+            ));
+        }
+
+        // The port is missing if there is no colon after a (possibly IPv6) host
+        let has_port = match authority.rfind(']') {
+            Some(pos) => authority[pos..].contains(':'),
+            None => authority.contains(':'),
+        };
+        let host = if has_port {
+            authority.to_string()
+        } else {
+            format!("{authority}:80")
+        };
+
+        return Ok(Self {
+            host,
+            base_path: base_path.trim_end_matches('/').to_string(),
+            format,
+            headers: Vec::new(),
+            timeout: Some(Duration::from_secs(30)),
+        });
+    }
+
+    /**
+    Returns the URL of the served database.
+     */
+    pub fn url(&self) -> String {
+        return format!("http://{}{}", self.host, self.base_path);
+    }
+
+    /**
+    Returns the data format of the served database.
+     */
+    pub fn data_format(&self) -> &dyn Format {
+        return &*self.format;
+    }
+
+    /**
+    Adds a header which is sent with every request, e.g. for authentication.
+    An existing header with the same name is replaced.
+     */
+    pub fn set_header<A: Into<String>, B: Into<String>>(&mut self, name: A, value: B) {
+        let name = name.into();
+        self.headers
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(&name));
+        self.headers.push((name, value.into()));
+    }
+
+    /**
+    Sets the timeout for connecting, sending and receiving. [`None`] disables
+    the timeout.
+
+    Defaults to 30 seconds.
+     */
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /**
+    Returns the list of all type names of the served database.
+     */
+    pub fn type_names(&self) -> std::io::Result<Vec<String>> {
+        let response = self.request("GET", "/", Message::default())?;
+        return parse_listing(response);
+    }
+
+    /**
+    Returns the names of all entries of the type `type_name`. If the type
+    doesn't exist, an empty list is returned.
+     */
+    pub fn entry_names<O: AsRef<OsStr>>(&self, type_name: O) -> std::io::Result<Vec<String>> {
+        let path = format!("/{}/", encode_segment(type_name.as_ref()));
+        let response = self.request("GET", &path, Message::default())?;
+        if response.status() == Some(404) {
+            return Ok(Vec::new());
+        }
+        return parse_listing(response);
+    }
+
+    /**
+    Returns `true` if the entry `key` exists on the server.
+     */
+    pub fn exists<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> std::io::Result<bool> {
+        return Ok(self.checksum(key)?.is_some());
+    }
+
+    /**
+    Returns the checksum of the file of the entry `key` or [`None`] if the
+    entry doesn't exist.
+     */
+    pub fn checksum<'a, T: Into<DatabaseKey<'a>>>(&self, key: T) -> std::io::Result<Option<u32>> {
+        let response = self.request("HEAD", &resource_path(key.into()), Message::default())?;
+        if response.status() == Some(404) {
+            return Ok(None);
+        }
+        let response = check_status(response, "HEAD")?;
+        return Ok(checksum_header(&response));
+    }
+
+    /**
+    Returns the serialized entry `key` as it is stored on the server or
+    [`None`] if the entry doesn't exist.
+     */
+    pub fn get_raw<'a, T: Into<DatabaseKey<'a>>>(
+        &self,
+        key: T,
+    ) -> std::io::Result<Option<RemoteFile>> {
+        let response = self.request("GET", &resource_path(key.into()), Message::default())?;
+        if response.status() == Some(404) {
+            return Ok(None);
+        }
+        let response = check_status(response, "GET")?;
+        return Ok(Some(RemoteFile {
+            checksum: checksum_header(&response),
+            bytes: response.body,
+        }));
+    }
+
+    /**
+    Replaces the file of the entry `key` on the server with `bytes`, provided
+    that `precondition` holds. Returns the checksum of the written file.
+
+    The bytes are stored as they are, i.e. they must be a valid serialized
+    representation of the entry in the format of the served database.
+     */
+    pub fn put_raw<'a, T: Into<DatabaseKey<'a>>>(
+        &self,
+        key: T,
+        bytes: &[u8],
+        precondition: Precondition,
+    ) -> std::io::Result<u32> {
+        let mut request = with_precondition(Message::default(), precondition);
+        request.body = bytes.to_vec();
+        let response = self.request("PUT", &resource_path(key.into()), request)?;
+        let response = check_status(response, "PUT")?;
+        // Servers always send the checksum, the fallback assumes the default algorithm
+        return Ok(checksum_header(&response)
+            .unwrap_or_else(|| ChecksumAlgorithm::default().checksum_bytes(bytes)));
+    }
+
+    /**
+    Removes the entry `key` from the server, provided that `precondition`
+    holds. Returns `false` if the entry didn't exist.
+     */
+    pub fn remove<'a, T: Into<DatabaseKey<'a>>>(
+        &self,
+        key: T,
+        precondition: Precondition,
+    ) -> std::io::Result<bool> {
+        let request = with_precondition(Message::default(), precondition);
+        let response = self.request("DELETE", &resource_path(key.into()), request)?;
+        if response.status() == Some(404) {
+            return Ok(false);
+        }
+        check_status(response, "DELETE")?;
+        return Ok(true);
+    }
+
+    /**
+    Reads the entry `name` of type `T`. The server resolves all links of the
+    entry, so no further requests are necessary.
+     */
+    pub fn read<T: DatabaseEntry, O: AsRef<OsStr>>(&self, name: O) -> std::io::Result<T> {
+        let key = DatabaseKey {
+            type_name: OsStr::new(T::folder_name()),
+            name: name.as_ref(),
+        };
+        let path = format!("{}?resolve", resource_path(key));
+        let response = self.request("GET", &path, Message::default())?;
+        let response = check_status(response, "GET")?;
+        let entry = self
+            .format
+            .deserialize_dyn(&response.body)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        match (entry as Box<dyn Any>).downcast::<T>() {
+            Ok(entry) => return Ok(*entry),
+            Err(_) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("type is not {}", type_name::<T>()),
+                ));
+            }
+        }
+    }
+
+    /**
+    Writes `instance` to the server, provided that `precondition` holds.
+    Returns the checksum of the written file.
+
+    Since the client can't write the linked entries as separate files, they
+    are embedded into the file of `instance` (like
+    [`WriteMode::Flat`](crate::WriteMode::Flat)).
+     */
+    pub fn write<T: DatabaseEntry>(
+        &self,
+        instance: &T,
+        precondition: Precondition,
+    ) -> std::io::Result<u32> {
+        let bytes = self
+            .format
+            .serialize_dyn(instance)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        let key = DatabaseKey {
+            type_name: OsStr::new(T::folder_name()),
+            name: instance.name(),
+        };
+        return self.put_raw(key, &bytes, precondition);
+    }
+
+    /**
+    Sends a single request and returns the response, whatever its status is.
+     */
+    fn request(&self, method: &str, path: &str, mut request: Message) -> std::io::Result<Message> {
+        request.start_line = format!("{method} {}{path} HTTP/1.1", self.base_path);
+        request.headers.push(("Host".into(), self.host.clone()));
+        request.headers.push(("Connection".into(), "close".into()));
+        request.headers.extend(self.headers.iter().cloned());
+
+        let stream = match self.timeout {
+            Some(timeout) => {
+                let mut last_err = None;
+                let mut stream = None;
+                for addr in std::net::ToSocketAddrs::to_socket_addrs(&self.host)? {
+                    match TcpStream::connect_timeout(&addr, timeout) {
+                        Ok(connected) => {
+                            stream = Some(connected);
+                            break;
+                        }
+                        Err(err) => last_err = Some(err),
+                    }
+                }
+                stream.ok_or_else(|| {
+                    last_err.unwrap_or_else(|| {
+                        Error::new(
+                            ErrorKind::NotFound,
+                            format!("Could not resolve host {}", self.host),
+                        )
+                    })
+                })?
+            }
+            None => TcpStream::connect(&self.host)?,
+        };
+        stream.set_read_timeout(self.timeout)?;
+        stream.set_write_timeout(self.timeout)?;
+
+        request.write(&mut &stream)?;
+        let mut reader = BufReader::new(&stream);
+        if method == "HEAD" {
+            return Message::read_head(&mut reader);
+        }
+        return Message::read(&mut reader, true);
+    }
+}
+
+/**
+Returns the percent-encoded path of the resource `key`.
+ */
+fn resource_path(key: DatabaseKey) -> String {
+    return format!(
+        "/{}/{}",
+        encode_segment(key.type_name),
+        encode_segment(key.name)
+    );
+}
+
+fn with_precondition(request: Message, precondition: Precondition) -> Message {
+    match precondition {
+        Precondition::None => return request,
+        Precondition::Absent => return request.with_header("If-None-Match", "*"),
+        Precondition::Checksum(checksum) => {
+            return request.with_header("If-Match", checksum.to_string());
+        }
+    }
+}
+
+fn checksum_header(response: &Message) -> Option<u32> {
+    return response.header(CHECKSUM_HEADER)?.parse().ok();
+}
+
+/**
+Converts a response with an unsuccessful status into an error.
+ */
+fn check_status(response: Message, method: &str) -> std::io::Result<Message> {
+    let status = response.status().unwrap_or_default();
+    if (200..300).contains(&status) {
+        return Ok(response);
+    }
+    let kind = match status {
+        404 => ErrorKind::NotFound,
+        401 | 403 => ErrorKind::PermissionDenied,
+        _ => ErrorKind::Other,
+    };
+    let mut message = format!("{method} request failed: {}", response.start_line);
+    let body = String::from_utf8_lossy(&response.body);
+    if !body.trim().is_empty() {
+        message.push_str(&format!(" ({})", body.trim()));
+    }
+    if status == 412 {
+        message = format!("Precondition failed: {message}");
+    }
+    return Err(Error::new(kind, message));
+}
+
+fn parse_listing(response: Message) -> std::io::Result<Vec<String>> {
+    let response = check_status(response, "GET")?;
+    let body = String::from_utf8(response.body)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    return body
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            decode_segment(line).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid entry in listing: \"{line}\""),
+                )
+            })
+        })
+        .collect();
+}
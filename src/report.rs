@@ -0,0 +1,695 @@
+/*!
+This module contains the [`DatabaseReport`] type, a structured list of
+[`Problem`]s found within a database. It is the common output format of all
+integrity checks of this crate (e.g. [`EntryVerification::report`]), so that
+the results of different checks can be combined and processed programmatically.
+
+Every [`Problem`] has a [`Severity`] and a [`SuggestedFix`]. The [`Display`]
+implementations of all types in this module produce human-readable output in
+the style of `fsck`:
+
+```text
+error: could not parse /path/to/db/Material/pure_cotton.yaml: ... (fix: inspect and repair the file manually)
+warning: checksum mismatch in /path/to/db/Shirt/mike.yaml: ... (fix: read the entry with ChecksumMismatchPolicy::Heal)
+```
+
+[`Display`]: std::fmt::Display
+ */
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    DatabaseKeyBuf, EntryVerification, FileStatus, LinkTarget, SignatureStatus, ValidationReport,
+};
+
+/**
+A list of [`Problem`]s found within a database.
+
+The problems are stored in the order in which they were found. Use
+[`DatabaseReport::max_severity`] or [`DatabaseReport::has_errors`] for a quick
+summary or filter the problems by [`Severity`] via
+[`DatabaseReport::with_severity`].
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DatabaseReport {
+    /**
+    All problems found within the database.
+     */
+    pub problems: Vec<Problem>,
+}
+
+impl DatabaseReport {
+    /**
+    Creates an empty report.
+     */
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /**
+    Returns `true` if the report does not contain any problems.
+     */
+    pub fn is_clean(&self) -> bool {
+        return self.problems.is_empty();
+    }
+
+    /**
+    Returns `true` if the report contains at least one problem of
+    [`Severity::Error`].
+     */
+    pub fn has_errors(&self) -> bool {
+        return self.max_severity() == Some(Severity::Error);
+    }
+
+    /**
+    Returns the highest [`Severity`] of all problems in the report or [`None`]
+    if the report is clean.
+     */
+    pub fn max_severity(&self) -> Option<Severity> {
+        return self.problems.iter().map(Problem::severity).max();
+    }
+
+    /**
+    Returns an iterator over all problems with the given `severity`.
+     */
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &Problem> {
+        return self
+            .problems
+            .iter()
+            .filter(move |problem| problem.severity() == severity);
+    }
+
+    /**
+    Adds a single problem to the report.
+     */
+    pub fn push(&mut self, problem: Problem) {
+        self.problems.push(problem);
+    }
+
+    /**
+    Moves all problems of `other` into `self`. This allows combining the
+    results of multiple checks into a single report.
+     */
+    pub fn append(&mut self, mut other: DatabaseReport) {
+        self.problems.append(&mut other.problems);
+    }
+}
+
+impl fmt::Display for DatabaseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in self.problems.iter() {
+            writeln!(
+                f,
+                "{}: {} (fix: {})",
+                problem.severity(),
+                problem,
+                problem.suggested_fix()
+            )?;
+        }
+        return Ok(());
+    }
+}
+
+/**
+The severity of a [`Problem`]. Severities are ordered from [`Severity::Info`]
+(lowest) to [`Severity::Error`] (highest).
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /**
+    The database works as expected, but might contain superfluous data.
+     */
+    Info,
+    /**
+    The database can still be read, but some data might be outdated or could
+    be misinterpreted.
+     */
+    Warning,
+    /**
+    Reading at least one database entry will fail.
+     */
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => return f.write_str("info"),
+            Severity::Warning => return f.write_str("warning"),
+            Severity::Error => return f.write_str("error"),
+        }
+    }
+}
+
+/**
+A single problem found within a database. See [`DatabaseReport`].
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /**
+    The file of a database entry which was expected to exist is missing.
+     */
+    Missing {
+        /**
+        Key of the missing entry.
+         */
+        key: DatabaseKeyBuf,
+        /**
+        Expected path of the missing file.
+         */
+        path: PathBuf,
+    },
+    /**
+    The file exists, but couldn't be read (e.g. due to missing permissions).
+     */
+    Unreadable {
+        /**
+        Path of the file.
+         */
+        path: PathBuf,
+        /**
+        The underlying error message.
+         */
+        message: String,
+    },
+    /**
+    The file exists, but couldn't be parsed by the [`Format`](crate::Format) of
+    the database.
+     */
+    Unparseable {
+        /**
+        Path of the file.
+         */
+        path: PathBuf,
+        /**
+        The underlying error message.
+         */
+        message: String,
+    },
+    /**
+    A link points to an entry which does not exist.
+     */
+    DanglingLink {
+        /**
+        Path of the file which contains the link.
+         */
+        path: PathBuf,
+        /**
+        The name stored within the link.
+         */
+        link: String,
+    },
+    /**
+    A link could point to multiple entries (see [`LinkTarget::Ambiguous`]).
+     */
+    AmbiguousLink {
+        /**
+        Path of the file which contains the link.
+         */
+        path: PathBuf,
+        /**
+        The name stored within the link.
+         */
+        link: String,
+        /**
+        Keys of all entries the link could point to.
+         */
+        candidates: Vec<DatabaseKeyBuf>,
+    },
+    /**
+    A link points (transitively) back to the file which contains it, so the
+    linked entries can't be inlined.
+     */
+    CyclicLink {
+        /**
+        Path of the file which contains the link.
+         */
+        path: PathBuf,
+        /**
+        The name stored within the link.
+         */
+        link: String,
+    },
+    /**
+    The checksum stored within a link does not match the checksum of the linked
+    file (see [`ChecksumMismatch`](crate::ChecksumMismatch)).
+     */
+    ChecksumMismatch {
+        /**
+        Path of the file which contains the link.
+         */
+        path: PathBuf,
+        /**
+        Path of the linked file.
+         */
+        linked_path: PathBuf,
+        /**
+        The checksum value stored in the link.
+         */
+        checksum_in_link: u32,
+        /**
+        The checksum value of the linked file.
+         */
+        checksum_of_file: u32,
+    },
+    /**
+    A database entry which is not linked by any other entry (and is not one of
+    the roots of the database, if those have been specified).
+     */
+    Orphan {
+        /**
+        Key of the orphaned entry.
+         */
+        key: DatabaseKeyBuf,
+        /**
+        Path of the orphaned file.
+         */
+        path: PathBuf,
+    },
+    /**
+    Multiple files within the same type folder whose names differ, but become
+    identical after sanitization (e.g. `Hank` and `hank` on a case-insensitive
+    file system). Copying the database to another file system or writing one
+    of the entries might silently overwrite the other ones.
+     */
+    NameCollision {
+        /**
+        Paths of all colliding files.
+         */
+        paths: Vec<PathBuf>,
+    },
+    /**
+    The detached signature of a file is missing or invalid (see
+    [`signature`](crate::signature)).
+     */
+    UnverifiedSignature {
+        /**
+        Path of the file.
+         */
+        path: PathBuf,
+        /**
+        The verification result, either
+        [`SignatureStatus::Missing`](crate::SignatureStatus::Missing) or
+        [`SignatureStatus::Invalid`](crate::SignatureStatus::Invalid).
+         */
+        status: SignatureStatus,
+    },
+    /**
+    A file at the database root or a folder within a type folder. Neither
+    belongs to the layout of a database (see
+    [`DatabaseManager::validate_layout`](crate::DatabaseManager::validate_layout)).
+     */
+    StrayFile {
+        /**
+        Path of the file or folder.
+         */
+        path: PathBuf,
+    },
+    /**
+    A file within a type folder which does not have the file extension of the
+    database (e.g. because it was written with another [`Format`](crate::Format)).
+    It is therefore not recognized as a database entry.
+     */
+    UnknownExtension {
+        /**
+        Path of the file.
+         */
+        path: PathBuf,
+    },
+    /**
+    Multiple type folders whose names only differ in case (e.g. `Material` and
+    `material`). Entries stored in a folder which isn't named exactly like the
+    type are not found.
+     */
+    CaseDuplicateFolders {
+        /**
+        Paths of all folders.
+         */
+        paths: Vec<PathBuf>,
+    },
+}
+
+impl Problem {
+    /**
+    Returns the [`Severity`] of the problem.
+     */
+    pub fn severity(&self) -> Severity {
+        match self {
+            Problem::Missing { .. }
+            | Problem::Unreadable { .. }
+            | Problem::Unparseable { .. }
+            | Problem::DanglingLink { .. }
+            | Problem::AmbiguousLink { .. }
+            | Problem::CyclicLink { .. }
+            | Problem::UnverifiedSignature { .. } => return Severity::Error,
+            Problem::ChecksumMismatch { .. }
+            | Problem::NameCollision { .. }
+            | Problem::StrayFile { .. }
+            | Problem::UnknownExtension { .. }
+            | Problem::CaseDuplicateFolders { .. } => {
+                return Severity::Warning;
+            }
+            Problem::Orphan { .. } => return Severity::Info,
+        }
+    }
+
+    /**
+    Returns a [`SuggestedFix`] for the problem.
+     */
+    pub fn suggested_fix(&self) -> SuggestedFix {
+        match self {
+            Problem::Missing { .. } | Problem::DanglingLink { .. } => {
+                return SuggestedFix::RestoreOrUnlink;
+            }
+            Problem::Unreadable { .. } | Problem::Unparseable { .. } => {
+                return SuggestedFix::RepairManually;
+            }
+            Problem::AmbiguousLink { .. } => return SuggestedFix::Disambiguate,
+            Problem::CyclicLink { .. } => return SuggestedFix::RepairManually,
+            Problem::ChecksumMismatch { .. } => return SuggestedFix::HealChecksums,
+            Problem::Orphan { path, .. } => return SuggestedFix::Remove(path.clone()),
+            Problem::NameCollision { .. } => return SuggestedFix::Rename,
+            Problem::UnverifiedSignature { .. } => return SuggestedFix::RestoreOrResign,
+            Problem::StrayFile { path } | Problem::UnknownExtension { path } => {
+                return SuggestedFix::MoveOrRemove(path.clone());
+            }
+            Problem::CaseDuplicateFolders { .. } => return SuggestedFix::MergeFolders,
+        }
+    }
+
+    /**
+    Returns the path of the file the problem was found in. For a
+    [`Problem::NameCollision`] or a [`Problem::CaseDuplicateFolders`], this is
+    the first of the colliding paths.
+     */
+    pub fn path(&self) -> &Path {
+        match self {
+            Problem::Missing { path, .. }
+            | Problem::Unreadable { path, .. }
+            | Problem::Unparseable { path, .. }
+            | Problem::DanglingLink { path, .. }
+            | Problem::AmbiguousLink { path, .. }
+            | Problem::CyclicLink { path, .. }
+            | Problem::ChecksumMismatch { path, .. }
+            | Problem::Orphan { path, .. }
+            | Problem::UnverifiedSignature { path, .. }
+            | Problem::StrayFile { path }
+            | Problem::UnknownExtension { path } => return path.as_path(),
+            Problem::NameCollision { paths } | Problem::CaseDuplicateFolders { paths } => {
+                return paths.first().map(PathBuf::as_path).unwrap_or(Path::new(""));
+            }
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Missing { key, path } => {
+                return write!(f, "entry {} is missing ({})", key, path.display());
+            }
+            Problem::Unreadable { path, message } => {
+                return write!(f, "could not read {}: {}", path.display(), message);
+            }
+            Problem::Unparseable { path, message } => {
+                return write!(f, "could not parse {}: {}", path.display(), message);
+            }
+            Problem::DanglingLink { path, link } => {
+                return write!(f, "dangling link \"{}\" in {}", link, path.display());
+            }
+            Problem::AmbiguousLink {
+                path,
+                link,
+                candidates,
+            } => {
+                write!(
+                    f,
+                    "ambiguous link \"{}\" in {} (candidates:",
+                    link,
+                    path.display()
+                )?;
+                for candidate in candidates.iter() {
+                    write!(f, " {}", candidate)?;
+                }
+                return f.write_str(")");
+            }
+            Problem::CyclicLink { path, link } => {
+                return write!(f, "cyclic link \"{}\" in {}", link, path.display());
+            }
+            Problem::ChecksumMismatch {
+                path,
+                linked_path,
+                checksum_in_link,
+                checksum_of_file,
+            } => {
+                return write!(
+                    f,
+                    "checksum mismatch in {}: link to {} has checksum {}, but the file has checksum {}",
+                    path.display(),
+                    linked_path.display(),
+                    checksum_in_link,
+                    checksum_of_file
+                );
+            }
+            Problem::Orphan { key, path } => {
+                return write!(
+                    f,
+                    "entry {} is not linked by any other entry ({})",
+                    key,
+                    path.display()
+                );
+            }
+            Problem::NameCollision { paths } => {
+                f.write_str("colliding file names:")?;
+                for path in paths.iter() {
+                    write!(f, " {}", path.display())?;
+                }
+                return Ok(());
+            }
+            Problem::UnverifiedSignature { path, status } => {
+                let status = match status {
+                    SignatureStatus::Missing => "missing",
+                    _ => "invalid",
+                };
+                return write!(f, "signature of {} is {}", path.display(), status);
+            }
+            Problem::StrayFile { path } => {
+                return write!(
+                    f,
+                    "{} does not belong to the database layout",
+                    path.display()
+                );
+            }
+            Problem::UnknownExtension { path } => {
+                return write!(
+                    f,
+                    "{} does not have the file extension of the database",
+                    path.display()
+                );
+            }
+            Problem::CaseDuplicateFolders { paths } => {
+                f.write_str("type folders differing only in case:")?;
+                for path in paths.iter() {
+                    write!(f, " {}", path.display())?;
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+/**
+A suggestion how to fix a [`Problem`]. See [`Problem::suggested_fix`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SuggestedFix {
+    /**
+    Restore the missing entry (e.g. by writing it again) or remove the link to
+    it.
+     */
+    RestoreOrUnlink,
+    /**
+    The file needs to be inspected and repaired manually (or restored from a
+    backup).
+     */
+    RepairManually,
+    /**
+    Rename one of the candidate entries or add a checksum to the link so it
+    can be resolved unambiguously.
+     */
+    Disambiguate,
+    /**
+    Update the checksums stored in the links, e.g. by reading the entry with
+    [`ChecksumMismatchPolicy::Heal`](crate::ChecksumMismatchPolicy::Heal).
+     */
+    HealChecksums,
+    /**
+    Remove the file at the given path if it is not needed anymore.
+     */
+    Remove(PathBuf),
+    /**
+    Rename all but one of the colliding entries (and update the links to them).
+     */
+    Rename,
+    /**
+    Restore the file from a trusted source or sign it again if the changes
+    were intentional.
+     */
+    RestoreOrResign,
+    /**
+    Move the file at the given path to the location expected by the database
+    (e.g. by fixing its file extension) or remove it.
+     */
+    MoveOrRemove(PathBuf),
+    /**
+    Merge the folders into the one named exactly like the type.
+     */
+    MergeFolders,
+}
+
+impl fmt::Display for SuggestedFix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SuggestedFix::RestoreOrUnlink => {
+                return f.write_str("restore the linked entry or remove the link");
+            }
+            SuggestedFix::RepairManually => {
+                return f.write_str("inspect and repair the file manually");
+            }
+            SuggestedFix::Disambiguate => {
+                return f.write_str("rename one of the candidates or add a checksum to the link");
+            }
+            SuggestedFix::HealChecksums => {
+                return f.write_str("read the entry with ChecksumMismatchPolicy::Heal");
+            }
+            SuggestedFix::Remove(path) => return write!(f, "remove {}", path.display()),
+            SuggestedFix::Rename => return f.write_str("rename all but one of the files"),
+            SuggestedFix::RestoreOrResign => {
+                return f.write_str("restore the file from a trusted source or sign it again");
+            }
+            SuggestedFix::MoveOrRemove(path) => {
+                return write!(
+                    f,
+                    "move {} to the expected location or remove it",
+                    path.display()
+                );
+            }
+            SuggestedFix::MergeFolders => {
+                return f.write_str("merge the folders into the one named like the type");
+            }
+        }
+    }
+}
+
+impl ValidationReport {
+    /**
+    Converts the validation results into a [`DatabaseReport`]: The problems of
+    [`EntryVerification::report`], followed by a [`Problem::Unparseable`] for
+    the file which aborted the typed read, unless a problem has already been
+    reported for that file.
+     */
+    pub fn report(&self) -> DatabaseReport {
+        let mut report = self.verification.report();
+        if let Err(err) = &self.read
+            && !report
+                .problems
+                .iter()
+                .any(|problem| problem.path() == err.path)
+        {
+            report.push(Problem::Unparseable {
+                path: err.path.clone(),
+                message: err.message.clone(),
+            });
+        }
+        return report;
+    }
+}
+
+impl EntryVerification {
+    /**
+    Converts the verification results into a [`DatabaseReport`] which contains
+    a [`Problem`] for every missing or invalid file and every invalid link.
+     */
+    pub fn report(&self) -> DatabaseReport {
+        let mut report = DatabaseReport::new();
+        for file in self.files.iter() {
+            let path = file.path.clone();
+            if let Some(status) = file.signature
+                && status != SignatureStatus::Valid
+            {
+                report.push(Problem::UnverifiedSignature {
+                    path: path.clone(),
+                    status,
+                });
+            }
+            match &file.status {
+                FileStatus::Valid => (),
+                FileStatus::Missing => {
+                    // Missing linked files are reported as dangling links
+                    // instead, hence only a missing root is reported here.
+                    if std::ptr::eq(file, &self.files[0]) {
+                        report.push(Problem::Missing {
+                            key: file.key.clone(),
+                            path,
+                        });
+                    }
+                    continue;
+                }
+                FileStatus::Unreadable(message) => {
+                    report.push(Problem::Unreadable {
+                        path,
+                        message: message.clone(),
+                    });
+                    continue;
+                }
+                FileStatus::Unparseable(message) => {
+                    report.push(Problem::Unparseable {
+                        path,
+                        message: message.clone(),
+                    });
+                    continue;
+                }
+            }
+
+            for link in file.links.iter() {
+                match &link.target {
+                    LinkTarget::Resolved(target) => {
+                        if let (Some(checksum_in_link), Some(checksum_of_file)) =
+                            (link.checksum_in_link, link.checksum_of_file)
+                            && link.is_checksum_mismatch()
+                        {
+                            report.push(Problem::ChecksumMismatch {
+                                path: path.clone(),
+                                linked_path: self.path_of(target),
+                                checksum_in_link,
+                                checksum_of_file,
+                            });
+                        }
+                    }
+                    LinkTarget::Dangling => report.push(Problem::DanglingLink {
+                        path: path.clone(),
+                        link: link.name.clone(),
+                    }),
+                    LinkTarget::Ambiguous(candidates) => report.push(Problem::AmbiguousLink {
+                        path: path.clone(),
+                        link: link.name.clone(),
+                        candidates: candidates.clone(),
+                    }),
+                }
+            }
+        }
+        return report;
+    }
+
+    fn path_of(&self, key: &DatabaseKeyBuf) -> PathBuf {
+        // The file report of a resolved link target is always part of the
+        // verification.
+        return self
+            .files
+            .iter()
+            .find(|file| &file.key == key)
+            .map(|file| file.path.clone())
+            .unwrap_or_default();
+    }
+}
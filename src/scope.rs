@@ -0,0 +1,172 @@
+/*!
+This module contains the tenant scoping of a [`DatabaseManager`], see
+[`DatabaseManager::scoped`].
+
+A service which manages the databases of many tenants (e.g. customers) can
+store each database within a subfolder of a common root directory
+(`<root>/<tenant>/<Type>/<name>`). A scoped manager confines all reads, writes
+and link resolution to the subfolder of its tenant, so the tenant id never has
+to be concatenated into paths or names manually.
+
+Scoped managers are cheap to create (e.g. one per request), but each of them
+starts with an empty [`Cache`](crate::Cache). The [`TenantCaches`] keep one
+[`SharedCache`] per tenant instead, which is reused by all managers scoped via
+[`DatabaseManager::scoped_with_caches`].
+ */
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io::{Error, ErrorKind};
+use std::path::{Component, Path};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{DatabaseManager, SharedCache};
+
+/**
+A registry of one [`SharedCache`] per tenant, see
+[`DatabaseManager::scoped_with_caches`]. The caches are namespaced by the
+tenant id, so cached instances are never shared between tenants. The registry
+can be cloned cheaply, all clones refer to the same caches.
+ */
+#[derive(Clone, Default, Debug)]
+pub struct TenantCaches {
+    caches: Arc<Mutex<HashMap<OsString, SharedCache>>>,
+}
+
+impl TenantCaches {
+    /**
+    Creates a new, empty [`TenantCaches`] registry.
+     */
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /**
+    Returns the [`SharedCache`] of `tenant`. The cache is created if it
+    doesn't exist yet.
+     */
+    pub fn get<O: AsRef<OsStr>>(&self, tenant: O) -> SharedCache {
+        return self
+            .caches
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(tenant.as_ref().to_os_string())
+            .or_default()
+            .clone();
+    }
+
+    /**
+    Removes the [`SharedCache`] of `tenant` from the registry (e.g. after the
+    database of the tenant has been deleted) and returns it, if any. Managers
+    which already use the cache keep using it, but managers scoped afterwards
+    start with a new, empty cache.
+     */
+    pub fn remove<O: AsRef<OsStr>>(&self, tenant: O) -> Option<SharedCache> {
+        return self
+            .caches
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(tenant.as_ref());
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Returns a manager for the database of `tenant`, which is located in the
+    subfolder `tenant` of `self.dir()`. The folder is created if it doesn't
+    exist yet. `tenant` must be a single, normal path segment (i.e. it must
+    not contain path separators and must not be `.` or `..`), otherwise an
+    error of kind [`ErrorKind::InvalidInput`] is returned.
+
+    The scoped manager uses the same [`Format`](crate::Format) and
+    configuration (e.g. write profiles, field aliases and the
+    [`CachePolicy`](crate::CachePolicy)) as `self`, but has its own, initially
+    empty [`Cache`](crate::Cache) (see [`DatabaseManager::scoped_with_caches`]
+    for sharing the cache of a tenant between scoped managers). Since links are resolved within the database
+    of the reading manager, entries of different tenants never share cached
+    instances or link to each other. Staged files (see
+    [`DatabaseManager::enable_staging`]) and subscribers (see
+    [`DatabaseManager::subscribe`]) are not shared either.
+
+    The tenant folders are located in the root directory of `self`, so `self`
+    should only be used to create scoped managers - otherwise, the tenant
+    folders are mistaken for type folders (e.g. by
+    [`DatabaseManager::validate_layout`]).
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let root = DatabaseManager::open("/path/to/tenants", SerdeYaml).expect("directory exists");
+    let acme = root.scoped("acme").expect("valid tenant id");
+    assert!(acme.dir().ends_with("tenants/acme"));
+    assert!(root.scoped("../other").is_err());
+    ```
+     */
+    pub fn scoped<O: AsRef<OsStr>>(&self, tenant: O) -> std::io::Result<DatabaseManager> {
+        let tenant = tenant.as_ref();
+        let mut components = Path::new(tenant).components();
+        let is_segment = matches!(
+            components.next(),
+            Some(Component::Normal(segment)) if segment == tenant
+        ) && components.next().is_none();
+        if !is_segment {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid tenant id {}", tenant.to_string_lossy()),
+            ));
+        }
+
+        let mut scoped =
+            DatabaseManager::with_boxed_format(self.dir().join(tenant), self.format.clone())?;
+        scoped.type_formats = self.type_formats.clone();
+        scoped.write_profiles = self.write_profiles.clone();
+        scoped.field_aliases = self.field_aliases.clone();
+        scoped.migrations = self.migrations.clone();
+        scoped.format_options = self.format_options.clone();
+        scoped.sharding = self.sharding;
+        scoped.set_cache_policy(self.cache_policy());
+        scoped.set_checksum_algorithm(self.checksum_algorithm());
+        scoped.set_preserve_unknown_fields(self.preserves_unknown_fields());
+        #[cfg(feature = "encryption")]
+        {
+            scoped.encryption = self.encryption.clone();
+        }
+        #[cfg(feature = "signatures")]
+        {
+            scoped.signatures = self.signatures.clone();
+        }
+        return Ok(scoped);
+    }
+    /**
+    Like [`DatabaseManager::scoped`], but the returned manager uses the
+    [`SharedCache`] of `tenant` from `caches` (see
+    [`DatabaseManager::set_shared_cache`]). Therefore, all managers scoped
+    with the same registry for the same tenant reuse each other's cached
+    instances, while the caches of different tenants stay separate.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let root = DatabaseManager::open("/path/to/tenants", SerdeYaml).expect("directory exists");
+    let caches = TenantCaches::new();
+
+    // Instances cached by one request of acme are reused by the next one
+    let first_request = root.scoped_with_caches("acme", &caches).expect("valid tenant id");
+    let second_request = root.scoped_with_caches("acme", &caches).expect("valid tenant id");
+    let globex = root.scoped_with_caches("globex", &caches).expect("valid tenant id");
+    ```
+     */
+    pub fn scoped_with_caches<O: AsRef<OsStr>>(
+        &self,
+        tenant: O,
+        caches: &TenantCaches,
+    ) -> std::io::Result<DatabaseManager> {
+        let mut scoped = self.scoped(tenant.as_ref())?;
+        scoped.set_shared_cache(Some(caches.get(tenant)));
+        return Ok(scoped);
+    }
+}
@@ -0,0 +1,790 @@
+/*!
+This module contains the [`DatabaseServer`], which serves an existing database
+over HTTP (requires the `server` feature). It implements the protocol described
+in the `remote` module, so it can be used by the `RemoteDatabase` client
+(requires the `remote` feature) as well as by any other HTTP client, e.g. a
+browser-based inspector:
+- `GET /` lists the type names of the database.
+- `GET /<type_name>/` lists the entry names of a type.
+- `GET /<type_name>/<name>` returns the serialized entry as it is stored in
+its file. With the query `?resolve`, all links of the entry are resolved and
+inlined.
+- `PUT /<type_name>/<name>` and `DELETE /<type_name>/<name>` replace or remove
+the entry. These methods are only available if the server is writable, see
+[`ServerOptions::writable`]. The body of a `PUT` request must be an entry of
+the type and with the name given by the URL, serialized like by
+[`DatabaseManager::write`].
+
+The server doesn't depend on a specific HTTP framework. The core of it is
+[`DatabaseServer::handle`], which maps a [`ServerRequest`] to a
+[`ServerResponse`] and can therefore be called from within a handler of e.g.
+`axum` or `hyper`. For simple setups, [`DatabaseServer::serve`] runs a minimal
+blocking HTTP/1.1 server on a [`TcpListener`].
+
+Access control is implemented via an [`Authorizer`] callback, which gets the
+request (including its headers, e.g. an `Authorization` token) and the
+requested kind of [`Access`]. [`DatabaseServer::serve`] authorizes a request
+before reading its body and limits the body size, the time spent on a
+connection and the number of concurrent connections, see [`ServerOptions`].
+ */
+
+use std::ffi::OsStr;
+use std::fmt::Debug;
+use std::fs;
+use std::io::{BufReader, Error, ErrorKind};
+use std::mem;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use crate::database_manager::replace_file;
+use crate::exchange::Inliner;
+use crate::http::{CHECKSUM_HEADER, Message, decode_segment, encode_segment};
+use crate::{DatabaseKeyBuf, DatabaseManager, ReadOptions, Value};
+
+/**
+The kind of access a [`ServerRequest`] requires, see [`Authorizer`].
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /**
+    Listing types and entries and reading entries (`GET` and `HEAD`).
+     */
+    Read,
+    /**
+    Writing and removing entries (`PUT` and `DELETE`).
+     */
+    Write,
+}
+
+/**
+A callback which decides whether a request is allowed, see
+[`ServerOptions::authorizer`]. Requests which are not allowed are answered
+with `403 Forbidden`. Requests received via [`DatabaseServer::serve`] are
+authorized before their body is read, so [`ServerRequest::body`] is empty
+when the callback is called for them.
+
+# Examples
+
+```
+use serde_mosaic::*;
+
+// Everybody may read, but only requests with the correct token may write
+let authorizer = Authorizer::new(|request, access| {
+    return access == Access::Read || request.header("Authorization") == Some("Bearer 1234");
+});
+```
+ */
+#[derive(Clone)]
+pub struct Authorizer(Arc<dyn Fn(&ServerRequest, Access) -> bool + Send + Sync>);
+
+impl Authorizer {
+    /**
+    Creates a new [`Authorizer`] from the given function.
+     */
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&ServerRequest, Access) -> bool + Send + Sync + 'static,
+    {
+        return Self(Arc::new(f));
+    }
+
+    /**
+    Returns `true` if `request` is allowed to perform `access`.
+     */
+    pub fn authorize(&self, request: &ServerRequest, access: Access) -> bool {
+        return (self.0)(request, access);
+    }
+}
+
+impl Debug for Authorizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.write_str("Authorizer");
+    }
+}
+
+/**
+Configures a [`DatabaseServer`].
+ */
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    /**
+    If `true`, entries can be written (`PUT`) and removed (`DELETE`).
+    Otherwise, these requests are answered with `405 Method Not Allowed`.
+
+    Defaults to `false`.
+     */
+    pub writable: bool,
+    /**
+    Decides which requests are allowed. If [`None`], all requests are allowed.
+
+    Defaults to [`None`].
+     */
+    pub authorizer: Option<Authorizer>,
+    /**
+    If `true`, `PUT` requests may create entries of types which don't have a
+    type folder in the database yet. Otherwise, these requests are answered
+    with `404 Not Found`.
+
+    Defaults to `false`.
+     */
+    pub allow_new_types: bool,
+    /**
+    The maximum size of a request body in bytes. Larger requests are answered
+    with `413 Content Too Large`. [`DatabaseServer::serve`] rejects them
+    before reading the body.
+
+    Defaults to 16 MiB.
+     */
+    pub max_body_bytes: usize,
+    /**
+    The read and write timeout of the connections processed by
+    [`DatabaseServer::serve`] and [`DatabaseServer::handle_connection`]. If
+    [`None`], a connection can be kept open indefinitely. Must not be zero.
+
+    Defaults to 30 seconds.
+     */
+    pub timeout: Option<Duration>,
+    /**
+    The maximum number of connections processed concurrently by
+    [`DatabaseServer::serve`]. Further connections are only accepted once
+    another connection has been closed.
+
+    Defaults to 64.
+     */
+    pub max_connections: usize,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        return Self {
+            writable: false,
+            authorizer: None,
+            allow_new_types: false,
+            max_body_bytes: 16 * 1024 * 1024,
+            timeout: Some(Duration::from_secs(30)),
+            max_connections: 64,
+        };
+    }
+}
+
+/**
+An HTTP request to a [`DatabaseServer`].
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerRequest {
+    /**
+    The request method, e.g. `GET`.
+     */
+    pub method: String,
+    /**
+    The path of the requested resource including the query, e.g.
+    `/Material/steel?resolve`.
+     */
+    pub path: String,
+    /**
+    The request headers.
+     */
+    pub headers: Vec<(String, String)>,
+    /**
+    The request body.
+     */
+    pub body: Vec<u8>,
+}
+
+impl ServerRequest {
+    /**
+    Creates a request without headers and body.
+     */
+    pub fn new<A: Into<String>, B: Into<String>>(method: A, path: B) -> Self {
+        return Self {
+            method: method.into(),
+            path: path.into(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        };
+    }
+
+    /**
+    Returns the value of the first header called `name` (case-insensitive).
+     */
+    pub fn header(&self, name: &str) -> Option<&str> {
+        return self
+            .headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim());
+    }
+}
+
+/**
+The response of a [`DatabaseServer`] to a [`ServerRequest`].
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerResponse {
+    /**
+    The HTTP status code, e.g. 200.
+     */
+    pub status: u16,
+    /**
+    The response headers. The `Content-Length` header is not included.
+     */
+    pub headers: Vec<(String, String)>,
+    /**
+    The response body.
+     */
+    pub body: Vec<u8>,
+}
+
+impl ServerResponse {
+    fn new(status: u16) -> Self {
+        return Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        };
+    }
+
+    fn text(status: u16, text: String) -> Self {
+        let mut response = Self::new(status);
+        response
+            .headers
+            .push(("Content-Type".into(), "text/plain; charset=utf-8".into()));
+        response.body = text.into_bytes();
+        return response;
+    }
+
+    fn with_checksum(mut self, checksum: u32) -> Self {
+        self.headers
+            .push((CHECKSUM_HEADER.into(), checksum.to_string()));
+        return self;
+    }
+
+    /**
+    Returns the canonical reason phrase of [`ServerResponse::status`].
+     */
+    pub fn reason(&self) -> &'static str {
+        match self.status {
+            200 => return "OK",
+            201 => return "Created",
+            204 => return "No Content",
+            400 => return "Bad Request",
+            403 => return "Forbidden",
+            404 => return "Not Found",
+            405 => return "Method Not Allowed",
+            412 => return "Precondition Failed",
+            413 => return "Content Too Large",
+            423 => return "Locked",
+            _ => return "Internal Server Error",
+        }
+    }
+}
+
+impl From<std::io::Error> for ServerResponse {
+    fn from(err: std::io::Error) -> Self {
+        let status = match err.kind() {
+            ErrorKind::NotFound => 404,
+            ErrorKind::PermissionDenied => 403,
+            ErrorKind::FileTooLarge => 413,
+            ErrorKind::ResourceBusy => 423,
+            _ => 500,
+        };
+        return Self::text(status, err.to_string());
+    }
+}
+
+/**
+Serves a database over HTTP, see the [module documentation](crate::server).
+
+The server owns its [`DatabaseManager`]. Reading requests are processed
+concurrently, while writing requests are processed one after another. The
+manager is still available via [`DatabaseServer::database_manager`] and
+[`DatabaseServer::database_manager_mut`].
+
+# Examples
+
+```no_run
+use std::net::TcpListener;
+use serde_mosaic::*;
+
+let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+let server = DatabaseServer::new(dbm, ServerOptions::default());
+let listener = TcpListener::bind("0.0.0.0:8080").expect("port is free");
+server.serve(listener).expect("listener works");
+```
+ */
+pub struct DatabaseServer {
+    dbm: RwLock<DatabaseManager>,
+    options: ServerOptions,
+}
+
+impl DatabaseServer {
+    /**
+    Creates a server for the database of `dbm`.
+     */
+    pub fn new(dbm: DatabaseManager, options: ServerOptions) -> Self {
+        return Self {
+            dbm: RwLock::new(dbm),
+            options,
+        };
+    }
+
+    /**
+    Returns the options of the server.
+     */
+    pub fn options(&self) -> &ServerOptions {
+        return &self.options;
+    }
+
+    /**
+    Grants shared access to the database manager of the server.
+     */
+    pub fn database_manager(&self) -> RwLockReadGuard<'_, DatabaseManager> {
+        return self.dbm.read().unwrap_or_else(PoisonError::into_inner);
+    }
+
+    /**
+    Grants exclusive access to the database manager of the server. No
+    requests are processed while the returned guard is alive.
+     */
+    pub fn database_manager_mut(&self) -> RwLockWriteGuard<'_, DatabaseManager> {
+        return self.dbm.write().unwrap_or_else(PoisonError::into_inner);
+    }
+
+    /**
+    Processes a single request and returns the response. This function never
+    fails - errors are reported via the status code of the response.
+     */
+    pub fn handle(&self, request: &ServerRequest) -> ServerResponse {
+        return match self.authorize(request) {
+            Ok(access) => self.respond(request, access),
+            Err(response) => response,
+        };
+    }
+
+    /**
+    Checks whether the method of `request` is allowed and whether the
+    [`ServerOptions::authorizer`] grants the required [`Access`]. Only the
+    method, the path and the headers of `request` are inspected.
+     */
+    fn authorize(&self, request: &ServerRequest) -> Result<Access, ServerResponse> {
+        let access = match request.method.as_str() {
+            "GET" | "HEAD" => Access::Read,
+            "PUT" | "DELETE" => Access::Write,
+            _ => return Err(self.method_not_allowed()),
+        };
+        if access == Access::Write && !self.options.writable {
+            return Err(self.method_not_allowed());
+        }
+        if let Some(authorizer) = &self.options.authorizer
+            && !authorizer.authorize(request, access)
+        {
+            return Err(ServerResponse::text(403, "Access denied".into()));
+        }
+        return Ok(access);
+    }
+
+    /**
+    Answers an authorized request, see [`DatabaseServer::handle`].
+     */
+    fn respond(&self, request: &ServerRequest, access: Access) -> ServerResponse {
+        if request.body.len() > self.options.max_body_bytes {
+            return ServerResponse::text(
+                413,
+                format!(
+                    "The request body exceeds the limit of {} bytes",
+                    self.options.max_body_bytes
+                ),
+            );
+        }
+        let (path, query) = match request.path.split_once('?') {
+            Some((path, query)) => (path, query),
+            None => (request.path.as_str(), ""),
+        };
+        let resolve = query
+            .split('&')
+            .any(|param| param == "resolve" || param == "resolve=true");
+
+        let mut segments = Vec::new();
+        for segment in path.trim_start_matches('/').split('/') {
+            match decode_segment(segment) {
+                Some(segment) => segments.push(segment),
+                None => {
+                    return ServerResponse::text(400, format!("Invalid path \"{path}\""));
+                }
+            }
+        }
+
+        let mut response = match segments.as_slice() {
+            [root] if root.is_empty() && access == Access::Read => self.list_types(),
+            [type_name, empty] if empty.is_empty() && access == Access::Read => {
+                self.list_entries(type_name)
+            }
+            [type_name, name] if is_valid_segment(type_name) && is_valid_segment(name) => {
+                let key = DatabaseKeyBuf::new(type_name, name);
+                match request.method.as_str() {
+                    "PUT" => self.put(&key, request),
+                    "DELETE" => self.delete(&key, request),
+                    _ => self.get(&key, resolve),
+                }
+            }
+            _ => ServerResponse::text(404, format!("No resource at \"{path}\"")),
+        };
+        if request.method == "HEAD" {
+            response.body.clear();
+        }
+        return response;
+    }
+
+    /**
+    Runs a minimal blocking HTTP/1.1 server which accepts connections from
+    `listener` and answers the requests via [`DatabaseServer::handle`]. Every
+    connection is processed in its own thread and closed after a single
+    request. At most [`ServerOptions::max_connections`] connections are
+    processed at the same time. This function only returns if accepting a
+    connection fails.
+     */
+    pub fn serve(&self, listener: TcpListener) -> std::io::Result<()> {
+        let slots = ConnectionSlots::default();
+        return std::thread::scope(|scope| {
+            for stream in listener.incoming() {
+                let stream = stream?;
+                let slot = slots.acquire(self.options.max_connections);
+                scope.spawn(move || {
+                    // A failing connection must not stop the server
+                    let _ = self.handle_connection(stream);
+                    drop(slot);
+                });
+            }
+            return Ok(());
+        });
+    }
+
+    /**
+    Reads a single request from `stream`, answers it via
+    [`DatabaseServer::handle`] and closes the connection. The request is
+    authorized before its body is read and bodies larger than
+    [`ServerOptions::max_body_bytes`] are rejected without being read.
+     */
+    pub fn handle_connection(&self, stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(self.options.timeout)?;
+        stream.set_write_timeout(self.options.timeout)?;
+        let mut reader = BufReader::new(&stream);
+        let mut message = Message::read_head(&mut reader)?;
+        let mut start_line = message.start_line.split_whitespace();
+        let (Some(method), Some(path)) = (start_line.next(), start_line.next()) else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Malformed request line \"{}\"", message.start_line),
+            ));
+        };
+        let mut request = ServerRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: message.headers.clone(),
+            body: Vec::new(),
+        };
+
+        let response = match self.authorize(&request) {
+            Ok(access) => {
+                match message.read_body(&mut reader, false, self.options.max_body_bytes) {
+                    Ok(()) => {
+                        request.body = mem::take(&mut message.body);
+                        self.respond(&request, access)
+                    }
+                    Err(err) if err.kind() == ErrorKind::FileTooLarge => err.into(),
+                    Err(err) => return Err(err),
+                }
+            }
+            Err(response) => response,
+        };
+
+        let mut message = Message::new(format!(
+            "HTTP/1.1 {} {}",
+            response.status,
+            response.reason()
+        ))
+        .with_header("Connection", "close");
+        message.headers.extend(response.headers);
+        message.body = response.body;
+        return message.write(&mut &stream);
+    }
+
+    fn method_not_allowed(&self) -> ServerResponse {
+        let allowed = if self.options.writable {
+            "GET, HEAD, PUT, DELETE"
+        } else {
+            "GET, HEAD"
+        };
+        let mut response = ServerResponse::text(405, "Method not allowed".into());
+        response.headers.push(("Allow".into(), allowed.into()));
+        return response;
+    }
+
+    fn list_types(&self) -> ServerResponse {
+        return match self.database_manager().type_folders() {
+            Ok(type_names) => listing(type_names.iter().map(|name| name.as_os_str())),
+            Err(err) => err.into(),
+        };
+    }
+
+    fn list_entries(&self, type_name: &str) -> ServerResponse {
+        let dbm = self.database_manager();
+        if !is_valid_segment(type_name) || !dbm.dir().join(type_name).is_dir() {
+            return ServerResponse::text(404, format!("No type \"{type_name}\""));
+        }
+        return match dbm.entry_names(OsStr::new(type_name)) {
+            Ok(names) => listing(names.iter().map(|name| name.as_os_str())),
+            Err(err) => err.into(),
+        };
+    }
+
+    fn get(&self, key: &DatabaseKeyBuf, resolve: bool) -> ServerResponse {
+        let dbm = self.database_manager();
+        let path = dbm.full_path_unchecked(key);
+        if !path.is_file() || dbm.is_expired_file(&path) {
+            return ServerResponse::text(404, format!("No entry {key}"));
+        }
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) => return err.into(),
+        };
+        let checksum = dbm.checksum_algorithm().checksum_bytes(&bytes);
+
+        let body = if resolve {
+            let inliner = match Inliner::for_entries(&dbm, std::slice::from_ref(key)) {
+                Ok(inliner) => inliner,
+                Err(err) => return err.into(),
+            };
+            let value = match inliner.inline_entry(key) {
+                Ok(value) => value,
+                Err(problem) => return ServerResponse::text(500, problem.to_string()),
+            };
+            match dbm.data_format().serialize_value(&value) {
+                Ok(body) => body,
+                Err(err) => return ServerResponse::text(500, err.to_string()),
+            }
+        } else {
+            match dbm.decode_file(&path, bytes) {
+                Ok(body) => body,
+                Err(err) => return err.into(),
+            }
+        };
+
+        let mut response = ServerResponse::new(200).with_checksum(checksum);
+        response.body = body;
+        return response;
+    }
+
+    fn put(&self, key: &DatabaseKeyBuf, request: &ServerRequest) -> ServerResponse {
+        let mut dbm = self.database_manager_mut();
+        let path = dbm.full_path_unchecked(key);
+        let existed = path.is_file() && !dbm.is_expired_file(&path);
+        let current_checksum = if existed {
+            dbm.checksum_algorithm().checksum_file(&path)
+        } else {
+            None
+        };
+        if let Some(response) = check_precondition(request, current_checksum) {
+            return response;
+        }
+        if let Some(response) = self.validate_entry(&mut dbm, key, &request.body) {
+            return response;
+        }
+
+        let result = (|| {
+            dbm.check_database_lock()?;
+            if let Some(folder) = path.parent() {
+                fs::create_dir_all(folder)?;
+            }
+            dbm.check_lock(&path)?;
+            let bytes = dbm.encode_file(&path, request.body.clone())?;
+            replace_file(&path, &bytes).map_err(|err| {
+                Error::new(
+                    err.kind(),
+                    format!("Could not write file {}: {}", path.display(), err),
+                )
+            })?;
+            dbm.sign_file(&path)?;
+            dbm.clear_expired(&path)?;
+            dbm.notify_written(&path, existed);
+            return Ok::<u32, Error>(dbm.checksum_algorithm().checksum_bytes(&bytes));
+        })();
+        dbm.evict_stale_cache_entries();
+
+        return match result {
+            Ok(checksum) => {
+                ServerResponse::new(if existed { 200 } else { 201 }).with_checksum(checksum)
+            }
+            Err(err) => err.into(),
+        };
+    }
+
+    /**
+    Checks that `body` is an entry of the type and with the name of `key` which
+    can be deserialized with its links resolved. Returns the response if the
+    entry is rejected.
+     */
+    fn validate_entry(
+        &self,
+        dbm: &mut DatabaseManager,
+        key: &DatabaseKeyBuf,
+        body: &[u8],
+    ) -> Option<ServerResponse> {
+        let type_name = key.type_name.as_os_str();
+        if !self.options.allow_new_types && !dbm.dir().join(type_name).is_dir() {
+            return Some(ServerResponse::text(
+                404,
+                format!("No type \"{}\"", type_name.to_string_lossy()),
+            ));
+        }
+        let value = match dbm.format_for_type(type_name).deserialize_value(body) {
+            Ok(value) => value,
+            Err(err) => return Some(ServerResponse::text(400, format!("Invalid entry: {err}"))),
+        };
+        let tag = match &value {
+            Value::Map(entries) if entries.len() == 1 => entries[0].0.as_str(),
+            _ => None,
+        };
+        if tag.map(OsStr::new) != Some(type_name) {
+            return Some(ServerResponse::text(
+                400,
+                format!(
+                    "The body is not an entry of the type {}",
+                    type_name.to_string_lossy()
+                ),
+            ));
+        }
+
+        let result = dbm.with_read_context(false, &ReadOptions::default(), |context| {
+            // SAFETY: The context only exists during this call, see DatabaseManager::from_str.
+            let dbm = unsafe { &*context.database_manager };
+            return dbm
+                .format_for_type(type_name)
+                .deserialize_dyn(body)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err));
+        });
+        match result {
+            Ok((entry, _)) if entry.name() == key.name.as_os_str() => return None,
+            Ok((entry, _)) => {
+                return Some(ServerResponse::text(
+                    400,
+                    format!(
+                        "The name \"{}\" of the entry doesn't match the name \"{}\" in the URL",
+                        entry.name().to_string_lossy(),
+                        key.name.to_string_lossy()
+                    ),
+                ));
+            }
+            Err(err) if err.kind() == ErrorKind::ResourceBusy => return Some(err.into()),
+            Err(err) => return Some(ServerResponse::text(400, format!("Invalid entry: {err}"))),
+        }
+    }
+
+    fn delete(&self, key: &DatabaseKeyBuf, request: &ServerRequest) -> ServerResponse {
+        let mut dbm = self.database_manager_mut();
+        let path = dbm.full_path_unchecked(key);
+        if !path.is_file() || dbm.is_expired_file(&path) {
+            return ServerResponse::text(404, format!("No entry {key}"));
+        }
+        let current_checksum = dbm.checksum_algorithm().checksum_file(&path);
+        if let Some(response) = check_precondition(request, current_checksum) {
+            return response;
+        }
+        let result = dbm.remove(key);
+        dbm.evict_stale_cache_entries();
+        return match result {
+            Ok(()) => ServerResponse::new(204),
+            Err(err) => err.into(),
+        };
+    }
+}
+
+/**
+Limits the number of connections processed concurrently by
+[`DatabaseServer::serve`].
+ */
+#[derive(Default)]
+struct ConnectionSlots {
+    active: Mutex<usize>,
+    released: Condvar,
+}
+
+impl ConnectionSlots {
+    /**
+    Blocks until less than `max` connections are active and occupies a slot
+    until the returned guard is dropped.
+     */
+    fn acquire(&self, max: usize) -> ConnectionSlot<'_> {
+        let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+        while *active >= max.max(1) {
+            active = self
+                .released
+                .wait(active)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        *active += 1;
+        return ConnectionSlot(self);
+    }
+}
+
+/**
+An occupied slot of [`ConnectionSlots`], which is released when dropped (also
+if processing the connection panicked).
+ */
+struct ConnectionSlot<'a>(&'a ConnectionSlots);
+
+impl Drop for ConnectionSlot<'_> {
+    fn drop(&mut self) {
+        *self.0.active.lock().unwrap_or_else(PoisonError::into_inner) -= 1;
+        self.0.released.notify_one();
+    }
+}
+
+/**
+Returns `false` for path segments which would escape the database directory
+or address nested folders.
+ */
+fn is_valid_segment(segment: &str) -> bool {
+    return !segment.is_empty()
+        && segment != "."
+        && segment != ".."
+        && !segment.contains(['/', '\\']);
+}
+
+/**
+Evaluates the `If-Match` and `If-None-Match` headers of `request` against the
+checksum of the current file. Returns the response if the precondition fails.
+ */
+fn check_precondition(request: &ServerRequest, current: Option<u32>) -> Option<ServerResponse> {
+    if let Some(expected) = request.header("If-Match") {
+        let holds = match current {
+            Some(current) => {
+                expected == "*"
+                    || expected.split(',').any(|tag| {
+                        tag.trim().trim_matches('"').parse::<u32>().ok() == Some(current)
+                    })
+            }
+            None => false,
+        };
+        if !holds {
+            return Some(ServerResponse::text(
+                412,
+                "The entry has been modified in the meantime".into(),
+            ));
+        }
+    }
+    if request.header("If-None-Match") == Some("*") && current.is_some() {
+        return Some(ServerResponse::text(412, "The entry already exists".into()));
+    }
+    return None;
+}
+
+fn listing<'a, I: Iterator<Item = &'a OsStr>>(names: I) -> ServerResponse {
+    let mut text = String::new();
+    for name in names {
+        text.push_str(&encode_segment(name));
+        text.push('\n');
+    }
+    return ServerResponse::text(200, text);
+}
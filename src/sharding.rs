@@ -0,0 +1,299 @@
+/*!
+This module contains the [`Sharding`] schemes which distribute the entries of a
+type over subfolders of its type folder, see
+[`DatabaseManager::set_sharding`].
+
+File systems such as ext4 or NTFS get slow when a single folder contains a very
+large number of files (roughly more than 100 000), since listing the folder and
+looking up files within it scale with its size. A sharded database stores the
+entry `pure_cotton` of the type `Material` e.g. in
+`/path/to/db/Material/pu/pure_cotton.yaml` instead of
+`/path/to/db/Material/pure_cotton.yaml`. The shard is derived from the name of
+the entry, so the path of an entry is still known without listing any folder.
+ */
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::{ChecksumAlgorithm, DatabaseManager};
+
+/**
+The scheme which determines the shard (i.e. the subfolder within its type
+folder) of a database entry from its name, see the
+[module documentation](crate::sharding).
+
+# Examples
+
+```
+use std::ffi::OsStr;
+use serde_mosaic::Sharding;
+
+assert_eq!(Sharding::None.shard(OsStr::new("pure_cotton")), None);
+assert_eq!(
+    Sharding::NamePrefix(2).shard(OsStr::new("pure_cotton")).as_deref(),
+    Some("pu")
+);
+assert_eq!(Sharding::NamePrefix(2).shard(OsStr::new("a")).as_deref(), Some("a_"));
+assert_eq!(Sharding::NameHash(2).shard(OsStr::new("pure_cotton")).unwrap().len(), 2);
+```
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Sharding {
+    /**
+    All entries of a type are stored directly within the type folder. This is
+    the default.
+     */
+    #[default]
+    None,
+    /**
+    The shard consists of the first `n` characters of the entry name. ASCII
+    letters and digits are kept, all other characters are replaced by `_`
+    (names shorter than `n` characters are padded with `_` as well). This
+    keeps the layout easy to navigate by hand, but entries are distributed
+    unevenly if many names share a prefix.
+     */
+    NamePrefix(usize),
+    /**
+    The shard consists of the first `n` (at most 8) hexadecimal digits of the
+    CRC-32 checksum of the entry name, which distributes the entries evenly
+    over `16^n` shards (e.g. 256 shards for `n = 2`).
+     */
+    NameHash(usize),
+}
+
+impl Sharding {
+    /**
+    Returns `true` if entries are stored within shards. This is `false` for
+    [`Sharding::None`] and for shards with a length of zero.
+     */
+    pub fn is_sharded(&self) -> bool {
+        match self {
+            Sharding::None => return false,
+            Sharding::NamePrefix(n) | Sharding::NameHash(n) => return *n > 0,
+        }
+    }
+
+    /**
+    Returns the shard of the entry `name` or [`None`] if the entries are not
+    sharded (see [`Sharding::is_sharded`]).
+     */
+    pub fn shard(&self, name: &OsStr) -> Option<String> {
+        if !self.is_sharded() {
+            return None;
+        }
+        match self {
+            Sharding::None => return None,
+            Sharding::NamePrefix(n) => {
+                let name = name.to_string_lossy();
+                let mut chars = name.chars();
+                let shard = (0..*n)
+                    .map(|_| match chars.next() {
+                        Some(ch) if ch.is_ascii_alphanumeric() => ch,
+                        _ => '_',
+                    })
+                    .collect();
+                return Some(shard);
+            }
+            Sharding::NameHash(n) => {
+                let hash = ChecksumAlgorithm::Crc32.checksum_bytes(name.as_encoded_bytes());
+                let mut shard = format!("{hash:08x}");
+                shard.truncate((*n).min(8));
+                return Some(shard);
+            }
+        }
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Sets the [`Sharding`] scheme of `self`, which is used to resolve the paths
+    of all entries afterwards. Existing files are not moved, so entries written
+    with another scheme can't be found anymore. Use
+    [`DatabaseManager::reshard`] to convert an existing database instead.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+        cotton_content: f64,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.set_sharding(Sharding::NamePrefix(2));
+
+    let material = Material { name: "pure_cotton".into(), cotton_content: 1.0 };
+    let path = dbm.write(&material, &WriteOptions::default()).expect("writing succeeds");
+    assert!(path.ends_with("Material/pu/pure_cotton.yaml"));
+    ```
+     */
+    pub fn set_sharding(&mut self, sharding: Sharding) {
+        self.sharding = sharding;
+    }
+
+    /**
+    Returns the [`Sharding`] scheme of `self`, see
+    [`DatabaseManager::set_sharding`].
+     */
+    pub fn sharding(&self) -> Sharding {
+        return self.sharding;
+    }
+
+    /**
+    Moves the files of all entries (including their signatures and expiry
+    files) from the current layout of `self` to the layout of `sharding` and
+    sets it as the new scheme (see [`DatabaseManager::set_sharding`]). Shards
+    which are empty afterwards are removed. Returns the new paths of the moved
+    entry files in alphabetical order.
+
+    Files which don't belong to an entry in the current layout (e.g. files
+    within a shard which doesn't match their name) are not moved. An error is
+    returned if the database or one of its entries is locked by another
+    manager (see [`DatabaseManager::lock`]) or if there are staged entries
+    which haven't been flushed yet (see [`DatabaseManager::enable_staging`]).
+     */
+    pub fn reshard(&mut self, sharding: Sharding) -> std::io::Result<Vec<PathBuf>> {
+        self.check_database_lock()?;
+        if !self.staged_files().is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Staged entries must be flushed before resharding the database",
+            ));
+        }
+        let mut moved = Vec::new();
+        for type_name in self.type_folders()? {
+            for path in self.type_folder_files(&type_name)? {
+                let Some(file_name) = path.file_name().and_then(OsStr::to_str) else {
+                    continue;
+                };
+
+                // Companion files move along with their entry
+                let entry_file_name = [".sig", ".expires"]
+                    .into_iter()
+                    .find_map(|suffix| file_name.strip_suffix(suffix))
+                    .unwrap_or(file_name);
+                let Some(name) = self.entry_name(&type_name, OsStr::new(entry_file_name)) else {
+                    continue;
+                };
+                if !self.is_in_shard(&type_name, &name, &path) {
+                    continue;
+                }
+                self.check_lock(&path.with_file_name(entry_file_name))?;
+
+                let mut new_path = self.dir().join(&type_name);
+                if let Some(shard) = sharding.shard(&name) {
+                    new_path.push(shard);
+                }
+                new_path.push(file_name);
+                if new_path == path {
+                    continue;
+                }
+                if let Some(folder) = new_path.parent() {
+                    fs::create_dir_all(folder)?;
+                }
+                fs::rename(&path, &new_path)?;
+                if entry_file_name == file_name {
+                    moved.push(new_path);
+                }
+            }
+            remove_empty_shards(&self.dir().join(&type_name))?;
+        }
+
+        self.sharding = sharding;
+        moved.sort();
+        return Ok(moved);
+    }
+
+    /**
+    Returns the type folder of the database file at `path`, independent of
+    its shard. Files outside of the database directory are assumed to be
+    located directly within their type folder.
+     */
+    pub(crate) fn type_folder_of<'a>(&self, path: &'a Path) -> Option<&'a OsStr> {
+        match path.strip_prefix(self.dir()) {
+            Ok(relative) => {
+                let mut components = relative.components();
+                let type_name = components.next()?.as_os_str();
+                // Files within the database directory itself have no type
+                components.next()?;
+                return Some(type_name);
+            }
+            Err(_) => return path.parent().and_then(Path::file_name),
+        }
+    }
+
+    /**
+    Returns the paths of all files at the locations of entries within the
+    type folder `type_name`, i.e. of the files directly within the folder or,
+    if the database is sharded, of the files within its shards. Besides entry
+    files, this includes e.g. signatures and temporary files.
+     */
+    pub(crate) fn type_folder_files(&self, type_name: &OsStr) -> std::io::Result<Vec<PathBuf>> {
+        let folder = self.dir().join(type_name);
+        if !folder.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&folder)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if !self.sharding.is_sharded() {
+                if file_type.is_file() {
+                    files.push(entry.path());
+                }
+                continue;
+            }
+            if file_type.is_dir() {
+                for shard_entry in fs::read_dir(entry.path())? {
+                    let shard_entry = shard_entry?;
+                    if shard_entry.file_type()?.is_file() {
+                        files.push(shard_entry.path());
+                    }
+                }
+            }
+        }
+        files.sort();
+        return Ok(files);
+    }
+
+    /**
+    Returns `true` if the file at `path` is located in the shard of the entry
+    `name` within the type folder `type_name`.
+     */
+    pub(crate) fn is_in_shard(&self, type_name: &OsStr, name: &OsStr, path: &Path) -> bool {
+        return self.full_path_unchecked((type_name, name)).parent() == path.parent();
+    }
+}
+
+/**
+Removes all empty subfolders of the type folder `folder`.
+ */
+pub(crate) fn remove_empty_shards(folder: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    if !folder.is_dir() {
+        return Ok(removed);
+    }
+    for entry in fs::read_dir(folder)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() && path.read_dir()?.next().is_none() {
+            fs::remove_dir(&path)?;
+            removed.push(path);
+        }
+    }
+    return Ok(removed);
+}
@@ -0,0 +1,212 @@
+/*!
+This module contains the [`SharedCache`], a [`Cache`] which can be shared by
+multiple [`DatabaseManager`]s, see [`DatabaseManager::set_shared_cache`].
+
+Every [`DatabaseManager`] owns its cache, so cloning a manager (e.g. to read
+in multiple threads at once) duplicates the cached instances and an entry
+which is read by two clones results in two distinct `Arc` instances. Managers
+which use the same [`SharedCache`] put the instances they read into the shared
+cache instead and therefore reuse the instances read by each other. The
+[`CacheStats`](crate::CacheStats) and the bookkeeping for the
+[`CacheCapacity`](crate::CacheCapacity) are part of the shared cache as well.
+ */
+
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{Cache, CacheUsage, DatabaseManager};
+
+/**
+A handle to a [`Cache`] which is shared by all [`DatabaseManager`]s it has
+been set for (see [`DatabaseManager::set_shared_cache`]). The handle can be
+cloned cheaply, all clones refer to the same cache.
+
+# Examples
+
+```no_run
+use std::ffi::OsStr;
+use std::sync::Arc;
+use std::thread;
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Shirt {
+    owner: String,
+    #[serde(serialize_with = "serialize_arc_link")]
+    #[serde(deserialize_with = "deserialize_arc_link")]
+    material: Arc<Material>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Shirt {
+    fn name(&self) -> &OsStr {
+        self.owner.as_ref()
+    }
+}
+
+let shared_cache = SharedCache::new();
+let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+dbm.set_shared_cache(Some(shared_cache.clone()));
+
+// Every thread uses its own manager, but all of them share the cache
+let materials: Vec<Arc<Material>> = thread::scope(|scope| {
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let mut dbm = dbm.clone();
+            scope.spawn(move || {
+                let shirt: Shirt = dbm.read("joe").expect("entry exists");
+                shirt.material
+            })
+        })
+        .collect();
+    handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+});
+assert!(materials.iter().all(|material| Arc::ptr_eq(material, &materials[0])));
+```
+ */
+#[derive(Clone, Default)]
+pub struct SharedCache {
+    cache: Arc<RwLock<Cache>>,
+    usage: Arc<Mutex<CacheUsage>>,
+}
+
+impl SharedCache {
+    /**
+    Creates a new, empty [`SharedCache`].
+     */
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /**
+    Gives shared access to the underlying [`Cache`]. The guard blocks all
+    managers which use the cache from resolving links, so it should only be
+    held briefly.
+     */
+    pub fn read(&self) -> RwLockReadGuard<'_, Cache> {
+        return self.cache.read().unwrap_or_else(PoisonError::into_inner);
+    }
+
+    /**
+    Gives exclusive access to the underlying [`Cache`], e.g. for manually
+    adding instances via [`CacheEntry::insert`](crate::CacheEntry::insert).
+    The guard blocks all managers which use the cache from resolving links,
+    so it should only be held briefly.
+     */
+    pub fn write(&self) -> RwLockWriteGuard<'_, Cache> {
+        return self.cache.write().unwrap_or_else(PoisonError::into_inner);
+    }
+
+    /**
+    Creates a new [`SharedCache`] from `cache` and its bookkeeping `usage`
+    (e.g. those of a [`DatabaseManager`] which is going to share its cache).
+     */
+    pub(crate) fn from_parts(cache: Cache, usage: CacheUsage) -> Self {
+        return Self {
+            cache: Arc::new(RwLock::new(cache)),
+            usage: Arc::new(Mutex::new(usage)),
+        };
+    }
+
+    /**
+    Moves the cache and its bookkeeping out of `self`, leaving an empty cache
+    behind. This is the inverse of [`SharedCache::from_parts`].
+     */
+    pub(crate) fn take_parts(&self) -> (Cache, CacheUsage) {
+        return self.with_usage(|cache, usage| (std::mem::take(cache), std::mem::take(usage)));
+    }
+
+    /**
+    Gives exclusive access to the underlying [`Cache`] and its bookkeeping.
+    The bookkeeping is only ever locked while holding the write lock of the
+    cache, so instances are evicted (see
+    [`CacheCapacity`](crate::CacheCapacity)) without any other manager
+    accessing the cache in the meantime.
+     */
+    pub(crate) fn with_usage<R, F: FnOnce(&mut Cache, &mut CacheUsage) -> R>(&self, f: F) -> R {
+        let mut cache = self.write();
+        let mut usage = self.usage.lock().unwrap_or_else(PoisonError::into_inner);
+        return f(&mut cache, &mut usage);
+    }
+}
+
+impl From<Cache> for SharedCache {
+    fn from(cache: Cache) -> Self {
+        return Self::from_parts(cache, CacheUsage::default());
+    }
+}
+
+impl std::fmt::Debug for SharedCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cache = self.read();
+        let entries: usize = cache.values().map(|subcache| subcache.len()).sum();
+        return f
+            .debug_struct("SharedCache")
+            .field("entries", &entries)
+            .finish();
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Sets the [`SharedCache`] of `self`. From now on, instances are read from
+    and put into the shared cache instead of the own [`Cache`] of `self`
+    (which is left untouched and can still be accessed via
+    [`DatabaseManager::cache`]). Clones of `self` use the same shared cache.
+    Passing [`None`] makes `self` use its own cache again.
+
+    The typed accessors (e.g. [`DatabaseManager::cache_get`]) operate on the
+    shared cache, if one is set. The [`CacheCapacity`](crate::CacheCapacity)
+    of `self` is enforced for the shared cache whenever `self` puts an
+    instance into it. The [`CacheStats`](crate::CacheStats) are tracked by the
+    shared cache, so [`DatabaseManager::cache_stats`] includes the cache hits
+    and misses of all managers which use it.
+     */
+    pub fn set_shared_cache(&mut self, shared_cache: Option<SharedCache>) {
+        self.shared_cache = shared_cache;
+    }
+
+    /**
+    Returns the [`SharedCache`] of `self`, if any.
+     */
+    pub fn shared_cache(&self) -> Option<&SharedCache> {
+        return self.shared_cache.as_ref();
+    }
+
+    /**
+    Gives exclusive access to the [`Cache`] which is currently used by `self`:
+    The [`SharedCache`] if one is set, otherwise the own cache of `self`.
+     */
+    pub(crate) fn with_cache_mut<R, F: FnOnce(&mut Cache) -> R>(&mut self, f: F) -> R {
+        match &self.shared_cache {
+            Some(shared_cache) => return f(&mut *shared_cache.write()),
+            None => return f(&mut self.cache),
+        }
+    }
+
+    /**
+    Like [`DatabaseManager::with_cache_mut`], but gives access to the
+    bookkeeping of the cache as well (see [`SharedCache::with_usage`]).
+     */
+    pub(crate) fn with_cache_usage<R, F: FnOnce(&mut Cache, &mut CacheUsage) -> R>(
+        &mut self,
+        f: F,
+    ) -> R {
+        match &self.shared_cache {
+            Some(shared_cache) => return shared_cache.with_usage(f),
+            None => return f(&mut self.cache, &mut self.cache_usage),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+/*!
+This module contains the [`SharedDatabaseManager`], a thread-safe front end for
+a [`DatabaseManager`] whose methods take a shared reference of `self`.
+
+Most methods of a [`DatabaseManager`] take a mutable reference of `self`, since
+reading an entry fills the [`Cache`](crate::Cache). Sharing a manager between
+threads (e.g. within the state of a web application) therefore requires a lock,
+and wrapping it in a [`Mutex`](std::sync::Mutex) serializes all operations. A
+[`SharedDatabaseManager`] instead reads on a clone of the manager, so reads
+are executed concurrently. All clones use the same
+[`SharedCache`](crate::SharedCache), hence instances cached by one read are
+immediately available to all other reads and writes.
+ */
+
+use std::ffi::{OsStr, OsString};
+use std::mem;
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{DatabaseEntry, DatabaseKey, DatabaseManager, ReadOptions, SharedCache, WriteOptions};
+
+/**
+A thread-safe wrapper around a [`DatabaseManager`], see the
+[module documentation](crate::shared_manager). The wrapper can be cloned
+cheaply, all clones use the same underlying manager.
+
+Reads are executed concurrently: Each read operates on a clone of the manager
+(see [`DatabaseManager::clone`]). In order to share the cached instances
+between these clones, the underlying manager uses a
+[`SharedCache`](crate::SharedCache) (see [`SharedDatabaseManager::new`]).
+Writes and removals are executed one after another.
+
+# Examples
+
+```no_run
+use std::ffi::OsStr;
+use std::thread;
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+
+#[derive(Serialize, Deserialize)]
+struct Material {
+    name: String,
+    cotton_content: f64,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Material {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+let dbm = SharedDatabaseManager::new(dbm);
+let material = Material { name: "cotton".into(), cotton_content: 1.0 };
+dbm.write(&material, &WriteOptions::default()).expect("writing succeeds");
+
+thread::scope(|scope| {
+    for _ in 0..4 {
+        scope.spawn(|| {
+            let material: Material = dbm.read("cotton").expect("entry exists");
+            assert_eq!(material.cotton_content, 1.0);
+        });
+    }
+});
+```
+ */
+#[derive(Clone)]
+pub struct SharedDatabaseManager {
+    dbm: Arc<RwLock<DatabaseManager>>,
+}
+
+impl SharedDatabaseManager {
+    /**
+    Creates a new [`SharedDatabaseManager`] from `dbm`. If `dbm` doesn't use a
+    [`SharedCache`] yet (see [`DatabaseManager::set_shared_cache`]), its
+    [`Cache`](crate::Cache) is moved into a new shared cache, which is
+    accessible via [`DatabaseManager::shared_cache`] afterwards. The shared
+    cache keeps the [`CacheStats`](crate::CacheStats) of `dbm` and is limited
+    by its [`CacheCapacity`](crate::CacheCapacity).
+     */
+    pub fn new(mut dbm: DatabaseManager) -> Self {
+        if dbm.shared_cache().is_none() {
+            let shared_cache =
+                SharedCache::from_parts(mem::take(&mut dbm.cache), mem::take(&mut dbm.cache_usage));
+            dbm.set_shared_cache(Some(shared_cache));
+        }
+        return Self {
+            dbm: Arc::new(RwLock::new(dbm)),
+        };
+    }
+
+    /**
+    Gives shared access to the underlying [`DatabaseManager`], e.g. for
+    inspecting its configuration. The guard blocks the current thread while an
+    operation is writing, so it should only be held briefly.
+     */
+    pub fn database_manager(&self) -> RwLockReadGuard<'_, DatabaseManager> {
+        return self.dbm.read().unwrap_or_else(PoisonError::into_inner);
+    }
+
+    /**
+    Gives exclusive access to the underlying [`DatabaseManager`], e.g. for
+    changing its configuration or for calling methods which aren't provided by
+    the wrapper. The guard blocks the current thread until all running
+    operations are finished, so it should only be held briefly.
+     */
+    pub fn database_manager_mut(&self) -> RwLockWriteGuard<'_, DatabaseManager> {
+        return self.dbm.write().unwrap_or_else(PoisonError::into_inner);
+    }
+
+    /**
+    Thread-safe version of [`DatabaseManager::read`].
+     */
+    pub fn read<T: DatabaseEntry, O: AsRef<OsStr>>(&self, name: O) -> std::io::Result<T> {
+        return self.read_with(name, &ReadOptions::default());
+    }
+
+    /**
+    Thread-safe version of [`DatabaseManager::read_with`]. The shared lock of
+    the underlying manager is only held while cloning it, so other operations
+    aren't blocked by the file I/O of the read. The clone uses the
+    [`SharedCache`] of the underlying manager. If the shared cache has been
+    removed (e.g. via [`SharedDatabaseManager::database_manager_mut`]), the
+    instances cached during a read are discarded afterwards.
+     */
+    pub fn read_with<T: DatabaseEntry, O: AsRef<OsStr>>(
+        &self,
+        name: O,
+        read_options: &ReadOptions,
+    ) -> std::io::Result<T> {
+        let mut dbm = self.database_manager().clone();
+        return dbm.read_with(name, read_options);
+    }
+
+    /**
+    Thread-safe version of [`DatabaseManager::write`].
+     */
+    pub fn write<T: DatabaseEntry>(
+        &self,
+        instance: &T,
+        write_options: &WriteOptions,
+    ) -> std::io::Result<PathBuf> {
+        return self.database_manager_mut().write(instance, write_options);
+    }
+
+    /**
+    Thread-safe version of [`DatabaseManager::remove`].
+     */
+    pub fn remove<'a, K: Into<DatabaseKey<'a>>>(&self, key: K) -> std::io::Result<()> {
+        return self.database_manager_mut().remove(key);
+    }
+
+    /**
+    Thread-safe version of [`DatabaseManager::exists`].
+     */
+    pub fn exists<'a, K: Into<DatabaseKey<'a>>>(&self, key: K) -> bool {
+        return self.database_manager().exists(key);
+    }
+
+    /**
+    Thread-safe version of [`DatabaseManager::list`]. The names are collected
+    while holding the shared lock of the underlying manager.
+     */
+    pub fn list<T: DatabaseEntry>(&self) -> std::io::Result<Vec<OsString>> {
+        return Ok(self.database_manager().list::<T>()?.collect());
+    }
+}
+
+impl From<DatabaseManager> for SharedDatabaseManager {
+    fn from(dbm: DatabaseManager) -> Self {
+        return Self::new(dbm);
+    }
+}
@@ -0,0 +1,309 @@
+/*!
+This module contains functionality to sign database files with detached
+[Ed25519](https://ed25519.cr.yp.to/) signatures and to verify these signatures
+when reading. This allows proving that (e.g. released) database entries
+haven't been tampered with.
+
+The signing and verification keys are configured via the [`Signatures`] type
+(requires the `signatures` feature), see
+[`DatabaseManager::set_signatures`]. If a signing key is configured, the
+signature of every file written by the [`DatabaseManager`] is stored next to
+the file, with `.sig` appended to the file name (e.g. `Material/cotton.yaml.sig`).
+Reading a file verifies its signature against the trusted public keys; the
+result is reported in [`ReadInfo::signature_problems`](crate::ReadInfo::signature_problems) and
+by [`DatabaseManager::verify_entry`] (see
+[`FileReport::signature`](crate::FileReport::signature)).
+
+The signature covers the file as stored on disk (i.e. after encryption, if
+used).
+ */
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::DatabaseManager;
+
+#[cfg(feature = "signatures")]
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+#[cfg(feature = "signatures")]
+use std::io::{Error, ErrorKind};
+
+/**
+The result of verifying the detached signature of a database file.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /**
+    The signature matches the file and was created by one of the trusted keys.
+     */
+    Valid,
+    /**
+    The file has no signature.
+     */
+    Missing,
+    /**
+    The signature is malformed, doesn't match the file (i.e. the file was
+    modified after signing) or wasn't created by a trusted key.
+     */
+    Invalid,
+}
+
+/**
+A file whose signature could not be verified during a read. See
+[`ReadInfo::signature_problems`](crate::ReadInfo::signature_problems).
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureProblem {
+    /**
+    Path of the read file.
+     */
+    pub file_path: PathBuf,
+    /**
+    The verification result, either [`SignatureStatus::Missing`] or
+    [`SignatureStatus::Invalid`].
+     */
+    pub status: SignatureStatus,
+}
+
+/**
+Specifies the behaviour of [`DatabaseManager::read`] when the signature of a
+read file can't be verified.
+ */
+#[cfg(feature = "signatures")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignaturePolicy {
+    #[default]
+    /**
+    The file is read regardless and the problem is reported in the
+    [`ReadInfo`](crate::ReadInfo) returned by
+    [`DatabaseManager::read_verbose`].
+     */
+    Report,
+    /**
+    Reading fails with an error of kind [`ErrorKind::InvalidData`].
+     */
+    Require,
+}
+
+/**
+The signing and verification keys of a [`DatabaseManager`]. See the module
+docstring and [`DatabaseManager::set_signatures`].
+
+# Examples
+
+```no_run
+use serde_mosaic::*;
+
+// The release process signs the files ...
+let secret_key = [7; 32];
+let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+dbm.set_signatures(Some(Signatures::new().sign_with(secret_key)));
+
+// ... and the consumers verify them with the public key
+let public_key = Signatures::public_key_of(secret_key);
+let signatures = Signatures::new()
+    .trust(public_key)
+    .expect("valid public key")
+    .policy(SignaturePolicy::Require);
+dbm.set_signatures(Some(signatures));
+```
+ */
+#[cfg(feature = "signatures")]
+#[derive(Clone)]
+pub struct Signatures {
+    signing_key: Option<SigningKey>,
+    trusted_keys: Vec<VerifyingKey>,
+    policy: SignaturePolicy,
+}
+
+#[cfg(feature = "signatures")]
+impl Signatures {
+    /**
+    Creates a configuration without any keys, which reports all files as
+    unsigned.
+     */
+    pub fn new() -> Self {
+        return Self {
+            signing_key: None,
+            trusted_keys: Vec::new(),
+            policy: SignaturePolicy::default(),
+        };
+    }
+
+    /**
+    Sets the secret key used to sign written files. The corresponding public
+    key is trusted as well.
+     */
+    pub fn sign_with(mut self, secret_key: [u8; 32]) -> Self {
+        let signing_key = SigningKey::from_bytes(&secret_key);
+        self.trusted_keys.push(signing_key.verifying_key());
+        self.signing_key = Some(signing_key);
+        return self;
+    }
+
+    /**
+    Adds a public key whose signatures are accepted. Returns an error if the
+    bytes are not a valid Ed25519 public key.
+     */
+    pub fn trust(mut self, public_key: [u8; 32]) -> std::io::Result<Self> {
+        let key = VerifyingKey::from_bytes(&public_key)
+            .map_err(|err| Error::new(ErrorKind::InvalidInput, err.to_string()))?;
+        self.trusted_keys.push(key);
+        return Ok(self);
+    }
+
+    /**
+    Sets the [`SignaturePolicy`] for reading.
+     */
+    pub fn policy(mut self, policy: SignaturePolicy) -> Self {
+        self.policy = policy;
+        return self;
+    }
+
+    /**
+    Returns the public key which belongs to `secret_key`.
+     */
+    pub fn public_key_of(secret_key: [u8; 32]) -> [u8; 32] {
+        return SigningKey::from_bytes(&secret_key)
+            .verifying_key()
+            .to_bytes();
+    }
+
+    fn verify(&self, bytes: &[u8], signature: &str) -> SignatureStatus {
+        let Some(signature) = decode_hex(signature.trim()) else {
+            return SignatureStatus::Invalid;
+        };
+        let signature = Signature::from_bytes(&signature);
+        if self
+            .trusted_keys
+            .iter()
+            .any(|key| key.verify_strict(bytes, &signature).is_ok())
+        {
+            return SignatureStatus::Valid;
+        }
+        return SignatureStatus::Invalid;
+    }
+}
+
+#[cfg(feature = "signatures")]
+impl Default for Signatures {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(feature = "signatures")]
+impl std::fmt::Debug for Signatures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f
+            .debug_struct("Signatures")
+            .field("signing", &self.signing_key.is_some())
+            .field("trusted_keys", &self.trusted_keys)
+            .field("policy", &self.policy)
+            .finish();
+    }
+}
+
+#[cfg(feature = "signatures")]
+fn decode_hex(hex: &str) -> Option<[u8; 64]> {
+    if hex.len() != 128 || !hex.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0; 64];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * index..2 * index + 2], 16).ok()?;
+    }
+    return Some(bytes);
+}
+
+#[cfg(feature = "signatures")]
+impl DatabaseManager {
+    /**
+    Sets the [`Signatures`] configuration of `self`. Passing [`None`] disables
+    both signing and verification.
+     */
+    pub fn set_signatures(&mut self, signatures: Option<Signatures>) {
+        self.signatures = signatures;
+    }
+
+    /**
+    Returns the [`Signatures`] configuration of `self`, if any.
+     */
+    pub fn signatures(&self) -> Option<&Signatures> {
+        return self.signatures.as_ref();
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Returns the path of the detached signature of the file at `path`.
+     */
+    pub(crate) fn signature_path(path: &Path) -> PathBuf {
+        let mut signature_path: OsString = path.as_os_str().to_os_string();
+        signature_path.push(".sig");
+        return PathBuf::from(signature_path);
+    }
+
+    /**
+    Signs the file at `path` if a signing key is configured.
+     */
+    pub(crate) fn sign_file(&self, path: &Path) -> std::io::Result<()> {
+        #[cfg(feature = "signatures")]
+        if let Some(signatures) = &self.signatures
+            && let Some(signing_key) = &signatures.signing_key
+        {
+            let signature = signing_key.sign(&std::fs::read(path)?).to_bytes();
+            let mut hex: String = signature
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            hex.push('\n');
+            crate::database_manager::replace_file(&Self::signature_path(path), hex.as_bytes())?;
+        }
+        let _ = path;
+        return Ok(());
+    }
+
+    /**
+    Returns `true` if signatures are verified when reading files.
+     */
+    pub(crate) fn checks_signatures(&self) -> bool {
+        #[cfg(feature = "signatures")]
+        return self.signatures.is_some();
+        #[cfg(not(feature = "signatures"))]
+        return false;
+    }
+
+    /**
+    Returns `true` if reading a file whose signature can't be verified must
+    fail (see [`SignaturePolicy::Require`]).
+     */
+    pub(crate) fn requires_signatures(&self) -> bool {
+        #[cfg(feature = "signatures")]
+        return self
+            .signatures
+            .as_ref()
+            .is_some_and(|signatures| signatures.policy == SignaturePolicy::Require);
+        #[cfg(not(feature = "signatures"))]
+        return false;
+    }
+
+    /**
+    Verifies the signature of the file at `path` whose contents are `bytes`.
+    Returns [`None`] if signatures are not checked.
+     */
+    pub(crate) fn check_signature(&self, path: &Path, bytes: &[u8]) -> Option<SignatureStatus> {
+        #[cfg(feature = "signatures")]
+        if let Some(signatures) = &self.signatures {
+            match std::fs::read_to_string(Self::signature_path(path)) {
+                Ok(signature) => return Some(signatures.verify(bytes, &signature)),
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    return Some(SignatureStatus::Missing);
+                }
+                Err(_) => return Some(SignatureStatus::Invalid),
+            }
+        }
+        let _ = (path, bytes);
+        return None;
+    }
+}
@@ -0,0 +1,326 @@
+/*!
+This module contains the staging mode of a [`DatabaseManager`], see
+[`DatabaseManager::enable_staging`].
+
+While staging is enabled, [`DatabaseManager::write`] serializes the entries as
+usual, but keeps the resulting file contents in memory instead of writing them
+to disk. Writing the same entry repeatedly only replaces its staged contents,
+so only the last version hits the disk when the staged files are written via
+[`DatabaseManager::flush`]. This is useful for applications which write very
+frequently (e.g. an editor which saves on every change).
+
+The staged files act as an overlay over the files on disk: Reading an entry
+(including its links), [`DatabaseManager::exists`] and
+[`DatabaseManager::checksum`] prefer the staged contents. A pipeline can
+therefore compute with provisional entries which link to each other and only
+persist the final state (or nothing at all via
+[`DatabaseManager::discard_staged`]).
+
+The staged files are shared between all clones of a manager. When the last
+clone is dropped, the remaining staged files are either written or discarded,
+depending on the [`StagingDropPolicy`].
+ */
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::database_manager::replace_file;
+use crate::{ChecksumAlgorithm, DatabaseManager};
+
+/**
+Specifies what happens to the staged files of a [`DatabaseManager`] when its
+last clone is dropped, see [`DatabaseManager::enable_staging`].
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StagingDropPolicy {
+    #[default]
+    /**
+    The staged files are written to disk. Since errors can't be reported
+    during a drop, they are ignored - call [`DatabaseManager::flush`]
+    explicitly to handle them.
+     */
+    Flush,
+    /**
+    The staged files are discarded.
+     */
+    Discard,
+}
+
+/**
+The staged files of a [`DatabaseManager`] (file path and the contents which
+would have been written to it).
+ */
+#[derive(Debug, Default)]
+pub(crate) struct Staging {
+    pub(crate) files: BTreeMap<PathBuf, Vec<u8>>,
+    drop_policy: StagingDropPolicy,
+}
+
+impl DatabaseManager {
+    /**
+    Enables the staging mode: From now on, all files written by `self` (and
+    its clones) are kept in memory until [`DatabaseManager::flush`] is called.
+    When the last clone of `self` is dropped, the staged files are treated
+    according to `drop_policy`. If staging is already enabled, only the drop
+    policy is changed.
+
+    Links to staged entries contain the checksums of the staged contents, so
+    the database is consistent once all staged files have been written.
+    Reading prefers staged contents over the files on disk (see the
+    [module documentation](crate::staging)). Bulk operations such as
+    [`DatabaseManager::merge_from`] or [`DatabaseManager::validate_layout`] only see
+    the files on disk.
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Document {
+        name: String,
+        text: String,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Document {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    dbm.enable_staging(StagingDropPolicy::Flush);
+
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    let mut document = Document { name: "draft".into(), text: String::new() };
+    for word in ["Hello", " ", "world"] {
+        document.text.push_str(word);
+        dbm.write(&document, &write_options).expect("serializable");
+    }
+
+    // The file is written only once
+    dbm.flush().expect("database is writable");
+    ```
+     */
+    pub fn enable_staging(&mut self, drop_policy: StagingDropPolicy) {
+        match &self.staging {
+            Some(staging) => lock(staging).drop_policy = drop_policy,
+            None => {
+                self.staging = Some(Arc::new(Mutex::new(Staging {
+                    files: BTreeMap::new(),
+                    drop_policy,
+                })));
+            }
+        }
+    }
+
+    /**
+    Writes all staged files via [`DatabaseManager::flush`] and disables the
+    staging mode for `self`. Clones of `self` keep staging (into their own,
+    shared staging area). If writing fails, the staging mode stays enabled.
+     */
+    pub fn disable_staging(&mut self) -> std::io::Result<Vec<PathBuf>> {
+        let written = self.flush()?;
+        self.staging = None;
+        return Ok(written);
+    }
+
+    /**
+    Returns `true` if the staging mode is enabled.
+     */
+    pub fn is_staging(&self) -> bool {
+        return self.staging.is_some();
+    }
+
+    /**
+    Returns the paths of all staged files in alphabetical order.
+     */
+    pub fn staged_files(&self) -> Vec<PathBuf> {
+        match &self.staging {
+            Some(staging) => return lock(staging).files.keys().cloned().collect(),
+            None => return Vec::new(),
+        }
+    }
+
+    /**
+    Writes all staged files to disk and returns their paths. The staging mode
+    stays enabled.
+
+    The files are written one after another. If writing a file fails, the
+    error is returned and the files which haven't been written yet stay
+    staged.
+     */
+    pub fn flush(&mut self) -> std::io::Result<Vec<PathBuf>> {
+        let Some(staging) = self.staging.clone() else {
+            return Ok(Vec::new());
+        };
+        let files = std::mem::take(&mut lock(&staging).files);
+        if files.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut written = Vec::new();
+        let mut files = files.into_iter();
+        let result = self.check_database_lock().and_then(|_| {
+            for (path, bytes) in files.by_ref() {
+                let existed = path.exists();
+                if let Err(err) = self.write_staged_file(&path, &bytes) {
+                    // Keep the file which could not be written
+                    lock(&staging).files.entry(path).or_insert(bytes);
+                    return Err(err);
+                }
+                self.notify_written(&path, existed);
+                written.push(path);
+            }
+            return Ok(());
+        });
+
+        // Newer versions which have been staged in the meantime win
+        let mut staging = lock(&staging);
+        for (path, bytes) in files {
+            staging.files.entry(path).or_insert(bytes);
+        }
+        drop(staging);
+
+        self.evict_stale_cache_entries();
+        return result.map(|_| written);
+    }
+
+    /**
+    Discards all staged files without writing them and returns their paths.
+    Cached entries which were read from staged contents are evicted.
+     */
+    pub fn discard_staged(&mut self) -> Vec<PathBuf> {
+        let discarded: Vec<PathBuf> = match &self.staging {
+            Some(staging) => std::mem::take(&mut lock(staging).files)
+                .into_keys()
+                .collect(),
+            None => return Vec::new(),
+        };
+        if !discarded.is_empty() {
+            self.evict_stale_cache_entries();
+        }
+        return discarded;
+    }
+
+    /**
+    Stages the file contents `bytes` for `path`. Does nothing if staging is
+    disabled.
+     */
+    pub(crate) fn stage(&self, path: &Path, bytes: Vec<u8>) {
+        if let Some(staging) = &self.staging {
+            lock(staging).files.insert(path.to_path_buf(), bytes);
+        }
+    }
+
+    /**
+    Returns `true` if there are staged contents for the file at `path`.
+     */
+    pub(crate) fn is_staged(&self, path: &Path) -> bool {
+        match &self.staging {
+            Some(staging) => return lock(staging).files.contains_key(path),
+            None => return false,
+        }
+    }
+
+    /**
+    Returns the staged contents of the file at `path`, if there are any.
+     */
+    pub(crate) fn staged(&self, path: &Path) -> Option<Vec<u8>> {
+        return lock(self.staging.as_ref()?).files.get(path).cloned();
+    }
+
+    /**
+    Removes the staged contents of the file at `path`. Returns `true` if the
+    file was staged.
+     */
+    pub(crate) fn unstage(&self, path: &Path) -> bool {
+        match &self.staging {
+            Some(staging) => return lock(staging).files.remove(path).is_some(),
+            None => return false,
+        }
+    }
+
+    /**
+    Returns `true` if the file at `path` is either staged or exists on disk.
+     */
+    pub(crate) fn file_exists(&self, path: &Path) -> bool {
+        return self.is_staged(path) || path.exists();
+    }
+
+    /**
+    Returns the staged contents of the file at `path` or reads the file from
+    disk if it isn't staged.
+     */
+    pub(crate) fn read_file(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        match self.staged(path) {
+            Some(bytes) => return Ok(bytes),
+            None => return fs::read(path),
+        }
+    }
+
+    /**
+    Returns the checksum of the staged contents of the file at `path` or of
+    the file on disk if it isn't staged, calculated with the
+    [`ChecksumAlgorithm`] of `self`.
+     */
+    pub(crate) fn file_checksum(&self, path: &Path) -> Option<u32> {
+        return self.file_checksum_with(path, self.checksum_algorithm);
+    }
+
+    /**
+    Like [`DatabaseManager::file_checksum`], but uses `algorithm` (e.g. the
+    algorithm of a link which is validated).
+     */
+    pub(crate) fn file_checksum_with(
+        &self,
+        path: &Path,
+        algorithm: ChecksumAlgorithm,
+    ) -> Option<u32> {
+        match self.staged(path) {
+            Some(bytes) => return Some(algorithm.checksum_bytes(&bytes)),
+            None => return algorithm.checksum_file(path),
+        }
+    }
+
+    fn write_staged_file(&self, path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        self.check_lock(path)?;
+        replace_file(path, bytes).map_err(|err| {
+            Error::new(
+                err.kind(),
+                format!("Could not write file {}: {}", path.display(), err),
+            )
+        })?;
+        self.sign_file(path)?;
+        return self.clear_expired(path);
+    }
+}
+
+impl Drop for DatabaseManager {
+    fn drop(&mut self) {
+        let flush = match &self.staging {
+            // Other clones still use the staged files
+            Some(staging) if Arc::strong_count(staging) == 1 => {
+                lock(staging).drop_policy == StagingDropPolicy::Flush
+            }
+            _ => false,
+        };
+        if flush {
+            let _ = self.flush();
+        }
+    }
+}
+
+fn lock(staging: &Mutex<Staging>) -> MutexGuard<'_, Staging> {
+    return staging.lock().unwrap_or_else(PoisonError::into_inner);
+}
@@ -0,0 +1,138 @@
+/*!
+This module contains functionality to gather statistics about a database. See
+[`DatabaseManager::disk_usage`].
+ */
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{DatabaseKeyBuf, DatabaseManager};
+
+/**
+The result of [`DatabaseManager::disk_usage`]: a breakdown of the disk space
+used by the database per type folder and a list of the largest entries.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct DiskUsage {
+    /**
+    The total size in bytes of all files within the type folders.
+     */
+    pub total_bytes: u64,
+    /**
+    The disk usage of every type folder, sorted by size (largest first).
+     */
+    pub per_type: Vec<TypeUsage>,
+    /**
+    The largest database entries, sorted by size (largest first).
+     */
+    pub largest_entries: Vec<EntryUsage>,
+}
+
+/**
+Disk usage of a single type folder. See [`DiskUsage`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeUsage {
+    /**
+    The name of the type folder.
+     */
+    pub type_name: OsString,
+    /**
+    The total size in bytes of all files within the type folder (including
+    files which are not database entries).
+     */
+    pub bytes: u64,
+    /**
+    The number of database entries within the type folder.
+     */
+    pub entries: usize,
+}
+
+/**
+Disk usage of a single database entry. See [`DiskUsage`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryUsage {
+    /**
+    The key of the database entry.
+     */
+    pub key: DatabaseKeyBuf,
+    /**
+    The path of the file which contains the database entry.
+     */
+    pub path: PathBuf,
+    /**
+    The size of the file in bytes.
+     */
+    pub bytes: u64,
+}
+
+impl DatabaseManager {
+    /**
+    Determines the disk space used by the database. The returned [`DiskUsage`]
+    contains the size of every type folder and the `top_n` largest database
+    entries.
+
+    Only the files directly within the type folders are taken into account,
+    files in the database root and nested folders are ignored. Sizes are file
+    lengths as reported by the file system metadata, not the allocated blocks.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let usage = dbm.disk_usage(10).expect("database is accessible");
+    for type_usage in usage.per_type.iter() {
+        println!("{:?}: {} bytes", type_usage.type_name, type_usage.bytes);
+    }
+    for entry in usage.largest_entries.iter() {
+        println!("{}: {} bytes", entry.key, entry.bytes);
+    }
+    ```
+     */
+    pub fn disk_usage(&self, top_n: usize) -> std::io::Result<DiskUsage> {
+        let mut usage = DiskUsage::default();
+        let mut entries: Vec<EntryUsage> = Vec::new();
+
+        for type_name in self.type_folders()? {
+            let mut type_usage = TypeUsage {
+                type_name: type_name.clone(),
+                bytes: 0,
+                entries: 0,
+            };
+            for path in self.type_folder_files(&type_name)? {
+                let metadata = fs::metadata(&path)?;
+                type_usage.bytes += metadata.len();
+                if let Some(name) = path
+                    .file_name()
+                    .and_then(|file_name| self.entry_name(&type_name, file_name))
+                {
+                    type_usage.entries += 1;
+                    entries.push(EntryUsage {
+                        key: DatabaseKeyBuf::new(type_name.clone(), name),
+                        path,
+                        bytes: metadata.len(),
+                    });
+                }
+            }
+            usage.total_bytes += type_usage.bytes;
+            usage.per_type.push(type_usage);
+        }
+
+        // Sort by size (largest first), ties are broken by name to keep the
+        // order deterministic.
+        usage.per_type.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then_with(|| a.type_name.cmp(&b.type_name))
+        });
+        entries.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.key.cmp(&b.key)));
+        entries.truncate(top_n);
+        usage.largest_entries = entries;
+
+        return Ok(usage);
+    }
+}
@@ -0,0 +1,435 @@
+/*!
+This module contains helpers for testing code which uses a [`DatabaseManager`]
+(requires the `testing` feature).
+
+The central type is [`TempDatabase`], which provides a database within a fresh
+temporary directory. Since every test gets its own database, tests don't
+depend on each other and can run in parallel. The function
+[`assert_roundtrip`] checks that an entry survives writing and reading, and a
+[`FaultInjector`] makes reading and writing fail on demand in order to test
+error paths.
+ */
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt::Debug;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{DatabaseEntry, DatabaseKey, DatabaseKeyBuf, DatabaseManager, Format, WriteOptions};
+
+/**
+A [`DatabaseManager`] working on a fresh temporary directory (see
+[`DatabaseManager::temp`]), which is removed when the [`TempDatabase`] (and
+all clones of its manager) have been dropped. It dereferences to the manager, so all manager methods can be called
+on it directly.
+
+The database can be pre-populated with fixtures, either from inline strings
+via [`TempDatabase::fixture`] or by copying a fixture directory via
+[`TempDatabase::from_fixtures`].
+
+# Examples
+
+```
+use serde_mosaic::*;
+use serde_mosaic::testing::TempDatabase;
+
+let dbm = TempDatabase::new(SerdeYaml)
+    .expect("temporary directory can be created")
+    .fixture("Material", "steel", "Material:\n  name: steel\n")
+    .expect("fixture can be written");
+assert!(dbm.exists(("Material", "steel")));
+```
+ */
+pub struct TempDatabase {
+    dbm: DatabaseManager,
+}
+
+impl TempDatabase {
+    /**
+    Creates an empty database within a new temporary directory (a subdirectory
+    of [`std::env::temp_dir`]).
+     */
+    pub fn new<F: Format + 'static>(format: F) -> std::io::Result<Self> {
+        return Ok(Self {
+            dbm: DatabaseManager::temp(format)?,
+        });
+    }
+
+    /**
+    Creates a database within a new temporary directory and copies the
+    contents of the fixture directory `fixtures` (e.g. a database checked into
+    the repository) into it. The fixture directory itself is never modified.
+     */
+    pub fn from_fixtures<P: AsRef<Path>, F: Format + 'static>(
+        fixtures: P,
+        format: F,
+    ) -> std::io::Result<Self> {
+        let temp_database = Self::new(format)?;
+        copy_dir(fixtures.as_ref(), temp_database.dir())?;
+        return Ok(temp_database);
+    }
+
+    /**
+    Writes a fixture file for the database entry `name` of the type
+    `type_name` with the given serialized `contents`. The file extension of
+    the [`Format`] is appended automatically.
+     */
+    pub fn fixture<T: AsRef<OsStr>, N: AsRef<OsStr>>(
+        self,
+        type_name: T,
+        name: N,
+        contents: &str,
+    ) -> std::io::Result<Self> {
+        let path = self
+            .dbm
+            .full_path_unchecked((type_name.as_ref(), name.as_ref()));
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        fs::write(path, contents)?;
+        return Ok(self);
+    }
+
+    /**
+    Returns the path of the temporary directory.
+     */
+    pub fn path(&self) -> &Path {
+        return self.dbm.dir();
+    }
+}
+
+impl Deref for TempDatabase {
+    type Target = DatabaseManager;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.dbm;
+    }
+}
+
+impl DerefMut for TempDatabase {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        return &mut self.dbm;
+    }
+}
+
+/**
+Recursively copies the contents of `source` into the directory `target`.
+ */
+fn copy_dir(source: &Path, target: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target_path: PathBuf = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target_path)?;
+        } else {
+            fs::copy(entry.path(), target_path)?;
+        }
+    }
+    return Ok(());
+}
+
+/**
+Writes `instance` into the database of `dbm` using `write_options`, reads it
+again and asserts that the read instance equals `instance`. Panics with a
+descriptive message if writing or reading fails or if the instances differ.
+
+The entry is read twice: first with an empty [`Cache`](crate::Cache), so all
+links are resolved by reading files, and then a second time with the cache
+populated by the first read. The original cache of `dbm` is restored
+afterwards.
+
+Since this function panics on failure, it can be used directly within
+property-based tests (e.g. with `proptest` or `quickcheck`). In this case,
+[`WriteOptions::name_collisions`](crate::WriteOptions::name_collisions) should
+usually be set to [`NameCollisions::Overwrite`](crate::NameCollisions::Overwrite),
+otherwise instances with the same name as an earlier instance are not written.
+
+# Examples
+
+```
+use std::ffi::OsStr;
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+use serde_mosaic::testing::{TempDatabase, assert_roundtrip};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Alloy {
+    name: String,
+    density: f64,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Alloy {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+let mut dbm = TempDatabase::new(SerdeYaml).expect("temporary directory can be created");
+let mut write_options = WriteOptions::default();
+write_options.name_collisions = NameCollisions::Overwrite;
+
+for density in [1.0, 2.5, 7850.0] {
+    let steel = Alloy { name: "steel".into(), density };
+    assert_roundtrip(&mut dbm, &steel, &write_options);
+}
+```
+ */
+pub fn assert_roundtrip<T: DatabaseEntry + PartialEq + Debug>(
+    dbm: &mut DatabaseManager,
+    instance: &T,
+    write_options: &WriteOptions,
+) {
+    let key = format!("{}/{}", T::folder_name(), instance.name().to_string_lossy());
+    if let Err(err) = dbm.write(instance, write_options) {
+        panic!("Writing {} failed: {}", key, err);
+    }
+
+    let original_cache = std::mem::take(dbm.cache_mut());
+    let mut check = |description: &str| match dbm.read::<T, _>(instance.name()) {
+        Ok(read) => {
+            if &read != instance {
+                return Err(format!(
+                    "Round trip of {} ({}) changed the entry:\nwritten: {:?}\nread: {:?}",
+                    key, description, instance, read
+                ));
+            }
+            return Ok(());
+        }
+        Err(err) => {
+            return Err(format!("Reading {} ({}) failed: {}", key, description, err));
+        }
+    };
+    let result = check("empty cache").and_then(|_| check("populated cache"));
+    *dbm.cache_mut() = original_cache;
+
+    if let Err(msg) = result {
+        panic!("{}", msg);
+    }
+}
+
+/**
+Scripted faults which make reading and writing of a [`DatabaseManager`] fail
+on demand. This allows testing how an application recovers from I/O errors,
+including errors occurring while resolving links deep within a composed
+entry.
+
+A fault injector is installed via [`DatabaseManager::set_fault_injector`].
+All clones of a [`FaultInjector`] share the same script, so a clone can be
+kept in order to change the script or to inspect the number of writes while
+the manager is in use. The following faults are available:
+- [`FaultInjector::fail_nth_write`]: The n-th file written after the
+installation fails.
+- [`FaultInjector::fail_read`] / [`FaultInjector::fail_write`]: Every read
+from / write to the file of the given key fails.
+- [`FaultInjector::slow_reads`]: Every file read is delayed.
+
+The faults are injected before the file system is accessed, i.e. a failed
+write leaves the existing file untouched.
+
+# Examples
+
+```
+use std::ffi::OsStr;
+use std::io::ErrorKind;
+use serde::{Serialize, Deserialize};
+use serde_mosaic::*;
+use serde_mosaic::testing::{FaultInjector, TempDatabase};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Fabric {
+    name: String,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Fabric {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+let mut dbm = TempDatabase::new(SerdeYaml).expect("temporary directory can be created");
+let faults = FaultInjector::new();
+dbm.set_fault_injector(Some(faults.clone()));
+
+let cotton = Fabric { name: "cotton".into() };
+faults.fail_nth_write(2, ErrorKind::StorageFull);
+assert!(dbm.write(&cotton, &WriteOptions::default()).is_ok());
+
+let wool = Fabric { name: "wool".into() };
+let err = dbm.write(&wool, &WriteOptions::default()).unwrap_err();
+assert_eq!(err.kind(), ErrorKind::StorageFull);
+assert!(!dbm.exists(&wool));
+
+faults.fail_read(&cotton, ErrorKind::PermissionDenied);
+assert!(dbm.read::<Fabric, _>("cotton").is_err());
+faults.clear();
+assert_eq!(dbm.read::<Fabric, _>("cotton").unwrap(), cotton);
+```
+ */
+#[derive(Clone, Debug, Default)]
+pub struct FaultInjector {
+    script: Arc<Mutex<FaultScript>>,
+}
+
+#[derive(Debug, Default)]
+struct FaultScript {
+    writes: u64,
+    failing_writes: HashMap<u64, ErrorKind>,
+    failing_write_keys: HashMap<DatabaseKeyBuf, ErrorKind>,
+    failing_read_keys: HashMap<DatabaseKeyBuf, ErrorKind>,
+    read_delay: Duration,
+}
+
+impl FaultInjector {
+    /**
+    Creates a new [`FaultInjector`] without any faults.
+     */
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /**
+    Lets the `n`-th file write (counted from one, including the writes which
+    already happened since the creation of `self`) fail with an error of the
+    given `kind`. Only this write fails; subsequent writes succeed again.
+     */
+    pub fn fail_nth_write(&self, n: u64, kind: ErrorKind) {
+        self.script().failing_writes.insert(n, kind);
+    }
+
+    /**
+    Lets every write to the file of the database entry `key` fail with an error
+    of the given `kind`.
+     */
+    pub fn fail_write<'a, K: Into<DatabaseKey<'a>>>(&self, key: K, kind: ErrorKind) {
+        self.script()
+            .failing_write_keys
+            .insert(key.into().into(), kind);
+    }
+
+    /**
+    Lets every read of the file of the database entry `key` fail with an error
+    of the given `kind`. This includes reading the entry as a linked entry of
+    another entry (unless the linked entry is taken from the [`Cache`](crate::Cache)).
+     */
+    pub fn fail_read<'a, K: Into<DatabaseKey<'a>>>(&self, key: K, kind: ErrorKind) {
+        self.script()
+            .failing_read_keys
+            .insert(key.into().into(), kind);
+    }
+
+    /**
+    Delays every file read by `delay`. A delay of [`Duration::ZERO`] disables
+    the delay again.
+     */
+    pub fn slow_reads(&self, delay: Duration) {
+        self.script().read_delay = delay;
+    }
+
+    /**
+    Removes all faults and delays. The write counter is not reset.
+     */
+    pub fn clear(&self) {
+        let mut script = self.script();
+        script.failing_writes.clear();
+        script.failing_write_keys.clear();
+        script.failing_read_keys.clear();
+        script.read_delay = Duration::ZERO;
+    }
+
+    /**
+    Returns the number of file writes (including failed ones) since the
+    creation of `self`.
+     */
+    pub fn writes(&self) -> u64 {
+        return self.script().writes;
+    }
+
+    fn script(&self) -> std::sync::MutexGuard<'_, FaultScript> {
+        return self
+            .script
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+
+    /**
+    Called before the file at `path` within the type folder `type_name` is
+    read.
+     */
+    pub(crate) fn before_read(&self, path: &Path, type_name: &OsStr) -> std::io::Result<()> {
+        let key = key_of_path(path, type_name);
+        let (delay, fault) = {
+            let script = self.script();
+            (
+                script.read_delay,
+                script.failing_read_keys.get(&key).copied(),
+            )
+        };
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+        if let Some(kind) = fault {
+            return Err(Error::new(
+                kind,
+                format!("Injected fault while reading file {}", path.display()),
+            ));
+        }
+        return Ok(());
+    }
+
+    /**
+    Called before the file at `path` within the type folder `type_name` is
+    written.
+     */
+    pub(crate) fn before_write(&self, path: &Path, type_name: &OsStr) -> std::io::Result<()> {
+        let key = key_of_path(path, type_name);
+        let mut script = self.script();
+        script.writes += 1;
+        let write = script.writes;
+        let fault = match script.failing_writes.remove(&write) {
+            Some(kind) => Some(kind),
+            None => script.failing_write_keys.get(&key).copied(),
+        };
+        if let Some(kind) = fault {
+            return Err(Error::new(
+                kind,
+                format!("Injected fault while writing file {}", path.display()),
+            ));
+        }
+        return Ok(());
+    }
+}
+
+/**
+Returns the key of the database entry stored in the file at `path` within the
+type folder `type_name`.
+ */
+fn key_of_path(path: &Path, type_name: &OsStr) -> DatabaseKeyBuf {
+    let name = path.file_stem().unwrap_or_default();
+    return DatabaseKeyBuf::new(type_name, name);
+}
+
+impl DatabaseManager {
+    /**
+    Installs the [`FaultInjector`] `fault_injector`, whose faults are applied
+    to all subsequent reads and writes of `self`. Passing [`None`] removes the
+    fault injector.
+     */
+    pub fn set_fault_injector(&mut self, fault_injector: Option<FaultInjector>) {
+        self.fault_injector = fault_injector;
+    }
+
+    /**
+    Returns the installed [`FaultInjector`], if any.
+     */
+    pub fn fault_injector(&self) -> Option<&FaultInjector> {
+        return self.fault_injector.as_ref();
+    }
+}
@@ -0,0 +1,670 @@
+/*!
+This module contains the [`Value`] type, a format-agnostic, untyped
+representation of a serialized database entry.
+
+The [`DatabaseManager`](crate::DatabaseManager) usually (de)serializes database
+entries via their concrete types. Some operations however need to inspect or
+modify files without knowing the concrete type of the stored entry, e.g. when
+the checksum inside a link needs to be updated (see
+[`ChecksumMismatchPolicy::Heal`](crate::ChecksumMismatchPolicy::Heal)). For
+this purpose, a [`Format`](crate::Format) can (optionally) convert between its
+bytes representation and a [`Value`] via
+[`Format::serialize_value`](crate::Format::serialize_value) and
+[`Format::deserialize_value`](crate::Format::deserialize_value).
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::fmt;
+
+use serde::de::{self, MapAccess, SeqAccess};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Serialize};
+
+use crate::DatabaseLink;
+
+/**
+An untyped representation of serialized data.
+
+Maps are stored as a vector of key-value pairs in order to preserve the order
+of the keys when a file is deserialized into a [`Value`], modified and then
+serialized again.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /**
+    An empty value (e.g. `null` in JSON or `~` in YAML).
+     */
+    Null,
+    /**
+    A boolean.
+     */
+    Bool(bool),
+    /**
+    A signed integer.
+     */
+    I64(i64),
+    /**
+    An unsigned integer which is too large for [`Value::I64`].
+     */
+    U64(u64),
+    /**
+    A floating point number.
+     */
+    F64(f64),
+    /**
+    A string.
+     */
+    String(String),
+    /**
+    A byte array.
+     */
+    Bytes(Vec<u8>),
+    /**
+    A sequence of values.
+     */
+    Seq(Vec<Value>),
+    /**
+    A map of key-value pairs in the order in which they were deserialized.
+     */
+    Map(Vec<(Value, Value)>),
+}
+
+impl Value {
+    /**
+    Returns the string slice if `self` is a [`Value::String`].
+     */
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => return Some(string.as_str()),
+            _ => return None,
+        }
+    }
+
+    /**
+    Returns the number as [`u64`] if `self` is a non-negative integer.
+     */
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::I64(int) => return u64::try_from(*int).ok(),
+            Value::U64(int) => return Some(*int),
+            _ => return None,
+        }
+    }
+
+    /**
+    Returns the value stored under the string key `key` if `self` is a
+    [`Value::Map`].
+     */
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => {
+                return entries
+                    .iter()
+                    .find(|(k, _)| k.as_str() == Some(key))
+                    .map(|(_, v)| v);
+            }
+            _ => return None,
+        }
+    }
+
+    /**
+    Mutable version of [`Value::get`].
+     */
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        match self {
+            Value::Map(entries) => {
+                return entries
+                    .iter_mut()
+                    .find(|(k, _)| k.as_str() == Some(key))
+                    .map(|(_, v)| v);
+            }
+            _ => return None,
+        }
+    }
+
+    /**
+    Inserts `value` under the string key `key` if `self` is a [`Value::Map`].
+    If the key already existed, its value is replaced (keeping its position)
+    and the old value is returned. Otherwise, the entry is appended. If `self`
+    is not a map, it is left unchanged.
+     */
+    pub fn insert(&mut self, key: &str, value: Value) -> Option<Value> {
+        let Value::Map(entries) = self else {
+            return None;
+        };
+        match entries.iter_mut().find(|(k, _)| k.as_str() == Some(key)) {
+            Some((_, old)) => return Some(std::mem::replace(old, value)),
+            None => {
+                entries.push((Value::String(key.to_string()), value));
+                return None;
+            }
+        }
+    }
+
+    /**
+    Removes the entry with the string key `key` if `self` is a [`Value::Map`]
+    and returns its value.
+     */
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let Value::Map(entries) = self else {
+            return None;
+        };
+        let index = entries.iter().position(|(k, _)| k.as_str() == Some(key))?;
+        return Some(entries.remove(index).1);
+    }
+
+    /**
+    Interprets `self` as a link, if possible. A map is interpreted as a link if
+    it has a string `name` entry, optionally a `checksum` (integer or string, see
+    [`parse_checksum`](crate::parse_checksum)), an `algorithm` (see
+    [`ChecksumAlgorithm::tag`](crate::ChecksumAlgorithm::tag)) and a `version`
+    entry and no other entries. Other entries are only allowed if the `version` is
+    newer than [`LINK_VERSION`](crate::LINK_VERSION).
+     */
+    pub(crate) fn as_link(&self) -> Option<DatabaseLink> {
+        let Value::Map(entries) = self else {
+            return None;
+        };
+        let mut name = None;
+        let mut checksum = None;
+        let mut algorithm = crate::ChecksumAlgorithm::default();
+        let mut version = 1;
+        let mut has_unknown_entries = false;
+        for (key, value) in entries.iter() {
+            match key.as_str()? {
+                "name" => name = Some(value.as_str()?.to_string()),
+                "checksum" => match value {
+                    Value::Null => (),
+                    Value::String(string) => checksum = Some(crate::parse_checksum(string)?),
+                    other => checksum = Some(u32::try_from(other.as_u64()?).ok()?),
+                },
+                "algorithm" => algorithm = crate::ChecksumAlgorithm::from_tag(value.as_str()?)?,
+                "version" => version = u32::try_from(value.as_u64()?).ok()?,
+                _ => has_unknown_entries = true,
+            }
+        }
+        if has_unknown_entries && version <= crate::LINK_VERSION {
+            return None;
+        }
+        return Some(DatabaseLink {
+            name: name?,
+            checksum,
+            algorithm,
+        });
+    }
+
+    /**
+    Overwrites the `name`, `checksum` and `algorithm` entries of a link map with
+    the values from `link`. The `algorithm` entry is removed for the default
+    algorithm and otherwise requires at least version 2 of the link
+    representation (see [`LINK_VERSION`](crate::LINK_VERSION)).
+     */
+    fn set_link(&mut self, link: &DatabaseLink) {
+        let Value::Map(entries) = self else {
+            return;
+        };
+        let checksum = match link.checksum {
+            Some(checksum) => Value::I64(checksum.into()),
+            None => Value::Null,
+        };
+        let mut has_checksum = false;
+        for (key, value) in entries.iter_mut() {
+            match key.as_str() {
+                Some("name") => *value = Value::String(link.name.clone()),
+                Some("checksum") => {
+                    *value = checksum.clone();
+                    has_checksum = true;
+                }
+                _ => (),
+            }
+        }
+        if !has_checksum && link.checksum.is_some() {
+            entries.push((Value::String("checksum".into()), checksum));
+        }
+
+        entries.retain(|(key, _)| key.as_str() != Some("algorithm"));
+        if !link.algorithm.is_default() {
+            entries.push((
+                Value::String("algorithm".into()),
+                Value::String(link.algorithm.tag().into()),
+            ));
+            let version = entries
+                .iter_mut()
+                .find(|(key, _)| key.as_str() == Some("version"));
+            match version {
+                Some((_, version)) if version.as_u64().is_some_and(|version| version >= 2) => (),
+                Some((_, version)) => *version = Value::I64(crate::LINK_VERSION.into()),
+                None => entries.push((
+                    Value::String("version".into()),
+                    Value::I64(crate::LINK_VERSION.into()),
+                )),
+            }
+        }
+    }
+
+    /**
+    Returns all links stored within the serialized database entry `self` (see
+    [`Value::for_each_link_mut`]).
+     */
+    pub(crate) fn links(&self) -> Vec<DatabaseLink> {
+        let mut links = Vec::new();
+        self.clone().for_each_link_mut(&mut |link| {
+            links.push(link.clone());
+            false
+        });
+        return links;
+    }
+
+    /**
+    Returns all string values stored within the serialized database entry
+    `self` as links without checksum. Since links written with
+    [`LinkStyle::Short`](crate::LinkStyle::Short) are plain strings, every
+    string value (except for map keys and the strings inside links) might be
+    such a link. Whether it actually is one can only be decided by the type of
+    the entry.
+     */
+    pub(crate) fn possible_short_links(&self) -> Vec<DatabaseLink> {
+        fn recurse(value: &Value, links: &mut Vec<DatabaseLink>) {
+            if value.as_link().is_some() {
+                return;
+            }
+            match value {
+                Value::String(name) => links.push(DatabaseLink {
+                    name: name.clone(),
+                    checksum: None,
+                    algorithm: crate::ChecksumAlgorithm::default(),
+                }),
+                Value::Seq(elements) => {
+                    for element in elements.iter() {
+                        recurse(element, links);
+                    }
+                }
+                Value::Map(entries) => {
+                    for (_, element) in entries.iter() {
+                        recurse(element, links);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        // =====================================================================
+
+        let mut links = Vec::new();
+        match self {
+            Value::Map(entries) if entries.len() == 1 => {
+                // Skip the type tag and the entry contents themselves
+                match &entries[0].1 {
+                    Value::Map(fields) => {
+                        for (_, field) in fields.iter() {
+                            recurse(field, &mut links);
+                        }
+                    }
+                    Value::Seq(elements) => {
+                        for element in elements.iter() {
+                            recurse(element, &mut links);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            other => recurse(other, &mut links),
+        }
+        return links;
+    }
+
+    /**
+    Calls `f` for every link stored within the serialized database entry
+    `self`. If `f` returns `true`, the link has been modified and is written
+    back into `self`. Returns `true` if any link was modified.
+
+    A database entry is serialized as a map with a single entry: The type name
+    as key and the actual contents of the entry as value. The contents
+    themselves are never interpreted as a link, only their (nested) fields.
+     */
+    pub(crate) fn for_each_link_mut<F: FnMut(&mut DatabaseLink) -> bool>(
+        &mut self,
+        f: &mut F,
+    ) -> bool {
+        let result: Result<bool, Infallible> =
+            self.try_for_each_link_value(&mut |value, mut link| {
+                if f(&mut link) {
+                    value.set_link(&link);
+                    return Ok(true);
+                }
+                return Ok(false);
+            });
+        match result {
+            Ok(modified) => return modified,
+            Err(never) => match never {},
+        }
+    }
+
+    /**
+    Calls `f` for every link stored within the serialized database entry
+    `self` (see [`Value::for_each_link_mut`]). `f` receives the value which
+    represents the link (and may replace it entirely) as well as the parsed
+    link. `f` returns whether it modified the value. Returns `true` if any
+    value was modified or the first error returned by `f`.
+     */
+    pub(crate) fn try_for_each_link_value<E, F>(&mut self, f: &mut F) -> Result<bool, E>
+    where
+        F: FnMut(&mut Value, DatabaseLink) -> Result<bool, E>,
+    {
+        fn recurse<E, F>(value: &mut Value, f: &mut F) -> Result<bool, E>
+        where
+            F: FnMut(&mut Value, DatabaseLink) -> Result<bool, E>,
+        {
+            if let Some(link) = value.as_link() {
+                return f(value, link);
+            }
+            let mut modified = false;
+            match value {
+                Value::Seq(elements) => {
+                    for element in elements.iter_mut() {
+                        modified |= recurse(element, f)?;
+                    }
+                }
+                Value::Map(entries) => {
+                    for (_, element) in entries.iter_mut() {
+                        modified |= recurse(element, f)?;
+                    }
+                }
+                _ => (),
+            }
+            return Ok(modified);
+        }
+
+        // =====================================================================
+
+        let mut modified = false;
+        match self {
+            Value::Map(entries) if entries.len() == 1 => {
+                // Skip the type tag and the entry contents themselves
+                match &mut entries[0].1 {
+                    Value::Map(fields) => {
+                        for (_, field) in fields.iter_mut() {
+                            modified |= recurse(field, f)?;
+                        }
+                    }
+                    Value::Seq(elements) => {
+                        for element in elements.iter_mut() {
+                            modified |= recurse(element, f)?;
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            other => modified |= recurse(other, f)?,
+        }
+        return Ok(modified);
+    }
+
+    /**
+    Replaces all parameter placeholders (`{{name}}`) in the string values of
+    `self` with the corresponding value from `parameters`. A string which
+    consists of a single placeholder is replaced by the parameter value
+    itself, otherwise the (scalar) parameter is embedded into the string. Map
+    keys are not modified. Returns whether any value was replaced or an error
+    message if a placeholder refers to an unknown parameter.
+     */
+    pub(crate) fn substitute_parameters(
+        &mut self,
+        parameters: &HashMap<String, Value>,
+    ) -> Result<bool, String> {
+        match self {
+            Value::String(string) => {
+                let placeholders = parameter_placeholders(string);
+                if placeholders.is_empty() {
+                    return Ok(false);
+                }
+                let mut substituted = String::new();
+                let mut end = 0;
+                for (range, name) in placeholders.iter() {
+                    let parameter = parameters
+                        .get(*name)
+                        .ok_or_else(|| format!("parameter {} is not given", name))?;
+                    if range.start == 0 && range.end == string.len() {
+                        *self = parameter.clone();
+                        return Ok(true);
+                    }
+                    let embedded = match parameter {
+                        Value::Bool(val) => val.to_string(),
+                        Value::I64(val) => val.to_string(),
+                        Value::U64(val) => val.to_string(),
+                        Value::F64(val) => val.to_string(),
+                        Value::String(val) => val.clone(),
+                        _ => {
+                            return Err(format!(
+                                "parameter {} is not a scalar and can't be embedded into a string",
+                                name
+                            ));
+                        }
+                    };
+                    substituted.push_str(&string[end..range.start]);
+                    substituted.push_str(&embedded);
+                    end = range.end;
+                }
+                substituted.push_str(&string[end..]);
+                *string = substituted;
+                return Ok(true);
+            }
+            Value::Seq(elements) => {
+                let mut modified = false;
+                for element in elements.iter_mut() {
+                    modified |= element.substitute_parameters(parameters)?;
+                }
+                return Ok(modified);
+            }
+            Value::Map(entries) => {
+                let mut modified = false;
+                for (_, element) in entries.iter_mut() {
+                    modified |= element.substitute_parameters(parameters)?;
+                }
+                return Ok(modified);
+            }
+            _ => return Ok(false),
+        }
+    }
+
+    /**
+    Renames the fields of the serialized database entry `self` (i.e. the keys
+    of the map stored under the type tag) according to `aliases`, which maps
+    old field names to new ones. A field is not renamed if the entry already
+    contains a field with the new name. Returns whether any field was renamed.
+     */
+    pub(crate) fn rename_entry_fields(&mut self, aliases: &HashMap<String, String>) -> bool {
+        let Value::Map(entries) = self else {
+            return false;
+        };
+        let [(_, Value::Map(fields))] = entries.as_mut_slice() else {
+            return false;
+        };
+        let mut names: HashSet<String> = fields
+            .iter()
+            .filter_map(|(key, _)| key.as_str().map(String::from))
+            .collect();
+        let mut renamed = false;
+        for (key, _) in fields.iter_mut() {
+            if let Some(old) = key.as_str()
+                && let Some(new) = aliases.get(old)
+                && !names.contains(new)
+            {
+                names.insert(new.clone());
+                *key = Value::String(new.clone());
+                renamed = true;
+            }
+        }
+        return renamed;
+    }
+
+    /**
+    Returns the contents of the serialized database entry `self`, i.e. the
+    value stored under the type tag. If `self` is not a tagged database entry,
+    [`None`] is returned.
+     */
+    pub(crate) fn into_entry_contents(self) -> Option<Value> {
+        match self {
+            Value::Map(mut entries) if entries.len() == 1 => return Some(entries.remove(0).1),
+            _ => return None,
+        }
+    }
+
+    /**
+    Mutable version of [`Value::into_entry_contents`].
+     */
+    pub(crate) fn entry_contents_mut(&mut self) -> Option<&mut Value> {
+        match self {
+            Value::Map(entries) if entries.len() == 1 => return Some(&mut entries[0].1),
+            _ => return None,
+        }
+    }
+}
+
+/**
+Returns the byte ranges and names of all parameter placeholders within
+`string`. A placeholder is a name consisting of ASCII alphanumeric characters,
+underscores and hyphens enclosed by `{{` and `}}` (surrounding whitespace is
+allowed).
+ */
+fn parameter_placeholders(string: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    let mut placeholders = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = string[offset..].find("{{") {
+        let start = offset + start;
+        let Some(len) = string[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        let name = string[start + 2..end - 2].trim();
+        if !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            placeholders.push((start..end, name));
+            offset = end;
+        } else {
+            offset = start + 2;
+        }
+    }
+    return placeholders;
+}
+
+impl Serialize for Value {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => return serializer.serialize_none(),
+            Value::Bool(val) => return serializer.serialize_bool(*val),
+            Value::I64(val) => return serializer.serialize_i64(*val),
+            Value::U64(val) => return serializer.serialize_u64(*val),
+            Value::F64(val) => return serializer.serialize_f64(*val),
+            Value::String(val) => return serializer.serialize_str(val),
+            Value::Bytes(val) => return serializer.serialize_bytes(val),
+            Value::Seq(elements) => {
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+                for element in elements.iter() {
+                    seq.serialize_element(element)?;
+                }
+                return seq.end();
+            }
+            Value::Map(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries.iter() {
+                    map.serialize_entry(key, value)?;
+                }
+                return map.end();
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = Value;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("any value")
+            }
+
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+                return Ok(Value::Bool(v));
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+                return Ok(Value::I64(v));
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+                match i64::try_from(v) {
+                    Ok(v) => return Ok(Value::I64(v)),
+                    Err(_) => return Ok(Value::U64(v)),
+                }
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+                return Ok(Value::F64(v));
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+                return Ok(Value::String(v.to_string()));
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+                return Ok(Value::String(v));
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Value, E> {
+                return Ok(Value::Bytes(v.to_vec()));
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Value, E> {
+                return Ok(Value::Bytes(v));
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+                return Ok(Value::Null);
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+                return Ok(Value::Null);
+            }
+
+            fn visit_some<D: serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Value, D::Error> {
+                return Value::deserialize(deserializer);
+            }
+
+            fn visit_newtype_struct<D: serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Value, D::Error> {
+                return Value::deserialize(deserializer);
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+                let mut elements = Vec::new();
+                while let Some(element) = seq.next_element()? {
+                    elements.push(element);
+                }
+                return Ok(Value::Seq(elements));
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                return Ok(Value::Map(entries));
+            }
+        }
+
+        return deserializer.deserialize_any(Visitor);
+    }
+}
@@ -0,0 +1,795 @@
+/*!
+This module contains functionality to verify the integrity of database entries
+without deserializing them into their concrete types. The central method is
+[`DatabaseManager::verify_entry`], which checks an entry and all entries it
+(transitively) links to and returns an [`EntryVerification`].
+
+Since links only store the name of the linked entry, but not its type, the
+linked entry is looked up in all type folders of the database. If multiple
+folders contain an entry with the linked name, the checksum stored in the link
+(if available) is used to determine the linked entry. If that is not possible,
+the link is reported as [`LinkTarget::Ambiguous`].
+
+[`DatabaseManager::validate`] combines this verification with a typed read of
+the entry and returns a [`ValidationReport`], which additionally detects
+problems which only show up when deserializing the concrete types (e.g. a
+missing field).
+
+[`DatabaseManager::verify_all`] checks every entry of the database (the
+`fsck` of this crate) and returns all problems as a single [`DatabaseReport`].
+
+[`DatabaseManager::validate_layout`] checks the files and folders of the
+database against the expected layout (type folders containing files with the
+file extension of the database) without reading any file.
+ */
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    DatabaseEntry, DatabaseKey, DatabaseKeyBuf, DatabaseLink, DatabaseManager, DatabaseReport,
+    LinkError, Problem, ReadInfo, ReadOptions, SignatureStatus, Value,
+};
+
+/**
+The result of [`DatabaseManager::verify_entry`]: a report for every file which
+belongs to the verified entry. The first report is always the one of the
+verified entry itself, followed by the reports of the linked entries in
+breadth-first order.
+ */
+#[derive(Debug, Clone)]
+pub struct EntryVerification {
+    /**
+    The reports of all files in the link closure of the verified entry.
+     */
+    pub files: Vec<FileReport>,
+}
+
+impl EntryVerification {
+    /**
+    Returns `true` if all files in the link closure exist and could be parsed,
+    all links could be resolved unambiguously and no checksum mismatches were
+    found.
+     */
+    pub fn is_valid(&self) -> bool {
+        return self.files.iter().all(FileReport::is_valid);
+    }
+}
+
+/**
+Verification report of a single database file. See
+[`DatabaseManager::verify_entry`].
+ */
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    /**
+    The key of the verified database entry.
+     */
+    pub key: DatabaseKeyBuf,
+    /**
+    The path of the file which contains the database entry.
+     */
+    pub path: PathBuf,
+    /**
+    Whether the file exists and could be parsed.
+     */
+    pub status: FileStatus,
+    /**
+    All links found within the file. Empty if the file couldn't be parsed.
+     */
+    pub links: Vec<LinkReport>,
+    /**
+    The result of verifying the detached signature of the file (see
+    [`signature`](crate::signature)). [`None`] if signatures are not
+    configured or the file couldn't be read.
+     */
+    pub signature: Option<SignatureStatus>,
+}
+
+impl FileReport {
+    /**
+    Returns `true` if the file could be parsed, all of its links are valid
+    (see [`LinkReport::is_valid`]) and its signature (if checked) is valid.
+     */
+    pub fn is_valid(&self) -> bool {
+        return self.status == FileStatus::Valid
+            && self.links.iter().all(LinkReport::is_valid)
+            && matches!(self.signature, None | Some(SignatureStatus::Valid));
+    }
+}
+
+/**
+The status of a single database file. See [`FileReport`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileStatus {
+    /**
+    The file exists and could be parsed by the [`Format`](crate::Format) of
+    the database manager.
+     */
+    Valid,
+    /**
+    The file does not exist.
+     */
+    Missing,
+    /**
+    The file exists, but couldn't be read. The error message is stored within
+    this variant.
+     */
+    Unreadable(String),
+    /**
+    The file exists, but couldn't be parsed (or the type stated in the file
+    does not match its folder). The error message is stored within this
+    variant.
+     */
+    Unparseable(String),
+}
+
+/**
+Verification report of a single link found in a database file. See
+[`FileReport`].
+ */
+#[derive(Debug, Clone)]
+pub struct LinkReport {
+    /**
+    The name stored within the link.
+     */
+    pub name: String,
+    /**
+    The database entry the link points to.
+     */
+    pub target: LinkTarget,
+    /**
+    The checksum stored within the link (if any).
+     */
+    pub checksum_in_link: Option<u32>,
+    /**
+    The checksum of the linked file, if the link could be resolved. It is
+    calculated with the [`ChecksumAlgorithm`](crate::ChecksumAlgorithm) the
+    checksum in the link has been calculated with.
+     */
+    pub checksum_of_file: Option<u32>,
+}
+
+impl LinkReport {
+    /**
+    Returns `true` if the link could be resolved unambiguously and the checksums
+    match (or the link does not contain a checksum).
+     */
+    pub fn is_valid(&self) -> bool {
+        return matches!(self.target, LinkTarget::Resolved(_)) && !self.is_checksum_mismatch();
+    }
+
+    /**
+    Returns `true` if both the link and the linked file have a checksum and
+    these checksums differ.
+     */
+    pub fn is_checksum_mismatch(&self) -> bool {
+        match (self.checksum_in_link, self.checksum_of_file) {
+            (Some(in_link), Some(of_file)) => return in_link != of_file,
+            _ => return false,
+        }
+    }
+}
+
+/**
+The database entry a link points to. See [`LinkReport`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /**
+    The link could be resolved unambiguously.
+     */
+    Resolved(DatabaseKeyBuf),
+    /**
+    No type folder of the database contains an entry with the linked name.
+     */
+    Dangling,
+    /**
+    Multiple type folders contain an entry with the linked name and the
+    checksum stored in the link (if any) doesn't help to decide which one is
+    meant. All candidates are stored within this variant.
+     */
+    Ambiguous(Vec<DatabaseKeyBuf>),
+}
+
+/**
+The result of [`DatabaseManager::validate`]: the [`EntryVerification`] of the
+validated entry and the outcome of reading it as its concrete type.
+ */
+#[derive(Debug, Clone)]
+pub struct ValidationReport {
+    /**
+    The untyped verification of the entry and all entries it links to, see
+    [`DatabaseManager::verify_entry`].
+     */
+    pub verification: EntryVerification,
+    /**
+    The [`ReadInfo`] of the typed read if it succeeded, otherwise the error
+    which aborted it.
+     */
+    pub read: Result<ReadInfo, TypedReadError>,
+}
+
+impl ValidationReport {
+    /**
+    Returns `true` if the verification found no problems (see
+    [`EntryVerification::is_valid`]) and the typed read succeeded.
+     */
+    pub fn is_valid(&self) -> bool {
+        return self.verification.is_valid() && self.read.is_ok();
+    }
+}
+
+/**
+The error which aborted the typed read of [`DatabaseManager::validate`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypedReadError {
+    /**
+    The path of the file which caused the error. If a linked entry couldn't be
+    read, this is the file of the linked entry (see [`LinkError::chain`]).
+     */
+    pub path: PathBuf,
+    /**
+    The [`ErrorKind`] of the error.
+     */
+    pub kind: ErrorKind,
+    /**
+    The error message.
+     */
+    pub message: String,
+}
+
+impl DatabaseManager {
+    /**
+    Validates the database entry of the type `T` with the given `name` and all
+    entries it links to without aborting at the first problem.
+
+    First, all files in the link closure are checked via
+    [`DatabaseManager::verify_entry`] for existence, parseability and checksum
+    mismatches. Then, the entry is read as `T` (bypassing the
+    [`Cache`](crate::Cache), see [`ReadOptions::bypass_cache`]), which
+    detects problems that only show up when deserializing the concrete types,
+    e.g. missing fields. The typed read stops at the first error, but since the
+    verification covers all files, the returned [`ValidationReport`] lists
+    every problem which can be found without the concrete types. Use
+    [`ValidationReport::report`] to convert it into a [`DatabaseReport`].
+
+    # Examples
+
+    ```no_run
+    use std::ffi::OsStr;
+    use serde::{Serialize, Deserialize};
+    use serde_mosaic::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Material {
+        name: String,
+    }
+
+    #[typetag::serde]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let names: Vec<_> = dbm.list::<Material>().expect("folder is accessible").collect();
+    for name in names {
+        let validation = dbm.validate::<Material, _>(&name);
+        if !validation.is_valid() {
+            print!("{}", validation.report());
+        }
+    }
+    ```
+     */
+    pub fn validate<T: DatabaseEntry, O: AsRef<OsStr>>(&mut self, name: O) -> ValidationReport {
+        let name = name.as_ref();
+        let verification = self.verify_entry((T::folder_name(), name));
+
+        let mut read_options = ReadOptions::default();
+        read_options.bypass_cache = true;
+        let read = match self.read_verbose_with::<T, _>(name, &read_options) {
+            Ok((_, read_info)) => Ok(read_info),
+            Err(err) => {
+                // Errors of linked entries point to the file of the linked entry
+                let path = LinkError::from_io_error(&err)
+                    .and_then(|link_error| link_error.chain().last().cloned())
+                    .unwrap_or_else(|| self.full_path_unchecked((T::folder_name(), name)));
+                Err(TypedReadError {
+                    path,
+                    kind: err.kind(),
+                    message: err.to_string(),
+                })
+            }
+        };
+        return ValidationReport { verification, read };
+    }
+
+    /**
+    Verifies the database entry specified by `key` and all entries it links to
+    (transitively) without deserializing them into their concrete types.
+
+    For every file in the link closure, it is checked whether the file exists
+    and can be parsed into an untyped [`Value`] by the
+    [`Format`](crate::Format) of `self`. For every link within these files, it
+    is checked whether the linked entry exists and whether the checksum of the
+    linked file matches the one stored in the link. Since the concrete types
+    are not known, this does not guarantee that [`DatabaseManager::read`]
+    succeeds - e.g. a missing field can't be detected.
+
+    This requires the [`Format`](crate::Format) of `self` to support untyped
+    values (see [`Format::deserialize_value`](crate::Format::deserialize_value)).
+    If it doesn't, all files are reported as [`FileStatus::Unparseable`].
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let verification = dbm.verify_entry(("Shirt", "mike"));
+    for file in verification.files.iter().filter(|file| !file.is_valid()) {
+        println!("{}: {:?}", file.path.display(), file.status);
+    }
+    ```
+     */
+    pub fn verify_entry<'a, K: Into<DatabaseKey<'a>>>(&self, key: K) -> EntryVerification {
+        let root: DatabaseKeyBuf = key.into().into();
+        let type_folders = self.type_folders().unwrap_or_default();
+
+        let mut visited: HashSet<DatabaseKeyBuf> = HashSet::new();
+        visited.insert(root.clone());
+        let mut queue = vec![root];
+        let mut files = Vec::new();
+
+        // Breadth-first traversal of the link graph
+        let mut index = 0;
+        while index < queue.len() {
+            let key = queue[index].clone();
+            index += 1;
+
+            let report = self.verify_file(key, &type_folders);
+            for link in report.links.iter() {
+                if let LinkTarget::Resolved(target) = &link.target
+                    && visited.insert(target.clone())
+                {
+                    queue.push(target.clone());
+                }
+            }
+            files.push(report);
+        }
+
+        return EntryVerification { files };
+    }
+
+    /**
+    Verifies every entry within every type folder of the database and returns
+    all problems found as a [`DatabaseReport`]. The problems are grouped by
+    [`Severity`](crate::Severity): All errors come first, followed by the
+    warnings and the infos (see also [`DatabaseReport::with_severity`]).
+
+    For every entry, the following checks are performed:
+    - The file is parsed and its links are resolved and checked for checksum
+    mismatches like in [`DatabaseManager::verify_entry`] (including the
+    signature check, if configured). Since every entry is checked on its own,
+    every problem is reported once for the file it occurs in, even if the
+    entry is linked by many other entries.
+    - The entry is deserialized into its concrete type (as registered via
+    `typetag`) with all of its links resolved, which detects e.g. missing
+    fields or a type which is unknown to the application. Links which point
+    (transitively) back to the entry are reported as [`Problem::CyclicLink`].
+
+    Afterwards, the layout of the database is checked via
+    [`DatabaseManager::validate_layout`]. An error is only returned if the
+    database directory itself can't be accessed.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let report = dbm.verify_all().expect("database is accessible");
+    print!("{report}");
+    if report.has_errors() {
+        std::process::exit(1);
+    }
+    ```
+     */
+    pub fn verify_all(&mut self) -> std::io::Result<DatabaseReport> {
+        let type_folders = self.type_folders()?;
+        let keys: Vec<DatabaseKeyBuf> = self.list_all()?.collect();
+
+        let mut report = DatabaseReport::new();
+        for key in keys {
+            let file = self.verify_file(key.clone(), &type_folders);
+            let parseable = file.status == FileStatus::Valid;
+            let path = file.path.clone();
+            let mut file_report = EntryVerification { files: vec![file] }.report();
+            if parseable && let Some(problem) = self.deserialize_entry(&key, &path) {
+                file_report.push(problem);
+            }
+            report.append(file_report);
+        }
+        report.append(self.validate_layout()?);
+
+        // The sort is stable, so the problems of a severity stay in order
+        report
+            .problems
+            .sort_by_key(|problem| Reverse(problem.severity()));
+        return Ok(report);
+    }
+
+    /**
+    Deserializes the entry `key` stored at `path` into its concrete type and
+    returns the [`Problem`] which prevented it, if any. Problems of linked
+    entries are not returned, since these are found when the linked entry
+    itself is deserialized - except for links pointing back to `key`.
+     */
+    fn deserialize_entry(&mut self, key: &DatabaseKeyBuf, path: &Path) -> Option<Problem> {
+        let Some(type_name) = key.type_name.to_str() else {
+            return Some(Problem::Unparseable {
+                path: path.to_path_buf(),
+                message: "type name is not valid UTF-8".to_string(),
+            });
+        };
+        let mut read_options = ReadOptions::default();
+        read_options.bypass_cache = true;
+        let result = self.with_read_context(false, &read_options, |context| {
+            return context.read_dyn(type_name, &key.name);
+        });
+        let err = result.err()?;
+        match LinkError::from_io_error(&err) {
+            Some(link_error) => {
+                // The chain ends with the entry itself if the link is cyclic
+                let chain = link_error.chain();
+                if chain.len() > 2 && chain.last() == chain.first() {
+                    return Some(Problem::CyclicLink {
+                        path: chain[chain.len() - 2].clone(),
+                        link: key.name.to_string_lossy().into_owned(),
+                    });
+                }
+                return None;
+            }
+            None => {
+                return Some(Problem::Unparseable {
+                    path: path.to_path_buf(),
+                    message: err.to_string(),
+                });
+            }
+        }
+    }
+
+    /**
+    Scans the database for files and folders which don't match the expected
+    layout and returns them as a [`DatabaseReport`]:
+    - Files at the database root ([`Problem::StrayFile`]). Lock files (see
+    [`lock`](crate::lock)) are ignored.
+    - Folders within a type folder ([`Problem::StrayFile`]). If the database
+    is sharded (see [`DatabaseManager::set_sharding`]), this applies to folders
+    within the shards instead, and files directly within a type folder or
+    entries within the wrong shard are reported.
+    - Files within a type folder which don't have the file extension of `self`
+    ([`Problem::UnknownExtension`]). Signatures (see
+    [`signature`](crate::signature)) and lock files of database entries are
+    ignored.
+    - Type folders whose names only differ in case
+    ([`Problem::CaseDuplicateFolders`]).
+    - Entries within the same type folder whose names only differ in case
+    ([`Problem::NameCollision`]).
+
+    Such issues usually don't cause errors, but lead to entries silently not
+    being found, e.g. when an entry has been stored in `material/` instead of
+    `Material/`. The files themselves are not parsed, use
+    [`DatabaseManager::verify_entry`] for that. See also
+    [`DatabaseManager::open_validated`].
+     */
+    pub fn validate_layout(&self) -> std::io::Result<DatabaseReport> {
+        let mut report = DatabaseReport::new();
+
+        let mut root_files = Vec::new();
+        for entry in fs::read_dir(self.dir())? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir()
+                && !entry
+                    .file_name()
+                    .as_encoded_bytes()
+                    .starts_with(b".serde_mosaic.")
+            {
+                root_files.push(entry.path());
+            }
+        }
+        root_files.sort();
+        for path in root_files {
+            report.push(Problem::StrayFile { path });
+        }
+
+        let type_folders = self.type_folders()?;
+        let mut folders_by_case: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        for type_name in type_folders.iter() {
+            folders_by_case
+                .entry(type_name.to_string_lossy().to_lowercase())
+                .or_default()
+                .push(self.dir().join(type_name));
+        }
+        for paths in folders_by_case.into_values() {
+            if paths.len() > 1 {
+                report.push(Problem::CaseDuplicateFolders { paths });
+            }
+        }
+
+        for type_name in type_folders.iter() {
+            let folder = self.dir().join(type_name);
+            let mut paths: Vec<PathBuf> = Vec::new();
+            for entry in fs::read_dir(&folder)? {
+                let path = entry?.path();
+                if !self.sharding.is_sharded() {
+                    paths.push(path);
+                } else if path.is_dir() {
+                    // The contents of the shards are checked below
+                    for shard_entry in fs::read_dir(&path)? {
+                        paths.push(shard_entry?.path());
+                    }
+                } else {
+                    // All files of a sharded database belong into a shard
+                    report.push(Problem::StrayFile { path });
+                }
+            }
+            paths.sort();
+
+            let mut names_by_case: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+            for path in paths {
+                let file_name = path.file_name().unwrap_or_default();
+                if path.is_dir() {
+                    report.push(Problem::StrayFile { path });
+                    continue;
+                }
+                if let Some(name) = self.entry_name(type_name, file_name) {
+                    if !self.is_in_shard(type_name, &name, &path) {
+                        report.push(Problem::StrayFile { path });
+                        continue;
+                    }
+                    names_by_case
+                        .entry(name.to_string_lossy().to_lowercase())
+                        .or_default()
+                        .push(path);
+                    continue;
+                }
+
+                // Signatures, lock and expiry files of existing (possibly
+                // expired) entries belong to the database
+                let is_companion = [".sig", ".lock", ".expires"].iter().any(|suffix| {
+                    file_name
+                        .to_str()
+                        .and_then(|file_name| file_name.strip_suffix(suffix))
+                        .filter(|entry_file_name| {
+                            self.entry_name(type_name, OsStr::new(entry_file_name))
+                                .is_some()
+                        })
+                        .is_some_and(|entry_file_name| {
+                            self.file_exists(&path.with_file_name(entry_file_name))
+                        })
+                });
+                if !is_companion {
+                    report.push(Problem::UnknownExtension { path });
+                }
+            }
+            for paths in names_by_case.into_values() {
+                if paths.len() > 1 {
+                    report.push(Problem::NameCollision { paths });
+                }
+            }
+        }
+        return Ok(report);
+    }
+
+    fn verify_file(&self, key: DatabaseKeyBuf, type_folders: &[OsString]) -> FileReport {
+        let path = self.full_path_unchecked(&key);
+        let mut report = FileReport {
+            key,
+            path,
+            status: FileStatus::Valid,
+            links: Vec::new(),
+            signature: None,
+        };
+
+        if !report.path.exists() {
+            report.status = FileStatus::Missing;
+            return report;
+        }
+        if self.checks_signatures()
+            && let Ok(bytes) = fs::read(&report.path)
+        {
+            report.signature = self.check_signature(&report.path, &bytes);
+        }
+        let value = match self.read_value(&report.key) {
+            Ok(value) => value,
+            Err(status) => {
+                report.status = status;
+                return report;
+            }
+        };
+
+        for link in value.links() {
+            let target = self.resolve_link(&link, type_folders);
+            let checksum_of_file = match &target {
+                LinkTarget::Resolved(target) => link
+                    .algorithm
+                    .checksum_file(&self.full_path_unchecked(target)),
+                _ => None,
+            };
+            report.links.push(LinkReport {
+                name: link.name,
+                target,
+                checksum_in_link: link.checksum,
+                checksum_of_file,
+            });
+        }
+        return report;
+    }
+
+    /**
+    Reads the file of the database entry `key` and parses it into an untyped
+    [`Value`]. The type tag of the file is checked against the type name of
+    `key`.
+     */
+    pub(crate) fn read_value(&self, key: &DatabaseKeyBuf) -> Result<Value, FileStatus> {
+        let path = self.full_path_unchecked(key);
+        let bytes = fs::read(&path)
+            .and_then(|bytes| self.decode_file(&path, bytes))
+            .map_err(|err| FileStatus::Unreadable(err.to_string()))?;
+        let value = self
+            .format_for_type(&key.type_name)
+            .deserialize_value(&bytes)
+            .map_err(|err| FileStatus::Unparseable(err.to_string()))?;
+        if let Value::Map(entries) = &value
+            && entries.len() == 1
+            && let Some(tag) = entries[0].0.as_str()
+            && tag != key.type_name.to_string_lossy()
+        {
+            return Err(FileStatus::Unparseable(format!(
+                "file contains type {}, but is stored in folder {}",
+                tag,
+                key.type_name.to_string_lossy()
+            )));
+        }
+        return Ok(value);
+    }
+
+    /**
+    Returns the names of all type folders (direct subdirectories) of the
+    database.
+     */
+    pub(crate) fn type_folders(&self) -> std::io::Result<Vec<OsString>> {
+        let mut folders = Vec::new();
+        for entry in fs::read_dir(self.dir())? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                folders.push(entry.file_name());
+            }
+        }
+        folders.sort();
+        return Ok(folders);
+    }
+
+    /**
+    Returns the keys of all database entries, i.e. of all files with the file
+    extension of `self` within the type folders. The keys are sorted by type
+    name first and name second.
+     */
+    pub(crate) fn entry_keys(&self) -> std::io::Result<Vec<DatabaseKeyBuf>> {
+        let mut keys = Vec::new();
+        for type_name in self.type_folders()? {
+            for name in self.entry_names(&type_name)? {
+                keys.push(DatabaseKeyBuf::new(type_name.clone(), name));
+            }
+        }
+        return Ok(keys);
+    }
+
+    /**
+    Returns the sorted names of all database entries within the type folder
+    `type_name`. If the folder doesn't exist, an empty vector is returned.
+     */
+    pub(crate) fn entry_names(&self, type_name: &OsStr) -> std::io::Result<Vec<OsString>> {
+        let mut names = Vec::new();
+        for path in self.type_folder_files(type_name)? {
+            if let Some(name) = path
+                .file_name()
+                .and_then(|file_name| self.entry_name(type_name, file_name))
+                && self.is_in_shard(type_name, &name, &path)
+                && !self.is_expired_file(&path)
+            {
+                names.push(name);
+            }
+        }
+        names.sort();
+        return Ok(names);
+    }
+
+    /**
+    Returns the number of database entries of the type `type_name`, including
+    staged entries (see [`DatabaseManager::enable_staging`]) which don't exist
+    on disk yet. Expired entries are not counted.
+     */
+    pub(crate) fn entry_count(&self, type_name: &OsStr) -> std::io::Result<u64> {
+        let staged = self
+            .staged_files()
+            .into_iter()
+            .filter(|path| self.type_folder_of(path) == Some(type_name) && !path.exists())
+            .count();
+        return Ok((self.entry_names(type_name)?.len() + staged) as u64);
+    }
+
+    /**
+    Returns the name of the database entry stored in a file called
+    `file_name` within the type folder `type_name` (i.e. `file_name` without
+    the file extension of the type, see
+    [`DatabaseManager::file_ext_for_type`]). If `file_name` does not have the
+    file extension of the type, [`None`] is returned.
+     */
+    pub(crate) fn entry_name(&self, type_name: &OsStr, file_name: &OsStr) -> Option<OsString> {
+        let file_ext = self.file_ext_for_type(type_name);
+        let mut suffix = OsString::new();
+        if !file_ext.is_empty() {
+            suffix.push(".");
+            suffix.push(file_ext);
+        }
+        let suffix = suffix.as_encoded_bytes();
+        let bytes = file_name.as_encoded_bytes();
+        if bytes.len() <= suffix.len() || !bytes.ends_with(suffix) {
+            return None;
+        }
+        // SAFETY: The bytes were obtained from an OsStr and are only split
+        // before an ASCII character (the dot of the extension).
+        return Some(unsafe {
+            OsString::from_encoded_bytes_unchecked(bytes[..bytes.len() - suffix.len()].to_vec())
+        });
+    }
+
+    /**
+    Determines the database entry `link` points to by searching all
+    `type_folders` for an entry with the linked name. See the module docstring.
+     */
+    pub(crate) fn resolve_link(
+        &self,
+        link: &DatabaseLink,
+        type_folders: &[OsString],
+    ) -> LinkTarget {
+        let mut candidates: Vec<DatabaseKeyBuf> = type_folders
+            .iter()
+            .map(|type_name| DatabaseKeyBuf::new(type_name, &link.name))
+            .filter(|key| self.exists(key))
+            .collect();
+
+        if candidates.len() > 1
+            && let Some(checksum_in_link) = link.checksum
+        {
+            let matching: Vec<DatabaseKeyBuf> = candidates
+                .iter()
+                .filter(|key| {
+                    link.algorithm
+                        .checksum_file(&self.full_path_unchecked(*key))
+                        == Some(checksum_in_link)
+                })
+                .cloned()
+                .collect();
+            if matching.len() == 1 {
+                candidates = matching;
+            }
+        }
+
+        match candidates.len() {
+            0 => return LinkTarget::Dangling,
+            1 => return LinkTarget::Resolved(candidates.remove(0)),
+            _ => return LinkTarget::Ambiguous(candidates),
+        }
+    }
+}
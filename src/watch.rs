@@ -0,0 +1,137 @@
+/*!
+This module contains the file watcher of a database, see
+[`DatabaseManager::watch`].
+
+In contrast to [`DatabaseManager::subscribe`], which only reports the changes
+made by the manager itself, a [`DatabaseWatcher`] observes the database
+directory via the file system notifications of the operating system (using
+the [`notify`](https://docs.rs/notify) crate). It therefore also reports
+entries which have been modified by other processes or by hand, e.g. to
+invalidate caches of long-running applications.
+ */
+
+use std::path::Path;
+use std::sync::mpsc::{Receiver, channel};
+
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{ChangeEvent, ChangeKind, DatabaseKeyBuf, DatabaseManager};
+
+/**
+Watches the directory of a database for changes of entry files, see
+[`DatabaseManager::watch`]. The directory is watched as long as this struct
+is alive.
+ */
+pub struct DatabaseWatcher {
+    // Dropping the watcher stops the notifications
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<ChangeEvent>,
+}
+
+impl DatabaseWatcher {
+    /**
+    Returns the receiver of the [`ChangeEvent`]s. The events are buffered
+    until they are received.
+     */
+    pub fn receiver(&self) -> &Receiver<ChangeEvent> {
+        return &self.receiver;
+    }
+}
+
+impl std::fmt::Debug for DatabaseWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.write_str("DatabaseWatcher");
+    }
+}
+
+impl DatabaseManager {
+    /**
+    Starts watching the directory of `self` (including all type folders and
+    shards) for changes of entry files and returns a [`DatabaseWatcher`],
+    which receives a [`ChangeEvent`] for every created, modified or removed
+    entry. Modified files are reported as [`ChangeKind::Overwritten`]. Other
+    files within the directory (e.g. lock files, temporary files or
+    signatures) are ignored.
+
+    All changes are reported, no matter whether they have been made by
+    `self`, by another manager, by another process or by hand. Since the
+    notifications are delivered by the operating system, a single change may
+    result in multiple events (e.g. a creation followed by a modification)
+    and the order of the events may differ between platforms. Changes of the
+    configuration of `self` after calling this method (e.g. via
+    [`DatabaseManager::set_sharding`]) are not taken into account.
+
+    # Examples
+
+    ```no_run
+    use serde_mosaic::*;
+
+    let mut dbm = DatabaseManager::open("/path/to/db", SerdeYaml).expect("directory exists");
+    let watcher = dbm.watch().expect("directory can be watched");
+    for event in watcher.receiver().iter() {
+        // Another process might have changed the entry
+        dbm.cache_mut().clear();
+        println!("{:?} {}", event.kind, event.key);
+    }
+    ```
+     */
+    pub fn watch(&self) -> std::io::Result<DatabaseWatcher> {
+        let (sender, receiver) = channel();
+        // Notifications contain canonical paths. The clone neither keeps the
+        // cached instances nor the watcher of the cache alive.
+        let mut dbm = self.clone();
+        dbm.dir = self.dir().canonicalize()?;
+        dbm.cache = Default::default();
+        dbm.cache_watcher = None;
+        let handler = move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            for path in event.paths.iter() {
+                let kind = match event.kind {
+                    EventKind::Create(_) => ChangeKind::Created,
+                    EventKind::Remove(_) => ChangeKind::Removed,
+                    EventKind::Modify(ModifyKind::Metadata(_)) => continue,
+                    // Files are replaced by renaming temporary files
+                    EventKind::Modify(ModifyKind::Name(_)) if !path.exists() => ChangeKind::Removed,
+                    EventKind::Modify(_) => ChangeKind::Overwritten,
+                    _ => continue,
+                };
+                if let Some(event) = dbm.external_change_event(kind, path) {
+                    // The receiver might have been dropped already
+                    let _ = sender.send(event);
+                }
+            }
+        };
+        let mut watcher = notify::recommended_watcher(handler).map_err(std::io::Error::other)?;
+        watcher
+            .watch(self.dir(), RecursiveMode::Recursive)
+            .map_err(std::io::Error::other)?;
+        return Ok(DatabaseWatcher {
+            _watcher: watcher,
+            receiver,
+        });
+    }
+
+    /**
+    Returns the [`ChangeEvent`] for the file at `file_path` or [`None`] if the
+    file is not a database entry.
+     */
+    fn external_change_event(&self, kind: ChangeKind, file_path: &Path) -> Option<ChangeEvent> {
+        let type_name = self.type_folder_of(file_path)?;
+        let name = self.entry_name(type_name, file_path.file_name()?)?;
+        if !self.is_in_shard(type_name, &name, file_path) {
+            return None;
+        }
+        return Some(ChangeEvent {
+            kind,
+            key: DatabaseKeyBuf::new(type_name, name),
+            file_path: file_path.to_path_buf(),
+            checksum: match kind {
+                ChangeKind::Removed => None,
+                ChangeKind::Created | ChangeKind::Overwritten => self.file_checksum(file_path),
+            },
+        });
+    }
+}
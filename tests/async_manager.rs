@@ -0,0 +1,46 @@
+#![cfg(feature = "tokio")]
+
+use std::sync::Arc;
+
+use serde_mosaic::*;
+
+mod utilities;
+use utilities::*;
+
+#[test]
+fn test_async_read_write_remove() {
+    let dbm = AsyncDatabaseManager::new(scratch_database("async_read_write_remove"));
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let shovel = Arc::new(Shovel {
+            name: "async_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 1,
+                name: "hickory".into(),
+            }),
+            blade: Material {
+                id: 2,
+                name: "carbon_steel".into(),
+            },
+        });
+        dbm.write(shovel.clone(), WriteOptions::default())
+            .await
+            .unwrap();
+
+        // Concurrent reads add their cached instances to the underlying manager
+        let reads = [(), ()].map(|_| {
+            let dbm = dbm.clone();
+            tokio::spawn(async move { dbm.read::<Shovel, _>("async_shovel").await })
+        });
+        for read in reads {
+            assert_eq!(read.await.unwrap().unwrap(), *shovel);
+        }
+        assert_eq!(dbm.database_manager().cache().len(), 1);
+
+        dbm.remove(&*shovel).await.unwrap();
+        assert!(dbm.read::<Shovel, _>("async_shovel").await.is_err());
+    });
+}
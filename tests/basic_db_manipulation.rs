@@ -54,3 +54,252 @@ fn test_format_readout() {
     let format_ref = dbm.data_format() as &dyn Any; // Possible since Rust 1.86
     assert!(format_ref.downcast_ref::<SerdeYaml>().is_some());
 }
+
+#[cfg(feature = "testing")]
+#[test]
+fn test_temp_database() {
+    use serde_mosaic::testing::TempDatabase;
+
+    let mut dbm = TempDatabase::new(SerdeYaml)
+        .unwrap()
+        .fixture(type_name::<Bar>(), "inline", "Bar: inline\n")
+        .unwrap();
+    let dir = dbm.path().to_path_buf();
+    assert_eq!(dbm.read::<Bar, _>("inline").unwrap(), Bar("inline".into()));
+    drop(dbm);
+    assert!(!dir.exists());
+
+    // Fixture directories are copied
+    let mut fixtures = TempDatabase::from_fixtures("tests/test_database", SerdeYaml).unwrap();
+    assert!(fixtures.exists(("Material", "steel")));
+    fixtures.remove(("Material", "steel")).unwrap();
+    assert!(Path::new("tests/test_database/Material/steel.yaml").exists());
+}
+
+#[test]
+fn test_temp_database_manager() {
+    let mut dbm = DatabaseManager::temp(SerdeYaml).unwrap();
+    assert!(dbm.is_temp());
+    let dir = dbm.dir().to_path_buf();
+    dbm.write(&Bar("scratch".into()), &WriteOptions::default())
+        .unwrap();
+
+    // The directory is only removed after the last clone has been dropped
+    let clone = dbm.clone();
+    drop(dbm);
+    assert!(clone.exists((type_name::<Bar>(), "scratch")));
+    drop(clone);
+    assert!(!dir.exists());
+
+    assert!(
+        !DatabaseManager::open(std::env::temp_dir(), SerdeYaml)
+            .unwrap()
+            .is_temp()
+    );
+}
+
+#[test]
+fn test_subscribe() {
+    let mut dbm = DatabaseManager::temp(SerdeYaml).unwrap();
+    let events = dbm.subscribe();
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+
+    dbm.write(&Bar("observed".into()), &write_options).unwrap();
+    dbm.write(&Bar("observed".into()), &write_options).unwrap();
+    dbm.remove((type_name::<Bar>(), "observed")).unwrap();
+
+    // Removing a missing entry doesn't generate an event
+    dbm.remove((type_name::<Bar>(), "observed")).unwrap();
+
+    let events: Vec<ChangeEvent> = events.try_iter().collect();
+    let kinds: Vec<ChangeKind> = events.iter().map(|event| event.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ChangeKind::Created,
+            ChangeKind::Overwritten,
+            ChangeKind::Removed
+        ]
+    );
+    assert!(
+        events
+            .iter()
+            .all(|event| event.key == DatabaseKeyBuf::new("Bar", "observed"))
+    );
+    assert!(events[0].checksum.is_some());
+    assert_eq!(events[0].checksum, events[1].checksum);
+    assert_eq!(events[2].checksum, None);
+
+    // Clones share the subscribers
+    let events = dbm.subscribe();
+    let mut clone = dbm.clone();
+    clone.write(&Bar("cloned".into()), &write_options).unwrap();
+    assert_eq!(events.try_iter().count(), 1);
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn test_watch() {
+    let dbm = DatabaseManager::temp(SerdeYaml).unwrap();
+    let watcher = dbm.watch().unwrap();
+
+    // Edited by hand
+    std::fs::create_dir_all(dbm.dir().join("Bar")).unwrap();
+    std::fs::write(dbm.dir().join("Bar/edited.yaml.tmp"), "---\nBar: edited\n").unwrap();
+    std::fs::write(dbm.dir().join("Bar/edited.yaml"), "---\nBar: edited\n").unwrap();
+
+    // Temporary files are ignored
+    let event = watcher
+        .receiver()
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(event.key, DatabaseKeyBuf::new("Bar", "edited"));
+    assert!(event.checksum.is_some());
+
+    std::fs::remove_file(dbm.dir().join("Bar/edited.yaml")).unwrap();
+    let removed = watcher
+        .receiver()
+        .iter()
+        .find(|event| event.kind == ChangeKind::Removed)
+        .unwrap();
+    assert_eq!(removed.key, DatabaseKeyBuf::new("Bar", "edited"));
+}
+
+#[test]
+fn test_scoped() {
+    let root = DatabaseManager::temp(SerdeYaml).unwrap();
+    let mut acme = root.scoped("acme").unwrap();
+    let mut globex = root.scoped("globex").unwrap();
+    assert_eq!(acme.dir(), root.dir().join("acme"));
+
+    acme.write(&Bar("report".into()), &WriteOptions::default())
+        .unwrap();
+    assert!(root.dir().join("acme/Bar/report.yaml").exists());
+    assert!(acme.exists((type_name::<Bar>(), "report")));
+    assert!(!globex.exists((type_name::<Bar>(), "report")));
+    assert!(globex.read::<Bar, _>("report").is_err());
+    assert_eq!(acme.read::<Bar, _>("report").unwrap(), Bar("report".into()));
+
+    // Scoping the same tenant again opens the existing database
+    assert!(
+        root.scoped("acme")
+            .unwrap()
+            .exists((type_name::<Bar>(), "report"))
+    );
+
+    for invalid in ["", ".", "..", "acme/Bar", "../acme"] {
+        let err = root.scoped(invalid).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
+
+#[test]
+fn test_scoped_with_caches() {
+    let root = DatabaseManager::temp(SerdeYaml).unwrap();
+    let caches = TenantCaches::new();
+    let mut acme = root.scoped_with_caches("acme", &caches).unwrap();
+    let globex = root.scoped_with_caches("globex", &caches).unwrap();
+
+    // Managers of the same tenant share the cache, other tenants don't
+    let report = std::sync::Arc::new(Bar("report".into()));
+    assert!(acme.cache_insert(report.clone()).is_none());
+    let acme_again = root.scoped_with_caches("acme", &caches).unwrap();
+    let cached = acme_again.cache_get::<Bar>("report").unwrap();
+    assert!(std::sync::Arc::ptr_eq(&cached, &report));
+    assert!(globex.cache_get::<Bar>("report").is_none());
+    assert!(
+        root.scoped("acme")
+            .unwrap()
+            .cache_get::<Bar>("report")
+            .is_none()
+    );
+
+    // Removed caches are not reused
+    assert!(caches.remove("acme").is_some());
+    let acme_new = root.scoped_with_caches("acme", &caches).unwrap();
+    assert!(acme_new.cache_get::<Bar>("report").is_none());
+}
+
+#[test]
+fn test_list() {
+    let mut dbm = DatabaseManager::temp(SerdeYaml).unwrap();
+    assert_eq!(dbm.list::<Bar>().unwrap().count(), 0);
+    for name in ["beta", "alpha"] {
+        dbm.write(&Bar(name.into()), &WriteOptions::default())
+            .unwrap();
+    }
+    std::fs::write(dbm.dir().join("Bar/notes.txt"), "not an entry").unwrap();
+
+    // Staged entries are listed as well
+    dbm.enable_staging(StagingDropPolicy::Discard);
+    dbm.write(&Bar("gamma".into()), &WriteOptions::default())
+        .unwrap();
+
+    let names: Vec<_> = dbm.list::<Bar>().unwrap().collect();
+    assert_eq!(names, ["alpha", "beta", "gamma"]);
+    let keys: Vec<_> = dbm.list_all().unwrap().map(|key| key.to_string()).collect();
+    assert_eq!(keys.len(), 3);
+
+    let metadata: Vec<EntryMetadata> = dbm
+        .list_with_metadata::<Bar>()
+        .unwrap()
+        .collect::<std::io::Result<_>>()
+        .unwrap();
+    assert_eq!(metadata[0].checksum, dbm.checksum(["Bar", "alpha"]));
+    assert!(metadata[0].modified.is_some());
+    assert!(metadata[0].bytes > 0);
+    assert_eq!(metadata[2].path, dbm.dir().join("Bar/gamma.yaml"));
+    assert!(metadata[2].modified.is_none());
+    assert!(
+        dbm.entry_metadata(["Bar", "delta"]).unwrap_err().kind() == std::io::ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn test_sharding() {
+    let mut dbm = DatabaseManager::temp(SerdeYaml).unwrap();
+    dbm.set_sharding(Sharding::NamePrefix(2));
+    for name in ["beta", "alpha", "a"] {
+        dbm.write(&Bar(name.into()), &WriteOptions::default())
+            .unwrap();
+    }
+    assert!(dbm.dir().join("Bar/be/beta.yaml").exists());
+    assert!(dbm.dir().join("Bar/al/alpha.yaml").exists());
+    assert!(dbm.dir().join("Bar/a_/a.yaml").exists());
+    assert!(!dbm.dir().join("Bar/beta.yaml").exists());
+
+    // Reading, listing and removing resolve the shards
+    assert_eq!(dbm.read::<Bar, _>("beta").unwrap(), Bar("beta".into()));
+    let names: Vec<_> = dbm.list::<Bar>().unwrap().collect();
+    assert_eq!(names, ["a", "alpha", "beta"]);
+    assert_eq!(
+        dbm.exists_many([["Bar", "alpha"], ["Bar", "gamma"]]),
+        [true, false]
+    );
+    assert!(dbm.validate_layout().unwrap().is_clean());
+    dbm.remove(["Bar", "alpha"]).unwrap();
+    assert!(!dbm.exists(["Bar", "alpha"]));
+    dbm.remove_empty_subfolders().unwrap();
+    assert!(!dbm.dir().join("Bar/al").exists());
+
+    // Adjusted names are stored in their own shard
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::AdjustName;
+    let path = dbm.write(&Bar("beta".into()), &write_options).unwrap();
+    assert_eq!(path, dbm.dir().join("Bar/be/beta_0.yaml"));
+
+    // Resharding moves all entries into the new layout
+    let moved = dbm.reshard(Sharding::None).unwrap();
+    assert_eq!(moved.len(), 3);
+    assert_eq!(dbm.sharding(), Sharding::None);
+    assert!(dbm.dir().join("Bar/beta.yaml").exists());
+    assert!(!dbm.dir().join("Bar/be").exists());
+    let names: Vec<_> = dbm.list::<Bar>().unwrap().collect();
+    assert_eq!(names, ["a", "beta", "beta_0"]);
+
+    dbm.reshard(Sharding::NameHash(1)).unwrap();
+    let shard = Sharding::NameHash(1).shard(OsStr::new("beta")).unwrap();
+    assert!(dbm.dir().join("Bar").join(shard).join("beta.yaml").exists());
+    assert_eq!(dbm.read::<Bar, _>("beta").unwrap(), Bar("beta".into()));
+}
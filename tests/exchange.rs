@@ -0,0 +1,488 @@
+use serde_mosaic::*;
+
+mod utilities;
+use utilities::*;
+
+#[test]
+fn test_adopt() {
+    let mut dbm = scratch_database("adopt");
+    let ceramic = Material {
+        id: 1,
+        name: "ceramic".to_string(),
+    };
+    dbm.write(&ceramic, &WriteOptions::default()).unwrap();
+
+    // A file created by another tool, with the wrong file extension and
+    // without checksum in the link
+    let foreign_dir = dbm.dir().with_file_name("adopt_foreign");
+    std::fs::create_dir_all(&foreign_dir).unwrap();
+    let foreign_file = foreign_dir.join("cup.txt");
+    std::fs::write(
+        &foreign_file,
+        "---\nCup:\n  name: foreign_cup\n  material:\n    name: ceramic\n",
+    )
+    .unwrap();
+
+    // Wrong type
+    let err = dbm
+        .adopt(
+            &foreign_file,
+            "Material",
+            "foreign_cup",
+            &AdoptOptions::default(),
+        )
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(!dbm.exists(("Material", "foreign_cup")));
+
+    // Copy the file into the database
+    let (path, checksum) = dbm
+        .adopt(
+            &foreign_file,
+            "Cup",
+            "foreign_cup",
+            &AdoptOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(path, dbm.full_path(("Cup", "foreign_cup")).unwrap());
+    assert_eq!(Some(checksum), dbm.checksum(("Cup", "foreign_cup")));
+    assert!(foreign_file.exists());
+    let cup: Cup = dbm.read("foreign_cup").unwrap();
+    assert_eq!(cup.material, ceramic);
+    let verification = dbm.verify_entry(("Cup", "foreign_cup"));
+    assert_eq!(verification.files[0].links[0].checksum_in_link, None);
+
+    // Adopting again fails unless overwriting is allowed. Move the file this
+    // time and add the checksum to the link.
+    let err = dbm
+        .adopt(
+            &foreign_file,
+            "Cup",
+            "foreign_cup",
+            &AdoptOptions::default(),
+        )
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+
+    let adopt_options = AdoptOptions {
+        move_file: true,
+        overwrite: true,
+        refresh_checksums: true,
+    };
+    dbm.adopt(&foreign_file, "Cup", "foreign_cup", &adopt_options)
+        .unwrap();
+    assert!(!foreign_file.exists());
+    let verification = dbm.verify_entry(("Cup", "foreign_cup"));
+    assert!(verification.is_valid());
+    assert_eq!(
+        verification.files[0].links[0].checksum_in_link,
+        dbm.checksum(&ceramic)
+    );
+}
+
+#[test]
+fn test_export_flat() {
+    let mut dbm = scratch_database("export_flat");
+    let user = User {
+        name: "Hank".into(),
+        shovel: std::sync::Arc::new(Shovel {
+            name: "Hanks_shovel".into(),
+            shaft: std::sync::Arc::new(Material {
+                id: 4,
+                name: "Hanks_birch".into(),
+            }),
+            blade: Material {
+                id: 5,
+                name: "Hanks_alloy".into(),
+            },
+        }),
+    };
+    let cup = Cup {
+        name: "broken_cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+    dbm.remove(&cup.material).unwrap();
+
+    let export_dir = dbm.dir().with_file_name("export_flat_target");
+    let _ = std::fs::remove_dir_all(&export_dir);
+    let export = dbm.export_flat(&export_dir).unwrap();
+
+    // Only the user is a root entry which can be exported, the cup has a
+    // dangling link.
+    assert_eq!(
+        export.written_files,
+        vec![export_dir.join("User").join("Hank.yaml")]
+    );
+    assert_eq!(
+        export.report.problems,
+        vec![Problem::DanglingLink {
+            path: dbm.full_path(&cup).unwrap(),
+            link: "ceramic".into(),
+        }]
+    );
+
+    // The exported file does not contain any links, but can still be read as
+    // a database entry
+    let contents = std::fs::read_to_string(&export.written_files[0]).unwrap();
+    assert!(contents.contains("Hanks_alloy"));
+    assert!(!contents.contains("checksum"));
+    let mut export_dbm = DatabaseManager::open(&export_dir, SerdeYaml).unwrap();
+    assert!(!export_dbm.exists(&*user.shovel));
+    let flat_user: User = export_dbm.read("Hank").unwrap();
+    assert_eq!(flat_user, user);
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_export_flat_encrypted() {
+    let mut dbm = scratch_database("export_flat_encrypted");
+    let key_lookup = |type_name: &std::ffi::OsStr| {
+        if type_name == "Cup" {
+            return Some([7; 32]);
+        }
+        return None;
+    };
+    dbm.set_encryption(Some(Encryption::new(key_lookup).encrypt::<Cup>()));
+    let cup = Cup {
+        name: "daves_cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    let export_dir = dbm.dir().with_file_name("export_flat_encrypted_target");
+    let _ = std::fs::remove_dir_all(&export_dir);
+    let export = dbm.export_flat(&export_dir).unwrap();
+    assert_eq!(
+        export.written_files,
+        vec![export_dir.join("Cup").join("daves_cup.yaml")]
+    );
+
+    // The exported cup is encrypted as well
+    let contents = std::fs::read(&export.written_files[0]).unwrap();
+    assert!(!String::from_utf8_lossy(&contents).contains("ceramic"));
+    let mut export_dbm = DatabaseManager::open(&export_dir, SerdeYaml).unwrap();
+    assert!(export_dbm.read::<Cup, _>("daves_cup").is_err());
+    export_dbm.set_encryption(Some(Encryption::new(key_lookup).encrypt::<Cup>()));
+    assert_eq!(export_dbm.read::<Cup, _>("daves_cup").unwrap(), cup);
+}
+
+#[test]
+fn test_export_entry_flat() {
+    let mut dbm = scratch_database("export_entry_flat");
+    let shelf = Shelf {
+        name: "garden_shelf".into(),
+        shovel: Some(std::sync::Arc::new(Shovel {
+            name: "spade".into(),
+            shaft: std::sync::Arc::new(Material {
+                id: 2,
+                name: "ash".into(),
+            }),
+            blade: Material {
+                id: 3,
+                name: "steel".into(),
+            },
+        })),
+    };
+    dbm.write(&shelf, &WriteOptions::default()).unwrap();
+
+    let mut document = Vec::new();
+    dbm.export_entry_flat::<Shelf, _, _>("garden_shelf", &mut document)
+        .unwrap();
+    let document = String::from_utf8(document).unwrap();
+    assert!(document.contains("id: 3"));
+    assert!(!document.contains("checksum"));
+
+    // The document can be read without a database manager, but is tagged with
+    // the type name like an entry file
+    let flat_shelf: std::collections::HashMap<String, Shelf> =
+        serde_yaml::from_str(&document).unwrap();
+    assert_eq!(flat_shelf["Shelf"], shelf);
+
+    let err = dbm
+        .export_entry_flat::<Shelf, _, _>("missing", std::io::sink())
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_import_exploded() {
+    let mut dbm = scratch_database("import_exploded");
+    let document = "Shovel:\n  name: spade\n  shaft:\n    id: 2\n    name: ash\n  blade:\n    id: 3\n    name: steel\n";
+    let path = dbm
+        .import_exploded::<Shovel, _>(document.as_bytes(), &WriteOptions::default())
+        .unwrap();
+    assert_eq!(Some(path.clone()), dbm.full_path(("Shovel", "spade")));
+
+    // The annotated fields have been written into their own files
+    assert!(dbm.exists(("Material", "ash")));
+    assert!(dbm.exists(("Material", "steel")));
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains("id:"));
+
+    let shovel: Shovel = dbm.read("spade").unwrap();
+    assert_eq!(shovel.shaft.id, 2);
+    assert_eq!(shovel.blade.name, "steel");
+
+    let err = dbm
+        .import_exploded::<Shovel, _>("Shovel: 1".as_bytes(), &WriteOptions::default())
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_jsonl_export_import() {
+    let mut dbm = scratch_database("jsonl_source");
+    let cups: Vec<Cup> = ["daves_cup", "hanks_cup"]
+        .into_iter()
+        .map(|name| Cup {
+            name: name.to_string(),
+            material: Material {
+                id: 1,
+                name: "ceramic".to_string(),
+            },
+        })
+        .collect();
+    for cup in cups.iter() {
+        dbm.write(cup, &WriteOptions::default()).unwrap();
+    }
+
+    let mut resolved = Vec::new();
+    assert_eq!(
+        dbm.export_jsonl::<Cup, _>(&mut resolved, LinkHandling::Resolve)
+            .unwrap(),
+        2
+    );
+    let resolved = String::from_utf8(resolved).unwrap();
+    assert_eq!(resolved.lines().count(), 2);
+    assert_eq!(
+        resolved.lines().next().unwrap(),
+        r#"{"name":"daves_cup","material":{"id":1,"name":"ceramic"}}"#
+    );
+
+    let mut preserved = Vec::new();
+    dbm.export_jsonl::<Cup, _>(&mut preserved, LinkHandling::Preserve)
+        .unwrap();
+    let preserved = String::from_utf8(preserved).unwrap();
+    assert!(preserved.contains(r#""material":{"name":"ceramic","checksum":"#));
+
+    // Preserved links can't be resolved in an empty database
+    let mut target = scratch_database("jsonl_target");
+    let err = target
+        .import_jsonl::<Cup, _>(preserved.as_bytes(), &WriteOptions::default())
+        .unwrap_err();
+    assert!(err.to_string().starts_with("line 1:"));
+
+    let paths = target
+        .import_jsonl::<Cup, _>(resolved.as_bytes(), &WriteOptions::default())
+        .unwrap();
+    assert_eq!(paths.len(), 2);
+    assert!(target.exists(("Material", "ceramic")));
+    for cup in cups.iter() {
+        assert_eq!(&target.read::<Cup, _>(&cup.name).unwrap(), cup);
+    }
+
+    // Now the preserved links can be resolved as well
+    target
+        .import_jsonl::<Cup, _>(preserved.as_bytes(), &WriteOptions::default())
+        .unwrap();
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_transcode_all() {
+    let mut dbm = scratch_database("transcode_source");
+    let cup = Cup {
+        name: "daves_cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    let dir = dbm.dir().with_file_name("transcode_target");
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut progress_calls = Vec::new();
+    let transcode = dbm
+        .transcode_all(&dir, SerdeJson, |progress| {
+            progress_calls.push((progress.key.clone(), progress.converted, progress.total));
+        })
+        .unwrap();
+    assert!(transcode.report.is_clean());
+    assert_eq!(transcode.written_files.len(), 2);
+    assert_eq!(
+        progress_calls,
+        vec![
+            (DatabaseKeyBuf::new("Cup", "daves_cup"), 1, 2),
+            (DatabaseKeyBuf::new("Material", "ceramic"), 2, 2)
+        ]
+    );
+
+    // The checksums in the links have been updated to the converted files
+    let mut target = DatabaseManager::open(&dir, SerdeJson).unwrap();
+    assert!(target.verify_entry(("Cup", "daves_cup")).is_valid());
+    assert_eq!(target.read::<Cup, _>("daves_cup").unwrap(), cup);
+
+    // Transcoding into the own directory is not allowed
+    let err = dbm.transcode_all(dbm.dir(), SerdeJson, |_| ()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_export_csv() {
+    let mut dbm = scratch_database("export_csv");
+    let stool = Stool {
+        name: "bar, \"high\" stool".into(),
+        leg_1: std::sync::Arc::new(Material {
+            id: 1,
+            name: "oak".into(),
+        }),
+        leg_2: std::sync::Arc::new(Material {
+            id: 1,
+            name: "oak".into(),
+        }),
+        leg_3: std::sync::Arc::new(Material {
+            id: 2,
+            name: "pine".into(),
+        }),
+        seat: std::sync::Arc::new(Material {
+            id: 3,
+            name: "leather".into(),
+        }),
+    };
+    dbm.write(&stool, &WriteOptions::default()).unwrap();
+
+    let mut csv = Vec::new();
+    let rows = dbm
+        .export_csv(
+            "Stool",
+            &["name", "leg_3.id", "seat.name", "seat.checksum", "missing"],
+            &mut csv,
+        )
+        .unwrap();
+    assert_eq!(rows, 1);
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "name,leg_3.id,seat.name,seat.checksum,missing\r\n\"bar, \"\"high\"\" stool\",2,leather,,\r\n"
+    );
+
+    // Unknown types result in an empty table
+    let mut csv = Vec::new();
+    assert_eq!(dbm.export_csv("Unknown", &["name"], &mut csv).unwrap(), 0);
+    assert_eq!(csv, b"name\r\n");
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_export_parquet() {
+    let mut dbm = scratch_database("export_parquet");
+    for (id, name) in [(1, "ceramic"), (2, "steel")] {
+        let cup = Cup {
+            name: format!("{}_cup", name),
+            material: Material {
+                id,
+                name: name.to_string(),
+            },
+        };
+        dbm.write(&cup, &WriteOptions::default()).unwrap();
+    }
+
+    let file = dbm.dir().with_file_name("export_parquet.parquet");
+    assert_eq!(dbm.export_parquet::<Cup, _>(&file).unwrap(), 2);
+    let bytes = std::fs::read(&file).unwrap();
+    assert!(bytes.starts_with(b"PAR1"));
+    assert!(bytes.ends_with(b"PAR1"));
+
+    // Dangling links prevent the export
+    dbm.remove(("Material", "steel")).unwrap();
+    let err = dbm.export_parquet::<Cup, _>(&file).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("dangling link \"steel\""));
+}
+
+#[cfg(feature = "figment")]
+#[test]
+fn test_config_provider() {
+    use figment::{Figment, Profile};
+
+    let mut dbm = scratch_database("config_provider");
+    let cup = Cup {
+        name: "daves_cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    let provider = dbm.config_provider(("Cup", "daves_cup")).unwrap();
+    let figment = Figment::from(provider.clone());
+    assert_eq!(
+        figment.extract_inner::<String>("material.name").unwrap(),
+        "ceramic"
+    );
+    assert_eq!(figment.extract::<Cup>().unwrap(), cup);
+
+    // The profile can be changed
+    let figment = Figment::from(provider.profile("release")).select("release");
+    assert_eq!(figment.extract_inner::<usize>("material.id").unwrap(), 1);
+    assert!(
+        Figment::from(
+            dbm.config_provider(("Cup", "daves_cup"))
+                .unwrap()
+                .profile("release")
+        )
+        .select(Profile::Default)
+        .extract::<Cup>()
+        .is_err()
+    );
+
+    let err = dbm.config_provider(("Cup", "hanks_cup")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn test_fork() {
+    let mut dbm = scratch_database("fork");
+    let cup = Cup {
+        name: "forked_cup".into(),
+        material: Material {
+            id: 9,
+            name: "forked_clay".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+    let _guard = dbm.lock(("Material", "forked_clay")).unwrap();
+
+    let fork_dir = std::env::temp_dir()
+        .join("serde_mosaic_tests")
+        .join("fork_target");
+    let _ = std::fs::remove_dir_all(&fork_dir);
+    let mut fork = dbm.fork(&fork_dir).unwrap();
+    assert_eq!(fork.read::<Cup, _>("forked_cup").unwrap(), cup);
+
+    // Lock files are not forked
+    assert!(!fork.is_locked(("Material", "forked_clay")));
+
+    // Changes in the fork don't affect the original database
+    let mut changed = cup.clone();
+    changed.material.id = 10;
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    fork.write(&changed.material, &write_options).unwrap();
+    fork.cache_mut().clear();
+    assert_eq!(fork.read::<Cup, _>("forked_cup").unwrap(), changed);
+    assert_eq!(dbm.read::<Cup, _>("forked_cup").unwrap(), cup);
+
+    // Forking into a non-empty directory fails
+    assert!(dbm.fork(&fork_dir).is_err());
+}
@@ -0,0 +1,711 @@
+use std::ffi::OsStr;
+use std::sync::Arc;
+
+use serde_mosaic::*;
+
+mod utilities;
+use utilities::*;
+
+#[test]
+fn test_compact() {
+    let mut dbm = scratch_database("compact");
+    let user = User {
+        name: "Hank".into(),
+        shovel: Arc::new(Shovel {
+            name: "Hanks_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 4,
+                name: "Hanks_birch".into(),
+            }),
+            blade: Material {
+                id: 5,
+                name: "Hanks_alloy".into(),
+            },
+        }),
+    };
+    let orphan = Material {
+        id: 6,
+        name: "leftover".into(),
+    };
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+    dbm.write(&orphan, &WriteOptions::default()).unwrap();
+    let orphan_path = dbm.full_path(&orphan).unwrap();
+    let empty_dir = dbm.dir().join("Empty");
+    std::fs::create_dir(&empty_dir).unwrap();
+
+    // Populate the cache with the shovel and its shaft
+    let _: User = dbm.read("Hank").unwrap();
+    assert_eq!(
+        dbm.cache()
+            .values()
+            .map(|subcache| subcache.len())
+            .sum::<usize>(),
+        2
+    );
+
+    // Edit the shaft by hand
+    let shaft_path = dbm.full_path(&*user.shovel.shaft).unwrap();
+    let contents = std::fs::read_to_string(&shaft_path).unwrap();
+    std::fs::write(&shaft_path, contents.replace("id: 4", "id: 7")).unwrap();
+
+    // Without roots and checksum refresh, only the empty folder is removed
+    let summary = dbm.compact(&CompactOptions::default()).unwrap();
+    assert_eq!(summary.removed_folders, vec![empty_dir]);
+    assert!(summary.removed_orphans.is_empty());
+    assert!(summary.refreshed_files.is_empty());
+    assert_eq!(summary.evicted_cache_entries, 1);
+    assert!(dbm.exists(&orphan));
+
+    let compact_options = CompactOptions {
+        roots: vec![DatabaseKeyBuf::new("User", "Hank")],
+        refresh_checksums: true,
+    };
+    let summary = dbm.compact(&compact_options).unwrap();
+    assert_eq!(
+        summary.refreshed_files,
+        vec![
+            dbm.full_path(&*user.shovel).unwrap(),
+            dbm.full_path(&user).unwrap()
+        ]
+    );
+    assert_eq!(summary.removed_orphans, vec![orphan_path]);
+    assert!(summary.removed_folders.is_empty());
+    assert_eq!(summary.evicted_cache_entries, 1);
+    assert!(summary.report.is_clean());
+
+    assert!(!dbm.exists(&orphan));
+    assert!(dbm.verify_entry(&user).is_valid());
+    let info = dbm.read_verbose::<User, _>("Hank").unwrap().1;
+    assert!(info.checksum_mismatch.is_empty());
+}
+
+#[test]
+fn test_compact_missing_root() {
+    let mut dbm = scratch_database("compact_missing_root");
+    let material = Material {
+        id: 1,
+        name: "birch".into(),
+    };
+    dbm.write(&material, &WriteOptions::default()).unwrap();
+
+    // A misspelled root must not turn all entries into orphans
+    let compact_options = CompactOptions {
+        roots: vec![DatabaseKeyBuf::new("Material", "brich")],
+        ..Default::default()
+    };
+    let summary = dbm.compact(&compact_options).unwrap();
+    assert!(summary.removed_orphans.is_empty());
+    assert!(matches!(
+        summary.report.problems.as_slice(),
+        [Problem::Missing { .. }]
+    ));
+    assert!(dbm.exists(&material));
+}
+
+#[test]
+fn test_disk_usage() {
+    let mut dbm = scratch_database("disk_usage");
+    for id in 0..3 {
+        let material = Material {
+            id,
+            name: format!("material_{}", "x".repeat(id * 50)),
+        };
+        dbm.write(&material, &WriteOptions::default()).unwrap();
+    }
+    let cup = Cup {
+        name: "cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    // Files without the database file extension count towards the folder size,
+    // but are not entries.
+    std::fs::write(dbm.dir().join("Cup").join("notes.txt"), "abc").unwrap();
+
+    let usage = dbm.disk_usage(2).unwrap();
+    assert_eq!(usage.per_type.len(), 2);
+    assert_eq!(usage.per_type[0].type_name, "Material");
+    assert_eq!(usage.per_type[0].entries, 4);
+    assert_eq!(usage.per_type[1].type_name, "Cup");
+    assert_eq!(usage.per_type[1].entries, 1);
+    assert_eq!(
+        usage.total_bytes,
+        usage.per_type.iter().map(|t| t.bytes).sum::<u64>()
+    );
+
+    assert_eq!(usage.largest_entries.len(), 2);
+    assert_eq!(
+        usage.largest_entries[0].key,
+        DatabaseKeyBuf::new("Material", format!("material_{}", "x".repeat(100)))
+    );
+    assert_eq!(
+        usage.largest_entries[1].key,
+        DatabaseKeyBuf::new("Material", format!("material_{}", "x".repeat(50)))
+    );
+    assert!(usage.largest_entries[0].bytes > usage.largest_entries[1].bytes);
+}
+
+#[test]
+fn test_find_orphans_and_gc() {
+    let mut dbm = scratch_database("find_orphans_and_gc");
+    let ash = Arc::new(Material {
+        id: 1,
+        name: "ash".into(),
+    });
+    let user = User {
+        name: "Ida".into(),
+        shovel: Arc::new(Shovel {
+            name: "Idas_shovel".into(),
+            shaft: ash.clone(),
+            blade: Material {
+                id: 2,
+                name: "steel".into(),
+            },
+        }),
+    };
+    let old_shovel = Shovel {
+        name: "old_shovel".into(),
+        shaft: ash,
+        blade: Material {
+            id: 3,
+            name: "rust".into(),
+        },
+    };
+    let leftover = Material {
+        id: 4,
+        name: "leftover".into(),
+    };
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+    dbm.write(&old_shovel, &WriteOptions::default()).unwrap();
+    dbm.write(&leftover, &WriteOptions::default()).unwrap();
+
+    let orphans: Vec<String> = dbm
+        .find_orphans()
+        .unwrap()
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    assert_eq!(
+        orphans,
+        ["Material/leftover", "Shovel/old_shovel", "User/Ida"]
+    );
+
+    // Without types, nothing is collected
+    assert!(dbm.gc(&GcOptions::default()).unwrap().orphans.is_empty());
+
+    // Removing the old shovel orphans its blade, but not the shared shaft
+    let mut gc_options = GcOptions {
+        types: vec!["Material".into(), "Shovel".into()],
+        dry_run: true,
+    };
+    let summary = dbm.gc(&gc_options).unwrap();
+    let orphans: Vec<String> = summary.orphans.iter().map(|key| key.to_string()).collect();
+    assert_eq!(
+        orphans,
+        ["Material/leftover", "Material/rust", "Shovel/old_shovel"]
+    );
+    assert!(summary.removed_files.is_empty());
+    assert!(dbm.exists(&old_shovel));
+
+    gc_options.dry_run = false;
+    let summary = dbm.gc(&gc_options).unwrap();
+    assert_eq!(summary.removed_files.len(), 3);
+    assert!(!dbm.exists(&old_shovel));
+    assert!(!dbm.exists(&leftover));
+    assert!(dbm.exists(&*user.shovel.shaft));
+    assert!(dbm.verify_entry(&user).is_valid());
+
+    // The links of unparseable files are unknown
+    std::fs::write(dbm.dir().join("Shovel/broken.yaml"), "Shovel: [").unwrap();
+    let err = dbm.find_orphans().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_referrers() {
+    let mut dbm = scratch_database("referrers");
+    let ash = Arc::new(Material {
+        id: 1,
+        name: "ash".into(),
+    });
+    for name in ["spade", "scoop"] {
+        let shovel = Shovel {
+            name: name.into(),
+            shaft: ash.clone(),
+            blade: Material {
+                id: 2,
+                name: "steel".into(),
+            },
+        };
+        dbm.write(&shovel, &WriteOptions::default()).unwrap();
+    }
+    let pine = Arc::new(Material {
+        id: 3,
+        name: "pine".into(),
+    });
+    let stool = Stool {
+        name: "stool".into(),
+        leg_1: ash.clone(),
+        leg_2: pine.clone(),
+        leg_3: pine.clone(),
+        seat: pine,
+    };
+    dbm.write(&stool, &WriteOptions::default()).unwrap();
+
+    let referrers = dbm.referrers(&*ash).unwrap();
+    assert_eq!(referrers.len(), 2);
+    assert_eq!(referrers[OsStr::new("Shovel")], ["scoop", "spade"]);
+    assert_eq!(referrers[OsStr::new("Stool")], ["stool"]);
+
+    assert_eq!(
+        dbm.referrers(("Material", "pine")).unwrap()[OsStr::new("Stool")],
+        ["stool"]
+    );
+    assert!(dbm.referrers(&stool).unwrap().is_empty());
+}
+
+#[test]
+fn test_remove_checked_and_recursive() {
+    let mut dbm = scratch_database("remove_checked_and_recursive");
+    let ash = Arc::new(Material {
+        id: 1,
+        name: "ash".into(),
+    });
+    let user = User {
+        name: "Jo".into(),
+        shovel: Arc::new(Shovel {
+            name: "Jos_shovel".into(),
+            shaft: ash.clone(),
+            blade: Material {
+                id: 2,
+                name: "bronze".into(),
+            },
+        }),
+    };
+    let stool = Stool {
+        name: "stool".into(),
+        leg_1: ash.clone(),
+        leg_2: ash.clone(),
+        leg_3: ash.clone(),
+        seat: ash.clone(),
+    };
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+    dbm.write(&stool, &WriteOptions::default()).unwrap();
+
+    // Linked entries can't be removed
+    let err = dbm.remove_checked(&*user.shovel).unwrap_err();
+    assert!(err.to_string().contains("User/Jo"));
+    assert!(dbm.exists(&*user.shovel));
+    assert!(dbm.remove_recursive(&*ash).is_err());
+
+    // The shaft is still used by the stool
+    let removed: Vec<String> = dbm
+        .remove_recursive(&user)
+        .unwrap()
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    assert_eq!(removed, ["Material/bronze", "Shovel/Jos_shovel", "User/Jo"]);
+    assert!(dbm.exists(&*ash));
+
+    dbm.remove_checked(&stool).unwrap();
+    dbm.remove_checked(&*ash).unwrap();
+    assert!(dbm.find_orphans().unwrap().is_empty());
+}
+
+#[test]
+fn test_rename() {
+    let mut dbm = scratch_database("rename");
+    let user = User {
+        name: "Kay".into(),
+        shovel: Arc::new(Shovel {
+            name: "Kays_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 1,
+                name: "ash".into(),
+            }),
+            blade: Material {
+                id: 2,
+                name: "iron".into(),
+            },
+        }),
+    };
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+
+    let path = dbm.rename(("Material", "ash"), "white_ash").unwrap();
+    assert_eq!(path, dbm.dir().join("Material/white_ash.yaml"));
+    assert!(!dbm.exists(("Material", "ash")));
+
+    // The shovel links to the new name and the checksums are up to date
+    dbm.cache_mut().clear();
+    let (read, info) = dbm.read_verbose::<User, _>("Kay").unwrap();
+    assert!(info.checksum_mismatch.is_empty());
+    assert_eq!(read.shovel.shaft.id, 1);
+    assert!(dbm.verify_entry(&user).is_valid());
+
+    assert_eq!(
+        dbm.rename(("Material", "ash"), "oak").unwrap_err().kind(),
+        std::io::ErrorKind::NotFound
+    );
+    assert_eq!(
+        dbm.rename(("Material", "white_ash"), "iron")
+            .unwrap_err()
+            .kind(),
+        std::io::ErrorKind::AlreadyExists
+    );
+    assert_eq!(
+        dbm.rename(("Material", "white_ash"), "../oak")
+            .unwrap_err()
+            .kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn test_short_links_in_link_graph() {
+    let mut dbm = scratch_database("short_links_in_link_graph");
+    let mut write_options = WriteOptions::default();
+    write_options.link_style = LinkStyle::Short;
+    let user = User {
+        name: "Lu".into(),
+        shovel: Arc::new(Shovel {
+            name: "Lus_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 1,
+                name: "ash".into(),
+            }),
+            blade: Material {
+                id: 2,
+                name: "bronze".into(),
+            },
+        }),
+    };
+    dbm.write(&user, &write_options).unwrap();
+    let ash = DatabaseKeyBuf::new("Material", "ash");
+
+    // Short links keep their targets alive
+    let orphans: Vec<String> = dbm
+        .find_orphans()
+        .unwrap()
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    assert_eq!(orphans, ["User/Lu"]);
+    let gc_options = GcOptions {
+        types: vec!["Material".into(), "Shovel".into()],
+        dry_run: false,
+    };
+    assert!(dbm.gc(&gc_options).unwrap().orphans.is_empty());
+    let compact_options = CompactOptions {
+        roots: vec![DatabaseKeyBuf::new("User", "Lu")],
+        ..Default::default()
+    };
+    assert!(
+        dbm.compact(&compact_options)
+            .unwrap()
+            .removed_orphans
+            .is_empty()
+    );
+    assert_eq!(
+        dbm.referrers(&ash).unwrap()[OsStr::new("Shovel")],
+        ["Lus_shovel"]
+    );
+
+    // Short links can't be rewritten, so renaming their targets is refused
+    assert!(dbm.remove_checked(&*user.shovel).is_err());
+    let err = dbm.rename(&ash, "oak").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    assert!(dbm.exists(&ash));
+
+    // Entries which might be linked via short links are not removed recursively
+    let removed: Vec<String> = dbm
+        .remove_recursive(&user)
+        .unwrap()
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    assert_eq!(removed, ["User/Lu"]);
+    assert!(dbm.exists(&*user.shovel));
+    assert!(dbm.read::<Shovel, _>("Lus_shovel").is_ok());
+}
+
+#[test]
+fn test_refresh_checksums() {
+    let mut dbm = scratch_database("refresh_checksums");
+    let user = User {
+        name: "Lou".into(),
+        shovel: Arc::new(Shovel {
+            name: "Lous_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 1,
+                name: "Lous_ash".into(),
+            }),
+            blade: Material {
+                id: 2,
+                name: "Lous_iron".into(),
+            },
+        }),
+    };
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+
+    // Edit both materials by hand
+    for (name, old, new) in [
+        ("Lous_ash", "id: 1", "id: 3"),
+        ("Lous_iron", "id: 2", "id: 4"),
+    ] {
+        let path = dbm.dir().join(format!("Material/{name}.yaml"));
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, contents.replace(old, new)).unwrap();
+    }
+    dbm.cache_mut().clear();
+    let (_, info) = dbm.read_verbose::<User, _>("Lou").unwrap();
+    assert_eq!(info.checksum_mismatch.len(), 2);
+
+    // Only the links to the shaft are refreshed, up to the user
+    let rewritten = dbm.refresh_checksums(("Material", "Lous_ash")).unwrap();
+    assert_eq!(
+        rewritten,
+        vec![
+            dbm.dir().join("Shovel/Lous_shovel.yaml"),
+            dbm.dir().join("User/Lou.yaml")
+        ]
+    );
+    let (read, info) = dbm.read_verbose::<User, _>("Lou").unwrap();
+    assert_eq!(read.shovel.shaft.id, 3);
+    assert_eq!(info.checksum_mismatch.len(), 1);
+    assert_eq!(
+        info.checksum_mismatch[0].file_path,
+        dbm.dir().join("Material/Lous_iron.yaml")
+    );
+
+    assert_eq!(dbm.refresh_all_checksums().unwrap().len(), 2);
+    let (_, info) = dbm.read_verbose::<User, _>("Lou").unwrap();
+    assert!(info.checksum_mismatch.is_empty());
+    assert!(dbm.refresh_all_checksums().unwrap().is_empty());
+
+    assert_eq!(
+        dbm.refresh_checksums(("Material", "Lous_oak"))
+            .unwrap_err()
+            .kind(),
+        std::io::ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn test_map_all() {
+    let mut dbm = scratch_database("map_all");
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    for (id, name) in [(1, "clay"), (2, "porcelain")] {
+        let cup = Cup {
+            name: format!("{}_cup", name),
+            material: Material {
+                id,
+                name: name.into(),
+            },
+        };
+        dbm.write(&cup, &write_options).unwrap();
+    }
+    std::fs::write(dbm.dir().join("Cup/broken_cup.yaml"), "Cup: [").unwrap();
+
+    let summary = dbm
+        .map_all(
+            |mut cup: Cup| {
+                cup.material.id += 10;
+                cup
+            },
+            &write_options,
+        )
+        .unwrap();
+    assert!(!summary.is_success());
+    assert_eq!(summary.failures.len(), 1);
+    assert_eq!(
+        summary.failures[0].0,
+        DatabaseKeyBuf::new("Cup", "broken_cup")
+    );
+    assert_eq!(summary.written_files.len(), 4);
+
+    let cup: Cup = dbm.read("clay_cup").unwrap();
+    assert_eq!(cup.material.id, 11);
+    let material: Material = dbm.read("porcelain").unwrap();
+    assert_eq!(material.id, 12);
+
+    // Failed entries are left untouched
+    let broken = std::fs::read_to_string(dbm.dir().join("Cup/broken_cup.yaml")).unwrap();
+    assert_eq!(broken, "Cup: [");
+}
+
+#[test]
+fn test_schema_migrations() {
+    let mut dbm = scratch_database("schema_migrations");
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    let cup = Cup {
+        name: "versioned_cup".into(),
+        material: Material {
+            id: 5,
+            name: "versioned_clay".into(),
+        },
+    };
+    dbm.write(&cup, &write_options).unwrap();
+
+    // Simulate an unversioned historical file which stored the id in tenths
+    // under a different field name
+    let path = dbm.dir().join("Material/versioned_clay.yaml");
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, contents.replace("id: 5", "number: 50")).unwrap();
+    assert!(dbm.read::<Cup, _>("versioned_cup").is_err());
+
+    // Version 0 -> 1 renames the field, version 1 -> 2 changes its unit
+    let migrations = MigrationRegistry::new()
+        .version::<Material>(2)
+        .register::<Material, _>(0, |mut contents| {
+            if let Some(number) = contents.remove("number") {
+                contents.insert("id", number);
+            }
+            contents
+        })
+        .register::<Material, _>(1, |mut contents| {
+            if let Some(id) = contents.get("id").and_then(Value::as_u64) {
+                contents.insert("id", Value::I64(id as i64 / 10));
+            }
+            contents
+        });
+    dbm.set_migrations(Some(migrations));
+    assert_eq!(
+        dbm.migrations().unwrap().current_version("Material"),
+        Some(2)
+    );
+    assert_eq!(dbm.migrations().unwrap().current_version("Cup"), None);
+    assert_eq!(dbm.read::<Cup, _>("versioned_cup").unwrap(), cup);
+
+    // Written entries are tagged with their current version and are not
+    // migrated again
+    dbm.write(&cup, &write_options).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("__version: 2"));
+    let cup_contents = std::fs::read_to_string(dbm.dir().join("Cup/versioned_cup.yaml")).unwrap();
+    assert!(!cup_contents.contains("__version"));
+    assert_eq!(dbm.read::<Cup, _>("versioned_cup").unwrap(), cup);
+
+    // Entries of newer versions are rejected
+    std::fs::write(&path, contents.replace("__version: 2", "__version: 3")).unwrap();
+    let err = dbm.read::<Material, _>("versioned_clay").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    // Missing migrations as well
+    std::fs::write(&path, &contents).unwrap();
+    dbm.set_migrations(Some(MigrationRegistry::new().version::<Material>(3)));
+    let err = dbm.read::<Material, _>("versioned_clay").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_open_or_repair() {
+    let mut dbm = scratch_database("open_or_repair");
+    let material = Material {
+        id: 7,
+        name: "crashed_clay".into(),
+    };
+    dbm.write(&material, &WriteOptions::default()).unwrap();
+
+    let dir = dbm.dir().to_path_buf();
+    std::fs::write(dir.join("Material/crashed_clay.yaml.tmp"), "Material:").unwrap();
+    std::fs::write(
+        dir.join("Material/old_clay.yml"),
+        "Material:\n  id: 8\n  name: old_clay\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("Material/crashed_clay.yml"), "Material: {}").unwrap();
+    std::fs::create_dir(dir.join("Empty")).unwrap();
+
+    let repair_options = RepairOptions {
+        legacy_extensions: vec!["yml".into()],
+        ..Default::default()
+    };
+    let (mut dbm, summary) =
+        DatabaseManager::open_or_repair(&dir, SerdeYaml, &repair_options).unwrap();
+    assert_eq!(
+        summary.removed_temp_files,
+        vec![dir.join("Material/crashed_clay.yaml.tmp")]
+    );
+    assert_eq!(
+        summary.renamed_files,
+        vec![(
+            dir.join("Material/old_clay.yml"),
+            dir.join("Material/old_clay.yaml")
+        )]
+    );
+    assert_eq!(summary.removed_folders, vec![dir.join("Empty")]);
+
+    // The legacy file could not be renamed, since the entry already exists
+    assert_eq!(
+        summary.report.problems,
+        vec![Problem::UnknownExtension {
+            path: dir.join("Material/crashed_clay.yml")
+        }]
+    );
+    assert_eq!(dbm.read::<Material, _>("old_clay").unwrap().id, 8);
+    assert_eq!(dbm.read::<Material, _>("crashed_clay").unwrap(), material);
+}
+
+#[test]
+fn test_prune_expired() {
+    use std::time::{Duration, SystemTime};
+
+    let mut dbm = scratch_database("prune_expired");
+    let cup = |name: &str| Cup {
+        name: name.into(),
+        material: Material {
+            id: 1,
+            name: "clay".into(),
+        },
+    };
+    let past = SystemTime::now() - Duration::from_secs(60);
+    let future = SystemTime::now() + Duration::from_secs(3600);
+    dbm.write_with_expiry(&cup("expired"), past, &WriteOptions::default())
+        .unwrap();
+    dbm.write_with_expiry(&cup("valid"), future, &WriteOptions::default())
+        .unwrap();
+    dbm.write(&cup("forever"), &WriteOptions::default())
+        .unwrap();
+
+    // Expired entries are absent, linked entries don't expire
+    assert!(!dbm.exists(("Cup", "expired")));
+    assert!(dbm.read::<Cup, _>("expired").is_err());
+    assert!(dbm.exists(("Cup", "valid")));
+    assert!(dbm.exists(("Material", "clay")));
+    assert_eq!(
+        dbm.exists_many([("Cup", "expired"), ("Cup", "valid"), ("Cup", "forever")]),
+        vec![false, true, true]
+    );
+    assert!(dbm.expiry(("Cup", "forever")).is_none());
+    assert!(dbm.validate_layout().unwrap().is_clean());
+
+    // Expiry can be changed afterwards
+    dbm.set_expiry(("Cup", "forever"), Some(past)).unwrap();
+    assert!(!dbm.exists(("Cup", "forever")));
+    assert!(dbm.set_expiry(("Cup", "forever"), None).is_err());
+
+    let pruned = dbm.prune_expired().unwrap();
+    assert_eq!(
+        pruned,
+        vec![
+            DatabaseKeyBuf::new("Cup", "expired"),
+            DatabaseKeyBuf::new("Cup", "forever")
+        ]
+    );
+    assert!(!dbm.dir().join("Cup").join("expired.yaml").exists());
+    assert!(!dbm.dir().join("Cup").join("expired.yaml.expires").exists());
+    assert!(dbm.read::<Cup, _>("valid").is_ok());
+
+    // A new entry in place of an expired one doesn't inherit the expiry
+    dbm.write_with_expiry(&cup("reused"), past, &WriteOptions::default())
+        .unwrap();
+    dbm.write(&cup("reused"), &WriteOptions::default()).unwrap();
+    assert!(dbm.exists(("Cup", "reused")));
+    assert!(dbm.expiry(("Cup", "reused")).is_none());
+}
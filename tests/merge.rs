@@ -0,0 +1,367 @@
+use serde_mosaic::*;
+
+mod utilities;
+use utilities::*;
+
+fn cup(name: &str, material_id: usize, material_name: &str) -> Cup {
+    return Cup {
+        name: name.into(),
+        material: Material {
+            id: material_id,
+            name: material_name.into(),
+        },
+    };
+}
+
+#[test]
+fn test_merge_from() {
+    let mut ours = scratch_database("merge_from_ours");
+    let mut theirs = scratch_database("merge_from_theirs");
+    let write_options = WriteOptions::default();
+
+    ours.write(&cup("shared_cup", 1, "clay"), &write_options)
+        .unwrap();
+    theirs
+        .write(&cup("shared_cup", 1, "clay"), &write_options)
+        .unwrap();
+    theirs
+        .write(&cup("new_cup", 2, "porcelain"), &write_options)
+        .unwrap();
+
+    // Same name, different contents
+    ours.write(&cup("conflict_cup", 3, "stoneware"), &write_options)
+        .unwrap();
+    theirs
+        .write(&cup("conflict_cup", 4, "earthenware"), &write_options)
+        .unwrap();
+
+    let summary = ours.merge_from(&theirs, &MergeOptions::default()).unwrap();
+    assert_eq!(
+        summary.added,
+        vec![
+            DatabaseKeyBuf::new("Cup", "new_cup"),
+            DatabaseKeyBuf::new("Material", "earthenware"),
+            DatabaseKeyBuf::new("Material", "porcelain"),
+        ]
+    );
+    assert_eq!(
+        summary.kept,
+        vec![DatabaseKeyBuf::new("Cup", "conflict_cup")]
+    );
+    assert_eq!(
+        summary.unchanged,
+        vec![
+            DatabaseKeyBuf::new("Cup", "shared_cup"),
+            DatabaseKeyBuf::new("Material", "clay"),
+        ]
+    );
+    assert!(summary.report.is_clean());
+    assert_eq!(
+        ours.read::<Cup, _>("new_cup").unwrap(),
+        cup("new_cup", 2, "porcelain")
+    );
+    assert_eq!(
+        ours.read::<Cup, _>("conflict_cup").unwrap(),
+        cup("conflict_cup", 3, "stoneware")
+    );
+
+    // Merging again only finds the conflict
+    let merge_options = MergeOptions {
+        strategy: MergeStrategy::KeepTheirs,
+    };
+    let summary = ours.merge_from(&theirs, &merge_options).unwrap();
+    assert!(summary.added.is_empty());
+    assert_eq!(
+        summary.overwritten,
+        vec![DatabaseKeyBuf::new("Cup", "conflict_cup")]
+    );
+    let (read, info) = ours.read_verbose::<Cup, _>("conflict_cup").unwrap();
+    assert_eq!(read, cup("conflict_cup", 4, "earthenware"));
+    assert!(info.checksum_mismatch.is_empty());
+
+    assert!(ours.merge_from(&ours.clone(), &merge_options).is_err());
+}
+
+#[test]
+fn test_merge_from_rename() {
+    let mut ours = scratch_database("merge_from_rename_ours");
+    let mut theirs = scratch_database("merge_from_rename_theirs");
+    let write_options = WriteOptions::default();
+
+    ours.write(&cup("ours_cup", 1, "glaze"), &write_options)
+        .unwrap();
+    theirs
+        .write(&cup("theirs_cup", 2, "glaze"), &write_options)
+        .unwrap();
+
+    let merge_options = MergeOptions {
+        strategy: MergeStrategy::Resolve(MergeResolver::new(|conflict| {
+            assert_eq!(conflict.key, DatabaseKeyBuf::new("Material", "glaze"));
+            return MergeDecision::Rename;
+        })),
+    };
+    let summary = ours.merge_from(&theirs, &merge_options).unwrap();
+    assert_eq!(
+        summary.renamed,
+        vec![(
+            DatabaseKeyBuf::new("Material", "glaze"),
+            DatabaseKeyBuf::new("Material", "glaze_0")
+        )]
+    );
+
+    // The link of the imported entry points to the renamed material
+    let (read, info) = ours.read_verbose::<Cup, _>("theirs_cup").unwrap();
+    assert_eq!(read.material.id, 2);
+    assert!(info.checksum_mismatch.is_empty());
+    assert_eq!(ours.read::<Cup, _>("ours_cup").unwrap().material.id, 1);
+}
+
+#[test]
+fn test_merge_from_keep_newer() {
+    let mut ours = scratch_database("merge_from_keep_newer_ours");
+    let mut theirs = scratch_database("merge_from_keep_newer_theirs");
+    let write_options = WriteOptions::default();
+
+    ours.write(&cup("old_cup", 1, "clay"), &write_options)
+        .unwrap();
+    theirs
+        .write(&cup("old_cup", 2, "clay"), &write_options)
+        .unwrap();
+    ours.write(&cup("new_cup", 3, "porcelain"), &write_options)
+        .unwrap();
+    theirs
+        .write(&cup("new_cup", 4, "porcelain"), &write_options)
+        .unwrap();
+
+    // Ours of old_cup and theirs of new_cup are outdated
+    let outdated = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+    for path in [
+        ours.full_path(("Cup", "old_cup")).unwrap(),
+        ours.full_path(("Material", "clay")).unwrap(),
+        theirs.full_path(("Cup", "new_cup")).unwrap(),
+        theirs.full_path(("Material", "porcelain")).unwrap(),
+    ] {
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(outdated)
+            .unwrap();
+    }
+
+    let merge_options = MergeOptions {
+        strategy: MergeStrategy::KeepNewer,
+    };
+    let summary = ours.merge_from(&theirs, &merge_options).unwrap();
+    assert_eq!(
+        summary.overwritten,
+        vec![
+            DatabaseKeyBuf::new("Cup", "old_cup"),
+            DatabaseKeyBuf::new("Material", "clay"),
+        ]
+    );
+    assert_eq!(
+        summary.kept,
+        vec![
+            DatabaseKeyBuf::new("Cup", "new_cup"),
+            DatabaseKeyBuf::new("Material", "porcelain"),
+        ]
+    );
+    assert_eq!(ours.read::<Cup, _>("old_cup").unwrap().material.id, 2);
+    assert_eq!(ours.read::<Cup, _>("new_cup").unwrap().material.id, 3);
+}
+
+#[test]
+fn test_sync_to() {
+    let mut source = scratch_database("sync_to_source");
+    let mut target = scratch_database("sync_to_target");
+    let write_options = WriteOptions::default();
+
+    source
+        .write(&cup("mirrored_cup", 1, "clay"), &write_options)
+        .unwrap();
+    target
+        .write(&cup("mirrored_cup", 2, "clay"), &write_options)
+        .unwrap();
+    target
+        .write(&cup("extra_cup", 3, "bone_china"), &write_options)
+        .unwrap();
+
+    // A dry run only reports the delta
+    let sync_options = SyncOptions {
+        delete_extraneous: true,
+        dry_run: true,
+    };
+    let summary = source.sync_to(&mut target, &sync_options).unwrap();
+    assert_eq!(
+        summary.updated,
+        vec![
+            DatabaseKeyBuf::new("Cup", "mirrored_cup"),
+            DatabaseKeyBuf::new("Material", "clay"),
+        ]
+    );
+    assert!(summary.unchanged.is_empty());
+    assert_eq!(
+        summary.deleted,
+        vec![
+            DatabaseKeyBuf::new("Cup", "extra_cup"),
+            DatabaseKeyBuf::new("Material", "bone_china"),
+        ]
+    );
+    assert_eq!(
+        target.read::<Cup, _>("mirrored_cup").unwrap().material.id,
+        2
+    );
+
+    let sync_options = SyncOptions {
+        delete_extraneous: true,
+        dry_run: false,
+    };
+    let summary = source.sync_to(&mut target, &sync_options).unwrap();
+    assert!(summary.created.is_empty());
+    assert_eq!(summary.deleted.len(), 2);
+    target.cache_mut().clear();
+    let (read, info) = target.read_verbose::<Cup, _>("mirrored_cup").unwrap();
+    assert_eq!(read, cup("mirrored_cup", 1, "clay"));
+    assert!(info.checksum_mismatch.is_empty());
+    assert!(!target.exists(("Cup", "extra_cup")));
+
+    // Synchronizing again doesn't change anything
+    let summary = source.sync_to(&mut target, &sync_options).unwrap();
+    assert!(summary.created.is_empty() && summary.updated.is_empty());
+    assert_eq!(summary.unchanged.len(), 2);
+}
+
+#[test]
+fn test_copy_entry_to() {
+    let mut staging = scratch_database("copy_entry_to_staging");
+    let mut production = scratch_database("copy_entry_to_production");
+    let write_options = WriteOptions::default();
+
+    staging
+        .write(&cup("new_cup", 1, "clay"), &write_options)
+        .unwrap();
+    staging
+        .write(&cup("draft_cup", 2, "porcelain"), &write_options)
+        .unwrap();
+    production
+        .write(&cup("old_cup", 3, "clay"), &write_options)
+        .unwrap();
+
+    // The linked material is copied along and overwrites the existing one
+    let copied = staging
+        .copy_entry_to(&mut production, ("Cup", "new_cup"))
+        .unwrap();
+    assert_eq!(
+        copied,
+        vec![
+            DatabaseKeyBuf::new("Cup", "new_cup"),
+            DatabaseKeyBuf::new("Material", "clay"),
+        ]
+    );
+    let (read, info) = production.read_verbose::<Cup, _>("new_cup").unwrap();
+    assert_eq!(read, cup("new_cup", 1, "clay"));
+    assert!(info.checksum_mismatch.is_empty());
+    assert!(!production.exists(("Cup", "draft_cup")));
+
+    let err = staging
+        .copy_entry_to(&mut production, ("Cup", "missing"))
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+    let copied = staging.copy_all_to(&mut production).unwrap();
+    assert_eq!(copied.len(), 4);
+    assert!(production.exists(("Cup", "draft_cup")));
+    assert!(production.exists(("Cup", "old_cup")));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_copy_entry_to_other_format() {
+    let mut staging = scratch_database("copy_entry_to_other_format_staging");
+    staging
+        .write(&cup("new_cup", 1, "clay"), &WriteOptions::default())
+        .unwrap();
+
+    let dir = std::env::temp_dir()
+        .join("serde_mosaic_tests")
+        .join("copy_entry_to_other_format_production");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut production = DatabaseManager::open(&dir, SerdeJson).unwrap();
+
+    staging
+        .copy_entry_to(&mut production, ("Cup", "new_cup"))
+        .unwrap();
+    assert!(dir.join("Material/clay.json").exists());
+    let (read, info) = production.read_verbose::<Cup, _>("new_cup").unwrap();
+    assert_eq!(read, cup("new_cup", 1, "clay"));
+    assert!(info.checksum_mismatch.is_empty());
+}
+
+#[test]
+fn test_diff() {
+    let mut before = scratch_database("diff_before");
+    let mut after = scratch_database("diff_after");
+    let write_options = WriteOptions::default();
+
+    before
+        .write(&cup("kept_cup", 1, "clay"), &write_options)
+        .unwrap();
+    after
+        .write(&cup("kept_cup", 1, "clay"), &write_options)
+        .unwrap();
+    before
+        .write(&cup("removed_cup", 2, "clay"), &write_options)
+        .unwrap();
+    after
+        .write(&cup("added_cup", 3, "porcelain"), &write_options)
+        .unwrap();
+    before
+        .write(&cup("changed_cup", 4, "stoneware"), &write_options)
+        .unwrap();
+    after
+        .write(&cup("changed_cup", 5, "stoneware"), &write_options)
+        .unwrap();
+
+    let diff = before.diff(&after).unwrap();
+    assert_eq!(
+        diff.only_in_self,
+        vec![DatabaseKeyBuf::new("Cup", "removed_cup")]
+    );
+    assert_eq!(
+        diff.only_in_other,
+        vec![
+            DatabaseKeyBuf::new("Cup", "added_cup"),
+            DatabaseKeyBuf::new("Material", "porcelain"),
+        ]
+    );
+    let changed: Vec<_> = diff.changed.iter().map(|entry| entry.key.clone()).collect();
+    assert_eq!(
+        changed,
+        vec![
+            DatabaseKeyBuf::new("Cup", "changed_cup"),
+            DatabaseKeyBuf::new("Material", "stoneware"),
+        ]
+    );
+    assert!(diff.changed.iter().all(|entry| entry.fields.is_empty()));
+    assert!(diff.report.is_clean());
+
+    let mut diff_options = DiffOptions::default();
+    diff_options.fields = true;
+    let diff = before.diff_with(&after, &diff_options).unwrap();
+    let fields = &diff.changed[1].fields;
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].path, "id");
+    assert_eq!(
+        fields[0].value_self.as_ref().and_then(Value::as_u64),
+        Some(4)
+    );
+    assert_eq!(
+        fields[0].value_other.as_ref().and_then(Value::as_u64),
+        Some(5)
+    );
+    assert_eq!(diff.changed[0].fields[0].path, "material.checksum");
+
+    assert!(before.diff(&before.clone()).unwrap().is_empty());
+}
@@ -2,13 +2,32 @@
 All database entries belonging to this test have a prepending 02.
  */
 
+use serde::{Deserialize, Serialize};
 use serde_mosaic::*;
 use serde_yaml::Value;
-use std::{ptr, sync::Arc};
+use std::{ffi::OsStr, ptr, sync::Arc};
 
 mod utilities;
 use utilities::*;
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Team {
+    name: String,
+    #[serde(deserialize_with = "deserialize_arc_link")]
+    #[serde(serialize_with = "serialize_arc_link")]
+    captain: Arc<User>,
+    #[serde(deserialize_with = "deserialize_arc_link")]
+    #[serde(serialize_with = "serialize_arc_link")]
+    keeper: Arc<User>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Team {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
 #[test]
 fn test_read_flat() {
     let mut dbm = test_database();
@@ -33,6 +52,40 @@ fn test_read_link_err() {
     assert!(err_msg.contains("invalid type: string \"42.0\", expected usize"));
 }
 
+#[test]
+fn test_read_link_error_chain() {
+    let mut dbm = scratch_database("read_link_error_chain");
+    let cupboard = Cupboard {
+        name: "kitchen_cupboard".into(),
+        cup: Some(Cup {
+            name: "chained_cup".into(),
+            material: Material {
+                id: 1,
+                name: "chained_clay".to_string(),
+            },
+        }),
+    };
+    dbm.write(&cupboard, &WriteOptions::default()).unwrap();
+    std::fs::remove_file(dbm.dir().join("Material/chained_clay.yaml")).unwrap();
+
+    let err = dbm.read::<Cupboard, _>("kitchen_cupboard").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(err.to_string().contains(
+        "(while reading Cupboard/kitchen_cupboard.yaml → Cup/chained_cup.yaml → Material/chained_clay.yaml)"
+    ));
+    let link_error = LinkError::from_io_error(&err).unwrap();
+    assert_eq!(link_error.chain().len(), 3);
+    assert_eq!(
+        link_error.chain()[2],
+        dbm.dir().join("Material/chained_clay.yaml")
+    );
+    assert!(link_error.message().contains("Could not find file"));
+
+    // Errors of the read entry itself are not link errors
+    let err = dbm.read::<Cupboard, _>("missing_cupboard").unwrap_err();
+    assert!(LinkError::from_io_error(&err).is_none());
+}
+
 #[test]
 fn test_read_arc_link() {
     let mut dbm = test_database();
@@ -166,3 +219,914 @@ fn test_read_arc_opt() {
     let empty_shelf: Shelf = dbm.read("empty_shelf").unwrap();
     assert!(empty_shelf.shovel.is_none());
 }
+
+#[test]
+fn test_read_heal_checksum_mismatch() {
+    let mut dbm = scratch_database("read_heal_checksum_mismatch");
+
+    let user = User {
+        name: "Hank".into(),
+        shovel: Arc::new(Shovel {
+            name: "Hanks_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 4,
+                name: "Hanks_birch".into(),
+            }),
+            blade: Material {
+                id: 5,
+                name: "Hanks_alloy".into(),
+            },
+        }),
+    };
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+
+    // Edit the blade by hand
+    let blade_path = dbm.full_path(&user.shovel.blade).unwrap();
+    let contents = std::fs::read_to_string(&blade_path).unwrap();
+    std::fs::write(&blade_path, contents.replace("id: 5", "id: 6")).unwrap();
+
+    // Without healing, the mismatch is reported on every read
+    for _ in 0..2 {
+        let mut dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+        let (read_user, read_info) = dbm.read_verbose::<User, _>("Hank").unwrap();
+        assert_eq!(read_user.shovel.blade.id, 6);
+        assert_eq!(read_info.checksum_mismatch.len(), 1);
+        assert_eq!(read_info.checksum_mismatch[0].file_path, blade_path);
+        assert!(read_info.healed_files.is_empty());
+    }
+
+    // Healing rewrites the shovel and, since the checksum of the shovel
+    // changed, the user as well
+    let mut read_options = ReadOptions::default();
+    read_options.checksum_mismatch = ChecksumMismatchPolicy::Heal;
+    let mut healing_dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    let (read_user, read_info) = healing_dbm
+        .read_verbose_with::<User, _>("Hank", &read_options)
+        .unwrap();
+    assert_eq!(read_user.shovel.blade.id, 6);
+    assert_eq!(read_info.checksum_mismatch.len(), 1);
+    assert_eq!(
+        read_info.healed_files,
+        vec![
+            dbm.full_path(&*user.shovel).unwrap(),
+            dbm.full_path(&user).unwrap()
+        ]
+    );
+
+    // The mismatch is gone
+    let mut dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    let (read_user, read_info) = dbm.read_verbose::<User, _>("Hank").unwrap();
+    assert_eq!(read_user.shovel.blade.id, 6);
+    assert!(read_info.checksum_mismatch.is_empty());
+}
+
+#[test]
+fn test_read_heal_cached_links() {
+    let mut dbm = scratch_database("read_heal_cached_links");
+
+    // Both users share the shovel, so it is taken from the cache for the second one
+    let shovel = Arc::new(Shovel {
+        name: "team_shovel".into(),
+        shaft: Arc::new(Material {
+            id: 4,
+            name: "team_birch".into(),
+        }),
+        blade: Material {
+            id: 5,
+            name: "team_alloy".into(),
+        },
+    });
+    let team = Team {
+        name: "team".into(),
+        captain: Arc::new(User {
+            name: "Kim".into(),
+            shovel: shovel.clone(),
+        }),
+        keeper: Arc::new(User {
+            name: "Lee".into(),
+            shovel,
+        }),
+    };
+    dbm.write(&team, &WriteOptions::default()).unwrap();
+
+    // Edit the blade by hand
+    let blade_path = dbm.full_path(&team.captain.shovel.blade).unwrap();
+    let contents = std::fs::read_to_string(&blade_path).unwrap();
+    std::fs::write(&blade_path, contents.replace("id: 5", "id: 6")).unwrap();
+
+    // Healing rewrites the shovel and therefore the links of both users
+    let mut read_options = ReadOptions::default();
+    read_options.checksum_mismatch = ChecksumMismatchPolicy::Heal;
+    let mut healing_dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    let (read_team, read_info) = healing_dbm
+        .read_verbose_with::<Team, _>("team", &read_options)
+        .unwrap();
+    assert_eq!(read_team.keeper.shovel.blade.id, 6);
+    assert!(
+        read_info
+            .healed_files
+            .contains(&dbm.full_path(&*team.captain).unwrap())
+    );
+    assert!(
+        read_info
+            .healed_files
+            .contains(&dbm.full_path(&*team.keeper).unwrap())
+    );
+
+    // The mismatch is gone
+    let mut dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    let (_, read_info) = dbm.read_verbose::<Team, _>("team").unwrap();
+    assert!(read_info.checksum_mismatch.is_empty());
+}
+
+#[test]
+fn test_read_mixed_checksum_algorithms() {
+    let mut dbm = scratch_database("read_mixed_checksum_algorithms");
+    dbm.set_checksum_algorithm(ChecksumAlgorithm::Sha256);
+
+    let user = User {
+        name: "Ida".into(),
+        shovel: Arc::new(Shovel {
+            name: "Idas_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 7,
+                name: "Idas_oak".into(),
+            }),
+            blade: Material {
+                id: 8,
+                name: "Idas_alloy".into(),
+            },
+        }),
+    };
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+    let user_path = dbm.full_path(&user).unwrap();
+    assert_eq!(
+        dbm.checksum(&user),
+        ChecksumAlgorithm::Sha256.checksum_file(&user_path)
+    );
+
+    // The links are tagged with the algorithm and a newer link version
+    let contents = std::fs::read_to_string(&user_path).unwrap();
+    assert!(contents.contains("algorithm: sha256"));
+    assert!(contents.contains("version: 2"));
+
+    // Managers using another algorithm validate the links correctly
+    let mut adler_dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    let (_, read_info) = adler_dbm.read_verbose::<User, _>("Ida").unwrap();
+    assert!(read_info.checksum_mismatch.is_empty());
+
+    // Edit the blade by hand
+    let blade_path = dbm.full_path(&user.shovel.blade).unwrap();
+    let contents = std::fs::read_to_string(&blade_path).unwrap();
+    std::fs::write(&blade_path, contents.replace("id: 8", "id: 9")).unwrap();
+
+    // Healed links use the algorithm of the healing manager
+    let mut read_options = ReadOptions::default();
+    read_options.checksum_mismatch = ChecksumMismatchPolicy::Heal;
+    let mut crc_dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    crc_dbm.set_checksum_algorithm(ChecksumAlgorithm::Crc32);
+    let (_, read_info) = crc_dbm
+        .read_verbose_with::<User, _>("Ida", &read_options)
+        .unwrap();
+    assert_eq!(read_info.checksum_mismatch.len(), 1);
+    let shovel_path = dbm.full_path(&*user.shovel).unwrap();
+    let contents = std::fs::read_to_string(&shovel_path).unwrap();
+    assert!(contents.contains("algorithm: crc32"));
+
+    let (read_user, read_info) = adler_dbm.read_verbose::<User, _>("Ida").unwrap();
+    assert_eq!(read_user.shovel.blade.id, 9);
+    assert!(read_info.checksum_mismatch.is_empty());
+}
+
+#[test]
+fn test_read_env_interpolation() {
+    let mut dbm = scratch_database("read_env_interpolation");
+    let cup = Cup {
+        name: "daves_cup".into(),
+        material: Material {
+            id: 1,
+            name: "ceramic".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    // Replace the id of the linked material by placeholders
+    let material_path = dbm.full_path(&cup.material).unwrap();
+    let contents = std::fs::read_to_string(&material_path).unwrap();
+    std::fs::write(
+        &material_path,
+        contents.replace(
+            "id: 1",
+            "id: ${SERDE_MOSAIC_TEST_ID}$${SERDE_MOSAIC_TEST_ID}",
+        ),
+    )
+    .unwrap();
+
+    // SAFETY: No other test reads or writes this variable.
+    unsafe { std::env::set_var("SERDE_MOSAIC_TEST_ID", "4") };
+
+    // Placeholders are not substituted by default
+    assert!(dbm.read::<Cup, _>("daves_cup").is_err());
+
+    // The escaped placeholder stays a literal string
+    let mut read_options = ReadOptions::default();
+    read_options.env_interpolation = EnvInterpolation::Lenient;
+    assert!(dbm.read_with::<Cup, _>("daves_cup", &read_options).is_err());
+
+    std::fs::write(
+        &material_path,
+        contents.replace("id: 1", "id: ${SERDE_MOSAIC_TEST_ID}2"),
+    )
+    .unwrap();
+    let read_cup: Cup = dbm.read_with("daves_cup", &read_options).unwrap();
+    assert_eq!(read_cup.material.id, 42);
+
+    // Unset variables are left as they are or result in an error
+    std::fs::write(
+        &material_path,
+        contents.replace("id: 1", "id: ${SERDE_MOSAIC_TEST_UNSET}"),
+    )
+    .unwrap();
+    let err = dbm
+        .read_with::<Cup, _>("daves_cup", &read_options)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    read_options.env_interpolation = EnvInterpolation::Strict;
+    let err = dbm
+        .read_with::<Cup, _>("daves_cup", &read_options)
+        .unwrap_err();
+    assert!(err.to_string().contains("SERDE_MOSAIC_TEST_UNSET"));
+}
+
+#[test]
+fn test_read_env_interpolation_bypasses_cache() {
+    let mut dbm = scratch_database("read_env_interpolation_bypasses_cache");
+    let shovel = Shovel {
+        name: "env_shovel".into(),
+        shaft: Arc::new(Material {
+            id: 1,
+            name: "env_ash".into(),
+        }),
+        blade: Material {
+            id: 2,
+            name: "env_steel".into(),
+        },
+    };
+    dbm.write(&shovel, &WriteOptions::default()).unwrap();
+
+    // Interpolate the id of the Arc-wrapped shaft
+    let shaft_path = dbm.full_path(&*shovel.shaft).unwrap();
+    let contents = std::fs::read_to_string(&shaft_path).unwrap();
+    std::fs::write(
+        &shaft_path,
+        contents.replace("id: 1", "id: ${SERDE_MOSAIC_TEST_SHAFT_ID}"),
+    )
+    .unwrap();
+
+    // Every read sees the shaft for the current environment
+    let mut read_options = ReadOptions::default();
+    read_options.env_interpolation = EnvInterpolation::Strict;
+    for id in ["7", "8", "7"] {
+        // SAFETY: No other test reads or writes this variable.
+        unsafe { std::env::set_var("SERDE_MOSAIC_TEST_SHAFT_ID", id) };
+        let read_shovel: Shovel = dbm.read_with("env_shovel", &read_options).unwrap();
+        assert_eq!(read_shovel.shaft.id.to_string(), id);
+    }
+    assert!(dbm.cache_get::<Material>("env_ash").is_none());
+}
+
+#[test]
+fn test_read_with_params() {
+    let mut dbm = scratch_database("read_with_params");
+    let cup = Cup {
+        name: "daves_cup".into(),
+        material: Material {
+            id: 1,
+            name: "ceramic".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    // Parameterize the cup and its linked material
+    let cup_path = dbm.full_path(&cup).unwrap();
+    let contents = std::fs::read_to_string(&cup_path).unwrap();
+    std::fs::write(
+        &cup_path,
+        contents.replace("name: daves_cup", "name: \"{{ owner }}s_cup\""),
+    )
+    .unwrap();
+    let material_path = dbm.full_path(&cup.material).unwrap();
+    let contents = std::fs::read_to_string(&material_path).unwrap();
+    std::fs::write(&material_path, contents.replace("id: 1", "id: \"{{id}}\"")).unwrap();
+
+    let mut parameters = std::collections::HashMap::new();
+    parameters.insert(
+        "owner".to_string(),
+        serde_mosaic::Value::String("hank".into()),
+    );
+    parameters.insert("id".to_string(), serde_mosaic::Value::I64(7));
+    let read_cup: Cup = dbm.read_with_params("daves_cup", &parameters).unwrap();
+    assert_eq!(read_cup.name, "hanks_cup");
+    assert_eq!(read_cup.material.id, 7);
+
+    // Missing parameters result in an error
+    parameters.remove("id");
+    let err = dbm
+        .read_with_params::<Cup, _>("daves_cup", &parameters)
+        .unwrap_err();
+    assert!(err.to_string().contains("parameter id is not given"));
+
+    // Without parameters, the placeholders are not substituted
+    assert!(dbm.read::<Cup, _>("daves_cup").is_err());
+}
+
+#[test]
+fn test_read_with_params_bypasses_cache() {
+    let mut dbm = scratch_database("read_with_params_bypasses_cache");
+    let shovel = Shovel {
+        name: "param_shovel".into(),
+        shaft: Arc::new(Material {
+            id: 1,
+            name: "param_ash".into(),
+        }),
+        blade: Material {
+            id: 2,
+            name: "param_steel".into(),
+        },
+    };
+    dbm.write(&shovel, &WriteOptions::default()).unwrap();
+
+    // Parameterize the Arc-wrapped shaft
+    let shaft_path = dbm.full_path(&*shovel.shaft).unwrap();
+    let contents = std::fs::read_to_string(&shaft_path).unwrap();
+    std::fs::write(&shaft_path, contents.replace("id: 1", "id: \"{{id}}\"")).unwrap();
+
+    // Every read sees the shaft for its own parameters
+    for id in [7, 8, 7] {
+        let parameters =
+            std::collections::HashMap::from([("id".to_string(), serde_mosaic::Value::I64(id))]);
+        let read_shovel: Shovel = dbm.read_with_params("param_shovel", &parameters).unwrap();
+        assert_eq!(read_shovel.shaft.id, id as usize);
+    }
+    assert!(dbm.cache_get::<Material>("param_ash").is_none());
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn test_read_injected_faults() {
+    use serde_mosaic::testing::FaultInjector;
+    use std::io::ErrorKind;
+    use std::time::Duration;
+
+    let mut dbm = scratch_database("test_read_injected_faults");
+    let cup = Cup {
+        name: "faulty_cup".into(),
+        material: Material {
+            id: 7,
+            name: "faulty_clay".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    let faults = FaultInjector::new();
+    dbm.set_fault_injector(Some(faults.clone()));
+
+    // Resolving the link fails
+    faults.fail_read(&cup.material, ErrorKind::PermissionDenied);
+    let err = dbm.read::<Cup, _>("faulty_cup").unwrap_err();
+    assert!(err.to_string().contains("faulty_clay"));
+
+    // Slow reads still succeed
+    faults.clear();
+    faults.slow_reads(Duration::from_millis(5));
+    assert_eq!(dbm.read::<Cup, _>("faulty_cup").unwrap(), cup);
+
+    // The second file write (the cup after its material) fails
+    faults.clear();
+    faults.fail_nth_write(faults.writes() + 2, ErrorKind::StorageFull);
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    assert!(dbm.write(&cup, &write_options).is_err());
+    assert!(dbm.write(&cup, &write_options).is_ok());
+
+    dbm.set_fault_injector(None);
+    assert_eq!(dbm.read::<Cup, _>("faulty_cup").unwrap(), cup);
+}
+
+#[test]
+fn test_read_field_aliases() {
+    let mut dbm = scratch_database("test_read_field_aliases");
+    let cup = Cup {
+        name: "old_cup".into(),
+        material: Material {
+            id: 3,
+            name: "old_clay".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    // Simulate historical files in which the fields had different names
+    for (path, old, new) in [
+        ("Cup/old_cup.yaml", "material:", "substance:"),
+        ("Material/old_clay.yaml", "id:", "identifier:"),
+    ] {
+        let path = dbm.dir().join(path);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, contents.replace(old, new)).unwrap();
+    }
+    assert!(dbm.read::<Cup, _>("old_cup").is_err());
+
+    // Aliases apply to linked entries as well
+    assert_eq!(dbm.set_field_alias("Cup", "substance", "material"), None);
+    dbm.set_field_alias("Material", "identifier", "id");
+    assert_eq!(dbm.read::<Cup, _>("old_cup").unwrap(), cup);
+    assert_eq!(dbm.field_aliases("Material").unwrap().len(), 1);
+
+    assert_eq!(
+        dbm.remove_field_alias("Material", "identifier"),
+        Some("id".to_string())
+    );
+    assert!(dbm.field_aliases("Material").is_none());
+    assert!(dbm.read::<Cup, _>("old_cup").is_err());
+}
+
+#[test]
+fn test_read_lenient_links() {
+    let mut dbm = scratch_database("test_read_lenient_links");
+    let cup = Cup {
+        name: "stray_cup".into(),
+        material: Material {
+            id: 4,
+            name: "stray_clay".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    // Hand-edited link with a stray key
+    let cup_file = dbm.dir().join("Cup/stray_cup.yaml");
+    std::fs::write(
+        &cup_file,
+        "Cup:\n  name: stray_cup\n  material:\n    name: stray_clay\n    comment: fired twice\n",
+    )
+    .unwrap();
+    assert!(dbm.read::<Cup, _>("stray_cup").is_err());
+
+    let mut read_options = ReadOptions::default();
+    read_options.link_parsing = LinkParsing::Lenient;
+    let (read, info) = dbm
+        .read_verbose_with::<Cup, _>("stray_cup", &read_options)
+        .unwrap();
+    assert_eq!(read, cup);
+    assert_eq!(
+        info.ignored_link_fields,
+        vec![IgnoredLinkFields {
+            file_path: Some(cup_file),
+            name: "stray_clay".into(),
+            fields: vec!["comment".into()],
+        }]
+    );
+
+    // Inlined entities are still preferred over lenient links
+    std::fs::write(
+        dbm.dir().join("Cup/stray_cup.yaml"),
+        "Cup:\n  name: stray_cup\n  material:\n    id: 5\n    name: inline_clay\n",
+    )
+    .unwrap();
+    let (read, info) = dbm
+        .read_verbose_with::<Cup, _>("stray_cup", &read_options)
+        .unwrap();
+    assert_eq!(read.material.id, 5);
+    assert!(info.ignored_link_fields.is_empty());
+}
+
+#[test]
+fn test_read_string_checksums() {
+    let mut dbm = scratch_database("test_read_string_checksums");
+    let cup = Cup {
+        name: "quoted_cup".into(),
+        material: Material {
+            id: 6,
+            name: "quoted_clay".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    let cup_file = dbm.dir().join("Cup/quoted_cup.yaml");
+    let material_file = dbm.full_path(("Material", "quoted_clay")).unwrap();
+    let checksum = checksum(&material_file).unwrap();
+    for (written, mismatch) in [
+        (format!("\"{}\"", checksum), false),
+        (format!("\"0x{:x}\"", checksum), false),
+        (format!("\"0X{:X}\"", checksum), false),
+        (format!("\"0x{:x}\"", checksum.wrapping_add(1)), true),
+    ] {
+        std::fs::write(
+            &cup_file,
+            format!(
+                "Cup:\n  name: quoted_cup\n  material:\n    name: quoted_clay\n    checksum: {}\n",
+                written
+            ),
+        )
+        .unwrap();
+        let (read, info) = dbm.read_verbose::<Cup, _>("quoted_cup").unwrap();
+        assert_eq!(read, cup);
+        assert_eq!(info.checksum_mismatch.len(), mismatch as usize);
+    }
+
+    // Strings which aren't a number are rejected
+    std::fs::write(
+        &cup_file,
+        "Cup:\n  name: quoted_cup\n  material:\n    name: quoted_clay\n    checksum: \"abc\"\n",
+    )
+    .unwrap();
+    assert!(dbm.read::<Cup, _>("quoted_cup").is_err());
+}
+
+#[test]
+fn test_read_cache_policy() {
+    let mut dbm = scratch_database("read_cache_policy");
+    assert_eq!(dbm.cache_policy(), CachePolicy::ReadThrough);
+    dbm.set_cache_policy(CachePolicy::WriteThrough);
+
+    let shovel = Shovel {
+        name: "cached_shovel".into(),
+        shaft: Arc::new(Material {
+            id: 1,
+            name: "oak".to_string(),
+        }),
+        blade: Material {
+            id: 2,
+            name: "iron".to_string(),
+        },
+    };
+    dbm.write(&shovel, &WriteOptions::default()).unwrap();
+
+    // Only the Arc-wrapped entry has been put into the cache
+    assert_eq!(dbm.cache().len(), 1);
+    let read: Shovel = dbm.read("cached_shovel").unwrap();
+    assert!(Arc::ptr_eq(&read.shaft, &shovel.shaft));
+
+    // One-off reads bypass the cache without populating it
+    dbm.cache_mut().clear();
+    let mut read_options = ReadOptions::default();
+    read_options.bypass_cache = true;
+    let read: Shovel = dbm.read_with("cached_shovel", &read_options).unwrap();
+    assert!(!Arc::ptr_eq(&read.shaft, &shovel.shaft));
+    assert_eq!(read, shovel);
+    assert_eq!(dbm.cache().len(), 0);
+}
+
+#[test]
+fn test_read_cache_accessors() {
+    let mut dbm = scratch_database("read_cache_accessors");
+    let shovel = Shovel {
+        name: "manual_shovel".into(),
+        shaft: Arc::new(Material {
+            id: 1,
+            name: "ash".to_string(),
+        }),
+        blade: Material {
+            id: 2,
+            name: "steel".to_string(),
+        },
+    };
+    dbm.write(&shovel, &WriteOptions::default()).unwrap();
+
+    // Manually cached instances take precedence over the file
+    let replacement = Arc::new(Material {
+        id: 5,
+        name: "ash".to_string(),
+    });
+    assert!(dbm.cache_insert(replacement.clone()).is_none());
+    let read: Shovel = dbm.read("manual_shovel").unwrap();
+    assert!(Arc::ptr_eq(&read.shaft, &replacement));
+    assert!(Arc::ptr_eq(
+        &dbm.cache_get::<Material>("ash").unwrap(),
+        &replacement
+    ));
+    assert!(dbm.cache_get::<Shovel>("ash").is_none());
+
+    let removed = dbm.cache_remove::<Material>("ash").unwrap();
+    assert!(Arc::ptr_eq(&removed, &replacement));
+    assert!(dbm.cache_remove::<Material>("ash").is_none());
+    assert_eq!(dbm.cache_clear::<Material>(), 0);
+
+    let read: Shovel = dbm.read("manual_shovel").unwrap();
+    assert_eq!(read.shaft.id, 1);
+    assert_eq!(dbm.cache_clear::<Material>(), 1);
+    assert!(dbm.cache().is_empty());
+}
+
+#[test]
+fn test_read_shared_cache() {
+    let mut dbm = scratch_database("read_shared_cache");
+    let shovel = Shovel {
+        name: "shared_shovel".into(),
+        shaft: Arc::new(Material {
+            id: 1,
+            name: "ash".to_string(),
+        }),
+        blade: Material {
+            id: 2,
+            name: "steel".to_string(),
+        },
+    };
+    dbm.write(&shovel, &WriteOptions::default()).unwrap();
+    let shared_cache = SharedCache::new();
+    dbm.set_shared_cache(Some(shared_cache.clone()));
+
+    // Clones use the same shared cache
+    let mut other = dbm.clone();
+    let first: Shovel = dbm.read("shared_shovel").unwrap();
+    let second: Shovel = other.read("shared_shovel").unwrap();
+    let shafts = [first.shaft, second.shaft];
+    assert!(Arc::ptr_eq(&shafts[0], &shafts[1]));
+    assert!(dbm.cache().is_empty());
+    assert_eq!(shared_cache.read().len(), 1);
+    assert!(Arc::ptr_eq(
+        &dbm.cache_get::<Material>("ash").unwrap(),
+        &shafts[0]
+    ));
+
+    // Without the shared cache, the own cache is used again
+    dbm.set_shared_cache(None);
+    let read: Shovel = dbm.read("shared_shovel").unwrap();
+    assert!(!Arc::ptr_eq(&read.shaft, &shafts[0]));
+    assert_eq!(dbm.cache().len(), 1);
+}
+
+#[test]
+fn test_read_arc() {
+    let mut dbm = scratch_database("read_arc");
+    let shovel = Shovel {
+        name: "top_level_shovel".into(),
+        shaft: Arc::new(Material {
+            id: 1,
+            name: "ash".to_string(),
+        }),
+        blade: Material {
+            id: 2,
+            name: "steel".to_string(),
+        },
+    };
+    dbm.write(&shovel, &WriteOptions::default()).unwrap();
+
+    let first = dbm.read_arc::<Material, _>("ash").unwrap();
+    let second = dbm.read_arc::<Material, _>("ash").unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+
+    // Links reuse the instance as well
+    let read: Shovel = dbm.read("top_level_shovel").unwrap();
+    assert!(Arc::ptr_eq(&read.shaft, &first));
+    let stats = dbm.cache_stats();
+    assert_eq!((stats.hits, stats.misses), (2, 1));
+
+    // Changed files are read again
+    std::fs::write(
+        dbm.dir().join("Material/ash.yaml"),
+        "---\nMaterial:\n  id: 3\n  name: ash\n",
+    )
+    .unwrap();
+    let third = dbm.read_arc::<Material, _>("ash").unwrap();
+    assert_eq!(third.id, 3);
+    assert!(!Arc::ptr_eq(&first, &third));
+
+    assert!(dbm.read_arc::<Material, _>("missing").is_err());
+}
+
+#[test]
+fn test_read_cache_invalidation() {
+    let mut dbm = scratch_database("read_cache_invalidation");
+    assert_eq!(dbm.cache_invalidation(), CacheInvalidation::Never);
+    let shovel = Shovel {
+        name: "edited_shovel".into(),
+        shaft: Arc::new(Material {
+            id: 1,
+            name: "birch".to_string(),
+        }),
+        blade: Material {
+            id: 2,
+            name: "bronze".to_string(),
+        },
+    };
+    dbm.write(&shovel, &WriteOptions::default()).unwrap();
+    let read: Shovel = dbm.read("edited_shovel").unwrap();
+    assert_eq!(read.shaft.id, 1);
+
+    // The link still contains the checksum of the cached instance
+    std::fs::write(
+        dbm.dir().join("Material/birch.yaml"),
+        "---\nMaterial:\n  id: 3\n  name: birch\n",
+    )
+    .unwrap();
+    let read: Shovel = dbm.read("edited_shovel").unwrap();
+    assert_eq!(read.shaft.id, 1);
+
+    dbm.set_cache_invalidation(CacheInvalidation::OnRead)
+        .unwrap();
+    let (read, info) = dbm.read_verbose::<Shovel, _>("edited_shovel").unwrap();
+    assert_eq!(read.shaft.id, 3);
+    assert_eq!(info.checksum_mismatch.len(), 1);
+}
+
+#[test]
+fn test_read_cache_capacity() {
+    let mut dbm = scratch_database("read_cache_capacity");
+    assert_eq!(dbm.cache_capacity(), CacheCapacity::Unbounded);
+    for (name, shaft) in [("spade", "ash"), ("scoop", "oak")] {
+        let shovel = Shovel {
+            name: name.into(),
+            shaft: Arc::new(Material {
+                id: 1,
+                name: shaft.to_string(),
+            }),
+            blade: Material {
+                id: 2,
+                name: "steel".to_string(),
+            },
+        };
+        dbm.write(&shovel, &WriteOptions::default()).unwrap();
+    }
+    dbm.set_cache_capacity(CacheCapacity::Entries(1));
+
+    let _: Shovel = dbm.read("spade").unwrap();
+    let _: Shovel = dbm.read("spade").unwrap();
+    let stats = dbm.cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 1, 0));
+    assert_eq!(stats.entries, 1);
+    assert!(stats.bytes > 0);
+
+    // The shaft of the spade is evicted in favour of the shaft of the scoop
+    let _: Shovel = dbm.read("scoop").unwrap();
+    let _: Shovel = dbm.read("spade").unwrap();
+    let stats = dbm.cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 3, 2));
+    assert_eq!(stats.entries, 1);
+
+    dbm.set_cache_capacity(CacheCapacity::Bytes(0));
+    assert_eq!(dbm.cache_stats().entries, 0);
+    dbm.reset_cache_stats();
+    assert_eq!(dbm.cache_stats(), CacheStats::default());
+}
+
+#[test]
+fn test_read_max_link_depth() {
+    let mut dbm = scratch_database("read_max_link_depth");
+    let cup = Cup {
+        name: "rack_cup".into(),
+        material: Material {
+            id: 1,
+            name: "clay".to_string(),
+        },
+    };
+    let rack = Rack {
+        name: "kitchen_rack".into(),
+        cup: Linked::Resolved(cup.clone()),
+    };
+    dbm.write(&rack, &WriteOptions::default()).unwrap();
+
+    // Only the top-level fields are read
+    let mut read_options = ReadOptions::default();
+    read_options.max_link_depth = Some(0);
+    let read: Rack = dbm.read_with("kitchen_rack", &read_options).unwrap();
+    let Linked::Unresolved { name, checksum, .. } = &read.cup else {
+        panic!("link must not be resolved");
+    };
+    assert_eq!(name, "rack_cup");
+    assert!(checksum.is_some());
+
+    // Writing the entry keeps the unresolved link
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&read, &write_options).unwrap();
+
+    read_options.max_link_depth = Some(1);
+    let read: Rack = dbm.read_with("kitchen_rack", &read_options).unwrap();
+    assert_eq!(read.cup.resolved(), Some(&cup));
+    let read: Rack = dbm.read("kitchen_rack").unwrap();
+    assert_eq!(read, rack);
+}
+
+#[test]
+fn test_read_link_or_default() {
+    let mut dbm = scratch_database("read_link_or_default");
+    let platter = Platter {
+        name: "serving_tray".into(),
+        material: Material {
+            id: 3,
+            name: "tray_wood".to_string(),
+        },
+        cup: Some(Cup {
+            name: "tray_cup".into(),
+            material: Material {
+                id: 4,
+                name: "tray_clay".to_string(),
+            },
+        }),
+    };
+    dbm.write(&platter, &WriteOptions::default()).unwrap();
+    let read: Platter = dbm.read("serving_tray").unwrap();
+    assert_eq!(read, platter);
+
+    std::fs::remove_file(dbm.dir().join("Material/tray_wood.yaml")).unwrap();
+    std::fs::remove_file(dbm.dir().join("Cup/tray_cup.yaml")).unwrap();
+    let (read, read_info) = dbm.read_verbose::<Platter, _>("serving_tray").unwrap();
+    assert_eq!(read.material, Material::default());
+    assert_eq!(read.cup, None);
+    assert_eq!(read_info.substituted_links.len(), 2);
+    assert_eq!(read_info.substituted_links[0].name, "tray_wood");
+    assert_eq!(
+        read_info.substituted_links[0].file_path,
+        Some(dbm.dir().join("Platter/serving_tray.yaml"))
+    );
+    assert_eq!(
+        read_info.substituted_links[1].linked_file,
+        dbm.dir().join("Cup/tray_cup.yaml")
+    );
+
+    // Other errors of the linked entries still fail the read
+    std::fs::write(dbm.dir().join("Material/tray_wood.yaml"), "not a material").unwrap();
+    assert!(dbm.read::<Platter, _>("serving_tray").is_err());
+}
+
+#[test]
+fn test_read_batch() {
+    let mut dbm = scratch_database("read_batch");
+    let shaft = Arc::new(Material {
+        id: 1,
+        name: "ash".to_string(),
+    });
+    let names = ["spade_1", "spade_2", "missing", "spade_3", "spade_4"];
+    for (blade_id, name) in names.iter().enumerate() {
+        if *name == "missing" {
+            continue;
+        }
+        let shovel = Shovel {
+            name: name.to_string(),
+            shaft: shaft.clone(),
+            blade: Material {
+                id: blade_id,
+                name: format!("steel_{blade_id}"),
+            },
+        };
+        dbm.write(&shovel, &WriteOptions::default()).unwrap();
+    }
+
+    let results = dbm.read_batch::<Shovel, _>(&names);
+    assert_eq!(results.len(), 5);
+    assert!(results[2].is_err());
+    assert_eq!(results[3].as_ref().unwrap().name, "spade_3");
+    dbm.cache_mut().clear();
+
+    // The shaft is shared by the entries read on different threads
+    let threads = std::num::NonZeroUsize::new(3).unwrap();
+    let results = dbm.read_batch_parallel::<Shovel, _>(&names, threads);
+    let shovels: Vec<Shovel> = results.into_iter().flatten().collect();
+    let read_names: Vec<&str> = shovels.iter().map(|shovel| shovel.name.as_str()).collect();
+    assert_eq!(read_names, ["spade_1", "spade_2", "spade_3", "spade_4"]);
+    for shovel in shovels.iter() {
+        assert!(Arc::ptr_eq(&shovel.shaft, &shovels[0].shaft));
+    }
+
+    // The instances cached by the workers end up in the cache of the manager
+    assert!(Arc::ptr_eq(
+        &dbm.cache_get::<Material>("ash").unwrap(),
+        &shovels[0].shaft
+    ));
+}
+
+#[test]
+fn test_read_all() {
+    let mut dbm = scratch_database("read_all");
+    let shaft = Arc::new(Material {
+        id: 1,
+        name: "ash".to_string(),
+    });
+    for (name, blade_id) in [("spade", 2), ("scoop", 3)] {
+        let shovel = Shovel {
+            name: name.into(),
+            shaft: shaft.clone(),
+            blade: Material {
+                id: blade_id,
+                name: format!("steel_{blade_id}"),
+            },
+        };
+        dbm.write(&shovel, &WriteOptions::default()).unwrap();
+    }
+    std::fs::write(dbm.dir().join("Shovel/broken.yaml"), "Shovel: [").unwrap();
+    dbm.cache_mut().clear();
+
+    // The corrupted file doesn't prevent reading the other entries
+    let summary = dbm.read_all::<Shovel>().unwrap();
+    assert!(!summary.is_success());
+    assert_eq!(summary.failures.len(), 1);
+    assert_eq!(summary.failures[0].0.to_string(), "Shovel/broken");
+
+    let names: Vec<_> = summary.entries.iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["scoop", "spade"]);
+    assert_eq!(summary.entries[0].1.blade.id, 3);
+    assert!(Arc::ptr_eq(
+        &summary.entries[0].1.shaft,
+        &summary.entries[1].1.shaft
+    ));
+}
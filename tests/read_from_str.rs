@@ -1,4 +1,4 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, de};
 use serde_mosaic::*;
 
 mod utilities;
@@ -52,3 +52,95 @@ fn test_read_from_str_opt() {
     assert!(shelf.shovel.is_some());
     assert_eq!(shelf.shovel.unwrap().name, "Georgs_shovel");
 }
+
+#[test]
+fn test_read_from_str_worker_threads() {
+    // Deserializes each cup on its own worker thread
+    fn deserialize_in_workers<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Cup>, D::Error> {
+        let raw: Vec<String> = Vec::deserialize(deserializer)?;
+        let handle = ReadContextHandle::current().expect("called from DatabaseManager");
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = raw
+                .iter()
+                .map(|raw| {
+                    let handle = handle.clone();
+                    scope.spawn(move || handle.enter(|| serde_yaml::from_str::<Cup>(raw)))
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap().map_err(de::Error::custom))
+                .collect()
+        })
+    }
+
+    #[derive(Deserialize)]
+    struct Cups {
+        #[serde(deserialize_with = "deserialize_in_workers")]
+        cups: Vec<Cup>,
+    }
+
+    let mut dbm = test_database();
+
+    let cups = indoc::indoc! {"
+    ---
+    cups:
+      - \"name: first_cup\\nmaterial:\\n  name: steel\"
+      - \"name: second_cup\\nmaterial:\\n  name: wood\"
+    "};
+
+    let cups = dbm.from_str::<Cups, SerdeYaml>(&cups).unwrap();
+    assert_eq!(cups.cups[0].material.id, 2);
+    assert_eq!(cups.cups[1].material.name, "wood");
+
+    // Without entering the handle, the link can't be resolved on another thread
+    let err = std::thread::spawn(|| {
+        serde_yaml::from_str::<Cup>("name: first_cup\nmaterial:\n  name: steel").unwrap_err()
+    })
+    .join()
+    .unwrap();
+    assert!(err.to_string().contains("No database manager has been set"));
+}
+
+#[test]
+fn test_read_from_str_link_versions() {
+    #[derive(Deserialize)]
+    struct Shelf {
+        #[serde(deserialize_with = "deserialize_link")]
+        shovel: Shovel,
+    }
+
+    let mut dbm = test_database();
+
+    // Links from a newer version may contain additional entries
+    let shelf = indoc::indoc! {"
+    ---
+    shovel:
+      name: Georgs_shovel
+      version: 3
+      modified: 2026-01-01
+    "};
+    let shelf = dbm.from_str::<Shelf, SerdeYaml>(&shelf).unwrap();
+    assert_eq!(shelf.shovel.name, "Georgs_shovel");
+
+    // An explicit version 1 is accepted as well
+    let shelf = indoc::indoc! {"
+    ---
+    shovel:
+      name: Georgs_shovel
+      version: 1
+    "};
+    let shelf = dbm.from_str::<Shelf, SerdeYaml>(&shelf).unwrap();
+    assert_eq!(shelf.shovel.name, "Georgs_shovel");
+
+    // Unknown entries in a link of a known version are rejected
+    let shelf = indoc::indoc! {"
+    ---
+    shovel:
+      name: Georgs_shovel
+      modified: 2026-01-01
+    "};
+    assert!(dbm.from_str::<Shelf, SerdeYaml>(&shelf).is_err());
+}
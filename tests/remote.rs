@@ -0,0 +1,99 @@
+#![cfg(feature = "remote")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+
+use serde_mosaic::*;
+
+mod utilities;
+use utilities::*;
+
+/**
+Answers one request per given response and returns the received requests.
+ */
+fn stub_server(responses: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/db/", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for response in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(&stream);
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(length) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = length.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8(body).unwrap());
+            (&stream).write_all(response.as_bytes()).unwrap();
+            requests.push(request);
+        }
+        return requests;
+    });
+    return (url, handle);
+}
+
+fn response(status: &str, headers: &str, body: &str) -> String {
+    return format!(
+        "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+}
+
+#[test]
+fn test_remote_database_client() {
+    let cup = Cup {
+        name: "my cup".into(),
+        material: Material {
+            id: 1,
+            name: "clay".into(),
+        },
+    };
+    let serialized = String::from_utf8(SerdeYaml.serialize_dyn(&cup).unwrap()).unwrap();
+
+    let (url, server) = stub_server(vec![
+        response("200 OK", "X-Mosaic-Checksum: 42\r\n", &serialized),
+        response("412 Precondition Failed", "", ""),
+        response("201 Created", "X-Mosaic-Checksum: 43\r\n", ""),
+        response("404 Not Found", "", ""),
+        response("200 OK", "", "Cup\nMaterial%20Types\n"),
+        response("204 No Content", "", ""),
+    ]);
+    let remote = RemoteDatabase::new(&url, SerdeYaml).unwrap();
+    assert_eq!(remote.url(), url.trim_end_matches('/'));
+
+    assert_eq!(remote.read::<Cup, _>("my cup").unwrap(), cup);
+    let err = remote.write(&cup, Precondition::Checksum(41)).unwrap_err();
+    assert!(err.to_string().starts_with("Precondition failed"));
+    assert_eq!(remote.write(&cup, Precondition::Absent).unwrap(), 43);
+    assert_eq!(remote.checksum(("Cup", "other cup")).unwrap(), None);
+    assert_eq!(remote.type_names().unwrap(), vec!["Cup", "Material Types"]);
+    assert!(
+        remote
+            .remove(("Cup", "my cup"), Precondition::None)
+            .unwrap()
+    );
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("GET /db/Cup/my%20cup?resolve HTTP/1.1\r\n"));
+    assert!(requests[1].starts_with("PUT /db/Cup/my%20cup HTTP/1.1\r\n"));
+    assert!(requests[1].contains("If-Match: 41\r\n"));
+    assert!(requests[1].ends_with(&serialized));
+    assert!(requests[2].contains("If-None-Match: *\r\n"));
+    assert!(requests[3].starts_with("HEAD /db/Cup/other%20cup HTTP/1.1\r\n"));
+    assert!(requests[4].starts_with("GET /db/ HTTP/1.1\r\n"));
+    assert!(requests[5].starts_with("DELETE /db/Cup/my%20cup HTTP/1.1\r\n"));
+
+    assert!(RemoteDatabase::new("https://example.com", SerdeYaml).is_err());
+}
@@ -0,0 +1,335 @@
+#![cfg(feature = "server")]
+
+use std::any::Any;
+
+use serde_mosaic::*;
+
+mod utilities;
+use utilities::*;
+
+fn cup(name: &str, material_name: &str) -> Cup {
+    return Cup {
+        name: name.into(),
+        material: Material {
+            id: 1,
+            name: material_name.into(),
+        },
+    };
+}
+
+fn request(method: &str, path: &str, headers: &[(&str, &str)], body: &[u8]) -> ServerRequest {
+    let mut request = ServerRequest::new(method, path);
+    request.headers = headers
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    request.body = body.to_vec();
+    return request;
+}
+
+fn checksum_header(response: &ServerResponse) -> Option<u32> {
+    return response
+        .headers
+        .iter()
+        .find(|(name, _)| name == "X-Mosaic-Checksum")
+        .and_then(|(_, value)| value.parse().ok());
+}
+
+#[test]
+fn test_server_read_only() {
+    let mut dbm = scratch_database("server_read_only");
+    dbm.write(&cup("my cup", "clay"), &WriteOptions::default())
+        .unwrap();
+    let file_contents = std::fs::read(dbm.full_path(("Cup", "my cup")).unwrap()).unwrap();
+    let file_checksum = dbm.checksum(("Cup", "my cup"));
+    let server = DatabaseServer::new(dbm, ServerOptions::default());
+
+    let response = server.handle(&request("GET", "/", &[], b""));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"Cup\nMaterial\n");
+
+    let response = server.handle(&request("GET", "/Cup/", &[], b""));
+    assert_eq!(response.body, b"my%20cup\n");
+    assert_eq!(
+        server.handle(&request("GET", "/Bowl/", &[], b"")).status,
+        404
+    );
+
+    // Raw representation
+    let response = server.handle(&request("GET", "/Cup/my%20cup", &[], b""));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, file_contents);
+    assert_eq!(checksum_header(&response), file_checksum);
+
+    // Link-resolved representation
+    let response = server.handle(&request("GET", "/Cup/my%20cup?resolve", &[], b""));
+    assert_eq!(response.status, 200);
+    let entry = SerdeYaml.deserialize_dyn(&response.body).unwrap() as Box<dyn Any>;
+    assert_eq!(*entry.downcast::<Cup>().unwrap(), cup("my cup", "clay"));
+
+    let response = server.handle(&request("HEAD", "/Cup/my%20cup", &[], b""));
+    assert_eq!(response.status, 200);
+    assert!(response.body.is_empty());
+    assert_eq!(checksum_header(&response), file_checksum);
+
+    assert_eq!(
+        server
+            .handle(&request("GET", "/Cup/other", &[], b""))
+            .status,
+        404
+    );
+    assert_eq!(
+        server
+            .handle(&request("GET", "/Cup/..%2F..%2Fsecret", &[], b""))
+            .status,
+        404
+    );
+    assert_eq!(
+        server.handle(&request("GET", "/Cup/%ZZ", &[], b"")).status,
+        400
+    );
+
+    // Writing is not allowed
+    assert_eq!(
+        server
+            .handle(&request("PUT", "/Cup/my%20cup", &[], b""))
+            .status,
+        405
+    );
+    assert_eq!(
+        server
+            .handle(&request("DELETE", "/Cup/my%20cup", &[], b""))
+            .status,
+        405
+    );
+    assert!(server.database_manager().exists(("Cup", "my cup")));
+}
+
+#[test]
+fn test_server_writable() {
+    let mut dbm = scratch_database("server_writable");
+    dbm.write(&cup("my cup", "clay"), &WriteOptions::default())
+        .unwrap();
+    let server = DatabaseServer::new(
+        dbm,
+        ServerOptions {
+            writable: true,
+            authorizer: Some(Authorizer::new(|request, access| {
+                return access == Access::Read
+                    || request.header("Authorization") == Some("Bearer 1234");
+            })),
+            ..Default::default()
+        },
+    );
+    let token = ("Authorization", "Bearer 1234");
+    let material = Material {
+        id: 2,
+        name: "porcelain".into(),
+    };
+    let body = SerdeYaml.serialize_dyn(&material).unwrap();
+
+    assert_eq!(
+        server
+            .handle(&request("PUT", "/Material/porcelain", &[], &body))
+            .status,
+        403
+    );
+    let response = server.handle(&request("PUT", "/Material/porcelain", &[token], &body));
+    assert_eq!(response.status, 201);
+    let written_checksum = checksum_header(&response).unwrap();
+    assert_eq!(
+        server
+            .database_manager_mut()
+            .read::<Material, _>("porcelain")
+            .unwrap(),
+        material
+    );
+
+    // Preconditions
+    let response = server.handle(&request(
+        "PUT",
+        "/Material/porcelain",
+        &[token, ("If-None-Match", "*")],
+        &body,
+    ));
+    assert_eq!(response.status, 412);
+    let wrong_checksum = (written_checksum + 1).to_string();
+    let response = server.handle(&request(
+        "DELETE",
+        "/Material/porcelain",
+        &[token, ("If-Match", &wrong_checksum)],
+        b"",
+    ));
+    assert_eq!(response.status, 412);
+
+    let response = server.handle(&request(
+        "DELETE",
+        "/Material/porcelain",
+        &[token, ("If-Match", &written_checksum.to_string())],
+        b"",
+    ));
+    assert_eq!(response.status, 204);
+    assert!(!server.database_manager().exists(("Material", "porcelain")));
+    assert_eq!(
+        server
+            .handle(&request("DELETE", "/Material/porcelain", &[token], b""))
+            .status,
+        404
+    );
+}
+
+#[test]
+fn test_server_rejects_invalid_entries() {
+    let mut dbm = scratch_database("server_rejects_invalid_entries");
+    dbm.write(&cup("my cup", "clay"), &WriteOptions::default())
+        .unwrap();
+    let server = DatabaseServer::new(
+        dbm,
+        ServerOptions {
+            writable: true,
+            max_body_bytes: 1024,
+            ..Default::default()
+        },
+    );
+    let material = Material {
+        id: 2,
+        name: "porcelain".into(),
+    };
+    let body = SerdeYaml.serialize_dyn(&material).unwrap();
+
+    // Unparseable body, wrong type tag and wrong name
+    let response = server.handle(&request("PUT", "/Material/porcelain", &[], b"[:"));
+    assert_eq!(response.status, 400);
+    let response = server.handle(&request("PUT", "/Cup/porcelain", &[], &body));
+    assert_eq!(response.status, 400);
+    let response = server.handle(&request("PUT", "/Material/glass", &[], &body));
+    assert_eq!(response.status, 400);
+    assert!(!server.database_manager().exists(("Material", "glass")));
+
+    // Types without type folder
+    let response = server.handle(&request("PUT", "/Shovel/porcelain", &[], &body));
+    assert_eq!(response.status, 404);
+
+    // Too large bodies
+    let response = server.handle(&request("PUT", "/Material/porcelain", &[], &[b' '; 2048]));
+    assert_eq!(response.status, 413);
+
+    let response = server.handle(&request("PUT", "/Material/porcelain", &[], &body));
+    assert_eq!(response.status, 201);
+}
+
+#[test]
+fn test_server_connection_limits() {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+
+    let dbm = scratch_database("server_connection_limits");
+    let server = Arc::new(DatabaseServer::new(
+        dbm,
+        ServerOptions {
+            writable: true,
+            authorizer: Some(Authorizer::new(|request, _| {
+                return request.header("Authorization") == Some("Bearer 1234");
+            })),
+            max_body_bytes: 1024,
+            timeout: Some(std::time::Duration::from_secs(5)),
+            max_connections: 1,
+            ..Default::default()
+        },
+    ));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    {
+        let server = server.clone();
+        std::thread::spawn(move || server.serve(listener));
+    }
+
+    // The responses are sent without waiting for the announced bodies
+    let send = |head: &str| {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(head.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        return response;
+    };
+    let response = send("PUT /Material/steel HTTP/1.1\r\nContent-Length: 100\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    let response = send(
+        "PUT /Material/steel HTTP/1.1\r\nAuthorization: Bearer 1234\r\nContent-Length: 1000000000\r\n\r\n",
+    );
+    assert!(response.starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+    let response = send(
+        "PUT /Material/steel HTTP/1.1\r\nAuthorization: Bearer 1234\r\nTransfer-Encoding: chunked\r\n\r\n800\r\n",
+    );
+    assert!(response.starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+
+    // A stalled connection occupies the only slot until it times out
+    let _stalled = TcpStream::connect(address).unwrap();
+    let start = std::time::Instant::now();
+    let response = send("GET / HTTP/1.1\r\nAuthorization: Bearer 1234\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(start.elapsed() >= std::time::Duration::from_secs(4));
+}
+
+#[cfg(feature = "remote")]
+#[test]
+fn test_server_with_remote_client() {
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    let mut dbm = scratch_database("server_with_remote_client");
+    dbm.write(&cup("my cup", "clay"), &WriteOptions::default())
+        .unwrap();
+    let options = ServerOptions {
+        writable: true,
+        ..Default::default()
+    };
+    let server = Arc::new(DatabaseServer::new(dbm, options));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    {
+        let server = server.clone();
+        std::thread::spawn(move || server.serve(listener));
+    }
+
+    let remote = RemoteDatabase::new(&url, SerdeYaml).unwrap();
+    assert_eq!(remote.type_names().unwrap(), vec!["Cup", "Material"]);
+    assert_eq!(
+        remote.read::<Cup, _>("my cup").unwrap(),
+        cup("my cup", "clay")
+    );
+
+    // Linked entries are embedded when writing via the client
+    let checksum = remote
+        .write(&cup("new cup", "glass"), Precondition::Absent)
+        .unwrap();
+    assert!(
+        remote
+            .write(&cup("new cup", "glass"), Precondition::Absent)
+            .is_err()
+    );
+    assert_eq!(remote.checksum(("Cup", "new cup")).unwrap(), Some(checksum));
+    assert_eq!(
+        remote.entry_names("Cup").unwrap(),
+        vec!["my cup", "new cup"]
+    );
+    assert_eq!(
+        server
+            .database_manager_mut()
+            .read::<Cup, _>("new cup")
+            .unwrap(),
+        cup("new cup", "glass")
+    );
+    assert!(!server.database_manager().exists(("Material", "glass")));
+
+    let raw = remote.get_raw(("Cup", "new cup")).unwrap().unwrap();
+    assert_eq!(raw.checksum, Some(checksum));
+    assert!(
+        remote
+            .remove(("Cup", "new cup"), Precondition::Checksum(checksum))
+            .unwrap()
+    );
+    assert!(!remote.exists(("Cup", "new cup")).unwrap());
+    assert!(remote.get_raw(("Cup", "new cup")).unwrap().is_none());
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use serde_mosaic::*;
+
+mod utilities;
+use utilities::*;
+
+#[test]
+fn test_shared_read_write_remove() {
+    let dbm = SharedDatabaseManager::new(scratch_database("shared_read_write_remove"));
+    let shovel = Shovel {
+        name: "shared_shovel".into(),
+        shaft: Arc::new(Material {
+            id: 1,
+            name: "hickory".into(),
+        }),
+        blade: Material {
+            id: 2,
+            name: "carbon_steel".into(),
+        },
+    };
+    dbm.write(&shovel, &WriteOptions::default()).unwrap();
+    assert!(dbm.exists(&shovel));
+    assert_eq!(dbm.list::<Shovel>().unwrap(), ["shared_shovel"]);
+
+    // Concurrent reads put their cached instances into the shared cache
+    let shovels: Vec<Shovel> = thread::scope(|scope| {
+        let reads: Vec<_> = (0..4)
+            .map(|_| scope.spawn(|| dbm.read::<Shovel, _>("shared_shovel")))
+            .collect();
+        return reads
+            .into_iter()
+            .map(|read| read.join().unwrap().unwrap())
+            .collect();
+    });
+    for read in shovels.iter() {
+        assert_eq!(*read, shovel);
+    }
+    let shared_cache = dbm.database_manager().shared_cache().unwrap().clone();
+    assert_eq!(shared_cache.read().len(), 1);
+
+    // Subsequent reads share the cached shaft
+    let first: Shovel = dbm.read("shared_shovel").unwrap();
+    let second: Shovel = dbm.read("shared_shovel").unwrap();
+    assert!(Arc::ptr_eq(&first.shaft, &second.shaft));
+
+    dbm.remove(&shovel).unwrap();
+    assert!(dbm.read::<Shovel, _>("shared_shovel").is_err());
+}
+
+#[test]
+fn test_shared_read_during_write() {
+    let dbm = SharedDatabaseManager::new(scratch_database("shared_read_during_write"));
+    let shovel = |id: usize| Shovel {
+        name: "busy_shovel".into(),
+        shaft: Arc::new(Material {
+            id,
+            name: "ash".into(),
+        }),
+        blade: Material {
+            id,
+            name: "spring_steel".into(),
+        },
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&shovel(0), &write_options).unwrap();
+
+    // Reads running concurrently to the writes always succeed
+    let done = AtomicBool::new(false);
+    thread::scope(|scope| {
+        let reads: Vec<_> = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    while !done.load(Ordering::Relaxed) {
+                        let read: Shovel = dbm.read("busy_shovel").unwrap();
+                        assert!(read.shaft.id <= 20);
+                    }
+                })
+            })
+            .collect();
+        for id in 1..=20 {
+            dbm.write(&shovel(id), &write_options).unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+        for read in reads {
+            read.join().unwrap();
+        }
+    });
+
+    // Stale instances cached by the reads are not returned
+    let read: Shovel = dbm.read("busy_shovel").unwrap();
+    assert_eq!(read, shovel(20));
+    let cached = dbm
+        .database_manager()
+        .cache_get::<Material>("ash")
+        .expect("shaft is cached");
+    assert!(Arc::ptr_eq(&cached, &read.shaft));
+}
+
+#[test]
+fn test_shared_cache_capacity() {
+    let mut dbm = scratch_database("shared_cache_capacity");
+    for name in ["spade", "scoop"] {
+        let shovel = Shovel {
+            name: name.into(),
+            shaft: Arc::new(Material {
+                id: 1,
+                name: format!("{name}_shaft"),
+            }),
+            blade: Material {
+                id: 2,
+                name: "steel".into(),
+            },
+        };
+        dbm.write(&shovel, &WriteOptions::default()).unwrap();
+    }
+    dbm.set_cache_capacity(CacheCapacity::Entries(1));
+    let dbm = SharedDatabaseManager::new(dbm);
+
+    // Every read runs on a clone, but the usage is tracked by the shared cache
+    let _: Shovel = dbm.read("spade").unwrap();
+    let _: Shovel = dbm.read("spade").unwrap();
+    let stats = dbm.database_manager().cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 1, 0));
+
+    // The shaft of the spade is evicted in favour of the shaft of the scoop
+    thread::scope(|scope| {
+        scope.spawn(|| dbm.read::<Shovel, _>("scoop").unwrap());
+    });
+    let stats = dbm.database_manager().cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 2, 1));
+    assert_eq!(stats.entries, 1);
+    assert!(
+        dbm.database_manager()
+            .cache_get::<Material>("scoop_shaft")
+            .is_some()
+    );
+    assert!(
+        dbm.database_manager()
+            .cache_get::<Material>("spade_shaft")
+            .is_none()
+    );
+
+    dbm.database_manager_mut().reset_cache_stats();
+    let stats = dbm.database_manager().cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (0, 0, 0));
+    assert_eq!(stats.entries, 1);
+}
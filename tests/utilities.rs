@@ -9,7 +9,7 @@ use std::{ffi::OsStr, path::Path, sync::Arc};
 use serde::{Deserialize, Serialize};
 use serde_mosaic::*;
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Material {
     pub id: usize,
@@ -131,7 +131,51 @@ impl DatabaseEntry for Cupboard {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Rack {
+    pub name: String,
+    #[serde(with = "serde_mosaic::with::linked")]
+    pub cup: Linked<Cup>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Rack {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Platter {
+    pub name: String,
+    #[serde(with = "serde_mosaic::with::link_or_default")]
+    pub material: Material,
+    #[serde(with = "serde_mosaic::with::opt_link_or_default")]
+    pub cup: Option<Cup>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Platter {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
 pub fn test_database() -> DatabaseManager {
     let path_db = "tests/test_database";
     return DatabaseManager::open(Path::new(path_db).to_path_buf(), SerdeYaml).unwrap();
 }
+
+/**
+Creates an empty database in the temporary directory of the system. Tests which
+inspect the entire database use this instead of [`test_database`] to avoid
+interference with tests running in parallel.
+ */
+pub fn scratch_database(name: &str) -> DatabaseManager {
+    let path = std::env::temp_dir().join("serde_mosaic_tests").join(name);
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+    return DatabaseManager::open(path, SerdeYaml).unwrap();
+}
@@ -0,0 +1,334 @@
+use std::sync::Arc;
+
+use serde_mosaic::*;
+
+mod utilities;
+use utilities::*;
+
+fn hanks_user() -> User {
+    return User {
+        name: "Hank".into(),
+        shovel: Arc::new(Shovel {
+            name: "Hanks_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 4,
+                name: "Hanks_birch".into(),
+            }),
+            blade: Material {
+                id: 5,
+                name: "Hanks_alloy".into(),
+            },
+        }),
+    };
+}
+
+#[test]
+fn test_verify_entry_valid() {
+    let mut dbm = scratch_database("verify_entry_valid");
+    let user = hanks_user();
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+
+    let verification = dbm.verify_entry(&user);
+    assert!(verification.is_valid());
+    assert!(verification.report().is_clean());
+
+    // Root first, then breadth-first
+    let keys: Vec<String> = verification
+        .files
+        .iter()
+        .map(|file| file.key.to_string())
+        .collect();
+    assert_eq!(
+        keys,
+        vec![
+            "User/Hank",
+            "Shovel/Hanks_shovel",
+            "Material/Hanks_birch",
+            "Material/Hanks_alloy"
+        ]
+    );
+    assert_eq!(verification.files[1].links.len(), 2);
+
+    // A cup with the same name as the blade makes the lookup ambiguous, but the
+    // checksum within the link still identifies the blade.
+    let cup = Cup {
+        name: "Hanks_alloy".into(),
+        material: Material {
+            id: 1,
+            name: "ceramic".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+    assert!(dbm.verify_entry(&user).is_valid());
+}
+
+#[test]
+fn test_verify_entry_problems() {
+    let mut dbm = scratch_database("verify_entry_problems");
+    let user = hanks_user();
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+
+    // Edit the shaft and corrupt the blade
+    let shaft_path = dbm.full_path(&*user.shovel.shaft).unwrap();
+    let contents = std::fs::read_to_string(&shaft_path).unwrap();
+    std::fs::write(&shaft_path, contents.replace("id: 4", "id: 7")).unwrap();
+    let blade_path = dbm.full_path(&user.shovel.blade).unwrap();
+    std::fs::write(&blade_path, "Material: [unclosed").unwrap();
+
+    let verification = dbm.verify_entry(&user);
+    assert!(!verification.is_valid());
+
+    let shovel = &verification.files[1];
+    assert_eq!(shovel.status, FileStatus::Valid);
+    assert!(shovel.links[0].is_checksum_mismatch());
+    assert!(shovel.links[1].is_checksum_mismatch());
+    assert_eq!(
+        shovel.links[0].target,
+        LinkTarget::Resolved(DatabaseKeyBuf::new("Material", "Hanks_birch"))
+    );
+
+    let blade = &verification.files[3];
+    assert_eq!(blade.path, blade_path);
+    assert!(matches!(blade.status, FileStatus::Unparseable(_)));
+
+    let report = verification.report();
+    assert_eq!(report.problems.len(), 3);
+    assert_eq!(report.max_severity(), Some(Severity::Error));
+    assert_eq!(report.with_severity(Severity::Warning).count(), 2);
+    assert_eq!(
+        report.problems[0],
+        Problem::ChecksumMismatch {
+            path: shovel.path.clone(),
+            linked_path: shaft_path.clone(),
+            checksum_in_link: shovel.links[0].checksum_in_link.unwrap(),
+            checksum_of_file: dbm.checksum(&*user.shovel.shaft).unwrap(),
+        }
+    );
+    assert_eq!(
+        report.problems[0].suggested_fix(),
+        SuggestedFix::HealChecksums
+    );
+    assert_eq!(report.problems[2].path(), blade_path.as_path());
+    assert_eq!(
+        report.problems[2].suggested_fix(),
+        SuggestedFix::RepairManually
+    );
+    assert!(
+        report
+            .to_string()
+            .starts_with("warning: checksum mismatch in")
+    );
+
+    // Removing the blade results in a dangling link
+    dbm.remove(&user.shovel.blade).unwrap();
+    let verification = dbm.verify_entry(&user);
+    assert_eq!(verification.files.len(), 3);
+    assert_eq!(verification.files[1].links[1].target, LinkTarget::Dangling);
+    assert!(
+        verification
+            .report()
+            .problems
+            .contains(&Problem::DanglingLink {
+                path: verification.files[1].path.clone(),
+                link: "Hanks_alloy".into(),
+            })
+    );
+
+    // Verifying a missing entry
+    let verification = dbm.verify_entry(("User", "nobody"));
+    assert_eq!(verification.files.len(), 1);
+    assert_eq!(verification.files[0].status, FileStatus::Missing);
+    assert!(matches!(
+        verification.report().problems.as_slice(),
+        [Problem::Missing { .. }]
+    ));
+}
+
+#[test]
+fn test_validate() {
+    let mut dbm = scratch_database("validate");
+    let user = hanks_user();
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+
+    let validation = dbm.validate::<User, _>("Hank");
+    assert!(validation.is_valid());
+    assert!(validation.report().is_clean());
+
+    // Removing a field keeps the blade parseable, but it can't be read anymore
+    let blade_path = dbm.full_path(&user.shovel.blade).unwrap();
+    let contents = std::fs::read_to_string(&blade_path).unwrap();
+    std::fs::write(&blade_path, contents.replace("id: 5\n", "")).unwrap();
+
+    let validation = dbm.validate::<User, _>("Hank");
+    assert!(!validation.is_valid());
+    assert_eq!(validation.verification.files[3].status, FileStatus::Valid);
+    let err = validation.read.as_ref().unwrap_err();
+    assert_eq!(err.path, blade_path);
+    assert!(err.message.contains("id"));
+
+    let report = validation.report();
+    assert_eq!(report.problems.len(), 2);
+    assert!(matches!(
+        report.problems[0],
+        Problem::ChecksumMismatch { .. }
+    ));
+    assert!(matches!(
+        &report.problems[1],
+        Problem::Unparseable { path, .. } if *path == blade_path
+    ));
+
+    // A missing entry is only reported once
+    let validation = dbm.validate::<User, _>("nobody");
+    assert_eq!(
+        validation.read.as_ref().unwrap_err().kind,
+        std::io::ErrorKind::NotFound
+    );
+    assert!(matches!(
+        validation.report().problems.as_slice(),
+        [Problem::Missing { .. }]
+    ));
+}
+
+#[test]
+fn test_verify_all() {
+    let mut dbm = scratch_database("verify_all");
+    let user = hanks_user();
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+    assert!(dbm.verify_all().unwrap().is_clean());
+
+    // The blade can be parsed, but not deserialized into a Material
+    let blade_path = dbm.full_path(&user.shovel.blade).unwrap();
+    let contents = std::fs::read_to_string(&blade_path).unwrap();
+    std::fs::write(&blade_path, contents.replace("id: 5\n", "")).unwrap();
+
+    // Entries of types unknown to the application can't be deserialized
+    let spoon_path = dbm.dir().join("Spoon/silver.yaml");
+    std::fs::create_dir(dbm.dir().join("Spoon")).unwrap();
+    std::fs::write(&spoon_path, "Spoon:\n  name: silver\n").unwrap();
+    std::fs::write(dbm.dir().join("notes.txt"), "stray").unwrap();
+
+    let report = dbm.verify_all().unwrap();
+    assert_eq!(report.problems.len(), 4);
+    assert!(matches!(
+        &report.problems[0],
+        Problem::Unparseable { path, .. } if *path == blade_path
+    ));
+    assert!(matches!(
+        &report.problems[1],
+        Problem::Unparseable { path, .. } if *path == spoon_path
+    ));
+    assert!(matches!(
+        report.problems[2],
+        Problem::ChecksumMismatch { .. }
+    ));
+    assert!(matches!(report.problems[3], Problem::StrayFile { .. }));
+}
+
+#[cfg(feature = "signatures")]
+#[test]
+fn test_signatures() {
+    let mut dbm = scratch_database("signatures");
+    let secret_key = [3; 32];
+    dbm.set_signatures(Some(Signatures::new().sign_with(secret_key)));
+
+    let cup = Cup {
+        name: "daves_cup".into(),
+        material: Material {
+            id: 1,
+            name: "ceramic".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+    let material_path = dbm.full_path(&cup.material).unwrap();
+    assert!(material_path.with_extension("yaml.sig").exists());
+
+    // Verify with the public key only
+    let mut dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    dbm.set_signatures(Some(
+        Signatures::new()
+            .trust(Signatures::public_key_of(secret_key))
+            .unwrap(),
+    ));
+    let (_, read_info) = dbm.read_verbose::<Cup, _>("daves_cup").unwrap();
+    assert!(read_info.signature_problems.is_empty());
+    assert!(dbm.verify_entry(&cup).is_valid());
+
+    // Tamper with the material
+    let contents = std::fs::read_to_string(&material_path).unwrap();
+    std::fs::write(&material_path, contents.replace("id: 1", "id: 2")).unwrap();
+    let (_, read_info) = dbm.read_verbose::<Cup, _>("daves_cup").unwrap();
+    assert_eq!(
+        read_info.signature_problems,
+        vec![SignatureProblem {
+            file_path: material_path.clone(),
+            status: SignatureStatus::Invalid
+        }]
+    );
+    let verification = dbm.verify_entry(&cup);
+    assert!(!verification.is_valid());
+    assert!(
+        verification
+            .report()
+            .problems
+            .contains(&Problem::UnverifiedSignature {
+                path: material_path.clone(),
+                status: SignatureStatus::Invalid
+            })
+    );
+
+    // Unknown keys are not trusted and unverified files can be rejected
+    dbm.set_signatures(Some(
+        Signatures::new()
+            .sign_with([4; 32])
+            .policy(SignaturePolicy::Require),
+    ));
+    let err = dbm.read::<Cup, _>("daves_cup").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    // Removing an entry removes its signature as well
+    dbm.remove(&cup.material).unwrap();
+    assert!(!material_path.with_extension("yaml.sig").exists());
+}
+
+#[test]
+fn test_validate_layout() {
+    let mut dbm = scratch_database("validate_layout");
+    let user = hanks_user();
+    dbm.write(&user, &WriteOptions::default()).unwrap();
+    let (dbm, report) = DatabaseManager::open_validated(dbm.dir(), SerdeYaml).unwrap();
+    assert!(report.is_clean());
+
+    let dir = dbm.dir().to_path_buf();
+    std::fs::write(dir.join("notes.txt"), "stray").unwrap();
+    std::fs::create_dir(dir.join("material")).unwrap();
+    std::fs::write(dir.join("material/Hanks_birch.yaml"), "Material: {}").unwrap();
+    std::fs::write(dir.join("Material/Hanks_alloy.json"), "{}").unwrap();
+    std::fs::write(dir.join("Material/hanks_alloy.yaml"), "Material: {}").unwrap();
+    std::fs::create_dir(dir.join("Material/nested")).unwrap();
+
+    let report = dbm.validate_layout().unwrap();
+    assert_eq!(
+        report.problems,
+        vec![
+            Problem::StrayFile {
+                path: dir.join("notes.txt")
+            },
+            Problem::CaseDuplicateFolders {
+                paths: vec![dir.join("Material"), dir.join("material")]
+            },
+            Problem::UnknownExtension {
+                path: dir.join("Material/Hanks_alloy.json")
+            },
+            Problem::StrayFile {
+                path: dir.join("Material/nested")
+            },
+            Problem::NameCollision {
+                paths: vec![
+                    dir.join("Material/Hanks_alloy.yaml"),
+                    dir.join("Material/hanks_alloy.yaml")
+                ]
+            },
+        ]
+    );
+    assert_eq!(report.max_severity(), Some(Severity::Warning));
+}
@@ -5,11 +5,28 @@ use std::{
     sync::Arc,
 };
 
+use serde::{Deserialize, Serialize};
 use serde_mosaic::*;
 
 mod utilities;
 use utilities::*;
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Gauge {
+    name: String,
+    readings: Vec<f64>,
+    #[serde(deserialize_with = "deserialize_link")]
+    #[serde(serialize_with = "serialize_link")]
+    material: Material,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Gauge {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
 #[test]
 fn test_write_flat() {
     let name = "test_write_flat";
@@ -90,16 +107,22 @@ fn test_write_alias() {
         },
     };
 
-    let mut alias: HashMap<OsString, OsString> = HashMap::new();
+    let mut alias: HashMap<DatabaseKeyBuf, OsString> = HashMap::new();
     alias.insert(
-        OsStr::new("aarons_cup").to_os_string(),
+        DatabaseKeyBuf::new(type_name::<Cup>(), "aarons_cup"),
         OsStr::new("sarahs_cup").to_os_string(),
     );
     alias.insert(
-        OsStr::new("meissner").to_os_string(),
+        DatabaseKeyBuf::new(type_name::<Material>(), "meissner"),
         OsStr::new("china").to_os_string(),
     );
 
+    // Aliases only apply to entries of the given type
+    alias.insert(
+        DatabaseKeyBuf::new(type_name::<Material>(), "aarons_cup"),
+        OsStr::new("unused").to_os_string(),
+    );
+
     let mut write_options = WriteOptions::default();
     write_options.write_mode = WriteMode::Link;
     write_options.name_collisions = NameCollisions::Overwrite;
@@ -128,6 +151,119 @@ fn test_write_alias() {
     let _ = dbm.remove((type_name::<Material>(), "china"));
 }
 
+#[test]
+fn test_write_renamer() {
+    let mut dbm = scratch_database("write_renamer");
+
+    let cup = Cup {
+        name: "aarons_cup".to_string(),
+        material: Material {
+            id: 2,
+            name: "meissner".to_string(),
+        },
+    };
+
+    let mut write_options = WriteOptions::default();
+    write_options.alias.insert(
+        DatabaseKeyBuf::new(type_name::<Cup>(), "aarons_cup"),
+        "sarahs_cup".into(),
+    );
+    write_options.renamer = Some(Renamer::new(|key| {
+        let mut name = OsString::from("2024_");
+        name.push(key.name);
+        return Some(name);
+    }));
+    dbm.write(&cup, &write_options).unwrap();
+
+    // The alias takes precedence over the renamer
+    assert!(dbm.exists((type_name::<Cup>(), "sarahs_cup")));
+    assert!(dbm.exists((type_name::<Material>(), "2024_meissner")));
+    assert!(!dbm.exists(&cup.material));
+
+    // The link points to the renamed file
+    let read_cup: Cup = dbm.read("sarahs_cup").unwrap();
+    assert_eq!(read_cup.material, cup.material);
+}
+
+#[test]
+fn test_write_alias_collision() {
+    let mut dbm = scratch_database("write_alias_collision");
+
+    let oak = Arc::new(Material {
+        id: 1,
+        name: "oak".to_string(),
+    });
+    let pine = Arc::new(Material {
+        id: 2,
+        name: "pine".to_string(),
+    });
+    let stool = Stool {
+        name: "milking_stool".to_string(),
+        leg_1: oak.clone(),
+        leg_2: oak.clone(),
+        leg_3: pine.clone(),
+        seat: pine,
+    };
+
+    // Without aliases, the same entry may be linked multiple times
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&stool, &write_options).unwrap();
+
+    // An alias target which collides with a non-aliased name is rejected
+    write_options
+        .alias
+        .insert(DatabaseKeyBuf::new("Material", "pine"), "oak".into());
+    let err = dbm.write(&stool, &write_options).unwrap_err();
+    assert!(err.to_string().contains("Alias collision"));
+    assert!(err.to_string().contains("oak"));
+    assert!(err.to_string().contains("pine"));
+
+    // The same is true for two aliases with the same target
+    write_options
+        .alias
+        .insert(DatabaseKeyBuf::new("Material", "oak"), "wood".into());
+    write_options
+        .alias
+        .insert(DatabaseKeyBuf::new("Material", "pine"), "wood".into());
+    let err = dbm.write(&stool, &write_options).unwrap_err();
+    assert!(err.to_string().contains("Alias collision"));
+    assert_eq!(dbm.read::<Material, _>("oak").unwrap(), *oak);
+}
+
+#[test]
+fn test_write_with_profile() {
+    let mut dbm = scratch_database("write_with_profile");
+
+    let mut import = WriteOptions::default();
+    import.name_collisions = NameCollisions::KeepExisting;
+    assert!(dbm.set_write_profile("import", import).is_none());
+    let mut regenerate = WriteOptions::default();
+    regenerate.name_collisions = NameCollisions::Overwrite;
+    dbm.set_write_profile("regenerate", regenerate);
+
+    let mut material = Material {
+        id: 1,
+        name: "steel".to_string(),
+    };
+    dbm.write_with_profile(&material, "import").unwrap();
+
+    // The import profile keeps the existing file ...
+    material.id = 2;
+    dbm.write_with_profile(&material, "import").unwrap();
+    assert_eq!(dbm.read::<Material, _>("steel").unwrap().id, 1);
+
+    // ... while the regenerate profile overwrites it
+    dbm.write_with_profile(&material, "regenerate").unwrap();
+    assert_eq!(dbm.read::<Material, _>("steel").unwrap().id, 2);
+
+    // Unknown profiles are rejected
+    assert!(dbm.remove_write_profile("regenerate").is_some());
+    assert!(dbm.write_profile("regenerate").is_none());
+    let err = dbm.write_with_profile(&material, "regenerate").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
 #[test]
 fn test_write_wo_overwrite() {
     let material = Material {
@@ -177,6 +313,324 @@ fn test_write_wo_overwrite() {
     assert!(!file_path_2.exists());
 }
 
+#[test]
+fn test_write_adjust_name_high_water_mark() {
+    let mut dbm = scratch_database("write_adjust_name_high_water_mark");
+    let material = Material {
+        id: 3,
+        name: "steel".to_string(),
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::AdjustName;
+
+    for _ in 0..3 {
+        dbm.write(&material, &write_options).unwrap();
+    }
+    assert!(dbm.exists((type_name::<Material>(), "steel_1")));
+
+    // Gaps are not filled
+    dbm.remove((type_name::<Material>(), "steel_0")).unwrap();
+    let file_path = dbm.write(&material, &write_options).unwrap();
+    assert_eq!(file_path.file_name().unwrap(), OsStr::new("steel_2.yaml"));
+
+    // A new manager finds the highest counter by listing the folder
+    let mut other_dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    let file_path = other_dbm.write(&material, &write_options).unwrap();
+    assert_eq!(file_path.file_name().unwrap(), OsStr::new("steel_3.yaml"));
+
+    // Files created by someone else are skipped
+    let file_path = dbm.write(&material, &write_options).unwrap();
+    assert_eq!(file_path.file_name().unwrap(), OsStr::new("steel_4.yaml"));
+}
+
+#[test]
+fn test_exists_many() {
+    let mut dbm = scratch_database("exists_many");
+    let cup = Cup {
+        name: "daves_cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    let keys = [
+        ("Material", "ceramic"),
+        ("Material", "steel"),
+        ("Cup", "daves_cup"),
+        ("Shovel", "spade"),
+    ];
+    assert_eq!(dbm.exists_many(keys), vec![true, false, true, false]);
+    assert_eq!(
+        dbm.missing_of(keys),
+        vec![
+            DatabaseKeyBuf::new("Material", "steel"),
+            DatabaseKeyBuf::new("Shovel", "spade")
+        ]
+    );
+}
+
+#[test]
+fn test_checksum_of() {
+    let mut dbm = scratch_database("checksum_of");
+    let cup = Cup {
+        name: "daves_cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+
+    let cup_checksum = dbm.checksum_of(&cup).unwrap();
+    let material_checksum = dbm.checksum_of(&cup.material).unwrap();
+
+    // Nothing has been written
+    assert!(!dbm.exists(&cup));
+    assert!(!dbm.exists(&cup.material));
+
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+    assert_eq!(dbm.checksum(&cup), Some(cup_checksum));
+    assert_eq!(dbm.checksum(&cup.material), Some(material_checksum));
+
+    // Changing the linked entry changes the checksum of the parent
+    let mut changed_cup = cup.clone();
+    changed_cup.material.id = 2;
+    assert_ne!(dbm.checksum_of(&changed_cup).unwrap(), cup_checksum);
+}
+
+#[test]
+fn test_is_in_sync() {
+    let mut dbm = scratch_database("is_in_sync");
+    let mut cup = Cup {
+        name: "daves_cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+    assert!(!dbm.is_in_sync(&cup).unwrap());
+
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+    let read_cup: Cup = dbm.read("daves_cup").unwrap();
+    assert!(dbm.is_in_sync(&read_cup).unwrap());
+
+    // Modifying a linked entry is detected as well
+    cup.material.id = 2;
+    assert!(!dbm.is_in_sync(&cup).unwrap());
+    assert!(!dbm.is_in_sync(&cup.material).unwrap());
+}
+
+#[test]
+fn test_write_if_changed() {
+    let mut dbm = scratch_database("write_if_changed");
+    let mut cup = Cup {
+        name: "daves_cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+
+    let (_, write_info) = dbm.write_if_changed(&cup, &write_options).unwrap();
+    assert_eq!(write_info.created_files.len(), 2);
+
+    // Nothing changed => nothing is written
+    let (_, write_info) = dbm.write_if_changed(&cup, &write_options).unwrap();
+    assert_eq!(write_info.unchanged_files.len(), 2);
+    assert!(write_info.overwritten_files.is_empty());
+
+    // Changing the material changes the link in the cup file as well
+    cup.material.id = 2;
+    let (_, write_info) = dbm.write_if_changed(&cup, &write_options).unwrap();
+    assert!(write_info.unchanged_files.is_empty());
+    assert_eq!(write_info.overwritten_files.len(), 2);
+
+    // Only the cup file differs
+    let cup_path = dbm.full_path(&cup).unwrap();
+    let mut cup_file = std::fs::read_to_string(&cup_path).unwrap();
+    cup_file.push_str("\n\n");
+    std::fs::write(&cup_path, cup_file).unwrap();
+    let (_, write_info) = dbm.write_if_changed(&cup, &write_options).unwrap();
+    assert_eq!(
+        write_info.unchanged_files,
+        vec![dbm.full_path(&cup.material).unwrap()]
+    );
+    assert_eq!(write_info.overwritten_files, vec![cup_path]);
+}
+
+#[test]
+fn test_write_if_revision() {
+    let mut dbm = scratch_database("write_if_revision");
+    let cup = Cup {
+        name: "daves_cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&cup, &write_options).unwrap();
+
+    let (mut read_cup, read_info) = dbm.read_verbose::<Cup, _>("daves_cup").unwrap();
+    assert_eq!(read_info.revision.files().count(), 2);
+    assert!(read_info.revision.changed_files().is_empty());
+
+    // Someone else modifies the linked material ...
+    let mut material = cup.material.clone();
+    material.id = 2;
+    dbm.write(&material, &write_options).unwrap();
+
+    // ... hence the write is rejected
+    read_cup.material.id = 3;
+    let material_path = dbm.full_path(&material).unwrap();
+    assert_eq!(read_info.revision.changed_files(), vec![material_path]);
+    let err = dbm
+        .write_if_revision(&read_cup, &read_info.revision, &write_options)
+        .unwrap_err();
+    assert!(err.to_string().contains("Revision conflict"));
+    assert_eq!(dbm.read::<Material, _>("ceramic").unwrap().id, 2);
+
+    // After reloading, the write succeeds
+    let (mut read_cup, read_info) = dbm.read_verbose::<Cup, _>("daves_cup").unwrap();
+    read_cup.material.id = 3;
+    dbm.write_if_revision(&read_cup, &read_info.revision, &write_options)
+        .unwrap();
+    assert_eq!(dbm.read::<Material, _>("ceramic").unwrap().id, 3);
+}
+
+#[test]
+fn test_write_if_revision_cached() {
+    let mut dbm = scratch_database("write_if_revision_cached");
+    let shovel = Shovel {
+        name: "daves_shovel".into(),
+        shaft: Arc::new(Material {
+            id: 4,
+            name: "oak".into(),
+        }),
+        blade: Material {
+            id: 5,
+            name: "iron".into(),
+        },
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&shovel, &write_options).unwrap();
+
+    // Put the shaft into the cache
+    dbm.read::<Shovel, _>("daves_shovel").unwrap();
+
+    // Someone else edits the shaft by hand ...
+    let shaft_path = dbm.full_path(&*shovel.shaft).unwrap();
+    let contents = std::fs::read_to_string(&shaft_path).unwrap();
+    std::fs::write(&shaft_path, contents.replace("id: 4", "id: 6")).unwrap();
+
+    // ... but the outdated shaft is taken from the cache ...
+    let (read_shovel, read_info) = dbm.read_verbose::<Shovel, _>("daves_shovel").unwrap();
+    assert_eq!(read_shovel.shaft.id, 4);
+    assert_eq!(read_info.revision.changed_files(), vec![shaft_path]);
+
+    // ... hence writing it back is rejected
+    let err = dbm
+        .write_if_revision(&read_shovel, &read_info.revision, &write_options)
+        .unwrap_err();
+    assert!(err.to_string().contains("Revision conflict"));
+    assert_eq!(dbm.read::<Material, _>("oak").unwrap().id, 6);
+}
+
+#[test]
+fn test_lock() {
+    let mut dbm = scratch_database("lock");
+    let mut other_dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    let mut material = Material {
+        id: 1,
+        name: "steel".to_string(),
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+
+    let guard = dbm.lock(&material).unwrap();
+    assert_eq!(guard.key(), &DatabaseKeyBuf::new("Material", "steel"));
+    assert!(guard.lock_path().exists());
+    assert!(!dbm.is_locked(&material));
+    assert!(other_dbm.is_locked(&material));
+
+    // Only the manager holding the lock may write and remove the entry
+    dbm.write(&material, &write_options).unwrap();
+    material.id = 2;
+    let err = other_dbm.write(&material, &write_options).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ResourceBusy);
+    assert_eq!(
+        other_dbm.remove(&material).unwrap_err().kind(),
+        std::io::ErrorKind::ResourceBusy
+    );
+    assert_eq!(
+        other_dbm.lock(&material).unwrap_err().kind(),
+        std::io::ErrorKind::ResourceBusy
+    );
+
+    // After releasing the lock, the other manager may write again
+    let lock_path = guard.lock_path().to_path_buf();
+    drop(guard);
+    assert!(!lock_path.exists());
+    other_dbm.write(&material, &write_options).unwrap();
+    assert_eq!(dbm.read::<Material, _>("steel").unwrap().id, 2);
+
+    // Stale locks are replaced
+    let lock_options = LockOptions {
+        stale_after: std::time::Duration::ZERO,
+    };
+    let stale_guard = dbm.lock_with(&material, &lock_options).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert!(!other_dbm.is_locked(&material));
+    let guard = other_dbm.lock(&material).unwrap();
+    assert!(stale_guard.refresh().is_err());
+    drop(stale_guard);
+    assert!(guard.lock_path().exists());
+}
+
+#[test]
+fn test_lock_database() {
+    let mut dbm = scratch_database("lock_database");
+    let mut other_dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    let material = Material {
+        id: 1,
+        name: "steel".to_string(),
+    };
+    dbm.write(&material, &WriteOptions::default()).unwrap();
+
+    // Any number of shared locks can exist at once ...
+    let shared_guard = dbm.lock_shared().unwrap();
+    assert_eq!(shared_guard.mode(), DatabaseLockMode::Shared);
+    let other_shared_guard = other_dbm.lock_shared().unwrap();
+    other_dbm.read::<Material, _>("steel").unwrap();
+
+    // ... but they prevent an exclusive lock
+    let err = dbm.lock_exclusive().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ResourceBusy);
+    drop(shared_guard);
+    drop(other_shared_guard);
+
+    // An exclusive lock keeps out all other managers
+    let guard = dbm.lock_exclusive().unwrap();
+    assert_eq!(guard.mode(), DatabaseLockMode::Exclusive);
+    dbm.read::<Material, _>("steel").unwrap();
+    let err = other_dbm.read::<Material, _>("steel").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ResourceBusy);
+    let err = other_dbm
+        .write(&material, &WriteOptions::default())
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ResourceBusy);
+    assert!(other_dbm.lock_shared().is_err());
+    assert!(other_dbm.lock_exclusive().is_err());
+
+    drop(guard);
+    other_dbm.read::<Material, _>("steel").unwrap();
+}
+
 #[test]
 fn test_to_be_removed() {
     let mut dbm = test_database();
@@ -446,3 +900,511 @@ fn write_arc_opt() {
         assert_eq!(report.created_files.len(), 1);
     }
 }
+
+#[test]
+fn test_write_size_limits() {
+    let mut dbm = scratch_database("write_size_limits");
+    let cup = Cup {
+        name: "limited_cup".to_string(),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+
+    // The cup itself is small enough, but the material exceeds its limit
+    let mut write_options = WriteOptions::default();
+    write_options.size_limits.max_entry_size = Some(1000);
+    write_options
+        .size_limits
+        .max_entry_size_per_type
+        .insert("Material".into(), 10);
+
+    let err = dbm.write(&cup, &write_options).unwrap_err();
+    assert!(err.to_string().contains("exceeds the limit of 10 bytes"));
+    assert!(!dbm.exists(&cup));
+    assert!(!dbm.exists(&cup.material));
+
+    // With the warn policy, the files are written and the violation is reported
+    write_options.size_limits.policy = SizeLimitPolicy::Warn;
+    let (_, write_info) = dbm.write_verbose(&cup, &write_options).unwrap();
+    assert!(dbm.exists(&cup));
+    assert_eq!(write_info.size_limit_violations.len(), 1);
+    let violation = &write_info.size_limit_violations[0];
+    assert_eq!(violation.file_path, dbm.full_path(&cup.material).unwrap());
+    assert_eq!(violation.limit, 10);
+    assert!(violation.size > 10);
+
+    // The global limit applies to the cup
+    write_options.size_limits.max_entry_size = Some(10);
+    write_options
+        .size_limits
+        .max_entry_size_per_type
+        .insert("Material".into(), 1000);
+    write_options.size_limits.policy = SizeLimitPolicy::Reject;
+    write_options.name_collisions = NameCollisions::Overwrite;
+    let err = dbm.write(&cup, &write_options).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::FileTooLarge);
+}
+
+#[test]
+fn test_write_preserve_yaml_comments() {
+    let dir = scratch_database("test_write_preserve_yaml_comments")
+        .dir()
+        .to_path_buf();
+    let mut dbm = DatabaseManager::open(&dir, SerdeYamlPreserving).unwrap();
+    for (id, name) in [(1, "steel"), (2, "oak"), (3, "beech")] {
+        let material = Material {
+            id,
+            name: name.into(),
+        };
+        dbm.write(&material, &WriteOptions::default()).unwrap();
+    }
+    std::fs::create_dir_all(dir.join("Stool")).unwrap();
+    std::fs::write(
+        dir.join("Stool/workshop_stool.yaml"),
+        indoc::indoc! {"
+            # Workshop stool
+            Stool:
+              # The seat comes first for us
+              seat:
+                name: oak # Solid wood
+              name: workshop_stool
+              # All legs are identical
+              leg_1:
+                name: steel
+              leg_2:
+                name: steel
+              leg_3:
+                name: steel
+            # End of file
+        "},
+    )
+    .unwrap();
+
+    let mut stool: Stool = dbm.read("workshop_stool").unwrap();
+    stool.seat = Arc::new(Material {
+        id: 3,
+        name: "beech".into(),
+    });
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&stool, &write_options).unwrap();
+
+    let contents = std::fs::read_to_string(dir.join("Stool/workshop_stool.yaml")).unwrap();
+    assert!(
+        contents.contains(indoc::indoc! {"
+            # Workshop stool
+            Stool:
+              # The seat comes first for us
+              seat:
+                name: beech # Solid wood
+        "}),
+        "{}",
+        contents
+    );
+    assert!(
+        contents.contains("  name: workshop_stool\n  # All legs are identical\n  leg_1:\n"),
+        "{}",
+        contents
+    );
+    assert!(contents.ends_with("# End of file\n"), "{}", contents);
+    assert_eq!(dbm.read::<Stool, _>("workshop_stool").unwrap(), stool);
+}
+
+#[test]
+fn test_write_format_options() {
+    let mut dbm = scratch_database("test_write_format_options");
+    let gauge = Gauge {
+        name: "manometer".into(),
+        readings: vec![1.0, 2.346, 1e-7],
+        material: Material {
+            id: 1,
+            name: "brass".into(),
+        },
+    };
+    let mut format_options = FormatOptions::default();
+    format_options.indent = 4;
+    format_options.float_precision = Some(2);
+    format_options.quote_style = QuoteStyle::Single;
+    format_options.line_width = Some(40);
+    dbm.set_format_options(Some(format_options.clone()));
+    dbm.write(&gauge, &WriteOptions::default()).unwrap();
+
+    let contents = std::fs::read_to_string(dbm.full_path(&gauge).unwrap()).unwrap();
+    assert!(
+        contents.starts_with(
+            "---\nGauge:\n    name: 'manometer'\n    readings: [1.0, 2.35, 1e-7]\n    material:\n        name: 'brass'\n        checksum: "
+        ),
+        "{}",
+        contents
+    );
+    let contents = std::fs::read_to_string(dbm.full_path(&gauge.material).unwrap()).unwrap();
+    assert_eq!(contents, "---\nMaterial:\n    id: 1\n    name: 'brass'\n");
+
+    // The checksums of the links match the formatted files
+    assert!(dbm.verify_entry(&gauge).is_valid());
+    assert!(dbm.is_in_sync(&gauge.material).unwrap());
+    let read_gauge: Gauge = dbm.read("manometer").unwrap();
+    assert_eq!(read_gauge.readings, vec![1.0, 2.35, 1e-7]);
+
+    // Sequences which don't fit into a line are written in block style
+    format_options.line_width = Some(20);
+    format_options.float_notation = FloatNotation::Decimal;
+    dbm.set_format_options(Some(format_options));
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&gauge, &write_options).unwrap();
+    let contents = std::fs::read_to_string(dbm.full_path(&gauge).unwrap()).unwrap();
+    assert!(
+        contents.contains("    readings:\n        - 1.0\n        - 2.35\n        - 0.0\n"),
+        "{}",
+        contents
+    );
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_write_format_options_json() {
+    let dir = scratch_database("test_write_format_options_json")
+        .dir()
+        .to_path_buf();
+    let mut dbm = DatabaseManager::open(&dir, SerdeJson).unwrap();
+    let mut format_options = FormatOptions::default();
+    format_options.line_width = Some(80);
+    dbm.set_format_options(Some(format_options));
+
+    let gauge = Gauge {
+        name: "thermometer".into(),
+        readings: vec![20.5, 21.0],
+        material: Material {
+            id: 2,
+            name: "glass".into(),
+        },
+    };
+    dbm.write(&gauge, &WriteOptions::default()).unwrap();
+    let contents = std::fs::read_to_string(dbm.full_path(&gauge.material).unwrap()).unwrap();
+    assert_eq!(
+        contents,
+        "{\n  \"Material\": {\n    \"id\": 2,\n    \"name\": \"glass\"\n  }\n}"
+    );
+    let contents = std::fs::read_to_string(dbm.full_path(&gauge).unwrap()).unwrap();
+    assert!(
+        contents.contains("\"readings\": [20.5, 21.0],"),
+        "{}",
+        contents
+    );
+    assert_eq!(dbm.read::<Gauge, _>("thermometer").unwrap(), gauge);
+}
+
+#[test]
+fn test_write_staged() {
+    let mut dbm = scratch_database("test_write_staged");
+    dbm.enable_staging(StagingDropPolicy::Discard);
+    let events = dbm.subscribe();
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+
+    let mut cup = Cup {
+        name: "staged_cup".into(),
+        material: Material {
+            id: 1,
+            name: "clay".into(),
+        },
+    };
+    dbm.write(&cup, &write_options).unwrap();
+    cup.material.id = 2;
+    dbm.write(&cup, &write_options).unwrap();
+
+    // Nothing has been written yet and repeated writes are coalesced
+    assert!(!dbm.dir().join("Cup").exists());
+    assert_eq!(dbm.staged_files().len(), 2);
+    assert_eq!(events.try_iter().count(), 0);
+
+    assert_eq!(dbm.flush().unwrap().len(), 2);
+    assert_eq!(events.try_iter().count(), 2);
+    assert!(dbm.staged_files().is_empty());
+    let (read, info) = dbm.read_verbose::<Cup, _>("staged_cup").unwrap();
+    assert_eq!(read, cup);
+    assert!(info.checksum_mismatch.is_empty());
+
+    // Staged files can be discarded
+    cup.material.id = 3;
+    dbm.write(&cup, &write_options).unwrap();
+    assert_eq!(dbm.discard_staged().len(), 2);
+    dbm.cache_mut().clear();
+    assert_eq!(dbm.read::<Cup, _>("staged_cup").unwrap().material.id, 2);
+
+    // The drop policy is applied when the last clone is dropped
+    dbm.enable_staging(StagingDropPolicy::Flush);
+    dbm.write(&cup, &write_options).unwrap();
+    let dir = dbm.dir().to_path_buf();
+    let clone = dbm.clone();
+    drop(dbm);
+    assert_eq!(clone.staged_files().len(), 2);
+    drop(clone);
+    let mut dbm = DatabaseManager::open(dir, SerdeYaml).unwrap();
+    assert_eq!(dbm.read::<Cup, _>("staged_cup").unwrap().material.id, 3);
+}
+
+#[test]
+fn test_write_entry_quotas() {
+    let mut dbm = scratch_database("write_entry_quotas");
+    let cup = |index: usize| Cup {
+        name: format!("cup_{index}"),
+        material: Material {
+            id: 1,
+            name: "ceramic".to_string(),
+        },
+    };
+
+    let mut write_options = WriteOptions::default();
+    write_options
+        .entry_quotas
+        .max_entries_per_type
+        .insert("Cup".into(), 2);
+    dbm.write(&cup(0), &write_options).unwrap();
+    dbm.write(&cup(1), &write_options).unwrap();
+    let err = dbm.write(&cup(2), &write_options).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::QuotaExceeded);
+    assert!(!dbm.exists(&cup(2)));
+
+    // Overwriting doesn't create a new entry
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&cup(1), &write_options).unwrap();
+
+    // Ring buffer: the least recently written entries are evicted
+    write_options.entry_quotas.policy = QuotaPolicy::EvictLeastRecentlyWritten;
+    let (_, write_info) = dbm.write_verbose(&cup(2), &write_options).unwrap();
+    assert_eq!(
+        write_info.evicted_files,
+        vec![dbm.dir().join("Cup").join("cup_0.yaml")]
+    );
+    dbm.write(&cup(3), &write_options).unwrap();
+    assert!(!dbm.exists(&cup(1)));
+    assert!(dbm.exists(&cup(2)));
+    assert!(dbm.exists(&cup(3)));
+}
+
+#[test]
+fn test_write_entry_quotas_linked() {
+    let mut dbm = scratch_database("write_entry_quotas_linked");
+    let material = |id: usize, name: &str| Material {
+        id,
+        name: name.to_string(),
+    };
+    let cup = Cup {
+        name: "mug".to_string(),
+        material: material(1, "clay"),
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    let mut write_options = WriteOptions::default();
+    write_options
+        .entry_quotas
+        .max_entries_per_type
+        .insert("Material".into(), 1);
+    write_options.entry_quotas.policy = QuotaPolicy::EvictLeastRecentlyWritten;
+
+    // The oldest material is still linked by the cup and therefore kept
+    let (_, write_info) = dbm
+        .write_verbose(&material(2, "glass"), &write_options)
+        .unwrap();
+    assert!(write_info.evicted_files.is_empty());
+    assert!(dbm.exists(("Material", "clay")));
+
+    let (_, write_info) = dbm
+        .write_verbose(&material(3, "steel"), &write_options)
+        .unwrap();
+    assert_eq!(
+        write_info.evicted_files,
+        vec![dbm.dir().join("Material").join("glass.yaml")]
+    );
+    assert!(dbm.exists(("Material", "clay")));
+    assert_eq!(dbm.read::<Cup, _>("mug").unwrap(), cup);
+
+    // If the links can't be determined, nothing is evicted and the write fails
+    std::fs::write(dbm.dir().join("Cup").join("broken.yaml"), "name: [").unwrap();
+    let err = dbm.write(&material(4, "wood"), &write_options).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("enforcing the entry quotas failed")
+    );
+    assert!(err.to_string().contains("broken.yaml"));
+    assert!(dbm.exists(("Material", "wood")));
+    assert!(dbm.exists(("Material", "steel")));
+}
+
+mod geometry {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub struct Material {
+        pub name: String,
+        pub density: f64,
+    }
+
+    #[typetag::serde(name = "geometry::Material")]
+    impl DatabaseEntry for Material {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub struct Cup {
+        pub name: String,
+        pub volume: f64,
+    }
+
+    #[typetag::serde(name = "geometry::Cup")]
+    impl DatabaseEntry for Cup {
+        fn name(&self) -> &OsStr {
+            self.name.as_ref()
+        }
+
+        fn folder_name() -> &'static str {
+            return qualified_type_name::<Self>();
+        }
+    }
+}
+
+#[test]
+fn test_write_folder_collisions() {
+    let mut dbm = scratch_database("write_folder_collisions");
+    let material = Material {
+        id: 1,
+        name: "clay".into(),
+    };
+    dbm.write(&material, &WriteOptions::default()).unwrap();
+
+    // Both types are named "Material", so they would share a folder
+    let dense_material = geometry::Material {
+        name: "lead".into(),
+        density: 11.3,
+    };
+    let err = dbm
+        .write(&dense_material, &WriteOptions::default())
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(!dbm.dir().join("Material/lead.yaml").exists());
+
+    // A qualified folder name disambiguates the types
+    let folder = geometry::Cup::folder_name();
+    assert!(folder.starts_with("Cup."));
+    assert_eq!(folder, qualified_type_name::<geometry::Cup>());
+    let measuring_cup = geometry::Cup {
+        name: "measuring_cup".into(),
+        volume: 0.25,
+    };
+    let path = dbm.write(&measuring_cup, &WriteOptions::default()).unwrap();
+    assert_eq!(path, dbm.dir().join(folder).join("measuring_cup.yaml"));
+    assert_eq!(
+        dbm.read::<geometry::Cup, _>("measuring_cup").unwrap(),
+        measuring_cup
+    );
+    assert!(dbm.list::<Cup>().unwrap().next().is_none());
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Swatch {
+    #[serde(skip)]
+    id: String,
+    rgb: [u8; 3],
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Swatch {
+    fn name(&self) -> &OsStr {
+        self.id.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Palette {
+    name: String,
+    #[serde(deserialize_with = "deserialize_vec_link")]
+    #[serde(serialize_with = "serialize_vec_link")]
+    swatches: Vec<Swatch>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Palette {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[test]
+fn test_write_deduplicated() {
+    let mut dbm = scratch_database("write_deduplicated");
+    let swatch = |id: &str, rgb: [u8; 3]| Swatch { id: id.into(), rgb };
+    dbm.write(&swatch("red", [255, 0, 0]), &WriteOptions::default())
+        .unwrap();
+
+    let palette = Palette {
+        name: "warm".into(),
+        swatches: vec![
+            swatch("swatch_0", [255, 0, 0]),
+            swatch("swatch_1", [255, 128, 0]),
+            swatch("swatch_2", [255, 128, 0]),
+        ],
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.deduplicate = true;
+    let (_, write_info) = dbm.write_verbose(&palette, &write_options).unwrap();
+
+    // Identical swatches are only written once, also within a single call
+    let swatch_folder = dbm.dir().join("Swatch");
+    assert_eq!(
+        write_info.deduplicated_files,
+        vec![
+            swatch_folder.join("red.yaml"),
+            swatch_folder.join("swatch_1.yaml")
+        ]
+    );
+    assert!(!swatch_folder.join("swatch_0.yaml").exists());
+    assert!(swatch_folder.join("swatch_1.yaml").exists());
+    assert!(!swatch_folder.join("swatch_2.yaml").exists());
+
+    let contents = std::fs::read_to_string(dbm.dir().join("Palette/warm.yaml")).unwrap();
+    assert!(contents.contains("red"));
+    assert!(!contents.contains("swatch_2"));
+    let read: Palette = dbm.read("warm").unwrap();
+    let rgbs: Vec<[u8; 3]> = read.swatches.iter().map(|swatch| swatch.rgb).collect();
+    assert_eq!(rgbs, [[255, 0, 0], [255, 128, 0], [255, 128, 0]]);
+
+    // Without deduplication, all linked entries are written
+    let (_, write_info) = dbm
+        .write_verbose(&palette, &WriteOptions::default())
+        .unwrap();
+    assert!(write_info.deduplicated_files.is_empty());
+    assert!(swatch_folder.join("swatch_2.yaml").exists());
+}
+
+#[test]
+fn test_write_to_string() {
+    let mut dbm = scratch_database("write_to_string");
+    let cup = Cup {
+        name: "mug".to_string(),
+        material: Material {
+            id: 4,
+            name: "stoneware".to_string(),
+        },
+    };
+
+    // The linked material is written, the cup itself is not
+    let text = dbm
+        .to_string_linked(&cup, &WriteOptions::default())
+        .unwrap();
+    assert!(dbm.exists(&cup.material));
+    assert!(!dbm.exists(&cup));
+    let path = dbm.write(&cup, &WriteOptions::default()).unwrap();
+    assert_eq!(std::fs::read_to_string(path).unwrap(), text);
+
+    // Flat serialization inlines the material
+    let flat = dbm.to_string_flat(&cup).unwrap();
+    assert!(flat.contains("id: 4"));
+    assert!(!text.contains("id: 4"));
+    assert_eq!(dbm.to_bytes_flat(&cup).unwrap(), flat.into_bytes());
+}
@@ -1,10 +1,146 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsStr,
+    rc::Rc,
+    sync::{Arc, Weak},
+};
 
+use serde::{Deserialize, Serialize};
 use serde_mosaic::*;
 
 mod utilities;
 use utilities::*;
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Glaze {
+    name: String,
+    color: String,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Glaze {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Vase {
+    name: String,
+    #[serde(deserialize_with = "deserialize_link")]
+    #[serde(serialize_with = "serialize_link")]
+    glaze: Glaze,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Vase {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Bench {
+    name: String,
+    #[serde(deserialize_with = "deserialize_rc_link")]
+    #[serde(serialize_with = "serialize_rc_link")]
+    left_leg: Rc<Material>,
+    #[serde(deserialize_with = "deserialize_rc_link")]
+    #[serde(serialize_with = "serialize_rc_link")]
+    right_leg: Rc<Material>,
+    #[serde(deserialize_with = "deserialize_opt_rc_link")]
+    #[serde(serialize_with = "serialize_opt_rc_link")]
+    cushion: Option<Rc<Material>>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Bench {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ToolRack {
+    name: String,
+    #[serde(deserialize_with = "deserialize_vec_link")]
+    #[serde(serialize_with = "serialize_vec_link")]
+    hooks: Vec<Material>,
+    #[serde(deserialize_with = "deserialize_vec_arc_link")]
+    #[serde(serialize_with = "serialize_vec_arc_link")]
+    shovels: Vec<Arc<Shovel>>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for ToolRack {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Kiln {
+    name: String,
+    #[serde(deserialize_with = "deserialize_map_link")]
+    #[serde(serialize_with = "serialize_map_link")]
+    glazes: BTreeMap<String, Glaze>,
+    #[serde(deserialize_with = "deserialize_map_link")]
+    #[serde(serialize_with = "serialize_map_link")]
+    shelves: HashMap<u32, Material>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Kiln {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Folder {
+    name: String,
+    #[serde(deserialize_with = "deserialize_vec_arc_link")]
+    #[serde(serialize_with = "serialize_vec_arc_link")]
+    documents: Vec<Arc<Document>>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Folder {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Document {
+    name: String,
+    #[serde(deserialize_with = "deserialize_weak_link")]
+    #[serde(serialize_with = "serialize_weak_link")]
+    folder: Weak<Folder>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Document {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Archive {
+    name: String,
+    #[serde(deserialize_with = "deserialize_arc_link")]
+    #[serde(serialize_with = "serialize_arc_link")]
+    folder: Arc<Folder>,
+}
+
+#[typetag::serde]
+impl DatabaseEntry for Archive {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
 #[test]
 fn write_and_read_arc() {
     let mut dbm = test_database();
@@ -33,3 +169,678 @@ fn write_and_read_arc() {
     let r_shelf: Shelf = dbm.read(w_shelf.name()).unwrap();
     assert_eq!(w_shelf, r_shelf);
 }
+
+#[cfg(feature = "compression")]
+#[test]
+fn write_and_read_compressed() {
+    let mut dbm = scratch_database("write_and_read_compressed");
+    let cup = Cup {
+        name: "daves_cup".into(),
+        material: Material {
+            id: 1,
+            name: "ceramic".into(),
+        },
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.compression = Some(Compression::new(0));
+    dbm.write(&cup, &write_options).unwrap();
+
+    // Both the cup and the linked material are compressed
+    let cup_file = std::fs::read(dbm.full_path(&cup).unwrap()).unwrap();
+    assert!(cup_file.starts_with(&[0x1f, 0x8b]));
+    let material_file = std::fs::read(dbm.full_path(&cup.material).unwrap()).unwrap();
+    assert!(material_file.starts_with(&[0x1f, 0x8b]));
+
+    let read_cup: Cup = dbm.read("daves_cup").unwrap();
+    assert_eq!(read_cup, cup);
+    assert!(dbm.verify_entry(&cup).is_valid());
+
+    // Entries below the threshold are not compressed
+    let hanks_cup = Cup {
+        name: "hanks_cup".into(),
+        material: cup.material.clone(),
+    };
+    write_options.compression = Some(Compression::new(u64::MAX));
+    dbm.write(&hanks_cup, &write_options).unwrap();
+    let cup_file = std::fs::read_to_string(dbm.full_path(&hanks_cup).unwrap()).unwrap();
+    assert!(cup_file.contains("hanks_cup"));
+    let read_cup: Cup = dbm.read("hanks_cup").unwrap();
+    assert_eq!(read_cup, hanks_cup);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn write_and_read_compressed_format() {
+    let dir = scratch_database("write_and_read_compressed_format")
+        .dir()
+        .to_path_buf();
+    let mut dbm = DatabaseManager::open(dir, Compressed::new(SerdeYaml, Gzip::default())).unwrap();
+    assert_eq!(dbm.file_ext(), "yaml.gz");
+    let cup = Cup {
+        name: "zipped_cup".into(),
+        material: Material {
+            id: 2,
+            name: "zipped_clay".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    // Both the cup and the linked material are compressed
+    let cup_path = dbm.dir().join("Cup/zipped_cup.yaml.gz");
+    assert!(std::fs::read(&cup_path).unwrap().starts_with(&[0x1f, 0x8b]));
+    let material_path = dbm.dir().join("Material/zipped_clay.yaml.gz");
+    assert!(
+        std::fs::read(&material_path)
+            .unwrap()
+            .starts_with(&[0x1f, 0x8b])
+    );
+    assert_eq!(
+        dbm.list::<Cup>().unwrap().collect::<Vec<_>>(),
+        ["zipped_cup"]
+    );
+
+    let read_cup: Cup = dbm.read("zipped_cup").unwrap();
+    assert_eq!(read_cup, cup);
+    assert!(dbm.verify_entry(&cup).is_valid());
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn write_and_read_encrypted() {
+    let mut dbm = scratch_database("write_and_read_encrypted");
+    let key_lookup = |type_name: &std::ffi::OsStr| {
+        if type_name == "Material" {
+            return Some([7; 32]);
+        }
+        return None;
+    };
+    dbm.set_encryption(Some(Encryption::new(key_lookup).encrypt::<Material>()));
+
+    let cup = Cup {
+        name: "daves_cup".into(),
+        material: Material {
+            id: 1,
+            name: "ceramic".into(),
+        },
+    };
+    dbm.write(&cup, &WriteOptions::default()).unwrap();
+
+    // Only the material is encrypted
+    let cup_file = std::fs::read_to_string(dbm.full_path(&cup).unwrap()).unwrap();
+    assert!(cup_file.contains("daves_cup"));
+    let material_file = std::fs::read(dbm.full_path(&cup.material).unwrap()).unwrap();
+    assert!(!String::from_utf8_lossy(&material_file).contains("id: 1"));
+
+    let read_cup: Cup = dbm.read("daves_cup").unwrap();
+    assert_eq!(read_cup, cup);
+    assert!(dbm.verify_entry(&cup).is_valid());
+
+    // Without the policy or with a wrong key, the material can't be read
+    let mut plain_dbm = DatabaseManager::open(dbm.dir(), SerdeYaml).unwrap();
+    assert!(plain_dbm.read::<Cup, _>("daves_cup").is_err());
+    plain_dbm.set_encryption(Some(
+        Encryption::new(|_| Some([8; 32])).encrypt_type("Material"),
+    ));
+    assert!(plain_dbm.read::<Cup, _>("daves_cup").is_err());
+
+    // Missing keys prevent writing
+    dbm.set_encryption(Some(Encryption::new(key_lookup).encrypt::<Cup>()));
+    let hanks_cup = Cup {
+        name: "hanks_cup".into(),
+        material: cup.material.clone(),
+    };
+    let err = dbm.write(&hanks_cup, &WriteOptions::default()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn write_and_read_roundtrip() {
+    use serde_mosaic::testing::assert_roundtrip;
+
+    let mut dbm = scratch_database("write_and_read_roundtrip");
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+
+    for id in 0..3 {
+        let oak = Arc::new(Material {
+            id,
+            name: "oak".into(),
+        });
+        let stool = Stool {
+            name: "roundtrip_stool".into(),
+            leg_1: oak.clone(),
+            leg_2: oak.clone(),
+            leg_3: oak,
+            seat: Arc::new(Material {
+                id: id + 10,
+                name: "leather".into(),
+            }),
+        };
+        assert_roundtrip(&mut dbm, &stool, &write_options);
+    }
+    assert!(dbm.cache().is_empty());
+}
+
+#[test]
+fn write_and_read_preserve_unknown_fields() {
+    let mut dbm = scratch_database("write_and_read_preserve_unknown_fields");
+    let vase = Vase {
+        name: "ming".into(),
+        glaze: Glaze {
+            name: "celadon".into(),
+            color: "green".into(),
+        },
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&vase, &write_options).unwrap();
+
+    // Annotate both files by hand
+    for path in ["Vase/ming.yaml", "Glaze/celadon.yaml"] {
+        let path = dbm.dir().join(path);
+        let mut contents = std::fs::read_to_string(&path).unwrap();
+        contents.push_str("  reviewed_by: Hank\n");
+        std::fs::write(&path, contents).unwrap();
+    }
+
+    dbm.set_preserve_unknown_fields(true);
+    let mut read_vase: Vase = dbm.read("ming").unwrap();
+    assert_eq!(read_vase, vase);
+    assert_eq!(
+        dbm.unknown_fields(("Glaze", "celadon")),
+        vec![(
+            Value::String("reviewed_by".into()),
+            Value::String("Hank".into())
+        )]
+    );
+
+    read_vase.glaze.color = "blue".into();
+    dbm.write(&read_vase, &write_options).unwrap();
+    for path in ["Vase/ming.yaml", "Glaze/celadon.yaml"] {
+        let contents = std::fs::read_to_string(dbm.dir().join(path)).unwrap();
+        assert!(contents.contains("reviewed_by: Hank"), "{}", contents);
+    }
+    let read_vase: Vase = dbm.read("ming").unwrap();
+    assert_eq!(read_vase.glaze.color, "blue");
+
+    // Without preservation, the unknown fields are dropped
+    dbm.set_preserve_unknown_fields(false);
+    dbm.write(&read_vase, &write_options).unwrap();
+    let contents = std::fs::read_to_string(dbm.dir().join("Vase/ming.yaml")).unwrap();
+    assert!(!contents.contains("reviewed_by"));
+}
+
+#[test]
+fn write_and_read_short_links() {
+    let mut dbm = scratch_database("write_and_read_short_links");
+    let mut write_options = WriteOptions::default();
+    write_options.link_style = LinkStyle::Short;
+
+    let shelf = Shelf {
+        name: "short_shelf".into(),
+        shovel: Some(Arc::new(Shovel {
+            name: "short_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 1,
+                name: "ash".into(),
+            }),
+            blade: Material {
+                id: 2,
+                name: "steel".into(),
+            },
+        })),
+    };
+    dbm.write(&shelf, &write_options).unwrap();
+
+    let shovel_file = dbm.full_path(("Shovel", "short_shovel")).unwrap();
+    let contents = std::fs::read_to_string(shovel_file).unwrap();
+    assert!(contents.contains("shaft: ash\n"));
+    assert!(contents.contains("blade: steel\n"));
+    let shelf_file = dbm.full_path(("Shelf", "short_shelf")).unwrap();
+    let contents = std::fs::read_to_string(shelf_file).unwrap();
+    assert!(contents.contains("shovel: short_shovel\n"));
+
+    dbm.cache_mut().clear();
+    let read: Shelf = dbm.read("short_shelf").unwrap();
+    assert_eq!(read, shelf);
+
+    // Hand-written short links
+    let cup_file = dbm.dir().join("Cup").join("mixed.yaml");
+    std::fs::create_dir_all(cup_file.parent().unwrap()).unwrap();
+    std::fs::write(&cup_file, "Cup:\n  name: mixed\n  material: ash\n").unwrap();
+    let cup: Cup = dbm.read("mixed").unwrap();
+    assert_eq!(cup.material.name, "ash");
+}
+
+#[test]
+fn write_and_read_staged_overlay() {
+    let mut dbm = scratch_database("write_and_read_staged_overlay");
+    dbm.enable_staging(StagingDropPolicy::Discard);
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+
+    let cup = Cup {
+        name: "provisional_cup".into(),
+        material: Material {
+            id: 1,
+            name: "provisional".into(),
+        },
+    };
+    dbm.write(&cup, &write_options).unwrap();
+    assert!(dbm.exists(("Cup", "provisional_cup")));
+    assert!(dbm.exists_many([("Material", "provisional")])[0]);
+    assert!(dbm.full_path(("Cup", "provisional_cup")).is_none());
+    assert!(dbm.checksum(("Cup", "provisional_cup")).is_some());
+
+    // Staged entries and their links are read from memory
+    let (read, info) = dbm.read_verbose::<Cup, _>("provisional_cup").unwrap();
+    assert_eq!(read, cup);
+    assert!(info.checksum_mismatch.is_empty());
+    assert!(dbm.is_in_sync(&cup).unwrap());
+
+    // Changing a staged linked entry is detected via the checksum
+    let material = Material {
+        id: 2,
+        name: "provisional".into(),
+    };
+    dbm.write(&material, &write_options).unwrap();
+    dbm.cache_mut().clear();
+    let (read, info) = dbm.read_verbose::<Cup, _>("provisional_cup").unwrap();
+    assert_eq!(read.material, material);
+    assert_eq!(info.checksum_mismatch.len(), 1);
+
+    // Nothing is left after discarding
+    assert_eq!(dbm.discard_staged().len(), 2);
+    assert!(!dbm.exists(("Cup", "provisional_cup")));
+    assert!(dbm.read::<Cup, _>("provisional_cup").is_err());
+}
+
+#[test]
+fn write_and_read_rc() {
+    let mut dbm = scratch_database("write_and_read_rc");
+    let leg = Rc::new(Material {
+        id: 1,
+        name: "beech".into(),
+    });
+    let bench = Bench {
+        name: "park_bench".into(),
+        left_leg: leg.clone(),
+        right_leg: leg,
+        cushion: None,
+    };
+    dbm.write(&bench, &WriteOptions::default()).unwrap();
+
+    // Both legs share one instance, which is not stored in the manager cache
+    let first: Bench = dbm.read("park_bench").unwrap();
+    assert_eq!(first, bench);
+    assert!(Rc::ptr_eq(&first.left_leg, &first.right_leg));
+    assert!(dbm.cache().is_empty());
+
+    // Instances are only shared within a single read call
+    let second: Bench = dbm.read("park_bench").unwrap();
+    assert!(!Rc::ptr_eq(&first.left_leg, &second.left_leg));
+
+    let mut read_options = ReadOptions::default();
+    read_options.bypass_cache = true;
+    let bypassed: Bench = dbm.read_with("park_bench", &read_options).unwrap();
+    assert!(!Rc::ptr_eq(&bypassed.left_leg, &bypassed.right_leg));
+    assert_eq!(bypassed, bench);
+}
+
+#[test]
+fn write_and_read_weak() {
+    let mut dbm = scratch_database("write_and_read_weak");
+    let folder = Arc::new_cyclic(|folder: &Weak<Folder>| Folder {
+        name: "invoices".into(),
+        documents: ["january", "february"]
+            .map(|name| {
+                Arc::new(Document {
+                    name: name.into(),
+                    folder: folder.clone(),
+                })
+            })
+            .to_vec(),
+    });
+    let archive = Archive {
+        name: "2024".into(),
+        folder: folder.clone(),
+    };
+    dbm.write(&archive, &WriteOptions::default()).unwrap();
+    assert!(dbm.exists(("Document", "january")));
+    dbm.cache_mut().clear();
+
+    // The weak links point to the folder which is being read
+    let read: Archive = dbm.read("2024").unwrap();
+    assert_eq!(read.folder.documents.len(), 2);
+    for document in read.folder.documents.iter() {
+        let parent = document.folder.upgrade().unwrap();
+        assert!(Arc::ptr_eq(&parent, &read.folder));
+    }
+
+    // A weak link to a dropped entry can't be written
+    let orphan = Document {
+        name: "march".into(),
+        folder: Weak::new(),
+    };
+    assert!(dbm.write(&orphan, &WriteOptions::default()).is_err());
+}
+
+#[test]
+fn write_and_read_vec() {
+    let mut dbm = scratch_database("write_and_read_vec");
+    let shaft = Arc::new(Material {
+        id: 1,
+        name: "ash".into(),
+    });
+    let shovels = ["spade", "scoop"].map(|name| {
+        Arc::new(Shovel {
+            name: name.into(),
+            shaft: shaft.clone(),
+            blade: Material {
+                id: 2,
+                name: "steel".into(),
+            },
+        })
+    });
+    let rack = ToolRack {
+        name: "garage_rack".into(),
+        hooks: vec![
+            Material {
+                id: 3,
+                name: "brass".into(),
+            },
+            Material {
+                id: 4,
+                name: "copper".into(),
+            },
+        ],
+        shovels: shovels.to_vec(),
+    };
+    dbm.write(&rack, &WriteOptions::default()).unwrap();
+
+    // Every element has been written to its own file
+    for name in ["brass", "copper"] {
+        assert!(dbm.exists(("Material", name)));
+    }
+    for name in ["spade", "scoop"] {
+        assert!(dbm.exists(("Shovel", name)));
+    }
+
+    let read: ToolRack = dbm.read("garage_rack").unwrap();
+    assert_eq!(read, rack);
+    assert!(Arc::ptr_eq(&read.shovels[0].shaft, &read.shovels[1].shaft));
+
+    // Lists written in flat mode are read as well
+    let mut write_options = WriteOptions::default();
+    write_options.write_mode = WriteMode::Flat;
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&rack, &write_options).unwrap();
+    let read: ToolRack = dbm.read("garage_rack").unwrap();
+    assert_eq!(read, rack);
+}
+
+#[test]
+fn write_and_read_map() {
+    let mut dbm = scratch_database("write_and_read_map");
+    let kiln = Kiln {
+        name: "studio_kiln".into(),
+        glazes: ["celadon", "tenmoku"]
+            .into_iter()
+            .map(|name| {
+                let glaze = Glaze {
+                    name: name.into(),
+                    color: "green".into(),
+                };
+                (format!("{name}_glaze"), glaze)
+            })
+            .collect(),
+        shelves: HashMap::from([(
+            1,
+            Material {
+                id: 1,
+                name: "cordierite".into(),
+            },
+        )]),
+    };
+    dbm.write(&kiln, &WriteOptions::default()).unwrap();
+
+    // Every value has been written to its own file and the keys are kept
+    assert!(dbm.exists(("Glaze", "celadon")));
+    assert!(dbm.exists(("Glaze", "tenmoku")));
+    assert!(dbm.exists(("Material", "cordierite")));
+    let file = std::fs::read_to_string(dbm.dir().join("Kiln/studio_kiln.yaml")).unwrap();
+    assert!(file.contains("celadon_glaze"));
+
+    let read: Kiln = dbm.read("studio_kiln").unwrap();
+    assert_eq!(read, kiln);
+}
+
+#[cfg(any(feature = "bincode", feature = "postcard"))]
+fn write_and_read_binary<F: Format + 'static>(name: &str, format: F) {
+    let dir = scratch_database(name).dir().to_path_buf();
+    let mut dbm = DatabaseManager::open(&dir, format).unwrap();
+    let shelf = Shelf {
+        name: "binary_shelf".into(),
+        shovel: Some(Arc::new(Shovel {
+            name: "binary_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 1,
+                name: "binary_pine".into(),
+            }),
+            blade: Material {
+                id: 2,
+                name: "binary_bronze".into(),
+            },
+        })),
+    };
+
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    for write_mode in [WriteMode::Link, WriteMode::Flat] {
+        write_options.write_mode = write_mode;
+        dbm.write(&shelf, &write_options).unwrap();
+        dbm.cache_mut().clear();
+        assert_eq!(dbm.read::<Shelf, _>("binary_shelf").unwrap(), shelf);
+    }
+    assert!(dbm.exists(("Material", "binary_pine")));
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn write_and_read_bincode() {
+    write_and_read_binary("write_and_read_bincode", Bincode);
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn write_and_read_postcard() {
+    write_and_read_binary("write_and_read_postcard", Postcard);
+}
+
+#[cfg(feature = "toml")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Kettle {
+    name: String,
+    #[serde(deserialize_with = "deserialize_link")]
+    #[serde(serialize_with = "serialize_link")]
+    body: Material,
+    #[serde(deserialize_with = "deserialize_opt_arc_link")]
+    #[serde(serialize_with = "serialize_opt_arc_link")]
+    #[serde(default)]
+    lid: Option<Arc<Material>>,
+}
+
+#[cfg(feature = "toml")]
+#[typetag::serde]
+impl DatabaseEntry for Kettle {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn write_and_read_toml() {
+    let dir = scratch_database("write_and_read_toml").dir().to_path_buf();
+    let mut dbm = DatabaseManager::open(&dir, SerdeToml).unwrap();
+    let mut kettle = Kettle {
+        name: "tea_kettle".into(),
+        body: Material {
+            id: 1,
+            name: "enamel".into(),
+        },
+        lid: Some(Arc::new(Material {
+            id: 2,
+            name: "cast_iron".into(),
+        })),
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&kettle, &write_options).unwrap();
+
+    // The links contain the name and the checksum of the linked file
+    let contents = std::fs::read_to_string(dir.join("Kettle/tea_kettle.toml")).unwrap();
+    let checksum = dbm.checksum(&kettle.body).unwrap();
+    assert!(contents.contains("[Kettle.body]"), "{contents}");
+    assert!(
+        contents.contains(&format!("checksum = {checksum}")),
+        "{contents}"
+    );
+    assert_eq!(dbm.read::<Kettle, _>("tea_kettle").unwrap(), kettle);
+
+    // Missing optional links are omitted
+    kettle.lid = None;
+    dbm.write(&kettle, &write_options).unwrap();
+    assert_eq!(dbm.read::<Kettle, _>("tea_kettle").unwrap(), kettle);
+}
+
+#[cfg(feature = "ron")]
+#[test]
+fn write_and_read_ron() {
+    let dir = scratch_database("write_and_read_ron").dir().to_path_buf();
+    let mut dbm = DatabaseManager::open(&dir, SerdeRon).unwrap();
+    let mut shelf = Shelf {
+        name: "ron_shelf".into(),
+        shovel: Some(Arc::new(Shovel {
+            name: "ron_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 1,
+                name: "ron_maple".into(),
+            }),
+            blade: Material {
+                id: 2,
+                name: "ron_steel".into(),
+            },
+        })),
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    for link_style in [LinkStyle::Map, LinkStyle::Short] {
+        write_options.link_style = link_style;
+        dbm.write(&shelf, &write_options).unwrap();
+        dbm.cache_mut().clear();
+        assert_eq!(dbm.read::<Shelf, _>("ron_shelf").unwrap(), shelf);
+    }
+
+    shelf.shovel = None;
+    dbm.write(&shelf, &write_options).unwrap();
+    assert_eq!(dbm.read::<Shelf, _>("ron_shelf").unwrap(), shelf);
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn write_and_read_mixed_formats() {
+    let mut dbm = scratch_database("write_and_read_mixed_formats");
+    assert!(dbm.set_format_for::<Material, _>(Bincode).is_none());
+    assert_eq!(dbm.file_ext_for_type("Material"), "bincode");
+    assert_eq!(dbm.file_ext_for_type("Shovel"), "yaml");
+
+    let shelf = Shelf {
+        name: "mixed_shelf".into(),
+        shovel: Some(Arc::new(Shovel {
+            name: "mixed_shovel".into(),
+            shaft: Arc::new(Material {
+                id: 1,
+                name: "mixed_maple".into(),
+            }),
+            blade: Material {
+                id: 2,
+                name: "mixed_steel".into(),
+            },
+        })),
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&shelf, &write_options).unwrap();
+    assert!(dbm.dir().join("Shovel/mixed_shovel.yaml").exists());
+    assert!(dbm.dir().join("Material/mixed_maple.bincode").exists());
+    assert!(!dbm.dir().join("Material/mixed_maple.yaml").exists());
+
+    dbm.cache_mut().clear();
+    assert_eq!(dbm.read::<Shelf, _>("mixed_shelf").unwrap(), shelf);
+    assert_eq!(
+        dbm.list::<Material>().unwrap().collect::<Vec<_>>(),
+        ["mixed_maple", "mixed_steel"]
+    );
+}
+
+#[cfg(feature = "macros")]
+#[mosaic]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Tray {
+    name: String,
+    #[mosaic(link)]
+    base: Material,
+    #[mosaic(link)]
+    handle: Option<Arc<Material>>,
+    #[mosaic(link)]
+    cups: Vec<Cup>,
+}
+
+#[cfg(feature = "macros")]
+#[typetag::serde]
+impl DatabaseEntry for Tray {
+    fn name(&self) -> &OsStr {
+        self.name.as_ref()
+    }
+}
+
+#[cfg(feature = "macros")]
+#[test]
+fn write_and_read_mosaic_attribute() {
+    let mut dbm = scratch_database("write_and_read_mosaic_attribute");
+    let tray = Tray {
+        name: "tea_tray".into(),
+        base: Material {
+            id: 1,
+            name: "oak".into(),
+        },
+        handle: Some(Arc::new(Material {
+            id: 2,
+            name: "brass".into(),
+        })),
+        cups: vec![Cup {
+            name: "tea_cup".into(),
+            material: Material {
+                id: 3,
+                name: "porcelain".into(),
+            },
+        }],
+    };
+    let mut write_options = WriteOptions::default();
+    write_options.name_collisions = NameCollisions::Overwrite;
+    dbm.write(&tray, &write_options).unwrap();
+
+    // All annotated fields are written as links
+    for key in [
+        ("Material", "oak"),
+        ("Material", "brass"),
+        ("Cup", "tea_cup"),
+        ("Material", "porcelain"),
+    ] {
+        assert!(dbm.exists(key), "{key:?}");
+    }
+    dbm.cache_mut().clear();
+    assert_eq!(dbm.read::<Tray, _>("tea_tray").unwrap(), tray);
+}